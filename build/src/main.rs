@@ -24,6 +24,10 @@ enum BuildCommand {
         /// The test-kind (unit, integration, tarpaulin)
         #[structopt(long)]
         test_kind: TestKind,
+
+        /// Additional program features (e.g. `permissioned`)
+        #[structopt(long)]
+        features: Vec<String>,
     },
 }
 
@@ -56,6 +60,7 @@ fn main() {
     let mut use_bpf = false;
     let mut build_args = vec![];
     let mut features = Vec::new();
+    let mut extra_features = Vec::new();
 
     match BuildCommand::from_args() {
         BuildCommand::Build { target, cluster } => {
@@ -69,8 +74,13 @@ fn main() {
                 _ => {}
             }
         }
-        BuildCommand::Test { target, test_kind } => {
+        BuildCommand::Test {
+            target,
+            test_kind,
+            features: additional_features,
+        } => {
             build_target = target;
+            extra_features = additional_features;
 
             match test_kind {
                 TestKind::Unit => {
@@ -110,9 +120,12 @@ fn main() {
     } else {
         vec![]
     };
-    if !features.is_empty() {
-        features.insert(0, "--features");
-    }
+    features.extend(extra_features.iter().map(String::as_str));
+    let features = if features.is_empty() {
+        vec![]
+    } else {
+        vec!["--features".to_string(), features.join(",")]
+    };
 
     let exit_code = Command::new("cargo")
         .arg(command)
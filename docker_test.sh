@@ -22,6 +22,7 @@ docker run -it --rm \
   -e RUST_BACKTRACE=1 \
   elusiv-dev sh -c '
   sh ./build.sh test --test-kind unit --target elusiv \
+  && sh ./build.sh test --test-kind unit --target elusiv --features permissioned \
   && sh ./build.sh test --test-kind integration --target elusiv \
   && sh ./build.sh test --test-kind unit --target elusiv-warden-network \
   && sh ./build.sh test --test-kind integration --target elusiv-warden-network'
\ No newline at end of file
@@ -1,19 +1,28 @@
-use crate::warden::FixedLenString;
+use crate::error::ElusivWardenNetworkError;
+use crate::network::FULL_WARDEN_COHORT_SIZE;
+use crate::warden::{ElusivWardenID, FixedLenString, WardenRegion};
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_derive::BorshSerDeSized;
 use elusiv_proc_macros::elusiv_account;
 use elusiv_types::{accounts::PDAAccountData, tokens::TokenID, ElusivOption};
+use elusiv_utils::guard;
+use solana_program::entrypoint::ProgramResult;
 use solana_program::pubkey::Pubkey;
 
 #[repr(u8)]
-#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Debug)]
-#[cfg_attr(feature = "elusiv-client", derive(Clone, PartialEq))]
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Debug, Clone, Copy, PartialEq)]
 pub enum ApaLevel {
     Flag1,
     Flag2,
     Outcast,
 }
 
+impl Default for ApaLevel {
+    fn default() -> Self {
+        ApaLevel::Flag1
+    }
+}
+
 #[repr(u8)]
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Debug)]
 #[cfg_attr(feature = "elusiv-client", derive(Clone, PartialEq))]
@@ -31,6 +40,32 @@ impl Default for ApaProponentRole {
 
 pub type ApaReason = FixedLenString<512>;
 
+#[repr(u8)]
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Debug, Clone, Copy, PartialEq)]
+pub enum ApaBondStatus {
+    Held,
+    Refunded,
+    Forfeited,
+}
+
+impl Default for ApaBondStatus {
+    fn default() -> Self {
+        ApaBondStatus::Held
+    }
+}
+
+/// Duration (in seconds) after which the bond of an unsettled [`ApaProposal`] can be forfeited by anyone
+pub const APA_PROPOSAL_BOND_EXPIRY: u64 = 60 * 60 * 24 * 30;
+
+/// Duration (in seconds) after the filing of an [`ApaProposal`] during which Full Wardens can vote on it
+pub const APA_VOTING_DURATION: u64 = 60 * 60 * 24 * 3;
+
+/// The max number of votes on a single [`ApaProposal`] (the cohort can rotate during the voting period)
+pub const APA_MAX_VOTES: usize = 2 * FULL_WARDEN_COHORT_SIZE;
+
+/// The denominator of [`ApaProposalsAccount::get_threshold_bps`]
+pub const APA_THRESHOLD_BPS_DENOMINATOR: u32 = 10_000;
+
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized)]
 #[cfg_attr(feature = "elusiv-client", derive(Clone, PartialEq, Debug))]
 pub struct ApaProposal {
@@ -41,14 +76,107 @@ pub struct ApaProposal {
     pub level: ApaLevel,
     pub token_constraint: ElusivOption<TokenID>,
     pub reason: ApaReason,
+
+    /// The jurisdiction the decision is scoped to (`None` applies globally)
+    ///
+    /// # Note
+    ///
+    /// A scoped decision is only enforced on payouts executed by Wardens attested within the region (see [`crate::warden::WardenAttestationAccount`]).
+    pub region_scope: ElusivOption<WardenRegion>,
 }
 
-#[elusiv_account(eager_type: true)]
+#[elusiv_account]
 pub struct ApaProposalAccount {
     #[no_getter]
     #[no_setter]
     pda_data: PDAAccountData,
     pub proposal: ApaProposal,
+
+    /// Lamports held in this account as the proponent's anti-spam bond
+    pub bond: u64,
+    pub bond_status: ApaBondStatus,
+
+    /// The Full Wardens that voted on the proposal and their votes (`true` approves the proposal)
+    pub votes_count: u32,
+    pub voters: [ElusivWardenID; APA_MAX_VOTES],
+    pub votes: [bool; APA_MAX_VOTES],
+    pub approvals_count: u32,
+
+    pub is_finalized: bool,
+    pub is_accepted: bool,
+}
+
+impl<'a> ApaProposalAccount<'a> {
+    /// Records the vote of a Full Warden (each Warden can only vote once)
+    pub fn vote(&mut self, warden_id: ElusivWardenID, approve: bool) -> ProgramResult {
+        guard!(
+            !self.get_is_finalized(),
+            ElusivWardenNetworkError::VotingError
+        );
+
+        let votes_count = self.get_votes_count() as usize;
+        guard!(
+            votes_count < APA_MAX_VOTES,
+            ElusivWardenNetworkError::VotingError
+        );
+        guard!(
+            (0..votes_count).all(|i| self.get_voters(i) != warden_id),
+            ElusivWardenNetworkError::VotingError
+        );
+
+        self.set_voters(votes_count, &warden_id);
+        self.set_votes(votes_count, &approve);
+        self.set_votes_count(&(votes_count as u32 + 1));
+
+        if approve {
+            self.set_approvals_count(&(self.get_approvals_count() + 1));
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the votes reach the `quorum` and the approvals reach the `threshold_bps` of the votes
+    ///
+    /// # Note
+    ///
+    /// A proposal without any approval is never accepted (independent of the configuration).
+    pub fn is_approved(&self, quorum: u32, threshold_bps: u16) -> bool {
+        let votes_count = self.get_votes_count();
+        let approvals_count = self.get_approvals_count();
+
+        approvals_count > 0
+            && votes_count >= quorum
+            && approvals_count as u64 * APA_THRESHOLD_BPS_DENOMINATOR as u64
+                >= threshold_bps as u64 * votes_count as u64
+    }
+}
+
+pub fn is_apa_voting_period_elapsed(proposal_timestamp: u64, timestamp: u64) -> bool {
+    proposal_timestamp
+        .checked_add(APA_VOTING_DURATION)
+        .map_or(false, |t| t <= timestamp)
+}
+
+/// The outcome of the accepted [`ApaProposal`]s against a target (PDA associated with the target's pubkey)
+///
+/// # Note
+///
+/// - The account only exists for flagged targets and holds the highest accepted [`ApaLevel`].
+/// - The Elusiv program reads the account directly, so the layout is only appended to.
+#[elusiv_account]
+pub struct ApaFlagAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub level: ApaLevel,
+    pub token_constraint: ElusivOption<TokenID>,
+
+    /// The id of the accepted proposal
+    pub proposal_id: u32,
+
+    /// The `region_scope` of the accepted proposal
+    pub region_scope: ElusivOption<WardenRegion>,
 }
 
 /// Maps an APA-target's pubkey to proposal-ids
@@ -57,7 +185,7 @@ pub struct ApaProposalAccount {
 ///
 /// Maps the PDA with [`None`] [`elusiv_types::PDAOffset`] to the proposal-id of the [`ApaProposal`] with the highest [`ApaLevel`].
 /// If there are multiple proposals, the successfull one is used.
-#[elusiv_account(eager_type: true)]
+#[elusiv_account]
 pub struct ApaTargetMapAccount {
     #[no_getter]
     #[no_setter]
@@ -65,10 +193,209 @@ pub struct ApaTargetMapAccount {
     pub proposal_id: ElusivOption<u32>,
 }
 
-#[elusiv_account(eager_type: true)]
+#[elusiv_account]
 pub struct ApaProposalsAccount {
     #[no_getter]
     #[no_setter]
     pda_data: PDAAccountData,
     pub number_of_proposals: u32,
+
+    /// The bond (in lamports) required for filing a new [`ApaProposal`]
+    ///
+    /// # Note
+    ///
+    /// Forfeited bonds are transferred into this account (treasury).
+    pub proposal_bond: u64,
+
+    /// The min number of votes required for accepting an [`ApaProposal`]
+    pub quorum: u32,
+
+    /// The min share of approvals of all votes (in basis points) required for accepting an [`ApaProposal`]
+    pub threshold_bps: u16,
+}
+
+/// Number of [`ApaProposal`]s indexed by a single [`ApaProposalsPageAccount`]
+pub const APA_PROPOSALS_PAGE_SIZE: u32 = 1024;
+
+/// Returns the index of the [`ApaProposalsPageAccount`] indexing the proposal with `proposal_id`
+pub const fn apa_proposals_page(proposal_id: u32) -> u32 {
+    proposal_id / APA_PROPOSALS_PAGE_SIZE
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, BorshSerDeSized, Debug, Clone, Copy, PartialEq, Default,
+)]
+pub struct ApaProposalIndexEntry {
+    pub level: ApaLevel,
+    pub bond_status: ApaBondStatus,
+}
+
+/// Index of [`APA_PROPOSALS_PAGE_SIZE`] consecutive [`ApaProposal`]s
+///
+/// # Notes
+///
+/// Once all proposals of a page are concluded (bonds settled), the page can be archived into an [`ApaProposalsPageSummaryAccount`] and closed.
+#[elusiv_account]
+pub struct ApaProposalsPageAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Number of proposals with a settled bond
+    pub number_of_concluded_proposals: u32,
+    pub entries: [ApaProposalIndexEntry; APA_PROPOSALS_PAGE_SIZE as usize],
+}
+
+impl<'a> ApaProposalsPageAccount<'a> {
+    pub fn is_concluded(&self) -> bool {
+        self.get_number_of_concluded_proposals() == APA_PROPOSALS_PAGE_SIZE
+    }
+
+    pub fn summary(&self) -> ApaProposalsPageSummary {
+        ApaProposalsPageSummary::new(
+            &(0..APA_PROPOSALS_PAGE_SIZE as usize)
+                .map(|i| self.get_entries(i))
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+/// Compact summary of the entries of an archived [`ApaProposalsPageAccount`]
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Debug, Clone, PartialEq)]
+pub struct ApaProposalsPageSummary {
+    /// Number of proposals per [`ApaLevel`] (`Flag1`, `Flag2`, `Outcast`)
+    pub level_counts: [u32; 3],
+
+    pub refunded_count: u32,
+    pub forfeited_count: u32,
+
+    /// `sha256` of the serialized [`ApaProposalIndexEntry`]s
+    pub entries_hash: [u8; 32],
+}
+
+impl ApaProposalsPageSummary {
+    pub fn new(entries: &[ApaProposalIndexEntry]) -> Self {
+        let mut level_counts = [0; 3];
+        let mut refunded_count = 0;
+        let mut forfeited_count = 0;
+        let mut bytes = Vec::new();
+
+        for entry in entries {
+            level_counts[entry.level as usize] += 1;
+
+            match entry.bond_status {
+                ApaBondStatus::Held => {}
+                ApaBondStatus::Refunded => refunded_count += 1,
+                ApaBondStatus::Forfeited => forfeited_count += 1,
+            }
+
+            bytes.extend(entry.try_to_vec().unwrap());
+        }
+
+        Self {
+            level_counts,
+            refunded_count,
+            forfeited_count,
+            entries_hash: solana_program::hash::hash(&bytes).to_bytes(),
+        }
+    }
+}
+
+#[elusiv_account]
+pub struct ApaProposalsPageSummaryAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+    pub summary: ApaProposalsPageSummary,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elusiv_types::{ProgramAccount, SizedAccount};
+
+    fn entry(level: ApaLevel, bond_status: ApaBondStatus) -> ApaProposalIndexEntry {
+        ApaProposalIndexEntry { level, bond_status }
+    }
+
+    #[test]
+    fn test_apa_proposal_vote() {
+        let mut data = vec![0; ApaProposalAccount::SIZE];
+        let mut proposal = ApaProposalAccount::new(&mut data).unwrap();
+
+        proposal.vote(0, true).unwrap();
+        proposal.vote(1, false).unwrap();
+
+        // Duplicate vote
+        assert_eq!(
+            proposal.vote(0, false),
+            Err(ElusivWardenNetworkError::VotingError.into())
+        );
+
+        assert_eq!(proposal.get_votes_count(), 2);
+        assert_eq!(proposal.get_approvals_count(), 1);
+
+        assert!(proposal.is_approved(2, 5_000));
+        assert!(!proposal.is_approved(3, 5_000));
+        assert!(!proposal.is_approved(2, 5_001));
+
+        for warden_id in 2..APA_MAX_VOTES as u32 {
+            proposal.vote(warden_id, true).unwrap();
+        }
+        assert_eq!(
+            proposal.vote(APA_MAX_VOTES as u32, true),
+            Err(ElusivWardenNetworkError::VotingError.into())
+        );
+
+        // Finalized proposals
+        let mut data = vec![0; ApaProposalAccount::SIZE];
+        let mut proposal = ApaProposalAccount::new(&mut data).unwrap();
+        assert!(!proposal.is_approved(0, 0));
+
+        proposal.set_is_finalized(&true);
+        assert_eq!(
+            proposal.vote(0, true),
+            Err(ElusivWardenNetworkError::VotingError.into())
+        );
+    }
+
+    #[test]
+    fn test_is_apa_voting_period_elapsed() {
+        assert!(!is_apa_voting_period_elapsed(100, 100));
+        assert!(!is_apa_voting_period_elapsed(
+            100,
+            100 + APA_VOTING_DURATION - 1
+        ));
+        assert!(is_apa_voting_period_elapsed(100, 100 + APA_VOTING_DURATION));
+        assert!(!is_apa_voting_period_elapsed(u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn test_apa_proposals_page() {
+        assert_eq!(apa_proposals_page(0), 0);
+        assert_eq!(apa_proposals_page(APA_PROPOSALS_PAGE_SIZE - 1), 0);
+        assert_eq!(apa_proposals_page(APA_PROPOSALS_PAGE_SIZE), 1);
+    }
+
+    #[test]
+    fn test_apa_proposals_page_summary() {
+        let entries = vec![
+            entry(ApaLevel::Flag1, ApaBondStatus::Refunded),
+            entry(ApaLevel::Outcast, ApaBondStatus::Forfeited),
+            entry(ApaLevel::Outcast, ApaBondStatus::Refunded),
+        ];
+        let summary = ApaProposalsPageSummary::new(&entries);
+
+        assert_eq!(summary.level_counts, [1, 0, 2]);
+        assert_eq!(summary.refunded_count, 2);
+        assert_eq!(summary.forfeited_count, 1);
+
+        // The hash commits to the order of the entries
+        let mut reordered = entries;
+        reordered.swap(0, 1);
+        assert_ne!(
+            summary.entries_hash,
+            ApaProposalsPageSummary::new(&reordered).entries_hash
+        );
+    }
 }
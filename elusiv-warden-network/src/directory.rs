@@ -0,0 +1,251 @@
+//! Client-side directory of all registered Wardens
+
+use crate::warden::{
+    BasicWardenAccount, BasicWardenMapAccount, ElusivBasicWarden, ElusivWardenID, WardenRegion,
+    WardensAccount,
+};
+use elusiv_types::{EagerAccount, PDAAccount};
+use solana_program::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// A source of (raw) account data, used to walk the Warden accounts
+pub trait WardenAccountSource {
+    /// Returns the data of the account at `pubkey` or `None` if the account does not exist
+    fn account_data(&self, pubkey: &Pubkey) -> Result<Option<Vec<u8>>, std::io::Error>;
+}
+
+impl WardenAccountSource for solana_client::rpc_client::RpcClient {
+    fn account_data(&self, pubkey: &Pubkey) -> Result<Option<Vec<u8>>, std::io::Error> {
+        self.get_account_with_commitment(pubkey, self.commitment())
+            .map(|response| response.value.map(|account| account.data))
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))
+    }
+}
+
+/// Snapshot of account data (e.g. fetched via `getMultipleAccounts`)
+impl WardenAccountSource for HashMap<Pubkey, Vec<u8>> {
+    fn account_data(&self, pubkey: &Pubkey) -> Result<Option<Vec<u8>>, std::io::Error> {
+        Ok(self.get(pubkey).cloned())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WardenDirectoryEntry {
+    pub warden_id: ElusivWardenID,
+    pub warden: ElusivBasicWarden,
+
+    /// The [`BasicWardenMapAccount`] of the Warden's key points at `warden_id`
+    pub is_mapped: bool,
+}
+
+impl WardenDirectoryEntry {
+    /// The Warden's metadata has been attested as valid
+    pub fn is_attested(&self) -> bool {
+        self.warden.is_metadata_valid.option() == Some(true)
+    }
+
+    /// The Warden has been registered as a metadata attester
+    pub fn is_attester(&self) -> bool {
+        self.warden.config.warden_features.attestation
+    }
+}
+
+/// Filters the entries of [`fetch_warden_directory`] (`None` matches all values)
+#[derive(Debug, Clone, Default)]
+pub struct WardenDirectoryFilter {
+    pub region: Option<WardenRegion>,
+    pub active: Option<bool>,
+    pub attested: Option<bool>,
+}
+
+impl WardenDirectoryFilter {
+    pub fn matches(&self, entry: &WardenDirectoryEntry) -> bool {
+        self.region
+            .map_or(true, |region| entry.warden.config.region == region)
+            && self
+                .active
+                .map_or(true, |active| entry.warden.is_active == active)
+            && self
+                .attested
+                .map_or(true, |attested| entry.is_attested() == attested)
+    }
+}
+
+/// Fetches all registered Wardens (in the order of their [`ElusivWardenID`]s) that match `filter`
+///
+/// # Note
+///
+/// Wardens whose [`BasicWardenAccount`] does not exist (yet) are skipped.
+pub fn fetch_warden_directory<S: WardenAccountSource>(
+    rpc: &S,
+    filter: &WardenDirectoryFilter,
+) -> Result<Vec<WardenDirectoryEntry>, std::io::Error> {
+    let wardens_account = rpc
+        .account_data(&WardensAccount::find(None).0)?
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "WardensAccount does not exist",
+            )
+        })?;
+    let next_warden_id = WardensAccount::new_eager(wardens_account)?.next_warden_id;
+
+    let mut entries = Vec::new();
+    for warden_id in 0..next_warden_id {
+        let data = match rpc.account_data(&BasicWardenAccount::find(Some(warden_id)).0)? {
+            Some(data) => data,
+            None => continue,
+        };
+        let warden = BasicWardenAccount::new_eager(data)?.warden;
+
+        let is_mapped = match rpc
+            .account_data(&BasicWardenMapAccount::find_with_pubkey(warden.config.key, None).0)?
+        {
+            Some(data) => BasicWardenMapAccount::new_eager(data)?.warden_id == warden_id,
+            None => false,
+        };
+
+        let entry = WardenDirectoryEntry {
+            warden_id,
+            warden,
+            is_mapped,
+        };
+
+        if filter.matches(&entry) {
+            entries.push(entry);
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warden::{
+        BasicWardenFeatures, ElusivBasicWardenConfig, Timezone, WardenAuthority, WardenCapacity,
+        WardenFeatures,
+    };
+    use elusiv_types::{ProgramAccount, SizedAccount, TOKENS};
+    use std::net::Ipv4Addr;
+
+    fn basic_warden(key: Pubkey, region: WardenRegion, is_active: bool) -> ElusivBasicWarden {
+        ElusivBasicWarden {
+            config: ElusivBasicWardenConfig {
+                ident: String::new().try_into().unwrap(),
+                key,
+                operator: None.into(),
+                addr: Ipv4Addr::new(0, 0, 0, 0),
+                rpc_port: 0,
+                tls_mode: crate::warden::TlsMode::NoTls,
+                uses_proxy: false,
+                jurisdiction: 0,
+                timezone: Timezone {
+                    area: 0,
+                    location: String::new().try_into().unwrap(),
+                },
+                region,
+                version: [0, 0, 0],
+                platform: String::new().try_into().unwrap(),
+                warden_features: WardenFeatures::default(),
+                basic_warden_features: BasicWardenFeatures::default(),
+                tokens: [false; TOKENS.len()],
+            },
+            lut: Pubkey::new_unique(),
+            asn: None.into(),
+            is_operator_confirmed: false,
+            is_metadata_valid: None.into(),
+            is_active,
+            join_timestamp: 0,
+            activation_timestamp: 0,
+            supported_features: 0,
+            capacity: WardenCapacity::default(),
+            report_timestamp: 0,
+            authority: WardenAuthority::Ed25519,
+        }
+    }
+
+    fn insert_warden(
+        accounts: &mut HashMap<Pubkey, Vec<u8>>,
+        warden_id: ElusivWardenID,
+        warden: &ElusivBasicWarden,
+    ) {
+        let mut data = vec![0; BasicWardenAccount::SIZE];
+        BasicWardenAccount::new(&mut data)
+            .unwrap()
+            .set_warden(warden);
+        accounts.insert(BasicWardenAccount::find(Some(warden_id)).0, data);
+
+        let mut data = vec![0; BasicWardenMapAccount::SIZE];
+        BasicWardenMapAccount::new(&mut data)
+            .unwrap()
+            .set_warden_id(&warden_id);
+        accounts.insert(
+            BasicWardenMapAccount::find_with_pubkey(warden.config.key, None).0,
+            data,
+        );
+    }
+
+    #[test]
+    fn test_fetch_warden_directory() {
+        let mut accounts = HashMap::new();
+
+        // Missing `WardensAccount`
+        assert!(fetch_warden_directory(&accounts, &WardenDirectoryFilter::default()).is_err());
+
+        let mut data = vec![0; WardensAccount::SIZE];
+        WardensAccount::new(&mut data)
+            .unwrap()
+            .set_next_warden_id(&4);
+        accounts.insert(WardensAccount::find(None).0, data);
+
+        let mut attested = basic_warden(Pubkey::new_unique(), WardenRegion::Europe, true);
+        attested.is_metadata_valid = Some(true).into();
+        insert_warden(&mut accounts, 0, &attested);
+        insert_warden(
+            &mut accounts,
+            1,
+            &basic_warden(Pubkey::new_unique(), WardenRegion::America, true),
+        );
+        insert_warden(
+            &mut accounts,
+            3,
+            &basic_warden(Pubkey::new_unique(), WardenRegion::Europe, false),
+        );
+
+        // Warden 2 does not exist
+        let all = fetch_warden_directory(&accounts, &WardenDirectoryFilter::default()).unwrap();
+        assert_eq!(
+            all.iter().map(|e| e.warden_id).collect::<Vec<_>>(),
+            vec![0, 1, 3]
+        );
+        assert!(all.iter().all(|e| e.is_mapped));
+
+        let filter = WardenDirectoryFilter {
+            region: Some(WardenRegion::Europe),
+            ..Default::default()
+        };
+        let europe = fetch_warden_directory(&accounts, &filter).unwrap();
+        assert_eq!(
+            europe.iter().map(|e| e.warden_id).collect::<Vec<_>>(),
+            vec![0, 3]
+        );
+
+        let filter = WardenDirectoryFilter {
+            region: Some(WardenRegion::Europe),
+            active: Some(true),
+            attested: Some(true),
+        };
+        let entries = fetch_warden_directory(&accounts, &filter).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].warden_id, 0);
+        assert!(entries[0].is_attested());
+        assert!(!entries[0].is_attester());
+
+        let filter = WardenDirectoryFilter {
+            attested: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(fetch_warden_directory(&accounts, &filter).unwrap().len(), 2);
+    }
+}
@@ -14,6 +14,14 @@ pub enum ElusivWardenNetworkError {
     Overflow = 0x08,
     Underflow = 0x09,
 
+    ProposalBondError = 0x0A,
+    InvalidWardenVersion = 0x0B,
+    InvalidWardenAuthority = 0x0C,
+    StakeError = 0x0D,
+    VerificationSlotError = 0x0E,
+    CohortError = 0x0F,
+    EpochError = 0x10,
+
     /// Placeholder, [`elusiv_types::token::TokenError`] uses 0x1xx error codes
     TokenError = 0x100,
 
@@ -1,23 +1,27 @@
 #![allow(clippy::large_enum_variant)]
 #![allow(clippy::too_many_arguments)]
 
-use crate::apa::{ApaProposal, ApaProposalsAccount, ApaTargetMapAccount};
+use crate::apa::{
+    apa_proposals_page, ApaFlagAccount, ApaProposal, ApaProposalAccount, ApaProposalsAccount,
+    ApaProposalsPageAccount, ApaProposalsPageSummaryAccount, ApaTargetMapAccount,
+};
 use crate::macros::ElusivInstruction;
-use crate::network::{ApaWardenNetworkAccount, BasicWardenNetworkAccount};
+use crate::network::{
+    ApaWardenNetworkAccount, BasicWardenNetworkAccount, FullWardenNetworkAccount,
+};
 use crate::processor;
+use crate::stake::WardenStakeAccount;
 use crate::warden::{
     ApaWardenAccount, BasicWardenAccount, BasicWardenAttesterMapAccount, BasicWardenMapAccount,
     BasicWardenStatsAccount, ElusivBasicWardenConfig, ElusivWardenID, Identifier, QuoteEnd,
-    QuoteStart, Timezone, WardenRegion, WardensAccount,
+    QuoteStart, StatsCertificateAccount, Timezone, WardenAttestationAccount, WardenAuthority,
+    WardenCapacity, WardenRegion, WardensAccount,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_types::AccountRepr;
 use solana_program::pubkey::Pubkey;
 use solana_program::system_program;
-use solana_program::sysvar::instructions;
 
-#[cfg(feature = "elusiv-client")]
-use crate::apa::ApaProposalAccount;
 #[cfg(feature = "elusiv-client")]
 use crate::operator::WardenOperatorAccount;
 #[cfg(feature = "elusiv-client")]
@@ -34,6 +38,7 @@ pub enum ElusivWardenNetworkInstruction {
     #[pda(basic_network, BasicWardenNetworkAccount, { writable, skip_pda_verification, account_info })]
     #[pda(apa_network, ApaWardenNetworkAccount, { writable, skip_pda_verification, account_info })]
     #[pda(proposals_account, ApaProposalsAccount, { writable, skip_pda_verification, account_info })]
+    #[pda(proposals_page_account, ApaProposalsPageAccount, pda_offset = Some(0), { writable, skip_pda_verification, account_info })]
     #[sys(system_program, key = system_program::ID, { ignore })]
     Init,
 
@@ -43,7 +48,8 @@ pub enum ElusivWardenNetworkInstruction {
     #[pda(warden_map_account, BasicWardenMapAccount, pda_pubkey = config.key, { writable, skip_pda_verification, account_info })]
     #[pda(wardens, WardensAccount, { writable })]
     #[pda(basic_network, BasicWardenNetworkAccount, { writable })]
-    #[sys(system_program, key = system_program::ID, { ignore })]
+    #[pda(stake_account, WardenStakeAccount, pda_offset = Some(warden_id), { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID)]
     RegisterBasicWarden {
         warden_id: ElusivWardenID,
         config: ElusivBasicWardenConfig,
@@ -51,6 +57,7 @@ pub enum ElusivWardenNetworkInstruction {
 
     #[acc(warden, { signer })]
     #[pda(warden_account, BasicWardenAccount, pda_offset = Some(warden_id), { writable })]
+    #[sys(instructions, sysvar = Instructions)]
     UpdateBasicWardenState {
         warden_id: ElusivWardenID,
         is_active: bool,
@@ -59,10 +66,65 @@ pub enum ElusivWardenNetworkInstruction {
     #[acc(warden, { signer })]
     #[pda(warden_account, BasicWardenAccount, pda_offset = Some(warden_id), { writable })]
     #[acc(lut_account)]
+    #[sys(instructions, sysvar = Instructions)]
     UpdateBasicWardenLut {
         warden_id: ElusivWardenID,
     },
 
+    #[acc(warden, { signer })]
+    #[pda(warden_account, BasicWardenAccount, pda_offset = Some(warden_id), { writable })]
+    #[sys(instructions, sysvar = Instructions)]
+    UpdateBasicWardenCapabilities {
+        warden_id: ElusivWardenID,
+        version: [u16; 3],
+        supported_features: u64,
+        capacity: WardenCapacity,
+    },
+
+    #[acc(warden, { signer })]
+    #[pda(warden_account, BasicWardenAccount, pda_offset = Some(warden_id), { writable })]
+    #[sys(instructions, sysvar = Instructions)]
+    SetBasicWardenAuthority {
+        warden_id: ElusivWardenID,
+        authority: WardenAuthority,
+    },
+
+    // -------- Warden stake --------
+    #[acc(payer, { signer, writable })]
+    #[pda(stake_account, WardenStakeAccount, pda_offset = Some(warden_id), { writable, account_info })]
+    #[sys(system_program, key = system_program::ID)]
+    DepositStake {
+        warden_id: ElusivWardenID,
+        amount: u64,
+    },
+
+    #[acc(warden, { signer, writable })]
+    #[pda(warden_account, BasicWardenAccount, pda_offset = Some(warden_id))]
+    #[pda(stake_account, WardenStakeAccount, pda_offset = Some(warden_id), { writable, account_info })]
+    #[pda(wardens, WardensAccount)]
+    WithdrawStake {
+        warden_id: ElusivWardenID,
+        amount: u64,
+    },
+
+    #[acc(authority, { signer })]
+    #[acc(warden)]
+    #[pda(warden_account, BasicWardenAccount, pda_offset = Some(warden_id))]
+    #[pda(stats_account, BasicWardenStatsAccount, pda_pubkey = warden.pubkey(), pda_offset = Some(year.into()))]
+    #[pda(stake_account, WardenStakeAccount, pda_offset = Some(warden_id), { writable, account_info })]
+    #[pda(wardens, WardensAccount, { writable, account_info })]
+    SlashStake {
+        warden_id: ElusivWardenID,
+        year: u16,
+        amount: u64,
+    },
+
+    #[acc(authority, { signer })]
+    #[pda(wardens, WardensAccount, { writable })]
+    SetMinimumStake {
+        minimum_stake: u64,
+    },
+
     // -------- APA Warden --------
     #[acc(warden, { signer, writable })]
     #[pda(warden_map_account, BasicWardenMapAccount, pda_pubkey = warden.pubkey())]
@@ -92,6 +154,29 @@ pub enum ElusivWardenNetworkInstruction {
 
     CompleteApaGenesisNetwork,
 
+    // -------- Full Warden --------
+    #[acc(payer, { signer, writable })]
+    #[pda(full_network, FullWardenNetworkAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenFullWardenNetworkAccount,
+
+    #[acc(warden, { signer })]
+    #[pda(warden_map_account, BasicWardenMapAccount, pda_pubkey = warden.pubkey())]
+    #[pda(full_network, FullWardenNetworkAccount, { writable })]
+    ApplyFullWarden {
+        warden_id: ElusivWardenID,
+    },
+
+    #[acc(authority, { signer })]
+    #[pda(full_network, FullWardenNetworkAccount, { writable })]
+    ApproveFullWardenApplication {
+        application_index: u32,
+        warden_id: ElusivWardenID,
+    },
+
+    #[pda(full_network, FullWardenNetworkAccount, { writable })]
+    RotateFullWardenNetworkEpoch,
+
     // -------- Warden operator --------
     #[acc(operator, { signer, writable })]
     #[pda(operator_account, WardenOperatorAccount, pda_pubkey = operator.pubkey(), { writable, skip_pda_verification, account_info })]
@@ -119,24 +204,107 @@ pub enum ElusivWardenNetworkInstruction {
 
     #[acc(warden)]
     #[pda(stats_account, BasicWardenStatsAccount, pda_pubkey = warden.pubkey(), pda_offset = Some(year.into()), { writable })]
-    #[sys(instructions, key = instructions::ID)]
+    #[sys(instructions, sysvar = Instructions)]
     TrackBasicWardenStats {
         year: u16,
         can_fail: bool,
     },
 
+    #[acc(warden, { signer, writable })]
+    #[pda(warden_account, BasicWardenAccount, pda_offset = Some(warden_id))]
+    #[pda(stats_account, BasicWardenStatsAccount, pda_pubkey = warden.pubkey(), pda_offset = Some(year.into()))]
+    #[pda(certificate_account, StatsCertificateAccount, pda_pubkey = warden.pubkey(), pda_offset = Some(year.into()), { writable, find_pda, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    IssueStatsCertificate {
+        warden_id: ElusivWardenID,
+        year: u16,
+    },
+
+    // -------- Verification slots --------
+    #[acc(warden)]
+    #[pda(warden_account, BasicWardenAccount, pda_offset = Some(warden_id))]
+    #[pda(stats_account, BasicWardenStatsAccount, pda_pubkey = warden.pubkey(), pda_offset = Some(year.into()))]
+    #[pda(wardens, WardensAccount, { writable })]
+    UpdateWardenRanking {
+        warden_id: ElusivWardenID,
+        year: u16,
+    },
+
+    #[pda(wardens, WardensAccount, { writable })]
+    AssignVerificationSlot,
+
     // -------- APA --------
     #[acc(proponent, { signer, writable })]
     #[pda(proposal_account, ApaProposalAccount, pda_offset = Some(proposal_id), { writable, skip_pda_verification, account_info })]
     #[pda(proposals_account, ApaProposalsAccount, { writable })]
+    #[pda(page_account, ApaProposalsPageAccount, pda_offset = Some(apa_proposals_page(proposal_id)), { writable })]
     #[pda(map_account, ApaTargetMapAccount, pda_pubkey = proposal.target, { writable, find_pda, account_info })]
     #[acc(token_mint)]
-    #[sys(system_program, key = system_program::ID, { ignore })]
+    #[sys(system_program, key = system_program::ID)]
     ProposeApaProposal {
         proposal_id: u32,
         proposal: ApaProposal,
     },
 
+    #[acc(authority, { signer })]
+    #[pda(proposals_account, ApaProposalsAccount, { writable })]
+    SetApaProposalBond {
+        proposal_bond: u64,
+    },
+
+    #[acc(signer, { signer })]
+    #[acc(proponent, { writable })]
+    #[pda(proposal_account, ApaProposalAccount, pda_offset = Some(proposal_id), { writable, account_info })]
+    #[pda(proposals_account, ApaProposalsAccount, { writable, account_info })]
+    #[pda(page_account, ApaProposalsPageAccount, pda_offset = Some(apa_proposals_page(proposal_id)), { writable })]
+    SettleApaProposalBond {
+        proposal_id: u32,
+        refund: bool,
+    },
+
+    #[acc(payer, { signer, writable })]
+    #[pda(proposals_account, ApaProposalsAccount)]
+    #[pda(page_account, ApaProposalsPageAccount, pda_offset = Some(page), { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenApaProposalsPage {
+        page: u32,
+    },
+
+    #[acc(payer, { signer, writable })]
+    #[pda(page_account, ApaProposalsPageAccount, pda_offset = Some(page), { writable, account_info })]
+    #[pda(summary_account, ApaProposalsPageSummaryAccount, pda_offset = Some(page), { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    ArchiveApaProposalsPage {
+        page: u32,
+    },
+
+    #[acc(warden, { signer })]
+    #[pda(warden_map_account, BasicWardenMapAccount, pda_pubkey = warden.pubkey())]
+    #[pda(full_network, FullWardenNetworkAccount)]
+    #[pda(proposal_account, ApaProposalAccount, pda_offset = Some(proposal_id), { writable })]
+    VoteApaProposal {
+        proposal_id: u32,
+        approve: bool,
+    },
+
+    #[acc(payer, { signer, writable })]
+    #[pda(proposal_account, ApaProposalAccount, pda_offset = Some(proposal_id), { writable })]
+    #[pda(proposals_account, ApaProposalsAccount)]
+    #[pda(map_account, ApaTargetMapAccount, pda_pubkey = target, { writable })]
+    #[pda(flag_account, ApaFlagAccount, pda_pubkey = target, { writable, find_pda, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    FinalizeApaProposal {
+        proposal_id: u32,
+        target: Pubkey,
+    },
+
+    #[acc(authority, { signer })]
+    #[pda(proposals_account, ApaProposalsAccount, { writable })]
+    SetApaVotingParameters {
+        quorum: u32,
+        threshold_bps: u16,
+    },
+
     // -------- Metadata attestation --------
     #[acc(signer, { signer, writable })]
     #[pda(attester_account, BasicWardenAttesterMapAccount, pda_pubkey = attester, { writable, skip_pda_verification, account_info })]
@@ -170,6 +338,18 @@ pub enum ElusivWardenNetworkInstruction {
         uses_proxy: bool,
     },
 
+    #[acc(attester, { signer, writable })]
+    #[pda(attester_warden_account, BasicWardenAccount, pda_offset = Some(attester_warden_id))]
+    #[pda(warden_account, BasicWardenAccount, pda_offset = Some(warden_id))]
+    #[pda(attestation_account, WardenAttestationAccount, pda_pubkey = warden, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    SetWardenAttestationLevel {
+        attester_warden_id: ElusivWardenID,
+        warden_id: ElusivWardenID,
+        warden: Pubkey,
+        level: u8,
+    },
+
     // -------- Program state management --------
     #[cfg(not(feature = "mainnet"))]
     #[acc(payer, { signer })]
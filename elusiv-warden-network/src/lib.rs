@@ -1,4 +1,6 @@
 pub mod apa;
+#[cfg(feature = "elusiv-client")]
+pub mod directory;
 pub mod entrypoint;
 pub mod error;
 pub mod instruction;
@@ -6,6 +8,7 @@ pub mod macros;
 pub mod network;
 pub mod operator;
 pub mod processor;
+pub mod stake;
 pub mod warden;
 
 pub use entrypoint::*;
@@ -90,6 +90,146 @@ impl<'a> BasicWardenNetworkAccount<'a> {
     }
 }
 
+/// The number of Wardens in a cohort of the [`ElusivFullWardenNetwork`]
+pub const FULL_WARDEN_COHORT_SIZE: usize = 16;
+
+/// The max number of applications for the next cohort of the [`ElusivFullWardenNetwork`]
+pub const MAX_FULL_WARDEN_APPLICATIONS: usize = 2 * FULL_WARDEN_COHORT_SIZE;
+
+/// Duration (in seconds) of an epoch of the [`ElusivFullWardenNetwork`], after which the next cohort can replace the current one
+pub const FULL_WARDEN_EPOCH_DURATION: u64 = 60 * 60 * 24 * 7;
+
+warden_network!(
+    ElusivFullWardenNetwork,
+    NetworkSize::Fixed(FULL_WARDEN_COHORT_SIZE)
+);
+
+/// The current cohort of the [`ElusivFullWardenNetwork`] and the applications for the next cohort
+///
+/// # Notes
+///
+/// - Basic Wardens apply for the next cohort during an epoch, applications are approved by the program authority.
+/// - Once the epoch has elapsed and a full cohort has been approved, the approved applicants replace the current cohort.
+#[elusiv_account]
+pub struct FullWardenNetworkAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The epoch of the current cohort (`0` before the first cohort)
+    pub epoch: u32,
+    pub epoch_start_timestamp: u64,
+
+    pub members_count: u32,
+    pub members: [ElusivWardenID; ElusivFullWardenNetwork::SIZE.max()],
+
+    pub applications_count: u32,
+    pub applicants: [ElusivWardenID; MAX_FULL_WARDEN_APPLICATIONS],
+    pub approvals: [bool; MAX_FULL_WARDEN_APPLICATIONS],
+    pub approvals_count: u32,
+}
+
+impl<'a> FullWardenNetworkAccount<'a> {
+    /// Returns true if `warden_id` is a member of the current cohort
+    pub fn is_member(&self, warden_id: ElusivWardenID) -> bool {
+        (0..self.get_members_count() as usize).any(|i| self.get_members(i) == warden_id)
+    }
+
+    fn application_index(&self, warden_id: ElusivWardenID) -> Option<usize> {
+        (0..self.get_applications_count() as usize).find(|&i| self.get_applicants(i) == warden_id)
+    }
+
+    /// Registers the application of a Warden for the next cohort and returns its index
+    pub fn apply(&mut self, warden_id: ElusivWardenID) -> Result<u32, ProgramError> {
+        let applications_count = self.get_applications_count();
+        guard!(
+            (applications_count as usize) < MAX_FULL_WARDEN_APPLICATIONS,
+            ElusivWardenNetworkError::WardenRegistrationError
+        );
+        guard!(
+            self.application_index(warden_id).is_none(),
+            ElusivWardenNetworkError::WardenRegistrationError
+        );
+
+        self.set_applicants(applications_count as usize, &warden_id);
+        self.set_approvals(applications_count as usize, &false);
+        self.set_applications_count(&(applications_count + 1));
+
+        Ok(applications_count)
+    }
+
+    /// Approves the application at `application_index` for the next cohort
+    pub fn approve(
+        &mut self,
+        application_index: usize,
+        warden_id: ElusivWardenID,
+    ) -> ProgramResult {
+        guard!(
+            application_index < self.get_applications_count() as usize
+                && self.get_applicants(application_index) == warden_id,
+            ElusivWardenNetworkError::InvalidInstructionData
+        );
+        guard!(
+            !self.get_approvals(application_index),
+            ElusivWardenNetworkError::WardenAlreadyConfirmed
+        );
+
+        let approvals_count = self.get_approvals_count();
+        guard!(
+            (approvals_count as usize) < ElusivFullWardenNetwork::SIZE.max(),
+            ElusivWardenNetworkError::CohortError
+        );
+
+        self.set_approvals(application_index, &true);
+        self.set_approvals_count(&(approvals_count + 1));
+
+        Ok(())
+    }
+
+    /// Replaces the current cohort with the approved applicants and starts the next epoch
+    ///
+    /// # Note
+    ///
+    /// Requires the current epoch to have elapsed (except for the first cohort) and a full cohort of approved applicants.
+    pub fn rotate_epoch(&mut self, timestamp: u64) -> ProgramResult {
+        let epoch = self.get_epoch();
+        if epoch > 0 {
+            guard!(
+                timestamp
+                    >= self
+                        .get_epoch_start_timestamp()
+                        .saturating_add(FULL_WARDEN_EPOCH_DURATION),
+                ElusivWardenNetworkError::EpochError
+            );
+        }
+        guard!(
+            self.get_approvals_count() as usize == ElusivFullWardenNetwork::SIZE.max(),
+            ElusivWardenNetworkError::CohortError
+        );
+
+        let approved: Vec<ElusivWardenID> = (0..self.get_applications_count() as usize)
+            .filter(|&i| self.get_approvals(i))
+            .map(|i| self.get_applicants(i))
+            .collect();
+
+        for (i, warden_id) in approved.iter().enumerate() {
+            self.set_members(i, warden_id);
+        }
+        self.set_members_count(&(approved.len() as u32));
+
+        for i in 0..self.get_applications_count() as usize {
+            self.set_approvals(i, &false);
+        }
+        self.set_applications_count(&0);
+        self.set_approvals_count(&0);
+
+        self.set_epoch(&(epoch + 1));
+        self.set_epoch_start_timestamp(&timestamp);
+
+        Ok(())
+    }
+}
+
 warden_network!(ElusivApaWardenNetwork, NetworkSize::Fixed(6));
 
 #[elusiv_account]
@@ -219,3 +359,72 @@ impl<'a> ApaWardenNetworkAccount<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elusiv_types::{ProgramAccount, SizedAccount};
+
+    #[test]
+    fn test_full_warden_network_rotate_epoch() {
+        let mut data = vec![0; FullWardenNetworkAccount::SIZE];
+        let mut network = FullWardenNetworkAccount::new(&mut data).unwrap();
+        let size = ElusivFullWardenNetwork::SIZE.max() as u32;
+
+        for warden_id in 0..size + 1 {
+            assert_eq!(network.apply(warden_id).unwrap(), warden_id);
+        }
+
+        // Duplicate application
+        assert_eq!(
+            network.apply(0),
+            Err(ElusivWardenNetworkError::WardenRegistrationError.into())
+        );
+
+        // Mismatched warden_id
+        assert_eq!(
+            network.approve(0, 1),
+            Err(ElusivWardenNetworkError::InvalidInstructionData.into())
+        );
+
+        // The first cohort does not wait for an epoch to elapse, but requires a full cohort
+        for warden_id in 1..size {
+            network.approve(warden_id as usize, warden_id).unwrap();
+        }
+        assert_eq!(
+            network.rotate_epoch(100),
+            Err(ElusivWardenNetworkError::CohortError.into())
+        );
+
+        network.approve(size as usize, size).unwrap();
+        assert_eq!(
+            network.approve(0, 0),
+            Err(ElusivWardenNetworkError::CohortError.into())
+        );
+
+        network.rotate_epoch(100).unwrap();
+        assert_eq!(network.get_epoch(), 1);
+        assert_eq!(network.get_members_count(), size);
+        assert!(!network.is_member(0));
+        assert!((1..size + 1).all(|warden_id| network.is_member(warden_id)));
+        assert_eq!(network.get_applications_count(), 0);
+        assert_eq!(network.get_approvals_count(), 0);
+
+        // The next cohort replaces the current one once the epoch has elapsed
+        for warden_id in 0..size {
+            network.apply(warden_id).unwrap();
+            network.approve(warden_id as usize, warden_id).unwrap();
+        }
+        assert_eq!(
+            network.rotate_epoch(100 + FULL_WARDEN_EPOCH_DURATION - 1),
+            Err(ElusivWardenNetworkError::EpochError.into())
+        );
+
+        network
+            .rotate_epoch(100 + FULL_WARDEN_EPOCH_DURATION)
+            .unwrap();
+        assert_eq!(network.get_epoch(), 2);
+        assert!(network.is_member(0));
+        assert!(!network.is_member(size));
+    }
+}
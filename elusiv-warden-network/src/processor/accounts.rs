@@ -1,10 +1,10 @@
 use crate::{
-    apa::ApaProposalsAccount,
-    network::{ApaWardenNetworkAccount, BasicWardenNetworkAccount},
+    apa::{ApaProposalsAccount, ApaProposalsPageAccount},
+    network::{ApaWardenNetworkAccount, BasicWardenNetworkAccount, FullWardenNetworkAccount},
     warden::WardensAccount,
 };
 use elusiv_types::UnverifiedAccountInfo;
-use elusiv_utils::open_pda_account_without_offset;
+use elusiv_utils::{open_pda_account_with_offset, open_pda_account_without_offset};
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
 
 pub fn init<'a, 'b>(
@@ -13,6 +13,7 @@ pub fn init<'a, 'b>(
     basic_network_account: UnverifiedAccountInfo<'a, 'b>,
     apa_network_account: UnverifiedAccountInfo<'a, 'b>,
     apa_proposals_account: UnverifiedAccountInfo<'a, 'b>,
+    apa_proposals_page_account: UnverifiedAccountInfo<'a, 'b>,
 ) -> ProgramResult {
     open_pda_account_without_offset::<WardensAccount>(
         &crate::id(),
@@ -38,10 +39,30 @@ pub fn init<'a, 'b>(
         apa_proposals_account.get_unsafe(),
         None,
     )?;
+    open_pda_account_with_offset::<ApaProposalsPageAccount>(
+        &crate::id(),
+        payer,
+        apa_proposals_page_account.get_unsafe(),
+        0,
+        None,
+    )?;
 
     Ok(())
 }
 
+/// Opens the [`FullWardenNetworkAccount`] (not part of [`init`], since the full network has been added later)
+pub fn open_full_warden_network_account<'a, 'b>(
+    payer: &AccountInfo<'b>,
+    full_network_account: UnverifiedAccountInfo<'a, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<FullWardenNetworkAccount>(
+        &crate::id(),
+        payer,
+        full_network_account.get_unsafe(),
+        None,
+    )
+}
+
 /// Closes a program owned account in devnet and localhost
 ///
 /// # Notes
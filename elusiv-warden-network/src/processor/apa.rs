@@ -1,24 +1,38 @@
-use super::current_timestamp;
+use super::{current_timestamp, verify_full_warden_cohort_member};
 use crate::apa::{
-    ApaProponentRole, ApaProposal, ApaProposalAccount, ApaProposalsAccount, ApaTargetMapAccount,
+    apa_proposals_page, is_apa_voting_period_elapsed, ApaBondStatus, ApaFlagAccount,
+    ApaProponentRole, ApaProposal, ApaProposalAccount, ApaProposalIndexEntry, ApaProposalsAccount,
+    ApaProposalsPageAccount, ApaProposalsPageSummaryAccount, ApaTargetMapAccount,
+    APA_PROPOSALS_PAGE_SIZE, APA_PROPOSAL_BOND_EXPIRY, APA_THRESHOLD_BPS_DENOMINATOR,
 };
 use crate::error::ElusivWardenNetworkError;
-use elusiv_types::{elusiv_token, UnverifiedAccountInfo, SPL_TOKEN_COUNT};
+use crate::network::FullWardenNetworkAccount;
+use crate::warden::BasicWardenMapAccount;
+use elusiv_types::{elusiv_token, ElusivOption, UnverifiedAccountInfo, SPL_TOKEN_COUNT};
 use elusiv_utils::{
-    guard, open_pda_account_with_associated_pubkey, open_pda_account_with_offset, pda_account,
+    close_account, guard, open_pda_account_with_associated_pubkey, open_pda_account_with_offset,
+    pda_account, transfer_lamports_from_pda_checked, transfer_with_system_program,
 };
 use solana_program::program_error::ProgramError;
 use solana_program::program_option::COption;
 use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
 
 /// Processes an [`ApaProposal`]
+///
+/// # Notes
+///
+/// - The proponent deposits the current `proposal_bond` of the [`ApaProposalsAccount`] into the [`ApaProposalAccount`].
+/// - The proposal is indexed in the [`ApaProposalsPageAccount`] of the `proposal_id`.
 pub fn propose_apa_proposal<'b>(
     proponent: &AccountInfo<'b>,
     mut proposal_account: UnverifiedAccountInfo<'_, 'b>,
     proposals_account: &mut ApaProposalsAccount,
+    page_account: &mut ApaProposalsPageAccount,
     target_map_account: &AccountInfo<'b>,
     token_mint: &AccountInfo,
+    system_program: &AccountInfo<'b>,
 
     proposal_id: u32,
     proposal: ApaProposal,
@@ -83,12 +97,32 @@ pub fn propose_apa_proposal<'b>(
         )?;
     }
 
+    let bond = proposals_account.get_proposal_bond();
+    if bond > 0 {
+        transfer_with_system_program(
+            proponent,
+            proposal_account.get_safe()?,
+            system_program,
+            bond,
+        )?;
+    }
+
     pda_account!(
         mut proposal_account,
         ApaProposalAccount,
         proposal_account.get_safe()?
     );
     proposal_account.set_proposal(&proposal);
+    proposal_account.set_bond(&bond);
+    proposal_account.set_bond_status(&ApaBondStatus::Held);
+
+    page_account.set_entries(
+        (proposal_id % APA_PROPOSALS_PAGE_SIZE) as usize,
+        &ApaProposalIndexEntry {
+            level: proposal.level,
+            bond_status: ApaBondStatus::Held,
+        },
+    );
 
     proposals_account.set_number_of_proposals(
         &proposal_count
@@ -98,3 +132,302 @@ pub fn propose_apa_proposal<'b>(
 
     Ok(())
 }
+
+/// Sets the bond required for new [`ApaProposal`]s
+///
+/// # Notes
+///
+/// `authority` needs to be the program's keypair.
+pub fn set_apa_proposal_bond(
+    authority: &AccountInfo,
+    proposals_account: &mut ApaProposalsAccount,
+
+    proposal_bond: u64,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ElusivWardenNetworkError::InvalidSigner
+    );
+
+    proposals_account.set_proposal_bond(&proposal_bond);
+
+    Ok(())
+}
+
+/// Sets the quorum and approval threshold for accepting [`ApaProposal`]s
+///
+/// # Notes
+///
+/// `authority` needs to be the program's keypair.
+pub fn set_apa_voting_parameters(
+    authority: &AccountInfo,
+    proposals_account: &mut ApaProposalsAccount,
+
+    quorum: u32,
+    threshold_bps: u16,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ElusivWardenNetworkError::InvalidSigner
+    );
+    guard!(
+        threshold_bps as u32 <= APA_THRESHOLD_BPS_DENOMINATOR,
+        ElusivWardenNetworkError::InvalidInstructionData
+    );
+
+    proposals_account.set_quorum(&quorum);
+    proposals_account.set_threshold_bps(&threshold_bps);
+
+    Ok(())
+}
+
+/// Votes on an [`ApaProposal`]
+///
+/// # Notes
+///
+/// Only members of the current cohort of the [`crate::network::ElusivFullWardenNetwork`] can vote (once) during the voting period.
+pub fn vote_apa_proposal(
+    _warden: &AccountInfo,
+    warden_map_account: &BasicWardenMapAccount,
+    full_network_account: &FullWardenNetworkAccount,
+    proposal_account: &mut ApaProposalAccount,
+
+    _proposal_id: u32,
+    approve: bool,
+) -> ProgramResult {
+    verify_full_warden_cohort_member(warden_map_account, full_network_account)?;
+
+    guard!(
+        !is_apa_voting_period_elapsed(
+            proposal_account.get_proposal().timestamp,
+            current_timestamp()?
+        ),
+        ElusivWardenNetworkError::VotingError
+    );
+
+    proposal_account.vote(warden_map_account.get_warden_id(), approve)
+}
+
+/// Finalizes the vote on an [`ApaProposal`] once the voting period has elapsed
+///
+/// # Notes
+///
+/// - An accepted proposal flags its target in the [`ApaFlagAccount`] (opened by the `payer` if required), unless the target is already flagged with a higher [`crate::apa::ApaLevel`].
+/// - The [`ApaTargetMapAccount`] of the target is updated to the proposal flagging the target.
+pub fn finalize_apa_proposal<'b>(
+    payer: &AccountInfo<'b>,
+    proposal_account: &mut ApaProposalAccount,
+    proposals_account: &ApaProposalsAccount,
+    map_account: &mut ApaTargetMapAccount,
+    flag_account: &AccountInfo<'b>,
+
+    proposal_id: u32,
+    target: Pubkey,
+) -> ProgramResult {
+    guard!(
+        !proposal_account.get_is_finalized(),
+        ElusivWardenNetworkError::VotingError
+    );
+
+    let proposal = proposal_account.get_proposal();
+    guard!(
+        proposal.target == target,
+        ElusivWardenNetworkError::InvalidInstructionData
+    );
+    guard!(
+        is_apa_voting_period_elapsed(proposal.timestamp, current_timestamp()?),
+        ElusivWardenNetworkError::VotingError
+    );
+
+    let is_accepted = proposal_account.is_approved(
+        proposals_account.get_quorum(),
+        proposals_account.get_threshold_bps(),
+    );
+    proposal_account.set_is_finalized(&true);
+    proposal_account.set_is_accepted(&is_accepted);
+
+    if !is_accepted {
+        return Ok(());
+    }
+
+    if flag_account.lamports() == 0 {
+        open_pda_account_with_associated_pubkey::<ApaFlagAccount>(
+            &crate::id(),
+            payer,
+            flag_account,
+            &target,
+            None,
+            None,
+        )?;
+    } else {
+        pda_account!(flag_account, ApaFlagAccount, flag_account);
+        if flag_account.get_level() as u8 > proposal.level as u8 {
+            return Ok(());
+        }
+    }
+
+    pda_account!(mut flag_account, ApaFlagAccount, flag_account);
+    flag_account.set_level(&proposal.level);
+    flag_account.set_token_constraint(&proposal.token_constraint);
+    flag_account.set_proposal_id(&proposal_id);
+    flag_account.set_region_scope(&proposal.region_scope);
+
+    map_account.set_proposal_id(&ElusivOption::Some(proposal_id));
+
+    Ok(())
+}
+
+/// Settles the bond of an [`ApaProposal`]
+///
+/// # Notes
+///
+/// - `refund`: the bond is returned to the proponent (accepted proposal), requires `signer` to be the program's keypair
+/// - otherwise the bond is forfeited to the [`ApaProposalsAccount`], which requires `signer` to be the program's keypair (frivolous proposal) or [`APA_PROPOSAL_BOND_EXPIRY`] to have passed
+pub fn settle_apa_proposal_bond<'a>(
+    signer: &AccountInfo,
+    proponent: &AccountInfo<'a>,
+    proposal_account: &AccountInfo<'a>,
+    proposals_account: &AccountInfo<'a>,
+    page_account: &mut ApaProposalsPageAccount,
+
+    proposal_id: u32,
+    refund: bool,
+) -> ProgramResult {
+    let is_authority = *signer.key == crate::ID;
+
+    let bond = {
+        pda_account!(mut proposal, ApaProposalAccount, proposal_account);
+        guard!(
+            matches!(proposal.get_bond_status(), ApaBondStatus::Held),
+            ElusivWardenNetworkError::ProposalBondError
+        );
+
+        let apa_proposal = proposal.get_proposal();
+        guard!(
+            apa_proposal.proponent == *proponent.key,
+            ElusivWardenNetworkError::ProposalBondError
+        );
+
+        if refund {
+            guard!(is_authority, ElusivWardenNetworkError::InvalidSigner);
+            proposal.set_bond_status(&ApaBondStatus::Refunded);
+        } else {
+            guard!(
+                is_authority
+                    || is_apa_proposal_bond_expired(apa_proposal.timestamp, current_timestamp()?),
+                ElusivWardenNetworkError::ProposalBondError
+            );
+            proposal.set_bond_status(&ApaBondStatus::Forfeited);
+        }
+
+        let entry_index = (proposal_id % APA_PROPOSALS_PAGE_SIZE) as usize;
+        let mut entry = page_account.get_entries(entry_index);
+        entry.bond_status = proposal.get_bond_status();
+        page_account.set_entries(entry_index, &entry);
+        page_account.set_number_of_concluded_proposals(
+            &(page_account.get_number_of_concluded_proposals() + 1),
+        );
+
+        proposal.get_bond()
+    };
+
+    if bond == 0 {
+        return Ok(());
+    }
+
+    let recipient = if refund { proponent } else { proposals_account };
+    transfer_lamports_from_pda_checked(proposal_account, recipient, bond)
+}
+
+/// Opens the [`ApaProposalsPageAccount`] indexing the next proposals
+///
+/// # Notes
+///
+/// The first page is opened during program initialization.
+pub fn open_apa_proposals_page<'b>(
+    payer: &AccountInfo<'b>,
+    proposals_account: &ApaProposalsAccount,
+    page_account: UnverifiedAccountInfo<'_, 'b>,
+
+    page: u32,
+) -> ProgramResult {
+    guard!(
+        page == apa_proposals_page(proposals_account.get_number_of_proposals()),
+        ElusivWardenNetworkError::ProposalError
+    );
+
+    open_pda_account_with_offset::<ApaProposalsPageAccount>(
+        &crate::id(),
+        payer,
+        page_account.get_unsafe(),
+        page,
+        None,
+    )
+}
+
+/// Archives a concluded [`ApaProposalsPageAccount`] into an [`ApaProposalsPageSummaryAccount`]
+///
+/// # Notes
+///
+/// The page account is closed and its rent is transferred to the `payer` (who pays for the summary account).
+pub fn archive_apa_proposals_page<'a, 'b>(
+    payer: &AccountInfo<'b>,
+    page_account: &AccountInfo<'b>,
+    mut summary_account: UnverifiedAccountInfo<'a, 'b>,
+
+    page: u32,
+) -> ProgramResult {
+    let summary = {
+        pda_account!(page_account, ApaProposalsPageAccount, page_account);
+        guard!(
+            page_account.is_concluded(),
+            ElusivWardenNetworkError::ProposalError
+        );
+
+        page_account.summary()
+    };
+
+    open_pda_account_with_offset::<ApaProposalsPageSummaryAccount>(
+        &crate::id(),
+        payer,
+        summary_account.get_unsafe_and_set_is_verified(),
+        page,
+        None,
+    )?;
+
+    {
+        pda_account!(
+            mut summary_account,
+            ApaProposalsPageSummaryAccount,
+            summary_account.get_safe()?
+        );
+        summary_account.set_summary(&summary);
+    }
+
+    close_account(payer, page_account)
+}
+
+pub fn is_apa_proposal_bond_expired(proposal_timestamp: u64, timestamp: u64) -> bool {
+    proposal_timestamp
+        .checked_add(APA_PROPOSAL_BOND_EXPIRY)
+        .map_or(false, |t| t <= timestamp)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_apa_proposal_bond_expired() {
+        assert!(!is_apa_proposal_bond_expired(0, 0));
+        assert!(!is_apa_proposal_bond_expired(
+            100,
+            100 + APA_PROPOSAL_BOND_EXPIRY - 1
+        ));
+        assert!(is_apa_proposal_bond_expired(
+            100,
+            100 + APA_PROPOSAL_BOND_EXPIRY
+        ));
+        assert!(!is_apa_proposal_bond_expired(u64::MAX, u64::MAX));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::error::ElusivWardenNetworkError;
+use crate::warden::{BasicWardenAccount, ElusivBasicWarden, ElusivWardenID, WardenAuthority};
+use elusiv_utils::guard;
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::secp256k1_program;
+use solana_program::sysvar::instructions;
+
+const SECP256K1_SIGNATURE_OFFSETS_SIZE: usize = 11;
+const SECP256K1_ETH_ADDRESS_SIZE: usize = 20;
+
+/// Verifies that `signer` acts on behalf of `basic_warden`
+///
+/// # Notes
+///
+/// - [`WardenAuthority::Secp256k1`]: `signer` can be any account (e.g. a relayer), but a secp256k1 program instruction preceding the current instruction has to sign the current instruction's data.
+/// - The secp256k1 signature itself is verified by the runtime, so we only check the signed eth-address and message.
+pub fn verify_basic_warden_authority(
+    signer: &AccountInfo,
+    basic_warden: &ElusivBasicWarden,
+    instructions_account: &AccountInfo,
+) -> ProgramResult {
+    guard!(signer.is_signer, ProgramError::MissingRequiredSignature);
+
+    match &basic_warden.authority {
+        WardenAuthority::Ed25519 => {
+            guard!(
+                *signer.key == basic_warden.config.key,
+                ProgramError::MissingRequiredSignature
+            );
+        }
+        WardenAuthority::Multisig { multisig } => {
+            guard!(
+                signer.key == multisig,
+                ProgramError::MissingRequiredSignature
+            );
+        }
+        WardenAuthority::Secp256k1 { eth_address } => {
+            let index = instructions::load_current_index_checked(instructions_account)?;
+            let current_ix =
+                instructions::load_instruction_at_checked(index as usize, instructions_account)?;
+
+            let is_signed = (0..index).any(|i| {
+                match instructions::load_instruction_at_checked(i as usize, instructions_account) {
+                    Ok(ix) => {
+                        ix.program_id == secp256k1_program::ID
+                            && secp256k1_instruction_signs(
+                                &ix.data,
+                                i as u8,
+                                eth_address,
+                                &current_ix.data,
+                            )
+                    }
+                    Err(_) => false,
+                }
+            });
+            guard!(is_signed, ElusivWardenNetworkError::InvalidWardenAuthority);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns true if the secp256k1 program instruction `data` (at `instruction_index`) contains a signature of `message` by `eth_address`
+///
+/// # Note
+///
+/// Only offsets referencing the secp256k1 instruction itself are accepted.
+pub fn secp256k1_instruction_signs(
+    data: &[u8],
+    instruction_index: u8,
+    eth_address: &[u8; SECP256K1_ETH_ADDRESS_SIZE],
+    message: &[u8],
+) -> bool {
+    let count = match data.first() {
+        Some(count) => *count as usize,
+        None => return false,
+    };
+
+    (0..count).any(|i| {
+        let start = 1 + i * SECP256K1_SIGNATURE_OFFSETS_SIZE;
+        let offsets = match data.get(start..start + SECP256K1_SIGNATURE_OFFSETS_SIZE) {
+            Some(offsets) => offsets,
+            None => return false,
+        };
+        let u16_at = |i: usize| u16::from_le_bytes([offsets[i], offsets[i + 1]]) as usize;
+
+        let signature_instruction_index = offsets[2];
+        let eth_address_offset = u16_at(3);
+        let eth_address_instruction_index = offsets[5];
+        let message_offset = u16_at(6);
+        let message_size = u16_at(8);
+        let message_instruction_index = offsets[10];
+
+        if signature_instruction_index != instruction_index
+            || eth_address_instruction_index != instruction_index
+            || message_instruction_index != instruction_index
+        {
+            return false;
+        }
+
+        data.get(eth_address_offset..eth_address_offset + SECP256K1_ETH_ADDRESS_SIZE)
+            == Some(&eth_address[..])
+            && data.get(message_offset..message_offset + message_size) == Some(message)
+    })
+}
+
+/// Replaces the [`WardenAuthority`] of a Warden (authorized by the current authority)
+pub fn set_basic_warden_authority(
+    warden: &AccountInfo,
+    warden_account: &mut BasicWardenAccount,
+    instructions_account: &AccountInfo,
+
+    _warden_id: ElusivWardenID,
+    authority: WardenAuthority,
+) -> ProgramResult {
+    let mut basic_warden = warden_account.get_warden();
+    verify_basic_warden_authority(warden, &basic_warden, instructions_account)?;
+
+    basic_warden.authority = authority;
+    warden_account.set_warden(&basic_warden);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secp256k1_instruction_data(
+        instruction_index: u8,
+        eth_address: &[u8; 20],
+        message: &[u8],
+    ) -> Vec<u8> {
+        let eth_address_offset = 1 + SECP256K1_SIGNATURE_OFFSETS_SIZE;
+        let signature_offset = eth_address_offset + 20;
+        let message_offset = signature_offset + 65;
+
+        let mut data = vec![1];
+        data.extend((signature_offset as u16).to_le_bytes());
+        data.push(instruction_index);
+        data.extend((eth_address_offset as u16).to_le_bytes());
+        data.push(instruction_index);
+        data.extend((message_offset as u16).to_le_bytes());
+        data.extend((message.len() as u16).to_le_bytes());
+        data.push(instruction_index);
+        data.extend(eth_address);
+        data.extend([0; 65]);
+        data.extend(message);
+        data
+    }
+
+    #[test]
+    fn test_secp256k1_instruction_signs() {
+        let eth_address = [7; 20];
+        let message = [1, 2, 3, 4];
+        let data = secp256k1_instruction_data(2, &eth_address, &message);
+
+        assert!(secp256k1_instruction_signs(
+            &data,
+            2,
+            &eth_address,
+            &message
+        ));
+
+        // Invalid instruction index
+        assert!(!secp256k1_instruction_signs(
+            &data,
+            1,
+            &eth_address,
+            &message
+        ));
+
+        // Invalid eth-address
+        assert!(!secp256k1_instruction_signs(&data, 2, &[8; 20], &message));
+
+        // Invalid message
+        assert!(!secp256k1_instruction_signs(
+            &data,
+            2,
+            &eth_address,
+            &[1, 2, 3]
+        ));
+        assert!(!secp256k1_instruction_signs(
+            &data,
+            2,
+            &eth_address,
+            &[1, 2, 3, 5]
+        ));
+
+        // Truncated data
+        assert!(!secp256k1_instruction_signs(
+            &data[..data.len() - 1],
+            2,
+            &eth_address,
+            &message
+        ));
+        assert!(!secp256k1_instruction_signs(&[], 2, &eth_address, &message));
+        assert!(!secp256k1_instruction_signs(
+            &[1, 0],
+            2,
+            &eth_address,
+            &message
+        ));
+    }
+}
@@ -1,17 +1,23 @@
 use crate::error::ElusivWardenNetworkError;
-use crate::processor::{current_timestamp, unix_timestamp_to_day_and_year};
+use crate::processor::{
+    current_slot, current_timestamp, unix_timestamp_to_day_and_year, verify_basic_warden_authority,
+};
+use crate::stake::WardenStakeAccount;
 use crate::warden::{
-    BasicWardenAccount, BasicWardenAttesterMapAccount, BasicWardenMapAccount,
-    BasicWardenStatsAccount, Timezone, WardenRegion,
+    is_monotonic_version_update, BasicWardenAccount, BasicWardenAttesterMapAccount,
+    BasicWardenMapAccount, BasicWardenStatsAccount, StatsCertificateAccount, Timezone,
+    WardenAttestationAccount, WardenAuthority, WardenCapacity, WardenRegion,
+    VERIFICATION_SLOT_DURATION, WARDEN_FEATURE_PROOF_VERIFICATION,
 };
 use crate::{
     network::BasicWardenNetworkAccount,
     warden::{ElusivBasicWarden, ElusivBasicWardenConfig, ElusivWardenID, WardensAccount},
 };
-use elusiv_types::UnverifiedAccountInfo;
+use elusiv_types::sysvar::InstructionsAccount;
+use elusiv_types::{ElusivOption, PDAAccount, UnverifiedAccountInfo};
 use elusiv_utils::{
     close_account, guard, open_pda_account_with_associated_pubkey, open_pda_account_with_offset,
-    pda_account,
+    pda_account, transfer_with_system_program,
 };
 use solana_program::account_info::AccountInfo;
 use solana_program::entrypoint::ProgramResult;
@@ -25,6 +31,8 @@ pub fn register_basic_warden<'a, 'b>(
     mut warden_map_account: UnverifiedAccountInfo<'a, 'b>,
     wardens_account: &mut WardensAccount,
     basic_network_account: &mut BasicWardenNetworkAccount,
+    mut stake_account: UnverifiedAccountInfo<'a, 'b>,
+    system_program: &AccountInfo<'b>,
 
     warden_id: ElusivWardenID,
     config: ElusivBasicWardenConfig,
@@ -48,6 +56,10 @@ pub fn register_basic_warden<'a, 'b>(
         is_metadata_valid: None.into(),
         activation_timestamp: current_timestamp,
         join_timestamp: current_timestamp,
+        supported_features: 0,
+        capacity: WardenCapacity::default(),
+        report_timestamp: current_timestamp,
+        authority: WardenAuthority::Ed25519,
     };
 
     guard!(
@@ -92,21 +104,46 @@ pub fn register_basic_warden<'a, 'b>(
     );
     warden_map_account.set_warden_id(&warden_id);
 
+    // The minimum stake is deposited as the Warden's collateral
+    open_pda_account_with_offset::<WardenStakeAccount>(
+        &crate::id(),
+        warden,
+        stake_account.get_unsafe_and_set_is_verified(),
+        warden_id,
+        None,
+    )?;
+
+    let minimum_stake = wardens_account.get_minimum_stake();
+    if minimum_stake > 0 {
+        transfer_with_system_program(
+            warden,
+            stake_account.get_safe()?,
+            system_program,
+            minimum_stake,
+        )?;
+    }
+
+    pda_account!(
+        mut stake_account,
+        WardenStakeAccount,
+        stake_account.get_safe()?
+    );
+    stake_account.set_warden_id(&warden_id);
+    stake_account.set_stake(&minimum_stake);
+
     Ok(())
 }
 
 pub fn update_basic_warden_state(
     warden: &AccountInfo,
     warden_account: &mut BasicWardenAccount,
+    instructions_account: &AccountInfo,
 
     _warden_id: ElusivWardenID,
     is_active: bool,
 ) -> ProgramResult {
     let mut basic_warden = warden_account.get_warden();
-    guard!(
-        *warden.key == basic_warden.config.key,
-        ProgramError::MissingRequiredSignature
-    );
+    verify_basic_warden_authority(warden, &basic_warden, instructions_account)?;
 
     // `activation_timestamp` is used to track all `is_active` changes
     if is_active != basic_warden.is_active {
@@ -118,20 +155,49 @@ pub fn update_basic_warden_state(
     Ok(())
 }
 
+/// Records the self-reported software version, supported features and capacity of a Warden
+///
+/// # Notes
+///
+/// The version can not be downgraded.
+pub fn update_basic_warden_capabilities(
+    warden: &AccountInfo,
+    warden_account: &mut BasicWardenAccount,
+    instructions_account: &AccountInfo,
+
+    _warden_id: ElusivWardenID,
+    version: [u16; 3],
+    supported_features: u64,
+    capacity: WardenCapacity,
+) -> ProgramResult {
+    let mut basic_warden = warden_account.get_warden();
+    verify_basic_warden_authority(warden, &basic_warden, instructions_account)?;
+    guard!(
+        is_monotonic_version_update(&basic_warden.config.version, &version),
+        ElusivWardenNetworkError::InvalidWardenVersion
+    );
+
+    basic_warden.config.version = version;
+    basic_warden.supported_features = supported_features;
+    basic_warden.capacity = capacity;
+    basic_warden.report_timestamp = current_timestamp()?;
+    warden_account.set_warden(&basic_warden);
+
+    Ok(())
+}
+
 pub fn update_basic_warden_lut(
     warden: &AccountInfo,
     warden_account: &mut BasicWardenAccount,
     lut_account: &AccountInfo,
+    instructions_account: &AccountInfo,
 
     _warden_id: ElusivWardenID,
 ) -> ProgramResult {
     // TODO: verify lut_account to be a valid, frozen LUT (but not required ATM)
 
     let mut basic_warden = warden_account.get_warden();
-    guard!(
-        *warden.key == basic_warden.config.key,
-        ProgramError::MissingRequiredSignature
-    );
+    verify_basic_warden_authority(warden, &basic_warden, instructions_account)?;
 
     basic_warden.lut = *lut_account.key;
     warden_account.set_warden(&basic_warden);
@@ -240,6 +306,76 @@ pub fn attest_basic_warden_metadata(
     Ok(())
 }
 
+/// Sets the attestation level of a Warden (required by the Elusiv program in permissioned mode)
+///
+/// # Note
+///
+/// The attested region of the Warden is recorded with the level (and needs to be refreshed by setting the level again after a region update).
+pub fn set_warden_attestation_level<'b>(
+    attester: &AccountInfo<'b>,
+    attester_warden_account: &BasicWardenAccount,
+    warden_account: &BasicWardenAccount,
+    mut attestation_account: UnverifiedAccountInfo<'_, 'b>,
+
+    _attester_warden_id: ElusivWardenID,
+    warden_id: ElusivWardenID,
+    warden: Pubkey,
+    level: u8,
+) -> ProgramResult {
+    let attester_warden = attester_warden_account.get_warden();
+    guard!(
+        *attester.key == attester_warden.config.key,
+        ElusivWardenNetworkError::InvalidSigner
+    );
+    guard!(
+        attester_warden.config.warden_features.attestation,
+        ElusivWardenNetworkError::InvalidSigner
+    );
+    let attested_warden = warden_account.get_warden();
+    guard!(
+        attested_warden.config.key == warden,
+        ElusivWardenNetworkError::InvalidInstructionData
+    );
+
+    if attestation_account.get_unsafe().data_is_empty() {
+        open_pda_account_with_associated_pubkey::<WardenAttestationAccount>(
+            &crate::id(),
+            attester,
+            attestation_account.get_unsafe(),
+            &warden,
+            None,
+            None,
+        )?;
+    } else {
+        guard!(
+            *attestation_account.get_unsafe().owner == crate::id(),
+            ElusivWardenNetworkError::InvalidInstructionData
+        );
+        WardenAttestationAccount::verify_account_with_pubkey(
+            attestation_account.get_unsafe(),
+            warden,
+            None,
+        )?;
+    }
+
+    pda_account!(
+        mut attestation_account,
+        WardenAttestationAccount,
+        attestation_account.get_unsafe_and_set_is_verified()
+    );
+    attestation_account.set_warden_id(&warden_id);
+    attestation_account.set_level(&level);
+
+    // Region-scoped APA decisions rely on the region, so it's only recorded once attested
+    let region = match attested_warden.is_metadata_valid.option() {
+        Some(true) => ElusivOption::Some(attested_warden.config.region),
+        _ => ElusivOption::None,
+    };
+    attestation_account.set_region(&region);
+
+    Ok(())
+}
+
 pub fn open_basic_warden_stats_account<'b>(
     warden: &AccountInfo,
     payer: &AccountInfo<'b>,
@@ -266,7 +402,109 @@ pub fn open_basic_warden_stats_account<'b>(
     Ok(())
 }
 
-const ELUSIV_PROGRAM_ID: Pubkey = crate::macros::program_id!(elusiv);
+/// Issues (or refreshes) the [`StatsCertificateAccount`] of a Warden for the `year`
+///
+/// # Notes
+///
+/// The summary is computed from the Warden's [`BasicWardenStatsAccount`], so it can't be self-reported.
+pub fn issue_stats_certificate<'b>(
+    warden: &AccountInfo<'b>,
+    warden_account: &BasicWardenAccount,
+    stats_account: &BasicWardenStatsAccount,
+    certificate_account: &AccountInfo<'b>,
+
+    warden_id: ElusivWardenID,
+    year: u16,
+) -> ProgramResult {
+    guard!(
+        *warden.key == warden_account.get_warden().config.key,
+        ElusivWardenNetworkError::InvalidSigner
+    );
+    guard!(
+        stats_account.get_year() == year,
+        ElusivWardenNetworkError::StatsError
+    );
+
+    if certificate_account.lamports() == 0 {
+        open_pda_account_with_associated_pubkey::<StatsCertificateAccount>(
+            &crate::id(),
+            warden,
+            certificate_account,
+            warden.key,
+            Some(year as u32),
+            None,
+        )?;
+    }
+
+    let summary = stats_account.summary(warden_id, *warden.key, current_timestamp()?);
+
+    pda_account!(
+        mut certificate_account,
+        StatsCertificateAccount,
+        certificate_account
+    );
+    certificate_account.set_digest(&summary.digest());
+    certificate_account.set_summary(&summary);
+
+    Ok(())
+}
+
+/// Updates the ranking weight of a Warden in the [`WardensAccount`] (see [`BasicWardenStatsAccount::ranking_weight`])
+///
+/// # Notes
+///
+/// - Can be called by anyone, since the weight is computed from the Warden's accounts.
+/// - Inactive Wardens and Wardens not supporting proof verifications are removed from the ranking.
+pub fn update_warden_ranking(
+    warden: &AccountInfo,
+    warden_account: &BasicWardenAccount,
+    stats_account: &BasicWardenStatsAccount,
+    wardens_account: &mut WardensAccount,
+
+    warden_id: ElusivWardenID,
+    year: u16,
+) -> ProgramResult {
+    let basic_warden = warden_account.get_warden();
+    guard!(
+        *warden.key == basic_warden.config.key,
+        ElusivWardenNetworkError::InvalidInstructionData
+    );
+
+    let current_timestamp = current_timestamp()?;
+    let (day, y) = unix_timestamp_to_day_and_year(current_timestamp)
+        .ok_or(ElusivWardenNetworkError::TimestampError)?;
+    guard!(
+        y == year && stats_account.get_year() == year,
+        ElusivWardenNetworkError::StatsError
+    );
+
+    let weight = if basic_warden.is_active
+        && basic_warden.supports_features(WARDEN_FEATURE_PROOF_VERIFICATION)
+    {
+        let uptime_days =
+            current_timestamp.saturating_sub(basic_warden.activation_timestamp) / 86_400;
+        stats_account.ranking_weight(day, uptime_days.try_into().unwrap_or(u32::MAX))
+    } else {
+        0
+    };
+    wardens_account.update_ranking(warden_id, weight);
+
+    Ok(())
+}
+
+/// Assigns the next proof verification slot to a ranked Warden (weighted by the ranking)
+///
+/// # Notes
+///
+/// - Can be called by anyone, once the current verification slot has expired (see [`VERIFICATION_SLOT_DURATION`]).
+/// - Wardens only start verifications during their assigned verification slots, which avoids contention on the verification accounts.
+pub fn assign_verification_slot(wardens_account: &mut WardensAccount) -> ProgramResult {
+    wardens_account.assign_verification_slot(current_slot()?)?;
+
+    Ok(())
+}
+
+const ELUSIV_PROGRAM_ID: Pubkey = crate::macros::program_id!("elusiv");
 
 pub struct TrackableElusivInstruction {
     pub instruction_id: u8,
@@ -294,7 +532,7 @@ pub const TRACKABLE_ELUSIV_INSTRUCTIONS: [TrackableElusivInstruction; 3] = [
 pub fn track_basic_warden_stats(
     warden: &AccountInfo,
     stats_account: &mut BasicWardenStatsAccount,
-    instructions_account: &AccountInfo,
+    instructions_account: &InstructionsAccount,
 
     year: u16,
     can_fail: bool,
@@ -316,7 +554,7 @@ pub fn track_basic_warden_stats(
 fn track_basic_warden_stats_inner(
     warden: &AccountInfo,
     stats_account: &mut BasicWardenStatsAccount,
-    instructions_account: &AccountInfo,
+    instructions_account: &InstructionsAccount,
 
     year: u16,
 ) -> ProgramResult {
@@ -331,7 +569,7 @@ fn track_basic_warden_stats_inner(
         ElusivWardenNetworkError::StatsError
     );
 
-    let index = instructions::load_current_index_checked(instructions_account)?;
+    let index = instructions_account.sysvar().current_index;
     let previous_ix = instructions::load_instruction_at_checked(
         index
             .checked_sub(1)
@@ -0,0 +1,72 @@
+use super::current_timestamp;
+use crate::error::ElusivWardenNetworkError;
+use crate::network::FullWardenNetworkAccount;
+use crate::warden::{BasicWardenMapAccount, ElusivWardenID};
+use elusiv_utils::guard;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+
+/// Applies for the next cohort of the [`crate::network::ElusivFullWardenNetwork`]
+///
+/// # Notes
+///
+/// Only registered Basic Wardens can apply (see [`FullWardenNetworkAccount::apply`]).
+pub fn apply_full_warden(
+    _warden: &AccountInfo,
+    warden_map_account: &BasicWardenMapAccount,
+    full_network_account: &mut FullWardenNetworkAccount,
+
+    warden_id: ElusivWardenID,
+) -> ProgramResult {
+    guard!(
+        warden_map_account.get_warden_id() == warden_id,
+        ElusivWardenNetworkError::InvalidInstructionData
+    );
+
+    full_network_account.apply(warden_id)?;
+
+    Ok(())
+}
+
+/// Approves an application for the next cohort of the [`crate::network::ElusivFullWardenNetwork`]
+///
+/// # Notes
+///
+/// `authority` needs to be the program's keypair.
+pub fn approve_full_warden_application(
+    authority: &AccountInfo,
+    full_network_account: &mut FullWardenNetworkAccount,
+
+    application_index: u32,
+    warden_id: ElusivWardenID,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ElusivWardenNetworkError::InvalidSigner
+    );
+
+    full_network_account.approve(application_index as usize, warden_id)
+}
+
+/// Replaces the current cohort of the [`crate::network::ElusivFullWardenNetwork`] with the approved applicants
+///
+/// # Notes
+///
+/// Anyone can rotate the epoch, once it has elapsed (see [`FullWardenNetworkAccount::rotate_epoch`]).
+pub fn rotate_full_warden_network_epoch(
+    full_network_account: &mut FullWardenNetworkAccount,
+) -> ProgramResult {
+    full_network_account.rotate_epoch(current_timestamp()?)
+}
+
+/// Verifies that the Warden of `warden_map_account` is a member of the current cohort of the [`crate::network::ElusivFullWardenNetwork`]
+pub fn verify_full_warden_cohort_member(
+    warden_map_account: &BasicWardenMapAccount,
+    full_network_account: &FullWardenNetworkAccount,
+) -> ProgramResult {
+    guard!(
+        full_network_account.is_member(warden_map_account.get_warden_id()),
+        ElusivWardenNetworkError::CohortError
+    );
+
+    Ok(())
+}
@@ -1,13 +1,19 @@
 mod accounts;
 mod apa;
 mod apa_warden;
+mod auth;
 mod basic_warden;
+mod full_warden;
 mod operator;
+mod stake;
 mod utils;
 
 pub use accounts::*;
 pub use apa::*;
 pub use apa_warden::*;
+pub use auth::*;
 pub use basic_warden::*;
+pub use full_warden::*;
 pub use operator::*;
+pub use stake::*;
 pub use utils::*;
@@ -0,0 +1,173 @@
+use super::{current_timestamp, unix_timestamp_to_day_and_year};
+use crate::error::ElusivWardenNetworkError;
+use crate::stake::{
+    is_stake_slashable, is_stake_withdrawal_allowed, WardenStakeAccount,
+    STAKE_SLASHING_INACTIVITY_DAYS,
+};
+use crate::warden::{BasicWardenAccount, BasicWardenStatsAccount, ElusivWardenID, WardensAccount};
+use elusiv_utils::{
+    guard, pda_account, transfer_lamports_from_pda_checked, transfer_with_system_program,
+};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+
+/// Deposits `amount` lamports into the [`WardenStakeAccount`] of a Warden
+///
+/// # Notes
+///
+/// Anyone can top-up the stake of a Warden.
+pub fn deposit_stake<'b>(
+    payer: &AccountInfo<'b>,
+    stake_account: &AccountInfo<'b>,
+    system_program: &AccountInfo<'b>,
+
+    _warden_id: ElusivWardenID,
+    amount: u64,
+) -> ProgramResult {
+    guard!(amount > 0, ElusivWardenNetworkError::StakeError);
+
+    transfer_with_system_program(payer, stake_account, system_program, amount)?;
+
+    pda_account!(mut account, WardenStakeAccount, stake_account);
+    let stake = account
+        .get_stake()
+        .checked_add(amount)
+        .ok_or(ElusivWardenNetworkError::Overflow)?;
+    account.set_stake(&stake);
+
+    Ok(())
+}
+
+/// Withdraws `amount` lamports of a Warden's stake to the Warden's key
+///
+/// # Notes
+///
+/// Only stake above the `minimum_stake` of the [`WardensAccount`] can be withdrawn by an active Warden.
+pub fn withdraw_stake<'a>(
+    warden: &AccountInfo<'a>,
+    warden_account: &BasicWardenAccount,
+    stake_account: &AccountInfo<'a>,
+    wardens_account: &WardensAccount,
+
+    _warden_id: ElusivWardenID,
+    amount: u64,
+) -> ProgramResult {
+    let basic_warden = warden_account.get_warden();
+    guard!(
+        *warden.key == basic_warden.config.key,
+        ElusivWardenNetworkError::InvalidSigner
+    );
+
+    {
+        pda_account!(mut account, WardenStakeAccount, stake_account);
+        let remaining_stake = account
+            .get_stake()
+            .checked_sub(amount)
+            .ok_or(ElusivWardenNetworkError::StakeError)?;
+
+        guard!(
+            is_stake_withdrawal_allowed(
+                basic_warden.is_active,
+                basic_warden.activation_timestamp,
+                remaining_stake,
+                wardens_account.get_minimum_stake(),
+                current_timestamp()?,
+            ),
+            ElusivWardenNetworkError::StakeError
+        );
+
+        account.set_stake(&remaining_stake);
+    }
+
+    transfer_lamports_from_pda_checked(stake_account, warden, amount)
+}
+
+/// Slashes up to `amount` lamports of the stake of an inactive Warden
+///
+/// # Notes
+///
+/// - `authority` needs to be the program's keypair
+/// - the [`BasicWardenStatsAccount`] of the current year has to show no activity for the last [`STAKE_SLASHING_INACTIVITY_DAYS`]
+/// - slashed lamports are transferred into the [`WardensAccount`] (treasury)
+#[allow(clippy::too_many_arguments)]
+pub fn slash_stake<'a>(
+    authority: &AccountInfo,
+    warden: &AccountInfo,
+    warden_account: &BasicWardenAccount,
+    stats_account: &BasicWardenStatsAccount,
+    stake_account: &AccountInfo<'a>,
+    wardens_account: &AccountInfo<'a>,
+
+    _warden_id: ElusivWardenID,
+    year: u16,
+    amount: u64,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ElusivWardenNetworkError::InvalidSigner
+    );
+
+    let basic_warden = warden_account.get_warden();
+    guard!(
+        *warden.key == basic_warden.config.key,
+        ElusivWardenNetworkError::InvalidInstructionData
+    );
+
+    let current_timestamp = current_timestamp()?;
+    let (day, y) = unix_timestamp_to_day_and_year(current_timestamp)
+        .ok_or(ElusivWardenNetworkError::TimestampError)?;
+    guard!(
+        y == year && stats_account.get_year() == year,
+        ElusivWardenNetworkError::StatsError
+    );
+
+    let recent_activity =
+        stats_account.activity(day.saturating_sub(STAKE_SLASHING_INACTIVITY_DAYS - 1)..day + 1);
+    guard!(
+        is_stake_slashable(
+            basic_warden.is_active,
+            basic_warden.activation_timestamp,
+            day,
+            recent_activity,
+            current_timestamp,
+        ),
+        ElusivWardenNetworkError::StakeError
+    );
+
+    let slashed_amount = {
+        pda_account!(mut account, WardenStakeAccount, stake_account);
+        let stake = account.get_stake();
+        let slashed_amount = amount.min(stake);
+
+        account.set_stake(&(stake - slashed_amount));
+        account.set_slashed(&account.get_slashed().saturating_add(slashed_amount));
+
+        slashed_amount
+    };
+
+    if slashed_amount == 0 {
+        return Ok(());
+    }
+
+    transfer_lamports_from_pda_checked(stake_account, wardens_account, slashed_amount)
+}
+
+/// Sets the stake required for registering new Wardens
+///
+/// # Notes
+///
+/// `authority` needs to be the program's keypair.
+pub fn set_minimum_stake(
+    authority: &AccountInfo,
+    wardens_account: &mut WardensAccount,
+
+    minimum_stake: u64,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ElusivWardenNetworkError::InvalidSigner
+    );
+
+    wardens_account.set_minimum_stake(&minimum_stake);
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use crate::warden::ElusivWardenID;
+use elusiv_proc_macros::elusiv_account;
+use elusiv_types::accounts::PDAAccountData;
+
+/// Number of days without tracked activity, after which the stake of an active Warden can be slashed
+pub const STAKE_SLASHING_INACTIVITY_DAYS: u32 = 7;
+
+/// Duration (in seconds) a Warden has to be inactive, before its stake can be withdrawn below the minimum stake
+pub const STAKE_UNBONDING_PERIOD: u64 = 60 * 60 * 24 * 14;
+
+/// The collateral of a single [`crate::warden::ElusivBasicWarden`]
+///
+/// # Note
+///
+/// The minimum stake of the [`crate::warden::WardensAccount`] is deposited on registration.
+#[elusiv_account]
+pub struct WardenStakeAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub warden_id: ElusivWardenID,
+
+    /// Lamports held in this account as the Warden's stake
+    pub stake: u64,
+
+    /// Total amount of slashed lamports
+    pub slashed: u64,
+}
+
+/// Returns true if a Warden can reduce its stake to `remaining_stake`
+///
+/// # Note
+///
+/// Stake above the minimum stake can always be withdrawn, the rest only after the Warden has been inactive for [`STAKE_UNBONDING_PERIOD`].
+pub fn is_stake_withdrawal_allowed(
+    is_active: bool,
+    activation_timestamp: u64,
+    remaining_stake: u64,
+    minimum_stake: u64,
+    timestamp: u64,
+) -> bool {
+    if remaining_stake >= minimum_stake {
+        return true;
+    }
+
+    !is_active
+        && activation_timestamp
+            .checked_add(STAKE_UNBONDING_PERIOD)
+            .map_or(false, |t| t <= timestamp)
+}
+
+/// Returns true if the stake of a Warden can be slashed for inactivity
+///
+/// # Note
+///
+/// A Warden is slashable, if it has been active for the last [`STAKE_SLASHING_INACTIVITY_DAYS`] (of the current year) without any tracked activity.
+pub fn is_stake_slashable(
+    is_active: bool,
+    activation_timestamp: u64,
+    day: u32,
+    recent_activity: u32,
+    timestamp: u64,
+) -> bool {
+    let inactivity_period = STAKE_SLASHING_INACTIVITY_DAYS as u64 * 86_400;
+
+    is_active
+        && day >= STAKE_SLASHING_INACTIVITY_DAYS
+        && recent_activity == 0
+        && activation_timestamp
+            .checked_add(inactivity_period)
+            .map_or(false, |t| t <= timestamp)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_stake_withdrawal_allowed() {
+        assert!(is_stake_withdrawal_allowed(true, 0, 100, 100, 0));
+        assert!(!is_stake_withdrawal_allowed(true, 0, 99, 100, u64::MAX));
+
+        assert!(!is_stake_withdrawal_allowed(
+            false,
+            100,
+            0,
+            100,
+            100 + STAKE_UNBONDING_PERIOD - 1
+        ));
+        assert!(is_stake_withdrawal_allowed(
+            false,
+            100,
+            0,
+            100,
+            100 + STAKE_UNBONDING_PERIOD
+        ));
+        assert!(!is_stake_withdrawal_allowed(
+            false,
+            u64::MAX,
+            0,
+            100,
+            u64::MAX
+        ));
+    }
+
+    #[test]
+    fn test_is_stake_slashable() {
+        let timestamp = STAKE_SLASHING_INACTIVITY_DAYS as u64 * 86_400;
+        let day = STAKE_SLASHING_INACTIVITY_DAYS;
+
+        assert!(is_stake_slashable(true, 0, day, 0, timestamp));
+
+        // Inactive Wardens
+        assert!(!is_stake_slashable(false, 0, day, 0, timestamp));
+
+        // Tracked activity
+        assert!(!is_stake_slashable(true, 0, day, 1, timestamp));
+
+        // Recently activated
+        assert!(!is_stake_slashable(true, 1, day, 0, timestamp));
+
+        // Window exceeds the current year
+        assert!(!is_stake_slashable(true, 0, day - 1, 0, timestamp));
+    }
+}
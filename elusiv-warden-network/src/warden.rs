@@ -12,13 +12,129 @@ use std::net::Ipv4Addr;
 pub type ElusivWardenID = u32;
 
 /// The [`ElusivWardensAccount`] assigns each new Warden it's [`ElusivWardenID`]
-#[elusiv_account(eager_type: true)]
+#[elusiv_account]
 pub struct WardensAccount {
     #[no_getter]
     #[no_setter]
     pda_data: PDAAccountData,
 
     pub next_warden_id: ElusivWardenID,
+
+    /// The stake (in lamports) required for registering a new Warden
+    ///
+    /// # Note
+    ///
+    /// Slashed stakes are transferred into this account (treasury).
+    pub minimum_stake: u64,
+
+    /// The ranked Wardens and their weights (see [`WardensAccount::update_ranking`])
+    pub ranked_count: u32,
+    pub ranked_warden_ids: [ElusivWardenID; MAX_RANKED_WARDENS],
+    pub ranking_weights: [u32; MAX_RANKED_WARDENS],
+
+    /// The state of the weighted round-robin over the ranked Wardens
+    pub ranking_current_weights: [i64; MAX_RANKED_WARDENS],
+
+    /// The Warden assigned to the current verification slot
+    pub assigned_warden_id: ElusivOption<ElusivWardenID>,
+
+    /// The slot at which the current verification slot has been assigned
+    pub assignment_slot: u64,
+}
+
+/// The max number of Wardens in the ranking of the [`WardensAccount`]
+pub const MAX_RANKED_WARDENS: usize = 32;
+
+/// The number of slots of a verification slot, during which only the assigned Warden starts proof verifications
+pub const VERIFICATION_SLOT_DURATION: u64 = 150;
+
+/// The number of days of activity considered for the ranking weight of a Warden
+pub const RANKING_ACTIVITY_DAYS: u32 = 30;
+
+impl<'a> WardensAccount<'a> {
+    fn ranking_index(&self, warden_id: ElusivWardenID) -> Option<usize> {
+        (0..self.get_ranked_count() as usize).find(|&i| self.get_ranked_warden_ids(i) == warden_id)
+    }
+
+    /// Sets the ranking weight of a Warden (a weight of `0` removes the Warden from the ranking)
+    ///
+    /// # Note
+    ///
+    /// If the ranking is full, a new Warden replaces the Warden with the lowest weight (if its own weight is higher).
+    pub fn update_ranking(&mut self, warden_id: ElusivWardenID, weight: u32) {
+        let count = self.get_ranked_count() as usize;
+
+        match self.ranking_index(warden_id) {
+            Some(index) if weight == 0 => {
+                let last = count - 1;
+                self.set_ranked_warden_ids(index, &self.get_ranked_warden_ids(last));
+                self.set_ranking_weights(index, &self.get_ranking_weights(last));
+                self.set_ranking_current_weights(index, &self.get_ranking_current_weights(last));
+                self.set_ranked_count(&(last as u32));
+            }
+            Some(index) => self.set_ranking_weights(index, &weight),
+            None if weight == 0 => {}
+            None => {
+                let index = if count < MAX_RANKED_WARDENS {
+                    self.set_ranked_count(&(count as u32 + 1));
+                    count
+                } else {
+                    let lowest = (0..count)
+                        .min_by_key(|&i| self.get_ranking_weights(i))
+                        .unwrap();
+                    if self.get_ranking_weights(lowest) >= weight {
+                        return;
+                    }
+                    lowest
+                };
+
+                self.set_ranked_warden_ids(index, &warden_id);
+                self.set_ranking_weights(index, &weight);
+                self.set_ranking_current_weights(index, &0);
+            }
+        }
+    }
+
+    /// Assigns the next verification slot to a ranked Warden, once the current verification slot has expired
+    ///
+    /// # Note
+    ///
+    /// Uses a smooth weighted round-robin: each Warden is assigned a share of the verification slots proportional to its weight, spread evenly over time.
+    pub fn assign_verification_slot(&mut self, slot: u64) -> Result<ElusivWardenID, ProgramError> {
+        if self.get_assigned_warden_id().option().is_some() {
+            guard!(
+                slot >= self
+                    .get_assignment_slot()
+                    .saturating_add(VERIFICATION_SLOT_DURATION),
+                ElusivWardenNetworkError::VerificationSlotError
+            );
+        }
+
+        let count = self.get_ranked_count() as usize;
+        guard!(count > 0, ElusivWardenNetworkError::VerificationSlotError);
+
+        let total_weight: i64 = (0..count).map(|i| self.get_ranking_weights(i) as i64).sum();
+
+        let mut selected = 0;
+        let mut selected_weight = i64::MIN;
+        for i in 0..count {
+            let current_weight =
+                self.get_ranking_current_weights(i) + self.get_ranking_weights(i) as i64;
+            self.set_ranking_current_weights(i, &current_weight);
+
+            if current_weight > selected_weight {
+                selected = i;
+                selected_weight = current_weight;
+            }
+        }
+        self.set_ranking_current_weights(selected, &(selected_weight - total_weight));
+
+        let warden_id = self.get_ranked_warden_ids(selected);
+        self.set_assigned_warden_id(&Some(warden_id).into());
+        self.set_assignment_slot(&slot);
+
+        Ok(warden_id)
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone, PartialEq, Eq)]
@@ -127,6 +243,44 @@ impl WardenRegion {
     }
 }
 
+/// Bits of the self-reported `supported_features` of an [`ElusivBasicWarden`]
+pub const WARDEN_FEATURE_RPC: u64 = 1 << 0;
+pub const WARDEN_FEATURE_RELAY: u64 = 1 << 1;
+pub const WARDEN_FEATURE_INSTANT_RELAY: u64 = 1 << 2;
+pub const WARDEN_FEATURE_COMMITMENT_HASHING: u64 = 1 << 3;
+pub const WARDEN_FEATURE_PROOF_VERIFICATION: u64 = 1 << 4;
+pub const WARDEN_FEATURE_APA: u64 = 1 << 5;
+pub const WARDEN_FEATURE_ATTESTATION: u64 = 1 << 6;
+
+/// Self-reported capacity hints of a Warden (used for scheduling work)
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct WardenCapacity {
+    /// Upstream bandwidth in Mbit/s
+    pub bandwidth_mbps: u32,
+    pub max_concurrent_verifications: u16,
+    pub max_transactions_per_minute: u32,
+}
+
+/// Controls the operations of a [`ElusivBasicWarden`]
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub enum WardenAuthority {
+    /// The ed25519 key `config.key` signs
+    Ed25519,
+
+    /// A secp256k1 key (e.g. HSM-backed) signs the instruction data, verified through a preceding secp256k1 program instruction
+    Secp256k1 { eth_address: [u8; 20] },
+
+    /// A multisig PDA signs (through a CPI of the multisig program)
+    Multisig { multisig: Pubkey },
+}
+
+/// Returns true if `new_version` is not lower than `version` (semantic versioning order)
+pub fn is_monotonic_version_update(version: &[u16; 3], new_version: &[u16; 3]) -> bool {
+    new_version >= version
+}
+
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone, PartialEq)]
 #[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
 pub struct ElusivBasicWardenConfig {
@@ -167,10 +321,25 @@ pub struct ElusivBasicWarden {
 
     /// Indicates the last time, `is_active` has been changed
     pub activation_timestamp: u64,
+
+    /// Bitset of `WARDEN_FEATURE_*` flags (self-reported alongside `config.version`)
+    pub supported_features: u64,
+    pub capacity: WardenCapacity,
+
+    /// Indicates the last time, the version, features or capacity have been reported
+    pub report_timestamp: u64,
+
+    pub authority: WardenAuthority,
+}
+
+impl ElusivBasicWarden {
+    pub fn supports_features(&self, features: u64) -> bool {
+        self.supported_features & features == features
+    }
 }
 
 /// An account associated with a single [`ElusivBasicWarden`]
-#[elusiv_account(eager_type: true)]
+#[elusiv_account]
 pub struct BasicWardenAccount {
     #[no_getter]
     #[no_setter]
@@ -180,7 +349,7 @@ pub struct BasicWardenAccount {
 }
 
 /// An account associated with a single [`ElusivBasicWarden`]
-#[elusiv_account(eager_type: true)]
+#[elusiv_account]
 pub struct BasicWardenMapAccount {
     #[no_getter]
     #[no_setter]
@@ -210,10 +379,16 @@ impl WardenStatistics {
 
         Ok(self)
     }
+
+    /// Returns the total activity of all `days`
+    pub fn activity(&self, days: std::ops::Range<u32>) -> u32 {
+        days.filter_map(|day| self.activity.get(day as usize))
+            .fold(0, |acc, a| acc.saturating_add(*a))
+    }
 }
 
 /// An account associated with a single [`ElusivBasicWarden`] storing activity statistics for a single year
-#[elusiv_account(eager_type: true)]
+#[elusiv_account]
 pub struct BasicWardenStatsAccount {
     #[no_getter]
     #[no_setter]
@@ -227,6 +402,92 @@ pub struct BasicWardenStatsAccount {
     pub migrate: WardenStatistics,
 }
 
+impl<'a> BasicWardenStatsAccount<'a> {
+    /// Returns the total activity (store, send and migrate) of all `days`
+    pub fn activity(&self, days: std::ops::Range<u32>) -> u32 {
+        self.get_store()
+            .activity(days.clone())
+            .saturating_add(self.get_send().activity(days.clone()))
+            .saturating_add(self.get_migrate().activity(days))
+    }
+
+    /// Returns the ranking weight of a Warden at `day`: its activity during the last [`RANKING_ACTIVITY_DAYS`] days plus its uptime in days (capped at [`RANKING_ACTIVITY_DAYS`])
+    pub fn ranking_weight(&self, day: u32, uptime_days: u32) -> u32 {
+        self.activity(day.saturating_sub(RANKING_ACTIVITY_DAYS - 1)..day + 1)
+            .saturating_add(uptime_days.min(RANKING_ACTIVITY_DAYS))
+    }
+
+    pub fn summary(
+        &self,
+        warden_id: ElusivWardenID,
+        warden: Pubkey,
+        issued_at: u64,
+    ) -> WardenStatsSummary {
+        let store = self.get_store();
+        let send = self.get_send();
+        let migrate = self.get_migrate();
+        let active_days = (0..366)
+            .filter(|&day| {
+                store.activity[day] > 0 || send.activity[day] > 0 || migrate.activity[day] > 0
+            })
+            .count() as u16;
+
+        WardenStatsSummary {
+            warden_id,
+            warden,
+            year: self.get_year(),
+            store: store.total,
+            send: send.total,
+            migrate: migrate.total,
+            active_days,
+            last_activity_timestamp: self.get_last_activity_timestamp(),
+            issued_at,
+        }
+    }
+}
+
+/// Summary of the [`BasicWardenStatsAccount`] of an [`ElusivBasicWarden`] for a single year
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone, PartialEq)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct WardenStatsSummary {
+    pub warden_id: ElusivWardenID,
+    pub warden: Pubkey,
+    pub year: u16,
+
+    pub store: u32,
+    pub send: u32,
+    pub migrate: u32,
+
+    /// Number of days with any activity
+    pub active_days: u16,
+    pub last_activity_timestamp: u64,
+
+    /// The timestamp at which the summary was issued
+    pub issued_at: u64,
+}
+
+impl WardenStatsSummary {
+    /// `sha256` of the serialized summary (for off-chain references)
+    pub fn digest(&self) -> [u8; 32] {
+        solana_program::hash::hash(&self.try_to_vec().unwrap()).to_bytes()
+    }
+}
+
+/// A [`WardenStatsSummary`] issued by the program (associated with the Warden's key and the year)
+///
+/// # Note
+///
+/// Only the program can write to this PDA, so third parties can consume the summary without trusting self-reported numbers.
+#[elusiv_account]
+pub struct StatsCertificateAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub summary: WardenStatsSummary,
+    pub digest: [u8; 32],
+}
+
 /// An account associated with a single [`ElusivBasicWarden`]
 #[elusiv_account]
 pub struct BasicWardenAttesterMapAccount {
@@ -237,12 +498,31 @@ pub struct BasicWardenAttesterMapAccount {
     pub warden_id: ElusivWardenID,
 }
 
+/// The attestation level of an [`ElusivBasicWarden`] (associated with the Warden's key)
+///
+/// # Note
+///
+/// The Elusiv program reads `level` (in permissioned mode) and `region` (for region-scoped APA flags) directly, so the layout is only appended to.
+#[elusiv_account]
+pub struct WardenAttestationAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub warden_id: ElusivWardenID,
+    pub level: u8,
+
+    /// The region of the Warden at the time of the attestation (`None` if its metadata has not been attested as valid)
+    pub region: ElusivOption<WardenRegion>,
+}
+
 const HALF_QUOTE_SIZE: usize = 558;
 const FULL_QUOTE_SIZE: usize = 1116;
 
 /// An SGX quote.
 /// See [the remote attestation crate](https://github.com/elusiv-privacy/rust-sgx-remote-attestation)
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
 pub struct Quote(pub [u8; FULL_QUOTE_SIZE]);
 
 /// The first half of an SGX quote.
@@ -250,6 +530,7 @@ pub struct Quote(pub [u8; FULL_QUOTE_SIZE]);
 ///
 /// See also [`QuoteEnd`]
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
 pub struct QuoteStart(pub [u8; HALF_QUOTE_SIZE]);
 
 impl QuoteStart {
@@ -272,6 +553,7 @@ impl QuoteStart {
 ///
 /// See also [`QuoteStart`]
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
 pub struct QuoteEnd(pub [u8; HALF_QUOTE_SIZE]);
 
 #[elusiv_account]
@@ -284,3 +566,175 @@ pub struct ApaWardenAccount {
     pub network_member_index: u32,
     // pub latest_quote: Quote,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use elusiv_types::{ProgramAccount, SizedAccount};
+
+    #[test]
+    fn test_is_monotonic_version_update() {
+        assert!(is_monotonic_version_update(&[1, 2, 3], &[1, 2, 3]));
+        assert!(is_monotonic_version_update(&[1, 2, 3], &[1, 2, 4]));
+        assert!(is_monotonic_version_update(&[1, 2, 3], &[1, 3, 0]));
+        assert!(is_monotonic_version_update(&[1, 2, 3], &[2, 0, 0]));
+
+        assert!(!is_monotonic_version_update(&[1, 2, 3], &[1, 2, 2]));
+        assert!(!is_monotonic_version_update(&[1, 2, 3], &[1, 1, 9]));
+        assert!(!is_monotonic_version_update(&[1, 2, 3], &[0, 9, 9]));
+    }
+
+    #[test]
+    fn test_warden_statistics_activity() {
+        let mut stats = WardenStatistics {
+            activity: [0; 366],
+            total: 0,
+        };
+        stats.activity[3] = 2;
+        stats.activity[5] = 1;
+
+        assert_eq!(stats.activity(0..3), 0);
+        assert_eq!(stats.activity(3..6), 3);
+        assert_eq!(stats.activity(0..400), 3);
+    }
+
+    #[test]
+    fn test_update_ranking() {
+        let mut data = vec![0; WardensAccount::SIZE];
+        let mut wardens_account = WardensAccount::new(&mut data).unwrap();
+
+        wardens_account.update_ranking(0, 0);
+        assert_eq!(wardens_account.get_ranked_count(), 0);
+
+        for warden_id in 0..MAX_RANKED_WARDENS as u32 {
+            wardens_account.update_ranking(warden_id, warden_id + 1);
+        }
+        assert_eq!(
+            wardens_account.get_ranked_count(),
+            MAX_RANKED_WARDENS as u32
+        );
+
+        // Updating a ranked Warden
+        wardens_account.update_ranking(1, 100);
+        assert_eq!(wardens_account.get_ranking_weights(1), 100);
+
+        // A full ranking only accepts Wardens with a higher weight than the lowest ranked Warden
+        wardens_account.update_ranking(100, 1);
+        assert_eq!(wardens_account.ranking_index(100), None);
+        wardens_account.update_ranking(100, 2);
+        assert_eq!(wardens_account.ranking_index(0), None);
+        assert_eq!(wardens_account.ranking_index(100), Some(0));
+
+        // Removing a Warden
+        wardens_account.update_ranking(2, 0);
+        assert_eq!(
+            wardens_account.get_ranked_count(),
+            MAX_RANKED_WARDENS as u32 - 1
+        );
+        assert_eq!(wardens_account.ranking_index(2), None);
+        assert_eq!(
+            wardens_account.ranking_index(MAX_RANKED_WARDENS as u32 - 1),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_assign_verification_slot() {
+        let mut data = vec![0; WardensAccount::SIZE];
+        let mut wardens_account = WardensAccount::new(&mut data).unwrap();
+
+        assert_eq!(
+            wardens_account.assign_verification_slot(0),
+            Err(ElusivWardenNetworkError::VerificationSlotError.into())
+        );
+
+        wardens_account.update_ranking(7, 3);
+        wardens_account.update_ranking(8, 1);
+
+        let mut slot = 0;
+        let mut assignments = Vec::new();
+        for _ in 0..8 {
+            assignments.push(wardens_account.assign_verification_slot(slot).unwrap());
+            assert_eq!(
+                wardens_account.get_assigned_warden_id().option(),
+                Some(assignments[assignments.len() - 1])
+            );
+
+            // The current verification slot has not expired yet
+            assert_eq!(
+                wardens_account.assign_verification_slot(slot + VERIFICATION_SLOT_DURATION - 1),
+                Err(ElusivWardenNetworkError::VerificationSlotError.into())
+            );
+
+            slot += VERIFICATION_SLOT_DURATION;
+        }
+
+        // Assignments are proportional to the weights and spread evenly
+        assert_eq!(assignments, vec![7, 7, 8, 7, 7, 7, 8, 7]);
+    }
+
+    #[test]
+    fn test_ranking_weight() {
+        let mut data = vec![0; BasicWardenStatsAccount::SIZE];
+        let mut stats_account = BasicWardenStatsAccount::new(&mut data).unwrap();
+
+        let mut send = stats_account.get_send();
+        send.activity[10] = 5;
+        send.activity[50] = 2;
+        stats_account.set_send(&send);
+
+        assert_eq!(stats_account.ranking_weight(5, 0), 0);
+        assert_eq!(stats_account.ranking_weight(10, 0), 5);
+        assert_eq!(stats_account.ranking_weight(39, 0), 5);
+        assert_eq!(stats_account.ranking_weight(40, 0), 0);
+        assert_eq!(stats_account.ranking_weight(50, 3), 5);
+
+        // The uptime is capped
+        assert_eq!(
+            stats_account.ranking_weight(50, 1000),
+            2 + RANKING_ACTIVITY_DAYS
+        );
+    }
+
+    #[test]
+    fn test_warden_stats_summary() {
+        let mut data = vec![0; BasicWardenStatsAccount::SIZE];
+        let mut stats_account = BasicWardenStatsAccount::new(&mut data).unwrap();
+
+        let mut store = stats_account.get_store();
+        store.activity[1] = 2;
+        store.activity[7] = 1;
+        store.total = 3;
+        stats_account.set_store(&store);
+
+        let mut send = stats_account.get_send();
+        send.activity[7] = 4;
+        send.total = 4;
+        stats_account.set_send(&send);
+
+        stats_account.set_year(&2023);
+        stats_account.set_last_activity_timestamp(&100);
+
+        let warden = Pubkey::new_unique();
+        let summary = stats_account.summary(1, warden, 200);
+        assert_eq!(
+            summary,
+            WardenStatsSummary {
+                warden_id: 1,
+                warden,
+                year: 2023,
+                store: 3,
+                send: 4,
+                migrate: 0,
+                active_days: 2,
+                last_activity_timestamp: 100,
+                issued_at: 200,
+            }
+        );
+
+        assert_ne!(
+            summary.digest(),
+            stats_account.summary(2, warden, 200).digest()
+        );
+    }
+}
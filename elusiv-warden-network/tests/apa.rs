@@ -4,15 +4,22 @@ use assert_matches::assert_matches;
 use async_trait::async_trait;
 use common::*;
 use elusiv_types::{
-    ElusivOption, PDAAccount, ProgramAccount, SignerAccount, UserAccount, WritableSignerAccount,
-    SPL_TOKEN_COUNT,
+    ElusivOption, PDAAccount, ProgramAccount, SignerAccount, SizedAccount, UserAccount,
+    WritableSignerAccount, WritableUserAccount, SPL_TOKEN_COUNT,
 };
 use elusiv_warden_network::error::ElusivWardenNetworkError;
-use elusiv_warden_network::warden::{QuoteEnd, QuoteStart};
+use elusiv_warden_network::warden::{QuoteEnd, QuoteStart, WardenRegion};
 use elusiv_warden_network::{
-    apa::{ApaLevel, ApaProponentRole, ApaProposal, ApaProposalAccount, ApaProposalsAccount},
+    apa::{
+        ApaBondStatus, ApaFlagAccount, ApaLevel, ApaProponentRole, ApaProposal, ApaProposalAccount,
+        ApaProposalIndexEntry, ApaProposalsAccount, ApaProposalsPageAccount,
+        ApaProposalsPageSummary, ApaProposalsPageSummaryAccount, ApaTargetMapAccount,
+        APA_PROPOSALS_PAGE_SIZE,
+    },
     instruction::ElusivWardenNetworkInstruction,
-    network::{ApaWardenNetworkAccount, ElusivApaWardenNetwork, WardenNetwork},
+    network::{
+        ApaWardenNetworkAccount, ElusivApaWardenNetwork, FullWardenNetworkAccount, WardenNetwork,
+    },
 };
 use solana_program::{
     instruction::{Instruction, InstructionError},
@@ -266,6 +273,7 @@ async fn test_propose_apa_proposal() {
         token_constraint: ElusivOption::None,
         target: Pubkey::new_unique(),
         reason: String::new().try_into().unwrap(),
+        region_scope: ElusivOption::None,
     };
 
     // Invalid proposal_id
@@ -322,6 +330,430 @@ async fn test_propose_apa_proposal() {
     assert_eq!(proposal, apa_proposal_account.proposal);
 }
 
+#[tokio::test]
+async fn test_apa_proposal_bond() {
+    const BOND: u64 = 1_000_000;
+
+    let mut test = start_test_with_setup().await;
+    let proponent = Actor::new(&mut test).await;
+    let other = Actor::new(&mut test).await;
+
+    // Only the program authority can set the bond
+    test.ix_fails_with_warden_error(
+        ElusivWardenNetworkInstruction::set_apa_proposal_bond_instruction(
+            BOND,
+            SignerAccount(other.pubkey),
+        ),
+        &[&other.keypair],
+        ElusivWardenNetworkError::InvalidSigner,
+    )
+    .await;
+
+    test.set_pda_account::<ApaProposalsAccount, _>(
+        &elusiv_warden_network::id(),
+        None,
+        None,
+        |data| {
+            let mut account = ApaProposalsAccount::new(data).unwrap();
+            account.set_proposal_bond(&BOND);
+        },
+    )
+    .await;
+
+    let proposal = ApaProposal {
+        proponent: Pubkey::new_from_array([0; 32]),
+        timestamp: 0,
+        proponent_role: ApaProponentRole::Default,
+        level: ApaLevel::Flag1,
+        token_constraint: ElusivOption::None,
+        target: Pubkey::new_unique(),
+        reason: String::new().try_into().unwrap(),
+        region_scope: ElusivOption::None,
+    };
+    test.ix_should_succeed(
+        ElusivWardenNetworkInstruction::propose_apa_proposal_instruction(
+            0,
+            proposal,
+            WritableSignerAccount(proponent.pubkey),
+            UserAccount(Pubkey::new_unique()),
+        ),
+        &[&proponent.keypair],
+    )
+    .await;
+
+    let proposal_account = test.eager_account::<ApaProposalAccount, _>(Some(0)).await;
+    assert_eq!(proposal_account.bond, BOND);
+    assert_eq!(proposal_account.bond_status, ApaBondStatus::Held);
+    assert_eq!(
+        test.pda_lamports(
+            &ApaProposalAccount::find(Some(0)).0,
+            ApaProposalAccount::SIZE
+        )
+        .await
+        .0,
+        BOND
+    );
+
+    // Refund requires the program authority
+    test.ix_fails_with_warden_error(
+        ElusivWardenNetworkInstruction::settle_apa_proposal_bond_instruction(
+            0,
+            true,
+            SignerAccount(other.pubkey),
+            WritableUserAccount(proponent.pubkey),
+        ),
+        &[&other.keypair],
+        ElusivWardenNetworkError::InvalidSigner,
+    )
+    .await;
+
+    let forfeit_ix = ElusivWardenNetworkInstruction::settle_apa_proposal_bond_instruction(
+        0,
+        false,
+        SignerAccount(other.pubkey),
+        WritableUserAccount(proponent.pubkey),
+    );
+
+    // Forfeiture before the expiry
+    test.ix_fails_with_warden_error(
+        forfeit_ix.clone(),
+        &[&other.keypair],
+        ElusivWardenNetworkError::ProposalBondError,
+    )
+    .await;
+
+    // Expire the proposal
+    test.set_pda_account::<ApaProposalAccount, _>(
+        &elusiv_warden_network::id(),
+        None,
+        Some(0),
+        |data| {
+            let mut account = ApaProposalAccount::new(data).unwrap();
+            let mut proposal = account.get_proposal();
+            proposal.timestamp = 0;
+            account.set_proposal(&proposal);
+        },
+    )
+    .await;
+
+    // Invalid proponent
+    test.ix_fails_with_warden_error(
+        ElusivWardenNetworkInstruction::settle_apa_proposal_bond_instruction(
+            0,
+            false,
+            SignerAccount(other.pubkey),
+            WritableUserAccount(other.pubkey),
+        ),
+        &[&other.keypair],
+        ElusivWardenNetworkError::ProposalBondError,
+    )
+    .await;
+
+    let treasury = ApaProposalsAccount::find(None).0;
+    let treasury_lamports = test.lamports(&treasury).await.0;
+
+    test.ix_should_succeed(forfeit_ix.clone(), &[&other.keypair])
+        .await;
+
+    assert_eq!(test.lamports(&treasury).await.0, treasury_lamports + BOND);
+    assert_eq!(
+        test.pda_lamports(
+            &ApaProposalAccount::find(Some(0)).0,
+            ApaProposalAccount::SIZE
+        )
+        .await
+        .0,
+        0
+    );
+    assert_eq!(
+        test.eager_account::<ApaProposalAccount, _>(Some(0))
+            .await
+            .bond_status,
+        ApaBondStatus::Forfeited
+    );
+
+    let page_account = test
+        .eager_account::<ApaProposalsPageAccount, _>(Some(0))
+        .await;
+    assert_eq!(page_account.number_of_concluded_proposals, 1);
+    assert_eq!(
+        page_account.entries[0],
+        ApaProposalIndexEntry {
+            level: ApaLevel::Flag1,
+            bond_status: ApaBondStatus::Forfeited,
+        }
+    );
+
+    // A bond can only be settled once
+    test.ix_fails_with_warden_error(
+        forfeit_ix,
+        &[&other.keypair],
+        ElusivWardenNetworkError::ProposalBondError,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_apa_proposals_page() {
+    let mut test = start_test_with_setup().await;
+    let payer = Actor::new(&mut test).await;
+
+    let open_ix = ElusivWardenNetworkInstruction::open_apa_proposals_page_instruction(
+        1,
+        WritableSignerAccount(payer.pubkey),
+    );
+    let archive_ix = ElusivWardenNetworkInstruction::archive_apa_proposals_page_instruction(
+        0,
+        WritableSignerAccount(payer.pubkey),
+    );
+
+    // The first page is not full
+    test.ix_fails_with_warden_error(
+        open_ix.clone(),
+        &[&payer.keypair],
+        ElusivWardenNetworkError::ProposalError,
+    )
+    .await;
+
+    test.set_pda_account::<ApaProposalsAccount, _>(
+        &elusiv_warden_network::id(),
+        None,
+        None,
+        |data| {
+            let mut account = ApaProposalsAccount::new(data).unwrap();
+            account.set_number_of_proposals(&APA_PROPOSALS_PAGE_SIZE);
+        },
+    )
+    .await;
+
+    test.ix_should_succeed(open_ix, &[&payer.keypair]).await;
+    assert!(
+        test.account_does_exist(&ApaProposalsPageAccount::find(Some(1)).0)
+            .await
+    );
+
+    let entries: Vec<ApaProposalIndexEntry> = (0..APA_PROPOSALS_PAGE_SIZE)
+        .map(|i| ApaProposalIndexEntry {
+            level: ApaLevel::Outcast,
+            bond_status: if i % 2 == 0 {
+                ApaBondStatus::Refunded
+            } else {
+                ApaBondStatus::Forfeited
+            },
+        })
+        .collect();
+
+    // The page contains unsettled proposals
+    test.set_pda_account::<ApaProposalsPageAccount, _>(
+        &elusiv_warden_network::id(),
+        None,
+        Some(0),
+        |data| {
+            let mut account = ApaProposalsPageAccount::new(data).unwrap();
+            for (i, entry) in entries.iter().enumerate() {
+                account.set_entries(i, entry);
+            }
+            account.set_number_of_concluded_proposals(&(APA_PROPOSALS_PAGE_SIZE - 1));
+        },
+    )
+    .await;
+
+    test.ix_fails_with_warden_error(
+        archive_ix.clone(),
+        &[&payer.keypair],
+        ElusivWardenNetworkError::ProposalError,
+    )
+    .await;
+
+    test.set_pda_account::<ApaProposalsPageAccount, _>(
+        &elusiv_warden_network::id(),
+        None,
+        Some(0),
+        |data| {
+            let mut account = ApaProposalsPageAccount::new(data).unwrap();
+            account.set_number_of_concluded_proposals(&APA_PROPOSALS_PAGE_SIZE);
+        },
+    )
+    .await;
+
+    test.ix_should_succeed(archive_ix.clone(), &[&payer.keypair])
+        .await;
+
+    assert!(
+        test.account_does_not_exist(&ApaProposalsPageAccount::find(Some(0)).0)
+            .await
+    );
+    let summary_account = test
+        .eager_account::<ApaProposalsPageSummaryAccount, _>(Some(0))
+        .await;
+    assert_eq!(
+        summary_account.summary,
+        ApaProposalsPageSummary::new(&entries)
+    );
+    assert_eq!(
+        summary_account.summary.level_counts,
+        [0, 0, APA_PROPOSALS_PAGE_SIZE]
+    );
+
+    // A page can only be archived once
+    test.ix_should_fail(archive_ix, &[&payer.keypair]).await;
+}
+
+#[tokio::test]
+async fn test_apa_proposal_voting() {
+    let mut test = start_test_with_setup().await;
+    let proponent = Actor::new(&mut test).await;
+    let mut warden = Actor::new(&mut test).await;
+    let target = Pubkey::new_unique();
+
+    test.ix_should_succeed(
+        ElusivWardenNetworkInstruction::open_full_warden_network_account_instruction(
+            WritableSignerAccount(proponent.pubkey),
+        ),
+        &[&proponent.keypair],
+    )
+    .await;
+    register_warden(&mut test, &mut warden).await;
+
+    let proposal = ApaProposal {
+        proponent: Pubkey::new_from_array([0; 32]),
+        timestamp: 0,
+        proponent_role: ApaProponentRole::Default,
+        level: ApaLevel::Flag2,
+        token_constraint: ElusivOption::Some(1),
+        target,
+        reason: String::new().try_into().unwrap(),
+        region_scope: ElusivOption::Some(WardenRegion::Europe),
+    };
+    test.ix_should_succeed(
+        ElusivWardenNetworkInstruction::propose_apa_proposal_instruction(
+            0,
+            proposal,
+            WritableSignerAccount(proponent.pubkey),
+            UserAccount(Pubkey::new_unique()),
+        ),
+        &[&proponent.keypair],
+    )
+    .await;
+
+    let vote_ix = ElusivWardenNetworkInstruction::vote_apa_proposal_instruction(
+        0,
+        true,
+        SignerAccount(warden.pubkey),
+    );
+    let finalize_ix = ElusivWardenNetworkInstruction::finalize_apa_proposal_instruction(
+        0,
+        target,
+        WritableSignerAccount(proponent.pubkey),
+    );
+
+    // Only members of the current Full Warden cohort can vote
+    test.ix_fails_with_warden_error(
+        vote_ix.clone(),
+        &[&warden.keypair],
+        ElusivWardenNetworkError::CohortError,
+    )
+    .await;
+
+    test.set_pda_account::<FullWardenNetworkAccount, _>(
+        &elusiv_warden_network::id(),
+        None,
+        None,
+        |data| {
+            let mut account = FullWardenNetworkAccount::new(data).unwrap();
+            account.set_members_count(&1);
+            account.set_members(0, &0);
+        },
+    )
+    .await;
+
+    test.ix_should_succeed(vote_ix.clone(), &[&warden.keypair])
+        .await;
+
+    // Duplicate vote
+    test.ix_fails_with_warden_error(
+        vote_ix.clone(),
+        &[&warden.keypair],
+        ElusivWardenNetworkError::VotingError,
+    )
+    .await;
+
+    // Finalization before the end of the voting period
+    test.ix_fails_with_warden_error(
+        finalize_ix.clone(),
+        &[&proponent.keypair],
+        ElusivWardenNetworkError::VotingError,
+    )
+    .await;
+
+    // Only the program authority can set the voting parameters
+    test.ix_fails_with_warden_error(
+        ElusivWardenNetworkInstruction::set_apa_voting_parameters_instruction(
+            1,
+            5_000,
+            SignerAccount(proponent.pubkey),
+        ),
+        &[&proponent.keypair],
+        ElusivWardenNetworkError::InvalidSigner,
+    )
+    .await;
+
+    test.set_pda_account::<ApaProposalsAccount, _>(
+        &elusiv_warden_network::id(),
+        None,
+        None,
+        |data| {
+            let mut account = ApaProposalsAccount::new(data).unwrap();
+            account.set_quorum(&1);
+            account.set_threshold_bps(&5_000);
+        },
+    )
+    .await;
+
+    // End the voting period
+    test.set_pda_account::<ApaProposalAccount, _>(
+        &elusiv_warden_network::id(),
+        None,
+        Some(0),
+        |data| {
+            let mut account = ApaProposalAccount::new(data).unwrap();
+            let mut proposal = account.get_proposal();
+            proposal.timestamp = 0;
+            account.set_proposal(&proposal);
+        },
+    )
+    .await;
+
+    test.ix_should_succeed(finalize_ix.clone(), &[&proponent.keypair])
+        .await;
+
+    let proposal_account = test.eager_account::<ApaProposalAccount, _>(Some(0)).await;
+    assert!(proposal_account.is_finalized);
+    assert!(proposal_account.is_accepted);
+
+    let flag_account = test.eager_account2::<ApaFlagAccount, _>(target, None).await;
+    assert_eq!(flag_account.level, ApaLevel::Flag2);
+    assert_eq!(flag_account.token_constraint.option(), Some(1));
+    assert_eq!(flag_account.proposal_id, 0);
+    assert_eq!(
+        flag_account.region_scope.option(),
+        Some(WardenRegion::Europe)
+    );
+
+    let map_account = test
+        .eager_account2::<ApaTargetMapAccount, _>(target, None)
+        .await;
+    assert_eq!(map_account.proposal_id.option(), Some(0));
+
+    // A proposal can only be finalized once
+    test.ix_fails_with_warden_error(
+        finalize_ix,
+        &[&proponent.keypair],
+        ElusivWardenNetworkError::VotingError,
+    )
+    .await;
+}
+
 #[async_trait]
 trait IxFailsWith {
     async fn ix_fails_with_warden_error(
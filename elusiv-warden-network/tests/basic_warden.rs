@@ -1,13 +1,20 @@
 mod common;
 
 use common::*;
-use elusiv_types::{ProgramAccount, SignerAccount, UserAccount, WritableSignerAccount, TOKENS};
+use elusiv_types::{
+    PDAAccount, ProgramAccount, SignerAccount, SizedAccount, UserAccount, WritableSignerAccount,
+    TOKENS,
+};
 use elusiv_warden_network::{
     instruction::ElusivWardenNetworkInstruction,
     processor::{unix_timestamp_to_day_and_year, TRACKABLE_ELUSIV_INSTRUCTIONS},
+    stake::WardenStakeAccount,
     warden::{
         BasicWardenAccount, BasicWardenFeatures, BasicWardenMapAccount, BasicWardenStatsAccount,
-        ElusivBasicWardenConfig, Timezone, WardenFeatures, WardenRegion,
+        ElusivBasicWardenConfig, StatsCertificateAccount, Timezone, WardenAuthority,
+        WardenCapacity, WardenFeatures, WardenRegion, WardensAccount, VERIFICATION_SLOT_DURATION,
+        WARDEN_FEATURE_APA, WARDEN_FEATURE_PROOF_VERIFICATION, WARDEN_FEATURE_RELAY,
+        WARDEN_FEATURE_RPC,
     },
 };
 use solana_program::{
@@ -290,6 +297,192 @@ async fn test_update_lut() {
     assert_eq!(basic_warden_account.warden.lut, lut);
 }
 
+#[tokio::test]
+async fn test_update_capabilities() {
+    let mut test = start_test_with_setup().await;
+
+    let mut warden = Actor::new(&mut test).await;
+    register_warden(&mut test, &mut warden).await;
+
+    let capacity = WardenCapacity {
+        bandwidth_mbps: 1000,
+        max_concurrent_verifications: 4,
+        max_transactions_per_minute: 600,
+    };
+
+    fn update_capabilities_ix(
+        warden: Pubkey,
+        version: [u16; 3],
+        supported_features: u64,
+        capacity: WardenCapacity,
+    ) -> Instruction {
+        ElusivWardenNetworkInstruction::update_basic_warden_capabilities_instruction(
+            0,
+            version,
+            supported_features,
+            capacity,
+            SignerAccount(warden),
+        )
+    }
+
+    // Invalid signer
+    test.ix_should_fail_simple(update_capabilities_ix(
+        warden.pubkey,
+        [0, 1, 0],
+        WARDEN_FEATURE_RPC,
+        capacity.clone(),
+    ))
+    .await;
+    test.ix_should_fail_simple(update_capabilities_ix(
+        test.payer(),
+        [0, 1, 0],
+        WARDEN_FEATURE_RPC,
+        capacity.clone(),
+    ))
+    .await;
+
+    let features = WARDEN_FEATURE_RPC | WARDEN_FEATURE_RELAY;
+    test.ix_should_succeed(
+        update_capabilities_ix(warden.pubkey, [0, 1, 0], features, capacity.clone()),
+        &[&warden.keypair],
+    )
+    .await;
+
+    let basic_warden_account = test.eager_account::<BasicWardenAccount, _>(Some(0)).await;
+    assert_eq!(basic_warden_account.warden.config.version, [0, 1, 0]);
+    assert_eq!(basic_warden_account.warden.supported_features, features);
+    assert_eq!(basic_warden_account.warden.capacity, capacity);
+    assert!(basic_warden_account
+        .warden
+        .supports_features(WARDEN_FEATURE_RELAY));
+    assert!(!basic_warden_account
+        .warden
+        .supports_features(WARDEN_FEATURE_APA));
+
+    // Version downgrade
+    test.ix_should_fail(
+        update_capabilities_ix(warden.pubkey, [0, 0, 9], features, capacity.clone()),
+        &[&warden.keypair],
+    )
+    .await;
+
+    // Same version (e.g. updated capacity)
+    let capacity = WardenCapacity {
+        bandwidth_mbps: 100,
+        ..capacity
+    };
+    test.ix_should_succeed(
+        update_capabilities_ix(warden.pubkey, [0, 1, 0], features, capacity.clone()),
+        &[&warden.keypair],
+    )
+    .await;
+
+    // Version upgrade
+    test.ix_should_succeed(
+        update_capabilities_ix(
+            warden.pubkey,
+            [1, 0, 0],
+            features | WARDEN_FEATURE_APA,
+            capacity.clone(),
+        ),
+        &[&warden.keypair],
+    )
+    .await;
+
+    let basic_warden_account = test.eager_account::<BasicWardenAccount, _>(Some(0)).await;
+    assert_eq!(basic_warden_account.warden.config.version, [1, 0, 0]);
+    assert_eq!(basic_warden_account.warden.capacity, capacity);
+    assert!(basic_warden_account
+        .warden
+        .supports_features(WARDEN_FEATURE_APA));
+}
+
+#[tokio::test]
+async fn test_set_authority() {
+    let mut test = start_test_with_setup().await;
+
+    let mut warden = Actor::new(&mut test).await;
+    register_warden(&mut test, &mut warden).await;
+    let multisig = Actor::new(&mut test).await;
+
+    let basic_warden_account = test.eager_account::<BasicWardenAccount, _>(Some(0)).await;
+    assert_eq!(
+        basic_warden_account.warden.authority,
+        WardenAuthority::Ed25519
+    );
+
+    let set_authority_ix = |authority: WardenAuthority, signer: Pubkey| {
+        ElusivWardenNetworkInstruction::set_basic_warden_authority_instruction(
+            0,
+            authority,
+            SignerAccount(signer),
+        )
+    };
+    let update_state_ix = |signer: Pubkey| {
+        ElusivWardenNetworkInstruction::update_basic_warden_state_instruction(
+            0,
+            true,
+            SignerAccount(signer),
+        )
+    };
+
+    // Invalid signer
+    test.ix_should_fail(
+        set_authority_ix(
+            WardenAuthority::Multisig {
+                multisig: multisig.pubkey,
+            },
+            multisig.pubkey,
+        ),
+        &[&multisig.keypair],
+    )
+    .await;
+
+    test.ix_should_succeed(
+        set_authority_ix(
+            WardenAuthority::Multisig {
+                multisig: multisig.pubkey,
+            },
+            warden.pubkey,
+        ),
+        &[&warden.keypair],
+    )
+    .await;
+
+    let basic_warden_account = test.eager_account::<BasicWardenAccount, _>(Some(0)).await;
+    assert_eq!(
+        basic_warden_account.warden.authority,
+        WardenAuthority::Multisig {
+            multisig: multisig.pubkey
+        }
+    );
+
+    // The warden key is not able to sign anymore
+    test.ix_should_fail(update_state_ix(warden.pubkey), &[&warden.keypair])
+        .await;
+    test.ix_should_succeed(update_state_ix(multisig.pubkey), &[&multisig.keypair])
+        .await;
+
+    let basic_warden_account = test.eager_account::<BasicWardenAccount, _>(Some(0)).await;
+    assert!(basic_warden_account.warden.is_active);
+
+    // Secp256k1 authority requires a preceding secp256k1 instruction
+    test.ix_should_succeed(
+        set_authority_ix(
+            WardenAuthority::Secp256k1 {
+                eth_address: [1; 20],
+            },
+            multisig.pubkey,
+        ),
+        &[&multisig.keypair],
+    )
+    .await;
+    test.ix_should_fail(update_state_ix(multisig.pubkey), &[&multisig.keypair])
+        .await;
+    test.ix_should_fail(update_state_ix(warden.pubkey), &[&warden.keypair])
+        .await;
+}
+
 #[tokio::test]
 async fn test_open_stats_account() {
     let mut test = start_test_with_setup().await;
@@ -318,6 +511,92 @@ async fn test_open_stats_account() {
     }
 }
 
+#[tokio::test]
+async fn test_issue_stats_certificate() {
+    let mut test = start_test_with_setup().await;
+
+    let mut warden = Actor::new(&mut test).await;
+    register_warden(&mut test, &mut warden).await;
+    let other = Actor::new(&mut test).await;
+
+    let year = 2023;
+    test.ix_should_succeed_simple(
+        ElusivWardenNetworkInstruction::open_basic_warden_stats_account_instruction(
+            year,
+            UserAccount(warden.pubkey),
+            WritableSignerAccount(test.payer()),
+        ),
+    )
+    .await;
+
+    test.set_pda_account::<BasicWardenStatsAccount, _>(
+        &elusiv_warden_network::id(),
+        Some(warden.pubkey),
+        Some(year as u32),
+        |data| {
+            let mut account = BasicWardenStatsAccount::new(data).unwrap();
+            let mut store = account.get_store();
+            store.activity[10] = 5;
+            store.total = 5;
+            account.set_store(&store);
+        },
+    )
+    .await;
+
+    // Only the Warden can request its certificate
+    test.ix_should_fail(
+        ElusivWardenNetworkInstruction::issue_stats_certificate_instruction(
+            0,
+            year,
+            WritableSignerAccount(other.pubkey),
+        ),
+        &[&other.keypair],
+    )
+    .await;
+
+    let issue_ix = ElusivWardenNetworkInstruction::issue_stats_certificate_instruction(
+        0,
+        year,
+        WritableSignerAccount(warden.pubkey),
+    );
+    test.ix_should_succeed(issue_ix.clone(), &[&warden.keypair])
+        .await;
+
+    let certificate = test
+        .eager_account2::<StatsCertificateAccount, _>(warden.pubkey, Some(year as u32))
+        .await;
+    assert_eq!(certificate.summary.warden_id, 0);
+    assert_eq!(certificate.summary.warden, warden.pubkey);
+    assert_eq!(certificate.summary.year, year);
+    assert_eq!(certificate.summary.store, 5);
+    assert_eq!(certificate.summary.active_days, 1);
+    assert_eq!(certificate.digest, certificate.summary.digest());
+
+    // Certificates can be refreshed
+    test.set_pda_account::<BasicWardenStatsAccount, _>(
+        &elusiv_warden_network::id(),
+        Some(warden.pubkey),
+        Some(year as u32),
+        |data| {
+            let mut account = BasicWardenStatsAccount::new(data).unwrap();
+            let mut send = account.get_send();
+            send.activity[11] = 1;
+            send.total = 1;
+            account.set_send(&send);
+        },
+    )
+    .await;
+
+    test.ix_should_succeed(issue_ix, &[&warden.keypair]).await;
+
+    let certificate = test
+        .eager_account2::<StatsCertificateAccount, _>(warden.pubkey, Some(year as u32))
+        .await;
+    assert_eq!(certificate.summary.send, 1);
+    assert_eq!(certificate.summary.active_days, 2);
+    assert_eq!(certificate.digest, certificate.summary.digest());
+}
+
 #[tokio::test]
 async fn test_track_stats() {
     let mut test = start_test_with_setup().await;
@@ -441,3 +720,236 @@ async fn test_track_stats() {
         .await;
     }
 }
+
+#[tokio::test]
+async fn test_stake() {
+    const MINIMUM_STAKE: u64 = 1_000_000;
+
+    let mut test = start_test_with_setup().await;
+    let mut warden = Actor::new(&mut test).await;
+    let other = Actor::new(&mut test).await;
+
+    test.set_pda_account::<WardensAccount, _>(&elusiv_warden_network::id(), None, None, |data| {
+        let mut account = WardensAccount::new(data).unwrap();
+        account.set_minimum_stake(&MINIMUM_STAKE);
+    })
+    .await;
+
+    // The minimum stake is deposited on registration
+    register_warden(&mut test, &mut warden).await;
+
+    let stake_account = WardenStakeAccount::find(Some(0)).0;
+    assert_eq!(
+        test.pda_lamports(&stake_account, WardenStakeAccount::SIZE)
+            .await
+            .0,
+        MINIMUM_STAKE
+    );
+    assert_eq!(
+        test.eager_account::<WardenStakeAccount, _>(Some(0))
+            .await
+            .stake,
+        MINIMUM_STAKE
+    );
+
+    // Anyone can deposit stake
+    test.ix_should_succeed(
+        ElusivWardenNetworkInstruction::deposit_stake_instruction(
+            0,
+            MINIMUM_STAKE,
+            WritableSignerAccount(other.pubkey),
+        ),
+        &[&other.keypair],
+    )
+    .await;
+    assert_eq!(
+        test.pda_lamports(&stake_account, WardenStakeAccount::SIZE)
+            .await
+            .0,
+        MINIMUM_STAKE * 2
+    );
+
+    // Only the Warden can withdraw
+    test.ix_should_fail(
+        ElusivWardenNetworkInstruction::withdraw_stake_instruction(
+            0,
+            MINIMUM_STAKE,
+            WritableSignerAccount(other.pubkey),
+        ),
+        &[&other.keypair],
+    )
+    .await;
+
+    // Stake above the minimum stake can be withdrawn
+    test.ix_should_succeed(
+        ElusivWardenNetworkInstruction::withdraw_stake_instruction(
+            0,
+            MINIMUM_STAKE,
+            WritableSignerAccount(warden.pubkey),
+        ),
+        &[&warden.keypair],
+    )
+    .await;
+    assert_eq!(
+        test.pda_lamports(&stake_account, WardenStakeAccount::SIZE)
+            .await
+            .0,
+        MINIMUM_STAKE
+    );
+
+    // The minimum stake is bonded
+    test.ix_should_fail(
+        ElusivWardenNetworkInstruction::withdraw_stake_instruction(
+            0,
+            1,
+            WritableSignerAccount(warden.pubkey),
+        ),
+        &[&warden.keypair],
+    )
+    .await;
+
+    // Only the program authority can slash or set the minimum stake
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let year = unix_timestamp_to_day_and_year(timestamp).unwrap().1;
+    test.ix_should_succeed_simple(
+        ElusivWardenNetworkInstruction::open_basic_warden_stats_account_instruction(
+            year,
+            UserAccount(warden.pubkey),
+            WritableSignerAccount(test.payer()),
+        ),
+    )
+    .await;
+
+    test.ix_should_fail(
+        ElusivWardenNetworkInstruction::slash_stake_instruction(
+            0,
+            year,
+            MINIMUM_STAKE,
+            SignerAccount(other.pubkey),
+            UserAccount(warden.pubkey),
+        ),
+        &[&other.keypair],
+    )
+    .await;
+
+    test.ix_should_fail(
+        ElusivWardenNetworkInstruction::set_minimum_stake_instruction(
+            0,
+            SignerAccount(other.pubkey),
+        ),
+        &[&other.keypair],
+    )
+    .await;
+    assert_eq!(
+        test.pda_lamports(&stake_account, WardenStakeAccount::SIZE)
+            .await
+            .0,
+        MINIMUM_STAKE
+    );
+}
+
+#[tokio::test]
+async fn test_verification_slots() {
+    let mut test = start_test_with_setup().await;
+
+    let timestamp = test.clock().await.unix_timestamp as u64;
+    let (day, year) = unix_timestamp_to_day_and_year(timestamp).unwrap();
+
+    // No ranked Wardens
+    test.ix_should_fail_simple(
+        ElusivWardenNetworkInstruction::assign_verification_slot_instruction(),
+    )
+    .await;
+
+    for (warden_id, activity) in [(0, 3), (1, 1)] {
+        let mut warden = Actor::new(&mut test).await;
+        register_warden(&mut test, &mut warden).await;
+
+        test.ix_should_succeed_simple(
+            ElusivWardenNetworkInstruction::open_basic_warden_stats_account_instruction(
+                year,
+                UserAccount(warden.pubkey),
+                WritableSignerAccount(test.payer()),
+            ),
+        )
+        .await;
+
+        test.set_pda_account::<BasicWardenStatsAccount, _>(
+            &elusiv_warden_network::id(),
+            Some(warden.pubkey),
+            Some(year as u32),
+            |data| {
+                let mut account = BasicWardenStatsAccount::new(data).unwrap();
+                let mut send = account.get_send();
+                send.activity[day as usize - 1] = activity;
+                send.total = activity;
+                account.set_send(&send);
+            },
+        )
+        .await;
+
+        let update_ranking_ix = ElusivWardenNetworkInstruction::update_warden_ranking_instruction(
+            warden_id,
+            year,
+            UserAccount(warden.pubkey),
+        );
+
+        // Inactive Wardens are not ranked
+        test.ix_should_succeed_simple(update_ranking_ix.clone())
+            .await;
+        let wardens_account = test.eager_account::<WardensAccount, _>(None).await;
+        assert_eq!(wardens_account.ranked_count, warden_id);
+
+        test.set_pda_account::<BasicWardenAccount, _>(
+            &elusiv_warden_network::id(),
+            None,
+            Some(warden_id),
+            |data| {
+                let mut account = BasicWardenAccount::new(data).unwrap();
+                let mut warden = account.get_warden();
+                warden.is_active = true;
+                warden.activation_timestamp = timestamp;
+                warden.supported_features = WARDEN_FEATURE_PROOF_VERIFICATION;
+                account.set_warden(&warden);
+            },
+        )
+        .await;
+
+        test.ix_should_succeed_simple(update_ranking_ix).await;
+        let wardens_account = test.eager_account::<WardensAccount, _>(None).await;
+        assert_eq!(wardens_account.ranked_count, warden_id + 1);
+        assert_eq!(
+            wardens_account.ranked_warden_ids[warden_id as usize],
+            warden_id
+        );
+        assert_eq!(
+            wardens_account.ranking_weights[warden_id as usize],
+            activity
+        );
+    }
+
+    // Verification slots are assigned proportional to the ranking weights
+    for expected_warden_id in [0, 0, 1, 0] {
+        test.ix_should_succeed_simple(
+            ElusivWardenNetworkInstruction::assign_verification_slot_instruction(),
+        )
+        .await;
+
+        let wardens_account = test.eager_account::<WardensAccount, _>(None).await;
+        assert_eq!(
+            wardens_account.assigned_warden_id.option(),
+            Some(expected_warden_id)
+        );
+
+        // The current verification slot has not expired yet
+        test.ix_should_fail_simple(
+            ElusivWardenNetworkInstruction::assign_verification_slot_instruction(),
+        )
+        .await;
+
+        test.warp_slots(VERIFICATION_SLOT_DURATION).await;
+    }
+}
@@ -13,7 +13,7 @@ use elusiv_warden_network::{
 use solana_program::pubkey::Pubkey;
 use std::net::Ipv4Addr;
 
-pub const ELUSIV_PROGRAM_ID: Pubkey = elusiv_proc_macros::program_id!(elusiv);
+pub const ELUSIV_PROGRAM_ID: Pubkey = elusiv_proc_macros::program_id!("elusiv");
 pub const OTHER_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
     1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26,
     27, 28, 29, 30, 31, 32,
@@ -0,0 +1,88 @@
+//! Backfills the encrypted notes of historical deposits (commitments stored without a ciphertext)
+//!
+//! # Usage
+//!
+//! `cargo run --example backfill --features elusiv-client -- <backfills.json> <identifier-keypair.json>`
+//!
+//! The backfill file contains a list of [`CommitmentMetadataBackfill`]s, created by the depositor's wallet (which encrypts the notes).
+//! The client pays the rent of the backfilled accounts, the identifier key (the `identifier` of all notes) proves that the notes are backfilled by their depositor.
+//!
+//! # Note
+//!
+//! - Already backfilled notes are skipped, so the example can be rerun after a partial failure.
+//! - Only commitments of the active MT can be backfilled (the program verifies the commitments against the stored leaves).
+
+mod common;
+
+use common::{child_accounts, lamports_to_sol, ExampleClient, Result};
+use elusiv::instruction::ElusivInstruction;
+use elusiv::state::metadata::{CommitmentMetadataAccount, CommitmentMetadataBackfill};
+use elusiv::state::program_account::{PDAAccount, SizedAccount};
+use elusiv::state::storage::StorageAccount;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Signer};
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let (path, identifier_path) = match (args.next(), args.next()) {
+        (Some(path), Some(identifier_path)) => (path, identifier_path),
+        _ => return Err("Usage: backfill <backfills.json> <identifier-keypair.json>".into()),
+    };
+    let backfills: Vec<CommitmentMetadataBackfill> =
+        serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let identifier = read_keypair_file(&identifier_path)?;
+
+    let client = ExampleClient::from_env()?;
+    let payer = client.client.pubkey();
+
+    let mut pending = Vec::with_capacity(backfills.len());
+    for backfill in backfills {
+        if backfill.identifier != identifier.pubkey() {
+            return Err(format!(
+                "The note of the commitment {} has a different identifier",
+                Pubkey::new_from_array(backfill.commitment)
+            )
+            .into());
+        }
+
+        let backfill_pubkey =
+            CommitmentMetadataAccount::backfill_pubkey(&backfill.commitment, &identifier.pubkey());
+        if client
+            .account_exists(&CommitmentMetadataAccount::find_with_pubkey(backfill_pubkey, None).0)?
+        {
+            println!(
+                "Skipping the backfilled commitment {}",
+                Pubkey::new_from_array(backfill.commitment)
+            );
+            continue;
+        }
+
+        pending.push(backfill);
+    }
+
+    let rent = client.rent(CommitmentMetadataAccount::SIZE)? * pending.len() as u64;
+    println!(
+        "Backfilling {} notes (rent: {} SOL)",
+        pending.len(),
+        lamports_to_sol(rent)
+    );
+    if client.balance(&payer)? < rent {
+        return Err("Insufficient client balance".into());
+    }
+
+    let mut storage_data = client.pda_data::<StorageAccount>(None)?;
+    let storage_accounts = child_accounts::<StorageAccount>(&mut storage_data)?;
+
+    let transactions = ElusivInstruction::backfill_commitment_metadata_transactions(
+        pending,
+        &storage_accounts,
+        payer,
+    )?;
+    for transaction in transactions {
+        let count = transaction.len();
+        let signature = client.send(transaction, &[&client.client, &identifier])?;
+        println!("Backfilled {} notes: {}", count, signature);
+    }
+
+    Ok(())
+}
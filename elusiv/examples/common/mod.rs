@@ -0,0 +1,223 @@
+//! Client shared by the examples
+//!
+//! # Configuration
+//!
+//! - `ELUSIV_RPC_URL`: the RPC endpoint (default: `http://127.0.0.1:8899`)
+//! - `ELUSIV_KEYPAIR`: the keypair of the client (default: `~/.config/solana/id.json`)
+//! - `ELUSIV_WARDEN_KEYPAIRS`: comma-separated keypairs of the candidate Wardens (default: the client's keypair)
+//!
+//! The program-id is selected with the `mainnet`/`devnet` features (testnet otherwise).
+
+#![allow(dead_code)]
+
+use elusiv::instruction::ElusivInstruction;
+use elusiv::processor::{warden_attestation_account, warden_attestation_level};
+use elusiv::state::governor::GovernorAccount;
+use elusiv::state::program_account::{PDAAccount, ProgramAccount};
+use elusiv::state::warden_nonce::WardenNonceAccount;
+use elusiv_types::{ElusivOption, ParentAccount};
+use solana_client::rpc_client::RpcClient;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use std::cell::Cell;
+use std::error::Error;
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+
+pub struct ExampleClient {
+    pub rpc: RpcClient,
+
+    /// The owner of the funds (signs stores)
+    pub client: Keypair,
+
+    /// The Warden relaying the requests (pays and signs all other transactions)
+    pub warden: Keypair,
+
+    last_blockhash: Cell<Hash>,
+}
+
+/// The subset of the [`GovernorAccount`] the examples depend on
+pub struct GovernorConfig {
+    pub fee_version: u32,
+    pub program_fee: elusiv::state::fee::ProgramFee,
+    pub commitment_batching_rate: u32,
+    pub token_fee_spread_bps: u16,
+    pub required_warden_attestation_level: u8,
+    pub require_warden_nonces: bool,
+    pub active_mt_shards: u32,
+}
+
+impl ExampleClient {
+    pub fn from_env() -> Result<Self> {
+        let rpc_url = std::env::var("ELUSIV_RPC_URL").unwrap_or_else(|_| DEFAULT_RPC_URL.into());
+        let keypair_path = std::env::var("ELUSIV_KEYPAIR").unwrap_or_else(|_| {
+            format!(
+                "{}/.config/solana/id.json",
+                std::env::var("HOME").unwrap_or_default()
+            )
+        });
+
+        let rpc = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+        let client = read_keypair_file(&keypair_path)?;
+
+        let candidates = match std::env::var("ELUSIV_WARDEN_KEYPAIRS") {
+            Ok(paths) => paths
+                .split(',')
+                .map(read_keypair_file)
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+            Err(_) => vec![read_keypair_file(&keypair_path)?],
+        };
+
+        let mut example_client = ExampleClient {
+            rpc,
+            client,
+            warden: Keypair::new(),
+            last_blockhash: Cell::new(Hash::default()),
+        };
+
+        let required_level = example_client.governor()?.required_warden_attestation_level;
+        example_client.warden = example_client.select_warden(candidates, required_level)?;
+
+        Ok(example_client)
+    }
+
+    /// Selects the first candidate Warden with at least the `required_level` of attestation
+    ///
+    /// # Note
+    ///
+    /// Attestations are only enforced by `permissioned` deployments, a `required_level` of zero accepts any Warden.
+    fn select_warden(&self, candidates: Vec<Keypair>, required_level: u8) -> Result<Keypair> {
+        for candidate in candidates {
+            if required_level == 0 {
+                return Ok(candidate);
+            }
+
+            let data = self
+                .rpc
+                .get_account_data(&warden_attestation_account(&candidate.pubkey()))
+                .unwrap_or_default();
+            if warden_attestation_level(&data) >= required_level {
+                return Ok(candidate);
+            }
+        }
+
+        Err(format!(
+            "No candidate Warden is attested with the required level {}",
+            required_level
+        )
+        .into())
+    }
+
+    pub fn data(&self, pubkey: &Pubkey) -> Result<Vec<u8>> {
+        Ok(self.rpc.get_account_data(pubkey)?)
+    }
+
+    pub fn account_exists(&self, pubkey: &Pubkey) -> Result<bool> {
+        Ok(self
+            .rpc
+            .get_account_with_commitment(pubkey, self.rpc.commitment())?
+            .value
+            .is_some())
+    }
+
+    pub fn pda_data<A: PDAAccount>(&self, offset: Option<u32>) -> Result<Vec<u8>> {
+        self.data(&A::find(offset).0)
+    }
+
+    pub fn governor(&self) -> Result<GovernorConfig> {
+        let slot = self.rpc.get_slot()?;
+        let mut data = self.pda_data::<GovernorAccount>(None)?;
+        let governor = GovernorAccount::new(&mut data)?;
+        let (fee_version, program_fee) = governor.active_fee_version(slot);
+
+        Ok(GovernorConfig {
+            fee_version,
+            program_fee,
+            commitment_batching_rate: governor.get_commitment_batching_rate(),
+            token_fee_spread_bps: governor.get_token_fee_spread_bps(),
+            required_warden_attestation_level: governor.get_required_warden_attestation_level(),
+            require_warden_nonces: governor.get_require_warden_nonces(),
+            active_mt_shards: governor.get_active_mt_shards(),
+        })
+    }
+
+    /// Returns the next nonce of the Warden (if it has opened a [`WardenNonceAccount`])
+    pub fn next_warden_nonce(&self, is_required: bool) -> Result<ElusivOption<u64>> {
+        let pubkey = WardenNonceAccount::find_with_pubkey(self.warden.pubkey(), None).0;
+        if !self.account_exists(&pubkey)? {
+            if is_required {
+                return Err(
+                    "The Warden requires a `WardenNonceAccount` (see `OpenWardenNonceAccount`)"
+                        .into(),
+                );
+            }
+            return Ok(ElusivOption::None);
+        }
+
+        let mut data = self.data(&pubkey)?;
+        let warden_nonce_account = WardenNonceAccount::new(&mut data)?;
+
+        Ok(ElusivOption::Some(
+            warden_nonce_account.get_last_nonce() + 1,
+        ))
+    }
+
+    pub fn balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        Ok(self.rpc.get_balance(pubkey)?)
+    }
+
+    pub fn rent(&self, data_len: usize) -> Result<u64> {
+        Ok(self.rpc.get_minimum_balance_for_rent_exemption(data_len)?)
+    }
+
+    /// Sends and confirms a transaction (prepended with the required compute-budget-instructions)
+    ///
+    /// Oversized transactions are rejected before reaching the RPC (with a size breakdown).
+    ///
+    /// # Note
+    ///
+    /// Repeated computation instructions are identical, so consecutive transactions never share a blockhash.
+    pub fn send(&self, instructions: Vec<Instruction>, signers: &[&Keypair]) -> Result<Signature> {
+        let instructions = ElusivInstruction::with_compute_budget(instructions);
+        ElusivInstruction::check_transaction_size(&instructions, &signers[0].pubkey())?;
+
+        let mut blockhash = self.rpc.get_latest_blockhash()?;
+        if blockhash == self.last_blockhash.get() {
+            blockhash = self.rpc.get_new_latest_blockhash(&blockhash)?;
+        }
+        self.last_blockhash.set(blockhash);
+
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&signers[0].pubkey()),
+            signers,
+            blockhash,
+        );
+
+        Ok(self.rpc.send_and_confirm_transaction(&transaction)?)
+    }
+
+    /// Sends a transaction signed (and paid) by the Warden
+    pub fn send_as_warden(&self, instructions: Vec<Instruction>) -> Result<Signature> {
+        self.send(instructions, &[&self.warden])
+    }
+}
+
+/// Returns the enabled child-accounts of the parent-account `P` with the account `data`
+pub fn child_accounts<'a, P: ParentAccount<'a, 'a, 'a>>(data: &'a mut [u8]) -> Result<Vec<Pubkey>> {
+    let parent = P::new(data)?;
+
+    Ok((0..P::COUNT)
+        .filter_map(|i| parent.get_child_pubkey(i))
+        .collect())
+}
+
+pub fn lamports_to_sol(lamports: u64) -> f64 {
+    lamports as f64 / solana_program::native_token::LAMPORTS_PER_SOL as f64
+}
@@ -0,0 +1,250 @@
+//! Verifies a send-proof (in Lamports) and finalizes the send to its recipient
+//!
+//! # Usage
+//!
+//! `cargo run --example send --features elusiv-client -- <send.json>`
+//!
+//! The send file contains the `proof` ([`RawProof`]) and the `public_inputs` ([`SendPublicInputs`]) created by the client's prover,
+//! together with the `tree_indices`, the `recipient`, the `identifier`, the `iv` and the `encrypted_owner` of the send (and an optional `reference` and `memo`).
+//! A note stored with the `store` example can be spent once the prover has built the send on its commitment.
+//!
+//! The selected Warden (see `common`) pays the verification fee upfront, computes the verification and finalizes the send.
+
+mod common;
+
+use common::{child_accounts, lamports_to_sol, ExampleClient, Result};
+use elusiv::instruction::{
+    ElusivInstruction, SignerAccount, UserAccount, WritableSignerAccount, WritableUserAccount,
+};
+use elusiv::processor::{
+    apa_flag_account, minimum_commitment_mt_index, warden_attestation_account, FinalizeSendData,
+    ProofRequest, RecipientBinding, COMPUTE_VERIFICATION_IX_COUNT, MAX_MT_COUNT,
+};
+use elusiv::proof::vkey::{SendQuadraVKey, VerifyingKeyInfo};
+use elusiv::state::commitment::{CommitmentQueue, CommitmentQueueAccount};
+use elusiv::state::governor::PROTOCOL_VERSION;
+use elusiv::state::nullifier::NullifierAccount;
+use elusiv::state::pending_release::PendingReleaseAccount;
+use elusiv::state::program_account::{PDAAccount, ProgramAccount};
+use elusiv::state::proof::VerificationAccount;
+use elusiv::state::queue::RingQueue;
+use elusiv::state::recipient::RecipientPreferenceAccount;
+use elusiv::state::storage::{commitment_mt_shard, mt_shard_offset, StorageAccount};
+use elusiv::state::vkey::VKeyAccount;
+use elusiv::state::warden_nonce::WardenNonceAccount;
+use elusiv::state::warden_usage::WardenUsageAccount;
+use elusiv::token::LAMPORTS_TOKEN_ID;
+use elusiv::types::{compute_fee_rec_lamports, Proof, RawProof, SendPublicInputs, U256};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+
+const VERIFICATION_ACCOUNT_INDEX: u8 = 0;
+
+#[derive(serde::Deserialize)]
+struct SendRequest {
+    proof: RawProof,
+    public_inputs: SendPublicInputs,
+    tree_indices: [u32; MAX_MT_COUNT],
+    recipient: U256,
+    identifier: U256,
+    iv: U256,
+    encrypted_owner: U256,
+
+    #[serde(default)]
+    reference: U256,
+
+    #[serde(default)]
+    memo: Option<Vec<u8>>,
+}
+
+fn main() -> Result<()> {
+    let path = std::env::args().nth(1).ok_or("Usage: send <send.json>")?;
+    let request: SendRequest = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+
+    let client = ExampleClient::from_env()?;
+    let governor = client.governor()?;
+    let warden = client.warden.pubkey();
+
+    let public_inputs = request.public_inputs;
+    let join_split = &public_inputs.join_split;
+    let recipient = Pubkey::new_from_array(request.recipient);
+    let identifier = Pubkey::new_from_array(request.identifier);
+    let reference = Pubkey::new_from_array(request.reference);
+
+    if join_split.token_id != LAMPORTS_TOKEN_ID {
+        return Err("The example only sends Lamports".into());
+    }
+    if join_split.fee_version != governor.fee_version {
+        return Err(format!(
+            "The proof has to be created with the fee-version {}",
+            governor.fee_version
+        )
+        .into());
+    }
+
+    // The fee is bound to the proof, so it has to match the fee computed by the program
+    let mut expected_inputs = public_inputs.clone();
+    compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut expected_inputs, &governor.program_fee);
+    if expected_inputs.join_split.fee != join_split.fee {
+        return Err(format!(
+            "The proof has to be created with a fee of {} Lamports",
+            expected_inputs.join_split.fee
+        )
+        .into());
+    }
+    println!(
+        "Sending {} SOL to {} (fee: {} SOL)",
+        lamports_to_sol(join_split.amount),
+        recipient,
+        lamports_to_sol(join_split.fee),
+    );
+
+    let user_accounts = |accounts: &[Pubkey]| -> Vec<UserAccount> {
+        accounts.iter().map(|a| UserAccount(*a)).collect()
+    };
+    let writable_user_accounts = |accounts: &[Pubkey]| -> Vec<WritableUserAccount> {
+        accounts.iter().map(|a| WritableUserAccount(*a)).collect()
+    };
+
+    let mut nullifier_data0 = client.pda_data::<NullifierAccount>(Some(request.tree_indices[0]))?;
+    let nullifier_accounts0 = child_accounts::<NullifierAccount>(&mut nullifier_data0)?;
+    // The second nullifier-account is only required for a second (unique) root
+    let nullifier_accounts1 = if join_split.roots().iter().flatten().count() > 1 {
+        let mut data = client.pda_data::<NullifierAccount>(Some(request.tree_indices[1]))?;
+        child_accounts::<NullifierAccount>(&mut data)?
+    } else {
+        Vec::new()
+    };
+
+    // Init
+    let nullifier_duplicate_account = join_split.nullifier_duplicate_pda().0;
+    let warden_nonce = client.next_warden_nonce(governor.require_warden_nonces)?;
+    let signature = client.send_as_warden(vec![
+        ElusivInstruction::init_verification_instruction(
+            PROTOCOL_VERSION,
+            VERIFICATION_ACCOUNT_INDEX,
+            SendQuadraVKey::VKEY_ID,
+            request.tree_indices,
+            ProofRequest::Send(public_inputs.clone()),
+            false,
+            false,
+            RecipientBinding {
+                iv: request.iv,
+                encrypted_owner: request.encrypted_owner,
+                transaction_reference: request.reference,
+                memo: request.memo.clone(),
+            },
+            warden_nonce,
+            WritableSignerAccount(warden),
+            WritableUserAccount(nullifier_duplicate_account),
+            UserAccount(recipient),
+            UserAccount(identifier),
+            &user_accounts(&nullifier_accounts0),
+            &user_accounts(&nullifier_accounts1),
+            UserAccount(system_program::id()),
+            WritableUserAccount(WardenNonceAccount::find_with_pubkey(warden, None).0),
+        ),
+        ElusivInstruction::init_verification_transfer_fee_sol_instruction(
+            VERIFICATION_ACCOUNT_INDEX,
+            warden,
+        ),
+        ElusivInstruction::init_verification_proof_instruction(
+            VERIFICATION_ACCOUNT_INDEX,
+            Proof::try_from(request.proof)?,
+            SignerAccount(warden),
+        ),
+    ])?;
+    println!("Initialized the verification: {}", signature);
+
+    // Computation
+    let mut vkey_data = client.pda_data::<VKeyAccount>(Some(SendQuadraVKey::VKEY_ID))?;
+    let vkey_accounts = child_accounts::<VKeyAccount>(&mut vkey_data)?;
+    let verification_account =
+        VerificationAccount::find_with_pubkey(warden, Some(VERIFICATION_ACCOUNT_INDEX.into())).0;
+
+    let is_verified = loop {
+        let mut data = client.data(&verification_account)?;
+        if let Some(is_verified) = VerificationAccount::new(&mut data)?
+            .get_is_verified()
+            .option()
+        {
+            break is_verified;
+        }
+
+        let compute_instruction = ElusivInstruction::compute_verification_instruction(
+            VERIFICATION_ACCOUNT_INDEX,
+            SendQuadraVKey::VKEY_ID,
+            0,
+            UserAccount(warden),
+            &user_accounts(&vkey_accounts),
+        );
+        client.send_as_warden(vec![compute_instruction; COMPUTE_VERIFICATION_IX_COUNT - 2])?;
+    };
+    println!(
+        "Computed the verification (the proof is {})",
+        if is_verified { "valid" } else { "invalid" }
+    );
+
+    // Finalization (the output commitment is inserted into the active MT of its MT-shard)
+    let mt_shard = commitment_mt_shard(
+        &join_split.output_commitment.reduce(),
+        governor.active_mt_shards,
+    );
+    let (commitment_index, mt_index) = {
+        let mut storage_data = client.pda_data::<StorageAccount>(mt_shard_offset(mt_shard))?;
+        let storage_account = StorageAccount::new(&mut storage_data)?;
+        let mut queue_data =
+            client.pda_data::<CommitmentQueueAccount>(mt_shard_offset(mt_shard))?;
+        let mut queue_account = CommitmentQueueAccount::new(&mut queue_data)?;
+
+        minimum_commitment_mt_index(
+            storage_account.get_trees_count(),
+            storage_account.get_next_commitment_ptr(),
+            CommitmentQueue::new(&mut queue_account).len(),
+        )
+    };
+
+    let signature = client.send_as_warden(vec![
+        ElusivInstruction::finalize_verification_send_instruction(
+            VERIFICATION_ACCOUNT_INDEX,
+            FinalizeSendData {
+                total_amount: join_split.total_amount(),
+                token_id: LAMPORTS_TOKEN_ID,
+                mt_index,
+                commitment_index,
+                iv: request.iv,
+                encrypted_owner: request.encrypted_owner,
+            },
+            request.memo.is_some(),
+            UserAccount(recipient),
+            UserAccount(identifier),
+            UserAccount(reference),
+            UserAccount(warden),
+        ),
+        ElusivInstruction::finalize_verification_insert_nullifier_instruction(
+            VERIFICATION_ACCOUNT_INDEX,
+            UserAccount(warden),
+            Some(request.tree_indices[0]),
+            &writable_user_accounts(&nullifier_accounts0),
+        ),
+        ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+            mt_shard,
+            VERIFICATION_ACCOUNT_INDEX,
+            WritableSignerAccount(warden),
+            WritableUserAccount(recipient),
+            WritableUserAccount(join_split.optional_fee.collector),
+            WritableUserAccount(nullifier_duplicate_account),
+            WritableUserAccount(RecipientPreferenceAccount::find_with_pubkey(recipient, None).0),
+            WritableUserAccount(
+                PendingReleaseAccount::find_with_pubkey(nullifier_duplicate_account, None).0,
+            ),
+            WritableUserAccount(WardenUsageAccount::find_with_pubkey(warden, None).0),
+            UserAccount(apa_flag_account(&recipient)),
+            UserAccount(warden_attestation_account(&warden)),
+        ),
+    ])?;
+    println!("Finalized the send: {}", signature);
+
+    Ok(())
+}
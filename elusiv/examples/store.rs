@@ -0,0 +1,285 @@
+//! Stores a base-commitment (in Lamports) and cranks its hash computations until the commitment is inserted into the active MT
+//!
+//! # Usage
+//!
+//! `cargo run --example store --features elusiv-client -- <request.json>`
+//!
+//! The request file contains the `request` ([`BaseCommitmentHashRequest`]) and the `metadata` of the note, both created by the client's SDK.
+//! The selected Warden (see `common`, registered in the Elusiv Warden Network) reserves and opens the hashing account and performs all computations, the client pays the fees.
+//!
+//! # Note
+//!
+//! Each step fails on a diverging program state, so the example doubles as a smoke test for release candidates (followed by the `send` example).
+
+mod common;
+
+use common::{child_accounts, lamports_to_sol, ExampleClient, GovernorConfig, Result};
+use elusiv::commitment::{
+    commitment_hash_computation_instructions, commitments_per_batch, BaseCommitmentHashComputation,
+};
+use elusiv::instruction::{
+    ElusivInstruction, SignerAccount, UserAccount, WritableSignerAccount, WritableUserAccount,
+};
+use elusiv::processor::{
+    basic_warden_map_account, verify_recent_commitment_index, BaseCommitmentHashRequest,
+};
+use elusiv::state::commitment::{
+    BaseCommitmentHashingAccount, BaseCommitmentHashingSlotsAccount, CommitmentHashingAccount,
+    CommitmentQueue, CommitmentQueueAccount,
+};
+use elusiv::state::fee::token_fee_spread;
+use elusiv::state::metadata::{CommitmentMetadata, CommitmentMetadataAccount, MetadataAccount};
+use elusiv::state::program_account::{PDAAccount, ProgramAccount, SizedAccount};
+use elusiv::state::queue::{Queue, RingQueue};
+use elusiv::state::storage::{commitment_mt_shard, mt_shard_offset, StorageAccount};
+use elusiv::state::warden_usage::WardenUsageAccount;
+use elusiv::token::{Token, LAMPORTS_TOKEN_ID};
+use elusiv_computation::PartialComputation;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+#[derive(serde::Deserialize)]
+struct StoreRequest {
+    request: BaseCommitmentHashRequest,
+    metadata: CommitmentMetadata,
+}
+
+/// The Lamports required by a store
+struct StoreFees {
+    /// Paid by the client (besides the `amount`)
+    network_fee: u64,
+    computation_fee: u64,
+    subvention: u64,
+
+    /// Fronted by the Warden (and reimbursed once the base-commitment hash is finalized)
+    hashing_account_rent: u64,
+}
+
+impl StoreFees {
+    fn new(client: &ExampleClient, governor: &GovernorConfig, amount: u64) -> Result<Self> {
+        let fee = &governor.program_fee;
+        let computation_fee = fee.base_commitment_hash_computation_fee().0
+            + fee
+                .commitment_hash_computation_fee(governor.commitment_batching_rate)
+                .0;
+        let spread = token_fee_spread(
+            Token::new(LAMPORTS_TOKEN_ID, computation_fee),
+            governor.token_fee_spread_bps,
+        );
+
+        Ok(StoreFees {
+            network_fee: fee.base_commitment_network_fee.calc(amount) + spread.amount(),
+            computation_fee,
+            subvention: fee.base_commitment_subvention.0,
+            hashing_account_rent: client.rent(BaseCommitmentHashingAccount::SIZE)?,
+        })
+    }
+
+    fn client_total(&self, amount: u64) -> u64 {
+        amount + self.network_fee + self.computation_fee - self.subvention
+    }
+
+    fn warden_total(&self) -> u64 {
+        self.computation_fee + self.hashing_account_rent
+    }
+}
+
+fn main() -> Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .ok_or("Usage: store <request.json>")?;
+    let StoreRequest { request, metadata } = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+
+    let client = ExampleClient::from_env()?;
+    let governor = client.governor()?;
+    let warden = client.warden.pubkey();
+
+    if request.token_id != LAMPORTS_TOKEN_ID {
+        return Err("The example only stores Lamports".into());
+    }
+    if request.fee_version != governor.fee_version
+        || request.min_batching_rate != governor.commitment_batching_rate
+    {
+        return Err(format!(
+            "The request has to be created with the fee-version {} and the batching-rate {}",
+            governor.fee_version, governor.commitment_batching_rate
+        )
+        .into());
+    }
+
+    {
+        let mut data = client.pda_data::<StorageAccount>(None)?;
+        let storage_account = StorageAccount::new(&mut data)?;
+        if !verify_recent_commitment_index(request.recent_commitment_index, &storage_account) {
+            return Err("The recent-commitment-index of the request is outdated".into());
+        }
+    }
+
+    // Fees
+    let fees = StoreFees::new(&client, &governor, request.amount)?;
+    println!(
+        "Storing {} SOL (network fee: {} SOL, computation fee: {} SOL, subvention: {} SOL)",
+        lamports_to_sol(request.amount),
+        lamports_to_sol(fees.network_fee),
+        lamports_to_sol(fees.computation_fee),
+        lamports_to_sol(fees.subvention),
+    );
+
+    if client.balance(&client.client.pubkey())? < fees.client_total(request.amount) {
+        return Err("Insufficient client balance".into());
+    }
+    if client.balance(&warden)? < fees.warden_total() {
+        return Err("Insufficient Warden balance".into());
+    }
+
+    // The instance reserved by the program is predicted with the current slot
+    let hash_account_index = {
+        let mut data = client.pda_data::<BaseCommitmentHashingSlotsAccount>(None)?;
+        let mut hashing_slots = BaseCommitmentHashingSlotsAccount::new(&mut data)?;
+        hashing_slots.acquire(&warden, client.rpc.get_slot()?)?
+    };
+
+    let warden_nonce = client.next_warden_nonce(governor.require_warden_nonces)?;
+    let signature = client.send(
+        vec![
+            ElusivInstruction::acquire_hashing_slot_instruction(
+                SignerAccount(warden),
+                UserAccount(basic_warden_map_account(&warden)),
+            ),
+            ElusivInstruction::store_base_commitment_sol_instruction(
+                hash_account_index,
+                request.clone(),
+                metadata,
+                warden_nonce,
+                client.client.pubkey(),
+                warden,
+            ),
+        ],
+        &[&client.warden, &client.client],
+    )?;
+    println!(
+        "Stored in hashing account {}: {}",
+        hash_account_index, signature
+    );
+
+    // Base-commitment hash
+    for _ in 0..BaseCommitmentHashComputation::IX_COUNT {
+        client.send_as_warden(vec![
+            ElusivInstruction::compute_base_commitment_hash_instruction(hash_account_index),
+        ])?;
+    }
+
+    // The commitment is enqueued into (and inserted into the active MT of) its MT-shard
+    let mt_shard = commitment_mt_shard(&request.commitment.reduce(), governor.active_mt_shards);
+    let commitment = Pubkey::new_from_array(request.commitment.reduce());
+    let signature = client.send_as_warden(vec![
+        ElusivInstruction::finalize_base_commitment_hash_instruction(
+            mt_shard,
+            hash_account_index,
+            request.fee_version,
+            vec![],
+            WritableUserAccount(warden),
+            WritableUserAccount(CommitmentMetadataAccount::find_with_pubkey(commitment, None).0),
+            WritableUserAccount(WardenUsageAccount::find_with_pubkey(warden, None).0),
+        ),
+    ])?;
+    println!("Enqueued commitment {}: {}", commitment, signature);
+
+    crank_commitment_hashes(&client, &governor, mt_shard)
+}
+
+/// Hashes batches of enqueued commitments into the active MT of `mt_shard`, until no full batch is left
+fn crank_commitment_hashes(
+    client: &ExampleClient,
+    governor: &GovernorConfig,
+    mt_shard: u32,
+) -> Result<()> {
+    let warden = client.warden.pubkey();
+    let offset = mt_shard_offset(mt_shard);
+
+    let mut storage_data = client.pda_data::<StorageAccount>(offset)?;
+    let storage_accounts = child_accounts::<StorageAccount>(&mut storage_data)?;
+    let mut metadata_data = client.pda_data::<MetadataAccount>(offset)?;
+    let metadata_accounts = child_accounts::<MetadataAccount>(&mut metadata_data)?;
+
+    let user_accounts = |accounts: &[Pubkey]| -> Vec<UserAccount> {
+        accounts.iter().map(|a| UserAccount(*a)).collect()
+    };
+    let writable_user_accounts = |accounts: &[Pubkey]| -> Vec<WritableUserAccount> {
+        accounts.iter().map(|a| WritableUserAccount(*a)).collect()
+    };
+
+    loop {
+        let (is_active, fee_version, batching_rate, instruction, finalization_ix) = {
+            let mut data = client.pda_data::<CommitmentHashingAccount>(offset)?;
+            let hashing_account = CommitmentHashingAccount::new(&mut data)?;
+            (
+                hashing_account.get_is_active(),
+                hashing_account.get_fee_version(),
+                hashing_account.get_batching_rate(),
+                hashing_account.get_instruction(),
+                hashing_account.get_finalization_ix(),
+            )
+        };
+
+        if !is_active {
+            let queue_len = {
+                let mut data = client.pda_data::<CommitmentQueueAccount>(offset)?;
+                let mut queue_account = CommitmentQueueAccount::new(&mut data)?;
+                CommitmentQueue::new(&mut queue_account).len()
+            };
+
+            if (queue_len as usize) < commitments_per_batch(governor.commitment_batching_rate) {
+                println!(
+                    "{} commitments remain enqueued (waiting for a full batch)",
+                    queue_len
+                );
+                return Ok(());
+            }
+
+            client.send_as_warden(vec![
+                ElusivInstruction::init_commitment_hash_setup_instruction(
+                    mt_shard,
+                    false,
+                    &user_accounts(&storage_accounts),
+                ),
+                ElusivInstruction::init_commitment_hash_instruction(
+                    mt_shard,
+                    false,
+                    &writable_user_accounts(&metadata_accounts),
+                ),
+            ])?;
+            continue;
+        }
+
+        // The nonce distinguishes otherwise identical transactions
+        for nonce in
+            instruction as usize..commitment_hash_computation_instructions(batching_rate).len()
+        {
+            client.send_as_warden(vec![
+                ElusivInstruction::compute_commitment_hash_instruction(
+                    mt_shard,
+                    fee_version,
+                    nonce as u32,
+                    WritableSignerAccount(warden),
+                    WritableUserAccount(WardenUsageAccount::find_with_pubkey(warden, None).0),
+                ),
+            ])?;
+        }
+
+        for _ in finalization_ix..=batching_rate {
+            client.send_as_warden(vec![
+                ElusivInstruction::finalize_commitment_hash_instruction(
+                    mt_shard,
+                    fee_version,
+                    &writable_user_accounts(&storage_accounts),
+                ),
+            ])?;
+        }
+
+        println!(
+            "Inserted a batch of {} commitments",
+            commitments_per_batch(batching_rate)
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Dumps canonical test vectors as JSON
+//!
+//! SDKs in other languages use these vectors to validate their implementations against the program.
+//!
+//! # Usage
+//!
+//! `cargo run --bin test-vectors --features test-vectors > test-vectors.json`
+//!
+//! # Note
+//!
+//! - field elements are little-endian hex strings of their canonical representation (`_mr` values are in mr-form)
+//! - the PDAs are derived for the program-ids of all clusters in `Id.toml`
+
+use ark_bn254::Fr;
+use ark_ff::Zero;
+use elusiv::commitment::poseidon_hash::full_poseidon2_hash;
+use elusiv::commitment::MAX_COMMITMENT_BATCHING_RATE;
+use elusiv::fields::{fr_to_u256_le, fr_to_u256_le_repr, u256_to_fr_skip_mr, u64_to_scalar};
+use elusiv::state::commitment::{
+    BaseCommitmentBufferAccount, CommitmentBufferAccount, CommitmentHashingAccount,
+    CommitmentQueueAccount,
+};
+use elusiv::state::fee::{FeeAccount, ProgramFee};
+use elusiv::state::governor::{
+    FeatureFlagsAccount, FeeCollectorAccount, GovernorAccount, PoolAccount, QuarantineAccount,
+    TokenRegistryAccount, UpgradeGateAccount,
+};
+use elusiv::state::metadata::{MetadataAccount, MetadataQueueAccount};
+use elusiv::state::metrics::MetricsAccount;
+use elusiv::state::nullifier::NullifierAccount;
+use elusiv::state::program_account::{PDAAccount, PDAOffset};
+use elusiv::state::proof::VerifierRAMAccount;
+use elusiv::state::storage::{StorageAccount, EMPTY_TREE, MT_HEIGHT};
+use serde_json::{json, Value};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+const ID_TOML: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../Id.toml"));
+const CLUSTERS: [&str; 3] = ["mainnet", "devnet", "testnet"];
+
+fn main() {
+    let vectors = json!({
+        "poseidon": poseidon_vectors(),
+        "empty_tree": empty_tree_vectors(),
+        "fees": fee_vectors(),
+        "pdas": pda_vectors(),
+    });
+
+    println!("{}", serde_json::to_string_pretty(&vectors).unwrap());
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn fr_hex(fr: &Fr) -> String {
+    hex(&fr_to_u256_le_repr(fr))
+}
+
+fn poseidon_vectors() -> Vec<Value> {
+    let zero_hash = full_poseidon2_hash(Fr::zero(), Fr::zero());
+    let inputs = [
+        (Fr::zero(), Fr::zero()),
+        (u64_to_scalar(1), u64_to_scalar(2)),
+        (u64_to_scalar(u64::MAX), u64_to_scalar(LAMPORTS_PER_SOL)),
+        (zero_hash, Fr::zero()),
+        (zero_hash, zero_hash),
+    ];
+
+    inputs
+        .iter()
+        .map(|(a, b)| {
+            let hash = full_poseidon2_hash(*a, *b);
+            json!({
+                "inputs": [fr_hex(a), fr_hex(b)],
+                "output": fr_hex(&hash),
+                "output_mr": hex(&fr_to_u256_le(&hash)),
+            })
+        })
+        .collect()
+}
+
+fn empty_tree_vectors() -> Value {
+    let levels: Vec<Value> = EMPTY_TREE
+        .iter()
+        .enumerate()
+        .map(|(level, value)| {
+            json!({
+                "level": level,
+                "value": fr_hex(&u256_to_fr_skip_mr(value)),
+                "value_mr": hex(value),
+            })
+        })
+        .collect();
+
+    json!({
+        "height": MT_HEIGHT,
+        "root": fr_hex(&u256_to_fr_skip_mr(&EMPTY_TREE[MT_HEIGHT as usize])),
+        "levels": levels,
+    })
+}
+
+fn fee_vectors() -> Value {
+    let program_fee = ProgramFee::new(5000, 11, 100, 33, 44, 300, 555).unwrap();
+    let amounts = [0, 1_000, 999_999, LAMPORTS_PER_SOL];
+
+    let commitment_hash_fees: Vec<Value> = (0..=MAX_COMMITMENT_BATCHING_RATE as u32)
+        .map(|min_batching_rate| {
+            json!({
+                "min_batching_rate": min_batching_rate,
+                "fee": program_fee.commitment_hash_computation_fee(min_batching_rate).0,
+            })
+        })
+        .collect();
+
+    let proof_verification_fees: Vec<Value> = (0..=4)
+        .map(|input_preparation_tx_count| {
+            json!({
+                "input_preparation_tx_count": input_preparation_tx_count,
+                "fee": program_fee.proof_verification_computation_fee(input_preparation_tx_count).0,
+            })
+        })
+        .collect();
+
+    let network_fees: Vec<Value> = amounts
+        .iter()
+        .map(|amount| {
+            json!({
+                "amount": amount,
+                "base_commitment_network_fee": program_fee.base_commitment_network_fee.calc(*amount),
+                "proof_network_fee": program_fee.proof_network_fee.calc(*amount),
+            })
+        })
+        .collect();
+
+    json!({
+        "program_fee": {
+            "lamports_per_tx": program_fee.lamports_per_tx.0,
+            "base_commitment_network_fee": program_fee.base_commitment_network_fee.0,
+            "proof_network_fee": program_fee.proof_network_fee.0,
+            "base_commitment_subvention": program_fee.base_commitment_subvention.0,
+            "proof_subvention": program_fee.proof_subvention.0,
+            "warden_hash_tx_reward": program_fee.warden_hash_tx_reward.0,
+            "warden_proof_reward": program_fee.warden_proof_reward.0,
+            "proof_base_tx_count": program_fee.proof_base_tx_count,
+        },
+        "hash_tx_compensation": program_fee.hash_tx_compensation().0,
+        "base_commitment_hash_computation_fee": program_fee.base_commitment_hash_computation_fee().0,
+        "commitment_hash_computation_fees": commitment_hash_fees,
+        "proof_verification_computation_fees": proof_verification_fees,
+        "network_fees": network_fees,
+    })
+}
+
+fn pda<T: PDAAccount>(program_id: &Pubkey, offset: PDAOffset) -> Value {
+    let seeds = T::seeds(T::SEED, None, offset);
+    let seeds: Vec<&[u8]> = seeds.iter().map(|x| &x[..]).collect();
+    let (pubkey, bump) = Pubkey::find_program_address(&seeds, program_id);
+
+    json!({
+        "account": T::IDENT,
+        "offset": offset,
+        "pubkey": pubkey.to_string(),
+        "bump": bump,
+    })
+}
+
+fn program_id(cluster: &str) -> Pubkey {
+    let ids: toml::Value = toml::from_str(ID_TOML).unwrap();
+    let id = ids["program_id"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|id| id["name"].as_str() == Some("elusiv"))
+        .unwrap()[cluster]
+        .as_str()
+        .unwrap();
+
+    Pubkey::from_str(id).unwrap()
+}
+
+fn pda_vectors() -> Vec<Value> {
+    CLUSTERS
+        .iter()
+        .map(|cluster| {
+            let program_id = program_id(cluster);
+
+            json!({
+                "cluster": cluster,
+                "program_id": program_id.to_string(),
+                "accounts": [
+                    pda::<GovernorAccount>(&program_id, None),
+                    pda::<PoolAccount>(&program_id, None),
+                    pda::<FeeCollectorAccount>(&program_id, None),
+                    pda::<QuarantineAccount>(&program_id, None),
+                    pda::<UpgradeGateAccount>(&program_id, None),
+                    pda::<FeatureFlagsAccount>(&program_id, None),
+                    pda::<TokenRegistryAccount>(&program_id, None),
+                    pda::<MetricsAccount>(&program_id, None),
+                    pda::<FeeAccount>(&program_id, Some(0)),
+                    pda::<StorageAccount>(&program_id, None),
+                    pda::<MetadataAccount>(&program_id, None),
+                    pda::<CommitmentHashingAccount>(&program_id, None),
+                    pda::<CommitmentQueueAccount>(&program_id, None),
+                    pda::<MetadataQueueAccount>(&program_id, None),
+                    pda::<BaseCommitmentBufferAccount>(&program_id, None),
+                    pda::<CommitmentBufferAccount>(&program_id, None),
+                    pda::<VerifierRAMAccount>(&program_id, Some(0)),
+                    pda::<NullifierAccount>(&program_id, Some(0)),
+                    pda::<NullifierAccount>(&program_id, Some(1)),
+                ],
+            })
+        })
+        .collect()
+}
@@ -1,110 +1,6 @@
-use borsh::BorshSerialize;
-pub use elusiv_types::bytes::*;
-
-macro_rules! div_ceiling {
-    ($id: ident, $ty: ty) => {
-        #[doc = " Rounds a integer division up"]
-        #[doc = ""]
-        #[doc = " # Panics"]
-        #[doc = ""]
-        #[doc = " Panics for a zero-divisor"]
-        pub const fn $id(divident: $ty, divisor: $ty) -> $ty {
-            if divisor == 0 {
-                panic!()
-            }
-            (divident + divisor - 1) / divisor
-        }
-    };
-}
-
-div_ceiling!(div_ceiling_u32, u32);
-div_ceiling!(div_ceiling_u64, u64);
-div_ceiling!(div_ceiling_usize, usize);
-
-macro_rules! safe_num_downcast {
-    ($id: ident, $h: ty, $l: ty) => {
-        pub const fn $id(u: $h) -> $l {
-            if u > <$l>::MAX as $h {
-                panic!()
-            }
-            u as $l
-        }
-    };
-}
-
-safe_num_downcast!(u64_as_u32_safe, u64, u32);
-safe_num_downcast!(usize_as_u32_safe, usize, u32);
-safe_num_downcast!(usize_as_u16_safe, usize, u16);
-safe_num_downcast!(usize_as_u8_safe, usize, u8);
-
-pub const fn u64_as_usize_safe(u: u64) -> usize {
-    u64_as_u32_safe(u) as usize
-}
-
-pub fn contains<N: BorshSerialize + BorshSerDeSized>(v: &N, data: &[u8]) -> bool {
-    let length = data.len() / N::SIZE;
-    find(v, data, length).is_some()
-}
-
-pub fn find<N: BorshSerialize + BorshSerDeSized>(
-    v: &N,
-    data: &[u8],
-    length: usize,
-) -> Option<usize> {
-    let bytes = match N::try_to_vec(v) {
-        Ok(v) => v,
-        Err(_) => return None,
-    };
-
-    assert!(data.len() >= length);
+//! Re-exports the `no_std` compatible bytes core of [`elusiv_types::bytes`]
 
-    // TODO: optimize with byte alignment
-
-    let last_index = N::SIZE - 1;
-    let mut offset = 0;
-    for i in 0..length {
-        if data[offset] == bytes[0] {
-            for j in 1..N::SIZE {
-                if data[offset + j] != bytes[j] {
-                    break;
-                }
-
-                if j == last_index {
-                    return Some(i);
-                }
-            }
-        }
-
-        offset += N::SIZE;
-    }
-
-    None
-}
-
-pub fn is_zero(s: &[u8]) -> bool {
-    for i in (0..s.len()).step_by(16) {
-        if s.len() - i >= 16 {
-            let arr: [u8; 16] = s[i..i + 16].try_into().unwrap();
-            if u128::from_be_bytes(arr) != 0 {
-                return false;
-            }
-        } else {
-            for &bit in s.iter().skip(i) {
-                if bit != 0 {
-                    return false;
-                }
-            }
-        }
-    }
-    true
-}
-
-pub fn slice_to_array<N: Default + Copy, const SIZE: usize>(s: &[N]) -> [N; SIZE] {
-    assert!(s.len() >= SIZE);
-    let mut a = [N::default(); SIZE];
-    a[..SIZE].copy_from_slice(&s[..SIZE]);
-    a
-}
+pub use elusiv_types::bytes::*;
 
 #[cfg(test)]
 mod tests {
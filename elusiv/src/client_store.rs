@@ -0,0 +1,314 @@
+//! Reference note-store layer for clients (wallets, indexers)
+//!
+//! # Note
+//!
+//! - [`NoteRecord`] is the canonical representation of a note, [`NOTE_STORE_SQL_SCHEMA`] its relational schema.
+//! - [`sync_notes`] incrementally imports the commitments inserted since the store's [`SyncCursor`].
+//! - Decryption of the [`CommitmentMetadata`] is left to the client (it depends on the wallet's keys).
+
+use crate::event::ElusivEvent;
+use crate::state::metadata::CommitmentMetadata;
+use crate::state::storage::MT_COMMITMENT_COUNT;
+use crate::types::U256;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// SQL schema of the note store (SQLite dialect)
+pub const NOTE_STORE_SQL_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS notes (
+    mt_index INTEGER NOT NULL,
+    leaf_index INTEGER NOT NULL,
+    commitment BLOB NOT NULL UNIQUE,
+    amount INTEGER NOT NULL,
+    token_id INTEGER NOT NULL,
+    is_spent INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (mt_index, leaf_index)
+);
+
+CREATE TABLE IF NOT EXISTS sync_cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    mt_index INTEGER NOT NULL,
+    commitment_count INTEGER NOT NULL
+);
+";
+
+/// A note owned by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteRecord {
+    pub commitment: U256,
+
+    /// The index of the MT containing the commitment
+    pub mt_index: u32,
+
+    /// The index of the commitment in its MT
+    pub leaf_index: u32,
+
+    pub amount: u64,
+    pub token_id: u16,
+    pub is_spent: bool,
+}
+
+impl NoteRecord {
+    /// The position of the commitment across all MTs
+    pub fn global_index(&self) -> u64 {
+        self.mt_index as u64 * MT_COMMITMENT_COUNT as u64 + self.leaf_index as u64
+    }
+}
+
+/// The value of a note, as decrypted by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteValue {
+    pub amount: u64,
+    pub token_id: u16,
+}
+
+/// The position up to which a store has been synced (all commitments before are imported)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct SyncCursor {
+    pub mt_index: u32,
+
+    /// The number of already imported commitments of the MT `mt_index`
+    pub commitment_count: u32,
+}
+
+impl SyncCursor {
+    /// Returns the leaf indices of the MT `mt_index` that still need to be imported, given an [`ElusivEvent::MerkleRootUpdated`] event
+    pub fn pending_range(&self, event: &ElusivEvent) -> Option<(u32, Range<u32>)> {
+        match event {
+            ElusivEvent::MerkleRootUpdated {
+                mt_index,
+                commitment_count,
+                ..
+            } => {
+                let start = match (*mt_index).cmp(&self.mt_index) {
+                    std::cmp::Ordering::Less => return None,
+                    std::cmp::Ordering::Equal => self.commitment_count,
+                    std::cmp::Ordering::Greater => 0,
+                };
+
+                if start >= *commitment_count {
+                    return None;
+                }
+
+                Some((*mt_index, start..*commitment_count))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A commitment (with its metadata) that has been inserted into a MT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsertedCommitment {
+    pub mt_index: u32,
+    pub leaf_index: u32,
+    pub commitment: U256,
+    pub metadata: CommitmentMetadata,
+}
+
+/// Persistence layer of the notes of a client
+pub trait NoteStore {
+    type Error;
+
+    fn sync_cursor(&self) -> Result<SyncCursor, Self::Error>;
+    fn set_sync_cursor(&mut self, cursor: SyncCursor) -> Result<(), Self::Error>;
+
+    /// Inserts or replaces the note at the position of `note`
+    fn upsert_note(&mut self, note: NoteRecord) -> Result<(), Self::Error>;
+
+    /// Marks the note at a specific position as spent (returns false if the note does not exist)
+    fn mark_spent(&mut self, mt_index: u32, leaf_index: u32) -> Result<bool, Self::Error>;
+
+    /// Returns all notes ordered by their [`NoteRecord::global_index`]
+    fn notes(&self) -> Result<Vec<NoteRecord>, Self::Error>;
+
+    /// Returns the unspent notes of a token
+    fn unspent_notes(&self, token_id: u16) -> Result<Vec<NoteRecord>, Self::Error> {
+        Ok(self
+            .notes()?
+            .into_iter()
+            .filter(|note| !note.is_spent && note.token_id == token_id)
+            .collect())
+    }
+
+    /// Returns the sum of all unspent notes of a token
+    fn balance(&self, token_id: u16) -> Result<u64, Self::Error> {
+        Ok(self
+            .unspent_notes(token_id)?
+            .iter()
+            .fold(0u64, |acc, note| acc.saturating_add(note.amount)))
+    }
+}
+
+/// In-memory [`NoteStore`]
+#[derive(Debug, Default)]
+pub struct MemoryNoteStore {
+    cursor: SyncCursor,
+    notes: BTreeMap<(u32, u32), NoteRecord>,
+}
+
+impl NoteStore for MemoryNoteStore {
+    type Error = std::convert::Infallible;
+
+    fn sync_cursor(&self) -> Result<SyncCursor, Self::Error> {
+        Ok(self.cursor)
+    }
+
+    fn set_sync_cursor(&mut self, cursor: SyncCursor) -> Result<(), Self::Error> {
+        self.cursor = cursor;
+        Ok(())
+    }
+
+    fn upsert_note(&mut self, note: NoteRecord) -> Result<(), Self::Error> {
+        self.notes.insert((note.mt_index, note.leaf_index), note);
+        Ok(())
+    }
+
+    fn mark_spent(&mut self, mt_index: u32, leaf_index: u32) -> Result<bool, Self::Error> {
+        Ok(match self.notes.get_mut(&(mt_index, leaf_index)) {
+            Some(note) => {
+                note.is_spent = true;
+                true
+            }
+            None => false,
+        })
+    }
+
+    fn notes(&self) -> Result<Vec<NoteRecord>, Self::Error> {
+        Ok(self.notes.values().copied().collect())
+    }
+}
+
+/// Imports the notes of the client from `commitments` (ordered by their position) and advances the [`SyncCursor`]
+///
+/// # Note
+///
+/// - Commitments before the store's cursor are skipped, so overlapping sources can be synced repeatedly.
+/// - `decrypt` returns the [`NoteValue`] of commitments owned by the client (and `None` for all others).
+/// - Returns the number of imported notes.
+pub fn sync_notes<S, I, F>(store: &mut S, commitments: I, mut decrypt: F) -> Result<usize, S::Error>
+where
+    S: NoteStore,
+    I: IntoIterator<Item = InsertedCommitment>,
+    F: FnMut(&InsertedCommitment) -> Option<NoteValue>,
+{
+    let mut cursor = store.sync_cursor()?;
+    let mut imported = 0;
+
+    for commitment in commitments {
+        let position = SyncCursor {
+            mt_index: commitment.mt_index,
+            commitment_count: commitment.leaf_index,
+        };
+        if position < cursor {
+            continue;
+        }
+
+        if let Some(value) = decrypt(&commitment) {
+            store.upsert_note(NoteRecord {
+                commitment: commitment.commitment,
+                mt_index: commitment.mt_index,
+                leaf_index: commitment.leaf_index,
+                amount: value.amount,
+                token_id: value.token_id,
+                is_spent: false,
+            })?;
+            imported += 1;
+        }
+
+        cursor = SyncCursor {
+            mt_index: commitment.mt_index,
+            commitment_count: commitment.leaf_index + 1,
+        };
+    }
+
+    store.set_sync_cursor(cursor)?;
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elusiv_types::BorshSerDeSized;
+
+    fn inserted_commitment(mt_index: u32, leaf_index: u32) -> InsertedCommitment {
+        InsertedCommitment {
+            mt_index,
+            leaf_index,
+            commitment: [leaf_index as u8 + 1; 32],
+            metadata: [mt_index as u8; CommitmentMetadata::SIZE],
+        }
+    }
+
+    #[test]
+    fn test_pending_range() {
+        let cursor = SyncCursor {
+            mt_index: 1,
+            commitment_count: 4,
+        };
+        let event = |mt_index, commitment_count| ElusivEvent::MerkleRootUpdated {
+            root: [0; 32],
+            mt_index,
+            commitment_count,
+        };
+
+        assert_eq!(cursor.pending_range(&event(0, 10)), None);
+        assert_eq!(cursor.pending_range(&event(1, 4)), None);
+        assert_eq!(cursor.pending_range(&event(1, 10)), Some((1, 4..10)));
+        assert_eq!(cursor.pending_range(&event(2, 2)), Some((2, 0..2)));
+        assert_eq!(
+            cursor.pending_range(&ElusivEvent::QueuePressure {
+                queue_len: 0,
+                capacity: 0,
+                watermark: 0
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sync_notes() {
+        let mut store = MemoryNoteStore::default();
+
+        // Owned notes have an even leaf-index
+        let decrypt = |c: &InsertedCommitment| {
+            (c.leaf_index % 2 == 0).then_some(NoteValue {
+                amount: c.leaf_index as u64 + 1,
+                token_id: 0,
+            })
+        };
+
+        let commitments: Vec<_> = (0..4).map(|i| inserted_commitment(0, i)).collect();
+        assert_eq!(sync_notes(&mut store, commitments.clone(), decrypt), Ok(2));
+        assert_eq!(
+            store.sync_cursor().unwrap(),
+            SyncCursor {
+                mt_index: 0,
+                commitment_count: 4
+            }
+        );
+
+        // Already synced commitments are skipped
+        let mut commitments = commitments;
+        commitments.push(inserted_commitment(1, 0));
+        assert_eq!(sync_notes(&mut store, commitments, decrypt), Ok(1));
+
+        let notes = store.notes().unwrap();
+        assert_eq!(
+            notes
+                .iter()
+                .map(|n| (n.mt_index, n.leaf_index))
+                .collect::<Vec<_>>(),
+            vec![(0, 0), (0, 2), (1, 0)]
+        );
+        assert_eq!(notes[1].commitment, [3; 32]);
+        assert_eq!(notes[2].global_index(), MT_COMMITMENT_COUNT as u64);
+        assert_eq!(store.balance(0), Ok(1 + 3 + 1));
+
+        assert_eq!(store.mark_spent(0, 2), Ok(true));
+        assert_eq!(store.mark_spent(0, 1), Ok(false));
+        assert_eq!(store.balance(0), Ok(2));
+        assert_eq!(store.unspent_notes(1), Ok(vec![]));
+    }
+}
@@ -225,6 +225,22 @@ mod tests {
         assert_eq!(hash_count_per_batch(3), 4 + 2 + 1 + n - 3);
     }
 
+    struct QuaternaryHashComputation;
+    elusiv_hash_compute_units!(QuaternaryHashComputation, 10, arity = 4);
+
+    #[test]
+    fn test_quaternary_hash_computation() {
+        // 8 full and 60 partial rounds per hash
+        assert_eq!(QuaternaryHashComputation::TOTAL_ROUNDS, 10 * 68);
+        assert_eq!(
+            QuaternaryHashComputation::INSTRUCTION_ROUNDS
+                .iter()
+                .map(|&rounds| rounds as u32)
+                .sum::<u32>(),
+            QuaternaryHashComputation::TOTAL_ROUNDS
+        );
+    }
+
     #[test]
     fn test_base_commitment_hash_computation() {
         zero_program_account!(mut account, BaseCommitmentHashingAccount);
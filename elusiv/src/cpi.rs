@@ -0,0 +1,570 @@
+//! CPI interface for on-chain programs storing (depositing) into or sending from Elusiv
+//!
+//! # Note
+//!
+//! - The `*Accounts` structs list the accounts in the order of the corresponding [`ElusivInstruction`] (the Elusiv PDAs are verified by the program).
+//! - The `*_account_metas` helpers return the account metas of an instruction, the wrappers invoke the instruction with `invoke_signed`.
+//! - Child accounts of parent accounts (e.g. nullifier accounts) are supplied by the caller.
+//! - The current [`PROTOCOL_VERSION`] is used for all requests.
+
+use crate::instruction::ElusivInstruction;
+use crate::processor::{BaseCommitmentHashRequest, ProofRequest, RecipientBinding, MAX_MT_COUNT};
+use crate::state::governor::PROTOCOL_VERSION;
+use crate::state::metadata::CommitmentMetadata;
+use crate::types::Proof;
+use borsh::BorshSerialize;
+use elusiv_types::ElusivOption;
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program::invoke_signed;
+
+fn account_meta(account: &AccountInfo, is_writable: bool, is_signer: bool) -> AccountMeta {
+    if is_writable {
+        AccountMeta::new(*account.key, is_signer)
+    } else {
+        AccountMeta::new_readonly(*account.key, is_signer)
+    }
+}
+
+fn invoke_elusiv_instruction(
+    instruction: ElusivInstruction,
+    account_metas: Vec<AccountMeta>,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction =
+        Instruction::new_with_bytes(crate::id(), &instruction.try_to_vec()?, account_metas);
+
+    invoke_signed(&instruction, account_infos, signers_seeds)
+}
+
+/// The accounts of [`ElusivInstruction::StoreBaseCommitment`]
+pub struct StoreBaseCommitmentAccounts<'a, 'b> {
+    /// Signer (e.g. a PDA of the calling program)
+    pub sender: &'b AccountInfo<'a>,
+
+    /// `sender` for `token_id = 0`, otherwise the sender's token-account
+    pub sender_account: &'b AccountInfo<'a>,
+
+    pub fee_payer: &'b AccountInfo<'a>,
+    pub fee_payer_account: &'b AccountInfo<'a>,
+    pub pool: &'b AccountInfo<'a>,
+    pub pool_account: &'b AccountInfo<'a>,
+    pub fee_collector: &'b AccountInfo<'a>,
+    pub fee_collector_account: &'b AccountInfo<'a>,
+    pub sol_price_account: &'b AccountInfo<'a>,
+    pub token_price_account: &'b AccountInfo<'a>,
+    pub governor: &'b AccountInfo<'a>,
+    pub token_registry: &'b AccountInfo<'a>,
+    pub storage_account: &'b AccountInfo<'a>,
+    pub hashing_account: &'b AccountInfo<'a>,
+    pub hashing_slots: &'b AccountInfo<'a>,
+    pub buffer: &'b AccountInfo<'a>,
+
+    /// `system_program` for `token_id = 0`, otherwise the token program
+    pub token_program: &'b AccountInfo<'a>,
+
+    pub system_program: &'b AccountInfo<'a>,
+    pub metrics: &'b AccountInfo<'a>,
+    pub warden_attestation_account: &'b AccountInfo<'a>,
+    pub warden_nonce_account: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> StoreBaseCommitmentAccounts<'a, 'b> {
+    fn account_infos(&self) -> Vec<AccountInfo<'a>> {
+        vec![
+            self.sender.clone(),
+            self.sender_account.clone(),
+            self.fee_payer.clone(),
+            self.fee_payer_account.clone(),
+            self.pool.clone(),
+            self.pool_account.clone(),
+            self.fee_collector.clone(),
+            self.fee_collector_account.clone(),
+            self.sol_price_account.clone(),
+            self.token_price_account.clone(),
+            self.governor.clone(),
+            self.token_registry.clone(),
+            self.storage_account.clone(),
+            self.hashing_account.clone(),
+            self.hashing_slots.clone(),
+            self.buffer.clone(),
+            self.token_program.clone(),
+            self.system_program.clone(),
+            self.metrics.clone(),
+            self.warden_attestation_account.clone(),
+            self.warden_nonce_account.clone(),
+        ]
+    }
+}
+
+pub fn store_base_commitment_account_metas(
+    accounts: &StoreBaseCommitmentAccounts,
+) -> Vec<AccountMeta> {
+    vec![
+        account_meta(accounts.sender, false, true),
+        account_meta(accounts.sender_account, true, false),
+        account_meta(accounts.fee_payer, true, true),
+        account_meta(accounts.fee_payer_account, true, false),
+        account_meta(accounts.pool, true, false),
+        account_meta(accounts.pool_account, true, false),
+        account_meta(accounts.fee_collector, true, false),
+        account_meta(accounts.fee_collector_account, true, false),
+        account_meta(accounts.sol_price_account, false, false),
+        account_meta(accounts.token_price_account, false, false),
+        account_meta(accounts.governor, false, false),
+        account_meta(accounts.token_registry, false, false),
+        account_meta(accounts.storage_account, false, false),
+        account_meta(accounts.hashing_account, true, false),
+        account_meta(accounts.hashing_slots, true, false),
+        account_meta(accounts.buffer, true, false),
+        account_meta(accounts.token_program, false, false),
+        account_meta(accounts.system_program, false, false),
+        account_meta(accounts.metrics, true, false),
+        account_meta(accounts.warden_attestation_account, false, false),
+        account_meta(accounts.warden_nonce_account, true, false),
+    ]
+}
+
+/// Stores a base commitment (the deposit of the `sender`) using [`ElusivInstruction::StoreBaseCommitment`]
+pub fn store_base_commitment(
+    accounts: &StoreBaseCommitmentAccounts,
+    hash_account_index: u32,
+    hash_account_bump: u8,
+    request: BaseCommitmentHashRequest,
+    metadata: CommitmentMetadata,
+    warden_nonce: ElusivOption<u64>,
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_elusiv_instruction(
+        ElusivInstruction::StoreBaseCommitment {
+            protocol_version: PROTOCOL_VERSION,
+            hash_account_index,
+            hash_account_bump,
+            request,
+            metadata,
+            warden_nonce,
+        },
+        store_base_commitment_account_metas(accounts),
+        &accounts.account_infos(),
+        signers_seeds,
+    )
+}
+
+/// The accounts of [`ElusivInstruction::InitVerification`]
+pub struct InitVerificationAccounts<'a, 'b> {
+    pub fee_payer: &'b AccountInfo<'a>,
+    pub verification_account: &'b AccountInfo<'a>,
+    pub vkey_account: &'b AccountInfo<'a>,
+    pub nullifier_duplicate_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub recipient: &'b AccountInfo<'a>,
+    pub identifier_account: &'b AccountInfo<'a>,
+    pub storage_account: &'b AccountInfo<'a>,
+    pub buffer: &'b AccountInfo<'a>,
+    pub nullifier_account0: &'b AccountInfo<'a>,
+    pub nullifier_child_accounts0: &'b [AccountInfo<'a>],
+    pub nullifier_account1: &'b AccountInfo<'a>,
+    pub nullifier_child_accounts1: &'b [AccountInfo<'a>],
+    pub governor: &'b AccountInfo<'a>,
+    pub commitment_hash_queue: &'b AccountInfo<'a>,
+    pub warden_attestation_account: &'b AccountInfo<'a>,
+    pub warden_nonce_account: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> InitVerificationAccounts<'a, 'b> {
+    fn account_infos(&self) -> Vec<AccountInfo<'a>> {
+        let mut account_infos = vec![
+            self.fee_payer.clone(),
+            self.verification_account.clone(),
+            self.vkey_account.clone(),
+            self.nullifier_duplicate_account.clone(),
+            self.system_program.clone(),
+            self.recipient.clone(),
+            self.identifier_account.clone(),
+            self.storage_account.clone(),
+            self.buffer.clone(),
+            self.nullifier_account0.clone(),
+        ];
+        account_infos.extend_from_slice(self.nullifier_child_accounts0);
+        account_infos.push(self.nullifier_account1.clone());
+        account_infos.extend_from_slice(self.nullifier_child_accounts1);
+        account_infos.extend([
+            self.governor.clone(),
+            self.commitment_hash_queue.clone(),
+            self.warden_attestation_account.clone(),
+            self.warden_nonce_account.clone(),
+        ]);
+
+        account_infos
+    }
+}
+
+pub fn init_verification_account_metas(accounts: &InitVerificationAccounts) -> Vec<AccountMeta> {
+    let mut account_metas = vec![
+        account_meta(accounts.fee_payer, true, true),
+        account_meta(accounts.verification_account, true, false),
+        account_meta(accounts.vkey_account, false, false),
+        account_meta(accounts.nullifier_duplicate_account, true, false),
+        account_meta(accounts.system_program, false, false),
+        account_meta(accounts.recipient, false, false),
+        account_meta(accounts.identifier_account, false, false),
+        account_meta(accounts.storage_account, false, false),
+        account_meta(accounts.buffer, true, false),
+        account_meta(accounts.nullifier_account0, false, false),
+    ];
+    account_metas.extend(
+        accounts
+            .nullifier_child_accounts0
+            .iter()
+            .map(|account| account_meta(account, false, false)),
+    );
+    account_metas.push(account_meta(accounts.nullifier_account1, false, false));
+    account_metas.extend(
+        accounts
+            .nullifier_child_accounts1
+            .iter()
+            .map(|account| account_meta(account, false, false)),
+    );
+    account_metas.extend([
+        account_meta(accounts.governor, false, false),
+        account_meta(accounts.commitment_hash_queue, false, false),
+        account_meta(accounts.warden_attestation_account, false, false),
+        account_meta(accounts.warden_nonce_account, true, false),
+    ]);
+
+    account_metas
+}
+
+/// Initializes a proof verification (the first step of a send) using [`ElusivInstruction::InitVerification`]
+#[allow(clippy::too_many_arguments)]
+pub fn init_verification(
+    accounts: &InitVerificationAccounts,
+    verification_account_index: u8,
+    vkey_id: u32,
+    tree_indices: [u32; MAX_MT_COUNT],
+    request: ProofRequest,
+    skip_nullifier_pda: bool,
+    reuse_verification_account: bool,
+    recipient_binding: RecipientBinding,
+    warden_nonce: ElusivOption<u64>,
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_elusiv_instruction(
+        ElusivInstruction::InitVerification {
+            protocol_version: PROTOCOL_VERSION,
+            verification_account_index,
+            vkey_id,
+            tree_indices,
+            request,
+            skip_nullifier_pda,
+            reuse_verification_account,
+            recipient_binding,
+            warden_nonce,
+        },
+        init_verification_account_metas(accounts),
+        &accounts.account_infos(),
+        signers_seeds,
+    )
+}
+
+/// The accounts of [`ElusivInstruction::InitVerificationTransferFee`]
+pub struct InitVerificationTransferFeeAccounts<'a, 'b> {
+    pub fee_payer: &'b AccountInfo<'a>,
+    pub fee_payer_account: &'b AccountInfo<'a>,
+    pub pool: &'b AccountInfo<'a>,
+    pub pool_account: &'b AccountInfo<'a>,
+    pub fee_collector: &'b AccountInfo<'a>,
+    pub fee_collector_account: &'b AccountInfo<'a>,
+    pub sol_price_account: &'b AccountInfo<'a>,
+    pub token_price_account: &'b AccountInfo<'a>,
+    pub governor: &'b AccountInfo<'a>,
+    pub token_registry: &'b AccountInfo<'a>,
+    pub verification_account: &'b AccountInfo<'a>,
+
+    /// `system_program` for `token_id = 0`, otherwise the token program
+    pub token_program: &'b AccountInfo<'a>,
+
+    pub system_program: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> InitVerificationTransferFeeAccounts<'a, 'b> {
+    fn account_infos(&self) -> Vec<AccountInfo<'a>> {
+        vec![
+            self.fee_payer.clone(),
+            self.fee_payer_account.clone(),
+            self.pool.clone(),
+            self.pool_account.clone(),
+            self.fee_collector.clone(),
+            self.fee_collector_account.clone(),
+            self.sol_price_account.clone(),
+            self.token_price_account.clone(),
+            self.governor.clone(),
+            self.token_registry.clone(),
+            self.verification_account.clone(),
+            self.token_program.clone(),
+            self.system_program.clone(),
+        ]
+    }
+}
+
+pub fn init_verification_transfer_fee_account_metas(
+    accounts: &InitVerificationTransferFeeAccounts,
+) -> Vec<AccountMeta> {
+    vec![
+        account_meta(accounts.fee_payer, true, true),
+        account_meta(accounts.fee_payer_account, true, false),
+        account_meta(accounts.pool, true, false),
+        account_meta(accounts.pool_account, true, false),
+        account_meta(accounts.fee_collector, true, false),
+        account_meta(accounts.fee_collector_account, true, false),
+        account_meta(accounts.sol_price_account, false, false),
+        account_meta(accounts.token_price_account, false, false),
+        account_meta(accounts.governor, false, false),
+        account_meta(accounts.token_registry, false, false),
+        account_meta(accounts.verification_account, true, false),
+        account_meta(accounts.token_program, false, false),
+        account_meta(accounts.system_program, false, false),
+    ]
+}
+
+/// Transfers the verification fee of a send using [`ElusivInstruction::InitVerificationTransferFee`]
+pub fn init_verification_transfer_fee(
+    accounts: &InitVerificationTransferFeeAccounts,
+    verification_account_index: u8,
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_elusiv_instruction(
+        ElusivInstruction::InitVerificationTransferFee {
+            verification_account_index,
+        },
+        init_verification_transfer_fee_account_metas(accounts),
+        &accounts.account_infos(),
+        signers_seeds,
+    )
+}
+
+/// The accounts of [`ElusivInstruction::InitVerificationProof`]
+pub struct InitVerificationProofAccounts<'a, 'b> {
+    pub fee_payer: &'b AccountInfo<'a>,
+    pub verification_account: &'b AccountInfo<'a>,
+}
+
+pub fn init_verification_proof_account_metas(
+    accounts: &InitVerificationProofAccounts,
+) -> Vec<AccountMeta> {
+    vec![
+        account_meta(accounts.fee_payer, false, true),
+        account_meta(accounts.verification_account, true, false),
+    ]
+}
+
+/// Supplies the proof of a send using [`ElusivInstruction::InitVerificationProof`]
+pub fn init_verification_proof(
+    accounts: &InitVerificationProofAccounts,
+    verification_account_index: u8,
+    proof: Proof,
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_elusiv_instruction(
+        ElusivInstruction::InitVerificationProof {
+            verification_account_index,
+            proof,
+        },
+        init_verification_proof_account_metas(accounts),
+        &[
+            accounts.fee_payer.clone(),
+            accounts.verification_account.clone(),
+        ],
+        signers_seeds,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{
+        SignerAccount, UserAccount, WritableSignerAccount, WritableUserAccount,
+    };
+    use crate::state::commitment::{
+        BaseCommitmentBufferAccount, BaseCommitmentHashingAccount,
+        BaseCommitmentHashingSlotsAccount,
+    };
+    use crate::state::governor::{
+        FeeCollectorAccount, GovernorAccount, PoolAccount, TokenRegistryAccount,
+    };
+    use crate::state::metrics::MetricsAccount;
+    use crate::state::proof::VerificationAccount;
+    use crate::state::storage::StorageAccount;
+    use crate::types::RawU256;
+    use elusiv_types::PDAAccount;
+    use solana_program::pubkey::Pubkey;
+    use solana_program::system_program;
+
+    #[test]
+    fn test_store_base_commitment_account_metas() {
+        let hash_account_index = 1;
+        let keys = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            PoolAccount::find(None).0,
+            Pubkey::new_unique(),
+            FeeCollectorAccount::find(None).0,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            GovernorAccount::find(None).0,
+            TokenRegistryAccount::find(None).0,
+            StorageAccount::find(None).0,
+            BaseCommitmentHashingAccount::find(Some(hash_account_index)).0,
+            BaseCommitmentHashingSlotsAccount::find(None).0,
+            BaseCommitmentBufferAccount::find(None).0,
+            system_program::ID,
+            system_program::ID,
+            MetricsAccount::find(None).0,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let mut lamports = [0; 21];
+        let mut data = vec![vec![]; 21];
+        let owner = crate::id();
+        let infos: Vec<AccountInfo> = keys
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+            })
+            .collect();
+
+        let accounts = StoreBaseCommitmentAccounts {
+            sender: &infos[0],
+            sender_account: &infos[1],
+            fee_payer: &infos[2],
+            fee_payer_account: &infos[3],
+            pool: &infos[4],
+            pool_account: &infos[5],
+            fee_collector: &infos[6],
+            fee_collector_account: &infos[7],
+            sol_price_account: &infos[8],
+            token_price_account: &infos[9],
+            governor: &infos[10],
+            token_registry: &infos[11],
+            storage_account: &infos[12],
+            hashing_account: &infos[13],
+            hashing_slots: &infos[14],
+            buffer: &infos[15],
+            token_program: &infos[16],
+            system_program: &infos[17],
+            metrics: &infos[18],
+            warden_attestation_account: &infos[19],
+            warden_nonce_account: &infos[20],
+        };
+
+        let instruction = ElusivInstruction::store_base_commitment_instruction(
+            PROTOCOL_VERSION,
+            hash_account_index,
+            0,
+            BaseCommitmentHashRequest {
+                base_commitment: RawU256::new([0; 32]),
+                recent_commitment_index: 0,
+                amount: 0,
+                token_id: 0,
+                commitment: RawU256::new([0; 32]),
+                fee_version: 0,
+                min_batching_rate: 0,
+            },
+            [0; 17],
+            ElusivOption::None,
+            SignerAccount(keys[0]),
+            WritableUserAccount(keys[1]),
+            WritableSignerAccount(keys[2]),
+            WritableUserAccount(keys[3]),
+            WritableUserAccount(keys[5]),
+            WritableUserAccount(keys[7]),
+            UserAccount(keys[8]),
+            UserAccount(keys[9]),
+            UserAccount(keys[16]),
+            UserAccount(keys[19]),
+            WritableUserAccount(keys[20]),
+        );
+
+        assert_eq!(
+            store_base_commitment_account_metas(&accounts),
+            instruction.accounts
+        );
+        assert_eq!(accounts.account_infos().len(), instruction.accounts.len());
+    }
+
+    #[test]
+    fn test_init_verification_transfer_fee_account_metas() {
+        let verification_account_index = 1;
+        let fee_payer = Pubkey::new_unique();
+        let keys = [
+            fee_payer,
+            Pubkey::new_unique(),
+            PoolAccount::find(None).0,
+            Pubkey::new_unique(),
+            FeeCollectorAccount::find(None).0,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            GovernorAccount::find(None).0,
+            TokenRegistryAccount::find(None).0,
+            VerificationAccount::find_with_pubkey(
+                fee_payer,
+                Some(verification_account_index.into()),
+            )
+            .0,
+            system_program::ID,
+            system_program::ID,
+        ];
+        let mut lamports = [0; 13];
+        let mut data = vec![vec![]; 13];
+        let owner = crate::id();
+        let infos: Vec<AccountInfo> = keys
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+            })
+            .collect();
+
+        let accounts = InitVerificationTransferFeeAccounts {
+            fee_payer: &infos[0],
+            fee_payer_account: &infos[1],
+            pool: &infos[2],
+            pool_account: &infos[3],
+            fee_collector: &infos[4],
+            fee_collector_account: &infos[5],
+            sol_price_account: &infos[6],
+            token_price_account: &infos[7],
+            governor: &infos[8],
+            token_registry: &infos[9],
+            verification_account: &infos[10],
+            token_program: &infos[11],
+            system_program: &infos[12],
+        };
+
+        let instruction = ElusivInstruction::init_verification_transfer_fee_instruction(
+            verification_account_index,
+            WritableSignerAccount(keys[0]),
+            WritableUserAccount(keys[1]),
+            WritableUserAccount(keys[3]),
+            WritableUserAccount(keys[5]),
+            UserAccount(keys[6]),
+            UserAccount(keys[7]),
+            UserAccount(keys[11]),
+        );
+
+        assert_eq!(
+            init_verification_transfer_fee_account_metas(&accounts),
+            instruction.accounts
+        );
+        assert_eq!(accounts.account_infos().len(), instruction.accounts.len());
+    }
+}
@@ -36,7 +36,27 @@ pub fn process_instruction(
 
     match instruction::ElusivInstruction::deserialize(&mut &instruction_data[..]) {
         Ok(instruction) => {
-            instruction::ElusivInstruction::process(program_id, accounts, instruction)
+            #[cfg(feature = "balance-tracking")]
+            crate::processor::ledger::begin_ledger();
+
+            #[cfg(feature = "invariants")]
+            crate::processor::invariants::begin_invariants(accounts);
+
+            let result = instruction::ElusivInstruction::process(program_id, accounts, instruction);
+
+            // Debits have to equal credits for all lamport/token movements of the instruction
+            #[cfg(feature = "balance-tracking")]
+            if result.is_ok() {
+                crate::processor::ledger::finish_ledger().assert_balanced();
+            }
+
+            // The commitment pipeline has to be consistent after each instruction
+            #[cfg(feature = "invariants")]
+            if result.is_ok() {
+                crate::processor::invariants::assert_invariants(program_id, accounts);
+            }
+
+            result
         }
         Err(_) => Err(ProgramError::InvalidInstructionData),
     }
@@ -59,6 +59,73 @@ pub enum ElusivError {
     // Accounts
     ChildAccountAlreadyExists,
     ChildAccouttDoesNotExists,
+
+    // Verification
+    ForfeitureTimeoutNotReached,
+    EarliestExecutionSlotNotReached,
+    LatestExecutionSlotPassed,
+    InFlightCapReached,
+    FeeVersionNotYetActive,
+    UpgradeTimelockNotReached,
+
+    // Token registry
+    TokenNotVerified,
+    InvalidTokenConfig,
+
+    // Backpressure
+    CommitmentQueuePressure,
+
+    // Self-finalization
+    SelfFinalizationTimeoutNotReached,
+
+    // Request expiry
+    RequestExpired,
+
+    // Instruction deprecation
+    DeprecatedInstruction,
+
+    // Permissioned mode
+    WardenNotAttested,
+
+    // Identifier format
+    InvalidIdentifier,
+
+    // Protocol versioning
+    UnsupportedProtocolVersion,
+
+    // Tree age
+    AmountExceedsTreeAgeLimit,
+
+    // Emergency halt
+    ProtocolPaused,
+
+    // Warden nonces
+    InvalidWardenNonce,
+
+    // Timelocked sends
+    TimelockNotReached,
+
+    // Commitment hashing fair-share
+    FairShareExceeded,
+
+    // Base-commitment hashing slots
+    NoFreeHashingSlot,
+    HashingSlotReserved,
+
+    // APA enforcement
+    ApaFlaggedRecipient,
+
+    // Verification cancellation
+    CancellationTimeoutNotReached,
+
+    // Pool yield
+    PoolLiquidityDepleted,
+
+    // In-flight fee-payer-slots
+    InFlightFeePayerSlotsExhausted,
+
+    // MT-shards
+    InvalidMtShard,
 }
 
 #[cfg(not(tarpaulin_include))]
@@ -0,0 +1,163 @@
+use borsh::BorshSerialize;
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+
+#[cfg(feature = "elusiv-client")]
+use borsh::BorshDeserialize;
+
+/// Events emitted by the program (as program-data logs)
+///
+/// # Note
+///
+/// Variants are only appended, since indexers rely on the borsh tag.
+#[derive(BorshSerialize, Debug, PartialEq)]
+#[cfg_attr(feature = "elusiv-client", derive(BorshDeserialize))]
+pub enum ElusivEvent {
+    /// A payout has been moved into the quarantine escrow, since the recipient does not accept it
+    PayoutQuarantined {
+        recipient: Pubkey,
+        token_id: u16,
+        amount: u64,
+    },
+
+    /// A governance approved program upgrade has been deployed and confirmed
+    UpgradeConfirmed {
+        program_data_hash: [u8; 32],
+        deployment_slot: u64,
+        upgrade_count: u32,
+    },
+
+    /// The root of the active MT has changed (after a batch of commitments has been inserted)
+    MerkleRootUpdated {
+        root: [u8; 32],
+        mt_index: u32,
+
+        /// The number of commitments in the MT after the insertion
+        commitment_count: u32,
+    },
+
+    /// The commitment queue has reached the watermark of the governor (new verifications are rejected until it drains)
+    QueuePressure {
+        queue_len: u32,
+        capacity: u32,
+        watermark: u32,
+    },
+
+    /// Dust has been swept from the pool into the fee collector
+    PoolDustSwept {
+        token_id: u16,
+        amount: u64,
+
+        /// The pool's balance after the sweep
+        pool_balance: u64,
+
+        /// The total amount of dust swept for the token
+        total_swept: u64,
+    },
+
+    /// Pool lamports have been delegated to the whitelisted stake pool
+    PoolLamportsDelegated {
+        amount: u64,
+
+        /// The total delegated principal
+        delegated_lamports: u64,
+
+        /// The pool's liquid balance after the delegation
+        pool_balance: u64,
+    },
+
+    /// Lamports have been withdrawn from the whitelisted stake pool back into the pool
+    PoolLamportsUndelegated {
+        amount: u64,
+
+        /// The total delegated principal
+        delegated_lamports: u64,
+
+        /// The pool's liquid balance after the withdrawal
+        pool_balance: u64,
+
+        /// True if the withdrawal has been triggered by a depleted buffer
+        is_refill: bool,
+    },
+
+    /// A viewing key has been published for an `identifier`
+    ViewingKeyPublished {
+        identifier: Pubkey,
+        viewing_key: [u8; 32],
+    },
+
+    /// The (encrypted) memo of a send has been recorded under the viewing key of its `identifier`
+    ViewingKeyMemoRecorded {
+        identifier: Pubkey,
+
+        /// The index of the memo (per `identifier`)
+        memo_index: u32,
+        memo: Vec<u8>,
+    },
+
+    /// A payout to a recipient flagged by the APA has been moved into the quarantine escrow (only releasable by governance)
+    ApaPayoutQuarantined {
+        recipient: Pubkey,
+        token_id: u16,
+        amount: u64,
+    },
+}
+
+impl ElusivEvent {
+    pub fn emit(&self) {
+        if let Ok(data) = self.try_to_vec() {
+            sol_log_data(&[&data]);
+        }
+    }
+
+    /// Parses the (base64-decoded) data of a program-data log
+    #[cfg(feature = "elusiv-client")]
+    pub fn from_log_data(data: &[u8]) -> Option<Self> {
+        Self::try_from_slice(data).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialization() {
+        let recipient = Pubkey::new_unique();
+        let event = ElusivEvent::PayoutQuarantined {
+            recipient,
+            token_id: 1,
+            amount: 2,
+        };
+        let data = event.try_to_vec().unwrap();
+
+        assert_eq!(data[0], 0);
+        assert_eq!(&data[1..33], &recipient.to_bytes());
+        assert_eq!(&data[33..35], &1u16.to_le_bytes());
+        assert_eq!(&data[35..], &2u64.to_le_bytes());
+
+        let event = ElusivEvent::MerkleRootUpdated {
+            root: [1; 32],
+            mt_index: 2,
+            commitment_count: 3,
+        };
+        let data = event.try_to_vec().unwrap();
+
+        assert_eq!(data.len(), 1 + 32 + 4 + 4);
+        assert_eq!(data[0], 2);
+        assert_eq!(&data[1..33], &[1; 32]);
+        assert_eq!(&data[33..37], &2u32.to_le_bytes());
+        assert_eq!(&data[37..], &3u32.to_le_bytes());
+
+        let event = ElusivEvent::QueuePressure {
+            queue_len: 200,
+            capacity: 239,
+            watermark: 200,
+        };
+        let data = event.try_to_vec().unwrap();
+
+        assert_eq!(data.len(), 1 + 4 + 4 + 4);
+        assert_eq!(data[0], 3);
+        assert_eq!(&data[1..5], &200u32.to_le_bytes());
+        assert_eq!(&data[5..9], &239u32.to_le_bytes());
+    }
+}
@@ -1,8 +1,6 @@
-use crate::bytes::slice_to_array;
-use crate::{
-    bytes::BorshSerDeSized,
-    types::{u256_to_le_limbs, U256},
-};
+use crate::bytes::{slice_to_array, u256_to_le_limbs};
+pub use crate::bytes::{u64_limb, u64_to_u256_skip_mr};
+use crate::{bytes::BorshSerDeSized, types::U256};
 use ark_bn254::{Fq, Fq12, Fq2, Fq6, Fr, G1Affine, G1Projective, G2Affine};
 use ark_ff::{BigInteger256, One, PrimeField};
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -61,10 +59,6 @@ fn le_u256(slice: &[u8]) -> BigInteger256 {
     BigInteger256([l0, l1, l2, l3])
 }
 
-pub fn u64_limb(slice: &[u8], offset: usize) -> u64 {
-    u64::from_le_bytes(u64_array!(slice, offset))
-}
-
 /// Deserializes 32 bytes into a base field element
 macro_rules! fq_skip_mr {
     ($v: expr) => {
@@ -369,14 +363,6 @@ pub fn u64_to_u256(v: u64) -> U256 {
     fr_to_u256_le(&u64_to_scalar(v))
 }
 
-/// Converts an [`u64`] into a [`U256`] without performing a montgomery reduction
-pub fn u64_to_u256_skip_mr(v: u64) -> U256 {
-    let mut u = [0; 32];
-    let v = v.to_le_bytes();
-    u[..8].copy_from_slice(&v[..]);
-    u
-}
-
 pub fn fr_to_u256_le(fr: &Fr) -> U256 {
     let s = <Wrap<Fr>>::try_to_vec(&Wrap(*fr)).unwrap();
     slice_to_array::<u8, 32>(&s)
@@ -0,0 +1,145 @@
+//! Canonical `identifier` and `iv` (salt) of a send
+//!
+//! # Note
+//!
+//! - Clients derive both values from the recipient's view key and a payment reference (using distinct domains), so all SDKs generate the same values for the same payment.
+//! - Without the view key the values of different payment references cannot be linked.
+//! - The program only checks the format (see [`verify_identifier_format`]), since the view key is never revealed.
+
+use crate::error::ElusivError;
+use crate::macros::guard;
+use crate::types::U256;
+use solana_program::entrypoint::ProgramResult;
+
+/// Domain of the `identifier` derivation
+pub const IDENTIFIER_DOMAIN: &[u8] = b"elusiv-identifier-v1";
+
+/// Domain of the `iv` derivation
+pub const IV_DOMAIN: &[u8] = b"elusiv-iv-v1";
+
+/// Maximum length of a payment reference (in bytes)
+pub const MAX_PAYMENT_REFERENCE_LEN: usize = 64;
+
+/// Returns true if `value` fits into 253 bits (like `hashed_inputs`)
+fn is_canonical_value(value: &U256) -> bool {
+    value[31] & !0b11111 == 0
+}
+
+/// Verifies the format of an `identifier` and `iv` bound to `recipient`
+///
+/// # Note
+///
+/// - Both values need to be non-zero canonical (253 bit) values.
+/// - The `identifier` can neither equal the `iv` nor the `recipient` (which would link the send to the recipient).
+pub fn verify_identifier_format(identifier: &U256, iv: &U256, recipient: &U256) -> ProgramResult {
+    guard!(
+        *identifier != [0; 32] && *iv != [0; 32],
+        ElusivError::InvalidIdentifier
+    );
+    guard!(
+        is_canonical_value(identifier) && is_canonical_value(iv),
+        ElusivError::InvalidIdentifier
+    );
+    guard!(
+        identifier != iv && identifier != recipient,
+        ElusivError::InvalidIdentifier
+    );
+
+    Ok(())
+}
+
+/// The canonical `identifier` and `iv` of a payment
+#[cfg(feature = "elusiv-client")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanonicalIdentifier {
+    pub identifier: U256,
+    pub iv: U256,
+}
+
+#[cfg(feature = "elusiv-client")]
+impl CanonicalIdentifier {
+    /// Derives the `identifier` and `iv` from the recipient's `view_key` and a `payment_reference`
+    ///
+    /// # Note
+    ///
+    /// Returns `None` if the `payment_reference` exceeds [`MAX_PAYMENT_REFERENCE_LEN`].
+    pub fn derive(view_key: &U256, payment_reference: &[u8]) -> Option<Self> {
+        if payment_reference.len() > MAX_PAYMENT_REFERENCE_LEN {
+            return None;
+        }
+
+        Some(Self {
+            identifier: derive_value(IDENTIFIER_DOMAIN, view_key, payment_reference),
+            iv: derive_value(IV_DOMAIN, view_key, payment_reference),
+        })
+    }
+}
+
+/// `sha256(domain || view_key || len(payment_reference) || payment_reference)`, masked to 253 bits
+#[cfg(feature = "elusiv-client")]
+fn derive_value(domain: &[u8], view_key: &U256, payment_reference: &[u8]) -> U256 {
+    let len = [payment_reference.len() as u8];
+    let mut hash =
+        solana_program::hash::hashv(&[domain, view_key, &len, payment_reference]).to_bytes();
+
+    hash[31] &= 0b11111;
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_identifier_format() {
+        let recipient = [3; 32];
+        let valid = [1; 32];
+
+        assert_eq!(
+            verify_identifier_format(&valid, &[2; 32], &recipient),
+            Ok(())
+        );
+
+        for (identifier, iv) in [
+            ([0; 32], [2; 32]),
+            (valid, [0; 32]),
+            ([255; 32], [2; 32]),
+            (valid, [255; 32]),
+            (valid, valid),
+            (recipient, [2; 32]),
+        ] {
+            assert_eq!(
+                verify_identifier_format(&identifier, &iv, &recipient),
+                Err(ElusivError::InvalidIdentifier.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_canonical_identifier() {
+        let view_key = [7; 32];
+        let a = CanonicalIdentifier::derive(&view_key, b"invoice-1").unwrap();
+        let b = CanonicalIdentifier::derive(&view_key, b"invoice-2").unwrap();
+
+        assert_eq!(
+            a,
+            CanonicalIdentifier::derive(&view_key, b"invoice-1").unwrap()
+        );
+        assert_ne!(a.identifier, b.identifier);
+        assert_ne!(a.iv, b.iv);
+        assert_ne!(
+            a,
+            CanonicalIdentifier::derive(&[8; 32], b"invoice-1").unwrap()
+        );
+
+        assert_eq!(
+            verify_identifier_format(&a.identifier, &a.iv, &[0; 32]),
+            Ok(())
+        );
+
+        assert!(CanonicalIdentifier::derive(&view_key, &[0; MAX_PAYMENT_REFERENCE_LEN]).is_some());
+        assert!(
+            CanonicalIdentifier::derive(&view_key, &[0; MAX_PAYMENT_REFERENCE_LEN + 1]).is_none()
+        );
+    }
+}
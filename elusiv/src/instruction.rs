@@ -3,24 +3,48 @@
 use super::processor;
 use super::processor::BaseCommitmentHashRequest;
 use crate::macros::*;
-use crate::processor::{FinalizeSendData, ProofRequest, VKeyAccountDataPacket, MAX_MT_COUNT};
+use crate::processor::{
+    FinalizeSendData, ProofRequest, RecipientBinding, SubAccountKind, VKeyAccountDataPacket,
+    MAX_CLOSE_BATCH_SIZE, MAX_FINALIZE_BATCH_SIZE, MAX_MT_COUNT, MAX_STORE_BATCH_SIZE,
+};
 use crate::state::{
+    circuit_registry::CircuitRegistryAccount,
     commitment::{
-        BaseCommitmentBufferAccount, BaseCommitmentHashingAccount, CommitmentBufferAccount,
-        CommitmentHashingAccount, CommitmentQueueAccount,
+        BaseCommitmentBufferAccount, BaseCommitmentHashingAccount,
+        BaseCommitmentHashingSlotsAccount, CommitmentBufferAccount, CommitmentHashingAccount,
+        CommitmentQueueAccount,
     },
+    deployment::DeploymentInfoAccount,
     fee::{FeeAccount, ProgramFee},
-    governor::{FeeCollectorAccount, GovernorAccount, PoolAccount},
+    governor::{
+        ApaEnforcement, FeatureFlagsAccount, FeeCollectorAccount, GovernorAccount, PoolAccount,
+        QuarantineAccount, TokenRegistryAccount, UpgradeGateAccount,
+    },
+    history::VerificationHistoryAccount,
     metadata::{CommitmentMetadata, MetadataAccount, MetadataQueueAccount},
+    metrics::MetricsAccount,
     nullifier::NullifierAccount,
-    proof::VerificationAccount,
-    storage::StorageAccount,
+    pending_release::PendingReleaseAccount,
+    proof::{VerificationAccount, VerifierRAMAccount},
+    recipient::{RecipientPreferenceAccount, TOKEN_COUNT},
+    rollover::RolloverStateAccount,
+    storage::{mt_index_shard, mt_shard_offset, StorageAccount, TreeConfigAccount},
+    viewing_key::ViewingKeyAccount,
     vkey::VKeyAccount,
+    warden_nonce::WardenNonceAccount,
+    warden_usage::WardenUsageAccount,
 };
-use crate::types::Proof;
+use crate::types::{Proof, U256};
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_types::{AccountRepr, ElusivOption};
-use solana_program::{pubkey::Pubkey, system_program, sysvar::instructions};
+use solana_program::{pubkey::Pubkey, system_program};
+
+#[cfg(feature = "pool-yield")]
+use crate::state::governor::PoolYieldAccount;
+#[cfg(not(feature = "mainnet"))]
+use crate::state::migration::MigrationManifestAccount;
+#[cfg(feature = "alt-bn128")]
+use crate::state::proof::{BatchVerificationAccount, MAX_BATCH_VERIFICATION_SIZE};
 
 #[cfg(feature = "elusiv-client")]
 pub use elusiv_types::accounts::{
@@ -44,53 +68,137 @@ pub enum ElusivInstruction {
     #[acc(sol_price_account)]
     #[acc(token_price_account)]
     #[pda(governor, GovernorAccount)]
+    #[pda(token_registry, TokenRegistryAccount)]
     #[pda(storage_account, StorageAccount)]
     #[pda(hashing_account, BaseCommitmentHashingAccount, pda_offset = Some(hash_account_index), { writable, skip_pda_verification, account_info })]
+    #[pda(hashing_slots, BaseCommitmentHashingSlotsAccount, { writable })]
     #[pda(buffer, BaseCommitmentBufferAccount, { writable })]
     #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
     #[sys(system_program, key = system_program::ID)]
+    #[pda(metrics, MetricsAccount, { writable })]
+    #[acc(warden_attestation_account)] // only read in permissioned mode
+    #[acc(warden_nonce_account, { writable })] // only read with a `warden_nonce`
     StoreBaseCommitment {
+        protocol_version: u8,
         hash_account_index: u32,
         hash_account_bump: u8,
         request: BaseCommitmentHashRequest,
         metadata: CommitmentMetadata,
+        warden_nonce: ElusivOption<u64>,
+    },
+
+    /// Stores multiple base commitments of the same sender (unused hashing accounts can be any accounts)
+    #[acc(sender, { signer })]
+    #[acc(sender_account, { writable })]
+    #[acc(fee_payer, { writable, signer })]
+    #[acc(fee_payer_account, { writable })]
+    #[pda(pool, PoolAccount, { writable, account_info })]
+    #[acc(pool_account, { writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { writable, account_info })]
+    #[acc(fee_collector_account, { writable })]
+    #[acc(sol_price_account)]
+    #[acc(token_price_account)]
+    #[pda(governor, GovernorAccount)]
+    #[pda(token_registry, TokenRegistryAccount)]
+    #[pda(storage_account, StorageAccount)]
+    #[pda(buffer, BaseCommitmentBufferAccount, { writable })]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    #[sys(system_program, key = system_program::ID)]
+    #[pda(metrics, MetricsAccount, { writable })]
+    #[acc(warden_attestation_account)] // only read in permissioned mode
+    #[acc(warden_nonce_account, { writable })] // only read with a `warden_nonce`
+    #[pda(hashing_slots, BaseCommitmentHashingSlotsAccount, { writable })]
+    #[acc(hashing_account0, { writable })]
+    #[acc(hashing_account1, { writable })]
+    #[acc(hashing_account2, { writable })]
+    #[acc(hashing_account3, { writable })]
+    StoreBaseCommitmentBatch {
+        protocol_version: u8,
+        hash_account_indices: [u32; MAX_STORE_BATCH_SIZE],
+        hash_account_bumps: [u8; MAX_STORE_BATCH_SIZE],
+        requests: Vec<(BaseCommitmentHashRequest, CommitmentMetadata)>,
+        warden_nonce: ElusivOption<u64>,
     },
 
+    /// Reserves a free `BaseCommitmentHashingAccount` instance for the registered warden `fee_payer` (its index is returned as return data)
+    #[acc(fee_payer, { signer })]
+    #[acc(warden_map_account)]
+    #[pda(hashing_slots, BaseCommitmentHashingSlotsAccount, { writable })]
+    AcquireHashingSlot,
+
     #[pda(hashing_account, BaseCommitmentHashingAccount, pda_offset = Some(hash_account_index), { writable })]
     ComputeBaseCommitmentHash { hash_account_index: u32 },
 
-    #[acc(original_fee_payer, { writable })]
+    /// The `original_fee_payer` can attach the sender's encrypted note (`ciphertext`), which is stored in a `CommitmentMetadataAccount`
+    #[acc(original_fee_payer, { writable })] // signer if a `ciphertext` is supplied
     #[pda(pool, PoolAccount, { writable, account_info })]
     #[pda(fee, FeeAccount, pda_offset = Some(fee_version))]
     #[pda(hashing_account, BaseCommitmentHashingAccount, pda_offset = Some(hash_account_index), { writable, account_info })]
-    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
-    #[pda(metadata_queue, MetadataQueueAccount, { writable })]
+    #[pda(hashing_slots, BaseCommitmentHashingSlotsAccount, { writable })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metadata_queue, MetadataQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(governor, GovernorAccount)]
+    #[acc(commitment_metadata_account, { writable })] // only used if a `ciphertext` is supplied
+    #[acc(warden_usage_account, { writable })] // only updated if opened by the `original_fee_payer`
+    #[sys(system_program, key = system_program::ID, { ignore })]
     FinalizeBaseCommitmentHash {
+        mt_shard: u32,
         hash_account_index: u32,
         fee_version: u32,
+        ciphertext: Vec<u8>,
+    },
+
+    /// The depositor (signing with the `identifier` key of the note) attaches the encrypted note of a commitment stored without a `ciphertext`
+    #[acc(payer, { writable, signer })]
+    #[acc(identifier_account, { signer })]
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_index_shard(mt_index)), { include_child_accounts })]
+    #[pda(governor, GovernorAccount)]
+    #[acc(commitment_metadata_account, { writable })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    BackfillCommitmentMetadata {
+        mt_index: u32,
+        commitment_index: u32,
+        commitment: U256,
+        identifier: U256,
+        ciphertext: Vec<u8>,
     },
 
     // -------- Commitment hashing --------
-    /// Hashes commitments in a new MT-root
-    #[pda(commitment_hashing_account, CommitmentHashingAccount, { writable })]
-    #[pda(storage_account, StorageAccount, { include_child_accounts })]
-    InitCommitmentHashSetup { insertion_can_fail: bool },
+    /// Hashes commitments in a new MT-root (each MT-shard has its own hashing pipeline)
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_shard), { include_child_accounts })]
+    InitCommitmentHashSetup {
+        mt_shard: u32,
+        insertion_can_fail: bool,
+    },
 
-    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
-    #[pda(metadata_queue, MetadataQueueAccount, { writable })]
-    #[pda(commitment_hashing_account, CommitmentHashingAccount, { writable })]
-    #[pda(metadata_account, MetadataAccount, { writable, include_child_accounts })]
-    InitCommitmentHash { insertion_can_fail: bool },
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metadata_queue, MetadataQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metadata_account, MetadataAccount, pda_offset = mt_shard_offset(mt_shard), { writable, include_child_accounts })]
+    #[pda(governor, GovernorAccount)]
+    InitCommitmentHash {
+        mt_shard: u32,
+        insertion_can_fail: bool,
+    },
 
     #[acc(fee_payer, { writable, signer })]
     #[pda(fee, FeeAccount, pda_offset = Some(fee_version))]
     #[pda(pool, PoolAccount, { writable, account_info })]
-    #[pda(commitment_hashing_account, CommitmentHashingAccount, { writable })]
-    ComputeCommitmentHash { fee_version: u32, nonce: u32 },
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[acc(warden_usage_account, { writable })] // only updated if opened by the `fee_payer`
+    ComputeCommitmentHash {
+        mt_shard: u32,
+        fee_version: u32,
+        nonce: u32,
+    },
 
-    #[pda(commitment_hashing_account, CommitmentHashingAccount, { writable })]
-    #[pda(storage_account, StorageAccount, { include_child_accounts, writable })]
-    FinalizeCommitmentHash,
+    #[pda(fee, FeeAccount, pda_offset = Some(fee_version))]
+    #[pda(pool, PoolAccount, { writable, account_info })]
+    #[pda(fee_collector, FeeCollectorAccount, { writable, account_info })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_shard), { include_child_accounts, writable })]
+    FinalizeCommitmentHash { mt_shard: u32, fee_version: u32 },
 
     // -------- Proof Verification --------
     /// Proof verification initialization
@@ -99,17 +207,27 @@ pub enum ElusivInstruction {
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id))]
     #[acc(nullifier_duplicate_account, { writable })]
     #[sys(system_program, key = system_program::ID, { ignore })]
+    #[acc(recipient)]
     #[acc(identifier_account)]
     #[pda(storage_account, StorageAccount)]
+    #[pda(storage_account1, StorageAccount, pda_offset = mt_shard_offset(1))]
     #[pda(buffer, CommitmentBufferAccount, { writable })]
     #[pda(nullifier_account0, NullifierAccount, pda_offset = Some(tree_indices[0]), { include_child_accounts })]
     #[pda(nullifier_account1, NullifierAccount, pda_offset = Some(tree_indices[1]), { include_child_accounts })]
+    #[pda(governor, GovernorAccount)]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount)]
+    #[acc(warden_attestation_account)] // only read in permissioned mode
+    #[acc(warden_nonce_account, { writable })] // only read with a `warden_nonce`
     InitVerification {
+        protocol_version: u8,
         verification_account_index: u8,
         vkey_id: u32,
         tree_indices: [u32; MAX_MT_COUNT],
         request: ProofRequest,
         skip_nullifier_pda: bool,
+        reuse_verification_account: bool,
+        recipient_binding: RecipientBinding,
+        warden_nonce: ElusivOption<u64>,
     },
 
     #[acc(fee_payer, { writable, signer })]
@@ -121,6 +239,7 @@ pub enum ElusivInstruction {
     #[acc(sol_price_account)]
     #[acc(token_price_account)]
     #[pda(governor, GovernorAccount)]
+    #[pda(token_registry, TokenRegistryAccount)]
     #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
     #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
     #[sys(system_program, key = system_program::ID)]
@@ -136,11 +255,43 @@ pub enum ElusivInstruction {
     /// Proof verification computation
     #[acc(original_fee_payer, { ignore })]
     #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    #[pda(ram_account, VerifierRAMAccount, pda_offset = Some(ram_account_index), { writable, account_info })]
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { include_child_accounts })]
-    #[sys(instructions_account, key = instructions::ID)]
+    #[sys(instructions_account, sysvar = Instructions)]
     ComputeVerification {
         verification_account_index: u8,
         vkey_id: u32,
+        ram_account_index: u32,
+    },
+
+    /// Hand-off of a partial proof verification computation between wardens
+    #[acc(warden, { signer })]
+    #[acc(original_fee_payer, { ignore })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    #[pda(ram_account, VerifierRAMAccount, pda_offset = Some(ram_account_index), { account_info })]
+    ExportVerificationCheckpoint {
+        verification_account_index: u8,
+        ram_account_index: u32,
+    },
+
+    #[acc(original_fee_payer, { ignore })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    #[pda(ram_account, VerifierRAMAccount, pda_offset = Some(ram_account_index), { writable, account_info })]
+    #[pda(new_ram_account, VerifierRAMAccount, pda_offset = Some(new_ram_account_index), { writable, account_info })]
+    ImportVerificationCheckpoint {
+        verification_account_index: u8,
+        ram_account_index: u32,
+        new_ram_account_index: u32,
+        checkpoint_hash: U256,
+    },
+
+    /// Releases the RAM account of a stale verification (permissionless)
+    #[acc(original_fee_payer, { ignore })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    #[pda(ram_account, VerifierRAMAccount, pda_offset = Some(ram_account_index), { writable, account_info })]
+    ReleaseStaleVerifierRamAccount {
+        verification_account_index: u8,
+        ram_account_index: u32,
     },
 
     /// Finalizing proofs
@@ -148,11 +299,13 @@ pub enum ElusivInstruction {
     #[acc(identifier_account)]
     #[acc(transaction_reference_account)]
     #[acc(original_fee_payer, { ignore })]
-    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_index_shard(data.mt_index)), { writable })]
     #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
-    #[pda(storage_account, StorageAccount)]
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_index_shard(data.mt_index)))]
     #[pda(buffer, CommitmentBufferAccount, { writable })]
-    #[sys(instructions_account, key = instructions::ID)]
+    #[sys(instructions_account, sysvar = Instructions)]
+    #[pda(viewing_key_account, ViewingKeyAccount, pda_pubkey = identifier_account.pubkey(), { writable, account_info, find_pda })]
+    #[pda(governor, GovernorAccount)]
     FinalizeVerificationSend {
         verification_account_index: u8,
         data: FinalizeSendData,
@@ -169,13 +322,26 @@ pub enum ElusivInstruction {
     #[pda(pool, PoolAccount, { account_info, writable })]
     #[pda(fee_collector, FeeCollectorAccount, { account_info, writable })]
     #[acc(optional_fee_collector, { account_info, writable })]
-    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
-    #[pda(metadata_queue, MetadataQueueAccount, { writable })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metadata_queue, MetadataQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
     #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info })]
     #[acc(nullifier_duplicate_account, { writable, owned })]
     #[sys(system_program, key = system_program::ID, { ignore })]
-    #[sys(instructions_account, key = instructions::ID)]
-    FinalizeVerificationTransferLamports { verification_account_index: u8 },
+    #[sys(instructions_account, sysvar = Instructions)]
+    #[acc(recipient_preference_account, { writable })]
+    #[pda(quarantine, QuarantineAccount, { account_info, writable })]
+    #[pda(metrics, MetricsAccount, { writable })]
+    #[pda(verification_history, VerificationHistoryAccount, { writable })]
+    #[pda(governor, GovernorAccount)]
+    #[acc(pending_release_account, { writable })] // only used by timelocked sends
+    #[acc(warden_usage_account, { writable })]
+    // only updated if opened by the `original_fee_payer`
+    #[acc(apa_flag_account)] // only read with an enabled `ApaEnforcement`
+    #[acc(warden_attestation_account)] // of the `original_fee_payer` (region-scoped APA flags)
+    FinalizeVerificationTransferLamports {
+        mt_shard: u32,
+        verification_account_index: u8,
+    },
 
     #[acc(original_fee_payer, { signer, writable })]
     #[acc(original_fee_payer_account, { writable })]
@@ -186,16 +352,182 @@ pub enum ElusivInstruction {
     #[pda(fee_collector, FeeCollectorAccount, { account_info, writable })]
     #[acc(fee_collector_account, { writable })]
     #[acc(optional_fee_collector, { account_info, writable })]
-    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
-    #[pda(metadata_queue, MetadataQueueAccount, { writable })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metadata_queue, MetadataQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
     #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info })]
     #[acc(nullifier_duplicate_account, { writable, owned })]
     #[sys(a_token_program, key = spl_associated_token_account::ID, { ignore })]
     #[sys(token_program, key = spl_token::ID)]
     #[sys(system_program, key = system_program::ID, { ignore })]
     #[acc(mint_account)]
-    #[sys(instructions_account, key = instructions::ID)]
-    FinalizeVerificationTransferToken { verification_account_index: u8 },
+    #[sys(instructions_account, sysvar = Instructions)]
+    #[acc(recipient_preference_account, { writable })] // of the recipient (the owner of a non-associated token account)
+    #[pda(quarantine, QuarantineAccount, { account_info, writable })]
+    #[acc(quarantine_account, { writable })]
+    #[pda(metrics, MetricsAccount, { writable })]
+    #[pda(verification_history, VerificationHistoryAccount, { writable })]
+    #[pda(governor, GovernorAccount)]
+    #[acc(warden_usage_account, { writable })]
+    // only updated if opened by the `original_fee_payer`
+    #[acc(apa_flag_account)] // only read with an enabled `ApaEnforcement`
+    #[acc(warden_attestation_account)] // of the `original_fee_payer` (region-scoped APA flags)
+    FinalizeVerificationTransferToken {
+        mt_shard: u32,
+        verification_account_index: u8,
+    },
+
+    /// Releases the lamports of a timelocked send (can be called by anyone after the unlock)
+    #[acc(recipient, { writable })]
+    #[acc(rent_payer, { writable })]
+    #[pda(pending_release_account, PendingReleaseAccount, pda_pubkey = nullifier_duplicate_account, { writable, account_info })]
+    ReleasePendingTransfer { nullifier_duplicate_account: Pubkey },
+
+    /// Closes a reusable verification account
+    #[acc(fee_payer, { writable, signer })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info })]
+    CloseVerificationAccount { verification_account_index: u8 },
+
+    /// Closes multiple reusable verification accounts (unused entries can be any accounts)
+    #[acc(fee_payer, { writable, signer })]
+    #[acc(verification_account0, { writable })]
+    #[acc(verification_account1, { writable })]
+    #[acc(verification_account2, { writable })]
+    #[acc(verification_account3, { writable })]
+    CloseVerificationAccounts {
+        verification_account_indices: [u8; MAX_CLOSE_BATCH_SIZE],
+        count: u8,
+    },
+
+    /// Takes over a verified (but not finalized) proof after a timeout
+    #[acc(warden, { writable, signer })]
+    #[acc(warden_account, { writable })]
+    #[acc(warden_map_account)]
+    #[acc(original_fee_payer, { writable })]
+    #[acc(original_fee_payer_account, { writable })]
+    #[pda(pool, PoolAccount, { writable, account_info })]
+    #[acc(pool_account, { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info })]
+    #[pda(new_verification_account, VerificationAccount, pda_pubkey = warden.pubkey(), pda_offset = Some(new_verification_account_index.into()), { writable, account_info, find_pda })]
+    #[acc(nullifier_duplicate_account)]
+    #[pda(governor, GovernorAccount)]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    #[sys(system_program, key = system_program::ID)]
+    ForfeitVerification {
+        verification_account_index: u8,
+        new_verification_account_index: u8,
+    },
+
+    /// Finalizes the lamports-transfers of multiple verifications (unused entries can be any accounts)
+    #[acc(original_fee_payer, { signer, writable })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { account_info, writable })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metadata_queue, MetadataQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(quarantine, QuarantineAccount, { account_info, writable })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    #[sys(instructions_account, sysvar = Instructions)]
+    #[pda(metrics, MetricsAccount, { writable })]
+    #[pda(verification_history, VerificationHistoryAccount, { writable })]
+    #[pda(governor, GovernorAccount)]
+    #[acc(warden_usage_account, { writable })] // only updated if opened by the `original_fee_payer`
+    #[acc(recipient0, { writable })]
+    #[acc(optional_fee_collector0, { writable })]
+    #[acc(verification_account0, { writable })]
+    #[acc(nullifier_duplicate_account0, { writable })]
+    #[acc(recipient_preference_account0, { writable })]
+    #[acc(recipient1, { writable })]
+    #[acc(optional_fee_collector1, { writable })]
+    #[acc(verification_account1, { writable })]
+    #[acc(nullifier_duplicate_account1, { writable })]
+    #[acc(recipient_preference_account1, { writable })]
+    #[acc(recipient2, { writable })]
+    #[acc(optional_fee_collector2, { writable })]
+    #[acc(verification_account2, { writable })]
+    #[acc(nullifier_duplicate_account2, { writable })]
+    #[acc(recipient_preference_account2, { writable })]
+    #[acc(recipient3, { writable })]
+    #[acc(optional_fee_collector3, { writable })]
+    #[acc(verification_account3, { writable })]
+    #[acc(nullifier_duplicate_account3, { writable })]
+    #[acc(recipient_preference_account3, { writable })]
+    FinalizeVerificationBatch {
+        mt_shard: u32,
+        verification_account_indices: [u8; MAX_FINALIZE_BATCH_SIZE],
+        count: u8,
+    },
+
+    /// Self-finalization by the user (replaces the transfer instruction after a timeout)
+    #[acc(identifier_account, { signer, writable })]
+    #[acc(original_fee_payer, { writable })]
+    #[acc(recipient, { writable })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[acc(pool_account, { writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { account_info, writable })]
+    #[acc(fee_collector_account, { writable })]
+    #[acc(optional_fee_collector, { writable })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metadata_queue, MetadataQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info })]
+    #[acc(nullifier_duplicate_account, { writable, owned })]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    #[acc(recipient_preference_account, { writable })] // of the recipient (the owner of a non-associated token account)
+    #[pda(quarantine, QuarantineAccount, { account_info, writable })]
+    #[acc(quarantine_account, { writable })]
+    #[pda(metrics, MetricsAccount, { writable })]
+    #[pda(verification_history, VerificationHistoryAccount, { writable })]
+    #[pda(governor, GovernorAccount)]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    #[acc(pending_release_account, { writable })] // only used by timelocked sends
+    #[acc(warden_usage_account, { writable })]
+    // only updated if opened by the `original_fee_payer`
+    #[acc(apa_flag_account)] // only read with an enabled `ApaEnforcement`
+    FinalizeVerificationTransferByUser {
+        mt_shard: u32,
+        verification_account_index: u8,
+    },
+
+    /// Cancels an abandoned (not yet verified) verification after a timeout
+    #[acc(fee_payer, { writable, signer })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[acc(pool_account, { writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { account_info })]
+    #[acc(fee_collector_account, { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info })]
+    #[acc(nullifier_duplicate_account, { writable, owned })]
+    #[acc(ram_account, { writable })] // only used if a `VerifierRAMAccount` is bound to the verification
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    CancelVerification { verification_account_index: u8 },
+
+    /// Groups multiple verifications for a combined proof verification (unused entries can be any accounts)
+    #[cfg(feature = "alt-bn128")]
+    #[acc(fee_payer, { writable, signer })]
+    #[pda(batch_verification_account, BatchVerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(batch_verification_account_index.into()), { writable, account_info, find_pda })]
+    #[acc(verification_account0)]
+    #[acc(verification_account1)]
+    #[acc(verification_account2)]
+    #[acc(verification_account3)]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    InitBatchVerification {
+        batch_verification_account_index: u8,
+        vkey_id: u32,
+        verification_account_indices: [u8; MAX_BATCH_VERIFICATION_SIZE],
+        count: u8,
+    },
+
+    /// Combined proof verification of all verifications in a batch
+    #[cfg(feature = "alt-bn128")]
+    #[acc(fee_payer, { writable, signer })]
+    #[pda(batch_verification_account, BatchVerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(batch_verification_account_index.into()), { writable, account_info })]
+    #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { include_child_accounts })]
+    #[acc(verification_account0, { writable })]
+    #[acc(verification_account1, { writable })]
+    #[acc(verification_account2, { writable })]
+    #[acc(verification_account3, { writable })]
+    ComputeBatchVerification {
+        batch_verification_account_index: u8,
+        vkey_id: u32,
+    },
 
     // -------- Verifying key management --------
     #[acc(signer, { writable, signer })]
@@ -234,16 +566,62 @@ pub enum ElusivInstruction {
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { writable })]
     ChangeVkeyAuthority { vkey_id: u32, authority: Pubkey },
 
+    #[acc(payer, { writable, signer })]
+    #[pda(circuit_registry, CircuitRegistryAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenCircuitRegistryAccount,
+
+    /// Registers the proving-key hash and the public-input layout of a circuit (the vkey hash is read from the vkey account)
+    #[acc(authority, { signer })]
+    #[pda(circuit_registry, CircuitRegistryAccount, { writable })]
+    #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { include_child_accounts })]
+    RegisterCircuit {
+        vkey_id: u32,
+        proving_key_hash: U256,
+        public_inputs_layout_hash: U256,
+    },
+
     // -------- MT management --------
-    /// Set the next MT as the active MT
-    #[pda(storage_account, StorageAccount, { writable, include_child_accounts })]
-    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
+    /// Set the next MT as the active MT (of the MT-shard of `active_mt_index`)
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_index_shard(active_mt_index)), { writable, include_child_accounts })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_index_shard(active_mt_index)), { writable })]
     #[pda(active_nullifier_account, NullifierAccount, pda_offset = Some(active_mt_index), { writable })]
+    #[pda(tree_config, TreeConfigAccount)]
     ResetActiveMerkleTree { active_mt_index: u32 },
 
+    #[acc(payer, { writable, signer })]
+    #[pda(rollover_state, RolloverStateAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenRolloverStateAccount,
+
+    /// Rollover step 1: freezes the root of the closed MT (steps are idempotent and can be resumed)
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_index_shard(closed_mt_index)), { include_child_accounts })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_index_shard(closed_mt_index)), { writable })]
+    #[pda(closed_nullifier_account, NullifierAccount, pda_offset = Some(closed_mt_index), { writable })]
+    #[pda(rollover_state, RolloverStateAccount, { writable })]
+    RolloverFreezeClosedMerkleTree { closed_mt_index: u32 },
+
+    /// Rollover step 2: opens the `NullifierAccount` of the next MT
+    #[acc(payer, { writable, signer })]
+    #[pda(next_nullifier_account, NullifierAccount, pda_offset = Some(closed_mt_index + 1), { writable, skip_pda_verification, account_info })]
+    #[pda(rollover_state, RolloverStateAccount, { writable })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    RolloverOpenNullifierAccount { closed_mt_index: u32 },
+
+    /// Rollover step 3: resets the `StorageAccount` for the next MT
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_index_shard(closed_mt_index)), { writable })]
+    #[pda(tree_config, TreeConfigAccount)]
+    #[pda(rollover_state, RolloverStateAccount, { writable })]
+    RolloverInitStorage { closed_mt_index: u32 },
+
+    /// Rollover step 4: re-points the `GovernorAccount` to the next MT (only for MT-shard `0`)
+    #[pda(governor, GovernorAccount, { writable })]
+    #[pda(rollover_state, RolloverStateAccount, { writable })]
+    RolloverRepointGovernor { closed_mt_index: u32 },
+
     /// Archives a `NullifierAccount` into a N-SMT
     #[acc(payer, { writable, signer })]
-    #[pda(storage_account, StorageAccount, { writable, include_child_accounts })]
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_index_shard(closed_mt_index)), { writable, include_child_accounts })]
     #[pda(nullifier_account, NullifierAccount, pda_offset = Some(closed_mt_index), { writable, include_child_accounts })]
     #[acc(archived_tree_account, { writable })]
     #[sys(system_program, key = system_program::ID, { ignore })]
@@ -265,17 +643,27 @@ pub enum ElusivInstruction {
     #[sys(system_program, key = system_program::ID, { ignore })]
     OpenNullifierAccount { mt_index: u32 },
 
-    #[pda(storage_account, StorageAccount, { writable })]
+    /// Opens the accounts of an additional MT-shard (the accounts of MT-shard `0` are the single-instance-accounts)
+    #[acc(payer, { writable, signer })]
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_shard), { writable, skip_pda_verification, account_info })]
+    #[pda(commitment_queue_account, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable, skip_pda_verification, account_info })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = mt_shard_offset(mt_shard), { writable, skip_pda_verification, account_info })]
+    #[pda(metadata_queue, MetadataQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable, skip_pda_verification, account_info })]
+    #[pda(metadata_account, MetadataAccount, pda_offset = mt_shard_offset(mt_shard), { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenMtShardAccounts { mt_shard: u32 },
+
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
     #[acc(child_account, { owned, writable })]
-    EnableStorageChildAccount { child_index: u32 },
+    EnableStorageChildAccount { mt_shard: u32, child_index: u32 },
 
     #[pda(nullifier_account, NullifierAccount, pda_offset = Some(mt_index), { writable })]
     #[acc(child_account, { owned, writable })]
     EnableNullifierChildAccount { mt_index: u32, child_index: u32 },
 
-    #[pda(metadata_account, MetadataAccount, { writable })]
+    #[pda(metadata_account, MetadataAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
     #[acc(child_account, { owned, writable })]
-    EnableMetadataChildAccount { child_index: u32 },
+    EnableMetadataChildAccount { mt_shard: u32, child_index: u32 },
 
     #[acc(payer, { writable, signer })]
     #[pda(governor, GovernorAccount, { writable, skip_pda_verification, account_info })]
@@ -316,6 +704,409 @@ pub enum ElusivInstruction {
     #[sys(system_program, key = system_program::ID, { ignore })]
     CreateNewAccountsV1,
 
+    #[acc(payer, { writable, signer })]
+    #[pda(quarantine, QuarantineAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenQuarantineAccount,
+
+    #[acc(authority, { signer })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
+    SetCommitmentQueueInFlightCap { in_flight_cap: u32 },
+
+    #[acc(payer, { writable, signer })]
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    #[pda(fee, FeeAccount, pda_offset = Some(fee_version), { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    ScheduleFeeVersion {
+        fee_version: u32,
+        program_fee: ProgramFee,
+        activation_slot: u64,
+    },
+
+    #[acc(signer, { signer })]
+    #[acc(original_fee_payer, { writable })]
+    #[pda(hashing_account, BaseCommitmentHashingAccount, pda_offset = Some(hash_account_index), { writable, account_info })]
+    #[pda(hashing_slots, BaseCommitmentHashingSlotsAccount, { writable })]
+    CloseBaseCommitmentHashingAccount { hash_account_index: u32 },
+
+    #[acc(payer, { writable, signer })]
+    #[pda(ram_account, VerifierRAMAccount, pda_offset = Some(ram_account_index), { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenVerifierRamAccount { ram_account_index: u32 },
+
+    #[acc(warden, { writable, signer })]
+    #[pda(warden_nonce_account, WardenNonceAccount, pda_pubkey = warden.pubkey(), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenWardenNonceAccount,
+
+    #[acc(warden, { writable, signer })]
+    #[pda(warden_usage_account, WardenUsageAccount, pda_pubkey = warden.pubkey(), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenWardenUsageAccount,
+
+    #[acc(payer, { writable, signer })]
+    #[pda(hashing_slots, BaseCommitmentHashingSlotsAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenBaseCommitmentHashingSlotsAccount,
+
+    #[acc(payer, { writable, signer })]
+    #[pda(upgrade_gate, UpgradeGateAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenUpgradeGateAccount,
+
+    /// Approves the program-data hash of a program upgrade (timelocked)
+    #[acc(authority, { signer })]
+    #[pda(upgrade_gate, UpgradeGateAccount, { writable })]
+    ApproveUpgrade { program_data_hash: U256 },
+
+    /// Records the hash of a deployed upgrade as the active program-data hash
+    #[pda(upgrade_gate, UpgradeGateAccount, { writable })]
+    #[acc(program_data)]
+    ConfirmUpgrade,
+
+    #[acc(payer, { writable, signer })]
+    #[pda(feature_flags, FeatureFlagsAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenFeatureFlagsAccount,
+
+    /// Rejects all instructions deprecated since `version` (or earlier)
+    #[acc(authority, { signer })]
+    #[pda(feature_flags, FeatureFlagsAccount, { writable })]
+    SetDeprecationEnforcementVersion { version: u32 },
+
+    #[acc(payer, { writable, signer })]
+    #[pda(token_registry, TokenRegistryAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenTokenRegistryAccount,
+
+    /// Pins the decimals and authorities of a token's mint into the [`TokenRegistryAccount`] (once per token)
+    #[acc(mint)]
+    #[pda(token_registry, TokenRegistryAccount, { writable })]
+    VerifyTokenConfig { token_id: u16 },
+
+    /// Pins a fixed conversion rate for a token, fee computations for this token skip the oracle
+    #[acc(authority, { signer })]
+    #[pda(token_registry, TokenRegistryAccount, { writable })]
+    SetFixedTokenRate {
+        token_id: u16,
+        lamports_per_token: u64,
+    },
+
+    /// Aborts the active commitment hashing computation (before any MT insertion) and re-enqueues its commitments
+    #[acc(authority, { signer })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metadata_queue, MetadataQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metadata_account, MetadataAccount, pda_offset = mt_shard_offset(mt_shard), { writable, include_child_accounts })]
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_shard))]
+    AbortCommitmentHash { mt_shard: u32 },
+
+    /// Registers a child-account for a parent-account and tags the child-account with it's parent-account
+    #[acc(parent_account, { writable })]
+    #[acc(child_account, { owned, writable })]
+    RegisterSubAccount {
+        kind: SubAccountKind,
+        child_index: u32,
+    },
+
+    #[acc(payer, { writable, signer })]
+    #[pda(metrics, MetricsAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenMetricsAccount,
+
+    #[acc(payer, { writable, signer })]
+    #[pda(verification_history, VerificationHistoryAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenVerificationHistoryAccount,
+
+    /// Sets the commitment queue length at which new verifications are rejected
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetCommitmentQueueWatermark { watermark: u32 },
+
+    /// Grows the commitment and metadata queues, once the commitment queue has been full for `QUEUE_PRESSURE_SLOTS`
+    #[pda(governor, GovernorAccount, { writable, account_info })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable, account_info })]
+    #[pda(metadata_queue, MetadataQueueAccount, { writable, account_info })]
+    ResizeQueueAccount,
+
+    /// Sets the number of `VerificationAccount` instances per fee-payer
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetVerificationAccountInstances { instances: u8 },
+
+    /// Sets the number of `VerificationAccount` instances per fee-payer reserved for merges and migrations
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetMergeVerificationAccountInstances { instances: u8 },
+
+    /// Sets the highest batching rate, queued commitments are batched with
+    #[acc(authority, { signer })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    SetMaxCommitmentBatchingRate {
+        mt_shard: u32,
+        max_batching_rate: u32,
+    },
+
+    #[acc(payer, { writable, signer })]
+    #[pda(tree_config, TreeConfigAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenTreeConfigAccount,
+
+    #[acc(payer, { writable, signer })]
+    #[pda(deployment_info, DeploymentInfoAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenDeploymentInfoAccount,
+
+    /// Publishes the program's version, features and the hash of a vkey
+    #[pda(deployment_info, DeploymentInfoAccount, { writable })]
+    #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { include_child_accounts })]
+    PublishDeploymentInfo { vkey_id: u32 },
+
+    /// Sets the height of the MTs activated after the active MT
+    #[acc(authority, { signer })]
+    #[pda(tree_config, TreeConfigAccount, { writable })]
+    SetTreeHeight { height: u32 },
+
+    /// Sets the number of concurrently active MTs (new commitments are assigned to the MT-shards by their parity)
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetActiveMtShards { active_mt_shards: u32 },
+
+    /// Sets the warden attestation level required from fee-payers of stores and verifications (`0` is the open mode)
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetRequiredWardenAttestationLevel { level: u8 },
+
+    /// Sets the range of protocol versions accepted by versioned instructions
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetSupportedProtocolVersions {
+        min_protocol_version: u8,
+        max_protocol_version: u8,
+    },
+
+    /// Sets the max join-split amount per commitment in the active MT for a token (`0` disables the limit)
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetTreeAgeAmountLimit { token_id: u16, limit: u64 },
+
+    /// Sets the max ciphertext length and fee multiplier (in basis points) of a commitment metadata size class (a length of `0` disables the class)
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetMetadataSizeClass {
+        class_index: u8,
+        max_ciphertext_len: u16,
+        fee_multiplier_bps: u16,
+    },
+
+    /// Sets the spread (in basis points) charged on lamport-denominated fees settled in a token
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetTokenFeeSpread { spread_bps: u16 },
+
+    /// Sets the fair-share (in basis points) of a fee-payer in the base-commitment hashes finalized within a window of slots
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetHashingFairShare { share_bps: u16, window_slots: u64 },
+
+    /// Requires (or stops requiring) warden nonces for stores and verifications
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetRequireWardenNonces { require_warden_nonces: bool },
+
+    /// Sets the handling of payouts to recipients flagged by the APA of the Elusiv Warden Network
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetApaEnforcement { apa_enforcement: ApaEnforcement },
+
+    /// Halts (or resumes) new stores, verifications and commitment hashing batches
+    #[acc(authority, { signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    SetProtocolPause { is_paused: bool },
+
+    /// Sweeps rounding dust from the pool into the fee collector
+    #[acc(authority, { signer })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[acc(pool_account, { writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { account_info })]
+    #[acc(fee_collector_account, { writable })]
+    #[pda(metrics, MetricsAccount, { writable })]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    SweepPoolDust { token_id: u16, amount: u64 },
+
+    #[cfg(feature = "pool-yield")]
+    #[acc(payer, { writable, signer })]
+    #[pda(pool_yield, PoolYieldAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenPoolYieldAccount,
+
+    /// Whitelists a stake pool for the delegation of idle pool lamports
+    #[cfg(feature = "pool-yield")]
+    #[acc(authority, { signer })]
+    #[pda(pool_yield, PoolYieldAccount, { writable })]
+    SetPoolYieldConfig {
+        stake_pool: Pubkey,
+        max_delegation_bps: u16,
+        buffer_bps: u16,
+    },
+
+    /// Delegates idle pool lamports to the whitelisted stake pool
+    #[cfg(feature = "pool-yield")]
+    #[acc(authority, { signer })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[pda(pool_yield, PoolYieldAccount, { writable })]
+    #[pda(metrics, MetricsAccount)]
+    #[acc(yield_authority, { writable })]
+    #[acc(yield_pool_token_account, { writable })]
+    #[acc(stake_pool, { writable })]
+    #[acc(stake_pool_withdraw_authority)]
+    #[acc(reserve_stake, { writable })]
+    #[acc(manager_fee_account, { writable })]
+    #[acc(pool_mint, { writable })]
+    #[sys(system_program, key = system_program::ID)]
+    #[acc(token_program)]
+    #[acc(stake_pool_program)]
+    DelegatePoolLamports { amount: u64 },
+
+    /// Withdraws lamports from the whitelisted stake pool into the pool (permissionless up to the pool's buffer deficit)
+    #[cfg(feature = "pool-yield")]
+    #[acc(signer, { signer })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[pda(pool_yield, PoolYieldAccount, { writable })]
+    #[pda(metrics, MetricsAccount)]
+    #[acc(verification_account)] // finalized send with a pending payout (or any account)
+    #[acc(yield_authority, { writable })]
+    #[acc(yield_pool_token_account, { writable })]
+    #[acc(stake_pool, { writable })]
+    #[acc(stake_pool_withdraw_authority)]
+    #[acc(reserve_stake, { writable })]
+    #[acc(manager_fee_account, { writable })]
+    #[acc(pool_mint, { writable })]
+    #[sys(clock, sysvar = Clock)]
+    #[sys(stake_history, key = solana_program::sysvar::stake_history::ID)]
+    #[sys(stake_program, key = solana_program::stake::program::ID)]
+    #[sys(system_program, key = system_program::ID)]
+    #[acc(token_program)]
+    #[acc(stake_pool_program)]
+    UndelegatePoolLamports { pool_tokens: u64 },
+
+    /// Writes the MT opening of a commitment (followed by the root) as return data (used by light indexers with simulated transactions)
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_shard), { include_child_accounts })]
+    GetMtOpening { mt_shard: u32, index: u32 },
+
+    // -------- Devnet reset (in order) --------
+    #[cfg(not(feature = "mainnet"))]
+    #[acc(authority, { signer })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(commitment_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metadata_queue, MetadataQueueAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metadata_account, MetadataAccount, pda_offset = mt_shard_offset(mt_shard), { writable })]
+    #[pda(metrics, MetricsAccount, { writable })]
+    #[pda(governor, GovernorAccount, { writable })]
+    ResetCommitmentQueues { mt_shard: u32 },
+
+    #[cfg(not(feature = "mainnet"))]
+    #[acc(authority, { signer })]
+    #[acc(recipient, { writable })]
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_shard), { writable, include_child_accounts })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = mt_shard_offset(mt_shard))]
+    #[pda(commitment_queue, CommitmentQueueAccount, pda_offset = mt_shard_offset(mt_shard))]
+    ResetStorageAccount { mt_shard: u32 },
+
+    #[cfg(not(feature = "mainnet"))]
+    #[acc(authority, { signer })]
+    #[pda(storage_account, StorageAccount, pda_offset = mt_shard_offset(mt_index_shard(mt_index)))]
+    #[pda(nullifier_account, NullifierAccount, pda_offset = Some(mt_index), { writable, include_child_accounts })]
+    ResetNullifierAccount { mt_index: u32 },
+
+    #[cfg(not(feature = "mainnet"))]
+    #[acc(authority, { signer })]
+    #[acc(fee_payer, { writable })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[acc(pool_account, { writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { account_info })]
+    #[acc(fee_collector_account, { writable })]
+    #[pda(governor, GovernorAccount)]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info })]
+    #[acc(nullifier_duplicate_account, { writable, owned })]
+    #[acc(ram_account, { writable })] // only used if a `VerifierRAMAccount` is bound to the verification
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    ResetVerificationAccount { verification_account_index: u8 },
+
+    // -------- Cluster migration --------
+    #[cfg(not(feature = "mainnet"))]
+    #[acc(payer, { writable, signer })]
+    #[pda(migration_manifest, MigrationManifestAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenMigrationManifestAccount,
+
+    /// Exports the next chunk of a program account's data as return data
+    #[cfg(not(feature = "mainnet"))]
+    #[acc(authority, { signer })]
+    #[pda(migration_manifest, MigrationManifestAccount, { writable })]
+    #[acc(account)]
+    ExportAccountChunk {
+        entry_index: u32,
+        offset: u32,
+        len: u32,
+    },
+
+    /// Imports the next chunk of an exported program account's data
+    #[cfg(not(feature = "mainnet"))]
+    #[acc(authority, { signer })]
+    #[pda(migration_manifest, MigrationManifestAccount, { writable })]
+    #[acc(account, { writable })]
+    ImportAccountChunk {
+        entry_index: u32,
+        offset: u32,
+        expected_hash: U256,
+        data: Vec<u8>,
+    },
+
+    // -------- Hash test vectors --------
+    /// Computes a range of Poseidon rounds and compares the state with a test vector
+    #[cfg(not(feature = "mainnet"))]
+    VerifyPoseidonTestVector {
+        state: [U256; 3],
+        first_round: u32,
+        round_count: u32,
+        expected: [U256; 3],
+    },
+
+    // -------- Recipient preferences --------
+    /// Sets the payout preferences of a recipient
+    #[acc(recipient, { writable, signer })]
+    #[pda(recipient_preference_account, RecipientPreferenceAccount, pda_pubkey = recipient.pubkey(), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    SetRecipientPreferences {
+        denies_all_payouts: bool,
+        denied_tokens: [bool; TOKEN_COUNT],
+    },
+
+    /// Releases quarantined payouts to the recipient
+    #[acc(recipient, { signer })]
+    #[acc(recipient_account, { writable })]
+    #[pda(recipient_preference_account, RecipientPreferenceAccount, pda_pubkey = recipient.pubkey(), { writable })]
+    #[pda(quarantine, QuarantineAccount, { account_info, writable })]
+    #[acc(quarantine_account, { writable })]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    ReleaseQuarantinedPayout { token_id: u16 },
+
+    // -------- Viewing keys --------
+    /// Publishes the viewing key of an identifier (voluntary disclosure, e.g. to an auditor)
+    #[acc(payer, { writable, signer })]
+    #[acc(identifier_account, { signer })]
+    #[pda(viewing_key_account, ViewingKeyAccount, pda_pubkey = identifier_account.pubkey(), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    PublishViewingKey { viewing_key: U256 },
+
+    // -------- Self-test --------
+    /// Validates a deployment and RPC path against the live program (writes a `SelfTestReport` as return data)
+    WardenSelfTest,
+
     // -------- NOP --------
     /// NOP-instruction
     Nop,
@@ -324,22 +1115,83 @@ pub enum ElusivInstruction {
 #[cfg(feature = "elusiv-client")]
 use elusiv_types::accounts::PDAAccount;
 
+#[cfg(feature = "elusiv-client")]
+use crate::state::metadata::{CommitmentMetadataAccount, CommitmentMetadataBackfill};
+
+#[cfg(feature = "elusiv-client")]
+use crate::state::storage::{StorageNodeSlice, MT_HEIGHT};
+
+/// Heap frame requested for instructions relying on just-in-time heap allocation (e.g. [`crate::state::proof::LazyRAM`])
+#[cfg(feature = "elusiv-client")]
+pub const ELUSIV_HEAP_FRAME_BYTES: u32 = 256 * 1024;
+
+/// Compute budget required by a transaction containing a specific [`ElusivInstruction`]
+#[cfg(feature = "elusiv-client")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudget {
+    pub compute_units: u32,
+    pub heap_frame_bytes: Option<u32>,
+}
+
 #[cfg(feature = "elusiv-client")]
 impl ElusivInstruction {
     pub fn store_base_commitment_sol_instruction(
         hash_account_index: u32,
         request: BaseCommitmentHashRequest,
         metadata: CommitmentMetadata,
+        warden_nonce: ElusivOption<u64>,
         client: Pubkey,
         warden: Pubkey,
     ) -> solana_program::instruction::Instruction {
         let hash_account_bump = BaseCommitmentHashingAccount::find(Some(hash_account_index)).1;
 
         ElusivInstruction::store_base_commitment_instruction(
+            crate::state::governor::PROTOCOL_VERSION,
             hash_account_index,
             hash_account_bump,
             request,
             metadata,
+            warden_nonce,
+            SignerAccount(client),
+            WritableUserAccount(client),
+            WritableSignerAccount(warden),
+            WritableUserAccount(warden),
+            WritableUserAccount(PoolAccount::find(None).0),
+            WritableUserAccount(FeeCollectorAccount::find(None).0),
+            UserAccount(system_program::id()),
+            UserAccount(system_program::id()),
+            UserAccount(system_program::id()),
+            UserAccount(processor::warden_attestation_account(&warden)),
+            WritableUserAccount(WardenNonceAccount::find_with_pubkey(warden, None).0),
+        )
+    }
+
+    pub fn store_base_commitment_batch_sol_instruction(
+        hash_account_indices: &[u32],
+        requests: Vec<(BaseCommitmentHashRequest, CommitmentMetadata)>,
+        warden_nonce: ElusivOption<u64>,
+        client: Pubkey,
+        warden: Pubkey,
+    ) -> solana_program::instruction::Instruction {
+        assert_eq!(hash_account_indices.len(), requests.len());
+        assert!(requests.len() <= MAX_STORE_BATCH_SIZE);
+
+        let mut indices = [0; MAX_STORE_BATCH_SIZE];
+        let mut bumps = [0; MAX_STORE_BATCH_SIZE];
+        let mut hashing_accounts = [warden; MAX_STORE_BATCH_SIZE];
+        for (i, &index) in hash_account_indices.iter().enumerate() {
+            let (pubkey, bump) = BaseCommitmentHashingAccount::find(Some(index));
+            indices[i] = index;
+            bumps[i] = bump;
+            hashing_accounts[i] = pubkey;
+        }
+
+        ElusivInstruction::store_base_commitment_batch_instruction(
+            crate::state::governor::PROTOCOL_VERSION,
+            indices,
+            bumps,
+            requests,
+            warden_nonce,
             SignerAccount(client),
             WritableUserAccount(client),
             WritableSignerAccount(warden),
@@ -349,6 +1201,12 @@ impl ElusivInstruction {
             UserAccount(system_program::id()),
             UserAccount(system_program::id()),
             UserAccount(system_program::id()),
+            UserAccount(processor::warden_attestation_account(&warden)),
+            WritableUserAccount(WardenNonceAccount::find_with_pubkey(warden, None).0),
+            WritableUserAccount(hashing_accounts[0]),
+            WritableUserAccount(hashing_accounts[1]),
+            WritableUserAccount(hashing_accounts[2]),
+            WritableUserAccount(hashing_accounts[3]),
         )
     }
 
@@ -389,6 +1247,293 @@ impl ElusivInstruction {
             UserAccount(spl_token::id()),
         )
     }
+
+    /// Returns the compute budget required by the instruction (`None` if the default budget suffices)
+    ///
+    /// # Note
+    ///
+    /// The values are derived from the [`elusiv_computation::PartialComputation`]s of the instructions.
+    pub fn compute_budget(&self) -> Option<ComputeBudget> {
+        use crate::commitment::{BaseCommitmentHashComputation, COMMITMENT_HASH_COMPUTE_BUDGET};
+        use crate::processor::COMPUTE_VERIFICATION_IX_COUNT;
+        use elusiv_computation::{PartialComputation, MAX_COMPUTE_UNIT_LIMIT};
+
+        let (compute_units, heap_frame_bytes) = match self {
+            ElusivInstruction::ComputeBaseCommitmentHash { .. } => {
+                (BaseCommitmentHashComputation::COMPUTE_BUDGET_PER_IX, None)
+            }
+            ElusivInstruction::ComputeCommitmentHash { .. } => {
+                (COMMITMENT_HASH_COMPUTE_BUDGET, None)
+            }
+
+            // Two compute-budget-instructions and the remaining compute-verification-instructions share a transaction
+            ElusivInstruction::ComputeVerification { .. } => (
+                MAX_COMPUTE_UNIT_LIMIT / (COMPUTE_VERIFICATION_IX_COUNT as u32 - 2),
+                Some(ELUSIV_HEAP_FRAME_BYTES),
+            ),
+
+            // All proofs of a batch are verified in a single instruction
+            #[cfg(feature = "alt-bn128")]
+            ElusivInstruction::ComputeBatchVerification { .. } => (MAX_COMPUTE_UNIT_LIMIT, None),
+
+            #[cfg(not(feature = "mainnet"))]
+            ElusivInstruction::VerifyPoseidonTestVector { .. } => (MAX_COMPUTE_UNIT_LIMIT, None),
+
+            // Nullifier insertion
+            ElusivInstruction::FinalizeVerificationSend { .. }
+            | ElusivInstruction::FinalizeVerificationInsertNullifier { .. } => {
+                (MAX_COMPUTE_UNIT_LIMIT, None)
+            }
+
+            _ => return None,
+        };
+
+        Some(ComputeBudget {
+            compute_units,
+            heap_frame_bytes,
+        })
+    }
+
+    /// Prepends the compute-budget instructions required by all [`ElusivInstruction`]s in `instructions`
+    ///
+    /// # Note
+    ///
+    /// - The compute units of all instructions are accumulated (capped at [`elusiv_computation::MAX_COMPUTE_UNIT_LIMIT`]), the largest heap frame is requested.
+    /// - Instructions of other programs are ignored.
+    pub fn with_compute_budget(
+        instructions: Vec<solana_program::instruction::Instruction>,
+    ) -> Vec<solana_program::instruction::Instruction> {
+        let budget = instructions
+            .iter()
+            .filter(|ix| ix.program_id == crate::ID)
+            .filter_map(|ix| ElusivInstruction::try_from_slice(&ix.data).ok())
+            .filter_map(|ix| ix.compute_budget())
+            .fold(None, |acc: Option<ComputeBudget>, budget| {
+                Some(match acc {
+                    None => budget,
+                    Some(acc) => ComputeBudget {
+                        compute_units: acc.compute_units.saturating_add(budget.compute_units),
+                        heap_frame_bytes: acc.heap_frame_bytes.max(budget.heap_frame_bytes),
+                    },
+                })
+            });
+
+        match budget {
+            Some(budget) => {
+                let mut ixs = elusiv_utils::compute_budget_instructions(
+                    budget.compute_units,
+                    budget.heap_frame_bytes,
+                );
+                ixs.extend(instructions);
+                ixs
+            }
+            None => instructions,
+        }
+    }
+
+    /// Verifies that the `instructions` fit into a single transaction paid by `fee_payer`
+    ///
+    /// # Note
+    ///
+    /// - The `instructions` need to contain the compute-budget instructions (see [`ElusivInstruction::with_compute_budget`]).
+    /// - The returned error contains the size breakdown of all instructions (named by their variant for [`ElusivInstruction`]s).
+    pub fn check_transaction_size(
+        instructions: &[solana_program::instruction::Instruction],
+        fee_payer: &Pubkey,
+    ) -> Result<usize, elusiv_utils::TransactionSizeError> {
+        elusiv_utils::check_transaction_size(instructions, fee_payer, |ix| {
+            ix.data
+                .first()
+                .filter(|_| ix.program_id == crate::ID)
+                .and_then(|&index| ElusivInstruction::VARIANT_NAMES.get(index as usize))
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| ix.program_id.to_string())
+        })
+    }
+
+    /// Returns the [`ElusivInstruction::BackfillCommitmentMetadata`] instructions of the depositor, packed into as few transactions (paid by `payer`) as possible
+    ///
+    /// # Note
+    ///
+    /// - All transactions need to be signed by the `payer` and the identifier keys of their notes.
+    /// - Each instruction only contains the child-account of `storage_accounts` (all child-accounts of the [`StorageAccount`]) storing the commitment.
+    /// - Fails if a single backfill exceeds the transaction size (the ciphertext is too large).
+    pub fn backfill_commitment_metadata_transactions(
+        backfills: Vec<CommitmentMetadataBackfill>,
+        storage_accounts: &[Pubkey],
+        payer: Pubkey,
+    ) -> Result<
+        Vec<Vec<solana_program::instruction::Instruction>>,
+        elusiv_utils::TransactionSizeError,
+    > {
+        let mut transactions: Vec<Vec<solana_program::instruction::Instruction>> = Vec::new();
+
+        for backfill in backfills {
+            let commitment_metadata_account = CommitmentMetadataAccount::find_with_pubkey(
+                CommitmentMetadataAccount::backfill_pubkey(
+                    &backfill.commitment,
+                    &backfill.identifier,
+                ),
+                None,
+            )
+            .0;
+            let storage_account = storage_accounts[StorageNodeSlice::new(
+                backfill.commitment_index as usize,
+                MT_HEIGHT as usize,
+            )
+            .child_index as usize];
+            let instruction = ElusivInstruction::backfill_commitment_metadata_instruction(
+                backfill.mt_index,
+                backfill.commitment_index,
+                backfill.commitment,
+                backfill.identifier.to_bytes(),
+                backfill.ciphertext,
+                WritableSignerAccount(payer),
+                SignerAccount(backfill.identifier),
+                &[UserAccount(storage_account)],
+                WritableUserAccount(commitment_metadata_account),
+            );
+
+            if let Some(transaction) = transactions.last_mut() {
+                transaction.push(instruction.clone());
+                if ElusivInstruction::check_transaction_size(transaction, &payer).is_ok() {
+                    continue;
+                }
+                transaction.pop();
+            }
+
+            ElusivInstruction::check_transaction_size(&[instruction.clone()], &payer)?;
+            transactions.push(vec![instruction]);
+        }
+
+        Ok(transactions)
+    }
+
+    /// Returns the instructions verifying the Poseidon hash of `(l, r)` against `expected` (in chunks of [`crate::processor::MAX_POSEIDON_TEST_VECTOR_ROUNDS`])
+    ///
+    /// # Note
+    ///
+    /// The intermediate states are computed with the reference implementation, only the final result is taken from `expected`.
+    #[cfg(not(feature = "mainnet"))]
+    pub fn poseidon_test_vector_instructions(
+        l: ark_bn254::Fr,
+        r: ark_bn254::Fr,
+        expected: ark_bn254::Fr,
+    ) -> Vec<solana_program::instruction::Instruction> {
+        use crate::commitment::poseidon_hash::{
+            binary_poseidon_hash_partial, BinarySpongeHashingState, TOTAL_POSEIDON_ROUNDS,
+        };
+        use crate::fields::fr_to_u256_le;
+        use crate::processor::MAX_POSEIDON_TEST_VECTOR_ROUNDS;
+
+        let serialize = |state: &BinarySpongeHashingState| state.0.map(|fr| fr_to_u256_le(&fr));
+        let mut state = BinarySpongeHashingState::new(l, r, false);
+        let mut instructions = Vec::new();
+
+        for first_round in
+            (0..TOTAL_POSEIDON_ROUNDS).step_by(MAX_POSEIDON_TEST_VECTOR_ROUNDS as usize)
+        {
+            let round_count =
+                MAX_POSEIDON_TEST_VECTOR_ROUNDS.min(TOTAL_POSEIDON_ROUNDS - first_round);
+            let input = serialize(&state);
+            for round in first_round..first_round + round_count {
+                binary_poseidon_hash_partial(round, &mut state);
+            }
+
+            let mut output = serialize(&state);
+            if first_round + round_count == TOTAL_POSEIDON_ROUNDS {
+                output[0] = fr_to_u256_le(&expected);
+            }
+
+            instructions.push(ElusivInstruction::verify_poseidon_test_vector_instruction(
+                input,
+                first_round,
+                round_count,
+                output,
+            ));
+        }
+
+        instructions
+    }
+
+    /// Returns the instructions (in the required order) to reset a devnet deployment to its genesis state
+    ///
+    /// # Note
+    ///
+    /// - The protocol is paused for the duration of the reset and resumed by the last instruction.
+    /// - `program_accounts` (e.g. base-commitment-hashing-accounts) are closed before the queues are reset.
+    /// - `nullifier_accounts` contains the `mt_index` and the enabled child-accounts of each [`NullifierAccount`].
+    /// - `storage_child_accounts` and `new_storage_child_accounts` contain the child-accounts of each MT-shard (indexed by `mt_shard`).
+    /// - `new_storage_child_accounts` need to be created (with the program as owner) beforehand, they replace the closed child-accounts.
+    /// - Verifications initialized before the reset cannot be finalized anymore, they can be closed using [`ElusivInstruction::reset_verification_account_instruction`].
+    /// - All instructions need to be signed by the program's keypair.
+    #[cfg(not(feature = "mainnet"))]
+    pub fn reset_protocol_state_instructions(
+        recipient: Pubkey,
+        program_accounts: &[Pubkey],
+        storage_child_accounts: &[Vec<Pubkey>],
+        nullifier_accounts: &[(u32, Vec<Pubkey>)],
+        new_storage_child_accounts: &[Vec<Pubkey>],
+    ) -> Vec<solana_program::instruction::Instruction> {
+        let writable = |accounts: &[Pubkey]| -> Vec<WritableUserAccount> {
+            accounts.iter().map(|a| WritableUserAccount(*a)).collect()
+        };
+
+        let mut ixs = vec![ElusivInstruction::set_protocol_pause_instruction(
+            true,
+            SignerAccount(crate::ID),
+        )];
+
+        ixs.extend(program_accounts.iter().map(|account| {
+            ElusivInstruction::close_program_account_instruction(
+                SignerAccount(crate::ID),
+                WritableUserAccount(recipient),
+                WritableUserAccount(*account),
+            )
+        }));
+
+        for (mt_shard, child_accounts) in storage_child_accounts.iter().enumerate() {
+            ixs.push(ElusivInstruction::reset_commitment_queues_instruction(
+                mt_shard as u32,
+                SignerAccount(crate::ID),
+            ));
+            ixs.push(ElusivInstruction::reset_storage_account_instruction(
+                mt_shard as u32,
+                SignerAccount(crate::ID),
+                WritableUserAccount(recipient),
+                &writable(child_accounts),
+            ));
+        }
+        ixs.extend(nullifier_accounts.iter().map(|(mt_index, child_accounts)| {
+            ElusivInstruction::reset_nullifier_account_instruction(
+                *mt_index,
+                SignerAccount(crate::ID),
+                &writable(child_accounts),
+            )
+        }));
+
+        // Genesis state
+        for (mt_shard, child_accounts) in new_storage_child_accounts.iter().enumerate() {
+            ixs.extend(
+                child_accounts
+                    .iter()
+                    .enumerate()
+                    .map(|(child_index, account)| {
+                        ElusivInstruction::enable_storage_child_account_instruction(
+                            mt_shard as u32,
+                            child_index as u32,
+                            WritableUserAccount(*account),
+                        )
+                    }),
+            );
+        }
+        ixs.push(ElusivInstruction::set_protocol_pause_instruction(
+            false,
+            SignerAccount(crate::ID),
+        ));
+
+        ixs
+    }
 }
 
 #[cfg(test)]
@@ -425,4 +1570,161 @@ mod tests {
             ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX
         );
     }
+
+    #[test]
+    fn test_with_compute_budget() {
+        use solana_program::instruction::Instruction;
+
+        let ix = |instruction: ElusivInstruction| {
+            Instruction::new_with_borsh(crate::ID, &instruction, vec![])
+        };
+        let compute_verification = ElusivInstruction::ComputeVerification {
+            verification_account_index: 0,
+            vkey_id: 0,
+            ram_account_index: 0,
+        };
+
+        // No budget required
+        assert_eq!(ElusivInstruction::Nop.compute_budget(), None);
+        assert_eq!(
+            ElusivInstruction::with_compute_budget(vec![ix(ElusivInstruction::Nop)]),
+            vec![ix(ElusivInstruction::Nop)]
+        );
+
+        let budget = compute_verification.compute_budget().unwrap();
+        assert_eq!(budget.heap_frame_bytes, Some(ELUSIV_HEAP_FRAME_BYTES));
+
+        let ixs = ElusivInstruction::with_compute_budget(vec![
+            ix(ElusivInstruction::Nop),
+            ix(compute_verification),
+        ]);
+        assert_eq!(ixs.len(), 4);
+        assert_eq!(
+            ixs[..2],
+            elusiv_utils::compute_budget_instructions(
+                budget.compute_units,
+                Some(ELUSIV_HEAP_FRAME_BYTES)
+            )
+        );
+
+        // Capped compute units
+        let ixs = ElusivInstruction::with_compute_budget(vec![
+            ix(ElusivInstruction::ComputeBaseCommitmentHash {
+                hash_account_index: 0,
+            }),
+            ix(ElusivInstruction::FinalizeVerificationInsertNullifier {
+                verification_account_index: 0,
+            }),
+        ]);
+        assert_eq!(ixs.len(), 3);
+        assert_eq!(
+            ixs[0],
+            elusiv_utils::compute_budget_instructions(
+                elusiv_computation::MAX_COMPUTE_UNIT_LIMIT,
+                None
+            )[0]
+        );
+    }
+
+    #[test]
+    fn test_check_transaction_size() {
+        use solana_sdk::packet::PACKET_DATA_SIZE;
+
+        let fee_payer = Pubkey::new_unique();
+        let compute_verification = |accounts_count: usize| {
+            let accounts: Vec<UserAccount> = (0..accounts_count)
+                .map(|_| UserAccount(Pubkey::new_unique()))
+                .collect();
+            ElusivInstruction::with_compute_budget(vec![
+                ElusivInstruction::compute_verification_instruction(
+                    0,
+                    0,
+                    0,
+                    UserAccount(fee_payer),
+                    &accounts,
+                ),
+            ])
+        };
+
+        let size = ElusivInstruction::check_transaction_size(&compute_verification(2), &fee_payer)
+            .unwrap();
+        assert!(size <= PACKET_DATA_SIZE);
+
+        // Each additional account requires its key and index
+        assert_eq!(
+            ElusivInstruction::check_transaction_size(&compute_verification(3), &fee_payer),
+            Ok(size + 33)
+        );
+
+        let ixs = compute_verification(40);
+        let err = ElusivInstruction::check_transaction_size(&ixs, &fee_payer).unwrap_err();
+        assert!(err.size > PACKET_DATA_SIZE);
+        assert_eq!(err.instructions.len(), 3);
+        assert_eq!(err.instructions[2].name, "ComputeVerification");
+        assert_eq!(err.instructions[2].accounts_count, ixs[2].accounts.len());
+        assert_eq!(err.instructions[2].data_len, ixs[2].data.len());
+        assert_eq!(
+            err.instructions[0].name,
+            solana_sdk::compute_budget::id().to_string()
+        );
+        assert!(err.to_string().contains("address lookup table"));
+    }
+
+    #[test]
+    fn test_backfill_commitment_metadata_transactions() {
+        let identifier = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let storage_accounts: Vec<Pubkey> = (0
+            ..<StorageAccount as elusiv_types::ParentAccount>::COUNT)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        let backfill = |commitment_index: u32, ciphertext_len: usize| CommitmentMetadataBackfill {
+            mt_index: 0,
+            commitment_index,
+            commitment: [commitment_index as u8; 32],
+            identifier,
+            ciphertext: vec![1; ciphertext_len],
+        };
+
+        let transactions = ElusivInstruction::backfill_commitment_metadata_transactions(
+            (0..8).map(|i| backfill(i, 100)).collect(),
+            &storage_accounts,
+            payer,
+        )
+        .unwrap();
+
+        // Backfills share transactions
+        assert!(transactions.len() > 1 && transactions.len() < 8);
+        assert_eq!(transactions.iter().map(|t| t.len()).sum::<usize>(), 8);
+        for transaction in &transactions {
+            assert!(ElusivInstruction::check_transaction_size(transaction, &payer).is_ok());
+        }
+
+        let instruction = &transactions[0][1];
+        assert_eq!(instruction.accounts[0].pubkey, payer);
+        assert_eq!(instruction.accounts[1].pubkey, identifier);
+        assert!(instruction.accounts[1].is_signer);
+        assert_eq!(
+            instruction.accounts[3].pubkey,
+            storage_accounts[StorageNodeSlice::new(1, MT_HEIGHT as usize).child_index as usize]
+        );
+        assert_eq!(
+            instruction.accounts[5].pubkey,
+            CommitmentMetadataAccount::find_with_pubkey(
+                CommitmentMetadataAccount::backfill_pubkey(&[1; 32], &identifier),
+                None
+            )
+            .0
+        );
+
+        // A single backfill exceeding the transaction size
+        assert!(
+            ElusivInstruction::backfill_commitment_metadata_transactions(
+                vec![backfill(0, 100), backfill(1, 1200)],
+                &storage_accounts,
+                payer,
+            )
+            .is_err()
+        );
+    }
 }
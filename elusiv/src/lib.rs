@@ -2,15 +2,25 @@
 
 pub mod buffer;
 pub mod bytes;
+#[cfg(feature = "client-store")]
+pub mod client_store;
 pub mod commitment;
+#[cfg(feature = "cpi")]
+pub mod cpi;
 pub mod entrypoint;
 mod error;
+pub mod event;
 pub mod fields;
+pub mod identifier;
 pub mod instruction;
 mod macros;
 pub mod map;
+#[cfg(feature = "elusiv-client")]
+pub mod metrics_exporter;
 pub mod processor;
 pub mod proof;
+#[cfg(feature = "elusiv-client")]
+pub mod rent_planner;
 pub mod state;
 pub mod token;
 pub mod types;
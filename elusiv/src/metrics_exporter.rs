@@ -0,0 +1,429 @@
+//! Prometheus text exposition of the queues, metrics and warden attestations for clients (wardens, operators)
+//!
+//! # Note
+//!
+//! - [`collect_metrics`] decodes the program accounts (e.g. fetched with `getMultipleAccounts`), identified by their PDAs; missing accounts are skipped.
+//! - `WardenAttestationAccount`s are owned by the Elusiv Warden Network, so they are passed together with their warden.
+//! - [`encode_text`] serializes the metrics in the Prometheus text exposition format (version `0.0.4`).
+
+use crate::processor::{warden_attestation_account, warden_attestation_level};
+use crate::rent_planner::ProgramAccountSnapshot;
+use crate::state::commitment::CommitmentQueueAccount;
+use crate::state::metadata::{MetadataQueue, MetadataQueueAccount};
+use crate::state::metrics::{MetricsAccount, SEND_SIZE_BUCKET_COUNT};
+use crate::state::program_account::{PDAAccount, ProgramAccount};
+use crate::state::queue::{Queue, RingQueue};
+use crate::token::TOKENS;
+use solana_program::pubkey::Pubkey;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+impl MetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricSample {
+    pub labels: Vec<(&'static str, String)>,
+    pub value: u64,
+}
+
+/// All samples of a single metric
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricFamily {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub kind: MetricKind,
+    pub samples: Vec<MetricSample>,
+}
+
+impl MetricFamily {
+    fn new(name: &'static str, help: &'static str, kind: MetricKind) -> Self {
+        MetricFamily {
+            name,
+            help,
+            kind,
+            samples: Vec::new(),
+        }
+    }
+
+    fn with_sample(mut self, labels: Vec<(&'static str, String)>, value: u64) -> Self {
+        self.samples.push(MetricSample { labels, value });
+        self
+    }
+}
+
+/// Returns the snapshot of the account with the address `pubkey`
+fn snapshot_data(accounts: &[ProgramAccountSnapshot], pubkey: &Pubkey) -> Option<Vec<u8>> {
+    accounts
+        .iter()
+        .find(|account| account.pubkey == *pubkey)
+        .map(|account| account.data.clone())
+}
+
+fn commitment_queue_metrics(data: &mut [u8]) -> Option<Vec<MetricFamily>> {
+    let account = CommitmentQueueAccount::new(data).ok()?;
+
+    Some(vec![
+        MetricFamily::new(
+            "elusiv_commitment_queue_len",
+            "Number of commitments waiting to be inserted into the active MT",
+            MetricKind::Gauge,
+        )
+        .with_sample(vec![], account.queue_len() as u64),
+        MetricFamily::new(
+            "elusiv_commitment_queue_capacity",
+            "Maximum number of queued commitments",
+            MetricKind::Gauge,
+        )
+        .with_sample(vec![], account.queue_size() as u64 - 1),
+        MetricFamily::new(
+            "elusiv_commitment_queue_in_flight_cap",
+            "Maximum number of queued commitments per fee-payer (zero if disabled)",
+            MetricKind::Gauge,
+        )
+        .with_sample(vec![], account.get_in_flight_cap() as u64),
+        MetricFamily::new(
+            "elusiv_commitment_queue_active_fee_payers",
+            "Number of fee-payers with queued commitments",
+            MetricKind::Gauge,
+        )
+        .with_sample(vec![], account.active_fee_payers() as u64),
+        MetricFamily::new(
+            "elusiv_commitment_queue_head_priority_lamports",
+            "Priority-bid of the next commitment (zero for an empty queue)",
+            MetricKind::Gauge,
+        )
+        .with_sample(vec![], account.head_priority().unwrap_or(0)),
+    ])
+}
+
+fn metadata_queue_metrics(data: &mut [u8]) -> Option<Vec<MetricFamily>> {
+    let mut account = MetadataQueueAccount::new(data).ok()?;
+    let queue = MetadataQueue::new(&mut account);
+
+    Some(vec![MetricFamily::new(
+        "elusiv_metadata_queue_len",
+        "Number of commitment metadata entries waiting to be stored",
+        MetricKind::Gauge,
+    )
+    .with_sample(vec![], queue.len() as u64)])
+}
+
+fn protocol_metrics(data: &mut [u8]) -> Option<Vec<MetricFamily>> {
+    let account = MetricsAccount::new(data).ok()?;
+
+    let per_token = |name, help, kind, value: &dyn Fn(usize) -> u64| {
+        TOKENS
+            .iter()
+            .enumerate()
+            .fold(MetricFamily::new(name, help, kind), |family, (i, token)| {
+                family.with_sample(vec![("token", token.ident.to_string())], value(i))
+            })
+    };
+
+    let mut send_sizes = MetricFamily::new(
+        "elusiv_sends_total",
+        "Number of sends per order of magnitude of the sent amount (in whole tokens)",
+        MetricKind::Counter,
+    );
+    for (i, token) in TOKENS.iter().enumerate() {
+        for bucket in 0..SEND_SIZE_BUCKET_COUNT {
+            send_sizes = send_sizes.with_sample(
+                vec![
+                    ("token", token.ident.to_string()),
+                    ("bucket", bucket.to_string()),
+                ],
+                account.get_send_size_histogram(i * SEND_SIZE_BUCKET_COUNT + bucket) as u64,
+            );
+        }
+    }
+
+    Some(vec![
+        per_token(
+            "elusiv_volume_in_total",
+            "Total amount stored into the pool",
+            MetricKind::Counter,
+            &|i| account.get_volume_in(i),
+        ),
+        per_token(
+            "elusiv_volume_out_total",
+            "Total amount sent out of the pool",
+            MetricKind::Counter,
+            &|i| account.get_volume_out(i),
+        ),
+        per_token(
+            "elusiv_dust_swept_total",
+            "Total amount of dust swept from the pool into the fee collector",
+            MetricKind::Counter,
+            &|i| account.get_dust_swept(i),
+        ),
+        per_token(
+            "elusiv_active_notes",
+            "Estimate of unspent notes",
+            MetricKind::Gauge,
+            &|i| account.get_active_notes(i),
+        ),
+        per_token(
+            "elusiv_pool_liability",
+            "Estimate of the amount owed to the holders of unspent notes",
+            MetricKind::Gauge,
+            &|i| account.pool_liability(i as u16),
+        ),
+        send_sizes,
+    ])
+}
+
+/// Decodes the queues, the [`MetricsAccount`] and the `WardenAttestationAccount`s into [`MetricFamily`]s
+///
+/// # Note
+///
+/// Attestations not matching the PDA of their warden are skipped.
+pub fn collect_metrics(
+    accounts: &[ProgramAccountSnapshot],
+    warden_attestations: &[(Pubkey, ProgramAccountSnapshot)],
+) -> Vec<MetricFamily> {
+    let mut families = Vec::new();
+
+    if let Some(mut data) = snapshot_data(accounts, &CommitmentQueueAccount::find(None).0) {
+        families.extend(commitment_queue_metrics(&mut data).unwrap_or_default());
+    }
+
+    if let Some(mut data) = snapshot_data(accounts, &MetadataQueueAccount::find(None).0) {
+        families.extend(metadata_queue_metrics(&mut data).unwrap_or_default());
+    }
+
+    if let Some(mut data) = snapshot_data(accounts, &MetricsAccount::find(None).0) {
+        families.extend(protocol_metrics(&mut data).unwrap_or_default());
+    }
+
+    let attestations = warden_attestations
+        .iter()
+        .filter(|(warden, account)| warden_attestation_account(warden) == account.pubkey)
+        .fold(
+            MetricFamily::new(
+                "elusiv_warden_attestation_level",
+                "Attestation level of a warden in the Elusiv Warden Network",
+                MetricKind::Gauge,
+            ),
+            |family, (warden, account)| {
+                family.with_sample(
+                    vec![("warden", warden.to_string())],
+                    warden_attestation_level(&account.data) as u64,
+                )
+            },
+        );
+    if !attestations.samples.is_empty() {
+        families.push(attestations);
+    }
+
+    families
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serializes `families` in the Prometheus text exposition format
+pub fn encode_text(families: &[MetricFamily]) -> String {
+    let mut text = String::new();
+
+    for family in families {
+        let _ = writeln!(text, "# HELP {} {}", family.name, family.help);
+        let _ = writeln!(text, "# TYPE {} {}", family.name, family.kind.as_str());
+
+        for sample in &family.samples {
+            text.push_str(family.name);
+
+            if !sample.labels.is_empty() {
+                let labels: Vec<_> = sample
+                    .labels
+                    .iter()
+                    .map(|(key, value)| format!("{}=\"{}\"", key, escape_label_value(value)))
+                    .collect();
+                let _ = write!(text, "{{{}}}", labels.join(","));
+            }
+
+            let _ = writeln!(text, " {}", sample.value);
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::CommitmentHashRequest;
+    use crate::state::commitment::CommitmentQueue;
+    use crate::state::program_account::{PDAAccountData, SizedAccount};
+    use crate::token::{LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+    use crate::types::U256;
+
+    fn snapshot<A: PDAAccount>(data: Vec<u8>) -> ProgramAccountSnapshot {
+        ProgramAccountSnapshot {
+            pubkey: A::find(None).0,
+            lamports: 1_000_000,
+            data,
+        }
+    }
+
+    fn sample_value(families: &[MetricFamily], name: &str, labels: &[(&str, &str)]) -> u64 {
+        families
+            .iter()
+            .find(|family| family.name == name)
+            .unwrap()
+            .samples
+            .iter()
+            .find(|sample| {
+                labels.iter().all(|label| {
+                    sample
+                        .labels
+                        .iter()
+                        .any(|(k, v)| *k == label.0 && v == label.1)
+                })
+            })
+            .unwrap()
+            .value
+    }
+
+    #[test]
+    fn test_collect_metrics() {
+        let mut queue_data = vec![0; <CommitmentQueueAccount as SizedAccount>::SIZE];
+        {
+            let mut account = CommitmentQueueAccount::new(&mut queue_data).unwrap();
+            account.set_in_flight_cap(&5);
+            let mut queue = CommitmentQueue::new(&mut account);
+            queue
+                .enqueue_with_priority(CommitmentHashRequest::default(), &U256::default(), 10)
+                .unwrap();
+        }
+
+        let mut metrics_data = vec![0; MetricsAccount::SIZE];
+        {
+            let mut account = MetricsAccount::new(&mut metrics_data).unwrap();
+            account.record_store(LAMPORTS_TOKEN_ID, 100).unwrap();
+            account.record_store(USDC_TOKEN_ID, 7).unwrap();
+        }
+
+        let warden = Pubkey::new_unique();
+        let mut attestation_data = vec![0; PDAAccountData::SIZE + 5];
+        attestation_data[PDAAccountData::SIZE + 4] = 2;
+
+        let accounts = vec![
+            snapshot::<CommitmentQueueAccount>(queue_data),
+            snapshot::<MetricsAccount>(metrics_data),
+        ];
+        let attestations = vec![
+            (
+                warden,
+                ProgramAccountSnapshot {
+                    pubkey: warden_attestation_account(&warden),
+                    lamports: 0,
+                    data: attestation_data.clone(),
+                },
+            ),
+            (
+                Pubkey::new_unique(),
+                ProgramAccountSnapshot {
+                    pubkey: Pubkey::new_unique(),
+                    lamports: 0,
+                    data: attestation_data,
+                },
+            ),
+        ];
+
+        let families = collect_metrics(&accounts, &attestations);
+
+        // Missing metadata queue
+        assert!(families
+            .iter()
+            .all(|family| family.name != "elusiv_metadata_queue_len"));
+
+        assert_eq!(
+            sample_value(&families, "elusiv_commitment_queue_len", &[]),
+            1
+        );
+        assert_eq!(
+            sample_value(&families, "elusiv_commitment_queue_in_flight_cap", &[]),
+            5
+        );
+        assert_eq!(
+            sample_value(&families, "elusiv_commitment_queue_active_fee_payers", &[]),
+            1
+        );
+        assert_eq!(
+            sample_value(
+                &families,
+                "elusiv_commitment_queue_head_priority_lamports",
+                &[]
+            ),
+            10
+        );
+
+        let lamports = TOKENS[LAMPORTS_TOKEN_ID as usize].ident;
+        let usdc = TOKENS[USDC_TOKEN_ID as usize].ident;
+        assert_eq!(
+            sample_value(&families, "elusiv_volume_in_total", &[("token", lamports)]),
+            100
+        );
+        assert_eq!(
+            sample_value(&families, "elusiv_active_notes", &[("token", usdc)]),
+            1
+        );
+
+        let attestations = families
+            .iter()
+            .find(|family| family.name == "elusiv_warden_attestation_level")
+            .unwrap();
+        assert_eq!(attestations.samples.len(), 1);
+        assert_eq!(
+            sample_value(
+                &families,
+                "elusiv_warden_attestation_level",
+                &[("warden", &warden.to_string())]
+            ),
+            2
+        );
+    }
+
+    #[test]
+    fn test_encode_text() {
+        let families = vec![
+            MetricFamily::new("a", "Help a", MetricKind::Gauge).with_sample(vec![], 1),
+            MetricFamily::new("b_total", "Help b", MetricKind::Counter)
+                .with_sample(vec![("token", String::from("SOL"))], 2)
+                .with_sample(
+                    vec![
+                        ("token", String::from("x\"y")),
+                        ("bucket", String::from("0")),
+                    ],
+                    3,
+                ),
+        ];
+
+        assert_eq!(
+            encode_text(&families),
+            "# HELP a Help a\n\
+             # TYPE a gauge\n\
+             a 1\n\
+             # HELP b_total Help b\n\
+             # TYPE b_total counter\n\
+             b_total{token=\"SOL\"} 2\n\
+             b_total{token=\"x\\\"y\",bucket=\"0\"} 3\n"
+        );
+    }
+}
@@ -1,29 +1,58 @@
 use super::utils::*;
 use crate::bytes::{is_zero, BorshSerDeSized, ElusivOption};
-use crate::commitment::DEFAULT_COMMITMENT_BATCHING_RATE;
+use crate::commitment::{DEFAULT_COMMITMENT_BATCHING_RATE, MAX_COMMITMENT_BATCHING_RATE};
 use crate::error::ElusivError;
+use crate::event::ElusivEvent;
 use crate::macros::*;
+use crate::proof::vkey::VerifyingKey;
+use crate::state::circuit_registry::CircuitRegistryAccount;
 use crate::state::commitment::{
-    BaseCommitmentBufferAccount, CommitmentBufferAccount, CommitmentHashingAccount,
-    CommitmentQueue, CommitmentQueueAccount,
+    BaseCommitmentBufferAccount, BaseCommitmentHashingSlotsAccount, CommitmentBufferAccount,
+    CommitmentHashingAccount, CommitmentQueue, CommitmentQueueAccount, COMMITMENT_QUEUE_GROWTH,
+    MAX_COMMITMENT_QUEUE_LEN,
 };
-use crate::state::metadata::{MetadataAccount, MetadataQueueAccount};
+use crate::state::deployment::{compiled_features, crate_version, git_hash, DeploymentInfoAccount};
+use crate::state::history::VerificationHistoryAccount;
+use crate::state::metadata::{MetadataAccount, MetadataQueue, MetadataQueueAccount};
+use crate::state::metrics::MetricsAccount;
+#[cfg(not(feature = "mainnet"))]
+use crate::state::migration::{MigrationManifestAccount, MAX_MIGRATION_CHUNK_SIZE};
 use crate::state::queue::RingQueue;
+use crate::state::rollover::{
+    RolloverStateAccount, ROLLOVER_STEP_FREEZE_CLOSED_MT, ROLLOVER_STEP_INIT_STORAGE,
+    ROLLOVER_STEP_OPEN_NULLIFIER_ACCOUNT, ROLLOVER_STEP_REPOINT_GOVERNOR,
+};
 use crate::state::{
     fee::{FeeAccount, ProgramFee},
-    governor::{FeeCollectorAccount, GovernorAccount, PoolAccount},
-    nullifier::{NullifierAccount, NullifierChildAccount},
+    governor::{
+        ApaEnforcement, FeatureFlagsAccount, FeeCollectorAccount, GovernorAccount, PoolAccount,
+        QuarantineAccount, TokenRegistryAccount, UpgradeGateAccount, MAX_METADATA_CIPHERTEXT_LEN,
+        MAX_TOKEN_FEE_SPREAD_BPS, METADATA_SIZE_CLASS_COUNT, MIN_HASHING_FAIR_SHARE_BPS,
+        MIN_METADATA_FEE_MULTIPLIER_BPS, PROTOCOL_VERSION,
+    },
+    nullifier::{split_nullifier_child_data, NullifierAccount, NullifierChildAccount},
+    proof::VerifierRAMAccount,
     queue::Queue,
-    storage::{StorageAccount, MT_COMMITMENT_COUNT},
+    recipient::TOKEN_COUNT,
+    storage::{
+        mt_index_shard, sharded_mt_index, StorageAccount, TreeConfigAccount, MIN_MT_HEIGHT,
+        MT_HEIGHT, MT_SHARD_COUNT,
+    },
+    vkey::VKeyAccount,
+    warden_nonce::WardenNonceAccount,
+    warden_usage::WardenUsageAccount,
 };
+use crate::token::{elusiv_token, unpack_token_state, Token};
+use crate::types::U256;
 use crate::{bytes::usize_as_u32_safe, map::ElusivMap};
+use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_types::{
-    split_child_account_data_mut, ChildAccount, ChildAccountConfig, ParentAccount, SizedAccount,
-    UnverifiedAccountInfo,
+    split_child_account_data_mut, ChildAccount, ChildAccountConfig, ChildAccountTag, PDAAccount,
+    ParentAccount, SizedAccount, UnverifiedAccountInfo,
 };
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError, rent::Rent,
-    sysvar::Sysvar,
+    account_info::AccountInfo, bpf_loader_upgradeable, entrypoint::ProgramResult,
+    program_error::ProgramError, pubkey::Pubkey, rent::Rent, sysvar::Sysvar,
 };
 
 /// Opens one single instance [`elusiv_types::PDAAccount`], as long this PDA does not already exist
@@ -91,11 +120,92 @@ pub fn open_nullifier_account<'b>(
     )
 }
 
+/// Opens the [`StorageAccount`], queues, [`CommitmentHashingAccount`] and [`MetadataAccount`] of an additional MT-shard
+///
+/// # Note
+///
+/// The first MT of the shard has the MT-index `sharded_mt_index(mt_shard, 0)`, its [`NullifierAccount`] needs to be opened separately.
+pub fn open_mt_shard_accounts<'a, 'b>(
+    payer: &AccountInfo<'b>,
+    storage_account: UnverifiedAccountInfo<'a, 'b>,
+    commitment_queue_account: UnverifiedAccountInfo<'a, 'b>,
+    commitment_hashing_account: UnverifiedAccountInfo<'a, 'b>,
+    metadata_queue: UnverifiedAccountInfo<'a, 'b>,
+    metadata_account: UnverifiedAccountInfo<'a, 'b>,
+
+    mt_shard: u32,
+) -> ProgramResult {
+    guard!(
+        mt_shard > 0 && mt_shard < MT_SHARD_COUNT,
+        ElusivError::InvalidMtShard
+    );
+
+    open_pda_account_with_offset::<StorageAccount>(
+        &crate::id(),
+        payer,
+        storage_account.get_unsafe(),
+        mt_shard,
+        None,
+    )?;
+    open_pda_account_with_offset::<CommitmentQueueAccount>(
+        &crate::id(),
+        payer,
+        commitment_queue_account.get_unsafe(),
+        mt_shard,
+        None,
+    )?;
+    open_pda_account_with_offset::<CommitmentHashingAccount>(
+        &crate::id(),
+        payer,
+        commitment_hashing_account.get_unsafe(),
+        mt_shard,
+        None,
+    )?;
+    open_pda_account_with_offset::<MetadataQueueAccount>(
+        &crate::id(),
+        payer,
+        metadata_queue.get_unsafe(),
+        mt_shard,
+        None,
+    )?;
+    open_pda_account_with_offset::<MetadataAccount>(
+        &crate::id(),
+        payer,
+        metadata_account.get_unsafe(),
+        mt_shard,
+        None,
+    )?;
+
+    let storage_account = storage_account.get_unsafe();
+    pda_account!(mut storage_account, StorageAccount, storage_account);
+    storage_account.set_mt_shard(&mt_shard);
+    storage_account.set_trees_count(&sharded_mt_index(mt_shard, 0));
+
+    Ok(())
+}
+
+/// Opens a [`VerifierRAMAccount`] for the pool of verifier RAMs
+pub fn open_verifier_ram_account<'b>(
+    payer: &AccountInfo<'b>,
+    ram_account: UnverifiedAccountInfo<'_, 'b>,
+
+    ram_account_index: u32,
+) -> ProgramResult {
+    open_pda_account_with_offset::<VerifierRAMAccount>(
+        &crate::id(),
+        payer,
+        ram_account.get_unsafe(),
+        ram_account_index,
+        None,
+    )
+}
+
 /// Enables the supplied child-account for the [`StorageAccount`]
 pub fn enable_storage_child_account(
     storage_account: &mut StorageAccount,
     child_account: &AccountInfo,
 
+    _mt_shard: u32,
     child_index: u32,
 ) -> ProgramResult {
     // Note: we don't zero-check these accounts, since we will never access data that has not been set by the program
@@ -131,10 +241,8 @@ pub fn enable_nullifier_child_account(
         None,
     )?;
 
-    // Set map size to zero
-    reset_map_child_account::<NullifierChildAccount>(child_account)?;
-
-    Ok(())
+    // Set map size to zero and clear the bloom filter
+    reset_nullifier_child_account(child_account)
 }
 
 /// Enables the supplied child-account for the [`MetadataAccount`]
@@ -142,6 +250,7 @@ pub fn enable_metadata_child_account(
     metadata_account: &mut MetadataAccount,
     child_account: &AccountInfo,
 
+    _mt_shard: u32,
     child_index: u32,
 ) -> ProgramResult {
     // Note: we don't zero-check these accounts, since we will never access data that has not been set by the program
@@ -154,17 +263,77 @@ pub fn enable_metadata_child_account(
     )
 }
 
+/// The parent-accounts that [`ChildAccount`]s can be registered for
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Debug, Clone, Copy)]
+pub enum SubAccountKind {
+    Storage,
+    Nullifier { mt_index: u32 },
+    Metadata,
+}
+
+/// Registers the supplied child-account for the parent-account of `kind`
+///
+/// # Note
+///
+/// - In contrast to the `enable_*_child_account` instructions, the child-account's [`ChildAccountConfig`] is tagged with the parent-account and `child_index`.
+/// - Size, ownership, rent-exemption and usage are validated and the pubkey is recorded in the parent-account in the same instruction.
+pub fn register_sub_account(
+    parent_account: &AccountInfo,
+    child_account: &AccountInfo,
+
+    kind: SubAccountKind,
+    child_index: u32,
+) -> ProgramResult {
+    match kind {
+        SubAccountKind::Storage => {
+            StorageAccount::verify_account(parent_account, None)?;
+            pda_account!(mut storage_account, StorageAccount, parent_account);
+            register_child_account(
+                &mut storage_account,
+                parent_account.key,
+                child_account,
+                child_index,
+            )
+        }
+        SubAccountKind::Nullifier { mt_index } => {
+            NullifierAccount::verify_account(parent_account, Some(mt_index))?;
+            pda_account!(mut nullifier_account, NullifierAccount, parent_account);
+            register_child_account(
+                &mut nullifier_account,
+                parent_account.key,
+                child_account,
+                child_index,
+            )?;
+
+            // Set map size to zero and clear the bloom filter
+            reset_nullifier_child_account(child_account)
+        }
+        SubAccountKind::Metadata => {
+            MetadataAccount::verify_account(parent_account, None)?;
+            pda_account!(mut metadata_account, MetadataAccount, parent_account);
+            register_child_account(
+                &mut metadata_account,
+                parent_account.key,
+                child_account,
+                child_index,
+            )
+        }
+    }
+}
+
 /// Closes the active MT and activates the next one
 ///
 /// # Notes
 ///
-/// There are two scenarios in which this is required/allowed:
+/// - There are two scenarios in which this is required/allowed:
 ///     1. the active MT is full
 ///     2. the active MT is not full but the remaining places in the MT are < than the batching rate of the next commitment in the commitment queue
+/// - The next MT uses the height configured in the [`TreeConfigAccount`].
 pub fn reset_active_merkle_tree(
     storage_account: &mut StorageAccount,
     queue: &mut CommitmentQueueAccount,
     active_nullifier_account: &mut NullifierAccount,
+    tree_config: &TreeConfigAccount,
 
     active_merkle_tree_index: u32,
 ) -> ProgramResult {
@@ -182,10 +351,159 @@ pub fn reset_active_merkle_tree(
     storage_account.set_trees_count(&(active_merkle_tree_index.checked_add(1).ok_or(MATH_ERR)?));
     active_nullifier_account.set_root(&storage_account.get_root()?);
     storage_account.reset();
+    storage_account.set_height(&tree_config.next_mt_height());
 
     Ok(())
 }
 
+pub fn open_rollover_state_account<'b>(
+    payer: &AccountInfo<'b>,
+    rollover_state_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<RolloverStateAccount>(
+        &crate::id(),
+        payer,
+        rollover_state_account.get_unsafe(),
+        None,
+    )
+}
+
+/// Rollover step 1: freezes the root of the full active MT in its [`NullifierAccount`]
+///
+/// # Note
+///
+/// Each rollover step is a no-op once completed, so a rollover can be resumed from any step.
+pub fn rollover_freeze_closed_merkle_tree(
+    storage_account: &StorageAccount,
+    queue: &mut CommitmentQueueAccount,
+    closed_nullifier_account: &mut NullifierAccount,
+    rollover_state: &mut RolloverStateAccount,
+
+    closed_mt_index: u32,
+) -> ProgramResult {
+    if rollover_state.is_step_completed(closed_mt_index, ROLLOVER_STEP_FREEZE_CLOSED_MT) {
+        return Ok(());
+    }
+
+    guard!(
+        storage_account.get_trees_count() == closed_mt_index,
+        ElusivError::InvalidInstructionData
+    );
+
+    let queue = CommitmentQueue::new(queue);
+    guard!(
+        is_mt_full(storage_account, &queue)?,
+        ElusivError::MerkleTreeIsNotFullYet
+    );
+
+    rollover_state.begin(closed_mt_index)?;
+    closed_nullifier_account.set_root(&storage_account.get_root()?);
+    rollover_state.complete_step(closed_mt_index, ROLLOVER_STEP_FREEZE_CLOSED_MT)
+}
+
+/// Rollover step 2: opens the [`NullifierAccount`] of the next MT (unless it has already been opened)
+pub fn rollover_open_nullifier_account<'b>(
+    payer: &AccountInfo<'b>,
+    next_nullifier_account: UnverifiedAccountInfo<'_, 'b>,
+    rollover_state: &mut RolloverStateAccount,
+
+    closed_mt_index: u32,
+) -> ProgramResult {
+    if rollover_state.is_step_completed(closed_mt_index, ROLLOVER_STEP_OPEN_NULLIFIER_ACCOUNT) {
+        return Ok(());
+    }
+
+    guard!(
+        rollover_state.is_step_completed(closed_mt_index, ROLLOVER_STEP_FREEZE_CLOSED_MT),
+        ElusivError::InvalidAccountState
+    );
+
+    let next_mt_index = closed_mt_index.checked_add(1).ok_or(MATH_ERR)?;
+    let next_nullifier_account = next_nullifier_account.get_unsafe();
+    if next_nullifier_account.lamports() == 0 {
+        open_pda_account_with_offset::<NullifierAccount>(
+            &crate::id(),
+            payer,
+            next_nullifier_account,
+            next_mt_index,
+            None,
+        )?;
+    } else {
+        guard!(
+            *next_nullifier_account.owner == crate::id(),
+            ElusivError::InvalidAccount
+        );
+        NullifierAccount::verify_account(next_nullifier_account, Some(next_mt_index))?;
+    }
+
+    rollover_state.complete_step(closed_mt_index, ROLLOVER_STEP_OPEN_NULLIFIER_ACCOUNT)
+}
+
+/// Rollover step 3: resets the [`StorageAccount`] for the next MT
+///
+/// # Note
+///
+/// If the MT has already been reset (e.g. with [`reset_active_merkle_tree`]) the step is only marked as completed.
+pub fn rollover_init_storage(
+    storage_account: &mut StorageAccount,
+    tree_config: &TreeConfigAccount,
+    rollover_state: &mut RolloverStateAccount,
+
+    closed_mt_index: u32,
+) -> ProgramResult {
+    if rollover_state.is_step_completed(closed_mt_index, ROLLOVER_STEP_INIT_STORAGE) {
+        return Ok(());
+    }
+
+    guard!(
+        rollover_state.is_step_completed(closed_mt_index, ROLLOVER_STEP_FREEZE_CLOSED_MT),
+        ElusivError::InvalidAccountState
+    );
+
+    let next_mt_index = closed_mt_index.checked_add(1).ok_or(MATH_ERR)?;
+    if storage_account.get_trees_count() != next_mt_index {
+        guard!(
+            storage_account.get_trees_count() == closed_mt_index,
+            ElusivError::InvalidAccountState
+        );
+
+        storage_account.set_trees_count(&next_mt_index);
+        storage_account.reset();
+        storage_account.set_height(&tree_config.next_mt_height());
+    }
+
+    rollover_state.complete_step(closed_mt_index, ROLLOVER_STEP_INIT_STORAGE)
+}
+
+/// Rollover step 4: re-points the [`GovernorAccount`] to the next MT (completing the rollover)
+///
+/// # Note
+///
+/// The `active_mt_index` of the [`GovernorAccount`] only tracks MT-shard `0`, rollovers of other MT-shards are only completed.
+pub fn rollover_repoint_governor(
+    governor: &mut GovernorAccount,
+    rollover_state: &mut RolloverStateAccount,
+
+    closed_mt_index: u32,
+) -> ProgramResult {
+    if rollover_state.is_step_completed(closed_mt_index, ROLLOVER_STEP_REPOINT_GOVERNOR) {
+        return Ok(());
+    }
+
+    guard!(
+        rollover_state.is_step_completed(
+            closed_mt_index,
+            ROLLOVER_STEP_OPEN_NULLIFIER_ACCOUNT | ROLLOVER_STEP_INIT_STORAGE
+        ),
+        ElusivError::InvalidAccountState
+    );
+
+    if mt_index_shard(closed_mt_index) == 0 {
+        governor.set_active_mt_index(&closed_mt_index.checked_add(1).ok_or(MATH_ERR)?);
+    }
+    rollover_state.complete_step(closed_mt_index, ROLLOVER_STEP_REPOINT_GOVERNOR)
+}
+
 pub fn create_new_accounts_v1<'a, 'b>(
     payer: &AccountInfo<'b>,
     commitment_buffer_account: UnverifiedAccountInfo<'a, 'b>,
@@ -249,7 +567,7 @@ fn is_mt_full(
 
     let commitments_count = storage_account.get_next_commitment_ptr() as usize;
     let queue_len = queue.next_batch()?.0.len();
-    if commitments_count + queue_len >= MT_COMMITMENT_COUNT {
+    if commitments_count + queue_len >= storage_account.commitment_capacity() {
         return Ok(true);
     }
 
@@ -294,6 +612,41 @@ pub fn setup_governor_account<'b>(
     Ok(())
 }
 
+/// Opens the [`QuarantineAccount`] (escrow for payouts denied by their recipients)
+pub fn open_quarantine_account<'b>(
+    payer: &AccountInfo<'b>,
+    quarantine_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<QuarantineAccount>(
+        &crate::id(),
+        payer,
+        quarantine_account.get_unsafe(),
+        None,
+    )
+}
+
+/// Sets the maximum number of commitments a single fee-payer can have in the [`CommitmentQueueAccount`]
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - a cap of `0` disables the in-flight limit
+pub fn set_commitment_queue_in_flight_cap(
+    authority: &AccountInfo,
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+
+    in_flight_cap: u32,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+
+    commitment_hash_queue.set_in_flight_cap(&in_flight_cap);
+
+    Ok(())
+}
+
 /// Changes the state of the [`GovernorAccount`]
 pub fn upgrade_governor_state(
     _authority: &AccountInfo,
@@ -343,262 +696,2461 @@ pub fn init_new_fee_version<'b>(
     Ok(())
 }
 
-/// Closes a program owned account in devnet and localhost
+/// Schedules the next fee-version, which replaces the current one at `activation_slot`
 ///
 /// # Note
 ///
-/// - `signer` needs to be the program's keypair
-/// - `recipient` receives the accounts Lamports
-#[cfg(not(feature = "mainnet"))]
-pub fn close_program_account<'a>(
-    signer: &AccountInfo,
-    recipient: &AccountInfo<'a>,
-    program_account: &AccountInfo<'a>,
+/// - `authority` needs to be the program's keypair
+/// - there can only be one scheduled fee-version at a time
+#[allow(clippy::too_many_arguments)]
+pub fn schedule_fee_version<'b>(
+    payer: &AccountInfo<'b>,
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+    mut new_fee_account: UnverifiedAccountInfo<'_, 'b>,
+
+    fee_version: u32,
+    program_fee: ProgramFee,
+    activation_slot: u64,
 ) -> ProgramResult {
-    assert!(!cfg!(feature = "mainnet"));
-    assert_eq!(*signer.key, crate::ID);
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
 
-    elusiv_utils::close_account(recipient, program_account)
-}
+    let slot = current_slot()?;
+    governor.activate_scheduled_fee_version(slot);
 
-/// Verifies a single user-supplied [`ChildAccount`] and then saves it's pubkey in the `parent_account`
-///
-/// # Note
-///
-/// If `size` is manually supplied (not the default [`C::SIZE`] is used) [`elusiv_types::ChildAccountConfig::SIZE`] needs to be contained in the size.
-pub fn setup_child_account<'a, 'b, 't, P: ParentAccount<'a, 'b, 't>>(
-    parent_account: &mut P,
-    child_account: &AccountInfo,
-    child_index: usize,
-    check_zeroness: bool,
-    size: Option<usize>,
-) -> ProgramResult {
-    if parent_account.get_child_pubkey(child_index).is_some() {
-        return Err(ElusivError::ChildAccountAlreadyExists.into());
-    }
+    guard!(
+        !governor.has_scheduled_fee_version(),
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        fee_version == governor.get_fee_version() + 1,
+        ElusivError::InvalidFeeVersion
+    );
+    guard!(activation_slot > slot, ElusivError::InvalidInstructionData);
+    guard!(program_fee.is_valid(), ElusivError::InvalidInstructionData);
 
-    verify_extern_data_account(
-        child_account,
-        size.unwrap_or(<P::Child as SizedAccount>::SIZE),
-        check_zeroness,
+    open_pda_account_with_offset::<FeeAccount>(
+        &crate::id(),
+        payer,
+        new_fee_account.get_unsafe_and_set_is_verified(),
+        fee_version,
+        None,
     )?;
-    parent_account.set_child_pubkey(child_index, ElusivOption::Some(*child_account.key));
-    P::Child::try_start_using_account(child_account)?;
+
+    pda_account!(mut fee_account, FeeAccount, new_fee_account.get_safe()?);
+    fee_account.set_program_fee(&program_fee);
+    fee_account.set_activation_slot(&activation_slot);
+
+    governor.set_scheduled_fee_version(&fee_version);
+    governor.set_scheduled_program_fee(&program_fee);
+    governor.set_scheduled_fee_activation_slot(&activation_slot);
 
     Ok(())
 }
 
-fn reset_map_child_account<C: ChildAccount>(child_account: &AccountInfo) -> ProgramResult {
-    let data = &mut child_account.data.borrow_mut()[..];
-    let (_, inner_data) = split_child_account_data_mut(data)?;
-    let mut map = ElusivMap::<(), (), 1>::new(&mut inner_data[..ElusivMap::<(), (), 1>::SIZE]);
-    map.reset();
+/// Opens the [`UpgradeGateAccount`]
+pub fn open_upgrade_gate_account<'b>(
+    payer: &AccountInfo<'b>,
+    upgrade_gate_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<UpgradeGateAccount>(
+        &crate::id(),
+        payer,
+        upgrade_gate_account.get_unsafe(),
+        None,
+    )
+}
 
-    Ok(())
+/// Opens the [`FeatureFlagsAccount`]
+pub fn open_feature_flags_account<'b>(
+    payer: &AccountInfo<'b>,
+    feature_flags_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<FeatureFlagsAccount>(
+        &crate::id(),
+        payer,
+        feature_flags_account.get_unsafe(),
+        None,
+    )
 }
 
-/// Verifies that an account with `data_len` > 10 KiB (non PDA) is formatted correctly
-fn verify_extern_data_account(
-    account: &AccountInfo,
-    data_len: usize,
-    check_zeroness: bool,
+/// Rejects all instructions with `#[deprecated_since(v)]` and `v <= version`
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - a version of `0` only logs deprecation warnings
+pub fn set_deprecation_enforcement_version(
+    authority: &AccountInfo,
+    feature_flags: &mut FeatureFlagsAccount,
+
+    version: u32,
 ) -> ProgramResult {
     guard!(
-        account.data_len() == data_len,
-        ProgramError::InvalidAccountData
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
     );
 
+    feature_flags.set_deprecation_enforcement_version(&version);
+
+    Ok(())
+}
+
+/// Approves the program-data hash of an upgrade, which can be deployed after [`crate::state::governor::UPGRADE_TIMELOCK_SLOTS`]
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - a new approval replaces a pending one
+pub fn approve_upgrade(
+    authority: &AccountInfo,
+    upgrade_gate: &mut UpgradeGateAccount,
+
+    program_data_hash: U256,
+) -> ProgramResult {
     guard!(
-        data_len >= ChildAccountConfig::SIZE,
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+
+    upgrade_gate.approve(&program_data_hash, current_slot()?)
+}
+
+/// Records the deployed program as the active program, if its program-data hash has been approved
+pub fn confirm_upgrade(
+    upgrade_gate: &mut UpgradeGateAccount,
+    program_data: &AccountInfo,
+) -> ProgramResult {
+    let (program_data_hash, deployment_slot) = program_data_hash(program_data)?;
+    upgrade_gate.confirm(&program_data_hash, deployment_slot)?;
+
+    ElusivEvent::UpgradeConfirmed {
+        program_data_hash,
+        deployment_slot,
+        upgrade_count: upgrade_gate.get_upgrade_count(),
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Size of the serialized `UpgradeableLoaderState::ProgramData` header (tag, slot, upgrade-authority option)
+const PROGRAM_DATA_METADATA_SIZE: usize = 4 + 8 + 1 + 32;
+const PROGRAM_DATA_TAG: u32 = 3;
+
+/// Returns the hash of the program's executable data and the slot of its deployment
+fn program_data_hash(program_data: &AccountInfo) -> Result<(U256, u64), ProgramError> {
+    let (program_data_key, _) =
+        Pubkey::find_program_address(&[crate::ID.as_ref()], &bpf_loader_upgradeable::id());
+    guard!(
+        *program_data.key == program_data_key,
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        *program_data.owner == bpf_loader_upgradeable::id(),
+        ElusivError::InvalidAccount
+    );
+
+    let data = &program_data.data.borrow()[..];
+    guard!(
+        data.len() >= PROGRAM_DATA_METADATA_SIZE,
         ProgramError::InvalidAccountData
     );
+    guard!(
+        data[..4] == PROGRAM_DATA_TAG.to_le_bytes(),
+        ProgramError::InvalidAccountData
+    );
+    let deployment_slot = u64::from_le_bytes(data[4..12].try_into().unwrap());
+    let hash = solana_program::hash::hash(&data[PROGRAM_DATA_METADATA_SIZE..]);
 
-    if check_zeroness {
-        guard!(
-            is_zero(&account.data.borrow()[..]),
-            ProgramError::InvalidAccountData
+    Ok((hash.to_bytes(), deployment_slot))
+}
+
+pub fn open_token_registry_account<'b>(
+    payer: &AccountInfo<'b>,
+    token_registry_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<TokenRegistryAccount>(
+        &crate::id(),
+        payer,
+        token_registry_account.get_unsafe(),
+        None,
+    )
+}
+
+pub fn open_metrics_account<'b>(
+    payer: &AccountInfo<'b>,
+    metrics_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<MetricsAccount>(
+        &crate::id(),
+        payer,
+        metrics_account.get_unsafe(),
+        None,
+    )
+}
+
+/// Opens the [`BaseCommitmentHashingSlotsAccount`] (see [`crate::processor::acquire_hashing_slot`])
+pub fn open_base_commitment_hashing_slots_account<'b>(
+    payer: &AccountInfo<'b>,
+    hashing_slots_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<BaseCommitmentHashingSlotsAccount>(
+        &crate::id(),
+        payer,
+        hashing_slots_account.get_unsafe(),
+        None,
+    )
+}
+
+pub fn open_verification_history_account<'b>(
+    payer: &AccountInfo<'b>,
+    verification_history_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<VerificationHistoryAccount>(
+        &crate::id(),
+        payer,
+        verification_history_account.get_unsafe(),
+        None,
+    )
+}
+
+pub fn open_tree_config_account<'b>(
+    payer: &AccountInfo<'b>,
+    tree_config_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<TreeConfigAccount>(
+        &crate::id(),
+        payer,
+        tree_config_account.get_unsafe(),
+        None,
+    )
+}
+
+pub fn open_deployment_info_account<'b>(
+    payer: &AccountInfo<'b>,
+    deployment_info_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<DeploymentInfoAccount>(
+        &crate::id(),
+        payer,
+        deployment_info_account.get_unsafe(),
+        None,
+    )
+}
+
+pub fn open_circuit_registry_account<'b>(
+    payer: &AccountInfo<'b>,
+    circuit_registry: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<CircuitRegistryAccount>(
+        &crate::id(),
+        payer,
+        circuit_registry.get_unsafe(),
+        None,
+    )
+}
+
+/// Opens the [`WardenNonceAccount`] of `warden` (see [`consume_warden_nonce`])
+pub fn open_warden_nonce_account<'a>(
+    warden: &AccountInfo<'a>,
+    warden_nonce_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    open_pda_account_with_associated_pubkey::<WardenNonceAccount>(
+        &crate::id(),
+        warden,
+        warden_nonce_account,
+        warden.key,
+        None,
+        None,
+    )
+}
+
+/// Opens the [`WardenUsageAccount`] of `warden` (see [`record_warden_usage`])
+pub fn open_warden_usage_account<'a>(
+    warden: &AccountInfo<'a>,
+    warden_usage_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    open_pda_account_with_associated_pubkey::<WardenUsageAccount>(
+        &crate::id(),
+        warden,
+        warden_usage_account,
+        warden.key,
+        None,
+        None,
+    )
+}
+
+/// Publishes the program's version, git hash and features and the hash of the vkey with `vkey_id` in the [`DeploymentInfoAccount`]
+///
+/// # Note
+///
+/// - can be called by anyone (all values are either embedded in the program binary or read from the [`VKeyAccount`])
+/// - called once per vkey after each deployment (or vkey version upgrade)
+pub fn publish_deployment_info(
+    deployment_info: &mut DeploymentInfoAccount,
+    vkey_account: &VKeyAccount,
+
+    vkey_id: u32,
+) -> ProgramResult {
+    guard!(
+        vkey_id < crate::processor::MAX_NUMBER_OF_VKEYS,
+        ElusivError::InvalidInstructionData
+    );
+
+    deployment_info.set_version(&crate_version());
+    deployment_info.set_git_hash(&git_hash());
+    deployment_info.set_features(&compiled_features());
+    deployment_info.set_protocol_version(&PROTOCOL_VERSION);
+
+    let vkey_hash = if vkey_account.is_setup() {
+        vkey_account.execute_on_child_account(0, |data| {
+            VerifyingKey::new(data, vkey_account.get_public_inputs_count() as usize)
+                .map(|vkey| vkey.key_hash())
+                .ok_or(ElusivError::InvalidAccountState)
+        })??
+    } else {
+        [0; 32]
+    };
+    deployment_info.set_vkey_hashes(vkey_id as usize, &vkey_hash);
+    deployment_info.set_vkey_versions(vkey_id as usize, &vkey_account.get_version());
+
+    Ok(())
+}
+
+/// Sets the height of the MTs activated after the active MT
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - `height` is bounded by [`MIN_MT_HEIGHT`] and [`MT_HEIGHT`] (the storage and the circuits are sized for [`MT_HEIGHT`])
+pub fn set_tree_height(
+    authority: &AccountInfo,
+    tree_config: &mut TreeConfigAccount,
+
+    height: u32,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        (MIN_MT_HEIGHT..=MT_HEIGHT).contains(&height),
+        ElusivError::InvalidInstructionData
+    );
+
+    tree_config.set_height(&height);
+
+    Ok(())
+}
+
+/// Sets the number of concurrently active MTs
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - the accounts of all MT-shards need to be opened beforehand (see [`open_mt_shard_accounts`])
+pub fn set_active_mt_shards(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    active_mt_shards: u32,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        (1..=MT_SHARD_COUNT).contains(&active_mt_shards),
+        ElusivError::InvalidMtShard
+    );
+
+    governor.set_active_mt_shards(&active_mt_shards);
+
+    Ok(())
+}
+
+/// Sets the commitment queue length at which [`ElusivEvent::QueuePressure`] is emitted and new verifications are rejected
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - a watermark of `0` disables the backpressure
+pub fn set_commitment_queue_watermark(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    watermark: u32,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        watermark <= CommitmentQueue::CAPACITY,
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_commitment_queue_watermark(&watermark);
+
+    Ok(())
+}
+
+/// The number of slots the commitment queue needs to stay full, before it can be grown
+pub const QUEUE_PRESSURE_SLOTS: u64 = 150;
+
+/// Grows the [`CommitmentQueueAccount`] and the [`MetadataQueueAccount`] by [`COMMITMENT_QUEUE_GROWTH`] slots
+///
+/// # Notes
+///
+/// - The commitment queue needs to have been full for [`QUEUE_PRESSURE_SLOTS`]:
+///     - the first call with a full queue records the slot in the [`GovernorAccount`] (a call with a non-full queue resets it),
+///     - a later call with a still full queue grows the queues.
+/// - The rent of the grown accounts is paid by the governor.
+/// - Both queues are grown at the same time, since their elements are enqueued and moved in parallel.
+pub fn resize_queue_account<'a>(
+    governor_info: &AccountInfo<'a>,
+    commitment_hash_queue_info: &AccountInfo<'a>,
+    metadata_queue_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    pda_account!(mut governor, GovernorAccount, governor_info);
+
+    let (queue_size, is_full) = {
+        pda_account!(
+            mut commitment_queue,
+            CommitmentQueueAccount,
+            commitment_hash_queue_info
         );
+        let commitment_queue = CommitmentQueue::new(&mut commitment_queue);
+        (commitment_queue.size(), commitment_queue.is_full())
+    };
+
+    if !is_full {
+        governor.set_commitment_queue_full_since_slot(&0);
+        return Ok(());
     }
 
-    // Check rent-exemption
-    if cfg!(test) {
-        // only unit-testing (since we have no ledger there)
-        guard!(
-            account.lamports() >= u32::MAX as u64,
-            ProgramError::AccountNotRentExempt
+    let slot = current_slot()?;
+    let full_since_slot = governor.get_commitment_queue_full_since_slot();
+    if full_since_slot == 0 {
+        governor.set_commitment_queue_full_since_slot(&slot);
+        return Ok(());
+    }
+
+    guard!(
+        slot >= full_since_slot + QUEUE_PRESSURE_SLOTS,
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        queue_size as usize + COMMITMENT_QUEUE_GROWTH <= MAX_COMMITMENT_QUEUE_LEN,
+        ElusivError::InvalidAccountState
+    );
+
+    grow_account(
+        governor_info,
+        commitment_hash_queue_info,
+        COMMITMENT_QUEUE_GROWTH * CommitmentQueueAccount::SLOT_SIZE,
+    )?;
+    grow_account(
+        governor_info,
+        metadata_queue_info,
+        COMMITMENT_QUEUE_GROWTH * MetadataQueueAccount::SLOT_SIZE,
+    )?;
+
+    {
+        pda_account!(
+            mut commitment_queue,
+            CommitmentQueueAccount,
+            commitment_hash_queue_info
         );
-    } else {
-        guard!(
-            account.lamports() >= Rent::get()?.minimum_balance(data_len),
-            ProgramError::AccountNotRentExempt
+        CommitmentQueue::new(&mut commitment_queue).grow_in_flight(queue_size)?;
+    }
+
+    {
+        pda_account!(
+            mut metadata_queue,
+            MetadataQueueAccount,
+            metadata_queue_info
         );
+        MetadataQueue::new(&mut metadata_queue).grow(queue_size)?;
     }
 
-    // Check ownership
-    guard!(*account.owner == crate::id(), ProgramError::IllegalOwner);
+    governor.set_commitment_queue_full_since_slot(&0);
 
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        macros::account_info,
-        processor::CommitmentHashRequest,
-        state::{program_account::SizedAccount, queue::RingQueue, storage::StorageChildAccount},
-        types::U256,
+/// Appends `additional_bytes` (zeroed) to `account` with the additional rent paid by `payer` (a PDA)
+fn grow_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    additional_bytes: usize,
+) -> ProgramResult {
+    let data_len = account.data_len() + additional_bytes;
+    let rent = Rent::get()?
+        .minimum_balance(data_len)
+        .saturating_sub(account.lamports());
+
+    transfer_lamports_from_pda_checked(payer, account, rent)?;
+    account.realloc(data_len, true)
+}
+
+/// Sets the number of [`crate::state::proof::VerificationAccount`] instances per fee-payer
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - `instances` is bounded by [`crate::processor::MAX_VERIFICATION_ACCOUNT_INSTANCES`]
+pub fn set_verification_account_instances(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    instances: u8,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        instances > 0 && instances <= crate::processor::MAX_VERIFICATION_ACCOUNT_INSTANCES,
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        instances > governor.get_merge_verification_account_instances(),
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_verification_account_instances(&instances);
+
+    Ok(())
+}
+
+/// Sets the number of [`crate::state::proof::VerificationAccount`] instances per fee-payer reserved for merges and migrations
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - at least one instance remains usable for sends
+pub fn set_merge_verification_account_instances(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    instances: u8,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+
+    let total_instances = match governor.get_verification_account_instances() {
+        0 => crate::processor::MAX_VERIFICATION_ACCOUNT_INSTANCES,
+        total_instances => total_instances,
     };
-    use elusiv_types::ProgramAccount;
-    use solana_program::{pubkey::Pubkey, system_program};
+    guard!(
+        instances < total_instances,
+        ElusivError::InvalidInstructionData
+    );
 
-    #[test]
-    fn test_enable_storage_child_account() {
-        let mut data = vec![0; StorageAccount::SIZE];
-        let mut storage_account = StorageAccount::new(&mut data).unwrap();
+    governor.set_merge_verification_account_instances(&instances);
 
-        // Account has invalid size
-        account_info!(
-            child_account,
-            Pubkey::new_unique(),
-            vec![0; StorageChildAccount::SIZE - 1]
+    Ok(())
+}
+
+/// Sets the highest batching rate, queued commitments are batched with
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair.
+pub fn set_max_commitment_batching_rate(
+    authority: &AccountInfo,
+    hashing_account: &mut CommitmentHashingAccount,
+
+    _mt_shard: u32,
+    max_batching_rate: u32,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        max_batching_rate as usize <= MAX_COMMITMENT_BATCHING_RATE,
+        ElusivError::InvalidInstructionData
+    );
+
+    hashing_account.set_max_batching_rate(&max_batching_rate);
+
+    Ok(())
+}
+
+/// Sets the warden attestation level required from fee-payers of stores and verifications
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - a `level` of zero switches back to the open mode
+/// - builds without the `permissioned` feature only accept the open mode
+pub fn set_required_warden_attestation_level(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    level: u8,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        level == 0 || cfg!(feature = "permissioned"),
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_required_warden_attestation_level(&level);
+
+    Ok(())
+}
+
+/// Sets the range of protocol versions accepted by versioned instructions
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - `max_protocol_version` is bounded by [`PROTOCOL_VERSION`]
+pub fn set_supported_protocol_versions(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    min_protocol_version: u8,
+    max_protocol_version: u8,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        min_protocol_version <= max_protocol_version && max_protocol_version <= PROTOCOL_VERSION,
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_min_protocol_version(&min_protocol_version);
+    governor.set_max_protocol_version(&max_protocol_version);
+
+    Ok(())
+}
+
+/// Sets the max join-split amount per commitment in the active MT for the token `token_id`
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - a `limit` of `0` disables the limit
+pub fn set_tree_age_amount_limit(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    token_id: u16,
+    limit: u64,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        (token_id as usize) < TOKEN_COUNT,
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_tree_age_amount_limits(token_id as usize, &limit);
+
+    Ok(())
+}
+
+/// Sets the max ciphertext length and the fee multiplier of the commitment metadata size class `class_index`
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - a `max_ciphertext_len` of `0` disables the class
+/// - the enabled classes need to stay ordered by their max ciphertext length
+pub fn set_metadata_size_class(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    class_index: u8,
+    max_ciphertext_len: u16,
+    fee_multiplier_bps: u16,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+
+    let class_index = class_index as usize;
+    guard!(
+        class_index < METADATA_SIZE_CLASS_COUNT,
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        max_ciphertext_len <= MAX_METADATA_CIPHERTEXT_LEN,
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        fee_multiplier_bps >= MIN_METADATA_FEE_MULTIPLIER_BPS,
+        ElusivError::InvalidInstructionData
+    );
+
+    if max_ciphertext_len != 0 {
+        for class in 0..METADATA_SIZE_CLASS_COUNT {
+            let max_len = governor.get_metadata_size_class_max_lens(class);
+            if max_len == 0 {
+                continue;
+            }
+
+            guard!(
+                class == class_index
+                    || (class < class_index && max_len < max_ciphertext_len)
+                    || (class > class_index && max_len > max_ciphertext_len),
+                ElusivError::InvalidInstructionData
+            );
+        }
+    }
+
+    governor.set_metadata_size_class_max_lens(class_index, &max_ciphertext_len);
+    governor.set_metadata_size_class_fee_multipliers(class_index, &fee_multiplier_bps);
+
+    Ok(())
+}
+
+/// Sets the spread charged on lamport-denominated fees settled in a token
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair
+pub fn set_token_fee_spread(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    spread_bps: u16,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        spread_bps <= MAX_TOKEN_FEE_SPREAD_BPS,
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_token_fee_spread_bps(&spread_bps);
+
+    Ok(())
+}
+
+/// Sets the fair-share of a fee-payer in the base-commitment hashes finalized within a window of `window_slots`
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair
+pub fn set_hashing_fair_share(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    share_bps: u16,
+    window_slots: u64,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        share_bps == 0
+            || ((MIN_HASHING_FAIR_SHARE_BPS..=10_000).contains(&share_bps) && window_slots > 0),
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_hashing_fair_share_bps(&share_bps);
+    governor.set_hashing_fair_share_window(&window_slots);
+
+    Ok(())
+}
+
+/// Requires (or stops requiring) warden nonces for stores and verifications (see [`consume_warden_nonce`])
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair
+pub fn set_require_warden_nonces(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    require_warden_nonces: bool,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+
+    governor.set_require_warden_nonces(&require_warden_nonces);
+
+    Ok(())
+}
+
+/// Sets the handling of payouts to recipients flagged by the APA of the Elusiv Warden Network (see [`crate::processor::is_apa_flagged`])
+///
+/// # Note
+///
+/// `authority` needs to be the program's keypair
+pub fn set_apa_enforcement(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    apa_enforcement: ApaEnforcement,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+
+    governor.set_apa_enforcement(&apa_enforcement);
+
+    Ok(())
+}
+
+/// Halts (or resumes) new stores, verifications and commitment hashing batches
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - in-flight computations can still be finalized (see [`GovernorAccount::get_is_protocol_paused`])
+pub fn set_protocol_pause(
+    authority: &AccountInfo,
+    governor: &mut GovernorAccount,
+
+    is_paused: bool,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+
+    governor.set_is_protocol_paused(&is_paused);
+
+    Ok(())
+}
+
+/// Number of decimals below one whole token that are considered dust (`10^-4` tokens)
+const POOL_DUST_PRECISION: u8 = 4;
+
+/// Returns the (exclusive) upper bound of a dust amount of the token `token_id`
+pub fn pool_dust_threshold(token_id: u16) -> Result<u64, ProgramError> {
+    let decimals = elusiv_token(token_id)?.decimals;
+    Ok(10u64.pow(decimals.saturating_sub(POOL_DUST_PRECISION) as u32))
+}
+
+/// Returns the balance of the `pool` exceeding its liability of the token `token_id` (see [`MetricsAccount::pool_liability`])
+///
+/// # Note
+///
+/// Swept dust has already left the pool's balance, so it's not subtracted from the liability again.
+pub fn pool_dust(
+    pool: &AccountInfo,
+    pool_account: &AccountInfo,
+    metrics: &MetricsAccount,
+    token_id: u16,
+) -> Result<u64, ProgramError> {
+    Ok(
+        pool_balance(pool, pool_account, token_id)?
+            .saturating_sub(metrics.pool_liability(token_id)),
+    )
+}
+
+fn pool_balance(
+    pool: &AccountInfo,
+    pool_account: &AccountInfo,
+    token_id: u16,
+) -> Result<u64, ProgramError> {
+    if token_id == 0 {
+        return Ok(pool.lamports());
+    }
+
+    Ok(unpack_token_state::<spl_token_2022::state::Account>(pool_account)?.amount)
+}
+
+/// Sweeps rounding dust from the `pool` into the `fee_collector`
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - `amount` needs to be below [`pool_dust_threshold`] and can't exceed the [`pool_dust`]
+/// - the sweep is recorded in the [`MetricsAccount`] and emitted as [`ElusivEvent::PoolDustSwept`]
+#[allow(clippy::too_many_arguments)]
+pub fn sweep_pool_dust<'a>(
+    authority: &AccountInfo,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    metrics: &mut MetricsAccount,
+    token_program: &AccountInfo<'a>,
+
+    token_id: u16,
+    amount: u64,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        amount > 0 && amount < pool_dust_threshold(token_id)?,
+        ElusivError::InvalidAmount
+    );
+
+    verify_program_token_account(pool, pool_account, token_id)?;
+    verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+
+    // Funds owed to the holders of unspent notes are never swept
+    guard!(
+        amount <= pool_dust(pool, pool_account, metrics, token_id)?,
+        ElusivError::InvalidAmount
+    );
+
+    // `pool` transfers `amount` to `fee_collector` (token)
+    transfer_token_from_pda::<PoolAccount>(
+        pool,
+        pool_account,
+        fee_collector_account,
+        token_program,
+        Token::new(token_id, amount),
+        None,
+        None,
+    )?;
+
+    metrics.record_dust_sweep(token_id, amount)?;
+
+    ElusivEvent::PoolDustSwept {
+        token_id,
+        amount,
+        pool_balance: pool_balance(pool, pool_account, token_id)?,
+        total_swept: metrics.get_dust_swept(token_id as usize),
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Pins the decimals and the authority state of a token's on-chain mint into the [`TokenRegistryAccount`]
+///
+/// # Note
+///
+/// - each token can only be verified once
+/// - the decimals of the mint need to match the token's config (`Token.toml`)
+/// - for Lamports (no mint) only the system-program key is checked
+pub fn verify_token_config(
+    mint: &AccountInfo,
+    token_registry: &mut TokenRegistryAccount,
+
+    token_id: u16,
+) -> ProgramResult {
+    let token = elusiv_token(token_id)?;
+    guard!(*mint.key == token.mint, ElusivError::InvalidAccount);
+
+    if token_id == 0 {
+        return token_registry.pin_token_config(token_id, token.decimals, false, false);
+    }
+
+    guard!(
+        *mint.owner == token.token_program,
+        ElusivError::InvalidAccount
+    );
+    let mint = unpack_token_state::<spl_token_2022::state::Mint>(mint)?;
+
+    token_registry.pin_token_config(
+        token_id,
+        mint.decimals,
+        mint.mint_authority.is_some(),
+        mint.freeze_authority.is_some(),
+    )
+}
+
+/// Pins a fixed conversion rate (Lamports per whole token) for a verified token
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - a rate of `0` restores the oracle-based pricing
+pub fn set_fixed_token_rate(
+    authority: &AccountInfo,
+    token_registry: &mut TokenRegistryAccount,
+
+    token_id: u16,
+    lamports_per_token: u64,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+
+    token_registry.set_token_fixed_rate(token_id, lamports_per_token)
+}
+
+/// Closes a program owned account in devnet and localhost
+///
+/// # Note
+///
+/// - `signer` needs to be the program's keypair
+/// - `recipient` receives the accounts Lamports
+#[cfg(not(feature = "mainnet"))]
+pub fn close_program_account<'a>(
+    signer: &AccountInfo,
+    recipient: &AccountInfo<'a>,
+    program_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    assert!(!cfg!(feature = "mainnet"));
+    assert_eq!(*signer.key, crate::ID);
+
+    elusiv_utils::close_account(recipient, program_account)
+}
+
+/// Clears the commitment- and metadata-queues, aborts an active commitment hash computation and resets the metrics in devnet and localhost
+///
+/// # Note
+///
+/// - This is the first step of a devnet reset (followed by [`reset_storage_account`] and [`reset_nullifier_account`]).
+/// - Requires a paused protocol (see [`set_protocol_pause`]), so no new commitments are enqueued during the reset.
+/// - Base-commitment hashings need to be closed beforehand (using [`close_program_account`]), since they would enqueue new commitments.
+/// - Increments the `reset_generation` of the [`GovernorAccount`], so pending verifications cannot be finalized anymore (they can be closed using [`crate::processor::reset_verification_account`]).
+#[cfg(not(feature = "mainnet"))]
+pub fn reset_commitment_queues(
+    authority: &AccountInfo,
+    commitment_hashing_account: &mut CommitmentHashingAccount,
+    commitment_queue: &mut CommitmentQueueAccount,
+    metadata_queue: &mut MetadataQueueAccount,
+    metadata_account: &mut MetadataAccount,
+    metrics: &mut MetricsAccount,
+    governor: &mut GovernorAccount,
+
+    _mt_shard: u32,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        governor.get_is_protocol_paused(),
+        ElusivError::InvalidAccountState
+    );
+
+    commitment_hashing_account.abort();
+    CommitmentQueue::new(commitment_queue).clear_in_flight();
+    crate::state::metadata::MetadataQueue::new(metadata_queue).clear();
+    metadata_account.set_next_metadata_ptr(&0);
+    metrics.reset();
+    governor.set_reset_generation(&governor.get_reset_generation().wrapping_add(1));
+
+    Ok(())
+}
+
+/// Closes all child-accounts of the [`StorageAccount`] and resets all MTs in devnet and localhost
+///
+/// # Note
+///
+/// - Requires an empty commitment-queue and no active commitment hash computation (see [`reset_commitment_queues`]).
+/// - `recipient` receives the child-accounts Lamports.
+/// - The child-accounts need to be enabled again (using [`enable_storage_child_account`]) before new commitments can be inserted.
+#[cfg(not(feature = "mainnet"))]
+pub fn reset_storage_account<'a, 'b, 't>(
+    authority: &AccountInfo,
+    recipient: &AccountInfo<'t>,
+    storage_account: &mut StorageAccount<'a, 'b, 't>,
+    commitment_hashing_account: &CommitmentHashingAccount,
+    commitment_queue: &CommitmentQueueAccount,
+
+    _mt_shard: u32,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        !commitment_hashing_account.get_is_active(),
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        commitment_queue.queue_len() == 0,
+        ElusivError::InvalidAccountState
+    );
+
+    for i in 0..StorageAccount::COUNT {
+        if storage_account.get_child_pubkey(i).is_none() {
+            continue;
+        }
+
+        // The child-account's data is not accessed, we only drain its Lamports
+        let child_account = unsafe { storage_account.get_child_account_unsafe(i) }?;
+        elusiv_utils::close_account(recipient, child_account)?;
+        storage_account.set_child_pubkey(i, ElusivOption::None);
+    }
+
+    storage_account.reset_all_trees();
+
+    Ok(())
+}
+
+/// Removes all nullifier-hashes from a [`NullifierAccount`] in devnet and localhost
+///
+/// # Note
+///
+/// - Requires a reset [`StorageAccount`] (see [`reset_storage_account`]).
+/// - All enabled child-accounts of the [`NullifierAccount`] need to be supplied.
+#[cfg(not(feature = "mainnet"))]
+pub fn reset_nullifier_account(
+    authority: &AccountInfo,
+    storage_account: &StorageAccount,
+    nullifier_account: &mut NullifierAccount,
+
+    mt_index: u32,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        storage_account.get_trees_count() == sharded_mt_index(mt_index_shard(mt_index), 0)
+            && storage_account.get_next_commitment_ptr() == 0,
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        (0..StorageAccount::COUNT).all(|i| storage_account.get_child_pubkey(i).is_none()),
+        ElusivError::InvalidAccountState
+    );
+
+    nullifier_account.reset()
+}
+
+#[cfg(not(feature = "mainnet"))]
+pub fn open_migration_manifest_account<'b>(
+    payer: &AccountInfo<'b>,
+    migration_manifest: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<MigrationManifestAccount>(
+        &crate::id(),
+        payer,
+        migration_manifest.get_unsafe(),
+        None,
+    )
+}
+
+/// Exports the next chunk of a program account's data as return data (for a cluster migration in devnet and localhost)
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair.
+/// - The first chunk registers the account as the entry `entry_index` of the [`MigrationManifestAccount`] (`offset` allows to skip a header).
+/// - The resulting hash-chain of an entry is required for importing the account (see [`import_account_chunk`]).
+#[cfg(not(feature = "mainnet"))]
+pub fn export_account_chunk(
+    authority: &AccountInfo,
+    migration_manifest: &mut MigrationManifestAccount,
+    account: &AccountInfo,
+
+    entry_index: u32,
+    offset: u32,
+    len: u32,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(*account.owner == crate::id(), ElusivError::InvalidAccount);
+    guard!(
+        len as usize <= MAX_MIGRATION_CHUNK_SIZE,
+        ElusivError::InvalidInstructionData
+    );
+
+    migration_manifest.register_or_verify_entry(
+        false,
+        entry_index,
+        account.key,
+        usize_as_u32_safe(account.data_len()),
+        offset,
+        &[0; 32],
+    )?;
+
+    let data = &account.data.borrow()[..];
+    let chunk = data
+        .get(offset as usize..offset as usize + len as usize)
+        .ok_or(ElusivError::InvalidInstructionData)?;
+    migration_manifest.append_chunk(entry_index, chunk)?;
+
+    solana_program::program::set_return_data(chunk);
+
+    Ok(())
+}
+
+/// Imports the next chunk of an exported program account's data (for a cluster migration in devnet and localhost)
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair.
+/// - `account` needs to be opened beforehand with the size of the exported account.
+/// - The first chunk registers the account as the entry `entry_index` of the [`MigrationManifestAccount`] with the `expected_hash` of the export.
+/// - The last chunk fails, if the hash-chain of the imported data does not match the `expected_hash`.
+#[cfg(not(feature = "mainnet"))]
+pub fn import_account_chunk(
+    authority: &AccountInfo,
+    migration_manifest: &mut MigrationManifestAccount,
+    account: &AccountInfo,
+
+    entry_index: u32,
+    offset: u32,
+    expected_hash: U256,
+    data: Vec<u8>,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(*account.owner == crate::id(), ElusivError::InvalidAccount);
+    guard!(
+        data.len() <= MAX_MIGRATION_CHUNK_SIZE,
+        ElusivError::InvalidInstructionData
+    );
+
+    migration_manifest.register_or_verify_entry(
+        true,
+        entry_index,
+        account.key,
+        usize_as_u32_safe(account.data_len()),
+        offset,
+        &expected_hash,
+    )?;
+    migration_manifest.append_chunk(entry_index, &data)?;
+
+    let account_data = &mut account.data.borrow_mut()[..];
+    account_data[offset as usize..offset as usize + data.len()].copy_from_slice(&data);
+
+    Ok(())
+}
+
+/// Verifies a single user-supplied [`ChildAccount`] and then saves it's pubkey in the `parent_account`
+///
+/// # Note
+///
+/// If `size` is manually supplied (not the default [`C::SIZE`] is used) [`elusiv_types::ChildAccountConfig::SIZE`] needs to be contained in the size.
+pub fn setup_child_account<'a, 'b, 't, P: ParentAccount<'a, 'b, 't>>(
+    parent_account: &mut P,
+    child_account: &AccountInfo,
+    child_index: usize,
+    check_zeroness: bool,
+    size: Option<usize>,
+) -> ProgramResult {
+    setup_tagged_child_account(
+        parent_account,
+        child_account,
+        child_index,
+        check_zeroness,
+        size,
+        ElusivOption::None,
+    )
+}
+
+/// Verifies a single user-supplied [`ChildAccount`] (of default size) and tags it with the `parent` and `child_index`
+fn register_child_account<'a, 'b, 't, P: ParentAccount<'a, 'b, 't>>(
+    parent_account: &mut P,
+    parent: &Pubkey,
+    child_account: &AccountInfo,
+    child_index: u32,
+) -> ProgramResult {
+    guard!(
+        (child_index as usize) < P::COUNT,
+        ElusivError::InvalidInstructionData
+    );
+
+    setup_tagged_child_account(
+        parent_account,
+        child_account,
+        child_index as usize,
+        false,
+        None,
+        ElusivOption::Some(ChildAccountTag {
+            parent: *parent,
+            child_index,
+        }),
+    )
+}
+
+fn setup_tagged_child_account<'a, 'b, 't, P: ParentAccount<'a, 'b, 't>>(
+    parent_account: &mut P,
+    child_account: &AccountInfo,
+    child_index: usize,
+    check_zeroness: bool,
+    size: Option<usize>,
+    tag: ElusivOption<ChildAccountTag>,
+) -> ProgramResult {
+    if parent_account.get_child_pubkey(child_index).is_some() {
+        return Err(ElusivError::ChildAccountAlreadyExists.into());
+    }
+
+    verify_extern_data_account(
+        child_account,
+        size.unwrap_or(<P::Child as SizedAccount>::SIZE),
+        check_zeroness,
+    )?;
+    parent_account.set_child_pubkey(child_index, ElusivOption::Some(*child_account.key));
+    P::Child::try_start_using_account_with_tag(child_account, tag)?;
+
+    Ok(())
+}
+
+fn reset_map_child_account<C: ChildAccount>(child_account: &AccountInfo) -> ProgramResult {
+    let data = &mut child_account.data.borrow_mut()[..];
+    let (_, inner_data) = split_child_account_data_mut(data)?;
+    let mut map = ElusivMap::<(), (), 1>::new(&mut inner_data[..ElusivMap::<(), (), 1>::SIZE]);
+    map.reset();
+
+    Ok(())
+}
+
+fn reset_nullifier_child_account(child_account: &AccountInfo) -> ProgramResult {
+    reset_map_child_account::<NullifierChildAccount>(child_account)?;
+
+    let data = &mut child_account.data.borrow_mut()[..];
+    let (_, inner_data) = split_child_account_data_mut(data)?;
+    if let (_, Some(bloom_filter)) = split_nullifier_child_data(inner_data) {
+        bloom_filter.fill(0);
+    }
+
+    Ok(())
+}
+
+/// Verifies that an account with `data_len` > 10 KiB (non PDA) is formatted correctly
+fn verify_extern_data_account(
+    account: &AccountInfo,
+    data_len: usize,
+    check_zeroness: bool,
+) -> ProgramResult {
+    guard!(
+        account.data_len() == data_len,
+        ProgramError::InvalidAccountData
+    );
+
+    guard!(
+        data_len >= ChildAccountConfig::SIZE,
+        ProgramError::InvalidAccountData
+    );
+
+    if check_zeroness {
+        guard!(
+            is_zero(&account.data.borrow()[..]),
+            ProgramError::InvalidAccountData
+        );
+    }
+
+    // Check rent-exemption
+    if cfg!(test) {
+        // only unit-testing (since we have no ledger there)
+        guard!(
+            account.lamports() >= u32::MAX as u64,
+            ProgramError::AccountNotRentExempt
+        );
+    } else {
+        guard!(
+            account.lamports() >= Rent::get()?.minimum_balance(data_len),
+            ProgramError::AccountNotRentExempt
+        );
+    }
+
+    // Check ownership
+    guard!(*account.owner == crate::id(), ProgramError::IllegalOwner);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        macros::account_info,
+        processor::CommitmentHashRequest,
+        state::{
+            governor::UPGRADE_TIMELOCK_SLOTS,
+            migration::MigrationManifestAccount,
+            nullifier::NullifierMap,
+            program_account::SizedAccount,
+            queue::RingQueue,
+            storage::{StorageChildAccount, MT_COMMITMENT_COUNT},
+        },
+        token::{LAMPORTS_TOKEN_ID, USDC_TOKEN_ID},
+        types::{RawU256, U256},
+    };
+    use elusiv_types::ProgramAccount;
+    use solana_program::{
+        program_option::COption, program_pack::Pack, pubkey::Pubkey, system_program,
+    };
+
+    #[test]
+    fn test_enable_storage_child_account() {
+        let mut data = vec![0; StorageAccount::SIZE];
+        let mut storage_account = StorageAccount::new(&mut data).unwrap();
+
+        // Account has invalid size
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; StorageChildAccount::SIZE - 1]
+        );
+        assert_eq!(
+            enable_storage_child_account(&mut storage_account, &child_account, 0, 0),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        storage_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+
+        // Account has already been setup
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; StorageChildAccount::SIZE]
+        );
+        assert_eq!(
+            enable_storage_child_account(&mut storage_account, &child_account, 0, 0),
+            Err(ElusivError::ChildAccountAlreadyExists.into())
+        );
+
+        // Success at different index
+        assert_eq!(
+            enable_storage_child_account(&mut storage_account, &child_account, 0, 3),
+            Ok(())
+        );
+        assert_eq!(child_account.data.borrow()[0], 1);
+
+        // Account already is use
+        assert_eq!(
+            enable_storage_child_account(&mut storage_account, &child_account, 0, 1),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+    }
+
+    #[test]
+    fn test_enable_nullifier_child_account() {
+        let mut data = vec![0; NullifierAccount::SIZE];
+        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
+
+        // Account has invalid size
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; NullifierChildAccount::SIZE - 1]
+        );
+        assert_eq!(
+            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 0),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        nullifier_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+
+        // Account has already been setup
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; NullifierChildAccount::SIZE]
+        );
+        assert_eq!(
+            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 0),
+            Err(ElusivError::ChildAccountAlreadyExists.into())
+        );
+
+        // Success at different index with
+        assert_eq!(
+            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 3),
+            Ok(())
+        );
+        assert_eq!(child_account.data.borrow()[0], 1);
+
+        // Account already is use
+        assert_eq!(
+            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 1),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+    }
+
+    #[test]
+    fn test_register_sub_account() {
+        let (pda, bump) = NullifierAccount::find(Some(1));
+        let mut data = vec![0; NullifierAccount::SIZE];
+        data[0] = bump;
+        account_info!(parent_account, pda, data);
+
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; NullifierChildAccount::SIZE]
+        );
+
+        // Invalid parent-account
+        assert!(register_sub_account(
+            &parent_account,
+            &child_account,
+            SubAccountKind::Nullifier { mt_index: 0 },
+            0
+        )
+        .is_err());
+        assert!(
+            register_sub_account(&parent_account, &child_account, SubAccountKind::Storage, 0)
+                .is_err()
+        );
+
+        // Invalid child-index
+        assert_eq!(
+            register_sub_account(
+                &parent_account,
+                &child_account,
+                SubAccountKind::Nullifier { mt_index: 1 },
+                NullifierAccount::COUNT as u32
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        // Invalid size
+        account_info!(
+            invalid_child_account,
+            Pubkey::new_unique(),
+            vec![0; NullifierChildAccount::SIZE + 1]
+        );
+        assert_eq!(
+            register_sub_account(
+                &parent_account,
+                &invalid_child_account,
+                SubAccountKind::Nullifier { mt_index: 1 },
+                0
+            ),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        register_sub_account(
+            &parent_account,
+            &child_account,
+            SubAccountKind::Nullifier { mt_index: 1 },
+            2,
+        )
+        .unwrap();
+
+        {
+            let data = &mut parent_account.data.borrow_mut()[..];
+            let nullifier_account = NullifierAccount::new(data).unwrap();
+            assert_eq!(
+                nullifier_account.get_child_pubkey(2),
+                Some(*child_account.key)
+            );
+        }
+
+        let config = ChildAccountConfig::try_from_slice(
+            &child_account.data.borrow()[..ChildAccountConfig::SIZE],
+        )
+        .unwrap();
+        assert!(config.is_in_use);
+        assert_eq!(
+            config.tag.option(),
+            Some(ChildAccountTag {
+                parent: pda,
+                child_index: 2
+            })
+        );
+
+        // Account already in use
+        assert_eq!(
+            register_sub_account(
+                &parent_account,
+                &child_account,
+                SubAccountKind::Nullifier { mt_index: 1 },
+                3
+            ),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+    }
+
+    #[test]
+    fn test_is_mt_full() {
+        let mut data = vec![0; StorageAccount::SIZE];
+        let mut storage_account = StorageAccount::new(&mut data).unwrap();
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+
+        let mut q_data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut q_data).unwrap();
+        let mut queue = CommitmentQueue::new(&mut queue);
+        queue
+            .enqueue(CommitmentHashRequest {
+                min_batching_rate: 1,
+                commitment: [0; 32],
+                fee_version: 0,
+            })
+            .unwrap();
+        queue
+            .enqueue(CommitmentHashRequest {
+                min_batching_rate: 1,
+                commitment: [0; 32],
+                fee_version: 0,
+            })
+            .unwrap();
+
+        assert!(is_mt_full(&storage_account, &queue).unwrap());
+
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 3));
+        assert!(!is_mt_full(&storage_account, &queue).unwrap());
+
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 2));
+        assert!(is_mt_full(&storage_account, &queue).unwrap());
+
+        // Lower height
+        storage_account.set_height(&MIN_MT_HEIGHT);
+        storage_account.set_next_commitment_ptr(&(two_pow!(MIN_MT_HEIGHT) as u32 - 3));
+        assert!(!is_mt_full(&storage_account, &queue).unwrap());
+
+        storage_account.set_next_commitment_ptr(&(two_pow!(MIN_MT_HEIGHT) as u32 - 2));
+        assert!(is_mt_full(&storage_account, &queue).unwrap());
+    }
+
+    #[test]
+    fn test_rollover() {
+        parent_account!(mut storage_account, StorageAccount);
+        parent_account!(mut closed_nullifier_account, NullifierAccount);
+        zero_program_account!(mut queue, CommitmentQueueAccount);
+        zero_program_account!(tree_config, TreeConfigAccount);
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(mut rollover_state, RolloverStateAccount);
+        account_info!(payer, Pubkey::new_unique(), true);
+
+        // Already opened next NullifierAccount
+        let (pubkey, bump) = NullifierAccount::find(Some(1));
+        let mut data = vec![0; NullifierAccount::SIZE];
+        data[0] = bump;
+        account_info!(next_nullifier_account, pubkey, data);
+
+        // All steps require the frozen closed MT
+        assert_eq!(
+            rollover_open_nullifier_account(
+                &payer,
+                UnverifiedAccountInfo::new(&next_nullifier_account),
+                &mut rollover_state,
+                0
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+        assert_eq!(
+            rollover_init_storage(&mut storage_account, &tree_config, &mut rollover_state, 0),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // MT is not full yet
+        assert_eq!(
+            rollover_freeze_closed_merkle_tree(
+                &storage_account,
+                &mut queue,
+                &mut closed_nullifier_account,
+                &mut rollover_state,
+                0
+            ),
+            Err(ElusivError::MerkleTreeIsNotFullYet.into())
+        );
+
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+        let root = storage_account.get_root().unwrap();
+
+        // Invalid MT index
+        assert_eq!(
+            rollover_freeze_closed_merkle_tree(
+                &storage_account,
+                &mut queue,
+                &mut closed_nullifier_account,
+                &mut rollover_state,
+                1
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        assert_eq!(
+            rollover_freeze_closed_merkle_tree(
+                &storage_account,
+                &mut queue,
+                &mut closed_nullifier_account,
+                &mut rollover_state,
+                0
+            ),
+            Ok(())
+        );
+        assert_eq!(closed_nullifier_account.get_root(), root);
+
+        // Re-pointing the governor requires all previous steps
+        assert_eq!(
+            rollover_repoint_governor(&mut governor, &mut rollover_state, 0),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        assert_eq!(
+            rollover_open_nullifier_account(
+                &payer,
+                UnverifiedAccountInfo::new(&next_nullifier_account),
+                &mut rollover_state,
+                0
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            rollover_init_storage(&mut storage_account, &tree_config, &mut rollover_state, 0),
+            Ok(())
+        );
+        assert_eq!(storage_account.get_trees_count(), 1);
+        assert_eq!(storage_account.get_next_commitment_ptr(), 0);
+
+        // Completed steps are no-ops (the storage is not reset twice)
+        storage_account.set_next_commitment_ptr(&1);
+        assert_eq!(
+            rollover_init_storage(&mut storage_account, &tree_config, &mut rollover_state, 0),
+            Ok(())
+        );
+        assert_eq!(
+            rollover_freeze_closed_merkle_tree(
+                &storage_account,
+                &mut queue,
+                &mut closed_nullifier_account,
+                &mut rollover_state,
+                0
+            ),
+            Ok(())
+        );
+        assert_eq!(storage_account.get_next_commitment_ptr(), 1);
+
+        assert_eq!(
+            rollover_repoint_governor(&mut governor, &mut rollover_state, 0),
+            Ok(())
+        );
+        assert_eq!(governor.get_active_mt_index(), 1);
+        assert!(!rollover_state.is_in_progress());
+    }
+
+    #[test]
+    fn test_set_tree_height() {
+        zero_program_account!(mut tree_config, TreeConfigAccount);
+        assert_eq!(tree_config.next_mt_height(), MT_HEIGHT);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_tree_height(&invalid_authority, &mut tree_config, MIN_MT_HEIGHT),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        for height in [0, MIN_MT_HEIGHT - 1, MT_HEIGHT + 1] {
+            assert_eq!(
+                set_tree_height(&authority, &mut tree_config, height),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        set_tree_height(&authority, &mut tree_config, MIN_MT_HEIGHT).unwrap();
+        assert_eq!(tree_config.next_mt_height(), MIN_MT_HEIGHT);
+    }
+
+    #[test]
+    fn test_open_mt_shard_accounts() {
+        test_account_info!(payer, 0);
+        test_account_info!(any, 0);
+
+        // MT-shard `0` uses the single-instance-accounts
+        for mt_shard in [0, MT_SHARD_COUNT] {
+            assert_eq!(
+                open_mt_shard_accounts(
+                    &payer,
+                    UnverifiedAccountInfo::new(&any),
+                    UnverifiedAccountInfo::new(&any),
+                    UnverifiedAccountInfo::new(&any),
+                    UnverifiedAccountInfo::new(&any),
+                    UnverifiedAccountInfo::new(&any),
+                    mt_shard,
+                ),
+                Err(ElusivError::InvalidMtShard.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_active_mt_shards() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_active_mt_shards(&invalid_authority, &mut governor, 2),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        for active_mt_shards in [0, MT_SHARD_COUNT + 1] {
+            assert_eq!(
+                set_active_mt_shards(&authority, &mut governor, active_mt_shards),
+                Err(ElusivError::InvalidMtShard.into())
+            );
+        }
+
+        set_active_mt_shards(&authority, &mut governor, 2).unwrap();
+        assert_eq!(governor.get_active_mt_shards(), 2);
+        assert_eq!(governor.commitment_mt_shard(&[3; 32]), 1);
+    }
+
+    #[test]
+    fn test_rollover_repoint_governor_mt_shard() {
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(mut rollover_state, RolloverStateAccount);
+
+        let closed_mt_index = sharded_mt_index(1, 0);
+        rollover_state.begin(closed_mt_index).unwrap();
+        rollover_state
+            .complete_step(
+                closed_mt_index,
+                ROLLOVER_STEP_FREEZE_CLOSED_MT
+                    | ROLLOVER_STEP_OPEN_NULLIFIER_ACCOUNT
+                    | ROLLOVER_STEP_INIT_STORAGE,
+            )
+            .unwrap();
+
+        // The governor only tracks the active MT of MT-shard `0`
+        assert_eq!(
+            rollover_repoint_governor(&mut governor, &mut rollover_state, closed_mt_index),
+            Ok(())
+        );
+        assert_eq!(governor.get_active_mt_index(), 0);
+        assert!(!rollover_state.is_in_progress());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_archive_closed_merkle_tree() {
+        test_account_info!(payer, 0);
+        let mut data = vec![0; StorageAccount::SIZE];
+        let mut storage_account = StorageAccount::new(&mut data).unwrap();
+        let mut data = vec![0; NullifierAccount::SIZE];
+        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
+        test_account_info!(archived_tree_account, 0);
+
+        archive_closed_merkle_tree(
+            &payer,
+            &mut storage_account,
+            &mut nullifier_account,
+            &archived_tree_account,
+            0,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_upgrade_governor_state() {
+        test_account_info!(authority, 0);
+        zero_program_account!(mut governor_account, GovernorAccount);
+        zero_program_account!(commitment_queue, CommitmentQueueAccount);
+
+        upgrade_governor_state(&authority, &mut governor_account, &commitment_queue, 1, 1).unwrap();
+    }
+
+    #[test]
+    fn test_confirm_upgrade() {
+        zero_program_account!(mut upgrade_gate, UpgradeGateAccount);
+        let program_data_key =
+            Pubkey::find_program_address(&[crate::ID.as_ref()], &bpf_loader_upgradeable::id()).0;
+        let executable = vec![1, 2, 3];
+        let hash = solana_program::hash::hash(&executable).to_bytes();
+
+        let mut data = vec![0; PROGRAM_DATA_METADATA_SIZE];
+        data[..4].copy_from_slice(&PROGRAM_DATA_TAG.to_le_bytes());
+        data[4..12].copy_from_slice(&UPGRADE_TIMELOCK_SLOTS.to_le_bytes());
+        data.extend(executable);
+
+        account_info!(authority, crate::ID, true);
+        approve_upgrade(&authority, &mut upgrade_gate, hash).unwrap();
+
+        // Invalid program-data account
+        account_info!(
+            invalid,
+            Pubkey::new_unique(),
+            data.clone(),
+            bpf_loader_upgradeable::id(),
+            false
+        );
+        assert_eq!(
+            confirm_upgrade(&mut upgrade_gate, &invalid),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid owner
+        account_info!(invalid_owner, program_data_key, data.clone());
+        assert_eq!(
+            confirm_upgrade(&mut upgrade_gate, &invalid_owner),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid tag
+        let mut invalid_data = data.clone();
+        invalid_data[0] = 2;
+        account_info!(
+            invalid_tag,
+            program_data_key,
+            invalid_data,
+            bpf_loader_upgradeable::id(),
+            false
+        );
+        assert_eq!(
+            confirm_upgrade(&mut upgrade_gate, &invalid_tag),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        account_info!(
+            program_data,
+            program_data_key,
+            data,
+            bpf_loader_upgradeable::id(),
+            false
+        );
+        confirm_upgrade(&mut upgrade_gate, &program_data).unwrap();
+        assert_eq!(upgrade_gate.get_active_program_data_hash(), hash);
+        assert_eq!(upgrade_gate.get_upgrade_count(), 1);
+    }
+
+    #[test]
+    fn test_approve_upgrade() {
+        zero_program_account!(mut upgrade_gate, UpgradeGateAccount);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            approve_upgrade(&invalid_authority, &mut upgrade_gate, [1; 32]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        approve_upgrade(&authority, &mut upgrade_gate, [1; 32]).unwrap();
+        assert!(upgrade_gate.get_is_pending());
+        assert_eq!(upgrade_gate.get_approved_program_data_hash(), [1; 32]);
+    }
+
+    #[test]
+    fn test_set_fixed_token_rate() {
+        zero_program_account!(mut token_registry, TokenRegistryAccount);
+        let decimals = elusiv_token(USDC_TOKEN_ID).unwrap().decimals;
+        token_registry
+            .pin_token_config(USDC_TOKEN_ID, decimals, true, true)
+            .unwrap();
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_fixed_token_rate(&invalid_authority, &mut token_registry, USDC_TOKEN_ID, 1),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        set_fixed_token_rate(&authority, &mut token_registry, USDC_TOKEN_ID, 1).unwrap();
+        assert!(token_registry.get_is_fixed_rate(USDC_TOKEN_ID as usize));
+        assert_eq!(token_registry.get_fixed_rate(USDC_TOKEN_ID as usize), 1);
+    }
+
+    #[test]
+    fn test_set_commitment_queue_watermark() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_commitment_queue_watermark(&invalid_authority, &mut governor, 1),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        assert_eq!(
+            set_commitment_queue_watermark(
+                &authority,
+                &mut governor,
+                CommitmentQueue::CAPACITY + 1
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        set_commitment_queue_watermark(&authority, &mut governor, CommitmentQueue::CAPACITY)
+            .unwrap();
+        assert_eq!(
+            governor.get_commitment_queue_watermark(),
+            CommitmentQueue::CAPACITY
+        );
+    }
+
+    #[test]
+    fn test_set_verification_account_instances() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_verification_account_instances(&invalid_authority, &mut governor, 1),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        for instances in [0, crate::processor::MAX_VERIFICATION_ACCOUNT_INSTANCES + 1] {
+            assert_eq!(
+                set_verification_account_instances(&authority, &mut governor, instances),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        set_verification_account_instances(&authority, &mut governor, 4).unwrap();
+        assert_eq!(governor.get_verification_account_instances(), 4);
+        assert!(!governor.is_valid_verification_account_index(4));
+    }
+
+    #[test]
+    fn test_set_merge_verification_account_instances() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_merge_verification_account_instances(&invalid_authority, &mut governor, 1),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        set_verification_account_instances(&authority, &mut governor, 4).unwrap();
+
+        // Sends keep at least one instance
+        assert_eq!(
+            set_merge_verification_account_instances(&authority, &mut governor, 4),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        set_merge_verification_account_instances(&authority, &mut governor, 3).unwrap();
+        assert_eq!(governor.get_merge_verification_account_instances(), 3);
+
+        // The total number of instances can not drop below the reserved instances
+        assert_eq!(
+            set_verification_account_instances(&authority, &mut governor, 3),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+    }
+
+    #[test]
+    fn test_publish_deployment_info() {
+        use crate::processor::proof::vkey_account;
+        use crate::proof::vkey::{SendQuadraVKey, VerifyingKeyInfo};
+
+        zero_program_account!(mut deployment_info, DeploymentInfoAccount);
+        vkey_account!(vkey, SendQuadraVKey);
+
+        assert_eq!(
+            publish_deployment_info(
+                &mut deployment_info,
+                &vkey,
+                crate::processor::MAX_NUMBER_OF_VKEYS
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        // Vkey is not setup
+        publish_deployment_info(&mut deployment_info, &vkey, 0).unwrap();
+        assert_eq!(deployment_info.get_version(), crate_version());
+        assert_eq!(deployment_info.get_features(), compiled_features());
+        assert_eq!(deployment_info.get_protocol_version(), PROTOCOL_VERSION);
+        assert_eq!(deployment_info.get_vkey_hashes(0), [0; 32]);
+
+        vkey.set_version(&1);
+        publish_deployment_info(&mut deployment_info, &vkey, 0).unwrap();
+
+        let source = SendQuadraVKey::verifying_key_source();
+        let expected_hash = VerifyingKey::new(&source, SendQuadraVKey::public_inputs_count())
+            .unwrap()
+            .key_hash();
+        assert_eq!(deployment_info.get_vkey_hashes(0), expected_hash);
+        assert_eq!(deployment_info.get_vkey_versions(0), 1);
+        assert_eq!(deployment_info.get_vkey_hashes(1), [0; 32]);
+    }
+
+    #[test]
+    fn test_set_max_commitment_batching_rate() {
+        zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_max_commitment_batching_rate(&invalid_authority, &mut hashing_account, 0, 1),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        assert_eq!(
+            set_max_commitment_batching_rate(
+                &authority,
+                &mut hashing_account,
+                0,
+                MAX_COMMITMENT_BATCHING_RATE as u32 + 1
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        set_max_commitment_batching_rate(
+            &authority,
+            &mut hashing_account,
+            0,
+            MAX_COMMITMENT_BATCHING_RATE as u32,
+        )
+        .unwrap();
+        assert_eq!(
+            hashing_account.get_max_batching_rate(),
+            MAX_COMMITMENT_BATCHING_RATE as u32
+        );
+    }
+
+    #[test]
+    fn test_set_required_warden_attestation_level() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_required_warden_attestation_level(&invalid_authority, &mut governor, 0),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        assert_eq!(
+            set_required_warden_attestation_level(&authority, &mut governor, 2).is_ok(),
+            cfg!(feature = "permissioned")
+        );
+
+        set_required_warden_attestation_level(&authority, &mut governor, 0).unwrap();
+        assert_eq!(governor.get_required_warden_attestation_level(), 0);
+    }
+
+    #[test]
+    fn test_set_supported_protocol_versions() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_supported_protocol_versions(&invalid_authority, &mut governor, 0, 1),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        for (min, max) in [(1, 0), (0, PROTOCOL_VERSION + 1)] {
+            assert_eq!(
+                set_supported_protocol_versions(&authority, &mut governor, min, max),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        set_supported_protocol_versions(
+            &authority,
+            &mut governor,
+            PROTOCOL_VERSION,
+            PROTOCOL_VERSION,
+        )
+        .unwrap();
+        assert!(!governor.is_supported_protocol_version(0));
+        assert!(governor.is_supported_protocol_version(PROTOCOL_VERSION));
+    }
+
+    #[test]
+    fn test_set_tree_age_amount_limit() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_tree_age_amount_limit(&invalid_authority, &mut governor, 0, 1),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        assert_eq!(
+            set_tree_age_amount_limit(&authority, &mut governor, TOKEN_COUNT as u16, 1),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        assert_eq!(governor.max_amount_for_tree_age(0, 10), None);
+        set_tree_age_amount_limit(&authority, &mut governor, 0, 1_000).unwrap();
+        assert_eq!(governor.max_amount_for_tree_age(0, 10), Some(10_000));
+        assert_eq!(
+            governor.max_amount_for_tree_age(0, u32::MAX),
+            Some(4_294_967_295_000)
+        );
+        assert_eq!(governor.max_amount_for_tree_age(1, 10), None);
+
+        set_tree_age_amount_limit(&authority, &mut governor, 0, 0).unwrap();
+        assert_eq!(governor.max_amount_for_tree_age(0, 10), None);
+    }
+
+    #[test]
+    fn test_set_metadata_size_class() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_metadata_size_class(&invalid_authority, &mut governor, 0, 64, 10_000),
+            Err(ProgramError::MissingRequiredSignature)
         );
+
+        account_info!(authority, crate::ID, true);
+        for (class_index, max_ciphertext_len, fee_multiplier_bps) in [
+            (METADATA_SIZE_CLASS_COUNT as u8, 64, 10_000),
+            (0, MAX_METADATA_CIPHERTEXT_LEN + 1, 10_000),
+            (0, 64, MIN_METADATA_FEE_MULTIPLIER_BPS - 1),
+        ] {
+            assert_eq!(
+                set_metadata_size_class(
+                    &authority,
+                    &mut governor,
+                    class_index,
+                    max_ciphertext_len,
+                    fee_multiplier_bps
+                ),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        // No enabled class
         assert_eq!(
-            enable_storage_child_account(&mut storage_account, &child_account, 0),
-            Err(ProgramError::InvalidAccountData)
+            governor.metadata_size_class(0),
+            Err(ElusivError::InvalidInstructionData.into())
         );
 
-        storage_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+        set_metadata_size_class(&authority, &mut governor, 0, 64, 10_000).unwrap();
+        set_metadata_size_class(&authority, &mut governor, 2, 256, 15_000).unwrap();
 
-        // Account has already been setup
-        account_info!(
-            child_account,
-            Pubkey::new_unique(),
-            vec![0; StorageChildAccount::SIZE]
+        // The classes need to stay ordered
+        assert_eq!(
+            set_metadata_size_class(&authority, &mut governor, 1, 64, 10_000),
+            Err(ElusivError::InvalidInstructionData.into())
         );
         assert_eq!(
-            enable_storage_child_account(&mut storage_account, &child_account, 0),
-            Err(ElusivError::ChildAccountAlreadyExists.into())
+            set_metadata_size_class(&authority, &mut governor, 3, 128, 10_000),
+            Err(ElusivError::InvalidInstructionData.into())
         );
+        set_metadata_size_class(&authority, &mut governor, 1, 128, 12_000).unwrap();
 
-        // Success at different index
+        assert_eq!(governor.metadata_size_class(0), Ok(0));
+        assert_eq!(governor.metadata_size_class(64), Ok(0));
+        assert_eq!(governor.metadata_size_class(65), Ok(1));
+        assert_eq!(governor.metadata_size_class(256), Ok(2));
         assert_eq!(
-            enable_storage_child_account(&mut storage_account, &child_account, 3),
-            Ok(())
+            governor.metadata_size_class(257),
+            Err(ElusivError::InvalidInstructionData.into())
         );
-        assert_eq!(child_account.data.borrow()[0], 1);
 
-        // Account already is use
+        // Larger ciphertexts pay for the rent of their class
+        let rent = Rent::default();
+        let byte_rent = |len: usize| rent.minimum_balance(len) - rent.minimum_balance(0);
         assert_eq!(
-            enable_storage_child_account(&mut storage_account, &child_account, 1),
-            Err(ProgramError::AccountAlreadyInitialized)
+            governor.metadata_ciphertext_fee(10, &rent),
+            Ok(byte_rent(64))
+        );
+        assert_eq!(
+            governor.metadata_ciphertext_fee(200, &rent),
+            Ok(byte_rent(256) * 3 / 2)
         );
+
+        // Disabling a class
+        set_metadata_size_class(&authority, &mut governor, 1, 0, 10_000).unwrap();
+        assert_eq!(governor.metadata_size_class(65), Ok(2));
     }
 
     #[test]
-    fn test_enable_nullifier_child_account() {
-        let mut data = vec![0; NullifierAccount::SIZE];
-        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
+    fn test_set_token_fee_spread() {
+        zero_program_account!(mut governor, GovernorAccount);
 
-        // Account has invalid size
-        account_info!(
-            child_account,
-            Pubkey::new_unique(),
-            vec![0; NullifierChildAccount::SIZE - 1]
-        );
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
         assert_eq!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 0),
-            Err(ProgramError::InvalidAccountData)
+            set_token_fee_spread(&invalid_authority, &mut governor, 100),
+            Err(ProgramError::MissingRequiredSignature)
         );
 
-        nullifier_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
-
-        // Account has already been setup
-        account_info!(
-            child_account,
-            Pubkey::new_unique(),
-            vec![0; NullifierChildAccount::SIZE]
-        );
+        account_info!(authority, crate::ID, true);
         assert_eq!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 0),
-            Err(ElusivError::ChildAccountAlreadyExists.into())
+            set_token_fee_spread(&authority, &mut governor, MAX_TOKEN_FEE_SPREAD_BPS + 1),
+            Err(ElusivError::InvalidInstructionData.into())
         );
 
-        // Success at different index with
+        set_token_fee_spread(&authority, &mut governor, MAX_TOKEN_FEE_SPREAD_BPS).unwrap();
         assert_eq!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 3),
-            Ok(())
+            governor.get_token_fee_spread_bps(),
+            MAX_TOKEN_FEE_SPREAD_BPS
         );
-        assert_eq!(child_account.data.borrow()[0], 1);
+    }
 
-        // Account already is use
+    #[test]
+    fn test_set_hashing_fair_share() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
         assert_eq!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 1),
-            Err(ProgramError::AccountAlreadyInitialized)
+            set_hashing_fair_share(&invalid_authority, &mut governor, 5_000, 100),
+            Err(ProgramError::MissingRequiredSignature)
         );
+
+        account_info!(authority, crate::ID, true);
+        for (share_bps, window_slots) in [
+            (MIN_HASHING_FAIR_SHARE_BPS - 1, 100),
+            (10_001, 100),
+            (5_000, 0),
+        ] {
+            assert_eq!(
+                set_hashing_fair_share(&authority, &mut governor, share_bps, window_slots),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        set_hashing_fair_share(&authority, &mut governor, 5_000, 100).unwrap();
+        assert_eq!(governor.get_hashing_fair_share_bps(), 5_000);
+        assert_eq!(governor.get_hashing_fair_share_window(), 100);
+
+        // Disabling
+        set_hashing_fair_share(&authority, &mut governor, 0, 0).unwrap();
+        assert_eq!(governor.get_hashing_fair_share_bps(), 0);
     }
 
     #[test]
-    fn test_is_mt_full() {
-        let mut data = vec![0; StorageAccount::SIZE];
-        let mut storage_account = StorageAccount::new(&mut data).unwrap();
-        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+    fn test_set_require_warden_nonces() {
+        zero_program_account!(mut governor, GovernorAccount);
 
-        let mut q_data = vec![0; CommitmentQueueAccount::SIZE];
-        let mut queue = CommitmentQueueAccount::new(&mut q_data).unwrap();
-        let mut queue = CommitmentQueue::new(&mut queue);
-        queue
-            .enqueue(CommitmentHashRequest {
-                min_batching_rate: 1,
-                commitment: [0; 32],
-                fee_version: 0,
-            })
-            .unwrap();
-        queue
-            .enqueue(CommitmentHashRequest {
-                min_batching_rate: 1,
-                commitment: [0; 32],
-                fee_version: 0,
-            })
-            .unwrap();
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_require_warden_nonces(&invalid_authority, &mut governor, true),
+            Err(ProgramError::MissingRequiredSignature)
+        );
 
-        assert!(is_mt_full(&storage_account, &queue).unwrap());
+        account_info!(authority, crate::ID, true);
+        set_require_warden_nonces(&authority, &mut governor, true).unwrap();
+        assert!(governor.get_require_warden_nonces());
 
-        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 3));
-        assert!(!is_mt_full(&storage_account, &queue).unwrap());
+        set_require_warden_nonces(&authority, &mut governor, false).unwrap();
+        assert!(!governor.get_require_warden_nonces());
+    }
 
-        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 2));
-        assert!(is_mt_full(&storage_account, &queue).unwrap());
+    #[test]
+    fn test_set_apa_enforcement() {
+        zero_program_account!(mut governor, GovernorAccount);
+        assert_eq!(governor.get_apa_enforcement(), ApaEnforcement::Disabled);
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_apa_enforcement(&invalid_authority, &mut governor, ApaEnforcement::Block),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        set_apa_enforcement(&authority, &mut governor, ApaEnforcement::Quarantine).unwrap();
+        assert_eq!(governor.get_apa_enforcement(), ApaEnforcement::Quarantine);
+
+        set_apa_enforcement(&authority, &mut governor, ApaEnforcement::Disabled).unwrap();
+        assert_eq!(governor.get_apa_enforcement(), ApaEnforcement::Disabled);
     }
 
     #[test]
-    #[should_panic]
-    fn test_archive_closed_merkle_tree() {
-        test_account_info!(payer, 0);
-        let mut data = vec![0; StorageAccount::SIZE];
-        let mut storage_account = StorageAccount::new(&mut data).unwrap();
-        let mut data = vec![0; NullifierAccount::SIZE];
-        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
-        test_account_info!(archived_tree_account, 0);
+    fn test_set_protocol_pause() {
+        zero_program_account!(mut governor, GovernorAccount);
 
-        archive_closed_merkle_tree(
-            &payer,
-            &mut storage_account,
-            &mut nullifier_account,
-            &archived_tree_account,
-            0,
-        )
-        .unwrap();
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            set_protocol_pause(&invalid_authority, &mut governor, true),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        set_protocol_pause(&authority, &mut governor, true).unwrap();
+        assert!(governor.get_is_protocol_paused());
+
+        set_protocol_pause(&authority, &mut governor, false).unwrap();
+        assert!(!governor.get_is_protocol_paused());
     }
 
     #[test]
-    #[should_panic]
-    fn test_upgrade_governor_state() {
-        test_account_info!(authority, 0);
-        zero_program_account!(mut governor_account, GovernorAccount);
-        zero_program_account!(commitment_queue, CommitmentQueueAccount);
+    fn test_sweep_pool_dust() {
+        zero_program_account!(mut metrics, MetricsAccount);
+        test_pda_account_info!(pool, PoolAccount);
+        test_pda_account_info!(fee_collector, FeeCollectorAccount);
+        test_account_info!(token_program, 0);
+        account_info!(authority, crate::ID, true);
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+
+        let threshold = pool_dust_threshold(LAMPORTS_TOKEN_ID).unwrap();
+        assert_eq!(threshold, 100_000);
+        assert_eq!(pool_dust_threshold(USDC_TOKEN_ID).unwrap(), 100);
+
+        macro_rules! sweep {
+            ($authority: ident, $pool_account: ident, $amount: expr) => {
+                sweep_pool_dust(
+                    &$authority,
+                    &pool,
+                    &$pool_account,
+                    &fee_collector,
+                    &fee_collector,
+                    &mut metrics,
+                    &token_program,
+                    LAMPORTS_TOKEN_ID,
+                    $amount,
+                )
+            };
+        }
 
-        upgrade_governor_state(&authority, &mut governor_account, &commitment_queue, 1, 1).unwrap();
+        assert_eq!(
+            sweep!(invalid_authority, pool, 1),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // Not dust
+        for amount in [0, threshold] {
+            assert_eq!(
+                sweep!(authority, pool, amount),
+                Err(ElusivError::InvalidAmount.into())
+            );
+        }
+
+        // Invalid pool_account
+        assert_eq!(
+            sweep!(authority, fee_collector, 1),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // The pool only holds `threshold + 5` lamports exceeding its liability
+        let pool_lamports = pool.lamports();
+        let fee_collector_lamports = fee_collector.lamports();
+        metrics
+            .record_store(LAMPORTS_TOKEN_ID, pool_lamports - threshold - 5)
+            .unwrap();
+        assert_eq!(
+            pool_dust(&pool, &pool, &metrics, LAMPORTS_TOKEN_ID),
+            Ok(threshold + 5)
+        );
+
+        sweep!(authority, pool, threshold - 1).unwrap();
+
+        assert_eq!(pool.lamports(), pool_lamports - threshold + 1);
+        assert_eq!(
+            fee_collector.lamports(),
+            fee_collector_lamports + threshold - 1
+        );
+        assert_eq!(
+            metrics.get_dust_swept(LAMPORTS_TOKEN_ID as usize),
+            threshold - 1
+        );
+
+        // Repeated sweeps can't exceed the remaining dust
+        assert_eq!(pool_dust(&pool, &pool, &metrics, LAMPORTS_TOKEN_ID), Ok(6));
+        assert_eq!(
+            sweep!(authority, pool, 7),
+            Err(ElusivError::InvalidAmount.into())
+        );
+        sweep!(authority, pool, 6).unwrap();
+        assert_eq!(
+            sweep!(authority, pool, 1),
+            Err(ElusivError::InvalidAmount.into())
+        );
+
+        assert_eq!(pool.lamports(), pool_lamports - threshold - 5);
+        assert_eq!(
+            metrics.get_dust_swept(LAMPORTS_TOKEN_ID as usize),
+            threshold + 5
+        );
     }
 
     #[test]
@@ -659,4 +3211,434 @@ mod tests {
         let mut map = Map::new(data);
         assert!(map.is_empty());
     }
+
+    #[test]
+    fn test_reset_nullifier_child_account() {
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![1; NullifierChildAccount::SIZE]
+        );
+        reset_nullifier_child_account(&child_account).unwrap();
+
+        let data = &mut child_account.data.borrow_mut()[..];
+        let (_, inner_data) = split_child_account_data_mut(data).unwrap();
+        let (map_data, bloom_filter) = split_nullifier_child_data(inner_data);
+        assert!(NullifierMap::new(map_data).is_empty());
+        assert!(bloom_filter.unwrap().iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn test_verify_token_config() {
+        zero_program_account!(mut token_registry, TokenRegistryAccount);
+        let usdc = elusiv_token(USDC_TOKEN_ID).unwrap();
+
+        let mint_data = |decimals: u8| {
+            let mint = spl_token::state::Mint {
+                mint_authority: COption::Some(Pubkey::new_unique()),
+                supply: 0,
+                decimals,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            };
+            let mut data = vec![0; spl_token::state::Mint::LEN];
+            mint.pack_into_slice(&mut data[..]);
+            data
+        };
+
+        // Invalid mint
+        account_info!(
+            invalid_mint,
+            Pubkey::new_unique(),
+            mint_data(usdc.decimals),
+            spl_token::id(),
+            false
+        );
+        assert_eq!(
+            verify_token_config(&invalid_mint, &mut token_registry, USDC_TOKEN_ID),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid owner
+        account_info!(invalid_owner, usdc.mint, mint_data(usdc.decimals));
+        assert_eq!(
+            verify_token_config(&invalid_owner, &mut token_registry, USDC_TOKEN_ID),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Mismatched decimals
+        account_info!(
+            mismatched_mint,
+            usdc.mint,
+            mint_data(usdc.decimals + 1),
+            spl_token::id(),
+            false
+        );
+        assert_eq!(
+            verify_token_config(&mismatched_mint, &mut token_registry, USDC_TOKEN_ID),
+            Err(ElusivError::InvalidTokenConfig.into())
+        );
+
+        account_info!(
+            mint,
+            usdc.mint,
+            mint_data(usdc.decimals),
+            spl_token::id(),
+            false
+        );
+        verify_token_config(&mint, &mut token_registry, USDC_TOKEN_ID).unwrap();
+        assert_eq!(token_registry.enforce_verified_token(USDC_TOKEN_ID), Ok(()));
+        assert_eq!(
+            token_registry.get_decimals(USDC_TOKEN_ID as usize),
+            usdc.decimals
+        );
+        assert!(token_registry.get_has_mint_authority(USDC_TOKEN_ID as usize));
+        assert!(!token_registry.get_has_freeze_authority(USDC_TOKEN_ID as usize));
+
+        // Only once
+        assert_eq!(
+            verify_token_config(&mint, &mut token_registry, USDC_TOKEN_ID),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // Lamports
+        account_info!(lamports_mint, system_program::id());
+        verify_token_config(&lamports_mint, &mut token_registry, LAMPORTS_TOKEN_ID).unwrap();
+        assert_eq!(
+            token_registry.enforce_verified_token(LAMPORTS_TOKEN_ID),
+            Ok(())
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "mainnet"))]
+    fn test_reset_protocol_state() {
+        use crate::processor::{reset_verification_account, ProofRequest};
+        use crate::state::proof::{VerificationAccount, VerificationAccountData};
+
+        account_info!(authority, crate::ID, true);
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        account_info!(recipient, Pubkey::new_unique());
+        account_info!(fee_payer, Pubkey::new_unique());
+        test_account_info!(any, 0);
+        zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+        zero_program_account!(mut metadata_account, MetadataAccount);
+        zero_program_account!(mut metrics, MetricsAccount);
+        zero_program_account!(mut governor, GovernorAccount);
+        parent_account!(mut storage_account, StorageAccount);
+        parent_account!(mut nullifier_account, NullifierAccount);
+
+        hashing_account.set_is_active(&true);
+        CommitmentQueue::new(&mut commitment_queue)
+            .enqueue_for_fee_payer(
+                CommitmentHashRequest {
+                    min_batching_rate: 0,
+                    commitment: [1; 32],
+                    fee_version: 0,
+                },
+                &[2; 32],
+            )
+            .unwrap();
+        crate::state::metadata::MetadataQueue::new(&mut metadata_queue)
+            .enqueue([3; 17])
+            .unwrap();
+        metadata_account.set_next_metadata_ptr(&1);
+        metrics.record_store(USDC_TOKEN_ID, 100).unwrap();
+
+        storage_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+        storage_account.set_child_pubkey(3, ElusivOption::Some(Pubkey::new_unique()));
+        storage_account.set_next_commitment_ptr(&2);
+        storage_account.set_trees_count(&1);
+
+        nullifier_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+        nullifier_account
+            .try_insert_nullifier_hash([4; 32])
+            .unwrap();
+
+        // A pending verification (initialized before the reset)
+        test_account_info!(verification_account, VerificationAccount::SIZE);
+        let (nullifier_duplicate_pda, bump) = {
+            pda_account!(mut v_acc, VerificationAccount, verification_account);
+            v_acc.set_other_data(&VerificationAccountData {
+                fee_payer: RawU256::new(fee_payer.key.to_bytes()),
+                reset_generation: governor.get_reset_generation(),
+                ..Default::default()
+            });
+
+            match v_acc.get_request() {
+                ProofRequest::Send(public_inputs) => {
+                    public_inputs.join_split.nullifier_duplicate_pda()
+                }
+                ProofRequest::Migrate(public_inputs) => {
+                    public_inputs.join_split.nullifier_duplicate_pda()
+                }
+            }
+        };
+        account_info!(
+            nullifier_duplicate_account,
+            nullifier_duplicate_pda,
+            vec![bump]
+        );
+
+        macro_rules! reset_verification {
+            () => {
+                reset_verification_account(
+                    &authority,
+                    &fee_payer,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &governor,
+                    &verification_account,
+                    &nullifier_duplicate_account,
+                    &any,
+                    &any,
+                    0,
+                )
+            };
+        }
+
+        // Storage can only be reset after the commitment queues
+        assert_eq!(
+            reset_storage_account(
+                &authority,
+                &recipient,
+                &mut storage_account,
+                &hashing_account,
+                &commitment_queue,
+                0
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // The pending verification is still valid
+        assert_eq!(
+            reset_verification!(),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // Invalid authority
+        assert_eq!(
+            reset_commitment_queues(
+                &invalid_authority,
+                &mut hashing_account,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &mut metadata_account,
+                &mut metrics,
+                &mut governor,
+                0
+            ),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // Protocol is not paused
+        assert_eq!(
+            reset_commitment_queues(
+                &authority,
+                &mut hashing_account,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &mut metadata_account,
+                &mut metrics,
+                &mut governor,
+                0
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        set_protocol_pause(&authority, &mut governor, true).unwrap();
+        reset_commitment_queues(
+            &authority,
+            &mut hashing_account,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &mut metadata_account,
+            &mut metrics,
+            &mut governor,
+            0,
+        )
+        .unwrap();
+        assert!(!hashing_account.get_is_active());
+        assert_eq!(commitment_queue.queue_len(), 0);
+        assert_eq!(
+            CommitmentQueue::new(&mut commitment_queue).in_flight_count(&[2; 32]),
+            0
+        );
+        assert!(crate::state::metadata::MetadataQueue::new(&mut metadata_queue).is_empty());
+        assert_eq!(metadata_account.get_next_metadata_ptr(), 0);
+        assert_eq!(metrics.get_volume_in(USDC_TOKEN_ID as usize), 0);
+        assert_eq!(governor.get_reset_generation(), 1);
+
+        // Nullifiers can only be reset after the storage-account
+        assert_eq!(
+            reset_nullifier_account(&authority, &storage_account, &mut nullifier_account, 0),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        let recipient_lamports = recipient.lamports();
+        let child_lamports = unsafe { storage_account.get_child_account_unsafe(0) }
+            .unwrap()
+            .lamports();
+        reset_storage_account(
+            &authority,
+            &recipient,
+            &mut storage_account,
+            &hashing_account,
+            &commitment_queue,
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            recipient.lamports(),
+            recipient_lamports + 2 * child_lamports
+        );
+        for i in 0..StorageAccount::COUNT {
+            assert!(storage_account.get_child_pubkey(i).is_none());
+        }
+        assert_eq!(
+            unsafe { storage_account.get_child_account_unsafe(3) }
+                .unwrap()
+                .lamports(),
+            0
+        );
+        assert_eq!(storage_account.get_next_commitment_ptr(), 0);
+        assert_eq!(storage_account.get_trees_count(), 0);
+
+        assert_eq!(
+            reset_nullifier_account(
+                &invalid_authority,
+                &storage_account,
+                &mut nullifier_account,
+                0
+            ),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // The `StorageAccount` of a different MT-shard
+        assert_eq!(
+            reset_nullifier_account(
+                &authority,
+                &storage_account,
+                &mut nullifier_account,
+                sharded_mt_index(1, 0)
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        assert!(!nullifier_account
+            .can_insert_nullifier_hash([4; 32])
+            .unwrap());
+        reset_nullifier_account(&authority, &storage_account, &mut nullifier_account, 0).unwrap();
+        assert_eq!(nullifier_account.get_nullifier_hash_count(), 0);
+        assert!(nullifier_account.get_max_values(0).option().is_none());
+        assert!(nullifier_account
+            .can_insert_nullifier_hash([4; 32])
+            .unwrap());
+
+        // The pending verification has been invalidated by the queue reset
+        let fee_payer_lamports = fee_payer.lamports();
+        let verification_lamports =
+            verification_account.lamports() + nullifier_duplicate_account.lamports();
+        reset_verification!().unwrap();
+        assert_eq!(
+            fee_payer.lamports(),
+            fee_payer_lamports + verification_lamports
+        );
+        assert_eq!(verification_account.lamports(), 0);
+
+        // Genesis state
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; StorageChildAccount::SIZE]
+        );
+        enable_storage_child_account(&mut storage_account, &child_account, 0, 0).unwrap();
+        assert_eq!(
+            storage_account.get_child_pubkey(0).option(),
+            Some(*child_account.key)
+        );
+
+        set_protocol_pause(&authority, &mut governor, false).unwrap();
+        assert!(!governor.get_is_protocol_paused());
+    }
+
+    #[test]
+    fn test_export_import_account_chunk() {
+        zero_program_account!(mut export_manifest, MigrationManifestAccount);
+        zero_program_account!(mut import_manifest, MigrationManifestAccount);
+        account_info!(authority, crate::ID, vec![]);
+        account_info!(invalid_authority, Pubkey::new_unique(), vec![]);
+        let data: Vec<u8> = (0..100).collect();
+        account_info!(source, Pubkey::new_unique(), data.clone());
+        test_account_info!(target, data.len());
+        test_account_info!(foreign_account, data.len(), Pubkey::new_unique());
+
+        assert_eq!(
+            export_account_chunk(&invalid_authority, &mut export_manifest, &source, 0, 0, 50),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            export_account_chunk(&authority, &mut export_manifest, &foreign_account, 0, 0, 50),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(
+            export_account_chunk(
+                &authority,
+                &mut export_manifest,
+                &source,
+                0,
+                0,
+                MAX_MIGRATION_CHUNK_SIZE as u32 + 1
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        export_account_chunk(&authority, &mut export_manifest, &source, 0, 0, 60).unwrap();
+        export_account_chunk(&authority, &mut export_manifest, &source, 0, 60, 40).unwrap();
+        assert!(export_manifest.is_complete());
+
+        let expected_hash = export_manifest.get_hashes(0);
+        import_account_chunk(
+            &authority,
+            &mut import_manifest,
+            &target,
+            0,
+            0,
+            expected_hash,
+            data[..60].to_vec(),
+        )
+        .unwrap();
+        assert!(!import_manifest.is_complete());
+
+        // Invalid data
+        assert_eq!(
+            import_account_chunk(
+                &authority,
+                &mut import_manifest,
+                &target,
+                0,
+                60,
+                expected_hash,
+                vec![0; 40],
+            ),
+            Err(ElusivError::InputsMismatch.into())
+        );
+
+        import_account_chunk(
+            &authority,
+            &mut import_manifest,
+            &target,
+            0,
+            60,
+            expected_hash,
+            data[60..].to_vec(),
+        )
+        .unwrap();
+        assert!(import_manifest.is_complete());
+        assert_eq!(&target.data.borrow()[..], &data[..]);
+    }
 }
@@ -1,40 +1,51 @@
-use super::utils::{close_account, open_pda_account_with_offset};
+use super::utils::{
+    close_account, current_slot, current_timestamp, open_pda_account_with_associated_pubkey,
+    open_pda_account_with_offset_and_rent_owner,
+};
 use crate::buffer::RingBuffer;
-use crate::bytes::usize_as_u32_safe;
+use crate::bytes::{usize_as_u32_safe, BorshSerDeSized, ElusivOption};
 use crate::commitment::{
     commitment_hash_computation_instructions, commitments_per_batch,
     compute_base_commitment_hash_partial, compute_commitment_hash_partial,
     BaseCommitmentHashComputation, MAX_HT_COMMITMENTS,
 };
 use crate::error::ElusivError;
+use crate::event::ElusivEvent;
 use crate::fields::{fr_to_u256_le, is_element_scalar_field, u256_to_big_uint, u256_to_fr_skip_mr};
 use crate::macros::{guard, pda_account, BorshSerDeSized};
 use crate::processor::utils::{
-    transfer_lamports_from_pda_checked, transfer_token, transfer_token_from_pda,
-    transfer_with_system_program, verify_program_token_account,
+    consume_warden_nonce, record_warden_usage, transfer_lamports_from_pda_checked, transfer_token,
+    transfer_token_from_pda, transfer_with_system_program, verify_basic_warden,
+    verify_program_token_account, verify_warden_attestation, MATH_ERR,
 };
 use crate::state::commitment::{
-    BaseCommitmentBufferAccount, BaseCommitmentHashingAccount, CommitmentHashingAccount,
-    CommitmentQueue, CommitmentQueueAccount, COMMITMENT_BUFFER_LEN,
+    BaseCommitmentBufferAccount, BaseCommitmentHashingAccount, BaseCommitmentHashingSlotsAccount,
+    CommitmentHashFeeEscrow, CommitmentHashingAccount, CommitmentQueue, CommitmentQueueAccount,
+    COMMITMENT_BUFFER_LEN,
 };
 use crate::state::governor::FeeCollectorAccount;
 use crate::state::metadata::{
-    CommitmentMetadata, MetadataAccount, MetadataQueue, MetadataQueueAccount,
+    CommitmentMetadata, CommitmentMetadataAccount, MetadataAccount, MetadataQueue,
+    MetadataQueueAccount,
 };
-use crate::state::storage::{StorageAccount, MT_COMMITMENT_COUNT};
+use crate::state::metrics::MetricsAccount;
+use crate::state::storage::{StorageAccount, MT_HEIGHT};
 use crate::state::{
-    fee::FeeAccount,
-    governor::GovernorAccount,
+    fee::{token_fee_spread, FeeAccount},
+    governor::{GovernorAccount, TokenRegistryAccount},
     queue::{Queue, RingQueue},
 };
-use crate::token::{Token, TokenPrice};
+use crate::token::Token;
 use crate::types::{RawU256, U256};
 use ark_bn254::Fr;
 use ark_ff::BigInteger256;
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_computation::PartialComputation;
 use elusiv_types::UnverifiedAccountInfo;
-use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program::set_return_data,
+    program_error::ProgramError, pubkey::Pubkey,
+};
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -93,6 +104,10 @@ pub const ZERO_COMMITMENT_RAW: U256 = [
 ///     - opens a [`BaseCommitmentHashingAccount`] for the computation,
 ///     - performs the hash computation,
 ///     - swaps fee from token into lamports (for tx compensation of the commitment hash).
+///
+/// A `warden_nonce` is consumed in the fee payer's [`crate::state::warden_nonce::WardenNonceAccount`] before the hashing account is opened (see [`crate::processor::init_verification`]).
+///
+/// The hashing account instance can not be reserved by another fee payer (see [`acquire_hashing_slot`]).
 #[allow(clippy::too_many_arguments)]
 pub fn store_base_commitment<'a, 'b>(
     sender: &AccountInfo<'a>,
@@ -108,20 +123,51 @@ pub fn store_base_commitment<'a, 'b>(
     token_usd_price_account: &AccountInfo,
 
     governor: &GovernorAccount,
+    token_registry: &TokenRegistryAccount,
     storage: &StorageAccount,
     mut hashing_account: UnverifiedAccountInfo<'b, 'a>,
+    hashing_slots: &mut BaseCommitmentHashingSlotsAccount,
     base_commitment_buffer: &mut BaseCommitmentBufferAccount,
     token_program: &AccountInfo<'a>,
     system_program: &AccountInfo<'a>,
+    metrics: &mut MetricsAccount,
+    warden_attestation_account: &AccountInfo,
+    warden_nonce_account: &AccountInfo,
 
+    protocol_version: u8,
     hash_account_index: u32,
     hash_account_bump: u8,
     request: BaseCommitmentHashRequest,
     metadata: CommitmentMetadata,
+    warden_nonce: ElusivOption<u64>,
 ) -> ProgramResult {
+    guard!(
+        !governor.get_is_protocol_paused(),
+        ElusivError::ProtocolPaused
+    );
+    guard!(
+        governor.is_supported_protocol_version(protocol_version),
+        ElusivError::UnsupportedProtocolVersion
+    );
+
+    consume_warden_nonce(
+        fee_payer.key,
+        warden_nonce_account,
+        warden_nonce,
+        governor.get_require_warden_nonces(),
+    )?;
+
     let token_id = request.token_id;
     let amount = Token::new_checked(token_id, request.amount)?;
-    let price = TokenPrice::new(sol_usd_price_account, token_usd_price_account, token_id)?;
+    let price =
+        token_registry.token_price(sol_usd_price_account, token_usd_price_account, token_id)?;
+
+    // In permissioned mode only attested wardens can relay stores
+    verify_warden_attestation(
+        fee_payer.key,
+        warden_attestation_account,
+        governor.get_required_warden_attestation_level(),
+    )?;
 
     guard!(
         is_element_scalar_field(u256_to_big_uint(&request.base_commitment.skip_mr())),
@@ -144,26 +190,25 @@ pub fn store_base_commitment<'a, 'b>(
         ElusivError::InvalidInstructionData
     );
 
-    guard!(
-        request.fee_version == governor.get_fee_version(),
-        ElusivError::InvalidFeeVersion
-    );
+    let fee = governor.program_fee_for_quote(request.fee_version, current_slot()?)?;
     guard!(
         request.min_batching_rate == governor.get_commitment_batching_rate(),
         ElusivError::InvalidBatchingRate
     );
 
-    let fee = governor.get_program_fee();
     let subvention = fee
         .base_commitment_subvention
         .into_token(&price, token_id)?;
     let computation_fee = (fee.base_commitment_hash_computation_fee()
         + fee.commitment_hash_computation_fee(request.min_batching_rate))?;
     let computation_fee_token = computation_fee.into_token(&price, token_id)?;
-    let network_fee = Token::new(
+
+    // Settling the computation fee in the stored token is charged a spread (collected with the network fee)
+    let spread = token_fee_spread(computation_fee_token, governor.get_token_fee_spread_bps());
+    let network_fee = (Token::new(
         token_id,
         fee.base_commitment_network_fee.calc(amount.amount()),
-    );
+    ) + spread)?;
 
     verify_program_token_account(pool, pool_account, token_id)?;
     verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
@@ -192,10 +237,14 @@ pub fn store_base_commitment<'a, 'b>(
     // `sender` transfers `amount` to `pool` (token)
     transfer_token(sender, sender_account, pool_account, token_program, amount)?;
 
-    // `fee_payer` rents `hashing_account`
-    open_pda_account_with_offset::<BaseCommitmentHashingAccount>(
+    // The instance can not be reserved by another fee payer
+    hashing_slots.open(hash_account_index, fee_payer.key, current_slot()?)?;
+
+    // `fee_payer` rents `hashing_account` (and is reimbursed once the hash is finalized)
+    let rent = open_pda_account_with_offset_and_rent_owner::<BaseCommitmentHashingAccount>(
         &crate::id(),
         fee_payer,
+        fee_payer.key,
         hashing_account.get_unsafe(),
         hash_account_index,
         Some(hash_account_bump),
@@ -223,7 +272,239 @@ pub fn store_base_commitment<'a, 'b>(
         BaseCommitmentHashingAccount,
         hashing_account.get_safe()?
     );
-    hashing_account.setup(request, metadata, fee_payer.key.to_bytes())
+    hashing_account.setup(request, metadata, rent.owner.to_bytes())?;
+    hashing_account.set_setup_timestamp(&current_timestamp()?);
+
+    metrics.record_store(token_id, amount.amount())
+}
+
+/// The maximum number of base commitments stored by [`store_base_commitment_batch`]
+pub const MAX_STORE_BATCH_SIZE: usize = 4;
+
+/// Stores up to [`MAX_STORE_BATCH_SIZE`] base commitments of the same `sender` with a single fee settlement
+///
+/// # Notes
+///
+/// All requests need to share the same `token_id`, `fee_version` and `min_batching_rate`.
+///
+/// The fees and amounts of all requests are accumulated and transferred at once, each request still requires its own [`BaseCommitmentHashingAccount`].
+///
+/// Unused `hashing_accounts` can be any accounts.
+#[allow(clippy::too_many_arguments)]
+pub fn store_base_commitment_batch<'a>(
+    sender: &AccountInfo<'a>,
+    sender_account: &AccountInfo<'a>,
+    fee_payer: &AccountInfo<'a>,
+    fee_payer_account: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+
+    sol_usd_price_account: &AccountInfo,
+    token_usd_price_account: &AccountInfo,
+
+    governor: &GovernorAccount,
+    token_registry: &TokenRegistryAccount,
+    storage: &StorageAccount,
+    base_commitment_buffer: &mut BaseCommitmentBufferAccount,
+    token_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    metrics: &mut MetricsAccount,
+    warden_attestation_account: &AccountInfo,
+    warden_nonce_account: &AccountInfo,
+    hashing_slots: &mut BaseCommitmentHashingSlotsAccount,
+    hashing_account0: &AccountInfo<'a>,
+    hashing_account1: &AccountInfo<'a>,
+    hashing_account2: &AccountInfo<'a>,
+    hashing_account3: &AccountInfo<'a>,
+
+    protocol_version: u8,
+    hash_account_indices: [u32; MAX_STORE_BATCH_SIZE],
+    hash_account_bumps: [u8; MAX_STORE_BATCH_SIZE],
+    requests: Vec<(BaseCommitmentHashRequest, CommitmentMetadata)>,
+    warden_nonce: ElusivOption<u64>,
+) -> ProgramResult {
+    guard!(
+        !governor.get_is_protocol_paused(),
+        ElusivError::ProtocolPaused
+    );
+    guard!(
+        governor.is_supported_protocol_version(protocol_version),
+        ElusivError::UnsupportedProtocolVersion
+    );
+    guard!(
+        !requests.is_empty() && requests.len() <= MAX_STORE_BATCH_SIZE,
+        ElusivError::InvalidInstructionData
+    );
+
+    consume_warden_nonce(
+        fee_payer.key,
+        warden_nonce_account,
+        warden_nonce,
+        governor.get_require_warden_nonces(),
+    )?;
+
+    // In permissioned mode only attested wardens can relay stores
+    verify_warden_attestation(
+        fee_payer.key,
+        warden_attestation_account,
+        governor.get_required_warden_attestation_level(),
+    )?;
+
+    let first = &requests[0].0;
+    let token_id = first.token_id;
+    let price =
+        token_registry.token_price(sol_usd_price_account, token_usd_price_account, token_id)?;
+
+    let fee = governor.program_fee_for_quote(first.fee_version, current_slot()?)?;
+    guard!(
+        first.min_batching_rate == governor.get_commitment_batching_rate(),
+        ElusivError::InvalidBatchingRate
+    );
+
+    let subvention = fee
+        .base_commitment_subvention
+        .into_token(&price, token_id)?;
+    let computation_fee = (fee.base_commitment_hash_computation_fee()
+        + fee.commitment_hash_computation_fee(first.min_batching_rate))?;
+    let computation_fee_token = computation_fee.into_token(&price, token_id)?;
+    let spread = token_fee_spread(computation_fee_token, governor.get_token_fee_spread_bps());
+
+    let mut total_amount = Token::new(token_id, 0);
+    let mut total_network_fee = Token::new(token_id, 0);
+    for (request, _) in &requests {
+        guard!(
+            request.token_id == token_id
+                && request.fee_version == first.fee_version
+                && request.min_batching_rate == first.min_batching_rate,
+            ElusivError::InvalidInstructionData
+        );
+
+        let amount = Token::new_checked(token_id, request.amount)?;
+
+        guard!(
+            is_element_scalar_field(u256_to_big_uint(&request.base_commitment.skip_mr())),
+            ElusivError::NonScalarValue
+        );
+        guard!(
+            is_element_scalar_field(u256_to_big_uint(&request.commitment.skip_mr())),
+            ElusivError::NonScalarValue
+        );
+        guard!(
+            verify_recent_commitment_index(request.recent_commitment_index, storage),
+            ElusivError::InvalidRecentCommitmentIndex
+        );
+        guard!(
+            u256_to_fr_skip_mr(&request.base_commitment.reduce()) != ZERO_BASE_COMMITMENT,
+            ElusivError::InvalidInstructionData
+        );
+
+        total_amount = (total_amount + amount)?;
+        total_network_fee = ((total_network_fee
+            + Token::new(
+                token_id,
+                fee.base_commitment_network_fee.calc(amount.amount()),
+            ))?
+            + spread)?;
+    }
+
+    let count = requests.len() as u64;
+    let total_subvention = Token::new(
+        token_id,
+        subvention.amount().checked_mul(count).ok_or(MATH_ERR)?,
+    );
+    let total_computation_fee = computation_fee.0.checked_mul(count).ok_or(MATH_ERR)?;
+    let total_computation_fee_token = Token::new(
+        token_id,
+        computation_fee_token
+            .amount()
+            .checked_mul(count)
+            .ok_or(MATH_ERR)?,
+    );
+
+    verify_program_token_account(pool, pool_account, token_id)?;
+    verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+
+    // `sender` transfers the accumulated `computation_fee_token` - `subvention` to `fee_payer` (token)
+    transfer_token(
+        sender,
+        sender_account,
+        fee_payer_account,
+        token_program,
+        (total_computation_fee_token - total_subvention)?,
+    )?;
+
+    // `fee_payer` transfers the accumulated `computation_fee` to `pool` (lamports)
+    transfer_with_system_program(fee_payer, pool, system_program, total_computation_fee)?;
+
+    // `sender` transfers the accumulated `network_fee` to `fee_collector` (token)
+    transfer_token(
+        sender,
+        sender_account,
+        fee_collector_account,
+        token_program,
+        total_network_fee,
+    )?;
+
+    // `sender` transfers the accumulated `amount` to `pool` (token)
+    transfer_token(
+        sender,
+        sender_account,
+        pool_account,
+        token_program,
+        total_amount,
+    )?;
+
+    // `fee_collector` transfers the accumulated `subvention` to `fee_payer` (token)
+    transfer_token_from_pda::<FeeCollectorAccount>(
+        fee_collector,
+        fee_collector_account,
+        fee_payer_account,
+        token_program,
+        total_subvention,
+        None,
+        None,
+    )?;
+
+    let hashing_accounts = [
+        hashing_account0,
+        hashing_account1,
+        hashing_account2,
+        hashing_account3,
+    ];
+    let timestamp = current_timestamp()?;
+    let slot = current_slot()?;
+
+    for (i, (request, metadata)) in requests.into_iter().enumerate() {
+        hashing_slots.open(hash_account_indices[i], fee_payer.key, slot)?;
+
+        // `fee_payer` rents `hashing_account` (and is reimbursed once the hash is finalized)
+        let rent = open_pda_account_with_offset_and_rent_owner::<BaseCommitmentHashingAccount>(
+            &crate::id(),
+            fee_payer,
+            fee_payer.key,
+            hashing_accounts[i],
+            hash_account_indices[i],
+            Some(hash_account_bumps[i]),
+        )?;
+
+        // Buffer duplicate check and insertion (also rejects duplicates inside the batch)
+        base_commitment_buffer.try_insert(&request.base_commitment.skip_mr())?;
+
+        let amount = request.amount;
+        pda_account!(
+            mut hashing_account,
+            BaseCommitmentHashingAccount,
+            hashing_accounts[i]
+        );
+        hashing_account.setup(request, metadata, rent.owner.to_bytes())?;
+        hashing_account.set_setup_timestamp(&timestamp);
+
+        metrics.record_store(token_id, amount)?;
+    }
+
+    Ok(())
 }
 
 pub fn verify_recent_commitment_index(
@@ -240,6 +521,25 @@ pub fn verify_recent_commitment_index(
         && next_commitment_index - recent_commitment_index < COMMITMENT_BUFFER_LEN
 }
 
+/// Reserves a free [`BaseCommitmentHashingAccount`] instance for `fee_payer`
+///
+/// # Note
+///
+/// - The index of the instance is returned as return data (little-endian `u32`), it stays reserved for [`crate::state::commitment::BASE_COMMITMENT_HASHING_SLOT_RESERVATION`] slots.
+/// - Only registered wardens can reserve instances (see [`BaseCommitmentHashingSlotsAccount`] for the limits).
+pub fn acquire_hashing_slot(
+    fee_payer: &AccountInfo,
+    warden_map_account: &AccountInfo,
+    hashing_slots: &mut BaseCommitmentHashingSlotsAccount,
+) -> ProgramResult {
+    verify_basic_warden(fee_payer.key, warden_map_account)?;
+
+    let index = hashing_slots.acquire(fee_payer.key, current_slot()?)?;
+    set_return_data(&index.to_le_bytes());
+
+    Ok(())
+}
+
 pub fn compute_base_commitment_hash(
     hashing_account: &mut BaseCommitmentHashingAccount,
 
@@ -258,11 +558,17 @@ pub fn finalize_base_commitment_hash<'a>(
     pool: &AccountInfo<'a>,
     fee: &FeeAccount,
     hashing_account_info: &AccountInfo<'a>,
+    hashing_slots: &mut BaseCommitmentHashingSlotsAccount,
     commitment_hash_queue: &mut CommitmentQueueAccount,
     metadata_queue: &mut MetadataQueueAccount,
+    governor: &GovernorAccount,
+    commitment_metadata_account: &AccountInfo<'a>,
+    warden_usage_account: &AccountInfo,
 
-    _hash_account_index: u32,
+    mt_shard: u32,
+    hash_account_index: u32,
     fee_version: u32,
+    ciphertext: Vec<u8>,
 ) -> ProgramResult {
     pda_account!(
         mut hashing_account,
@@ -286,6 +592,27 @@ pub fn finalize_base_commitment_hash<'a>(
         ElusivError::ComputationIsNotYetFinished
     );
 
+    // Only the `original_fee_payer` (relaying the sender's store) can attach an encrypted note
+    guard!(
+        ciphertext.is_empty() || original_fee_payer.is_signer,
+        ProgramError::MissingRequiredSignature
+    );
+
+    // The commitment is enqueued into the commitment queue of its MT-shard
+    let commitment = fr_to_u256_le(&hashing_account.get_state().result());
+    guard!(
+        governor.commitment_mt_shard(&commitment) == mt_shard,
+        ElusivError::InvalidMtShard
+    );
+
+    // A single fee-payer can not finalize (and earn the fees of) more than its fair-share of the base-commitment hashes
+    commitment_hash_queue.record_fair_share(
+        &original_fee_payer.key.to_bytes(),
+        current_slot()?,
+        governor.get_hashing_fair_share_bps(),
+        governor.get_hashing_fair_share_window(),
+    )?;
+
     // `pool` transfers `base_commitment_hash_fee` to `original_fee_payer` (lamports)
     transfer_lamports_from_pda_checked(
         pool,
@@ -295,25 +622,200 @@ pub fn finalize_base_commitment_hash<'a>(
             .0,
     )?;
 
-    let commitment = hashing_account.get_state().result();
     let mut commitment_queue = CommitmentQueue::new(commitment_hash_queue);
     let mut metadata_queue = MetadataQueue::new(metadata_queue);
+    let previous_len = commitment_queue.len();
 
     enqueue_commitment(
         &mut commitment_queue,
         &mut metadata_queue,
-        fr_to_u256_le(&commitment),
+        commitment,
         hashing_account.get_metadata(),
         fee_version,
         hashing_account.get_min_batching_rate(),
+        &original_fee_payer.key.to_bytes(),
     )?;
+    signal_commitment_queue_pressure(
+        governor,
+        previous_len,
+        commitment_queue.len(),
+        commitment_queue.capacity(),
+    );
+
+    if !ciphertext.is_empty() {
+        open_commitment_metadata_account(
+            original_fee_payer,
+            commitment_metadata_account,
+            governor,
+            &Pubkey::new_from_array(commitment),
+            commitment,
+            &ciphertext,
+        )?;
+    }
+
+    record_warden_usage(original_fee_payer.key, warden_usage_account, |usage| {
+        usage.record_hashing_rounds(BaseCommitmentHashComputation::TOTAL_ROUNDS);
+        usage.record_metadata_bytes(ciphertext.len());
+    })?;
 
     // Close hashing account
     hashing_account.set_is_active(&false);
+    hashing_slots.release(hash_account_index);
+    close_account(original_fee_payer, hashing_account_info)
+}
+
+/// Opens the [`CommitmentMetadataAccount`] (associated with `associated_pubkey`) of `commitment` containing the encrypted note `ciphertext`
+fn open_commitment_metadata_account<'a>(
+    payer: &AccountInfo<'a>,
+    commitment_metadata_account_info: &AccountInfo<'a>,
+    governor: &GovernorAccount,
+    associated_pubkey: &Pubkey,
+    commitment: U256,
+    ciphertext: &[u8],
+) -> ProgramResult {
+    let size_class = governor.metadata_size_class(ciphertext.len())?;
+
+    open_pda_account_with_associated_pubkey::<CommitmentMetadataAccount>(
+        &crate::id(),
+        payer,
+        commitment_metadata_account_info,
+        associated_pubkey,
+        None,
+        None,
+    )?;
+
+    pda_account!(
+        mut commitment_metadata_account,
+        CommitmentMetadataAccount,
+        commitment_metadata_account_info
+    );
+    commitment_metadata_account.set_commitment(&commitment);
+    commitment_metadata_account.set_size_class(&(size_class as u8));
+    commitment_metadata_account.set_ciphertext(ciphertext)
+}
+
+/// Attaches the encrypted note `ciphertext` to a commitment, that has been stored without one (e.g. before ciphertexts were supported)
+///
+/// # Note
+///
+/// - The depositor proves its identity by signing with the `identifier` key of the note, the backfilled [`CommitmentMetadataAccount`] is associated with the commitment and the identifier (see [`CommitmentMetadataAccount::backfill_pubkey`]).
+/// - The `commitment` is verified against the leaf at `commitment_index`, so only commitments of the active MT can be backfilled (the leaves of closed MTs are not stored).
+/// - The `payer` pays the rent of the account.
+#[allow(clippy::too_many_arguments)]
+pub fn backfill_commitment_metadata<'a>(
+    payer: &AccountInfo<'a>,
+    identifier_account: &AccountInfo,
+    storage_account: &StorageAccount,
+    governor: &GovernorAccount,
+    commitment_metadata_account_info: &AccountInfo<'a>,
+
+    mt_index: u32,
+    commitment_index: u32,
+    commitment: U256,
+    identifier: U256,
+    ciphertext: Vec<u8>,
+) -> ProgramResult {
+    guard!(!ciphertext.is_empty(), ElusivError::InvalidInstructionData);
+    guard!(
+        identifier_account.is_signer && identifier_account.key.to_bytes() == identifier,
+        ElusivError::InvalidIdentifier
+    );
+
+    guard!(
+        mt_index == storage_account.get_trees_count()
+            && commitment_index < storage_account.get_next_commitment_ptr(),
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        storage_account.get_node(commitment_index as usize, MT_HEIGHT as usize)? == commitment,
+        ElusivError::InputsMismatch
+    );
+
+    open_commitment_metadata_account(
+        payer,
+        commitment_metadata_account_info,
+        governor,
+        &CommitmentMetadataAccount::backfill_pubkey(&commitment, identifier_account.key),
+        commitment,
+        &ciphertext,
+    )?;
+
+    pda_account!(
+        mut commitment_metadata_account,
+        CommitmentMetadataAccount,
+        commitment_metadata_account_info
+    );
+    commitment_metadata_account
+        .set_backfill_identifier(&ElusivOption::Some(*identifier_account.key));
+    commitment_metadata_account.set_mt_index(&mt_index);
+    commitment_metadata_account.set_commitment_index(&commitment_index);
+
+    Ok(())
+}
+
+/// Duration (in seconds) after which anyone can close an unfinalized [`BaseCommitmentHashingAccount`]
+pub const BASE_COMMITMENT_HASHING_ACCOUNT_CLOSE_TIMEOUT: u64 = 60 * 60 * 24;
+
+/// Closes an unfinalized [`BaseCommitmentHashingAccount`] and returns the rent to `original_fee_payer`
+///
+/// # Note
+///
+/// - the `original_fee_payer` can close a finished account (all hash computations performed) at any time
+/// - anyone can close a finished or stale (unfinished) account after [`BASE_COMMITMENT_HASHING_ACCOUNT_CLOSE_TIMEOUT`]
+/// - the base-commitment-hash fee is only reimbursed by [`finalize_base_commitment_hash`], the prepaid computation fee of a closed account remains in the pool (as dust)
+/// - the commitment of a closed account is not inserted
+pub fn close_base_commitment_hashing_account<'a>(
+    signer: &AccountInfo<'a>,
+    original_fee_payer: &AccountInfo<'a>,
+    hashing_account_info: &AccountInfo<'a>,
+    hashing_slots: &mut BaseCommitmentHashingSlotsAccount,
+
+    hash_account_index: u32,
+) -> ProgramResult {
+    pda_account!(
+        hashing_account,
+        BaseCommitmentHashingAccount,
+        hashing_account_info
+    );
+    guard!(
+        hashing_account.get_fee_payer() == original_fee_payer.key.to_bytes(),
+        ElusivError::InvalidAccount
+    );
+
+    let is_finished =
+        hashing_account.get_instruction() as usize >= BaseCommitmentHashComputation::IX_COUNT;
+    if !is_finished || signer.key != original_fee_payer.key {
+        guard!(
+            is_base_commitment_hashing_account_close_timeout_reached(
+                hashing_account.get_setup_timestamp(),
+                current_timestamp()?
+            ),
+            if is_finished {
+                ElusivError::InvalidAccountState
+            } else {
+                ElusivError::ComputationIsNotYetFinished
+            }
+        );
+    }
+
+    hashing_slots.release(hash_account_index);
     close_account(original_fee_payer, hashing_account_info)
 }
 
+pub fn is_base_commitment_hashing_account_close_timeout_reached(
+    setup_timestamp: u64,
+    timestamp: u64,
+) -> bool {
+    setup_timestamp
+        .checked_add(BASE_COMMITMENT_HASHING_ACCOUNT_CLOSE_TIMEOUT)
+        .map_or(false, |t| t <= timestamp)
+}
+
 /// Enques a commitment and it's associated metadata into the corresponding queues
+///
+/// # Note
+///
+/// The commitment counts towards the in-flight cap of `fee_payer`.
 pub fn enqueue_commitment(
     commitment_queue: &mut CommitmentQueue,
     metadata_queue: &mut MetadataQueue,
@@ -321,21 +823,78 @@ pub fn enqueue_commitment(
     metadata: CommitmentMetadata,
     fee_version: u32,
     min_batching_rate: u32,
+    fee_payer: &U256,
 ) -> ProgramResult {
-    commitment_queue.enqueue(CommitmentHashRequest {
-        commitment,
-        fee_version,
-        min_batching_rate,
-    })?;
+    commitment_queue.enqueue_for_fee_payer(
+        CommitmentHashRequest {
+            commitment,
+            fee_version,
+            min_batching_rate,
+        },
+        fee_payer,
+    )?;
 
     metadata_queue.enqueue(metadata)
 }
 
+/// Enques a commitment and it's associated metadata ahead of all queued commitments with a lower `priority`
+///
+/// # Note
+///
+/// The commitment counts towards the in-flight cap of `fee_payer`.
+#[allow(clippy::too_many_arguments)]
+pub fn enqueue_commitment_with_priority(
+    commitment_queue: &mut CommitmentQueue,
+    metadata_queue: &mut MetadataQueue,
+    commitment: U256,
+    metadata: CommitmentMetadata,
+    fee_version: u32,
+    min_batching_rate: u32,
+    fee_payer: &U256,
+    priority: u64,
+) -> ProgramResult {
+    let moves = commitment_queue.enqueue_with_priority(
+        CommitmentHashRequest {
+            commitment,
+            fee_version,
+            min_batching_rate,
+        },
+        fee_payer,
+        priority,
+    )?;
+
+    // The metadata queue mirrors the order of the commitment queue
+    metadata_queue.enqueue(metadata)?;
+    metadata_queue.move_last_ahead(moves)
+}
+
+/// Emits a [`ElusivEvent::QueuePressure`] if the commitment queue has reached the watermark of the [`GovernorAccount`] by growing from `previous_len` to `queue_len`
+///
+/// # Note
+///
+/// `capacity` is the capacity of the (possibly grown) commitment queue.
+pub fn signal_commitment_queue_pressure(
+    governor: &GovernorAccount,
+    previous_len: u32,
+    queue_len: u32,
+    capacity: u32,
+) {
+    if governor.has_crossed_commitment_queue_watermark(previous_len, queue_len) {
+        ElusivEvent::QueuePressure {
+            queue_len,
+            capacity,
+            watermark: governor.get_commitment_queue_watermark(),
+        }
+        .emit();
+    }
+}
+
 /// Places the hash siblings into the hashing account
 pub fn init_commitment_hash_setup(
     hashing_account: &mut CommitmentHashingAccount,
     storage_account: &StorageAccount,
 
+    _mt_shard: u32,
     insertion_can_fail: bool,
 ) -> ProgramResult {
     match init_commitment_hash_setup_inner(hashing_account, storage_account) {
@@ -363,16 +922,26 @@ fn init_commitment_hash_setup_inner(
     let ordering = storage_account.get_next_commitment_ptr();
     let siblings = storage_account.get_mt_opening(ordering as usize)?;
 
-    hashing_account.setup(ordering, &siblings)
+    hashing_account.setup(ordering, &siblings)?;
+    hashing_account.set_mt_capacity(&usize_as_u32_safe(storage_account.commitment_capacity()));
+
+    Ok(())
 }
 
 /// Places the next batch from the commitment queue in the [`CommitmentHashingAccount`]
+///
+/// # Note
+///
+/// - The commitment queue is ordered by priority, so the batch consists of the highest-priority requests.
+/// - The batch is extended up to the `max_batching_rate` of the [`CommitmentHashingAccount`] (sharing the upper MT hashes among more commitments).
 pub fn init_commitment_hash(
     commitment_queue: &mut CommitmentQueueAccount,
     metadata_queue: &mut MetadataQueueAccount,
     hashing_account: &mut CommitmentHashingAccount,
     metadata_account: &mut MetadataAccount,
+    governor: &GovernorAccount,
 
+    _mt_shard: u32,
     insertion_can_fail: bool,
 ) -> ProgramResult {
     match init_commitment_hash_inner(
@@ -380,6 +949,7 @@ pub fn init_commitment_hash(
         metadata_queue,
         hashing_account,
         metadata_account,
+        governor,
     ) {
         Ok(()) => Ok(()),
         Err(e) => {
@@ -398,7 +968,12 @@ fn init_commitment_hash_inner(
     metadata_queue: &mut MetadataQueueAccount,
     hashing_account: &mut CommitmentHashingAccount,
     metadata_account: &mut MetadataAccount,
+    governor: &GovernorAccount,
 ) -> ProgramResult {
+    guard!(
+        !governor.get_is_protocol_paused(),
+        ElusivError::ProtocolPaused
+    );
     guard!(
         !hashing_account.get_is_active(),
         ElusivError::ComputationIsNotYetFinished
@@ -409,8 +984,12 @@ fn init_commitment_hash_inner(
     );
 
     let mut commitment_queue = CommitmentQueue::new(commitment_queue);
-    let (batch, batching_rate) = commitment_queue.next_batch()?;
-    commitment_queue.remove(usize_as_u32_safe(batch.len()))?;
+    let (batch, batching_rate) = commitment_queue.next_extended_batch(
+        hashing_account.get_max_batching_rate(),
+        hashing_account.get_ordering(),
+        hashing_account.mt_commitment_capacity(),
+    )?;
+    commitment_queue.remove_in_flight(usize_as_u32_safe(batch.len()))?;
 
     let mut metadata_queue = MetadataQueue::new(metadata_queue);
     for _ in 0..batch.len() {
@@ -423,7 +1002,8 @@ fn init_commitment_hash_inner(
 
     // Check for room for the commitment batch
     guard!(
-        hashing_account.get_ordering() as usize + batch.len() <= MT_COMMITMENT_COUNT,
+        hashing_account.get_ordering() as usize + batch.len()
+            <= hashing_account.mt_commitment_capacity(),
         ElusivError::NoRoomForCommitment
     );
 
@@ -432,15 +1012,77 @@ fn init_commitment_hash_inner(
         commitments[i] = batch[i].commitment;
     }
 
-    hashing_account.reset(batching_rate, fee_version, &commitments)
+    hashing_account.reset(batching_rate, fee_version, &commitments)?;
+    hashing_account.set_fee_escrow(&CommitmentHashFeeEscrow::new(&batch)?);
+
+    Ok(())
 }
 
-pub fn compute_commitment_hash<'a>(
+/// Aborts the active commitment hashing computation and re-enqueues its commitments
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - only possible as long as no commitment of the batch has been inserted into the active MT
+/// - used to recover from a computation that can not be continued (e.g. a runtime-forced abort)
+/// - the batch's fee escrow is released, the re-enqueued commitments are escrowed again with their next batch
+pub fn abort_commitment_hash(
+    authority: &AccountInfo,
+    commitment_queue: &mut CommitmentQueueAccount,
+    metadata_queue: &mut MetadataQueueAccount,
+    hashing_account: &mut CommitmentHashingAccount,
+    metadata_account: &mut MetadataAccount,
+    storage_account: &StorageAccount,
+
+    _mt_shard: u32,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        hashing_account.get_is_active(),
+        ElusivError::ComputationIsNotYetStarted
+    );
+
+    // Nothing has been written to the storage-account
+    guard!(
+        hashing_account.get_finalization_ix() == 0,
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        storage_account.get_next_commitment_ptr() == hashing_account.get_ordering(),
+        ElusivError::InvalidAccountState
+    );
+
+    let count = usize_as_u32_safe(commitments_per_batch(hashing_account.get_batching_rate()));
+
+    let mut commitment_queue = CommitmentQueue::new(commitment_queue);
+    commitment_queue.restore_in_flight(count)?;
+    for i in 0..count as usize {
+        guard!(
+            commitment_queue.view(i)?.commitment == hashing_account.get_hash_tree(i),
+            ElusivError::InvalidAccountState
+        );
+    }
+
+    let mut metadata_queue = MetadataQueue::new(metadata_queue);
+    metadata_queue.restore(count)?;
+    metadata_account.remove_last_commitment_metadata(count)?;
+
+    hashing_account.abort();
+
+    Ok(())
+}
+
+pub fn compute_commitment_hash<'a>(
     fee_payer: &AccountInfo<'a>,
     fee: &FeeAccount,
     pool: &AccountInfo<'a>,
     hashing_account: &mut CommitmentHashingAccount,
+    warden_usage_account: &AccountInfo,
 
+    _mt_shard: u32,
     fee_version: u32,
     _nonce: u32,
 ) -> ProgramResult {
@@ -453,24 +1095,47 @@ pub fn compute_commitment_hash<'a>(
         ElusivError::InvalidFeeVersion
     );
 
+    let round = hashing_account.get_round();
     compute_commitment_hash_partial(hashing_account)?;
 
-    transfer_lamports_from_pda_checked(
-        pool,
-        fee_payer,
-        fee.get_program_fee().hash_tx_compensation().0,
-    )
+    let rounds = hashing_account.get_round() - round;
+    record_warden_usage(fee_payer.key, warden_usage_account, |usage| {
+        usage.record_hashing_rounds(rounds)
+    })?;
+
+    // The compensation is paid out of the batch's fee escrow
+    let compensation = fee.get_program_fee().hash_tx_compensation().0;
+    let mut fee_escrow = hashing_account.get_fee_escrow();
+    fee_escrow.paid = fee_escrow.paid.checked_add(compensation).ok_or(MATH_ERR)?;
+    hashing_account.set_fee_escrow(&fee_escrow);
+
+    transfer_lamports_from_pda_checked(pool, fee_payer, compensation)
 }
 
 /// Requires `batching_rate + 1` calls
-pub fn finalize_commitment_hash(
+///
+/// # Note
+///
+/// - The last call emits [`ElusivEvent::MerkleRootUpdated`] with the new root.
+/// - The last call settles the batch's fee escrow: the remaining (not paid out) lamports are transferred from `pool` to `fee_collector`.
+pub fn finalize_commitment_hash<'a>(
+    fee: &FeeAccount,
+    pool: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
     hashing_account: &mut CommitmentHashingAccount,
     storage_account: &mut StorageAccount,
+
+    _mt_shard: u32,
+    fee_version: u32,
 ) -> ProgramResult {
     guard!(
         hashing_account.get_is_active(),
         ElusivError::ComputationIsNotYetStarted
     );
+    guard!(
+        hashing_account.get_fee_version() == fee_version,
+        ElusivError::InvalidFeeVersion
+    );
 
     let finalization_ix = hashing_account.get_finalization_ix();
     let batching_rate = hashing_account.get_batching_rate();
@@ -489,7 +1154,7 @@ pub fn finalize_commitment_hash(
 
     guard!(
         storage_account.get_next_commitment_ptr() as usize + commitments_per_batch(batching_rate)
-            <= MT_COMMITMENT_COUNT,
+            <= storage_account.commitment_capacity(),
         ElusivError::NoRoomForCommitment
     );
 
@@ -498,7 +1163,91 @@ pub fn finalize_commitment_hash(
     if finalization_ix == batching_rate {
         hashing_account.set_is_active(&false);
         hashing_account.set_setup(&false);
+
+        let fee_escrow = hashing_account.get_fee_escrow();
+        let remaining = fee_escrow.remaining(&fee.get_program_fee())?;
+        if remaining > 0 {
+            transfer_lamports_from_pda_checked(pool, fee_collector, remaining)?;
+        }
+        hashing_account.set_fee_escrow(&CommitmentHashFeeEscrow::default());
+
+        ElusivEvent::MerkleRootUpdated {
+            root: storage_account.get_root()?,
+            mt_index: storage_account.get_trees_count(),
+            commitment_count: storage_account.get_next_commitment_ptr(),
+        }
+        .emit();
+    }
+    Ok(())
+}
+
+/// Sets the MT opening of the commitment at `index` (in the active MT of `mt_shard`) and the current root as return data
+pub fn get_mt_opening(
+    storage_account: &StorageAccount,
+
+    _mt_shard: u32,
+    index: u32,
+) -> ProgramResult {
+    guard!(
+        index < storage_account.get_next_commitment_ptr(),
+        ElusivError::InvalidInstructionData
+    );
+
+    let opening = storage_account.get_mt_opening(index as usize)?;
+    let mut data = Vec::with_capacity((opening.len() + 1) * U256::SIZE);
+    for node in opening {
+        data.extend(node);
+    }
+    data.extend(storage_account.get_root()?);
+
+    set_return_data(&data);
+
+    Ok(())
+}
+
+/// Maximum number of Poseidon rounds computed by a single [`verify_poseidon_test_vector`] call
+#[cfg(not(feature = "mainnet"))]
+pub const MAX_POSEIDON_TEST_VECTOR_ROUNDS: u32 = 32;
+
+/// Computes the Poseidon rounds `[first_round, first_round + round_count)` on `state` and fails if the result does not match `expected`
+///
+/// # Note
+///
+/// - Dev-cluster only, used to validate hash backends against test vectors on a live cluster.
+/// - A full hash requires multiple calls (with the intermediate states as test vectors).
+/// - The states are serialized like a [`crate::commitment::poseidon_hash::BinarySpongeHashingState`] (see [`crate::fields::fr_to_u256_le`]).
+#[cfg(not(feature = "mainnet"))]
+pub fn verify_poseidon_test_vector(
+    state: [U256; 3],
+    first_round: u32,
+    round_count: u32,
+    expected: [U256; 3],
+) -> ProgramResult {
+    use crate::commitment::poseidon_hash::{
+        binary_poseidon_hash_partial, BinarySpongeHashingState, TOTAL_POSEIDON_ROUNDS,
+    };
+
+    guard!(
+        round_count > 0 && round_count <= MAX_POSEIDON_TEST_VECTOR_ROUNDS,
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        first_round
+            .checked_add(round_count)
+            .map_or(false, |last| last <= TOTAL_POSEIDON_ROUNDS),
+        ElusivError::InvalidInstructionData
+    );
+
+    let mut state = BinarySpongeHashingState::try_from_slice(&state.try_to_vec()?)?;
+    for round in first_round..first_round + round_count {
+        binary_poseidon_hash_partial(round, &mut state);
     }
+
+    guard!(
+        state.try_to_vec()? == expected.try_to_vec()?,
+        ElusivError::InputsMismatch
+    );
+
     Ok(())
 }
 
@@ -511,19 +1260,19 @@ mod tests {
     };
     use crate::macros::{
         account_info, parent_account, program_token_account_info, pyth_price_account_info,
-        test_account_info, test_pda_account_info, zero_program_account,
+        signing_test_account_info, test_account_info, test_pda_account_info, zero_program_account,
     };
     use crate::processor::mutate;
-    use crate::state::governor::PoolAccount;
+    use crate::state::fee::ProgramFee;
+    use crate::state::governor::{PoolAccount, PROTOCOL_VERSION};
     use crate::state::program_account::{PDAAccount, SizedAccount};
-    use crate::state::storage::{EMPTY_TREE, MT_HEIGHT};
+    use crate::state::storage::{EMPTY_TREE, MT_COMMITMENT_COUNT, MT_HEIGHT};
+    use crate::state::warden_usage::WardenUsageAccount;
     use crate::token::{lamports_token, usdc_token, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
     use ark_ff::Zero;
     use elusiv_types::tokens::Price;
     use elusiv_types::{BorshSerDeSized, TokenError};
     use solana_program::native_token::LAMPORTS_PER_SOL;
-    use solana_program::program_error::ProgramError;
-    use solana_program::pubkey::Pubkey;
     use solana_program::system_program;
     use std::str::FromStr;
 
@@ -573,9 +1322,12 @@ mod tests {
 
     #[test]
     fn test_store_base_commitment_lamports() {
+        zero_program_account!(mut metrics, MetricsAccount);
         zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(token_registry, TokenRegistryAccount);
         zero_program_account!(storage, StorageAccount);
         zero_program_account!(mut buffer, BaseCommitmentBufferAccount);
+        zero_program_account!(mut hashing_slots, BaseCommitmentHashingSlotsAccount);
         test_account_info!(sender, 0);
         test_account_info!(fee_payer, 0);
         test_account_info!(pool, 0);
@@ -678,16 +1430,23 @@ mod tests {
                     &any,
                     &any,
                     &governor,
+                    &token_registry,
                     &storage,
                     // The UnverifiedAccountInfo needs to be constructed for every single call since it might get modified
                     UnverifiedAccountInfo::new(&hashing_acc),
+                    &mut hashing_slots,
                     &mut buffer,
                     &sys,
                     &sys,
+                    &mut metrics,
+                    &sys,
+                    &sys,
+                    PROTOCOL_VERSION,
                     0,
                     bump,
                     request,
                     metadata,
+                    ElusivOption::None,
                 ),
                 Err(err)
             );
@@ -707,19 +1466,62 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &sys,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
 
+        // Missing warden nonce
+        governor.set_require_warden_nonces(&true);
+        assert_eq!(
+            store_base_commitment(
+                &sender,
+                &sender,
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &storage,
+                UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
+                &mut buffer,
+                &sys,
+                &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
+                0,
+                bump,
+                request.clone(),
+                metadata,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InvalidWardenNonce.into())
+        );
+        governor.set_require_warden_nonces(&false);
+
         // Invalid fee_collector_account
         assert_eq!(
             store_base_commitment(
@@ -734,15 +1536,22 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &sys,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -761,15 +1570,22 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &spl,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -788,15 +1604,22 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &sys,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 1,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(ProgramError::InvalidSeeds)
         );
@@ -815,19 +1638,62 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &sys,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 0,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(ProgramError::InvalidSeeds)
         );
 
+        // Instance reserved by another fee payer
+        hashing_slots.acquire(&Pubkey::new_unique(), 0).unwrap();
+        assert_eq!(
+            store_base_commitment(
+                &sender,
+                &sender,
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &storage,
+                UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
+                &mut buffer,
+                &sys,
+                &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
+                0,
+                bump,
+                request.clone(),
+                metadata,
+                ElusivOption::None,
+            ),
+            Err(ElusivError::HashingSlotReserved.into())
+        );
+        hashing_slots.release(0);
+
         assert_eq!(
             store_base_commitment(
                 &sender,
@@ -841,19 +1707,29 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &sys,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Ok(())
         );
 
+        // Release the opened instance (as the finalization does)
+        hashing_slots.release(0);
+
         // Duplicate insertion will fail
         assert_eq!(
             store_base_commitment(
@@ -868,15 +1744,22 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &sys,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request,
                 metadata,
+                ElusivOption::None,
             ),
             Err(ElusivError::DuplicateValue.into())
         );
@@ -884,9 +1767,12 @@ mod tests {
 
     #[test]
     fn test_store_base_commitment_token() {
+        zero_program_account!(mut metrics, MetricsAccount);
         zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(token_registry, TokenRegistryAccount);
         zero_program_account!(storage, StorageAccount);
         zero_program_account!(mut buffer, BaseCommitmentBufferAccount);
+        zero_program_account!(mut hashing_slots, BaseCommitmentHashingSlotsAccount);
         test_account_info!(sender);
         test_account_info!(fee_payer);
         test_account_info!(sender_token, 0, spl_token::id());
@@ -959,15 +1845,22 @@ mod tests {
                     &sol,
                     &usdc,
                     &governor,
+                    &token_registry,
                     &storage,
                     UnverifiedAccountInfo::new(&hashing_acc),
+                    &mut hashing_slots,
                     &mut buffer,
                     &spl,
                     &sys,
+                    &mut metrics,
+                    &sys,
+                    &sys,
+                    PROTOCOL_VERSION,
                     0,
                     bump,
                     request,
                     metadata,
+                    ElusivOption::None,
                 ),
                 Err(err)
             );
@@ -987,15 +1880,22 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &spl,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -1014,15 +1914,22 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &spl,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -1041,15 +1948,22 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &sys,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -1068,15 +1982,22 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &spl,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 1,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(ProgramError::InvalidSeeds)
         );
@@ -1095,15 +2016,22 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &spl,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -1122,15 +2050,22 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &spl,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -1149,15 +2084,22 @@ mod tests {
                 &usdc,
                 &usdc,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &spl,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(TokenError::InvalidPriceAccount.into())
         );
@@ -1176,15 +2118,22 @@ mod tests {
                 &sol,
                 &sol,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &spl,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Err(TokenError::InvalidPriceAccount.into())
         );
@@ -1202,19 +2151,29 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &spl,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request.clone(),
                 metadata,
+                ElusivOption::None,
             ),
             Ok(())
         );
 
+        // Release the opened instance (as the finalization does)
+        hashing_slots.release(0);
+
         // Duplicate insertion will fail
         assert_eq!(
             store_base_commitment(
@@ -1229,89 +2188,297 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &storage,
                 UnverifiedAccountInfo::new(&hashing_acc),
+                &mut hashing_slots,
                 &mut buffer,
                 &spl,
                 &sys,
+                &mut metrics,
+                &sys,
+                &sys,
+                PROTOCOL_VERSION,
                 0,
                 bump,
                 request,
                 metadata,
+                ElusivOption::None,
             ),
             Err(ElusivError::DuplicateValue.into())
         );
     }
 
     #[test]
-    fn test_compute_base_commitment_hash() {
-        zero_program_account!(mut hashing_account, BaseCommitmentHashingAccount);
-
-        // Inactive
-        assert_eq!(
-            compute_base_commitment_hash(&mut hashing_account, 0),
-            Err(ElusivError::ComputationIsNotYetStarted.into())
-        );
-
-        hashing_account.set_is_active(&true);
-
-        for _ in 0..BaseCommitmentHashComputation::IX_COUNT {
-            assert_eq!(
-                compute_base_commitment_hash(&mut hashing_account, 0),
-                Ok(())
-            );
-        }
+    fn test_store_base_commitment_batch() -> ProgramResult {
+        zero_program_account!(mut metrics, MetricsAccount);
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(token_registry, TokenRegistryAccount);
+        zero_program_account!(storage, StorageAccount);
+        zero_program_account!(mut buffer, BaseCommitmentBufferAccount);
+        zero_program_account!(mut hashing_slots, BaseCommitmentHashingSlotsAccount);
+        test_account_info!(sender, 0);
+        test_account_info!(fee_payer, 0);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        test_account_info!(any, 0);
+        account_info!(sys, system_program::id(), vec![]);
 
-        // Additional computations will fail
-        assert_eq!(
-            compute_base_commitment_hash(&mut hashing_account, 0),
-            Err(ElusivError::ComputationIsAlreadyFinished.into())
-        );
-        assert_eq!(
-            hashing_account.get_state().result(),
-            Fr::from_str(
-                "14744269619966411208579211824598458697587494354926760081771325075741142829156"
-            )
-            .unwrap()
+        let (pubkey0, bump0) = BaseCommitmentHashingAccount::find(Some(0));
+        let (pubkey1, bump1) = BaseCommitmentHashingAccount::find(Some(1));
+        account_info!(
+            hashing_acc0,
+            pubkey0,
+            vec![0; BaseCommitmentHashingAccount::SIZE]
         );
-    }
-
-    #[test]
-    fn test_finalize_base_commitment_hash() -> ProgramResult {
-        account_info!(fee_payer, Pubkey::new_unique(), vec![0]);
         account_info!(
-            h_account,
-            BaseCommitmentHashingAccount::find(Some(0)).0,
+            hashing_acc1,
+            pubkey1,
             vec![0; BaseCommitmentHashingAccount::SIZE]
         );
-        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
-        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
-        zero_program_account!(fee, FeeAccount);
-        test_account_info!(pool, 0);
 
-        // Inactive hashing account
-        {
-            pda_account!(mut h, BaseCommitmentHashingAccount, h_account);
-            h.set_instruction(&(BaseCommitmentHashComputation::IX_COUNT as u32));
-            h.set_fee_payer(&fee_payer.key.to_bytes());
-        }
-        assert_eq!(
-            finalize_base_commitment_hash(
-                &fee_payer,
-                &pool,
-                &fee,
-                &h_account,
-                &mut commitment_queue,
-                &mut metadata_queue,
-                0,
-                0
-            ),
-            Err(ElusivError::ComputationIsNotYetStarted.into())
-        );
+        governor.set_commitment_batching_rate(&4);
+        governor.set_fee_version(&1);
 
-        // Invalid original fee payer
-        {
-            pda_account!(mut h, BaseCommitmentHashingAccount, h_account);
+        let request = BaseCommitmentHashRequest {
+            base_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+            recent_commitment_index: 0,
+            amount: LAMPORTS_PER_SOL,
+            token_id: LAMPORTS_TOKEN_ID,
+            commitment: RawU256::new(u256_from_str_skip_mr("1")),
+            fee_version: 1,
+            min_batching_rate: 4,
+        };
+        let second_request = mutate(&request, |request| {
+            request.base_commitment = RawU256::new(u256_from_str_skip_mr("2"));
+            request.amount = 2 * LAMPORTS_PER_SOL;
+        });
+        let metadata = CommitmentMetadata::default();
+
+        macro_rules! store_batch {
+            ($requests: expr) => {
+                store_base_commitment_batch(
+                    &sender,
+                    &sender,
+                    &fee_payer,
+                    &fee_payer,
+                    &pool,
+                    &pool,
+                    &fee_collector,
+                    &fee_collector,
+                    &any,
+                    &any,
+                    &governor,
+                    &token_registry,
+                    &storage,
+                    &mut buffer,
+                    &sys,
+                    &sys,
+                    &mut metrics,
+                    &sys,
+                    &sys,
+                    &mut hashing_slots,
+                    &hashing_acc0,
+                    &hashing_acc1,
+                    &any,
+                    &any,
+                    PROTOCOL_VERSION,
+                    [0, 1, 0, 0],
+                    [bump0, bump1, 0, 0],
+                    $requests,
+                    ElusivOption::None,
+                )
+            };
+        }
+
+        let invalid_batches = [
+            // Empty batch
+            (vec![], ElusivError::InvalidInstructionData.into()),
+            // Batch too large
+            (
+                vec![(request.clone(), metadata); MAX_STORE_BATCH_SIZE + 1],
+                ElusivError::InvalidInstructionData.into(),
+            ),
+            // Mismatched token_id
+            (
+                vec![
+                    (request.clone(), metadata),
+                    (
+                        mutate(&second_request, |request| {
+                            request.token_id = USDC_TOKEN_ID;
+                        }),
+                        metadata,
+                    ),
+                ],
+                ElusivError::InvalidInstructionData.into(),
+            ),
+            // Mismatched fee_version
+            (
+                vec![
+                    (request.clone(), metadata),
+                    (
+                        mutate(&second_request, |request| {
+                            request.fee_version = 0;
+                        }),
+                        metadata,
+                    ),
+                ],
+                ElusivError::InvalidInstructionData.into(),
+            ),
+            // Invalid amount of a single request
+            (
+                vec![
+                    (request.clone(), metadata),
+                    (
+                        mutate(&second_request, |request| {
+                            request.amount = lamports_token().max + 1;
+                        }),
+                        metadata,
+                    ),
+                ],
+                TokenError::InvalidAmount.into(),
+            ),
+            // Zero-commitment
+            (
+                vec![
+                    (request.clone(), metadata),
+                    (
+                        mutate(&second_request, |request| {
+                            request.base_commitment =
+                                RawU256::new(fr_to_u256_le_repr(&ZERO_BASE_COMMITMENT));
+                        }),
+                        metadata,
+                    ),
+                ],
+                ElusivError::InvalidInstructionData.into(),
+            ),
+        ];
+
+        for (requests, err) in invalid_batches {
+            assert_eq!(store_batch!(requests), Err(err));
+        }
+
+        assert_eq!(
+            store_batch!(vec![
+                (request.clone(), metadata),
+                (second_request.clone(), metadata)
+            ]),
+            Ok(())
+        );
+
+        for hashing_acc in [&hashing_acc0, &hashing_acc1] {
+            pda_account!(hashing_account, BaseCommitmentHashingAccount, hashing_acc);
+            assert!(hashing_account.get_is_active());
+            assert_eq!(hashing_account.get_fee_payer(), fee_payer.key.to_bytes());
+        }
+        assert_eq!(
+            metrics.get_volume_in(LAMPORTS_TOKEN_ID as usize),
+            3 * LAMPORTS_PER_SOL
+        );
+        assert!(hashing_slots.get_is_open(0));
+        assert!(hashing_slots.get_is_open(1));
+
+        // Open instances
+        assert_eq!(
+            store_batch!(vec![(request.clone(), metadata)]),
+            Err(ElusivError::HashingSlotReserved.into())
+        );
+        hashing_slots.release(0);
+        hashing_slots.release(1);
+
+        // Duplicate base commitments inside the batch
+        let third_request = mutate(&request, |request| {
+            request.base_commitment = RawU256::new(u256_from_str_skip_mr("3"));
+        });
+        assert_eq!(
+            store_batch!(vec![
+                (third_request.clone(), metadata),
+                (third_request, metadata)
+            ]),
+            Err(ElusivError::DuplicateValue.into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compute_base_commitment_hash() {
+        zero_program_account!(mut hashing_account, BaseCommitmentHashingAccount);
+
+        // Inactive
+        assert_eq!(
+            compute_base_commitment_hash(&mut hashing_account, 0),
+            Err(ElusivError::ComputationIsNotYetStarted.into())
+        );
+
+        hashing_account.set_is_active(&true);
+
+        for _ in 0..BaseCommitmentHashComputation::IX_COUNT {
+            assert_eq!(
+                compute_base_commitment_hash(&mut hashing_account, 0),
+                Ok(())
+            );
+        }
+
+        // Additional computations will fail
+        assert_eq!(
+            compute_base_commitment_hash(&mut hashing_account, 0),
+            Err(ElusivError::ComputationIsAlreadyFinished.into())
+        );
+        assert_eq!(
+            hashing_account.get_state().result(),
+            Fr::from_str(
+                "14744269619966411208579211824598458697587494354926760081771325075741142829156"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_finalize_base_commitment_hash() -> ProgramResult {
+        account_info!(fee_payer, Pubkey::new_unique(), vec![0]);
+        zero_program_account!(mut hashing_slots, BaseCommitmentHashingSlotsAccount);
+        account_info!(
+            h_account,
+            BaseCommitmentHashingAccount::find(Some(0)).0,
+            vec![0; BaseCommitmentHashingAccount::SIZE]
+        );
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+        zero_program_account!(fee, FeeAccount);
+        zero_program_account!(governor, GovernorAccount);
+        test_account_info!(pool, 0);
+        test_account_info!(any, 0);
+
+        // Inactive hashing account
+        {
+            pda_account!(mut h, BaseCommitmentHashingAccount, h_account);
+            h.set_instruction(&(BaseCommitmentHashComputation::IX_COUNT as u32));
+            h.set_fee_payer(&fee_payer.key.to_bytes());
+        }
+        assert_eq!(
+            finalize_base_commitment_hash(
+                &fee_payer,
+                &pool,
+                &fee,
+                &h_account,
+                &mut hashing_slots,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &governor,
+                &any,
+                &any,
+                0,
+                0,
+                0,
+                vec![],
+            ),
+            Err(ElusivError::ComputationIsNotYetStarted.into())
+        );
+
+        // Invalid original fee payer
+        {
+            pda_account!(mut h, BaseCommitmentHashingAccount, h_account);
             h.set_is_active(&true);
             h.set_fee_payer(&[0; 32]);
         }
@@ -1321,10 +2488,16 @@ mod tests {
                 &pool,
                 &fee,
                 &h_account,
+                &mut hashing_slots,
                 &mut commitment_queue,
                 &mut metadata_queue,
+                &governor,
+                &any,
+                &any,
                 0,
-                0
+                0,
+                0,
+                vec![],
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -1341,10 +2514,16 @@ mod tests {
                 &pool,
                 &fee,
                 &h_account,
+                &mut hashing_slots,
                 &mut commitment_queue,
                 &mut metadata_queue,
+                &governor,
+                &any,
+                &any,
                 0,
-                0
+                0,
+                0,
+                vec![],
             ),
             Err(ElusivError::ComputationIsNotYetFinished.into())
         );
@@ -1356,10 +2535,16 @@ mod tests {
                 &pool,
                 &fee,
                 &h_account,
+                &mut hashing_slots,
                 &mut commitment_queue,
                 &mut metadata_queue,
+                &governor,
+                &any,
+                &any,
                 0,
-                1
+                0,
+                1,
+                vec![],
             ),
             Err(ElusivError::InvalidFeeVersion.into())
         );
@@ -1386,32 +2571,471 @@ mod tests {
                 &pool,
                 &fee,
                 &h_account,
+                &mut hashing_slots,
                 &mut commitment_queue,
                 &mut metadata_queue,
+                &governor,
+                &any,
+                &any,
                 0,
+                0,
+                0,
+                vec![],
+            ),
+            Err(ElusivError::QueueIsFull.into())
+        );
+
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        hashing_slots.open(0, fee_payer.key, 0)?;
+
+        // Invalid MT-shard (a single active MT)
+        assert_eq!(
+            finalize_base_commitment_hash(
+                &fee_payer,
+                &pool,
+                &fee,
+                &h_account,
+                &mut hashing_slots,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &governor,
+                &any,
+                &any,
+                1,
+                0,
+                0,
+                vec![],
+            ),
+            Err(ElusivError::InvalidMtShard.into())
+        );
+
+        assert_eq!(
+            finalize_base_commitment_hash(
+                &fee_payer,
+                &pool,
+                &fee,
+                &h_account,
+                &mut hashing_slots,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &governor,
+                &any,
+                &any,
+                0,
+                0,
+                0,
+                vec![],
+            ),
+            Ok(())
+        );
+        assert!(!hashing_slots.get_is_open(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_base_commitment_hash_ciphertext() -> ProgramResult {
+        zero_program_account!(mut hashing_slots, BaseCommitmentHashingSlotsAccount);
+        let fee_payer_pubkey = Pubkey::new_unique();
+        account_info!(fee_payer, fee_payer_pubkey, vec![0]);
+        account_info!(
+            signing_fee_payer,
+            fee_payer_pubkey,
+            vec![0],
+            crate::id(),
+            true
+        );
+        account_info!(
+            h_account,
+            BaseCommitmentHashingAccount::find(Some(0)).0,
+            vec![0; BaseCommitmentHashingAccount::SIZE]
+        );
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+        zero_program_account!(fee, FeeAccount);
+        zero_program_account!(mut governor, GovernorAccount);
+        test_account_info!(pool, 0);
+
+        governor.set_metadata_size_class_max_lens(0, &64);
+
+        let commitment = {
+            pda_account!(mut h, BaseCommitmentHashingAccount, h_account);
+            h.set_is_active(&true);
+            h.set_instruction(&(BaseCommitmentHashComputation::IX_COUNT as u32));
+            h.set_fee_payer(&fee_payer.key.to_bytes());
+            fr_to_u256_le(&h.get_state().result())
+        };
+        account_info!(
+            commitment_metadata_account,
+            CommitmentMetadataAccount::find_with_pubkey(Pubkey::new_from_array(commitment), None).0,
+            vec![0; CommitmentMetadataAccount::SIZE]
+        );
+        let (warden_usage_pubkey, bump) =
+            WardenUsageAccount::find_with_pubkey(fee_payer_pubkey, None);
+        let mut warden_usage_data = vec![0; WardenUsageAccount::SIZE];
+        warden_usage_data[0] = bump;
+        account_info!(warden_usage_account, warden_usage_pubkey, warden_usage_data);
+
+        // The original fee payer is required to sign
+        assert_eq!(
+            finalize_base_commitment_hash(
+                &fee_payer,
+                &pool,
+                &fee,
+                &h_account,
+                &mut hashing_slots,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &governor,
+                &commitment_metadata_account,
+                &warden_usage_account,
+                0,
+                0,
+                0,
+                vec![1; 64],
+            ),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // Ciphertext exceeds the size classes
+        assert_eq!(
+            finalize_base_commitment_hash(
+                &signing_fee_payer,
+                &pool,
+                &fee,
+                &h_account,
+                &mut hashing_slots,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &governor,
+                &commitment_metadata_account,
+                &warden_usage_account,
+                0,
+                0,
+                0,
+                vec![1; 65],
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+        finalize_base_commitment_hash(
+            &signing_fee_payer,
+            &pool,
+            &fee,
+            &h_account,
+            &mut hashing_slots,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &governor,
+            &commitment_metadata_account,
+            &warden_usage_account,
+            0,
+            0,
+            0,
+            vec![1; 64],
+        )
+        .unwrap();
+
+        pda_account!(
+            commitment_metadata_account,
+            CommitmentMetadataAccount,
+            commitment_metadata_account
+        );
+        assert_eq!(commitment_metadata_account.get_commitment(), commitment);
+        assert_eq!(commitment_metadata_account.get_size_class(), 0);
+        assert_eq!(commitment_metadata_account.ciphertext(), &[1; 64]);
+
+        pda_account!(warden_usage, WardenUsageAccount, warden_usage_account);
+        assert_eq!(
+            warden_usage.get_hashing_rounds(),
+            BaseCommitmentHashComputation::TOTAL_ROUNDS as u64
+        );
+        assert_eq!(warden_usage.get_metadata_bytes(), 64);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_backfill_commitment_metadata() {
+        parent_account!(mut storage_account, StorageAccount);
+        zero_program_account!(mut governor, GovernorAccount);
+        test_account_info!(payer, 0);
+        signing_test_account_info!(identifier);
+        account_info!(non_signing_identifier, *identifier.key);
+
+        let commitment = [1; 32];
+        governor.set_metadata_size_class_max_lens(0, &64);
+        storage_account.set_trees_count(&1);
+        storage_account.set_next_commitment_ptr(&2);
+        storage_account
+            .set_node(&commitment, 1, MT_HEIGHT as usize)
+            .unwrap();
+
+        account_info!(
+            commitment_metadata_account,
+            CommitmentMetadataAccount::find_with_pubkey(
+                CommitmentMetadataAccount::backfill_pubkey(&commitment, identifier.key),
+                None
+            )
+            .0,
+            vec![0; CommitmentMetadataAccount::SIZE]
+        );
+        account_info!(
+            non_backfill_account,
+            CommitmentMetadataAccount::find_with_pubkey(Pubkey::new_from_array(commitment), None).0,
+            vec![0; CommitmentMetadataAccount::SIZE]
+        );
+
+        // Empty ciphertext
+        assert_eq!(
+            backfill_commitment_metadata(
+                &payer,
+                &identifier,
+                &storage_account,
+                &governor,
+                &commitment_metadata_account,
+                1,
+                1,
+                commitment,
+                identifier.key.to_bytes(),
+                vec![],
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        // The signer needs to match the identifier of the note
+        for (identifier_account, identifier) in [
+            (&identifier, Pubkey::new_unique().to_bytes()),
+            (&non_signing_identifier, identifier.key.to_bytes()),
+        ] {
+            assert_eq!(
+                backfill_commitment_metadata(
+                    &payer,
+                    identifier_account,
+                    &storage_account,
+                    &governor,
+                    &commitment_metadata_account,
+                    1,
+                    1,
+                    commitment,
+                    identifier,
+                    vec![1; 64],
+                ),
+                Err(ElusivError::InvalidIdentifier.into())
+            );
+        }
+
+        // Position without an inserted commitment or in a closed MT
+        for (mt_index, commitment_index) in [(1, 2), (2, 0), (0, 1)] {
+            assert_eq!(
+                backfill_commitment_metadata(
+                    &payer,
+                    &identifier,
+                    &storage_account,
+                    &governor,
+                    &commitment_metadata_account,
+                    mt_index,
+                    commitment_index,
+                    commitment,
+                    identifier.key.to_bytes(),
+                    vec![1; 64],
+                ),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        // A different commitment at the position
+        assert_eq!(
+            backfill_commitment_metadata(
+                &payer,
+                &identifier,
+                &storage_account,
+                &governor,
+                &commitment_metadata_account,
+                1,
+                0,
+                commitment,
+                identifier.key.to_bytes(),
+                vec![1; 64],
+            ),
+            Err(ElusivError::InputsMismatch.into())
+        );
+
+        // Ciphertext exceeds the size classes
+        assert_eq!(
+            backfill_commitment_metadata(
+                &payer,
+                &identifier,
+                &storage_account,
+                &governor,
+                &commitment_metadata_account,
+                1,
+                1,
+                commitment,
+                identifier.key.to_bytes(),
+                vec![1; 65],
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        // The account needs to be associated with the identifier
+        assert_eq!(
+            backfill_commitment_metadata(
+                &payer,
+                &identifier,
+                &storage_account,
+                &governor,
+                &non_backfill_account,
+                1,
+                1,
+                commitment,
+                identifier.key.to_bytes(),
+                vec![1; 64],
+            ),
+            Err(ProgramError::InvalidSeeds)
+        );
+
+        backfill_commitment_metadata(
+            &payer,
+            &identifier,
+            &storage_account,
+            &governor,
+            &commitment_metadata_account,
+            1,
+            1,
+            commitment,
+            identifier.key.to_bytes(),
+            vec![1; 64],
+        )
+        .unwrap();
+
+        pda_account!(
+            commitment_metadata_account,
+            CommitmentMetadataAccount,
+            commitment_metadata_account
+        );
+        assert_eq!(commitment_metadata_account.get_commitment(), commitment);
+        assert_eq!(commitment_metadata_account.ciphertext(), &[1; 64]);
+        assert_eq!(
+            commitment_metadata_account
+                .get_backfill_identifier()
+                .option(),
+            Some(*identifier.key)
+        );
+        assert_eq!(commitment_metadata_account.get_mt_index(), 1);
+        assert_eq!(commitment_metadata_account.get_commitment_index(), 1);
+    }
+
+    #[test]
+    fn test_close_base_commitment_hashing_account() -> ProgramResult {
+        account_info!(fee_payer, Pubkey::new_unique(), vec![0]);
+        zero_program_account!(mut hashing_slots, BaseCommitmentHashingSlotsAccount);
+        account_info!(other_signer, Pubkey::new_unique(), vec![0]);
+        account_info!(
+            h_account,
+            BaseCommitmentHashingAccount::find(Some(0)).0,
+            vec![0; BaseCommitmentHashingAccount::SIZE]
+        );
+
+        {
+            pda_account!(mut h, BaseCommitmentHashingAccount, h_account);
+            h.setup(
+                BaseCommitmentHashRequest {
+                    base_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                    recent_commitment_index: 0,
+                    amount: LAMPORTS_PER_SOL,
+                    token_id: LAMPORTS_TOKEN_ID,
+                    commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                    fee_version: 0,
+                    min_batching_rate: 0,
+                },
+                CommitmentMetadata::default(),
+                fee_payer.key.to_bytes(),
+            )?;
+            h.set_setup_timestamp(&current_timestamp()?);
+            hashing_slots.open(0, fee_payer.key, current_slot()?)?;
+        }
+
+        // Computation not finished (and timeout not reached)
+        assert_eq!(
+            close_base_commitment_hashing_account(
+                &fee_payer,
+                &fee_payer,
+                &h_account,
+                &mut hashing_slots,
+                0
+            ),
+            Err(ElusivError::ComputationIsNotYetFinished.into())
+        );
+
+        {
+            pda_account!(mut h, BaseCommitmentHashingAccount, h_account);
+            for _ in 0..BaseCommitmentHashComputation::IX_COUNT {
+                compute_base_commitment_hash(&mut h, 0)?;
+            }
+            assert!(h.get_is_active());
+        }
+
+        // Invalid original fee payer
+        assert_eq!(
+            close_base_commitment_hashing_account(
+                &other_signer,
+                &other_signer,
+                &h_account,
+                &mut hashing_slots,
+                0
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Timeout not reached
+        assert_eq!(
+            close_base_commitment_hashing_account(
+                &other_signer,
+                &fee_payer,
+                &h_account,
+                &mut hashing_slots,
                 0
             ),
-            Err(ElusivError::QueueIsFull.into())
+            Err(ElusivError::InvalidAccountState.into())
         );
 
-        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         assert_eq!(
-            finalize_base_commitment_hash(
+            close_base_commitment_hashing_account(
+                &fee_payer,
                 &fee_payer,
-                &pool,
-                &fee,
                 &h_account,
-                &mut commitment_queue,
-                &mut metadata_queue,
-                0,
+                &mut hashing_slots,
                 0
             ),
             Ok(())
         );
+        assert!(!hashing_slots.get_is_open(0));
 
         Ok(())
     }
 
+    #[test]
+    fn test_is_base_commitment_hashing_account_close_timeout_reached() {
+        assert!(!is_base_commitment_hashing_account_close_timeout_reached(
+            0, 0
+        ));
+        assert!(!is_base_commitment_hashing_account_close_timeout_reached(
+            100,
+            100 + BASE_COMMITMENT_HASHING_ACCOUNT_CLOSE_TIMEOUT - 1
+        ));
+        assert!(is_base_commitment_hashing_account_close_timeout_reached(
+            100,
+            100 + BASE_COMMITMENT_HASHING_ACCOUNT_CLOSE_TIMEOUT
+        ));
+        assert!(!is_base_commitment_hashing_account_close_timeout_reached(
+            u64::MAX,
+            u64::MAX
+        ));
+    }
+
     #[test]
     fn test_init_commitment_hash_empty_queue() {
         parent_account!(storage_account, StorageAccount);
@@ -1419,14 +3043,17 @@ mod tests {
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(governor, GovernorAccount);
 
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, 0, false).unwrap();
         assert_eq!(
             init_commitment_hash(
                 &mut commitment_queue,
                 &mut metadata_queue,
                 &mut hashing_account,
                 &mut metadata_account,
+                &governor,
+                0,
                 false
             ),
             Err(ElusivError::QueueIsEmpty.into())
@@ -1439,6 +3066,7 @@ mod tests {
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(governor, GovernorAccount);
 
         {
             let mut commitment_queue = CommitmentQueue::new(&mut commitment_queue);
@@ -1450,6 +3078,7 @@ mod tests {
                 CommitmentMetadata::default(),
                 0,
                 0,
+                &[0; 32],
             )
             .unwrap();
         }
@@ -1462,6 +3091,8 @@ mod tests {
                 &mut metadata_queue,
                 &mut hashing_account,
                 &mut metadata_account,
+                &governor,
+                0,
                 false
             ),
             Err(ElusivError::ComputationIsNotYetFinished.into())
@@ -1475,6 +3106,7 @@ mod tests {
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(governor, GovernorAccount);
 
         {
             let mut commitment_queue = CommitmentQueue::new(&mut commitment_queue);
@@ -1486,18 +3118,21 @@ mod tests {
                 CommitmentMetadata::default(),
                 0,
                 0,
+                &[0; 32],
             )
             .unwrap();
         }
 
         storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, 0, false).unwrap();
         assert_eq!(
             init_commitment_hash(
                 &mut commitment_queue,
                 &mut metadata_queue,
                 &mut hashing_account,
                 &mut metadata_account,
+                &governor,
+                0,
                 false
             ),
             Err(ElusivError::NoRoomForCommitment.into())
@@ -1511,6 +3146,7 @@ mod tests {
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(governor, GovernorAccount);
 
         {
             let mut commitment_queue = CommitmentQueue::new(&mut commitment_queue);
@@ -1522,17 +3158,20 @@ mod tests {
                 CommitmentMetadata::default(),
                 0,
                 1,
+                &[0; 32],
             )
             .unwrap();
         }
 
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, 0, false).unwrap();
         assert_eq!(
             init_commitment_hash(
                 &mut commitment_queue,
                 &mut metadata_queue,
                 &mut hashing_account,
                 &mut metadata_account,
+                &governor,
+                0,
                 false
             ),
             Err(ElusivError::InvalidQueueAccess.into())
@@ -1546,6 +3185,7 @@ mod tests {
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(governor, GovernorAccount);
 
         {
             let mut commitment_queue = CommitmentQueue::new(&mut commitment_queue);
@@ -1558,19 +3198,22 @@ mod tests {
                     CommitmentMetadata::default(),
                     0,
                     1,
+                    &[0; 32],
                 )
                 .unwrap();
             }
         }
 
         storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 1));
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, 0, false).unwrap();
         assert_eq!(
             init_commitment_hash(
                 &mut commitment_queue,
                 &mut metadata_queue,
                 &mut hashing_account,
                 &mut metadata_account,
+                &governor,
+                0,
                 false
             ),
             Err(ElusivError::NoRoomForCommitment.into())
@@ -1585,6 +3228,7 @@ mod tests {
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(governor, GovernorAccount);
 
         let mut c_queue = CommitmentQueue::new(&mut commitment_queue);
         let mut m_queue = MetadataQueue::new(&mut metadata_queue);
@@ -1599,12 +3243,14 @@ mod tests {
             m_queue.enqueue([i; CommitmentMetadata::SIZE]).unwrap();
         }
 
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, 0, false).unwrap();
         init_commitment_hash(
             &mut commitment_queue,
             &mut metadata_queue,
             &mut hashing_account,
             &mut metadata_account,
+            &governor,
+            0,
             false,
         )
         .unwrap();
@@ -1622,6 +3268,139 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_abort_commitment_hash() {
+        parent_account!(mut storage_account, StorageAccount);
+        parent_account!(mut metadata_account, MetadataAccount);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+        zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(governor, GovernorAccount);
+        account_info!(authority, crate::ID, true);
+        test_account_info!(invalid_authority, 0);
+
+        let mut c_queue = CommitmentQueue::new(&mut commitment_queue);
+        let mut m_queue = MetadataQueue::new(&mut metadata_queue);
+        for i in 1..=4 {
+            c_queue
+                .enqueue(CommitmentHashRequest {
+                    commitment: [i; 32],
+                    min_batching_rate: 2,
+                    fee_version: 0,
+                })
+                .unwrap();
+            m_queue.enqueue([i; CommitmentMetadata::SIZE]).unwrap();
+        }
+
+        // Computation not active
+        assert_eq!(
+            abort_commitment_hash(
+                &authority,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &mut hashing_account,
+                &mut metadata_account,
+                &storage_account,
+                0,
+            ),
+            Err(ElusivError::ComputationIsNotYetStarted.into())
+        );
+
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, 0, false).unwrap();
+        init_commitment_hash(
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &mut hashing_account,
+            &mut metadata_account,
+            &governor,
+            0,
+            false,
+        )
+        .unwrap();
+        assert_eq!(CommitmentQueue::new(&mut commitment_queue).len(), 0);
+        assert_eq!(metadata_account.get_next_metadata_ptr(), 4);
+        assert_eq!(
+            hashing_account.get_fee_escrow().commitment_counts,
+            [0, 0, 4, 0, 0]
+        );
+
+        // Invalid authority
+        assert_eq!(
+            abort_commitment_hash(
+                &invalid_authority,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &mut hashing_account,
+                &mut metadata_account,
+                &storage_account,
+                0,
+            ),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // Storage-account has already been modified
+        storage_account.set_next_commitment_ptr(&1);
+        assert_eq!(
+            abort_commitment_hash(
+                &authority,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &mut hashing_account,
+                &mut metadata_account,
+                &storage_account,
+                0,
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+        storage_account.set_next_commitment_ptr(&0);
+
+        abort_commitment_hash(
+            &authority,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &mut hashing_account,
+            &mut metadata_account,
+            &storage_account,
+            0,
+        )
+        .unwrap();
+
+        assert!(!hashing_account.get_is_active());
+        assert!(!hashing_account.get_setup());
+        assert_eq!(metadata_account.get_next_metadata_ptr(), 0);
+        assert_eq!(
+            hashing_account.get_fee_escrow(),
+            CommitmentHashFeeEscrow::default()
+        );
+
+        let c_queue = CommitmentQueue::new(&mut commitment_queue);
+        assert_eq!(c_queue.len(), 4);
+        for i in 0..4 {
+            assert_eq!(c_queue.view(i).unwrap().commitment, [i as u8 + 1; 32]);
+        }
+        let m_queue = MetadataQueue::new(&mut metadata_queue);
+        assert_eq!(m_queue.len(), 4);
+        assert_eq!(m_queue.view(0).unwrap(), [1; CommitmentMetadata::SIZE]);
+
+        // The batch can be hashed again
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, 0, false).unwrap();
+        init_commitment_hash(
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &mut hashing_account,
+            &mut metadata_account,
+            &governor,
+            0,
+            false,
+        )
+        .unwrap();
+        assert_eq!(hashing_account.get_hash_tree(0), [1; 32]);
+        assert_eq!(
+            hashing_account.get_fee_escrow().commitment_counts,
+            [0, 0, 4, 0, 0]
+        );
+    }
+
     #[test]
     fn test_init_commitment_hash_setup_insertion_can_fail() {
         parent_account!(storage_account, StorageAccount);
@@ -1630,22 +3409,75 @@ mod tests {
         hashing_account.set_is_active(&true);
 
         assert_eq!(
-            init_commitment_hash_setup(&mut hashing_account, &storage_account, false),
+            init_commitment_hash_setup(&mut hashing_account, &storage_account, 0, false),
             Err(ElusivError::ComputationIsNotYetFinished.into())
         );
 
         assert_eq!(
-            init_commitment_hash_setup(&mut hashing_account, &storage_account, true),
+            init_commitment_hash_setup(&mut hashing_account, &storage_account, 0, true),
             Ok(())
         );
     }
 
+    #[test]
+    fn test_init_commitment_hash_protocol_paused() {
+        parent_account!(storage_account, StorageAccount);
+        parent_account!(mut metadata_account, MetadataAccount);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+        zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut governor, GovernorAccount);
+
+        {
+            let mut commitment_queue = CommitmentQueue::new(&mut commitment_queue);
+            let mut metadata_queue = MetadataQueue::new(&mut metadata_queue);
+            enqueue_commitment(
+                &mut commitment_queue,
+                &mut metadata_queue,
+                [0; 32],
+                CommitmentMetadata::default(),
+                0,
+                0,
+                &[0; 32],
+            )
+            .unwrap();
+        }
+
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, 0, false).unwrap();
+        governor.set_is_protocol_paused(&true);
+        assert_eq!(
+            init_commitment_hash(
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &mut hashing_account,
+                &mut metadata_account,
+                &governor,
+                0,
+                false
+            ),
+            Err(ElusivError::ProtocolPaused.into())
+        );
+
+        governor.set_is_protocol_paused(&false);
+        init_commitment_hash(
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &mut hashing_account,
+            &mut metadata_account,
+            &governor,
+            0,
+            false,
+        )
+        .unwrap();
+    }
+
     #[test]
     fn test_init_commitment_hash_insertion_can_fail() {
         parent_account!(mut metadata_account, MetadataAccount);
         zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         zero_program_account!(mut metadata_queue, MetadataQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(governor, GovernorAccount);
 
         assert_eq!(
             init_commitment_hash(
@@ -1653,6 +3485,8 @@ mod tests {
                 &mut metadata_queue,
                 &mut hashing_account,
                 &mut metadata_account,
+                &governor,
+                0,
                 false
             ),
             Err(ElusivError::ComputationIsNotYetFinished.into())
@@ -1664,6 +3498,8 @@ mod tests {
                 &mut metadata_queue,
                 &mut hashing_account,
                 &mut metadata_account,
+                &governor,
+                0,
                 true
             ),
             Ok(())
@@ -1676,33 +3512,58 @@ mod tests {
         zero_program_account!(fee, FeeAccount);
         test_account_info!(pool, 0);
         test_account_info!(fee_payer, 0);
+        test_account_info!(any, 0);
 
         // Inactive account
         assert_eq!(
-            compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, 0, 0),
+            compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, &any, 0, 0, 0),
             Err(ElusivError::ComputationIsNotYetStarted.into())
         );
 
         // Invalid fee_version
         hashing_account.set_is_active(&true);
         assert_eq!(
-            compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, 1, 0),
+            compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, &any, 0, 1, 0),
             Err(ElusivError::InvalidFeeVersion.into())
         );
 
-        compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, 0, 0).unwrap();
+        compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, &any, 0, 0, 0)
+            .unwrap();
+        assert_eq!(hashing_account.get_fee_escrow().paid, 0);
+
+        // The compensation is paid out of the fee escrow
+        zero_program_account!(mut fee, FeeAccount);
+        fee.set_program_fee(&ProgramFee::new(5000, 11, 100, 33, 44, 300, 555).unwrap());
+        let compensation = fee.get_program_fee().hash_tx_compensation().0;
+        let pool_lamports = pool.lamports();
+
+        compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, &any, 0, 0, 0)
+            .unwrap();
+        assert_eq!(hashing_account.get_fee_escrow().paid, compensation);
+        assert_eq!(pool.lamports(), pool_lamports - compensation);
     }
 
     #[test]
     fn test_finalize_commitment_hash() {
         parent_account!(mut storage_account, StorageAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(fee, FeeAccount);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
 
         // Computation not finished
         hashing_account.set_is_active(&true);
         hashing_account.set_instruction(&0);
         assert_eq!(
-            finalize_commitment_hash(&mut hashing_account, &mut storage_account),
+            finalize_commitment_hash(
+                &fee,
+                &pool,
+                &fee_collector,
+                &mut hashing_account,
+                &mut storage_account,
+                0,
+                0
+            ),
             Err(ElusivError::ComputationIsNotYetFinished.into())
         );
 
@@ -1711,7 +3572,15 @@ mod tests {
         hashing_account
             .set_instruction(&(commitment_hash_computation_instructions(0).len() as u32));
         assert_eq!(
-            finalize_commitment_hash(&mut hashing_account, &mut storage_account),
+            finalize_commitment_hash(
+                &fee,
+                &pool,
+                &fee_collector,
+                &mut hashing_account,
+                &mut storage_account,
+                0,
+                0
+            ),
             Err(ElusivError::ComputationIsNotYetStarted.into())
         );
 
@@ -1719,18 +3588,53 @@ mod tests {
         hashing_account.set_is_active(&true);
         storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
         assert_eq!(
-            finalize_commitment_hash(&mut hashing_account, &mut storage_account),
+            finalize_commitment_hash(
+                &fee,
+                &pool,
+                &fee_collector,
+                &mut hashing_account,
+                &mut storage_account,
+                0,
+                0
+            ),
             Err(ElusivError::NoRoomForCommitment.into())
         );
 
         storage_account.set_next_commitment_ptr(&0);
-        finalize_commitment_hash(&mut hashing_account, &mut storage_account).unwrap();
+
+        // Invalid fee_version
+        assert_eq!(
+            finalize_commitment_hash(
+                &fee,
+                &pool,
+                &fee_collector,
+                &mut hashing_account,
+                &mut storage_account,
+                0,
+                1
+            ),
+            Err(ElusivError::InvalidFeeVersion.into())
+        );
+
+        finalize_commitment_hash(
+            &fee,
+            &pool,
+            &fee_collector,
+            &mut hashing_account,
+            &mut storage_account,
+            0,
+            0,
+        )
+        .unwrap();
     }
 
     #[test]
     fn test_finalize_commitment_hash_valid() {
         parent_account!(mut storage_account, StorageAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut fee, FeeAccount);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
 
         let batching_rate = 4;
         let commitment_count = commitments_per_batch(batching_rate);
@@ -1740,6 +3644,24 @@ mod tests {
             &(commitment_hash_computation_instructions(batching_rate).len() as u32),
         );
 
+        // Fee escrow of 16 commitments with `min_batching_rate = 3` (and one compensated tx)
+        fee.set_program_fee(&ProgramFee::new(5000, 11, 100, 33, 44, 300, 555).unwrap());
+        let program_fee = fee.get_program_fee();
+        let compensation = program_fee.hash_tx_compensation().0;
+        let mut fee_escrow = CommitmentHashFeeEscrow::default();
+        fee_escrow.commitment_counts[3] = commitment_count as u32;
+        fee_escrow.paid = compensation;
+        hashing_account.set_fee_escrow(&fee_escrow);
+
+        let remaining = fee_escrow.remaining(&program_fee).unwrap();
+        assert_eq!(
+            remaining,
+            program_fee.commitment_hash_computation_fee(3).0 * commitment_count as u64
+                - compensation
+        );
+        let pool_lamports = pool.lamports();
+        let fee_collector_lamports = fee_collector.lamports();
+
         for level_inv in 0..=MT_HEIGHT {
             let level = MT_HEIGHT - level_inv;
             let level_size = commitment_count >> level;
@@ -1752,11 +3674,29 @@ mod tests {
         }
 
         for _ in 0..=batching_rate {
-            finalize_commitment_hash(&mut hashing_account, &mut storage_account).unwrap();
+            finalize_commitment_hash(
+                &fee,
+                &pool,
+                &fee_collector,
+                &mut hashing_account,
+                &mut storage_account,
+                0,
+                0,
+            )
+            .unwrap();
         }
 
         assert!(!hashing_account.get_is_active());
         assert!(!hashing_account.get_setup());
+
+        // Settled fee escrow
+        assert_eq!(pool.lamports(), pool_lamports - remaining);
+        assert_eq!(fee_collector.lamports(), fee_collector_lamports + remaining);
+        assert_eq!(
+            hashing_account.get_fee_escrow(),
+            CommitmentHashFeeEscrow::default()
+        );
+
         assert_eq!(
             storage_account.get_next_commitment_ptr(),
             commitment_count as u32
@@ -1774,4 +3714,74 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_get_mt_opening() {
+        parent_account!(mut storage_account, StorageAccount);
+
+        // Non-existent commitment
+        assert_eq!(
+            get_mt_opening(&storage_account, 0, 0),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        storage_account.set_next_commitment_ptr(&2);
+        assert_eq!(
+            get_mt_opening(&storage_account, 0, 2),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+        assert_eq!(get_mt_opening(&storage_account, 0, 1), Ok(()));
+    }
+
+    #[test]
+    #[cfg(not(feature = "mainnet"))]
+    fn test_verify_poseidon_test_vector() {
+        use crate::commitment::poseidon_hash::{
+            binary_poseidon_hash_partial, BinarySpongeHashingState, TOTAL_POSEIDON_ROUNDS,
+        };
+
+        let serialize = |state: &BinarySpongeHashingState| state.0.map(|fr| fr_to_u256_le(&fr));
+        let mut state = BinarySpongeHashingState::new(Fr::zero(), Fr::zero(), false);
+        let mut states = vec![serialize(&state)];
+        for round in 0..TOTAL_POSEIDON_ROUNDS {
+            binary_poseidon_hash_partial(round, &mut state);
+            states.push(serialize(&state));
+        }
+        assert_eq!(state.result(), full_poseidon2_hash(Fr::zero(), Fr::zero()));
+
+        // Invalid round ranges
+        for (first_round, round_count) in [
+            (0, 0),
+            (0, MAX_POSEIDON_TEST_VECTOR_ROUNDS + 1),
+            (TOTAL_POSEIDON_ROUNDS - 1, 2),
+            (u32::MAX, 1),
+        ] {
+            assert_eq!(
+                verify_poseidon_test_vector(states[0], first_round, round_count, states[1]),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        // Mismatch
+        assert_eq!(
+            verify_poseidon_test_vector(states[0], 0, 1, states[2]),
+            Err(ElusivError::InputsMismatch.into())
+        );
+
+        // Full hash in chunks
+        let mut first_round = 0;
+        while first_round < TOTAL_POSEIDON_ROUNDS {
+            let round_count =
+                MAX_POSEIDON_TEST_VECTOR_ROUNDS.min(TOTAL_POSEIDON_ROUNDS - first_round);
+            let last_round = (first_round + round_count) as usize;
+            verify_poseidon_test_vector(
+                states[first_round as usize],
+                first_round,
+                round_count,
+                states[last_round],
+            )
+            .unwrap();
+            first_round += round_count;
+        }
+    }
 }
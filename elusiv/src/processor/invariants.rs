@@ -0,0 +1,277 @@
+//! Hard invariants of the commitment pipeline, asserted after each successfully processed instruction
+//!
+//! # Note
+//!
+//! - Only active with the `invariants` feature (and in unit tests), intended for test-suites and debug builds.
+//! - The checked accounts are identified among the instruction's accounts (PDAs by pubkey, base-commitment hashing accounts by owner and size).
+//! - Violations panic, so a broken invariant fails the test (or transaction) that caused it instead of a later one.
+
+use crate::commitment::{
+    commitment_hash_computation_instructions, commitment_hash_computation_rounds,
+    commitments_per_batch, BaseCommitmentHashComputation, MAX_COMMITMENT_BATCHING_RATE,
+};
+use crate::state::commitment::{
+    BaseCommitmentHashingAccount, CommitmentHashingAccount, CommitmentQueue,
+    CommitmentQueueAccount, IN_FLIGHT_FEE_PAYERS_COUNT,
+};
+use crate::state::metadata::{MetadataQueue, MetadataQueueAccount};
+use crate::state::program_account::{PDAAccount, ProgramAccount, SizedAccount};
+use crate::state::queue::{Queue, RingQueue};
+use crate::state::storage::StorageAccount;
+use elusiv_computation::PartialComputation;
+use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+use std::cell::RefCell;
+
+/// The state of the commitment pipeline before an instruction
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InvariantsSnapshot {
+    /// `(next_commitment_ptr, trees_count)` of the [`StorageAccount`]
+    pub storage: Option<(u32, u32)>,
+
+    /// `finalization_ix` of the [`CommitmentHashingAccount`]
+    pub finalization_ix: Option<u32>,
+}
+
+thread_local! {
+    static SNAPSHOT: RefCell<InvariantsSnapshot> = RefCell::new(InvariantsSnapshot::default());
+}
+
+fn find_account<'a, 'b>(
+    accounts: &'a [AccountInfo<'b>],
+    pubkey: &Pubkey,
+) -> Option<&'a AccountInfo<'b>> {
+    accounts.iter().find(|account| account.key == pubkey)
+}
+
+fn storage_state(accounts: &[AccountInfo]) -> Option<(u32, u32)> {
+    let account = find_account(accounts, &StorageAccount::find(None).0)?;
+    let mut data = account.data.borrow_mut();
+    let storage_account = StorageAccount::new(&mut data).ok()?;
+
+    Some((
+        storage_account.get_next_commitment_ptr(),
+        storage_account.get_trees_count(),
+    ))
+}
+
+fn finalization_ix(accounts: &[AccountInfo]) -> Option<u32> {
+    let account = find_account(accounts, &CommitmentHashingAccount::find(None).0)?;
+    let mut data = account.data.borrow_mut();
+    let hashing_account = CommitmentHashingAccount::new(&mut data).ok()?;
+
+    Some(hashing_account.get_finalization_ix())
+}
+
+/// Snapshots the state required by [`assert_invariants`]
+pub fn begin_invariants(accounts: &[AccountInfo]) {
+    let snapshot = InvariantsSnapshot {
+        storage: storage_state(accounts),
+        finalization_ix: finalization_ix(accounts),
+    };
+    SNAPSHOT.with(|s| *s.borrow_mut() = snapshot);
+}
+
+/// Asserts all invariants against the snapshot of [`begin_invariants`]
+pub fn assert_invariants(program_id: &Pubkey, accounts: &[AccountInfo]) {
+    let snapshot = SNAPSHOT.with(|s| std::mem::take(&mut *s.borrow_mut()));
+
+    assert_queue_invariants(accounts);
+    assert_hashing_invariants(program_id, accounts);
+    assert_storage_invariants(&snapshot, accounts);
+}
+
+/// Queue headers point into the queue and the in-flight counts never exceed the queued requests
+fn assert_queue_invariants(accounts: &[AccountInfo]) {
+    if let Some(account) = find_account(accounts, &CommitmentQueueAccount::find(None).0) {
+        let mut data = account.data.borrow_mut();
+        let mut queue_account = CommitmentQueueAccount::new(&mut data).unwrap();
+
+        // Requests enqueued without a fee-payer (e.g. by a test setup) are not counted
+        let in_flight: u32 = (0..IN_FLIGHT_FEE_PAYERS_COUNT)
+            .map(|slot| queue_account.get_in_flight_counts(slot))
+            .sum();
+        let queue_len = queue_account.queue_len();
+        assert!(
+            in_flight <= queue_len,
+            "Commitment queue: {} in-flight requests, {} queued",
+            in_flight,
+            queue_len
+        );
+
+        assert_queue_headers(
+            "Commitment queue",
+            &CommitmentQueue::new(&mut queue_account),
+        );
+    }
+
+    if let Some(account) = find_account(accounts, &MetadataQueueAccount::find(None).0) {
+        let mut data = account.data.borrow_mut();
+        let mut queue_account = MetadataQueueAccount::new(&mut data).unwrap();
+        assert_queue_headers("Metadata queue", &MetadataQueue::new(&mut queue_account));
+    }
+}
+
+fn assert_queue_headers<Q: RingQueue>(name: &str, queue: &Q) {
+    let size = queue.size();
+    let (head, tail) = (queue.get_head(), queue.get_tail());
+    assert!(
+        head < size && tail < size,
+        "{}: head {} or tail {} exceed the size {}",
+        name,
+        head,
+        tail,
+        size
+    );
+    assert!(queue.len() <= queue.capacity());
+}
+
+/// The instructions and rounds of hashing accounts never exceed the totals of their computations
+fn assert_hashing_invariants(program_id: &Pubkey, accounts: &[AccountInfo]) {
+    for account in accounts {
+        if account.owner != program_id || account.data_len() != BaseCommitmentHashingAccount::SIZE {
+            continue;
+        }
+
+        let mut data = account.data.borrow_mut();
+        let hashing_account = match BaseCommitmentHashingAccount::new(&mut data) {
+            Ok(hashing_account) => hashing_account,
+            Err(_) => continue,
+        };
+
+        let (instruction, round) = (
+            hashing_account.get_instruction(),
+            hashing_account.get_round(),
+        );
+        assert!(
+            instruction as usize <= BaseCommitmentHashComputation::IX_COUNT
+                && round <= BaseCommitmentHashComputation::TOTAL_ROUNDS,
+            "Base commitment hashing account {}: instruction {}, round {}",
+            account.key,
+            instruction,
+            round
+        );
+    }
+
+    if let Some(account) = find_account(accounts, &CommitmentHashingAccount::find(None).0) {
+        let mut data = account.data.borrow_mut();
+        let hashing_account = CommitmentHashingAccount::new(&mut data).unwrap();
+
+        let batching_rate = hashing_account.get_batching_rate();
+        assert!(
+            batching_rate as usize <= MAX_COMMITMENT_BATCHING_RATE,
+            "Commitment hashing account: batching rate {}",
+            batching_rate
+        );
+
+        let instruction = hashing_account.get_instruction();
+        let round = hashing_account.get_round();
+        let finalization_ix = hashing_account.get_finalization_ix();
+        assert!(
+            instruction as usize <= commitment_hash_computation_instructions(batching_rate).len()
+                && round <= commitment_hash_computation_rounds(batching_rate)
+                && finalization_ix <= batching_rate + 1,
+            "Commitment hashing account: instruction {}, round {}, finalization {} (batching rate {})",
+            instruction,
+            round,
+            finalization_ix,
+            batching_rate
+        );
+    }
+}
+
+/// The storage pointer only advances by the commitments inserted with the final finalization of a batch
+fn assert_storage_invariants(snapshot: &InvariantsSnapshot, accounts: &[AccountInfo]) {
+    let ((ptr_before, trees_before), (ptr_after, trees_after)) =
+        match (snapshot.storage, storage_state(accounts)) {
+            (Some(before), Some(after)) => (before, after),
+            _ => return,
+        };
+
+    // The storage is reset for the next MT
+    if trees_before != trees_after {
+        return;
+    }
+
+    let mut inserted = 0;
+    if let Some(account) = find_account(accounts, &CommitmentHashingAccount::find(None).0) {
+        let mut data = account.data.borrow_mut();
+        let hashing_account = CommitmentHashingAccount::new(&mut data).unwrap();
+        let batching_rate = hashing_account.get_batching_rate();
+        let finalization_ix = hashing_account.get_finalization_ix();
+
+        if snapshot.finalization_ix == Some(batching_rate) && finalization_ix == batching_rate + 1 {
+            inserted = commitments_per_batch(batching_rate) as u32;
+            assert_eq!(
+                ptr_after,
+                hashing_account.get_ordering() + inserted,
+                "Storage pointer does not follow the inserted batch"
+            );
+        }
+    }
+
+    assert_eq!(
+        ptr_after as i64 - ptr_before as i64,
+        inserted as i64,
+        "Storage pointer advanced by {} with {} inserted commitments",
+        ptr_after as i64 - ptr_before as i64,
+        inserted
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::account_info;
+
+    #[test]
+    fn test_assert_invariants() {
+        account_info!(
+            queue,
+            CommitmentQueueAccount::find(None).0,
+            vec![0; CommitmentQueueAccount::SIZE]
+        );
+        let accounts = [queue];
+
+        begin_invariants(&accounts);
+        assert_invariants(&crate::id(), &accounts);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_invariants_queue_headers() {
+        account_info!(
+            queue,
+            CommitmentQueueAccount::find(None).0,
+            vec![0; CommitmentQueueAccount::SIZE]
+        );
+        {
+            let mut data = queue.data.borrow_mut();
+            let mut queue_account = CommitmentQueueAccount::new(&mut data).unwrap();
+            let mut queue = CommitmentQueue::new(&mut queue_account);
+            queue.set_tail(&queue.size());
+        }
+        let accounts = [queue];
+
+        begin_invariants(&accounts);
+        assert_invariants(&crate::id(), &accounts);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_invariants_in_flight_counts() {
+        account_info!(
+            queue,
+            CommitmentQueueAccount::find(None).0,
+            vec![0; CommitmentQueueAccount::SIZE]
+        );
+        {
+            let mut data = queue.data.borrow_mut();
+            let mut queue_account = CommitmentQueueAccount::new(&mut data).unwrap();
+            queue_account.set_in_flight_counts(0, &1);
+        }
+        let accounts = [queue];
+
+        begin_invariants(&accounts);
+        assert_invariants(&crate::id(), &accounts);
+    }
+}
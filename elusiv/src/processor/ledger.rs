@@ -0,0 +1,175 @@
+//! Transient double-entry ledger of the lamport/token movements of an instruction
+//!
+//! # Note
+//!
+//! - Only active with the `balance-tracking` feature (and in unit tests), otherwise [`tracked_transfer`] is a plain call.
+//! - Each movement measures the debit of the source and the credit of the destination, so accounting bugs surface as an unbalanced ledger instead of a drifting pool.
+
+use crate::token::TokenID;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+
+#[cfg(any(test, feature = "balance-tracking"))]
+use crate::token::LAMPORTS_TOKEN_ID;
+#[cfg(any(test, feature = "balance-tracking"))]
+use std::{cell::RefCell, collections::BTreeMap};
+
+/// The debits and credits (per token) of all tracked movements
+#[cfg(any(test, feature = "balance-tracking"))]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BalanceLedger {
+    pub movements: usize,
+
+    /// `(debits, credits)` per token
+    pub entries: BTreeMap<TokenID, (u128, u128)>,
+}
+
+#[cfg(any(test, feature = "balance-tracking"))]
+impl BalanceLedger {
+    fn record(&mut self, token_id: TokenID, debit: u64, credit: u64) {
+        let (debits, credits) = self.entries.entry(token_id).or_default();
+        *debits += debit as u128;
+        *credits += credit as u128;
+        self.movements += 1;
+    }
+
+    /// Returns the first token for which the debits do not equal the credits
+    pub fn unbalanced_token(&self) -> Option<TokenID> {
+        self.entries
+            .iter()
+            .find(|(_, (debits, credits))| debits != credits)
+            .map(|(token_id, _)| *token_id)
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.unbalanced_token().is_none()
+    }
+
+    pub fn assert_balanced(&self) {
+        if let Some(token_id) = self.unbalanced_token() {
+            let (debits, credits) = self.entries[&token_id];
+            panic!(
+                "Unbalanced ledger for token {}: {} debited, {} credited",
+                token_id, debits, credits
+            );
+        }
+    }
+}
+
+#[cfg(any(test, feature = "balance-tracking"))]
+thread_local! {
+    static LEDGER: RefCell<BalanceLedger> = RefCell::new(BalanceLedger::default());
+}
+
+/// Starts a new (empty) ledger
+#[cfg(any(test, feature = "balance-tracking"))]
+pub fn begin_ledger() {
+    LEDGER.with(|ledger| *ledger.borrow_mut() = BalanceLedger::default());
+}
+
+/// Returns the current ledger and starts a new one
+#[cfg(any(test, feature = "balance-tracking"))]
+pub fn finish_ledger() -> BalanceLedger {
+    LEDGER.with(|ledger| std::mem::take(&mut *ledger.borrow_mut()))
+}
+
+/// Returns the lamports or the token-account amount of an account
+#[cfg(any(test, feature = "balance-tracking"))]
+fn balance(account: &AccountInfo, token_id: TokenID) -> Option<u64> {
+    if token_id == LAMPORTS_TOKEN_ID {
+        return Some(account.lamports());
+    }
+
+    crate::token::unpack_token_state::<spl_token_2022::state::Account>(account)
+        .map(|account| account.amount)
+        .ok()
+}
+
+/// Performs `transfer` of `token_id` from `source` to `destination` and records the measured debit and credit
+///
+/// # Note
+///
+/// Movements with accounts, whose balances cannot be read, are only counted.
+pub fn tracked_transfer<F>(
+    source: &AccountInfo,
+    destination: &AccountInfo,
+    token_id: TokenID,
+    transfer: F,
+) -> ProgramResult
+where
+    F: FnOnce() -> ProgramResult,
+{
+    #[cfg(any(test, feature = "balance-tracking"))]
+    {
+        let balances = || balance(source, token_id).zip(balance(destination, token_id));
+
+        let before = balances();
+        transfer()?;
+        let after = balances();
+
+        let (debit, credit) = match (before, after) {
+            (
+                Some((source_before, destination_before)),
+                Some((source_after, destination_after)),
+            ) => (
+                source_before.saturating_sub(source_after),
+                destination_after.saturating_sub(destination_before),
+            ),
+            _ => (0, 0),
+        };
+
+        LEDGER.with(|ledger| ledger.borrow_mut().record(token_id, debit, credit));
+
+        Ok(())
+    }
+
+    #[cfg(not(any(test, feature = "balance-tracking")))]
+    {
+        let _ = (source, destination, token_id);
+        transfer()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::test_account_info;
+    use crate::processor::utils::transfer_lamports_from_pda_checked;
+
+    #[test]
+    fn test_tracked_transfer() {
+        test_account_info!(source);
+        test_account_info!(destination);
+
+        begin_ledger();
+        transfer_lamports_from_pda_checked(&source, &destination, 100).unwrap();
+        transfer_lamports_from_pda_checked(&destination, &source, 30).unwrap();
+
+        let ledger = finish_ledger();
+        assert_eq!(ledger.movements, 2);
+        assert_eq!(ledger.entries[&LAMPORTS_TOKEN_ID], (130, 130));
+        ledger.assert_balanced();
+        assert_eq!(finish_ledger(), BalanceLedger::default());
+
+        // Only the source is debited
+        tracked_transfer(&source, &destination, LAMPORTS_TOKEN_ID, || {
+            **source.try_borrow_mut_lamports()? -= 10;
+            Ok(())
+        })
+        .unwrap();
+        let ledger = finish_ledger();
+        assert_eq!(ledger.unbalanced_token(), Some(LAMPORTS_TOKEN_ID));
+        assert!(!ledger.is_balanced());
+
+        // Failed transfers are not recorded
+        assert!(transfer_lamports_from_pda_checked(&source, &destination, u64::MAX).is_err());
+        assert_eq!(finish_ledger().movements, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_balanced() {
+        let mut ledger = BalanceLedger::default();
+        ledger.record(1, 2, 1);
+        ledger.assert_balanced();
+    }
+}
@@ -1,11 +1,30 @@
 mod accounts;
 mod commitment;
+#[cfg(any(test, feature = "invariants"))]
+pub mod invariants;
+pub mod ledger;
+#[cfg(feature = "pool-yield")]
+mod pool_yield;
 mod proof;
+mod recipient;
 mod utils;
+mod viewing_key;
 mod vkey;
 
 pub use accounts::*;
 pub use commitment::*;
+#[cfg(feature = "pool-yield")]
+pub use pool_yield::*;
 pub use proof::*;
-pub use utils::{nop, program_token_account_address};
+pub use recipient::*;
+#[cfg(feature = "elusiv-client")]
+pub use utils::{
+    apa_flag_account, basic_warden_map_account, warden_attestation_account,
+    warden_attestation_level,
+};
+pub use utils::{
+    check_deprecated_instruction, is_apa_flagged, nop, program_token_account_address,
+    warden_self_test, SelfTestReport, FEATURE_FLAGS_ACCOUNT,
+};
+pub use viewing_key::*;
 pub use vkey::*;
@@ -0,0 +1,643 @@
+//! Delegation of idle pool lamports to a whitelisted SPL stake pool
+//!
+//! # Note
+//!
+//! - Lamports are deposited with `DepositSol` and withdrawn with `WithdrawSol` through the [`pool_yield_authority`] (a data-less PDA).
+//! - The pool tokens are held by the associated token account of the [`pool_yield_authority`].
+//! - Withdrawn lamports are always moved back into the pool.
+//! - The lamports liability is the pool's accounting of unspent notes (see [`MetricsAccount::pool_liability`]), so it can't be reset.
+//! - Payouts exceeding the liquid lamports are rejected (see [`super::utils::verify_pool_liquidity`]) until anyone refills the pool.
+
+use super::proof::lamports_payout;
+use super::utils::transfer_lamports_from_pda_checked;
+use crate::error::ElusivError;
+use crate::event::ElusivEvent;
+use crate::macros::{guard, pda_account};
+use crate::state::governor::{PoolYieldAccount, MAX_POOL_DELEGATION_BPS, MIN_POOL_BUFFER_BPS};
+use crate::state::metrics::MetricsAccount;
+use crate::state::program_account::SizedAccount;
+use crate::state::proof::{VerificationAccount, VerificationState};
+use crate::token::LAMPORTS_TOKEN_ID;
+use crate::types::ProofRequest;
+use elusiv_types::{ElusivOption, UnverifiedAccountInfo};
+use elusiv_utils::open_pda_account_without_offset;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program::invoke_signed;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey, stake, system_instruction, system_program, sysvar,
+};
+
+/// SPL stake pool program (`SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy`)
+pub const SPL_STAKE_POOL_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    6, 129, 78, 212, 202, 246, 138, 23, 70, 114, 253, 172, 134, 3, 26, 99, 232, 78, 161, 94, 250,
+    29, 68, 183, 34, 147, 246, 219, 219, 0, 22, 80,
+]);
+
+const STAKE_POOL_DEPOSIT_SOL: u8 = 14;
+const STAKE_POOL_WITHDRAW_SOL: u8 = 16;
+
+const POOL_YIELD_AUTHORITY_SEED: &[u8] = b"PoolYieldAuthority";
+
+/// The PDA depositing into and withdrawing from the stake pool
+pub fn pool_yield_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POOL_YIELD_AUTHORITY_SEED], &crate::id())
+}
+
+pub fn open_pool_yield_account<'b>(
+    payer: &AccountInfo<'b>,
+    pool_yield_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    open_pda_account_without_offset::<PoolYieldAccount>(
+        &crate::id(),
+        payer,
+        pool_yield_account.get_unsafe(),
+        None,
+    )
+}
+
+/// Whitelists a stake pool and sets the bounds of the delegation
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - `max_delegation_bps` is bounded by [`MAX_POOL_DELEGATION_BPS`], `buffer_bps` by [`MIN_POOL_BUFFER_BPS`]
+/// - the stake pool can only be replaced after all lamports have been withdrawn
+pub fn set_pool_yield_config(
+    authority: &AccountInfo,
+    pool_yield: &mut PoolYieldAccount,
+
+    stake_pool: Pubkey,
+    max_delegation_bps: u16,
+    buffer_bps: u16,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        max_delegation_bps <= MAX_POOL_DELEGATION_BPS
+            && buffer_bps >= MIN_POOL_BUFFER_BPS
+            && buffer_bps <= 10_000,
+        ElusivError::InvalidInstructionData
+    );
+
+    if pool_yield.get_stake_pool().option() != Some(stake_pool) {
+        guard!(
+            pool_yield.get_delegated_lamports() == 0,
+            ElusivError::InvalidAccountState
+        );
+    }
+
+    pool_yield.set_stake_pool(&ElusivOption::Some(stake_pool));
+    pool_yield.set_max_delegation_bps(&max_delegation_bps);
+    pool_yield.set_buffer_bps(&buffer_bps);
+
+    Ok(())
+}
+
+/// Delegates `amount` pool lamports to the whitelisted stake pool
+///
+/// # Note
+///
+/// `amount` is bounded by [`PoolYieldAccount::max_delegation`].
+pub fn delegate_pool_lamports<'a>(
+    authority: &AccountInfo,
+    pool: &AccountInfo<'a>,
+    pool_yield: &mut PoolYieldAccount,
+    metrics: &MetricsAccount,
+    yield_authority: &AccountInfo<'a>,
+    yield_pool_token_account: &AccountInfo<'a>,
+    stake_pool: &AccountInfo<'a>,
+    stake_pool_withdraw_authority: &AccountInfo<'a>,
+    reserve_stake: &AccountInfo<'a>,
+    manager_fee_account: &AccountInfo<'a>,
+    pool_mint: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    stake_pool_program: &AccountInfo<'a>,
+
+    amount: u64,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    let bump = verify_stake_pool_accounts(
+        pool_yield,
+        yield_authority,
+        yield_pool_token_account,
+        stake_pool,
+        pool_mint,
+        token_program,
+        stake_pool_program,
+    )?;
+
+    let liability = metrics.pool_liability(LAMPORTS_TOKEN_ID);
+    guard!(
+        amount > 0 && amount <= pool_yield.max_delegation(pool.lamports(), liability),
+        ElusivError::InvalidAmount
+    );
+
+    // `pool` moves `amount` to the (data-less) `yield_authority`, which deposits into the stake pool
+    transfer_lamports_from_pda_checked(pool, yield_authority, amount)?;
+
+    invoke_signed(
+        &deposit_sol_instruction(
+            stake_pool.key,
+            stake_pool_withdraw_authority.key,
+            reserve_stake.key,
+            yield_authority.key,
+            yield_pool_token_account.key,
+            manager_fee_account.key,
+            pool_mint.key,
+            amount,
+        ),
+        &[
+            stake_pool.clone(),
+            stake_pool_withdraw_authority.clone(),
+            reserve_stake.clone(),
+            yield_authority.clone(),
+            yield_pool_token_account.clone(),
+            manager_fee_account.clone(),
+            pool_mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            stake_pool_program.clone(),
+        ],
+        &[&[POOL_YIELD_AUTHORITY_SEED, &[bump]]],
+    )?;
+
+    let delegated_lamports = pool_yield
+        .get_delegated_lamports()
+        .checked_add(amount)
+        .ok_or(ElusivError::InvalidAmount)?;
+    pool_yield.set_delegated_lamports(&delegated_lamports);
+
+    ElusivEvent::PoolLamportsDelegated {
+        amount,
+        delegated_lamports,
+        pool_balance: pool.lamports(),
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Withdraws lamports from the whitelisted stake pool (by burning `pool_tokens`) back into the pool
+///
+/// # Note
+///
+/// - If the pool lacks the buffer or the payout of `verification_account` (see [`PoolYieldAccount::buffer_deficit`]) any signer can withdraw up to this deficit.
+/// - Otherwise `signer` needs to be the program's keypair.
+/// - The withdrawn lamports are deducted from the delegated principal.
+pub fn undelegate_pool_lamports<'a>(
+    signer: &AccountInfo,
+    pool: &AccountInfo<'a>,
+    pool_yield: &mut PoolYieldAccount,
+    metrics: &MetricsAccount,
+    verification_account: &AccountInfo,
+    yield_authority: &AccountInfo<'a>,
+    yield_pool_token_account: &AccountInfo<'a>,
+    stake_pool: &AccountInfo<'a>,
+    stake_pool_withdraw_authority: &AccountInfo<'a>,
+    reserve_stake: &AccountInfo<'a>,
+    manager_fee_account: &AccountInfo<'a>,
+    pool_mint: &AccountInfo<'a>,
+    clock: &AccountInfo<'a>,
+    stake_history: &AccountInfo<'a>,
+    stake_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    stake_pool_program: &AccountInfo<'a>,
+
+    pool_tokens: u64,
+) -> ProgramResult {
+    let bump = verify_stake_pool_accounts(
+        pool_yield,
+        yield_authority,
+        yield_pool_token_account,
+        stake_pool,
+        pool_mint,
+        token_program,
+        stake_pool_program,
+    )?;
+
+    let liability = metrics.pool_liability(LAMPORTS_TOKEN_ID);
+    let deficit = pool_yield.buffer_deficit(
+        pool.lamports(),
+        liability,
+        pending_payout(verification_account)?,
+    );
+    let is_refill = deficit > 0;
+    guard!(
+        is_refill || *signer.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(pool_tokens > 0, ElusivError::InvalidAmount);
+
+    let signers_seeds: &[&[&[u8]]] = &[&[POOL_YIELD_AUTHORITY_SEED, &[bump]]];
+    let balance = yield_authority.lamports();
+
+    invoke_signed(
+        &withdraw_sol_instruction(
+            stake_pool.key,
+            stake_pool_withdraw_authority.key,
+            yield_authority.key,
+            yield_pool_token_account.key,
+            reserve_stake.key,
+            manager_fee_account.key,
+            pool_mint.key,
+            pool_tokens,
+        ),
+        &[
+            stake_pool.clone(),
+            stake_pool_withdraw_authority.clone(),
+            yield_authority.clone(),
+            yield_pool_token_account.clone(),
+            reserve_stake.clone(),
+            manager_fee_account.clone(),
+            pool_mint.clone(),
+            clock.clone(),
+            stake_history.clone(),
+            stake_program.clone(),
+            token_program.clone(),
+            stake_pool_program.clone(),
+        ],
+        signers_seeds,
+    )?;
+
+    // `yield_authority` moves all withdrawn lamports into the pool
+    let amount = yield_authority
+        .lamports()
+        .checked_sub(balance)
+        .ok_or(ElusivError::InvalidAmount)?;
+    guard!(
+        *signer.key == crate::ID || amount <= deficit,
+        ElusivError::InvalidAmount
+    );
+    invoke_signed(
+        &system_instruction::transfer(yield_authority.key, pool.key, amount),
+        &[
+            yield_authority.clone(),
+            pool.clone(),
+            system_program.clone(),
+        ],
+        signers_seeds,
+    )?;
+
+    let delegated_lamports = pool_yield.get_delegated_lamports().saturating_sub(amount);
+    pool_yield.set_delegated_lamports(&delegated_lamports);
+
+    ElusivEvent::PoolLamportsUndelegated {
+        amount,
+        delegated_lamports,
+        pool_balance: pool.lamports(),
+        is_refill,
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Returns the lamports the pool still has to pay out for a finalized send with a valid proof (zero for any other account)
+fn pending_payout(verification_account_info: &AccountInfo) -> Result<u64, ProgramError> {
+    if *verification_account_info.owner != crate::id()
+        || verification_account_info.data_len() != VerificationAccount::SIZE
+    {
+        return Ok(0);
+    }
+
+    pda_account!(
+        verification_account,
+        VerificationAccount,
+        verification_account_info
+    );
+    if verification_account.get_state() != VerificationState::Finalized
+        || verification_account.get_is_verified().option() != Some(true)
+    {
+        return Ok(0);
+    }
+
+    let request = verification_account.get_request();
+    match &request {
+        ProofRequest::Send(public_inputs)
+            if public_inputs.join_split.token_id == LAMPORTS_TOKEN_ID =>
+        {
+            lamports_payout(&request, &verification_account.get_other_data())
+        }
+        _ => Ok(0),
+    }
+}
+
+/// Verifies the whitelisted stake pool and the accounts of the [`pool_yield_authority`] and returns the authority's bump
+fn verify_stake_pool_accounts(
+    pool_yield: &PoolYieldAccount,
+    yield_authority: &AccountInfo,
+    yield_pool_token_account: &AccountInfo,
+    stake_pool: &AccountInfo,
+    pool_mint: &AccountInfo,
+    token_program: &AccountInfo,
+    stake_pool_program: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    guard!(
+        pool_yield.get_stake_pool().option() == Some(*stake_pool.key),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        *stake_pool.owner == SPL_STAKE_POOL_PROGRAM_ID
+            && *stake_pool_program.key == SPL_STAKE_POOL_PROGRAM_ID,
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        *token_program.key == spl_token::ID,
+        ElusivError::InvalidAccount
+    );
+
+    let (authority, bump) = pool_yield_authority();
+    guard!(
+        *yield_authority.key == authority,
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        *yield_pool_token_account.key
+            == spl_associated_token_account::get_associated_token_address(
+                &authority,
+                pool_mint.key
+            ),
+        ElusivError::InvalidAccount
+    );
+
+    Ok(bump)
+}
+
+/// `StakePoolInstruction::DepositSol`
+fn deposit_sol_instruction(
+    stake_pool: &Pubkey,
+    stake_pool_withdraw_authority: &Pubkey,
+    reserve_stake: &Pubkey,
+    lamports_from: &Pubkey,
+    pool_tokens_to: &Pubkey,
+    manager_fee_account: &Pubkey,
+    pool_mint: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    let mut data = vec![STAKE_POOL_DEPOSIT_SOL];
+    data.extend(lamports.to_le_bytes());
+
+    Instruction {
+        program_id: SPL_STAKE_POOL_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*stake_pool, false),
+            AccountMeta::new_readonly(*stake_pool_withdraw_authority, false),
+            AccountMeta::new(*reserve_stake, false),
+            AccountMeta::new(*lamports_from, true),
+            AccountMeta::new(*pool_tokens_to, false),
+            AccountMeta::new(*manager_fee_account, false),
+            // The referral fee is paid to the manager
+            AccountMeta::new(*manager_fee_account, false),
+            AccountMeta::new(*pool_mint, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ],
+        data,
+    }
+}
+
+/// `StakePoolInstruction::WithdrawSol`
+fn withdraw_sol_instruction(
+    stake_pool: &Pubkey,
+    stake_pool_withdraw_authority: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    pool_tokens_from: &Pubkey,
+    reserve_stake: &Pubkey,
+    manager_fee_account: &Pubkey,
+    pool_mint: &Pubkey,
+    pool_tokens: u64,
+) -> Instruction {
+    let mut data = vec![STAKE_POOL_WITHDRAW_SOL];
+    data.extend(pool_tokens.to_le_bytes());
+
+    Instruction {
+        program_id: SPL_STAKE_POOL_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*stake_pool, false),
+            AccountMeta::new_readonly(*stake_pool_withdraw_authority, false),
+            AccountMeta::new_readonly(*user_transfer_authority, true),
+            AccountMeta::new(*pool_tokens_from, false),
+            AccountMeta::new(*reserve_stake, false),
+            // Lamports are received by the (system owned) `user_transfer_authority`
+            AccountMeta::new(*user_transfer_authority, false),
+            AccountMeta::new(*manager_fee_account, false),
+            AccountMeta::new(*pool_mint, false),
+            AccountMeta::new_readonly(sysvar::clock::ID, false),
+            AccountMeta::new_readonly(sysvar::stake_history::ID, false),
+            AccountMeta::new_readonly(stake::program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{account_info, zero_program_account};
+    use crate::state::proof::VerificationAccountData;
+    use crate::types::{JoinSplitPublicInputs, OptionalFee, RawU256, SendPublicInputs};
+
+    #[test]
+    fn test_set_pool_yield_config() {
+        zero_program_account!(mut pool_yield, PoolYieldAccount);
+        account_info!(authority, crate::ID, vec![]);
+        account_info!(invalid_authority, Pubkey::new_unique(), vec![]);
+        let stake_pool = Pubkey::new_unique();
+
+        assert_eq!(
+            set_pool_yield_config(
+                &invalid_authority,
+                &mut pool_yield,
+                stake_pool,
+                1_000,
+                5_000
+            ),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        for (max_delegation_bps, buffer_bps) in [
+            (MAX_POOL_DELEGATION_BPS + 1, MIN_POOL_BUFFER_BPS),
+            (MAX_POOL_DELEGATION_BPS, MIN_POOL_BUFFER_BPS - 1),
+            (MAX_POOL_DELEGATION_BPS, 10_001),
+        ] {
+            assert_eq!(
+                set_pool_yield_config(
+                    &authority,
+                    &mut pool_yield,
+                    stake_pool,
+                    max_delegation_bps,
+                    buffer_bps
+                ),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        assert_eq!(
+            set_pool_yield_config(&authority, &mut pool_yield, stake_pool, 1_000, 5_000),
+            Ok(())
+        );
+        assert_eq!(pool_yield.get_stake_pool().option(), Some(stake_pool));
+
+        // The stake pool cannot be replaced while lamports are delegated
+        pool_yield.set_delegated_lamports(&1);
+        assert_eq!(
+            set_pool_yield_config(
+                &authority,
+                &mut pool_yield,
+                Pubkey::new_unique(),
+                1_000,
+                5_000
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+        assert_eq!(
+            set_pool_yield_config(&authority, &mut pool_yield, stake_pool, 0, 10_000),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_verify_stake_pool_accounts() {
+        zero_program_account!(mut pool_yield, PoolYieldAccount);
+        let (authority, bump) = pool_yield_authority();
+        let mint = Pubkey::new_unique();
+        let stake_pool_key = Pubkey::new_unique();
+
+        account_info!(yield_authority, authority, vec![]);
+        account_info!(
+            yield_pool_token_account,
+            spl_associated_token_account::get_associated_token_address(&authority, &mint),
+            vec![]
+        );
+        account_info!(
+            stake_pool,
+            stake_pool_key,
+            vec![],
+            SPL_STAKE_POOL_PROGRAM_ID,
+            false
+        );
+        account_info!(pool_mint, mint, vec![]);
+        account_info!(token_program, spl_token::ID, vec![]);
+        account_info!(stake_pool_program, SPL_STAKE_POOL_PROGRAM_ID, vec![]);
+
+        // Stake pool not whitelisted
+        assert_eq!(
+            verify_stake_pool_accounts(
+                &pool_yield,
+                &yield_authority,
+                &yield_pool_token_account,
+                &stake_pool,
+                &pool_mint,
+                &token_program,
+                &stake_pool_program
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        pool_yield.set_stake_pool(&ElusivOption::Some(stake_pool_key));
+        assert_eq!(
+            verify_stake_pool_accounts(
+                &pool_yield,
+                &yield_authority,
+                &yield_pool_token_account,
+                &stake_pool,
+                &pool_mint,
+                &token_program,
+                &stake_pool_program
+            ),
+            Ok(bump)
+        );
+
+        // Pool tokens not held by the yield authority
+        assert_eq!(
+            verify_stake_pool_accounts(
+                &pool_yield,
+                &yield_authority,
+                &pool_mint,
+                &stake_pool,
+                &pool_mint,
+                &token_program,
+                &stake_pool_program
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+    }
+
+    #[test]
+    fn test_pending_payout() {
+        let mut public_inputs = SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![],
+                output_commitment: RawU256::new([0; 32]),
+                recent_commitment_index: 0,
+                fee_version: 0,
+                amount: 1_000_000,
+                fee: 0,
+                optional_fee: OptionalFee::default(),
+                token_id: LAMPORTS_TOKEN_ID,
+                metadata: Default::default(),
+            },
+            recipient_is_associated_token_account: false,
+            solana_pay_transfer: false,
+            hashed_inputs: [0; 32],
+            expiry: 0,
+            unlock_timestamp: 0,
+            earliest_execution_slot: 0,
+            latest_execution_slot: 0,
+        };
+
+        // Any account
+        account_info!(any, Pubkey::new_unique(), vec![]);
+        assert_eq!(pending_payout(&any), Ok(0));
+
+        account_info!(
+            verification_account,
+            Pubkey::new_unique(),
+            vec![0; VerificationAccount::SIZE]
+        );
+        {
+            pda_account!(mut v_acc, VerificationAccount, verification_account);
+            v_acc.set_request(&ProofRequest::Send(public_inputs.clone()));
+            v_acc.set_other_data(&VerificationAccountData {
+                network_fee: 10,
+                priority_fee: 5,
+                ..Default::default()
+            });
+            v_acc.set_is_verified(&ElusivOption::Some(true));
+        }
+
+        // Not yet finalized
+        assert_eq!(pending_payout(&verification_account), Ok(0));
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, verification_account);
+            v_acc.set_state(&VerificationState::Finalized);
+        }
+        assert_eq!(pending_payout(&verification_account), Ok(1_000_015));
+
+        // Invalid proof
+        {
+            pda_account!(mut v_acc, VerificationAccount, verification_account);
+            v_acc.set_is_verified(&ElusivOption::Some(false));
+        }
+        assert_eq!(pending_payout(&verification_account), Ok(0));
+
+        // Token sends are not paid out of the lamports pool
+        public_inputs.join_split.token_id = 1;
+        {
+            pda_account!(mut v_acc, VerificationAccount, verification_account);
+            v_acc.set_request(&ProofRequest::Send(public_inputs));
+            v_acc.set_is_verified(&ElusivOption::Some(true));
+        }
+        assert_eq!(pending_payout(&verification_account), Ok(0));
+    }
+}
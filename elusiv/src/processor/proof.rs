@@ -2,37 +2,59 @@ use super::utils::{DefaultInstructionsSysvar, InstructionsSysvar};
 use crate::buffer::RingBuffer;
 use crate::bytes::{usize_as_u32_safe, BorshSerDeSized, ElusivOption};
 use crate::error::ElusivError;
+use crate::event::ElusivEvent;
+use crate::identifier::verify_identifier_format;
 use crate::instruction::ElusivInstruction;
 use crate::macros::{guard, pda_account, BorshSerDeSized, EnumVariantIndex};
 use crate::processor::utils::{
-    close_account, create_associated_token_account, spl_token_account_rent,
+    close_account, consume_warden_nonce, create_associated_token_account, current_epoch,
+    current_slot, current_timestamp, is_apa_flagged, record_warden_usage, spl_token_account_rent,
     system_program_account_rent, transfer_lamports_from_pda_checked, transfer_token,
-    transfer_token_from_pda, verify_program_token_account,
+    transfer_token_from_pda, transfer_token_from_pda_with_mint, transfer_with_system_program,
+    verify_basic_warden, verify_pool_liquidity, verify_program_token_account,
+    verify_warden_attestation, warden_attested_region,
 };
-use crate::processor::{enqueue_commitment, verify_recent_commitment_index, ZERO_COMMITMENT_RAW};
-use crate::proof::verifier::{prepare_public_inputs_instructions, verify_partial};
+use crate::processor::{
+    enqueue_commitment_with_priority, payout_preference_wallet, recipient_accepts_payout,
+    record_quarantined_payout, record_viewing_key_memo, signal_commitment_queue_pressure,
+    verify_recent_commitment_index, ZERO_COMMITMENT_RAW,
+};
+use crate::proof::verifier::prepare_public_inputs_instructions;
+#[cfg(not(feature = "alt-bn128"))]
+use crate::proof::verifier::verify_partial;
 use crate::proof::vkey::{MigrateUnaryVKey, SendQuadraVKey, VerifyingKey, VerifyingKeyInfo};
 use crate::state::commitment::{CommitmentBufferAccount, CommitmentQueue, CommitmentQueueAccount};
-use crate::state::governor::{FeeCollectorAccount, GovernorAccount, PoolAccount};
+use crate::state::fee::token_fee_spread;
+use crate::state::governor::{
+    ApaEnforcement, FeeCollectorAccount, GovernorAccount, PoolAccount, TokenRegistryAccount,
+};
+use crate::state::history::{VerificationHistoryAccount, VerificationRecord};
 use crate::state::metadata::{MetadataQueue, MetadataQueueAccount};
+use crate::state::metrics::MetricsAccount;
 use crate::state::nullifier::NullifierAccount;
+use crate::state::pending_release::PendingReleaseAccount;
+use crate::state::program_account::{PDAAccount, PDAAccountData};
+#[cfg(feature = "alt-bn128")]
+use crate::state::proof::{BatchVerificationAccount, MAX_BATCH_VERIFICATION_SIZE};
 use crate::state::proof::{
-    NullifierDuplicateAccount, VerificationAccount, VerificationAccountData, VerificationState,
+    NullifierDuplicateAccount, VerificationAccount, VerificationAccountData,
+    VerificationCheckpoint, VerificationState, VerifierRAMAccount,
 };
 use crate::state::queue::{Queue, RingQueue};
-use crate::state::storage::{StorageAccount, MT_COMMITMENT_COUNT};
+use crate::state::storage::{mt_index_shard, StorageAccount, MT_COMMITMENT_COUNT, MT_SHARD_COUNT};
 use crate::state::vkey::VKeyAccount;
 use crate::token::{
     elusiv_token, verify_associated_token_account, verify_token_account, Lamports, Token,
-    TokenPrice,
 };
 use crate::types::{
     generate_hashed_inputs, InputCommitment, JoinSplitPublicInputs, MigratePublicInputs, Proof,
-    PublicInputs, RawU256, SendPublicInputs, JOIN_SPLIT_MAX_N_ARITY, U256,
+    PublicInputs, RawU256, SendPublicInputs, U256,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
-use elusiv_types::ParentAccount;
-use elusiv_utils::open_pda_account_with_associated_pubkey;
+use elusiv_types::{ParentAccount, SizedAccount};
+use elusiv_utils::{
+    open_pda_account_with_associated_pubkey, open_pda_account_with_associated_pubkey_and_rent_owner,
+};
 use solana_program::instruction::Instruction;
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
@@ -76,6 +98,14 @@ impl ProofRequest {
         }
     }
 
+    /// Returns true for requests that move no funds out of the pool (merges and migrations)
+    pub fn is_merge(&self) -> bool {
+        match self {
+            ProofRequest::Send(request) => request.join_split.amount == 0,
+            ProofRequest::Migrate(_) => true,
+        }
+    }
+
     pub fn vkey_id(&self) -> u32 {
         match self {
             ProofRequest::Send(_) => SendQuadraVKey::VKEY_ID,
@@ -90,35 +120,91 @@ pub const MAX_MT_COUNT: usize = 2;
 /// The maximum [`PDAOffset`] for [`VerificationAccount`] for a single fee payer
 pub const RESERVED_VERIFICATION_ACCOUNT_IDS: u8 = 128;
 
+/// Ceiling for the number of [`VerificationAccount`] instances per fee payer configured in the [`GovernorAccount`]
+pub const MAX_VERIFICATION_ACCOUNT_INSTANCES: u8 = RESERVED_VERIFICATION_ACCOUNT_IDS + 1;
+
 /// Initializes a new proof verification
 /// - subsequent calls of [`init_verification_transfer_fee`] and [`init_verification_proof`] required to start the computation
 /// - both need to be called by the same signer (-> the fee structure "enforces" [`init_verification_transfer_fee`] to be called in the same transaction)
+/// - using `reuse_verification_account` the [`VerificationAccount`] is kept open after a successful finalization,
+///   an already existing (closed) account at the same index is recycled instead of being rented again
+/// - the execution window (`earliest_execution_slot` and `latest_execution_slot` of send requests) is bound to the proof through `hashed_inputs`, the finalization is restricted to it
+/// - fails with [`ElusivError::CommitmentQueuePressure`] while the commitment queue is above the governor's watermark (clients should backoff)
+/// - fails with [`ElusivError::AmountExceedsTreeAgeLimit`] if the amount exceeds the governor's limit for the commitment count of the active MT the output commitment is assigned to
+/// - `tree_indices` specify the MTs (and with them the MT-shards, see [`crate::state::storage::sharded_mt_index`]) of the roots, `storage_account` and `storage_account1` are the [`StorageAccount`]s of the MT-shards
+/// - `recipient` is verified against `hashed_inputs` (using `recipient_binding`) and persisted, so it cannot be swapped before [`finalize_verification_send`]
+/// - send requests with a non-zero `expiry` are rejected after their expiry (both here and in [`finalize_verification_send`])
+/// - the funds of send requests with a non-zero `unlock_timestamp` are parked by [`finalize_verification_transfer_lamports`] until the unlock (see [`release_pending_transfer`])
+/// - the `identifier_account` and the `iv` need to follow the canonical format (see [`crate::identifier`])
+/// - a `warden_nonce` is consumed in the fee payer's [`crate::state::warden_nonce::WardenNonceAccount`] before any account is opened (duplicate submissions fail cheaply), the governor can require nonces
 #[allow(clippy::too_many_arguments)]
 pub fn init_verification<'a, 'b, 'c, 'd>(
     fee_payer: &AccountInfo<'a>,
     verification_account: &AccountInfo<'a>,
     vkey_account: &VKeyAccount,
     nullifier_duplicate_account: &AccountInfo<'a>,
-    _identifier_account: &AccountInfo,
+    recipient: &AccountInfo,
+    identifier_account: &AccountInfo,
     storage_account: &StorageAccount,
+    storage_account1: &StorageAccount,
     commitment_buffer: &mut CommitmentBufferAccount,
     nullifier_account0: &NullifierAccount<'b, 'c, 'd>,
     nullifier_account1: &NullifierAccount<'b, 'c, 'd>,
+    governor: &GovernorAccount,
+    commitment_hash_queue: &CommitmentQueueAccount,
+    warden_attestation_account: &AccountInfo,
+    warden_nonce_account: &AccountInfo,
 
+    protocol_version: u8,
     verification_account_index: u8,
     vkey_id: u32,
     tree_indices: [u32; MAX_MT_COUNT],
     request: ProofRequest,
     skip_nullifier_pda: bool,
+    reuse_verification_account: bool,
+    recipient_binding: RecipientBinding,
+    warden_nonce: ElusivOption<u64>,
 ) -> ProgramResult {
+    guard!(
+        !governor.get_is_protocol_paused(),
+        ElusivError::ProtocolPaused
+    );
+    guard!(
+        governor.is_supported_protocol_version(protocol_version),
+        ElusivError::UnsupportedProtocolVersion
+    );
+
+    consume_warden_nonce(
+        fee_payer.key,
+        warden_nonce_account,
+        warden_nonce,
+        governor.get_require_warden_nonces(),
+    )?;
+
     let raw_public_inputs = proof_request!(&request, public_inputs, public_inputs.public_signals());
 
     // Verify that an immutable vkey is setup
     guard!(vkey_account.is_setup(), ElusivError::InvalidAccount);
 
+    // In permissioned mode only attested wardens can verify proofs
+    verify_warden_attestation(
+        fee_payer.key,
+        warden_attestation_account,
+        governor.get_required_warden_attestation_level(),
+    )?;
+
+    // Reject early (before any fees are paid), if the commitment queue is congested
+    guard!(
+        !governor.is_commitment_queue_under_pressure(commitment_hash_queue.queue_len()),
+        ElusivError::CommitmentQueuePressure
+    );
+
     guard!(vkey_id == request.vkey_id(), ElusivError::InvalidAccount);
     guard!(
-        verification_account_index <= RESERVED_VERIFICATION_ACCOUNT_IDS,
+        governor.is_valid_verification_account_index_for_request(
+            verification_account_index,
+            request.is_merge()
+        ),
         ElusivError::InvalidAccount
     );
 
@@ -141,6 +227,22 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
                 public_inputs.verify_additional_constraints(),
                 ElusivError::InvalidPublicInputs
             );
+            guard!(
+                !public_inputs.is_expired(current_timestamp()?),
+                ElusivError::RequestExpired
+            );
+            guard!(
+                !public_inputs.is_after_execution_window(current_slot()?),
+                ElusivError::LatestExecutionSlotPassed
+            );
+
+            // Bind the recipient to the proof (prevents a recipient swap between init and finalize)
+            verify_recipient_binding(
+                public_inputs,
+                recipient,
+                identifier_account,
+                &recipient_binding,
+            )?;
 
             &public_inputs.join_split
         }
@@ -150,13 +252,26 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
         }
     };
 
+    let storage_accounts = [storage_account, storage_account1];
     check_join_split_public_inputs(
         join_split,
-        storage_account,
+        storage_accounts,
         [nullifier_account0, nullifier_account1],
         &tree_indices,
     )?;
 
+    // Large amounts need to wait until the active MT (receiving the output commitment) provides an adequate anonymity set
+    let output_mt_shard = governor.commitment_mt_shard(&join_split.output_commitment.reduce());
+    if let Some(max_amount) = governor.max_amount_for_tree_age(
+        join_split.token_id,
+        storage_accounts[output_mt_shard as usize].get_next_commitment_ptr(),
+    ) {
+        guard!(
+            join_split.amount <= max_amount,
+            ElusivError::AmountExceedsTreeAgeLimit
+        );
+    }
+
     // Open [`NullifierDuplicateAccount`]
     // - this account is used to prevent two proof verifications (of the same nullifier-hashes) at the same time
     // - using `skip_nullifier_pda` a second verification can be initialized, for more details see OS-ELV-ADV-05
@@ -182,15 +297,21 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
         )?;
     }
 
-    // Open `VerificationAccount`
-    open_pda_account_with_associated_pubkey::<VerificationAccount>(
-        &crate::id(),
-        fee_payer,
-        verification_account,
-        fee_payer.key,
-        Some(verification_account_index as u32),
-        None,
-    )?;
+    // Open `VerificationAccount` (or recycle an existing one)
+    let reuse_existing_account = reuse_verification_account
+        && verification_account.data_len() == VerificationAccount::SIZE
+        && *verification_account.owner == crate::id();
+
+    if !reuse_existing_account {
+        open_pda_account_with_associated_pubkey::<VerificationAccount>(
+            &crate::id(),
+            fee_payer,
+            verification_account,
+            fee_payer.key,
+            Some(verification_account_index as u32),
+            None,
+        )?;
+    }
 
     // Add the output commitment into the commitment-buffer
     commitment_buffer.try_insert(&join_split.output_commitment.reduce())?;
@@ -201,6 +322,12 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
         verification_account
     );
 
+    if reuse_existing_account {
+        verification_account.reset_for_reuse()?;
+    }
+
+    verification_account.set_init_slot(&current_slot()?);
+
     verification_account.setup(
         RawU256::new(fee_payer.key.to_bytes()),
         skip_nullifier_pda,
@@ -209,7 +336,16 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
         vkey_id,
         request,
         tree_indices,
-    )
+        reuse_verification_account,
+    )?;
+
+    verification_account.set_other_data(&mutate(&verification_account.get_other_data(), |data| {
+        data.recipient_wallet = ElusivOption::Some(RawU256::new(recipient.key.to_bytes()));
+        data.reset_generation = governor.get_reset_generation();
+    }));
+    verification_account.set_identifier(&ElusivOption::Some(*identifier_account.key));
+
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -227,6 +363,7 @@ pub fn init_verification_transfer_fee<'a>(
     token_usd_price_account: &AccountInfo,
 
     governor: &GovernorAccount,
+    token_registry: &TokenRegistryAccount,
     verification_account: &mut VerificationAccount,
     token_program: &AccountInfo<'a>,
     system_program: &AccountInfo<'a>,
@@ -247,14 +384,12 @@ pub fn init_verification_transfer_fee<'a>(
     let request = verification_account.get_request();
     let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
 
-    guard!(
-        request.fee_version() == governor.get_fee_version(),
-        ElusivError::InvalidFeeVersion
-    );
+    let fee = governor.program_fee_for_quote(request.fee_version(), current_slot()?)?;
     let token_id = join_split.token_id;
-    let price = TokenPrice::new(sol_usd_price_account, token_usd_price_account, token_id)?;
+    token_registry.enforce_verified_token(token_id)?;
+    let price =
+        token_registry.token_price(sol_usd_price_account, token_usd_price_account, token_id)?;
     let min_batching_rate = governor.get_commitment_batching_rate();
-    let fee = governor.get_program_fee();
     let subvention = fee.proof_subvention.into_token(&price, token_id)?;
     let input_preparation_tx_count =
         verification_account.get_prepare_inputs_instructions_count() as usize;
@@ -263,12 +398,23 @@ pub fn init_verification_transfer_fee<'a>(
         .into_token(&price, token_id)?;
     let commitment_hash_fee = fee.commitment_hash_computation_fee(min_batching_rate);
     let commitment_hash_fee_token = commitment_hash_fee.into_token(&price, token_id)?;
-    let network_fee = Token::new(token_id, fee.proof_network_fee.calc(join_split.amount));
+
+    // Settling the computation fees in the sent token is charged a spread (collected with the network fee)
+    let spread = token_fee_spread(
+        (commitment_hash_fee_token + proof_verification_fee)?,
+        governor.get_token_fee_spread_bps(),
+    );
+    let network_fee =
+        (Token::new(token_id, fee.proof_network_fee.calc(join_split.amount)) + spread)?;
 
     let fee =
         (((commitment_hash_fee_token + proof_verification_fee)? + network_fee)? - subvention)?;
     guard!(join_split.fee >= fee.amount(), ElusivError::InvalidFee);
 
+    // Any fee exceeding the required fees is a bid for an earlier commitment insertion
+    let priority_fee = Token::new(token_id, join_split.fee - fee.amount());
+    let priority = price.token_into_lamports(priority_fee)?;
+
     verify_program_token_account(pool, pool_account, token_id)?;
     verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
 
@@ -344,6 +490,10 @@ pub fn init_verification_transfer_fee<'a>(
         commitment_hash_fee_token: commitment_hash_fee_token.amount(),
         proof_verification_fee: proof_verification_fee.amount(),
         associated_token_account_rent: associated_token_account_rent_token,
+        reusable: other_data.reusable,
+        priority_fee: priority_fee.amount(),
+        priority: priority.0,
+        reset_generation: other_data.reset_generation,
     });
 
     verification_account.set_state(&VerificationState::FeeTransferred);
@@ -390,13 +540,20 @@ pub fn init_verification_proof(
 pub const COMPUTE_VERIFICATION_IX_COUNT: u16 = 7; // two compute-unit-instructions, five compute-instructions
 
 /// Partial proof verification computation
+///
+/// # Note
+///
+/// - With the `alt-bn128` feature the whole verification happens in a single call.
+/// - The first call binds the (unused) `ram_account` to the verification, it's released once the computation is finished.
 pub fn compute_verification(
     verification_account: &mut VerificationAccount,
+    ram_account_info: &AccountInfo,
     vkey_account: &VKeyAccount,
     instructions_account: &AccountInfo,
 
     _verification_account_index: u8,
     vkey_id: u32,
+    _ram_account_index: u32,
 ) -> ProgramResult {
     // Verify that an immutable vkey is setup
     guard!(vkey_account.is_setup(), ElusivError::InvalidAccount);
@@ -416,6 +573,23 @@ pub fn compute_verification(
         ),
         ElusivError::InvalidAccountState
     );
+    guard!(
+        verification_account.get_checkpoint().option().is_none(),
+        ElusivError::InvalidAccountState
+    );
+
+    pda_account!(mut ram_account, VerifierRAMAccount, ram_account_info);
+    match verification_account.get_ram_account().option() {
+        Some(pubkey) => guard!(pubkey == *ram_account_info.key, ElusivError::InvalidAccount),
+        None => {
+            guard!(
+                !ram_account.get_is_in_use(),
+                ElusivError::InvalidAccountState
+            );
+            ram_account.set_is_in_use(&true);
+            verification_account.set_ram_account(&ElusivOption::Some(*ram_account_info.key));
+        }
+    }
 
     // instruction_index is used to allow a uniform number of ixs per tx
     let instruction_index = if cfg!(test) {
@@ -428,7 +602,16 @@ pub fn compute_verification(
         let vkey = VerifyingKey::new(data, vkey_account.get_public_inputs_count() as usize)
             .ok_or(ElusivError::InvalidAccountState)?;
 
-        verify_partial(verification_account, &vkey, instruction_index)
+        #[cfg(feature = "alt-bn128")]
+        return verify_alt_bn128(verification_account, &vkey, instruction_index);
+
+        #[cfg(not(feature = "alt-bn128"))]
+        verify_partial(
+            verification_account,
+            &mut ram_account,
+            &vkey,
+            instruction_index,
+        )
     })?;
 
     match result {
@@ -436,6 +619,8 @@ pub fn compute_verification(
             if let Some(final_result) = result {
                 // After last round we receive the verification result
                 verification_account.set_is_verified(&ElusivOption::Some(final_result));
+                verification_account.set_verification_timestamp(&current_timestamp()?);
+                ram_account.set_is_in_use(&false);
             }
 
             Ok(())
@@ -446,6 +631,7 @@ pub fn compute_verification(
                 _ => {
                     // An error (!= InvalidAccountState) can only happen with flawed inputs -> cancel verification
                     verification_account.set_is_verified(&ElusivOption::Some(false));
+                    ram_account.set_is_in_use(&false);
                     Ok(())
                 }
             }
@@ -453,226 +639,1660 @@ pub fn compute_verification(
     }
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Clone, Default)]
-pub struct FinalizeSendData {
-    pub total_amount: u64,
-    pub token_id: u16,
+/// Exports a checkpoint of a started partial verification computation
+///
+/// # Note
+///
+/// - The checkpoint commits to the computation state (see [`VerificationAccount::checkpoint_hash`]) and the signing `warden`.
+/// - The computation is paused until the checkpoint is imported into a fresh [`VerifierRAMAccount`] (see [`import_verification_checkpoint`]).
+pub fn export_verification_checkpoint(
+    warden: &AccountInfo,
+    verification_account: &mut VerificationAccount,
+    ram_account_info: &AccountInfo,
 
-    /// Estimated index of the MT in which the next-commitment will be inserted
-    pub mt_index: u32,
+    _verification_account_index: u8,
+    _ram_account_index: u32,
+) -> ProgramResult {
+    guard!(
+        verification_account.get_is_verified().option().is_none(),
+        ElusivError::ComputationIsAlreadyFinished
+    );
+    guard!(
+        verification_account.get_checkpoint().option().is_none(),
+        ElusivError::InvalidAccountState
+    );
+    match verification_account.get_ram_account().option() {
+        Some(pubkey) => guard!(pubkey == *ram_account_info.key, ElusivError::InvalidAccount),
+        None => return Err(ElusivError::ComputationIsNotYetStarted.into()),
+    }
 
-    /// Estimated index of the next-commitment in the MT
-    pub commitment_index: u32,
+    pda_account!(ram_account, VerifierRAMAccount, ram_account_info);
+    let hash = verification_account.checkpoint_hash(&ram_account);
+    verification_account.set_checkpoint(&ElusivOption::Some(VerificationCheckpoint {
+        hash,
+        exporter: *warden.key,
+    }));
 
-    pub iv: U256,
-    pub encrypted_owner: U256,
+    Ok(())
 }
 
-const SPL_MEMO_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
-    5, 74, 83, 90, 153, 41, 33, 6, 77, 36, 232, 113, 96, 218, 56, 124, 124, 53, 181, 221, 188, 146,
-    187, 129, 228, 31, 168, 64, 65, 5, 68, 141,
-]);
-
-/// First finalize instruction
-///
-/// # Notes
+/// Imports the exported checkpoint of a verification into the unused `new_ram_account` and resumes the computation with it
 ///
-/// The complete transactions requires to include:
-/// - for a valid proof:
-///     [`finalize_verification_send`],
-///     [`finalize_verification_insert_nullifier`]+,
-///     [`finalize_verification_transfer_lamports`] or [`finalize_verification_transfer_token`].
+/// # Note
 ///
-/// - for an invalid proof:
-///     [`finalize_verification_send`],
-///     [`finalize_verification_transfer_lamports`] or [`finalize_verification_transfer_token`].
-#[allow(clippy::too_many_arguments)]
-pub fn finalize_verification_send(
-    recipient: &AccountInfo,
-    identifier_account: &AccountInfo,
-    transaction_reference: &AccountInfo, // if no reference is used, set this account to the same as `instructions_account`
-    commitment_hash_queue: &mut CommitmentQueueAccount,
+/// - `checkpoint_hash` is the hash received from the exporting warden, the import fails if it does not match the exported checkpoint.
+/// - The computation state is verified against the checkpoint before and after the import.
+pub fn import_verification_checkpoint(
     verification_account: &mut VerificationAccount,
-    storage_account: &StorageAccount,
-    buffer: &mut CommitmentBufferAccount,
-    instructions_account: &AccountInfo,
+    ram_account_info: &AccountInfo,
+    new_ram_account_info: &AccountInfo,
 
-    verification_account_index: u8,
-    data: FinalizeSendData,
-    uses_memo: bool,
+    _verification_account_index: u8,
+    _ram_account_index: u32,
+    _new_ram_account_index: u32,
+    checkpoint_hash: U256,
 ) -> ProgramResult {
+    let checkpoint = verification_account
+        .get_checkpoint()
+        .option()
+        .ok_or(ElusivError::InvalidAccountState)?;
     guard!(
-        verification_account.get_state() == VerificationState::ProofSetup,
-        ElusivError::InvalidAccountState
+        checkpoint.hash == checkpoint_hash,
+        ElusivError::InputsMismatch
     );
-
-    let request = verification_account.get_request();
-    let public_inputs = match request {
-        ProofRequest::Send(public_inputs) => public_inputs,
-        _ => return Err(ElusivError::FeatureNotAvailable.into()),
-    };
-
-    // Check spl-memo-instruction
-    let memo = if uses_memo {
-        Some(get_memo_from_instructions(
-            &DefaultInstructionsSysvar(instructions_account),
-            public_inputs.solana_pay_transfer,
-        )?)
-    } else {
-        None
-    };
-
-    // Verify `hashed_inputs`
-    let hash = generate_hashed_inputs(
-        &recipient.key.to_bytes(),
-        &identifier_account.key.to_bytes(),
-        &data.iv,
-        &data.encrypted_owner,
-        &(if transaction_reference.key != instructions_account.key {
-            transaction_reference.key.to_bytes()
-        } else {
-            [0; 32]
-        }),
-        public_inputs.recipient_is_associated_token_account,
-        &public_inputs.join_split.metadata,
-        &public_inputs.join_split.optional_fee,
-        &memo,
+    guard!(
+        verification_account.get_ram_account().option() == Some(*ram_account_info.key),
+        ElusivError::InvalidAccount
     );
     guard!(
-        hash == public_inputs.hashed_inputs,
-        ElusivError::InputsMismatch
+        new_ram_account_info.key != ram_account_info.key,
+        ElusivError::InvalidAccount
     );
 
-    // Set `recipient_wallet`
-    verification_account.set_other_data(&mutate(&verification_account.get_other_data(), |data| {
-        data.recipient_wallet = ElusivOption::Some(RawU256::new(recipient.key.to_bytes()))
-    }));
+    pda_account!(mut ram_account, VerifierRAMAccount, ram_account_info);
+    pda_account!(
+        mut new_ram_account,
+        VerifierRAMAccount,
+        new_ram_account_info
+    );
+    guard!(
+        !new_ram_account.get_is_in_use(),
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        verification_account.checkpoint_hash(&ram_account) == checkpoint.hash,
+        ElusivError::InvalidAccountState
+    );
 
-    match verification_account.get_is_verified() {
-        ElusivOption::None => return Err(ElusivError::ComputationIsNotYetFinished.into()),
-        ElusivOption::Some(false) => {
-            verification_account.set_state(&VerificationState::Finalized);
+    new_ram_account.copy_rams_from(&ram_account);
+    guard!(
+        verification_account.checkpoint_hash(&new_ram_account) == checkpoint.hash,
+        ElusivError::InvalidAccountState
+    );
 
-            // Attempt to remove the commitment from the commitment-buffer
-            if let Some(index) =
-                buffer.find_position(&public_inputs.join_split.output_commitment.reduce())
-            {
-                buffer.set_value(index, &[0; 32]);
-            }
+    ram_account.set_is_in_use(&false);
+    new_ram_account.set_is_in_use(&true);
+    verification_account.set_ram_account(&ElusivOption::Some(*new_ram_account_info.key));
+    verification_account.set_checkpoint(&ElusivOption::None);
 
-            return Ok(());
-        }
-        _ => {}
-    }
+    Ok(())
+}
 
-    enforce_finalize_send_instructions(
-        instructions_account,
-        public_inputs.join_split.token_id == 0,
-        verification_account_index,
-    )?;
+/// Releases the [`VerifierRAMAccount`] bound to a stale verification, after [`VERIFICATION_CANCELLATION_TIMEOUT`]
+///
+/// # Notes
+///
+/// - Permissionless, so abandoned verifications can't lock the pooled RAM accounts.
+/// - The computation is restarted (with a fresh RAM account) by the next [`compute_verification`], the `fee_payer` can still use [`cancel_verification`].
+pub fn release_stale_verifier_ram_account(
+    verification_account: &mut VerificationAccount,
+    ram_account_info: &AccountInfo,
 
-    let (commitment_index, mt_index) = minimum_commitment_mt_index(
-        storage_account.get_trees_count(),
-        storage_account.get_next_commitment_ptr(),
-        CommitmentQueue::new(commitment_hash_queue).len(),
-    );
+    _verification_account_index: u8,
+    _ram_account_index: u32,
+) -> ProgramResult {
     guard!(
-        data.total_amount == public_inputs.join_split.total_amount(),
-        ElusivError::InputsMismatch
+        verification_account.get_ram_account().option() == Some(*ram_account_info.key),
+        ElusivError::InvalidAccount
     );
     guard!(
-        data.token_id == public_inputs.join_split.token_id,
-        ElusivError::InputsMismatch
+        verification_account.get_is_verified().option().is_none(),
+        ElusivError::ComputationIsAlreadyFinished
     );
     guard!(
-        data.commitment_index <= commitment_index,
-        ElusivError::InputsMismatch
+        is_cancellation_timeout_reached(verification_account.get_init_slot(), current_slot()?),
+        ElusivError::CancellationTimeoutNotReached
     );
-    guard!(data.mt_index == mt_index, ElusivError::InputsMismatch);
 
-    verification_account.set_state(&VerificationState::InsertNullifiers);
-    verification_account.set_instruction(&0);
+    pda_account!(mut ram_account, VerifierRAMAccount, ram_account_info);
+    ram_account.set_is_in_use(&false);
+    verification_account.restart_computation();
 
     Ok(())
 }
 
-pub fn finalize_verification_insert_nullifier(
+/// Verifies the whole proof in a single call using the `alt_bn128` syscalls
+///
+/// # Note
+///
+/// Like in [`crate::proof::verifier::verify_partial`] only the last instruction of a transaction performs the computation.
+#[cfg(feature = "alt-bn128")]
+fn verify_alt_bn128(
     verification_account: &mut VerificationAccount,
-    nullifier_account: &mut NullifierAccount,
-
-    _verification_account_index: u8,
-) -> ProgramResult {
-    // TODO: Handle the case in which a duplicate verification has failed (funds flow to fee-collector)
+    vkey: &VerifyingKey,
+    instruction_index: u16,
+) -> Result<Option<bool>, ElusivError> {
+    if instruction_index != COMPUTE_VERIFICATION_IX_COUNT - 1 {
+        return Ok(None);
+    }
 
     guard!(
-        verification_account.get_state() == VerificationState::InsertNullifiers,
+        matches!(
+            verification_account.get_state(),
+            VerificationState::ProofSetup
+        ),
         ElusivError::InvalidAccountState
     );
 
-    let request = verification_account.get_request();
-    let public_inputs = match request {
-        ProofRequest::Send(public_inputs) => public_inputs,
-        _ => return Err(ElusivError::FeatureNotAvailable.into()),
+    let proof = Proof {
+        a: verification_account.a.get(),
+        b: verification_account.b.get(),
+        c: verification_account.c.get(),
     };
+    let public_inputs: Vec<U256> = (0..vkey.public_inputs_count)
+        .map(|i| verification_account.load_raw_public_input(i))
+        .collect();
 
-    let input_commitment_index = verification_account.get_instruction() as usize;
-    if input_commitment_index < public_inputs.join_split.input_commitments.len() {
-        // Insert nullifier hashes
-        let mut tree_index = 0;
-        for (index, input_commitment) in public_inputs
-            .join_split
-            .input_commitments
-            .iter()
-            .enumerate()
-        {
-            let tree_index = match input_commitment.root {
-                Some(_) => {
-                    let t = tree_index;
-                    tree_index += 1;
-                    t
-                }
-                None => 0,
-            };
-
-            if tree_index != 0 {
-                // TODO: add support for arbitrary MTs
-                return Err(ElusivError::FeatureNotAvailable.into());
-            }
+    crate::proof::alt_bn128::verify_proof(vkey, &proof, &public_inputs).map(Some)
+}
 
-            if index == input_commitment_index {
-                nullifier_account
-                    .try_insert_nullifier_hash(input_commitment.nullifier_hash.reduce())?;
-                break;
+/// Groups up to [`MAX_BATCH_VERIFICATION_SIZE`] [`VerificationAccount`]s of the `fee_payer` for a combined verification
+///
+/// # Note
+///
+/// - Only the first `count` accounts (and `verification_account_indices`) are used, the remaining accounts are ignored.
+/// - All proofs need to be setup and share the same vkey, their partial computation cannot be started yet.
+#[cfg(feature = "alt-bn128")]
+pub fn init_batch_verification<'a>(
+    fee_payer: &AccountInfo<'a>,
+    batch_verification_account_info: &AccountInfo<'a>,
+    verification_account0: &AccountInfo,
+    verification_account1: &AccountInfo,
+    verification_account2: &AccountInfo,
+    verification_account3: &AccountInfo,
+
+    batch_verification_account_index: u8,
+    vkey_id: u32,
+    verification_account_indices: [u8; MAX_BATCH_VERIFICATION_SIZE],
+    count: u8,
+) -> ProgramResult {
+    let count = count as usize;
+    guard!(
+        count > 1 && count <= MAX_BATCH_VERIFICATION_SIZE,
+        ElusivError::InvalidInstructionData
+    );
+
+    let verification_account_indices = &verification_account_indices[..count];
+    guard!(
+        verification_account_indices
+            .iter()
+            .collect::<HashSet<_>>()
+            .len()
+            == count,
+        ElusivError::InvalidInstructionData
+    );
+
+    let verification_accounts = [
+        verification_account0,
+        verification_account1,
+        verification_account2,
+        verification_account3,
+    ];
+
+    for (verification_account_info, verification_account_index) in verification_accounts
+        .iter()
+        .zip(verification_account_indices)
+    {
+        verify_batched_verification_account(
+            fee_payer,
+            verification_account_info,
+            *verification_account_index,
+            vkey_id,
+        )?;
+    }
+
+    open_pda_account_with_associated_pubkey::<BatchVerificationAccount>(
+        &crate::id(),
+        fee_payer,
+        batch_verification_account_info,
+        fee_payer.key,
+        Some(batch_verification_account_index as u32),
+        None,
+    )?;
+
+    pda_account!(
+        mut batch_verification_account,
+        BatchVerificationAccount,
+        batch_verification_account_info
+    );
+    batch_verification_account.set_vkey_id(&vkey_id);
+    batch_verification_account.set_count(&(count as u8));
+    for (i, verification_account_index) in verification_account_indices.iter().enumerate() {
+        batch_verification_account.set_verification_account_indices(i, verification_account_index);
+    }
+
+    Ok(())
+}
+
+/// Verifies all proofs of a [`BatchVerificationAccount`] in a single call and closes the batch
+///
+/// # Note
+///
+/// - If the batch is valid, all [`VerificationAccount`]s are verified.
+/// - An invalid batch does not identify the invalid proof, so the [`VerificationAccount`]s are left for their individual computation.
+#[cfg(feature = "alt-bn128")]
+pub fn compute_batch_verification<'a>(
+    fee_payer: &AccountInfo<'a>,
+    batch_verification_account_info: &AccountInfo<'a>,
+    vkey_account: &VKeyAccount,
+    verification_account0: &AccountInfo,
+    verification_account1: &AccountInfo,
+    verification_account2: &AccountInfo,
+    verification_account3: &AccountInfo,
+
+    _batch_verification_account_index: u8,
+    vkey_id: u32,
+) -> ProgramResult {
+    // Verify that an immutable vkey is setup
+    guard!(vkey_account.is_setup(), ElusivError::InvalidAccount);
+
+    let verification_account_indices = {
+        pda_account!(
+            batch_verification_account,
+            BatchVerificationAccount,
+            batch_verification_account_info
+        );
+        guard!(
+            batch_verification_account.get_vkey_id() == vkey_id,
+            ElusivError::InvalidAccount
+        );
+
+        (0..batch_verification_account.get_count() as usize)
+            .map(|i| batch_verification_account.get_verification_account_indices(i))
+            .collect::<Vec<u8>>()
+    };
+
+    let verification_accounts = [
+        verification_account0,
+        verification_account1,
+        verification_account2,
+        verification_account3,
+    ];
+    let public_inputs_count = vkey_account.get_public_inputs_count() as usize;
+
+    let mut proofs = Vec::with_capacity(verification_account_indices.len());
+    for (verification_account_info, verification_account_index) in verification_accounts
+        .iter()
+        .zip(&verification_account_indices)
+    {
+        verify_batched_verification_account(
+            fee_payer,
+            verification_account_info,
+            *verification_account_index,
+            vkey_id,
+        )?;
+
+        pda_account!(
+            mut verification_account,
+            VerificationAccount,
+            verification_account_info
+        );
+        let proof = Proof {
+            a: verification_account.a.get(),
+            b: verification_account.b.get(),
+            c: verification_account.c.get(),
+        };
+        let public_inputs = (0..public_inputs_count)
+            .map(|i| verification_account.load_raw_public_input(i))
+            .collect();
+
+        proofs.push((proof, public_inputs));
+    }
+
+    let result = vkey_account.execute_on_child_account_mut(0, |data| {
+        let vkey =
+            VerifyingKey::new(data, public_inputs_count).ok_or(ElusivError::InvalidAccountState)?;
+
+        crate::proof::alt_bn128::verify_proofs_batched(&vkey, &proofs)
+    })?;
+
+    if let Ok(true) = result {
+        let timestamp = current_timestamp()?;
+        for verification_account_info in verification_accounts
+            .iter()
+            .take(verification_account_indices.len())
+        {
+            pda_account!(
+                mut verification_account,
+                VerificationAccount,
+                verification_account_info
+            );
+            verification_account.set_is_verified(&ElusivOption::Some(true));
+            verification_account.set_verification_timestamp(&timestamp);
+        }
+    }
+
+    close_account(fee_payer, batch_verification_account_info)
+}
+
+/// Verifies that a [`VerificationAccount`] of the `fee_payer` can be verified in a batch
+#[cfg(feature = "alt-bn128")]
+fn verify_batched_verification_account(
+    fee_payer: &AccountInfo,
+    verification_account_info: &AccountInfo,
+    verification_account_index: u8,
+    vkey_id: u32,
+) -> ProgramResult {
+    guard!(
+        *verification_account_info.owner == crate::id(),
+        ElusivError::InvalidAccount
+    );
+    VerificationAccount::verify_account_with_pubkey(
+        verification_account_info,
+        *fee_payer.key,
+        Some(verification_account_index.into()),
+    )?;
+
+    pda_account!(
+        verification_account,
+        VerificationAccount,
+        verification_account_info
+    );
+    guard!(
+        verification_account.get_vkey_id() == vkey_id,
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        verification_account.get_is_verified().option().is_none(),
+        ElusivError::ComputationIsAlreadyFinished
+    );
+    guard!(
+        verification_account.get_state() == VerificationState::ProofSetup
+            && verification_account.get_ram_account().option().is_none(),
+        ElusivError::InvalidAccountState
+    );
+
+    Ok(())
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default)]
+pub struct FinalizeSendData {
+    pub total_amount: u64,
+    pub token_id: u16,
+
+    /// Estimated index of the MT in which the next-commitment will be inserted
+    pub mt_index: u32,
+
+    /// Estimated index of the next-commitment in the MT
+    pub commitment_index: u32,
+
+    pub iv: U256,
+    pub encrypted_owner: U256,
+}
+
+/// The remaining preimage of `hashed_inputs` (besides the recipient and identifier accounts), used to bind the recipient at [`init_verification`]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq)]
+pub struct RecipientBinding {
+    pub iv: U256,
+    pub encrypted_owner: U256,
+
+    /// `[0; 32]` if no transaction reference is used
+    pub transaction_reference: U256,
+
+    pub memo: Option<Vec<u8>>,
+}
+
+const SPL_MEMO_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    5, 74, 83, 90, 153, 41, 33, 6, 77, 36, 232, 113, 96, 218, 56, 124, 124, 53, 181, 221, 188, 146,
+    187, 129, 228, 31, 168, 64, 65, 5, 68, 141,
+]);
+
+/// First finalize instruction
+///
+/// # Notes
+///
+/// The complete transactions requires to include:
+/// - for a valid proof:
+///     [`finalize_verification_send`],
+///     [`finalize_verification_insert_nullifier`]+,
+///     [`finalize_verification_transfer_lamports`] or [`finalize_verification_transfer_token`].
+///
+/// - for an invalid proof:
+///     [`finalize_verification_send`],
+///     [`finalize_verification_transfer_lamports`] or [`finalize_verification_transfer_token`].
+///
+/// The memo of a valid send is recorded, if a viewing key has been published for the `identifier` (see [`record_viewing_key_memo`]).
+#[allow(clippy::too_many_arguments)]
+pub fn finalize_verification_send(
+    recipient: &AccountInfo,
+    identifier_account: &AccountInfo,
+    transaction_reference: &AccountInfo, // if no reference is used, set this account to the same as `instructions_account`
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+    verification_account: &mut VerificationAccount,
+    storage_account: &StorageAccount,
+    buffer: &mut CommitmentBufferAccount,
+    instructions_account: &AccountInfo,
+    viewing_key_account: &AccountInfo,
+    governor: &GovernorAccount,
+
+    verification_account_index: u8,
+    data: FinalizeSendData,
+    uses_memo: bool,
+) -> ProgramResult {
+    guard!(
+        verification_account.get_state() == VerificationState::ProofSetup,
+        ElusivError::InvalidAccountState
+    );
+
+    // Verifications initialized before a devnet reset cannot be finalized (see [`reset_verification_account`])
+    guard!(
+        verification_account.get_other_data().reset_generation == governor.get_reset_generation(),
+        ElusivError::InvalidAccountState
+    );
+
+    let request = verification_account.get_request();
+    let public_inputs = match request {
+        ProofRequest::Send(public_inputs) => public_inputs,
+        _ => return Err(ElusivError::FeatureNotAvailable.into()),
+    };
+    guard!(
+        !public_inputs.is_expired(current_timestamp()?),
+        ElusivError::RequestExpired
+    );
+    verify_execution_window(&public_inputs, current_slot()?)?;
+
+    // Check spl-memo-instruction
+    let memo = if uses_memo {
+        Some(get_memo_from_instructions(
+            &DefaultInstructionsSysvar(instructions_account),
+            public_inputs.solana_pay_transfer,
+        )?)
+    } else {
+        None
+    };
+
+    // The recipient is bound at `init_verification`
+    if let ElusivOption::Some(recipient_wallet) =
+        verification_account.get_other_data().recipient_wallet
+    {
+        guard!(
+            recipient_wallet.skip_mr() == recipient.key.to_bytes(),
+            ElusivError::InputsMismatch
+        );
+    }
+
+    // Verify `hashed_inputs`
+    let hash = generate_hashed_inputs(
+        &recipient.key.to_bytes(),
+        &identifier_account.key.to_bytes(),
+        &data.iv,
+        &data.encrypted_owner,
+        &(if transaction_reference.key != instructions_account.key {
+            transaction_reference.key.to_bytes()
+        } else {
+            [0; 32]
+        }),
+        public_inputs.recipient_is_associated_token_account,
+        &public_inputs.join_split.metadata,
+        &public_inputs.join_split.optional_fee,
+        public_inputs.expiry,
+        public_inputs.unlock_timestamp,
+        (
+            public_inputs.earliest_execution_slot,
+            public_inputs.latest_execution_slot,
+        ),
+        &memo,
+    );
+    guard!(
+        hash == public_inputs.hashed_inputs,
+        ElusivError::InputsMismatch
+    );
+
+    // Set `recipient_wallet`
+    verification_account.set_other_data(&mutate(&verification_account.get_other_data(), |data| {
+        data.recipient_wallet = ElusivOption::Some(RawU256::new(recipient.key.to_bytes()))
+    }));
+
+    match verification_account.get_is_verified() {
+        ElusivOption::None => return Err(ElusivError::ComputationIsNotYetFinished.into()),
+        ElusivOption::Some(false) => {
+            verification_account.set_state(&VerificationState::Finalized);
+
+            // Attempt to remove the commitment from the commitment-buffer
+            if let Some(index) =
+                buffer.find_position(&public_inputs.join_split.output_commitment.reduce())
+            {
+                buffer.set_value(index, &[0; 32]);
+            }
+
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    enforce_finalize_send_instructions(
+        instructions_account,
+        public_inputs.join_split.token_id == 0,
+        verification_account_index,
+    )?;
+
+    // The output commitment is inserted into the active MT of its MT-shard
+    guard!(
+        mt_index_shard(data.mt_index)
+            == governor.commitment_mt_shard(&public_inputs.join_split.output_commitment.reduce()),
+        ElusivError::InvalidMtShard
+    );
+
+    let (commitment_index, mt_index) = minimum_commitment_mt_index(
+        storage_account.get_trees_count(),
+        storage_account.get_next_commitment_ptr(),
+        CommitmentQueue::new(commitment_hash_queue).len(),
+    );
+    guard!(
+        data.total_amount == public_inputs.join_split.total_amount(),
+        ElusivError::InputsMismatch
+    );
+    guard!(
+        data.token_id == public_inputs.join_split.token_id,
+        ElusivError::InputsMismatch
+    );
+    guard!(
+        data.commitment_index <= commitment_index,
+        ElusivError::InputsMismatch
+    );
+    guard!(data.mt_index == mt_index, ElusivError::InputsMismatch);
+
+    if let Some(memo) = memo {
+        record_viewing_key_memo(viewing_key_account, identifier_account.key, &memo)?;
+    }
+
+    verification_account.set_state(&VerificationState::InsertNullifiers);
+    verification_account.set_instruction(&0);
+
+    Ok(())
+}
+
+pub fn finalize_verification_insert_nullifier(
+    verification_account: &mut VerificationAccount,
+    nullifier_account: &mut NullifierAccount,
+
+    _verification_account_index: u8,
+) -> ProgramResult {
+    // TODO: Handle the case in which a duplicate verification has failed (funds flow to fee-collector)
+
+    guard!(
+        verification_account.get_state() == VerificationState::InsertNullifiers,
+        ElusivError::InvalidAccountState
+    );
+
+    let request = verification_account.get_request();
+    let public_inputs = match request {
+        ProofRequest::Send(public_inputs) => public_inputs,
+        _ => return Err(ElusivError::FeatureNotAvailable.into()),
+    };
+
+    let input_commitment_index = verification_account.get_instruction() as usize;
+    if input_commitment_index < public_inputs.join_split.input_commitments.len() {
+        // TODO: add support for arbitrary MTs
+        guard!(
+            public_inputs
+                .join_split
+                .input_commitments
+                .iter()
+                .skip(1)
+                .all(|input_commitment| input_commitment.root.is_none()),
+            ElusivError::FeatureNotAvailable
+        );
+
+        // Insert nullifier hashes (in their canonical order)
+        let nullifier_hashes = public_inputs.join_split.canonical_nullifier_hashes();
+        nullifier_account
+            .try_insert_nullifier_hash(nullifier_hashes[input_commitment_index].reduce())?;
+
+        verification_account.set_instruction(&(input_commitment_index as u32 + 1));
+    } else if !nullifier_account.is_moved_nullifier_empty() {
+        // Insert moved nullifier hashes
+        nullifier_account.move_nullifier_hashes_to_next_account()?;
+    }
+
+    if input_commitment_index >= public_inputs.join_split.input_commitments.len() - 1
+        && nullifier_account.is_moved_nullifier_empty()
+    {
+        verification_account.set_state(&VerificationState::Finalized);
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn finalize_verification_transfer_lamports<'a>(
+    original_fee_payer: &AccountInfo<'a>,
+    recipient: &AccountInfo<'a>, // can be any account for merge/migrate
+    pool: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    optional_fee_collector: &AccountInfo<'a>,
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+    metadata_queue: &mut MetadataQueueAccount,
+    verification_account_info: &AccountInfo<'a>,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    instructions_account: &AccountInfo,
+    recipient_preference_account: &AccountInfo<'a>,
+    quarantine: &AccountInfo<'a>,
+    metrics: &mut MetricsAccount,
+    verification_history: &mut VerificationHistoryAccount,
+    governor: &GovernorAccount,
+    pending_release_account: &AccountInfo<'a>,
+    warden_usage_account: &AccountInfo,
+    apa_flag_account: &AccountInfo,
+    warden_attestation_account: &AccountInfo,
+
+    mt_shard: u32,
+    _verification_account_index: u8,
+) -> ProgramResult {
+    finalize_transfer_lamports(
+        original_fee_payer,
+        recipient,
+        pool,
+        fee_collector,
+        optional_fee_collector,
+        commitment_hash_queue,
+        metadata_queue,
+        verification_account_info,
+        nullifier_duplicate_account,
+        instructions_account,
+        recipient_preference_account,
+        quarantine,
+        metrics,
+        verification_history,
+        governor,
+        Some(pending_release_account),
+        warden_usage_account,
+        Some((apa_flag_account, warden_attestation_account)),
+        mt_shard,
+    )
+}
+
+/// Finalizes the lamports-transfer of a verification (see [`finalize_verification_transfer_lamports`])
+///
+/// # Note
+///
+/// Without a `pending_release_account` timelocked sends, and without `apa_accounts` (the APA-flag and warden-attestation accounts) enabled APA flags, can not be finalized.
+#[allow(clippy::too_many_arguments)]
+fn finalize_transfer_lamports<'a>(
+    original_fee_payer: &AccountInfo<'a>,
+    recipient: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    optional_fee_collector: &AccountInfo<'a>,
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+    metadata_queue: &mut MetadataQueueAccount,
+    verification_account_info: &AccountInfo<'a>,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    instructions_account: &AccountInfo,
+    recipient_preference_account: &AccountInfo<'a>,
+    quarantine: &AccountInfo<'a>,
+    metrics: &mut MetricsAccount,
+    verification_history: &mut VerificationHistoryAccount,
+    governor: &GovernorAccount,
+    pending_release_account: Option<&AccountInfo<'a>>,
+    warden_usage_account: &AccountInfo,
+    apa_accounts: Option<(&AccountInfo, &AccountInfo)>,
+    mt_shard: u32,
+) -> ProgramResult {
+    pda_account!(
+        mut verification_account,
+        VerificationAccount,
+        verification_account_info
+    );
+    verify_transfer_lamports_finalization(
+        original_fee_payer,
+        recipient,
+        pool,
+        optional_fee_collector,
+        nullifier_duplicate_account,
+        &verification_account,
+        governor,
+        mt_shard,
+    )?;
+
+    let data = verification_account.get_other_data();
+    let request = verification_account.get_request();
+    let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
+
+    // Invalid proof
+    if let ElusivOption::Some(false) = verification_account.get_is_verified() {
+        // `rent` and `commitment_hash_fee` flow to `fee_collector`
+        close_account(fee_collector, verification_account_info)?;
+        if !data.skip_nullifier_pda {
+            close_account(fee_collector, nullifier_duplicate_account)?;
+        }
+
+        verification_account.set_state(&VerificationState::Closed);
+
+        // `pool` transfers `subvention` to `fee_collector` (lamports)
+        transfer_lamports_from_pda_checked(pool, fee_collector, data.subvention)?;
+
+        // `pool` transfers `commitment_hash_fee` to `fee_collector` (lamports)
+        transfer_lamports_from_pda_checked(pool, fee_collector, data.commitment_hash_fee.0)?;
+
+        return Ok(());
+    }
+
+    if let ProofRequest::Send(public_inputs) = &request {
+        if public_inputs.join_split.amount > 0 {
+            let is_apa_quarantined = match apa_accounts {
+                Some((apa_flag_account, warden_attestation_account)) => enforce_apa_flag(
+                    governor,
+                    recipient.key,
+                    apa_flag_account,
+                    0,
+                    original_fee_payer.key,
+                    warden_attestation_account,
+                )?,
+                None => {
+                    guard!(
+                        governor.get_apa_enforcement() == ApaEnforcement::Disabled,
+                        ElusivError::FeatureNotAvailable
+                    );
+                    false
+                }
+            };
+
+            // Subtract the optional fee from the amount
+            let amount = public_inputs
+                .join_split
+                .amount
+                .checked_sub(public_inputs.join_split.optional_fee.amount)
+                .ok_or(ElusivError::InvalidAmount)?;
+
+            // Solana-pay transfers are always sent to the recipient (unless flagged by the APA)
+            let accepts_payout = !is_apa_quarantined
+                && (public_inputs.solana_pay_transfer
+                    || recipient_accepts_payout(recipient_preference_account, recipient.key, 0)?);
+
+            if public_inputs.solana_pay_transfer && accepts_payout {
+                // `pool` transfers `amount` to `original_fee_payer` (lamports)
+                transfer_lamports_from_pda_checked(
+                    pool,
+                    original_fee_payer,
+                    public_inputs.join_split.amount,
+                )?;
+
+                // Last instruction: `original_fee_payer` transfers `amount` to `recipient`
+                let instructions_sysvar = DefaultInstructionsSysvar(instructions_account);
+                enforce_instruction(
+                    &instructions_sysvar,
+                    instructions_sysvar.find_instruction_count()? - 1,
+                    &system_instruction::transfer(original_fee_payer.key, recipient.key, amount),
+                    false,
+                )?;
+            } else {
+                transfer_send_payout(
+                    public_inputs,
+                    Token::new(0, amount),
+                    recipient,
+                    recipient.key,
+                    recipient.key,
+                    is_apa_quarantined,
+                    accepts_payout,
+                    original_fee_payer,
+                    quarantine,
+                    quarantine,
+                    recipient_preference_account,
+                    pending_release_account,
+                    nullifier_duplicate_account,
+                    None,
+                    |destination, token| {
+                        transfer_lamports_from_pda_checked(pool, destination, token.amount())
+                    },
+                )?;
+            }
+
+            // `pool` transfers the optional fee to the corresponding collector
+            if public_inputs.join_split.optional_fee.amount > 0 {
+                transfer_lamports_from_pda_checked(
+                    pool,
+                    optional_fee_collector,
+                    public_inputs.join_split.optional_fee.amount,
+                )?;
+            }
+        }
+    }
+
+    // `pool` transfers `commitment_hash_fee_token (incl. subvention) + proof_verification_fee` to `fee_payer` (lamports)
+    transfer_lamports_from_pda_checked(
+        pool,
+        original_fee_payer,
+        (Lamports(data.commitment_hash_fee_token) + Lamports(data.proof_verification_fee))?.0,
+    )?;
+
+    // `pool` transfers `network_fee` and `priority_fee` to `fee_collector` (lamports)
+    transfer_lamports_from_pda_checked(
+        pool,
+        fee_collector,
+        (Lamports(data.network_fee) + Lamports(data.priority_fee))?.0,
+    )?;
+
+    complete_transfer_finalization(
+        original_fee_payer,
+        verification_account_info,
+        nullifier_duplicate_account,
+        &mut verification_account,
+        join_split,
+        &data,
+        commitment_hash_queue,
+        metadata_queue,
+        metrics,
+        verification_history,
+        governor,
+        warden_usage_account,
+    )
+}
+
+/// Performs the checks of [`finalize_verification_transfer_lamports`] that can fail before any funds are moved
+///
+/// # Note
+///
+/// The payout checks are skipped for an invalid proof (no payout).
+fn verify_transfer_lamports_finalization(
+    original_fee_payer: &AccountInfo,
+    recipient: &AccountInfo,
+    pool: &AccountInfo,
+    optional_fee_collector: &AccountInfo,
+    nullifier_duplicate_account: &AccountInfo,
+    verification_account: &VerificationAccount,
+    governor: &GovernorAccount,
+    mt_shard: u32,
+) -> ProgramResult {
+    let data = verification_account.get_other_data();
+    let request = verification_account.get_request();
+    let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
+
+    guard!(join_split.token_id == 0, ElusivError::InvalidAccountState);
+    guard!(
+        verification_account.get_state() == VerificationState::Finalized,
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        original_fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        *nullifier_duplicate_account.key
+            == join_split.create_nullifier_duplicate_pda(nullifier_duplicate_account)?,
+        ElusivError::InvalidAccount
+    );
+
+    if let ElusivOption::Some(false) = verification_account.get_is_verified() {
+        return Ok(());
+    }
+
+    verify_output_mt_shard(governor, join_split, mt_shard)?;
+
+    // A depleted pool rejects the finalization (before any funds are moved) until it is refilled
+    verify_pool_liquidity(pool, lamports_payout(&request, &data)?)?;
+
+    if let ProofRequest::Send(public_inputs) = &request {
+        if public_inputs.join_split.amount > 0 {
+            guard!(
+                recipient.key.to_bytes() == data.recipient_wallet.option().unwrap().skip_mr(),
+                ElusivError::InvalidRecipient
+            );
+            guard!(
+                public_inputs.join_split.amount >= public_inputs.join_split.optional_fee.amount,
+                ElusivError::InvalidAmount
+            );
+            if public_inputs.join_split.optional_fee.amount > 0 {
+                guard!(
+                    *optional_fee_collector.key == public_inputs.join_split.optional_fee.collector,
+                    ElusivError::InvalidAccount
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the lamports paid out of the pool by [`finalize_verification_transfer_lamports`] for a valid proof
+pub fn lamports_payout(
+    request: &ProofRequest,
+    data: &VerificationAccountData,
+) -> Result<u64, ProgramError> {
+    let amount = match request {
+        ProofRequest::Send(public_inputs) => public_inputs.join_split.amount,
+        _ => 0,
+    };
+
+    [
+        data.commitment_hash_fee_token,
+        data.proof_verification_fee,
+        data.network_fee,
+        data.priority_fee,
+    ]
+    .iter()
+    .try_fold(amount, |amount, fee| amount.checked_add(*fee))
+    .ok_or_else(|| ElusivError::InvalidAmount.into())
+}
+
+/// Releases the lamports of a timelocked send (parked by [`finalize_verification_transfer_lamports`]) to the recipient
+///
+/// # Note
+///
+/// - Can be called by anyone once the `unlock_timestamp` has been reached.
+/// - The [`PendingReleaseAccount`] is closed and its rent flows back to the `rent_payer`.
+pub fn release_pending_transfer<'a>(
+    recipient: &AccountInfo<'a>,
+    rent_payer: &AccountInfo<'a>,
+    pending_release_account_info: &AccountInfo<'a>,
+
+    _nullifier_duplicate_account: Pubkey,
+) -> ProgramResult {
+    let amount = {
+        pda_account!(
+            pending_release_account,
+            PendingReleaseAccount,
+            pending_release_account_info
+        );
+        guard!(
+            pending_release_account.is_unlocked(current_timestamp()?),
+            ElusivError::TimelockNotReached
+        );
+        guard!(
+            *recipient.key == pending_release_account.get_recipient(),
+            ElusivError::InvalidRecipient
+        );
+        guard!(
+            *rent_payer.key == pending_release_account.get_rent_payer(),
+            ElusivError::InvalidAccount
+        );
+
+        pending_release_account.get_amount()
+    };
+
+    // `pending_release_account` transfers `amount` to `recipient` (lamports)
+    transfer_lamports_from_pda_checked(pending_release_account_info, recipient, amount)?;
+
+    // The rent flows to `rent_payer`
+    close_account(rent_payer, pending_release_account_info)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn finalize_verification_transfer_token<'a>(
+    original_fee_payer: &AccountInfo<'a>,
+    original_fee_payer_account: &AccountInfo<'a>,
+    recipient: &AccountInfo<'a>, // can be any account for merge/migrate
+    recipient_wallet: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    optional_fee_collector: &AccountInfo<'a>,
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+    metadata_queue: &mut MetadataQueueAccount,
+    verification_account_info: &AccountInfo<'a>,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    mint_account: &AccountInfo<'a>,
+    instructions_account: &AccountInfo,
+    recipient_preference_account: &AccountInfo<'a>,
+    quarantine: &AccountInfo<'a>,
+    quarantine_account: &AccountInfo<'a>,
+    metrics: &mut MetricsAccount,
+    verification_history: &mut VerificationHistoryAccount,
+    governor: &GovernorAccount,
+    warden_usage_account: &AccountInfo,
+    apa_flag_account: &AccountInfo,
+    warden_attestation_account: &AccountInfo,
+
+    mt_shard: u32,
+    _verification_account_index: u8,
+) -> ProgramResult {
+    pda_account!(
+        mut verification_account,
+        VerificationAccount,
+        verification_account_info
+    );
+    let data = verification_account.get_other_data();
+    let request = verification_account.get_request();
+    let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
+    let recipient_address = data.recipient_wallet.option().unwrap().skip_mr();
+
+    let token_id = join_split.token_id;
+    guard!(token_id > 0, ElusivError::InvalidAccountState);
+
+    guard!(
+        verification_account.get_state() == VerificationState::Finalized,
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        original_fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        original_fee_payer_account.key.to_bytes() == data.fee_payer_account.skip_mr(),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        *nullifier_duplicate_account.key
+            == join_split.create_nullifier_duplicate_pda(nullifier_duplicate_account)?,
+        ElusivError::InvalidAccount
+    );
+
+    verify_program_token_account(pool, pool_account, token_id)?;
+    verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+
+    // Invalid proof
+    if let ElusivOption::Some(false) = verification_account.get_is_verified() {
+        // rent flows to `fee_collector`
+        close_verification_pdas(
+            fee_collector,
+            verification_account_info,
+            nullifier_duplicate_account,
+            data.skip_nullifier_pda,
+            false,
+        )?;
+
+        verification_account.set_state(&VerificationState::Closed);
+
+        // `pool` transfers `subvention` to `fee_collector` (token)
+        transfer_token_from_pda::<PoolAccount>(
+            pool,
+            pool_account,
+            fee_collector_account,
+            token_program,
+            Token::new(token_id, data.subvention),
+            None,
+            None,
+        )?;
+
+        // `pool` transfers `commitment_hash_fee` and `associated_token_account_rent` to `fee_collector` (lamports)
+        transfer_lamports_from_pda_checked(
+            pool,
+            fee_collector,
+            (data.commitment_hash_fee + spl_token_account_rent()?)?.0,
+        )?;
+
+        return Ok(());
+    }
+
+    verify_output_mt_shard(governor, join_split, mt_shard)?;
+
+    let mut associated_token_account_rent_token = None;
+    if let ProofRequest::Send(public_inputs) = &request {
+        if public_inputs.join_split.amount > 0 {
+            let mut actual_recipient = recipient;
+            let is_apa_quarantined = enforce_apa_flag(
+                governor,
+                &Pubkey::new_from_array(recipient_address),
+                apa_flag_account,
+                token_id,
+                original_fee_payer.key,
+                warden_attestation_account,
+            )?;
+
+            // Solana-pay transfers are always sent to the recipient (unless flagged by the APA)
+            let preference_wallet = payout_preference_wallet(
+                recipient,
+                &Pubkey::new_from_array(recipient_address),
+                token_id,
+                public_inputs.recipient_is_associated_token_account,
+            )?;
+            let accepts_payout = !is_apa_quarantined
+                && (public_inputs.solana_pay_transfer
+                    || recipient_accepts_payout(
+                        recipient_preference_account,
+                        &preference_wallet,
+                        token_id,
+                    )?);
+
+            if !accepts_payout {
+                if !public_inputs.recipient_is_associated_token_account {
+                    guard!(
+                        recipient.key.to_bytes() == recipient_address,
+                        ElusivError::InvalidRecipient
+                    );
+                } else {
+                    guard!(
+                        recipient_wallet.key.to_bytes() == recipient_address,
+                        ElusivError::InvalidRecipient
+                    );
+
+                    // No associated token account is created (the prepaid rent is refunded to `fee_payer`)
+                    associated_token_account_rent_token = Some(0);
+                }
+            } else if !public_inputs.recipient_is_associated_token_account {
+                // Any token account
+                guard!(
+                    recipient.key.to_bytes() == recipient_address,
+                    ElusivError::InvalidRecipient
+                );
+
+                // Invalid recipient token account -> funds flow to `fee_collector` instead
+                if verify_token_account(recipient, token_id) != Ok(true) {
+                    actual_recipient = fee_collector_account;
+                }
+            } else {
+                // Associated-token-account
+                guard!(
+                    recipient_wallet.key.to_bytes() == recipient_address,
+                    ElusivError::InvalidRecipient
+                );
+                guard!(
+                    verify_associated_token_account(recipient_wallet.key, recipient.key, token_id)?,
+                    ElusivError::InvalidRecipient
+                );
+
+                if recipient.lamports() == 0 {
+                    // Check if associated token accounts exists
+                    guard!(
+                        *mint_account.key == elusiv_token(token_id)?.mint,
+                        ElusivError::InvalidAccount
+                    );
+
+                    // We use signer (since it's an available system account) to sign the creation of the associated token account (refunded at the end)
+                    create_associated_token_account(
+                        original_fee_payer,
+                        recipient_wallet,
+                        recipient,
+                        mint_account,
+                        token_id,
+                    )?;
+
+                    // `pool` transfers `associated_token_account_rent` to `fee_payer` (token)
+                    associated_token_account_rent_token = Some(data.associated_token_account_rent);
+                } else {
+                    // TODO: can frozen account still receive funds?
+                    associated_token_account_rent_token = Some(0);
+                }
+            }
+
+            let optional_fee = Token::new(token_id, public_inputs.join_split.optional_fee.amount);
+
+            // Subtract the optional fee and optional associated_token_account_rent from the amount
+            let token = Token::new(
+                token_id,
+                public_inputs
+                    .join_split
+                    .amount
+                    .checked_sub(associated_token_account_rent_token.unwrap_or(0))
+                    .ok_or(ElusivError::InvalidAmount)?
+                    .checked_sub(public_inputs.join_split.optional_fee.amount)
+                    .ok_or(ElusivError::InvalidAmount)?,
+            );
+
+            if public_inputs.solana_pay_transfer && accepts_payout {
+                // `pool` transfers `amount` to `original_fee_payer_account` (token)
+                transfer_token_from_pda::<PoolAccount>(
+                    pool,
+                    pool_account,
+                    original_fee_payer_account,
+                    token_program,
+                    token,
+                    None,
+                    None,
+                )?;
+
+                // Last instruction: `original_fee_payer_account` transfers `amount` to `recipient` (token)
+                let instructions_sysvar = DefaultInstructionsSysvar(instructions_account);
+                enforce_instruction(
+                    &instructions_sysvar,
+                    instructions_sysvar.find_instruction_count()? - 1,
+                    &spl_token::instruction::transfer(
+                        token_program.key,
+                        original_fee_payer_account.key,
+                        actual_recipient.key,
+                        original_fee_payer.key,
+                        &[original_fee_payer.key],
+                        token.amount(),
+                    )?,
+                    false,
+                )?;
+            } else {
+                // Timelocked sends are lamports transfers (so no `pending_release_account` is required)
+                transfer_send_payout(
+                    public_inputs,
+                    token,
+                    actual_recipient,
+                    &Pubkey::new_from_array(recipient_address),
+                    &preference_wallet,
+                    is_apa_quarantined,
+                    accepts_payout,
+                    original_fee_payer,
+                    quarantine,
+                    quarantine_account,
+                    recipient_preference_account,
+                    None,
+                    nullifier_duplicate_account,
+                    Some(mint_account),
+                    |destination, token| {
+                        transfer_token_from_pda_with_mint::<PoolAccount>(
+                            pool,
+                            pool_account,
+                            destination,
+                            token_program,
+                            mint_account,
+                            token,
+                            None,
+                            None,
+                        )
+                    },
+                )?;
+            }
+
+            // `pool` transfers the optional fee to the corresponding collector (token)
+            if optional_fee.amount() > 0 {
+                guard!(
+                    *optional_fee_collector.key == public_inputs.join_split.optional_fee.collector,
+                    ElusivError::InvalidAccount
+                );
+
+                transfer_token_from_pda::<PoolAccount>(
+                    pool,
+                    pool_account,
+                    optional_fee_collector,
+                    token_program,
+                    optional_fee,
+                    None,
+                    None,
+                )?;
             }
         }
+    }
+
+    // `pool` transfers `commitment_hash_fee_token (incl. subvention) + proof_verification_fee + associated_token_account_rent_token?` to `fee_payer` (token)
+    transfer_token_from_pda::<PoolAccount>(
+        pool,
+        pool_account,
+        original_fee_payer_account,
+        token_program,
+        ((Token::new(token_id, data.commitment_hash_fee_token)
+            + Token::new(token_id, data.proof_verification_fee))?
+            + Token::new(token_id, associated_token_account_rent_token.unwrap_or(0)))?,
+        None,
+        None,
+    )?;
+
+    // `pool` transfers `network_fee` and `priority_fee` to `fee_collector` (token)
+    transfer_token_from_pda::<PoolAccount>(
+        pool,
+        pool_account,
+        fee_collector_account,
+        token_program,
+        (Token::new(token_id, data.network_fee) + Token::new(token_id, data.priority_fee))?,
+        None,
+        None,
+    )?;
+
+    if associated_token_account_rent_token.is_some() {
+        transfer_lamports_from_pda_checked(pool, original_fee_payer, spl_token_account_rent()?.0)?;
+    }
+
+    complete_transfer_finalization(
+        original_fee_payer,
+        verification_account_info,
+        nullifier_duplicate_account,
+        &mut verification_account,
+        join_split,
+        &data,
+        commitment_hash_queue,
+        metadata_queue,
+        metrics,
+        verification_history,
+        governor,
+        warden_usage_account,
+    )
+}
+
+pub const MAX_FINALIZE_BATCH_SIZE: usize = 4;
+
+/// Finalizes the lamports-transfers of up to [`MAX_FINALIZE_BATCH_SIZE`] verifications of the same `original_fee_payer`
+///
+/// # Notes
+///
+/// - Entries that can not be finalized are skipped, without failing the whole instruction (all checks of an entry happen before any of its funds are moved).
+/// - Solana-pay transfers, timelocked sends (require a `pending_release_account`) and sends with enabled APA flags (require an `apa_flag_account`) are not supported in batches.
+/// - Only the first `count` entries (and `verification_account_indices`) are used, the remaining accounts are ignored.
+#[allow(clippy::too_many_arguments)]
+pub fn finalize_verification_batch<'a>(
+    original_fee_payer: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+    metadata_queue: &mut MetadataQueueAccount,
+    quarantine: &AccountInfo<'a>,
+    instructions_account: &AccountInfo,
+    metrics: &mut MetricsAccount,
+    verification_history: &mut VerificationHistoryAccount,
+    governor: &GovernorAccount,
+    warden_usage_account: &AccountInfo,
+    recipient0: &AccountInfo<'a>,
+    optional_fee_collector0: &AccountInfo<'a>,
+    verification_account0: &AccountInfo<'a>,
+    nullifier_duplicate_account0: &AccountInfo<'a>,
+    recipient_preference_account0: &AccountInfo<'a>,
+    recipient1: &AccountInfo<'a>,
+    optional_fee_collector1: &AccountInfo<'a>,
+    verification_account1: &AccountInfo<'a>,
+    nullifier_duplicate_account1: &AccountInfo<'a>,
+    recipient_preference_account1: &AccountInfo<'a>,
+    recipient2: &AccountInfo<'a>,
+    optional_fee_collector2: &AccountInfo<'a>,
+    verification_account2: &AccountInfo<'a>,
+    nullifier_duplicate_account2: &AccountInfo<'a>,
+    recipient_preference_account2: &AccountInfo<'a>,
+    recipient3: &AccountInfo<'a>,
+    optional_fee_collector3: &AccountInfo<'a>,
+    verification_account3: &AccountInfo<'a>,
+    nullifier_duplicate_account3: &AccountInfo<'a>,
+    recipient_preference_account3: &AccountInfo<'a>,
+
+    mt_shard: u32,
+    verification_account_indices: [u8; MAX_FINALIZE_BATCH_SIZE],
+    count: u8,
+) -> ProgramResult {
+    let count = count as usize;
+    guard!(
+        count > 0 && count <= MAX_FINALIZE_BATCH_SIZE,
+        ElusivError::InvalidInstructionData
+    );
+
+    // Each entry enqueues one commitment (so a full queue would fail all remaining entries)
+    guard!(
+        CommitmentQueue::new(commitment_hash_queue).empty_slots() as usize >= count,
+        ElusivError::QueueIsFull
+    );
+    guard!(
+        MetadataQueue::new(metadata_queue).empty_slots() as usize >= count,
+        ElusivError::QueueIsFull
+    );
+
+    let entries = [
+        (
+            recipient0,
+            optional_fee_collector0,
+            verification_account0,
+            nullifier_duplicate_account0,
+            recipient_preference_account0,
+        ),
+        (
+            recipient1,
+            optional_fee_collector1,
+            verification_account1,
+            nullifier_duplicate_account1,
+            recipient_preference_account1,
+        ),
+        (
+            recipient2,
+            optional_fee_collector2,
+            verification_account2,
+            nullifier_duplicate_account2,
+            recipient_preference_account2,
+        ),
+        (
+            recipient3,
+            optional_fee_collector3,
+            verification_account3,
+            nullifier_duplicate_account3,
+            recipient_preference_account3,
+        ),
+    ];
+
+    for (i, entry) in entries.iter().take(count).enumerate() {
+        let (
+            recipient,
+            optional_fee_collector,
+            verification_account_info,
+            nullifier_duplicate_account,
+            recipient_preference_account,
+        ) = *entry;
+        let verification_account_index = verification_account_indices[i];
+
+        // All checks happen before any funds are moved, so a skipped entry has no side-effects
+        if let Err(e) = verify_batch_entry(
+            original_fee_payer,
+            recipient,
+            pool,
+            optional_fee_collector,
+            verification_account_info,
+            nullifier_duplicate_account,
+            recipient_preference_account,
+            commitment_hash_queue,
+            governor,
+            mt_shard,
+            verification_account_index,
+        ) {
+            solana_program::msg!("Skipping batch entry {}: {:?}", i, e);
+            continue;
+        }
+
+        finalize_transfer_lamports(
+            original_fee_payer,
+            recipient,
+            pool,
+            fee_collector,
+            optional_fee_collector,
+            commitment_hash_queue,
+            metadata_queue,
+            verification_account_info,
+            nullifier_duplicate_account,
+            instructions_account,
+            recipient_preference_account,
+            quarantine,
+            metrics,
+            verification_history,
+            governor,
+            None,
+            warden_usage_account,
+            None,
+            mt_shard,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Performs all checks of a batch entry (see [`verify_transfer_lamports_finalization`]), so that its finalization can not fail
+///
+/// # Note
+///
+/// Entries requiring per-entry accounts that are not part of a batch (timelocked sends and enabled APA flags) are rejected.
+#[allow(clippy::too_many_arguments)]
+fn verify_batch_entry(
+    original_fee_payer: &AccountInfo,
+    recipient: &AccountInfo,
+    pool: &AccountInfo,
+    optional_fee_collector: &AccountInfo,
+    verification_account_info: &AccountInfo,
+    nullifier_duplicate_account: &AccountInfo,
+    recipient_preference_account: &AccountInfo,
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+    governor: &GovernorAccount,
+    mt_shard: u32,
+    verification_account_index: u8,
+) -> ProgramResult {
+    guard!(
+        *verification_account_info.owner == crate::id()
+            && verification_account_info.data_len() == VerificationAccount::SIZE,
+        ElusivError::InvalidAccount
+    );
+    VerificationAccount::verify_account_with_pubkey(
+        verification_account_info,
+        *original_fee_payer.key,
+        Some(verification_account_index as u32),
+    )?;
 
-        verification_account.set_instruction(&(input_commitment_index as u32 + 1));
-    } else if !nullifier_account.is_moved_nullifier_empty() {
-        // Insert moved nullifier hashes
-        nullifier_account.move_nullifier_hashes_to_next_account()?;
+    pda_account!(
+        verification_account,
+        VerificationAccount,
+        verification_account_info
+    );
+    verify_transfer_lamports_finalization(
+        original_fee_payer,
+        recipient,
+        pool,
+        optional_fee_collector,
+        nullifier_duplicate_account,
+        &verification_account,
+        governor,
+        mt_shard,
+    )?;
+
+    let data = verification_account.get_other_data();
+    guard!(
+        data.skip_nullifier_pda || *nullifier_duplicate_account.owner == crate::id(),
+        ElusivError::InvalidAccount
+    );
+
+    if let ElusivOption::Some(false) = verification_account.get_is_verified() {
+        return Ok(());
     }
 
-    if input_commitment_index >= public_inputs.join_split.input_commitments.len() - 1
-        && nullifier_account.is_moved_nullifier_empty()
+    CommitmentQueue::new(commitment_hash_queue).verify_in_flight(&in_flight_fee_payer(
+        &verification_account,
+        original_fee_payer.key,
+    ))?;
+
+    if let ProofRequest::Send(public_inputs) = &verification_account.get_request() {
+        if public_inputs.join_split.amount > 0 {
+            guard!(
+                !public_inputs.solana_pay_transfer && !public_inputs.is_timelocked(),
+                ElusivError::FeatureNotAvailable
+            );
+            guard!(
+                governor.get_apa_enforcement() == ApaEnforcement::Disabled,
+                ElusivError::FeatureNotAvailable
+            );
+
+            recipient_accepts_payout(recipient_preference_account, recipient.key, 0)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Closes a reusable [`VerificationAccount`] that is not in use anymore (rent flows back to the `fee_payer`)
+pub fn close_verification_account<'a>(
+    fee_payer: &AccountInfo<'a>,
+    verification_account_info: &AccountInfo<'a>,
+
+    _verification_account_index: u8,
+) -> ProgramResult {
+    pda_account!(
+        verification_account,
+        VerificationAccount,
+        verification_account_info
+    );
+    let data = verification_account.get_other_data();
+
+    guard!(
+        verification_account.get_state() == VerificationState::Closed,
+        ElusivError::InvalidAccountState
+    );
+    guard!(data.reusable, ElusivError::InvalidAccountState);
+    guard!(
+        fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
+        ElusivError::InvalidAccount
+    );
+
+    close_account(fee_payer, verification_account_info)
+}
+
+pub const MAX_CLOSE_BATCH_SIZE: usize = 4;
+
+/// Closes up to [`MAX_CLOSE_BATCH_SIZE`] reusable [`VerificationAccount`]s of the same `fee_payer`
+///
+/// # Note
+///
+/// Only the first `count` accounts (and `verification_account_indices`) are used, the remaining accounts are ignored.
+pub fn close_verification_accounts<'a>(
+    fee_payer: &AccountInfo<'a>,
+    verification_account0: &AccountInfo<'a>,
+    verification_account1: &AccountInfo<'a>,
+    verification_account2: &AccountInfo<'a>,
+    verification_account3: &AccountInfo<'a>,
+
+    verification_account_indices: [u8; MAX_CLOSE_BATCH_SIZE],
+    count: u8,
+) -> ProgramResult {
+    let count = count as usize;
+    guard!(
+        count > 0 && count <= MAX_CLOSE_BATCH_SIZE,
+        ElusivError::InvalidInstructionData
+    );
+
+    let verification_accounts = [
+        verification_account0,
+        verification_account1,
+        verification_account2,
+        verification_account3,
+    ];
+
+    for (verification_account_info, verification_account_index) in verification_accounts
+        .iter()
+        .zip(verification_account_indices)
+        .take(count)
     {
-        verification_account.set_state(&VerificationState::Finalized);
+        guard!(
+            *verification_account_info.owner == crate::id(),
+            ElusivError::InvalidAccount
+        );
+        VerificationAccount::verify_account_with_pubkey(
+            verification_account_info,
+            *fee_payer.key,
+            Some(verification_account_index.into()),
+        )?;
+
+        close_verification_account(
+            fee_payer,
+            verification_account_info,
+            verification_account_index,
+        )?;
     }
 
     Ok(())
 }
 
+/// Number of slots (roughly one hour) after the initialization, after which an unfinished verification can be cancelled by its fee payer
+pub const VERIFICATION_CANCELLATION_TIMEOUT: u64 = 9_000;
+
+pub fn is_cancellation_timeout_reached(init_slot: u64, slot: u64) -> bool {
+    init_slot
+        .checked_add(VERIFICATION_CANCELLATION_TIMEOUT)
+        .map_or(false, |s| s <= slot)
+}
+
+/// Cancels a verification, whose computation has been abandoned, after [`VERIFICATION_CANCELLATION_TIMEOUT`]
+///
+/// # Notes
+///
+/// - Only the original `fee_payer` can cancel, it receives the rent of the [`VerificationAccount`] and the [`NullifierDuplicateAccount`].
+/// - If the fee has already been transferred, the `commitment_hash_fee` (and the associated token account rent) flow back to the `fee_payer`
+///   and the `subvention` flows back to the `fee_collector`.
+/// - A reusable [`VerificationAccount`] is kept open (and can be closed using [`close_verification_account`]).
+/// - The [`VerifierRAMAccount`] bound to the computation is released (if no RAM account is bound, `ram_account` can be any account).
+/// - Verified proofs cannot be cancelled (see [`forfeit_verification`] and [`finalize_verification_transfer_by_user`]).
 #[allow(clippy::too_many_arguments)]
-pub fn finalize_verification_transfer_lamports<'a>(
-    original_fee_payer: &AccountInfo<'a>,
-    recipient: &AccountInfo<'a>, // can be any account for merge/migrate
+pub fn cancel_verification<'a>(
+    fee_payer: &AccountInfo<'a>,
     pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
     fee_collector: &AccountInfo<'a>,
-    optional_fee_collector: &AccountInfo<'a>,
-    commitment_hash_queue: &mut CommitmentQueueAccount,
-    metadata_queue: &mut MetadataQueueAccount,
+    fee_collector_account: &AccountInfo<'a>,
     verification_account_info: &AccountInfo<'a>,
     nullifier_duplicate_account: &AccountInfo<'a>,
-    instructions_account: &AccountInfo,
+    ram_account_info: &AccountInfo,
+    token_program: &AccountInfo<'a>,
 
     _verification_account_index: u8,
 ) -> ProgramResult {
@@ -682,136 +2302,398 @@ pub fn finalize_verification_transfer_lamports<'a>(
         verification_account_info
     );
     let data = verification_account.get_other_data();
-    let request = verification_account.get_request();
-    let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
+    let state = verification_account.get_state();
 
-    guard!(join_split.token_id == 0, ElusivError::InvalidAccountState);
+    guard!(
+        fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        matches!(
+            state,
+            VerificationState::None
+                | VerificationState::FeeTransferred
+                | VerificationState::ProofSetup
+        ),
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        verification_account.get_is_verified().option().is_none(),
+        ElusivError::ComputationIsAlreadyFinished
+    );
+    guard!(
+        is_cancellation_timeout_reached(verification_account.get_init_slot(), current_slot()?),
+        ElusivError::CancellationTimeoutNotReached
+    );
+
+    abort_verification(
+        fee_payer,
+        pool,
+        pool_account,
+        fee_collector,
+        fee_collector_account,
+        &mut verification_account,
+        verification_account_info,
+        nullifier_duplicate_account,
+        ram_account_info,
+        token_program,
+    )
+}
+
+/// Closes a verification initialized before a devnet reset (see [`crate::processor::reset_commitment_queues`])
+///
+/// # Notes
+///
+/// - `authority` needs to be the program's keypair.
+/// - The verification cannot be finalized anymore, so the fee is refunded as in [`cancel_verification`] (without a timeout).
+#[cfg(not(feature = "mainnet"))]
+#[allow(clippy::too_many_arguments)]
+pub fn reset_verification_account<'a>(
+    authority: &AccountInfo,
+    fee_payer: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    governor: &GovernorAccount,
+    verification_account_info: &AccountInfo<'a>,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    ram_account_info: &AccountInfo,
+    token_program: &AccountInfo<'a>,
 
+    _verification_account_index: u8,
+) -> ProgramResult {
     guard!(
-        verification_account.get_state() == VerificationState::Finalized,
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+
+    pda_account!(
+        mut verification_account,
+        VerificationAccount,
+        verification_account_info
+    );
+    let data = verification_account.get_other_data();
+
+    guard!(
+        fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        matches!(
+            verification_account.get_state(),
+            VerificationState::None
+                | VerificationState::FeeTransferred
+                | VerificationState::ProofSetup
+        ),
         ElusivError::InvalidAccountState
     );
     guard!(
-        original_fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
+        data.reset_generation != governor.get_reset_generation(),
+        ElusivError::InvalidAccountState
+    );
+
+    abort_verification(
+        fee_payer,
+        pool,
+        pool_account,
+        fee_collector,
+        fee_collector_account,
+        &mut verification_account,
+        verification_account_info,
+        nullifier_duplicate_account,
+        ram_account_info,
+        token_program,
+    )
+}
+
+/// Releases the RAM account, refunds the fee (if transferred) and closes the PDAs of an unfinished verification
+#[allow(clippy::too_many_arguments)]
+fn abort_verification<'a>(
+    fee_payer: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    verification_account: &mut VerificationAccount,
+    verification_account_info: &AccountInfo<'a>,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    ram_account_info: &AccountInfo,
+    token_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    let data = verification_account.get_other_data();
+    let state = verification_account.get_state();
+
+    let request = verification_account.get_request();
+    verify_nullifier_duplicate_account(&request, nullifier_duplicate_account)?;
+
+    // Release the RAM account
+    if let Some(ram_account) = verification_account.get_ram_account().option() {
+        guard!(
+            ram_account == *ram_account_info.key,
+            ElusivError::InvalidAccount
+        );
+
+        pda_account!(mut ram_account, VerifierRAMAccount, ram_account_info);
+        ram_account.set_is_in_use(&false);
+        verification_account.set_ram_account(&ElusivOption::None);
+    }
+
+    // Refund the fee transferred in `init_verification_transfer_fee`
+    if state != VerificationState::None {
+        let token_id = data.token_id;
+        verify_program_token_account(pool, pool_account, token_id)?;
+        verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+
+        let associated_token_account_rent = match &request {
+            ProofRequest::Send(public_inputs)
+                if public_inputs.recipient_is_associated_token_account =>
+            {
+                spl_token_account_rent()?
+            }
+            _ => Lamports(0),
+        };
+
+        // `pool` transfers `commitment_hash_fee` (+ `associated_token_account_rent`)? to `fee_payer` (lamports)
+        transfer_lamports_from_pda_checked(
+            pool,
+            fee_payer,
+            (data.commitment_hash_fee + associated_token_account_rent)?.0,
+        )?;
+
+        // `pool` transfers `subvention` to `fee_collector` (token)
+        transfer_token_from_pda::<PoolAccount>(
+            pool,
+            pool_account,
+            fee_collector_account,
+            token_program,
+            Token::new(token_id, data.subvention),
+            None,
+            None,
+        )?;
+    }
+
+    verification_account.set_state(&VerificationState::Closed);
+
+    close_verification_pdas(
+        fee_payer,
+        verification_account_info,
+        nullifier_duplicate_account,
+        data.skip_nullifier_pda,
+        data.reusable,
+    )
+}
+
+/// Duration (in seconds) after which a verified but not finalized proof can be taken over by any registered warden
+pub const VERIFICATION_FORFEITURE_TIMEOUT: u64 = 60 * 60;
+
+pub fn is_forfeiture_timeout_reached(verification_timestamp: u64, timestamp: u64) -> bool {
+    verification_timestamp
+        .checked_add(VERIFICATION_FORFEITURE_TIMEOUT)
+        .map_or(false, |t| t <= timestamp)
+}
+
+/// Splits the reimbursement of a forfeited verification into the shares of the original fee payer and the new warden
+///
+/// # Notes
+///
+/// The original fee payer receives the `commitment_hash_fee_token` (that it prepaid) and half of the `proof_verification_fee`.
+pub fn forfeiture_reimbursement_split(data: &VerificationAccountData) -> (u64, u64) {
+    let warden_share = data.proof_verification_fee / 2;
+    let original_fee_payer_share =
+        data.commitment_hash_fee_token + (data.proof_verification_fee - warden_share);
+
+    (original_fee_payer_share, warden_share)
+}
+
+/// Allows any registered warden to take over a verified proof, that has not been finalized by the original fee payer
+///
+/// # Notes
+///
+/// After [`VERIFICATION_FORFEITURE_TIMEOUT`] the [`VerificationAccount`] is moved to a new account (associated with `warden`).
+/// The original fee payer receives its share of the reimbursement immediately (and the rent of the old account).
+/// The [`NullifierDuplicateAccount`] stays open (it's closed to `warden` by the finalization), so `warden` refunds its rent to the original fee payer.
+/// `warden` can then use the default finalization instructions and receives the remaining reimbursement.
+#[allow(clippy::too_many_arguments)]
+pub fn forfeit_verification<'a>(
+    warden: &AccountInfo<'a>,
+    warden_account: &AccountInfo<'a>,
+    warden_map_account: &AccountInfo,
+    original_fee_payer: &AccountInfo<'a>,
+    original_fee_payer_account: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    verification_account_info: &AccountInfo<'a>,
+    new_verification_account_info: &AccountInfo<'a>,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    governor: &GovernorAccount,
+    token_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+
+    _verification_account_index: u8,
+    new_verification_account_index: u8,
+) -> ProgramResult {
+    guard!(
+        governor.is_valid_verification_account_index(new_verification_account_index),
         ElusivError::InvalidAccount
     );
     guard!(
-        *nullifier_duplicate_account.key
-            == join_split.create_nullifier_duplicate_pda(nullifier_duplicate_account)?,
+        warden.key != original_fee_payer.key,
         ElusivError::InvalidAccount
     );
+    verify_basic_warden(warden.key, warden_map_account)?;
 
-    // Invalid proof
-    if let ElusivOption::Some(false) = verification_account.get_is_verified() {
-        // `rent` and `commitment_hash_fee` flow to `fee_collector`
-        close_account(fee_collector, verification_account_info)?;
+    let data = {
+        pda_account!(
+            verification_account,
+            VerificationAccount,
+            verification_account_info
+        );
+        let data = verification_account.get_other_data();
+
+        guard!(
+            verification_account.get_state() == VerificationState::ProofSetup,
+            ElusivError::InvalidAccountState
+        );
+        guard!(
+            matches!(
+                verification_account.get_is_verified(),
+                ElusivOption::Some(true)
+            ),
+            ElusivError::InvalidAccountState
+        );
+        guard!(
+            is_forfeiture_timeout_reached(
+                verification_account.get_verification_timestamp(),
+                current_timestamp()?
+            ),
+            ElusivError::ForfeitureTimeoutNotReached
+        );
+        guard!(
+            original_fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
+            ElusivError::InvalidAccount
+        );
+        guard!(
+            original_fee_payer_account.key.to_bytes() == data.fee_payer_account.skip_mr(),
+            ElusivError::InvalidAccount
+        );
         if !data.skip_nullifier_pda {
-            close_account(fee_collector, nullifier_duplicate_account)?;
+            verify_nullifier_duplicate_account(
+                &verification_account.get_request(),
+                nullifier_duplicate_account,
+            )?;
         }
 
-        verification_account.set_state(&VerificationState::Closed);
-
-        // `pool` transfers `subvention` to `fee_collector` (lamports)
-        transfer_lamports_from_pda_checked(pool, fee_collector, data.subvention)?;
-
-        // `pool` transfers `commitment_hash_fee` to `fee_collector` (lamports)
-        transfer_lamports_from_pda_checked(pool, fee_collector, data.commitment_hash_fee.0)?;
+        data
+    };
 
-        return Ok(());
+    let token_id = data.token_id;
+    verify_program_token_account(pool, pool_account, token_id)?;
+    guard!(
+        verify_token_account(warden_account, token_id)?,
+        ElusivError::InvalidAccount
+    );
+    if token_id == 0 {
+        guard!(
+            warden_account.key == warden.key,
+            ElusivError::InvalidAccount
+        );
     }
 
-    if let ProofRequest::Send(public_inputs) = &request {
-        if public_inputs.join_split.amount > 0 {
-            guard!(
-                recipient.key.to_bytes() == data.recipient_wallet.option().unwrap().skip_mr(),
-                ElusivError::InvalidRecipient
-            );
-
-            // Subtract the optional fee from the amount
-            let amount = public_inputs
-                .join_split
-                .amount
-                .checked_sub(public_inputs.join_split.optional_fee.amount)
-                .ok_or(ElusivError::InvalidAmount)?;
-
-            if public_inputs.solana_pay_transfer {
-                // `pool` transfers `amount` to `original_fee_payer` (lamports)
-                transfer_lamports_from_pda_checked(
-                    pool,
-                    original_fee_payer,
-                    public_inputs.join_split.amount,
-                )?;
+    let rent = open_pda_account_with_associated_pubkey_and_rent_owner::<VerificationAccount>(
+        &crate::id(),
+        warden,
+        warden.key,
+        new_verification_account_info,
+        warden.key,
+        Some(new_verification_account_index as u32),
+        None,
+    )?;
 
-                // Last instruction: `original_fee_payer` transfers `amount` to `recipient`
-                let instructions_sysvar = DefaultInstructionsSysvar(instructions_account);
-                enforce_instruction(
-                    &instructions_sysvar,
-                    instructions_sysvar.find_instruction_count()? - 1,
-                    &system_instruction::transfer(original_fee_payer.key, recipient.key, amount),
-                    false,
-                )?;
-            } else {
-                // `pool` transfers `amount` to `recipient` (lamports)
-                transfer_lamports_from_pda_checked(pool, recipient, amount)?;
-            }
+    // Copy all data (except the `PDAAccountData`) into the new account
+    new_verification_account_info.data.borrow_mut()[PDAAccountData::SIZE..]
+        .copy_from_slice(&verification_account_info.data.borrow()[PDAAccountData::SIZE..]);
 
-            // `pool` transfers the optional fee to the corresponding collector
-            if public_inputs.join_split.optional_fee.amount > 0 {
-                guard!(
-                    *optional_fee_collector.key == public_inputs.join_split.optional_fee.collector,
-                    ElusivError::InvalidAccount
-                );
+    let (original_fee_payer_share, warden_share) = forfeiture_reimbursement_split(&data);
 
-                transfer_lamports_from_pda_checked(
-                    pool,
-                    optional_fee_collector,
-                    public_inputs.join_split.optional_fee.amount,
-                )?;
-            }
-        }
+    {
+        pda_account!(
+            mut new_verification_account,
+            VerificationAccount,
+            new_verification_account_info
+        );
+
+        new_verification_account.set_other_data(&VerificationAccountData {
+            fee_payer: RawU256::new(rent.owner.to_bytes()),
+            fee_payer_account: RawU256::new(warden_account.key.to_bytes()),
+            commitment_hash_fee_token: 0,
+            proof_verification_fee: warden_share,
+            reusable: false,
+            ..data
+        });
     }
 
-    // `pool` transfers `commitment_hash_fee_token (incl. subvention) + proof_verification_fee` to `fee_payer` (lamports)
-    transfer_lamports_from_pda_checked(
+    // `pool` transfers the `original_fee_payer_share` to `original_fee_payer` (token)
+    transfer_token_from_pda::<PoolAccount>(
         pool,
-        original_fee_payer,
-        (Lamports(data.commitment_hash_fee_token) + Lamports(data.proof_verification_fee))?.0,
+        pool_account,
+        original_fee_payer_account,
+        token_program,
+        Token::new(token_id, original_fee_payer_share),
+        None,
+        None,
     )?;
 
-    // `pool` transfers `network_fee` to `fee_collector` (lamports)
-    transfer_lamports_from_pda_checked(pool, fee_collector, data.network_fee)?;
+    // `warden` refunds the rent of the `nullifier_duplicate_account` to `original_fee_payer` (lamports)
+    if !data.skip_nullifier_pda {
+        transfer_with_system_program(
+            warden,
+            original_fee_payer,
+            system_program,
+            nullifier_duplicate_account.lamports(),
+        )?;
+    }
 
-    // Close `verification_account` and `nullifier_duplicate_account`
-    close_verification_pdas(
-        original_fee_payer,
-        verification_account_info,
-        nullifier_duplicate_account,
-        data.skip_nullifier_pda,
-    )?;
+    // The rent of the old account flows back to `original_fee_payer`
+    close_account(original_fee_payer, verification_account_info)?;
 
-    let mut commitment_queue = CommitmentQueue::new(commitment_hash_queue);
-    let mut metadata_queue = MetadataQueue::new(metadata_queue);
+    {
+        pda_account!(
+            mut verification_account,
+            VerificationAccount,
+            verification_account_info
+        );
+        verification_account.set_state(&VerificationState::Closed);
+    }
 
-    enqueue_commitment(
-        &mut commitment_queue,
-        &mut metadata_queue,
-        join_split.output_commitment.reduce(),
-        join_split.metadata,
-        join_split.fee_version,
-        data.min_batching_rate,
-    )?;
+    Ok(())
+}
 
-    verification_account.set_state(&VerificationState::Closed);
+/// Duration (in seconds) after which the user can finalize a verified proof that has not been finalized by any warden
+pub const VERIFICATION_SELF_FINALIZATION_TIMEOUT: u64 = 60 * 60 * 24;
 
-    Ok(())
+pub fn is_self_finalization_timeout_reached(verification_timestamp: u64, timestamp: u64) -> bool {
+    verification_timestamp
+        .checked_add(VERIFICATION_SELF_FINALIZATION_TIMEOUT)
+        .map_or(false, |t| t <= timestamp)
 }
 
+/// Escape hatch for verified proofs that are held back by the original fee payer
+///
+/// # Notes
+///
+/// After [`VERIFICATION_SELF_FINALIZATION_TIMEOUT`] the user (signing with the identifier key of the proof) can use this instruction instead of
+/// [`finalize_verification_transfer_lamports`] or [`finalize_verification_transfer_token`] (following [`finalize_verification_send`] and [`finalize_verification_insert_nullifier`]+).
+///
+/// The recipient (and the optional fee collector) are paid, but the reimbursement of the original fee payer is left unclaimed in the pool.
+/// Solana-pay transfers and associated token accounts that do not exist yet are not supported.
+/// APA flags, recipient preferences and timelocks are enforced as by the warden's transfer (region-scoped flags always apply).
+/// The `identifier_account` pays the rent of the `pending_release_account` of a timelocked send.
 #[allow(clippy::too_many_arguments)]
-pub fn finalize_verification_transfer_token<'a>(
+pub fn finalize_verification_transfer_by_user<'a>(
+    identifier_account: &AccountInfo<'a>,
     original_fee_payer: &AccountInfo<'a>,
-    original_fee_payer_account: &AccountInfo<'a>,
-    recipient: &AccountInfo<'a>, // can be any account for merge/migrate
-    recipient_wallet: &AccountInfo<'a>,
+    recipient: &AccountInfo<'a>,
     pool: &AccountInfo<'a>,
     pool_account: &AccountInfo<'a>,
     fee_collector: &AccountInfo<'a>,
@@ -822,9 +2704,17 @@ pub fn finalize_verification_transfer_token<'a>(
     verification_account_info: &AccountInfo<'a>,
     nullifier_duplicate_account: &AccountInfo<'a>,
     token_program: &AccountInfo<'a>,
-    mint_account: &AccountInfo<'a>,
-    instructions_account: &AccountInfo,
+    recipient_preference_account: &AccountInfo<'a>,
+    quarantine: &AccountInfo<'a>,
+    quarantine_account: &AccountInfo<'a>,
+    metrics: &mut MetricsAccount,
+    verification_history: &mut VerificationHistoryAccount,
+    governor: &GovernorAccount,
+    pending_release_account: &AccountInfo<'a>,
+    warden_usage_account: &AccountInfo,
+    apa_flag_account: &AccountInfo,
 
+    mt_shard: u32,
     _verification_account_index: u8,
 ) -> ProgramResult {
     pda_account!(
@@ -833,23 +2723,54 @@ pub fn finalize_verification_transfer_token<'a>(
         verification_account_info
     );
     let data = verification_account.get_other_data();
-    let request = verification_account.get_request();
-    let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
-    let recipient_address = data.recipient_wallet.option().unwrap().skip_mr();
-
+    let public_inputs = match verification_account.get_request() {
+        ProofRequest::Send(public_inputs) => public_inputs,
+        _ => return Err(ElusivError::FeatureNotAvailable.into()),
+    };
+    let join_split = &public_inputs.join_split;
     let token_id = join_split.token_id;
-    guard!(token_id > 0, ElusivError::InvalidAccountState);
 
     guard!(
         verification_account.get_state() == VerificationState::Finalized,
         ElusivError::InvalidAccountState
     );
     guard!(
-        original_fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
-        ElusivError::InvalidAccount
+        matches!(
+            verification_account.get_is_verified(),
+            ElusivOption::Some(true)
+        ),
+        ElusivError::InvalidAccountState
     );
+    verify_output_mt_shard(governor, join_split, mt_shard)?;
     guard!(
-        original_fee_payer_account.key.to_bytes() == data.fee_payer_account.skip_mr(),
+        identifier_account.is_signer
+            && verification_account.get_identifier().option() == Some(*identifier_account.key),
+        ProgramError::MissingRequiredSignature
+    );
+
+    // APA flags are enforced before the timeout (blocked payouts are rejected right away)
+    // - self-finalizations are not executed by a warden, so region-scoped flags always apply
+    let is_apa_quarantined = match data.recipient_wallet.option() {
+        Some(recipient_wallet) if join_split.amount > 0 => enforce_apa_flag(
+            governor,
+            &Pubkey::new_from_array(recipient_wallet.skip_mr()),
+            apa_flag_account,
+            token_id,
+            identifier_account.key,
+            identifier_account,
+        )?,
+        _ => false,
+    };
+
+    guard!(
+        is_self_finalization_timeout_reached(
+            verification_account.get_verification_timestamp(),
+            current_timestamp()?
+        ),
+        ElusivError::SelfFinalizationTimeoutNotReached
+    );
+    guard!(
+        original_fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
         ElusivError::InvalidAccount
     );
     guard!(
@@ -857,218 +2778,363 @@ pub fn finalize_verification_transfer_token<'a>(
             == join_split.create_nullifier_duplicate_pda(nullifier_duplicate_account)?,
         ElusivError::InvalidAccount
     );
+    guard!(
+        !public_inputs.solana_pay_transfer,
+        ElusivError::FeatureNotAvailable
+    );
 
     verify_program_token_account(pool, pool_account, token_id)?;
     verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
 
-    // Invalid proof
-    if let ElusivOption::Some(false) = verification_account.get_is_verified() {
-        // rent flows to `fee_collector`
-        close_verification_pdas(
-            fee_collector,
-            verification_account_info,
-            nullifier_duplicate_account,
-            data.skip_nullifier_pda,
-        )?;
-
-        verification_account.set_state(&VerificationState::Closed);
-
-        // `pool` transfers `subvention` to `fee_collector` (token)
-        transfer_token_from_pda::<PoolAccount>(
-            pool,
-            pool_account,
-            fee_collector_account,
-            token_program,
-            Token::new(token_id, data.subvention),
-            None,
-            None,
-        )?;
-
-        // `pool` transfers `commitment_hash_fee` and `associated_token_account_rent` to `fee_collector` (lamports)
-        transfer_lamports_from_pda_checked(
-            pool,
-            fee_collector,
-            (data.commitment_hash_fee + spl_token_account_rent()?)?.0,
-        )?;
-
-        return Ok(());
-    }
-
-    let mut associated_token_account_rent_token = None;
-    if let ProofRequest::Send(public_inputs) = &request {
-        if public_inputs.join_split.amount > 0 {
-            let mut actual_recipient = recipient;
-
-            if !public_inputs.recipient_is_associated_token_account {
-                // Any token account
-                guard!(
-                    recipient.key.to_bytes() == recipient_address,
-                    ElusivError::InvalidRecipient
-                );
-
-                // Invalid recipient token account -> funds flow to `fee_collector` instead
-                if verify_token_account(recipient, token_id) != Ok(true) {
-                    actual_recipient = fee_collector_account;
-                }
-            } else {
-                // Associated-token-account
-                guard!(
-                    recipient_wallet.key.to_bytes() == recipient_address,
-                    ElusivError::InvalidRecipient
-                );
-                guard!(
-                    verify_associated_token_account(recipient_wallet.key, recipient.key, token_id)?,
-                    ElusivError::InvalidRecipient
-                );
-
-                if recipient.lamports() == 0 {
-                    // Check if associated token accounts exists
-                    guard!(
-                        *mint_account.key == elusiv_token(token_id)?.mint,
-                        ElusivError::InvalidAccount
-                    );
-
-                    // We use signer (since it's an available system account) to sign the creation of the associated token account (refunded at the end)
-                    create_associated_token_account(
-                        original_fee_payer,
-                        recipient_wallet,
-                        recipient,
-                        mint_account,
-                        token_id,
-                    )?;
-
-                    // `pool` transfers `associated_token_account_rent` to `fee_payer` (token)
-                    associated_token_account_rent_token = Some(data.associated_token_account_rent);
-                } else {
-                    // TODO: can frozen account still receive funds?
-                    associated_token_account_rent_token = Some(0);
-                }
-            }
-
-            let optional_fee = Token::new(token_id, public_inputs.join_split.optional_fee.amount);
+    if join_split.amount > 0 {
+        let recipient_address = Pubkey::new_from_array(
+            data.recipient_wallet
+                .option()
+                .ok_or(ElusivError::InvalidRecipient)?
+                .skip_mr(),
+        );
 
-            // Subtract the optional fee and optional associated_token_account_rent from the amount
-            let token = Token::new(
-                token_id,
-                public_inputs
-                    .join_split
+        if token_id == 0 || !public_inputs.recipient_is_associated_token_account {
+            guard!(
+                *recipient.key == recipient_address,
+                ElusivError::InvalidRecipient
+            );
+        } else {
+            guard!(
+                verify_associated_token_account(&recipient_address, recipient.key, token_id)?,
+                ElusivError::InvalidRecipient
+            );
+        }
+        if token_id > 0 {
+            guard!(
+                verify_token_account(recipient, token_id)?,
+                ElusivError::InvalidRecipient
+            );
+        }
+
+        // Subtract the optional fee from the amount
+        let amount = join_split
+            .amount
+            .checked_sub(join_split.optional_fee.amount)
+            .ok_or(ElusivError::InvalidAmount)?;
+
+        if token_id == 0 {
+            // `amount` (incl. the optional fee), `network_fee` and `priority_fee` are paid out of the pool
+            verify_pool_liquidity(
+                pool,
+                join_split
                     .amount
-                    .checked_sub(associated_token_account_rent_token.unwrap_or(0))
-                    .ok_or(ElusivError::InvalidAmount)?
-                    .checked_sub(public_inputs.join_split.optional_fee.amount)
+                    .checked_add(data.network_fee)
+                    .and_then(|amount| amount.checked_add(data.priority_fee))
                     .ok_or(ElusivError::InvalidAmount)?,
-            );
+            )?;
+        }
 
-            if public_inputs.solana_pay_transfer {
-                // `pool` transfers `amount` to `original_fee_payer_account` (token)
-                transfer_token_from_pda::<PoolAccount>(
-                    pool,
-                    pool_account,
-                    original_fee_payer_account,
-                    token_program,
-                    token,
-                    None,
-                    None,
-                )?;
+        let preference_wallet = payout_preference_wallet(
+            recipient,
+            &recipient_address,
+            token_id,
+            public_inputs.recipient_is_associated_token_account,
+        )?;
+        let accepts_payout = !is_apa_quarantined
+            && recipient_accepts_payout(
+                recipient_preference_account,
+                &preference_wallet,
+                token_id,
+            )?;
 
-                // Last instruction: `original_fee_payer_account` transfers `amount` to `recipient` (token)
-                let instructions_sysvar = DefaultInstructionsSysvar(instructions_account);
-                enforce_instruction(
-                    &instructions_sysvar,
-                    instructions_sysvar.find_instruction_count()? - 1,
-                    &spl_token::instruction::transfer(
-                        token_program.key,
-                        original_fee_payer_account.key,
-                        actual_recipient.key,
-                        original_fee_payer.key,
-                        &[original_fee_payer.key],
-                        token.amount(),
-                    )?,
-                    false,
-                )?;
-            } else {
-                // `pool` transfers `amount` to `recipient` (token)
+        transfer_send_payout(
+            &public_inputs,
+            Token::new(token_id, amount),
+            recipient,
+            &recipient_address,
+            &preference_wallet,
+            is_apa_quarantined,
+            accepts_payout,
+            identifier_account,
+            quarantine,
+            quarantine_account,
+            recipient_preference_account,
+            Some(pending_release_account),
+            nullifier_duplicate_account,
+            None,
+            |destination, token| {
                 transfer_token_from_pda::<PoolAccount>(
                     pool,
                     pool_account,
-                    actual_recipient,
+                    destination,
                     token_program,
                     token,
                     None,
                     None,
-                )?;
-            }
+                )
+            },
+        )?;
 
-            // `pool` transfers the optional fee to the corresponding collector (token)
-            if optional_fee.amount() > 0 {
-                guard!(
-                    *optional_fee_collector.key == public_inputs.join_split.optional_fee.collector,
-                    ElusivError::InvalidAccount
-                );
+        // `pool` transfers the optional fee to the corresponding collector
+        if join_split.optional_fee.amount > 0 {
+            guard!(
+                *optional_fee_collector.key == join_split.optional_fee.collector,
+                ElusivError::InvalidAccount
+            );
 
-                transfer_token_from_pda::<PoolAccount>(
-                    pool,
-                    pool_account,
-                    optional_fee_collector,
-                    token_program,
-                    optional_fee,
-                    None,
-                    None,
-                )?;
-            }
+            transfer_token_from_pda::<PoolAccount>(
+                pool,
+                pool_account,
+                optional_fee_collector,
+                token_program,
+                Token::new(token_id, join_split.optional_fee.amount),
+                None,
+                None,
+            )?;
         }
     }
 
-    // `pool` transfers `commitment_hash_fee_token (incl. subvention) + proof_verification_fee + associated_token_account_rent_token?` to `fee_payer` (token)
-    transfer_token_from_pda::<PoolAccount>(
-        pool,
-        pool_account,
-        original_fee_payer_account,
-        token_program,
-        ((Token::new(token_id, data.commitment_hash_fee_token)
-            + Token::new(token_id, data.proof_verification_fee))?
-            + Token::new(token_id, associated_token_account_rent_token.unwrap_or(0)))?,
-        None,
-        None,
-    )?;
-
-    // `pool` transfers `network_fee` to `fee_collector` (token)
+    // `pool` transfers `network_fee` and `priority_fee` to `fee_collector`
     transfer_token_from_pda::<PoolAccount>(
         pool,
         pool_account,
         fee_collector_account,
         token_program,
-        Token::new(token_id, data.network_fee),
+        (Token::new(token_id, data.network_fee) + Token::new(token_id, data.priority_fee))?,
         None,
         None,
     )?;
 
-    // Close `verification_account` and `nullifier_duplicate_account`
+    // The rent of `verification_account` and `nullifier_duplicate_account` flows back to `original_fee_payer`
+    complete_transfer_finalization(
+        original_fee_payer,
+        verification_account_info,
+        nullifier_duplicate_account,
+        &mut verification_account,
+        join_split,
+        &data,
+        commitment_hash_queue,
+        metadata_queue,
+        metrics,
+        verification_history,
+        governor,
+        warden_usage_account,
+    )
+}
+
+/// Transfers the payout of the primary recipient of a send from the pool
+///
+/// # Notes
+///
+/// - Shared by all transfer finalizations, so APA flags, recipient preferences and timelocks are enforced alike.
+/// - APA-flagged and declined payouts flow to the quarantine, timelocked payouts to the `pending_release_account` (released by [`release_pending_transfer`]).
+/// - `transfer_from_pool` moves a [`Token`] from the pool to a destination, `mint_account` is required for Token-2022 transfer-fees.
+/// - Solana-pay transfers are handled by the callers.
+#[allow(clippy::too_many_arguments)]
+fn transfer_send_payout<'a, F>(
+    public_inputs: &SendPublicInputs,
+    token: Token,
+    recipient: &AccountInfo<'a>,
+    recipient_address: &Pubkey,
+    preference_wallet: &Pubkey,
+    is_apa_quarantined: bool,
+    accepts_payout: bool,
+    rent_payer: &AccountInfo<'a>,
+    quarantine: &AccountInfo<'a>,
+    quarantine_account: &AccountInfo<'a>,
+    recipient_preference_account: &AccountInfo<'a>,
+    pending_release_account: Option<&AccountInfo<'a>>,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    mint_account: Option<&AccountInfo>,
+    transfer_from_pool: F,
+) -> ProgramResult
+where
+    F: Fn(&AccountInfo<'a>, Token) -> ProgramResult,
+{
+    let token_id = token.token_id();
+
+    if is_apa_quarantined || !accepts_payout {
+        // `pool` transfers `amount` to `quarantine`
+        verify_program_token_account(quarantine, quarantine_account, token_id)?;
+        transfer_from_pool(quarantine_account, token)?;
+
+        // Token-2022 transfer-fees are withheld from the amount received by the quarantine
+        let received = match mint_account {
+            Some(mint_account) => token.net_of_transfer_fee(mint_account, current_epoch()?)?,
+            None => token,
+        };
+
+        if is_apa_quarantined {
+            ElusivEvent::ApaPayoutQuarantined {
+                recipient: *recipient_address,
+                token_id,
+                amount: received.amount(),
+            }
+            .emit();
+        } else {
+            record_quarantined_payout(
+                recipient_preference_account,
+                preference_wallet,
+                token_id,
+                received.amount(),
+            )?;
+        }
+    } else if public_inputs.is_locked(current_timestamp()?) {
+        // `pool` transfers `amount` to `pending_release_account` (lamports), released by [`release_pending_transfer`]
+        let pending_release_account =
+            pending_release_account.ok_or(ElusivError::TimelockNotReached)?;
+        open_pending_release_account(
+            rent_payer,
+            pending_release_account,
+            nullifier_duplicate_account.key,
+            recipient.key,
+            token.into_lamports()?.0,
+            public_inputs.unlock_timestamp,
+        )?;
+        transfer_from_pool(pending_release_account, token)?;
+    } else {
+        // `pool` transfers `amount` to `recipient`
+        transfer_from_pool(recipient, token)?;
+    }
+
+    Ok(())
+}
+
+/// Closes the PDAs of a finalized transfer, enqueues its output commitment and records the finalization
+#[allow(clippy::too_many_arguments)]
+fn complete_transfer_finalization<'a>(
+    original_fee_payer: &AccountInfo<'a>,
+    verification_account_info: &AccountInfo<'a>,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    verification_account: &mut VerificationAccount,
+    join_split: &JoinSplitPublicInputs,
+    data: &VerificationAccountData,
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+    metadata_queue: &mut MetadataQueueAccount,
+    metrics: &mut MetricsAccount,
+    verification_history: &mut VerificationHistoryAccount,
+    governor: &GovernorAccount,
+    warden_usage_account: &AccountInfo,
+) -> ProgramResult {
+    let in_flight_fee_payer = in_flight_fee_payer(verification_account, original_fee_payer.key);
+
+    // Close `verification_account` (if not reusable) and `nullifier_duplicate_account`
     close_verification_pdas(
         original_fee_payer,
         verification_account_info,
         nullifier_duplicate_account,
         data.skip_nullifier_pda,
+        data.reusable,
     )?;
 
-    if associated_token_account_rent_token.is_some() {
-        transfer_lamports_from_pda_checked(pool, original_fee_payer, spl_token_account_rent()?.0)?;
-    }
-
     let mut commitment_queue = CommitmentQueue::new(commitment_hash_queue);
     let mut metadata_queue = MetadataQueue::new(metadata_queue);
+    let previous_len = commitment_queue.len();
 
-    enqueue_commitment(
+    enqueue_commitment_with_priority(
         &mut commitment_queue,
         &mut metadata_queue,
         join_split.output_commitment.reduce(),
         join_split.metadata,
         join_split.fee_version,
         data.min_batching_rate,
+        &in_flight_fee_payer,
+        data.priority,
     )?;
+    signal_commitment_queue_pressure(
+        governor,
+        previous_len,
+        commitment_queue.len(),
+        commitment_queue.capacity(),
+    );
 
     verification_account.set_state(&VerificationState::Closed);
 
+    record_verification_history(
+        verification_history,
+        join_split,
+        data,
+        original_fee_payer.key,
+    )?;
+
+    record_warden_usage(original_fee_payer.key, warden_usage_account, |usage| {
+        usage.record_verification()
+    })?;
+
+    metrics.record_finalization(
+        join_split.token_id,
+        join_split.amount,
+        join_split.input_commitments.len(),
+    )
+}
+
+/// Verifies that `mt_shard` is the MT-shard the output commitment of a finalized verification is assigned to (its commitment queue receives the commitment)
+fn verify_output_mt_shard(
+    governor: &GovernorAccount,
+    join_split: &JoinSplitPublicInputs,
+    mt_shard: u32,
+) -> ProgramResult {
+    guard!(
+        governor.commitment_mt_shard(&join_split.output_commitment.reduce()) == mt_shard,
+        ElusivError::InvalidMtShard
+    );
+
+    Ok(())
+}
+
+/// Returns the fee-payer whose in-flight cap a finalized verification counts towards
+///
+/// # Note
+///
+/// Sends count towards their user's `identifier` (the warden finalizes the requests of many users), other requests towards the `original_fee_payer`.
+fn in_flight_fee_payer(
+    verification_account: &VerificationAccount,
+    original_fee_payer: &Pubkey,
+) -> U256 {
+    verification_account
+        .get_identifier()
+        .option()
+        .unwrap_or(*original_fee_payer)
+        .to_bytes()
+}
+
+/// Appends a finalized verification to the [`VerificationHistoryAccount`]
+fn record_verification_history(
+    verification_history: &mut VerificationHistoryAccount,
+    join_split: &JoinSplitPublicInputs,
+    data: &VerificationAccountData,
+    original_fee_payer: &Pubkey,
+) -> ProgramResult {
+    verification_history.record(&VerificationRecord {
+        nullifier_set_hash: join_split
+            .associated_nullifier_duplicate_pda_pubkey()
+            .to_bytes(),
+        recipient: data
+            .recipient_wallet
+            .option()
+            .map(|recipient| recipient.skip_mr())
+            .unwrap_or_default(),
+        amount_hash: VerificationRecord::amount_hash(
+            join_split.token_id,
+            join_split.amount,
+            join_split.optional_fee.amount,
+        ),
+        warden: original_fee_payer.to_bytes(),
+        slot: current_slot()?,
+    });
+
+    Ok(())
+}
+
+/// Verifies that `nullifier_duplicate_account` is the [`NullifierDuplicateAccount`] of `request`
+fn verify_nullifier_duplicate_account(
+    request: &ProofRequest,
+    nullifier_duplicate_account: &AccountInfo,
+) -> ProgramResult {
+    let nullifier_duplicate_pda =
+        proof_request!(request, public_inputs, public_inputs.join_split_inputs())
+            .create_nullifier_duplicate_pda(nullifier_duplicate_account)?;
+    guard!(
+        *nullifier_duplicate_account.key == nullifier_duplicate_pda,
+        ElusivError::InvalidAccount
+    );
+
     Ok(())
 }
 
@@ -1077,8 +3143,11 @@ fn close_verification_pdas<'a>(
     verification_account: &AccountInfo<'a>,
     nullifier_duplicate_account: &AccountInfo<'a>,
     skipped_nullifier_pda: bool,
+    keep_verification_account: bool,
 ) -> ProgramResult {
-    close_account(beneficiary, verification_account)?;
+    if !keep_verification_account {
+        close_account(beneficiary, verification_account)?;
+    }
     if !skipped_nullifier_pda {
         close_account(beneficiary, nullifier_duplicate_account)?;
     }
@@ -1086,6 +3155,37 @@ fn close_verification_pdas<'a>(
     Ok(())
 }
 
+/// Opens the [`PendingReleaseAccount`] of a timelocked send (associated with its `nullifier_duplicate_account`)
+fn open_pending_release_account<'a>(
+    payer: &AccountInfo<'a>,
+    pending_release_account_info: &AccountInfo<'a>,
+    nullifier_duplicate_account: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    unlock_timestamp: u64,
+) -> ProgramResult {
+    open_pda_account_with_associated_pubkey::<PendingReleaseAccount>(
+        &crate::id(),
+        payer,
+        pending_release_account_info,
+        nullifier_duplicate_account,
+        None,
+        None,
+    )?;
+
+    pda_account!(
+        mut pending_release_account,
+        PendingReleaseAccount,
+        pending_release_account_info
+    );
+    pending_release_account.set_recipient(recipient);
+    pending_release_account.set_rent_payer(payer.key);
+    pending_release_account.set_amount(&amount);
+    pending_release_account.set_unlock_timestamp(&unlock_timestamp);
+
+    Ok(())
+}
+
 const TIMESTAMP_BITS_PRUNING: usize = 5;
 pub fn is_timestamp_valid(asserted_time: u64, timestamp: u64) -> bool {
     (asserted_time >> TIMESTAMP_BITS_PRUNING) <= (timestamp >> TIMESTAMP_BITS_PRUNING)
@@ -1098,7 +3198,7 @@ fn is_vec_duplicate_free<T: std::cmp::Eq + std::hash::Hash + std::clone::Clone>(
 }
 
 /// Computes the minimum index of a commitment and it's corresponding MT-index
-fn minimum_commitment_mt_index(
+pub fn minimum_commitment_mt_index(
     mt_index: u32,
     commitment_count: u32,
     commitment_queue_len: u32,
@@ -1109,9 +3209,64 @@ fn minimum_commitment_mt_index(
     (index, mt_index + mt_offset)
 }
 
+/// Verifies that `slot` lies within the (user-chosen) execution window of a send
+fn verify_execution_window(public_inputs: &SendPublicInputs, slot: u64) -> ProgramResult {
+    guard!(
+        !public_inputs.is_before_execution_window(slot),
+        ElusivError::EarliestExecutionSlotNotReached
+    );
+    guard!(
+        !public_inputs.is_after_execution_window(slot),
+        ElusivError::LatestExecutionSlotPassed
+    );
+
+    Ok(())
+}
+
+/// Verifies that `recipient` and `identifier_account` are bound to the send's `hashed_inputs`
+fn verify_recipient_binding(
+    public_inputs: &SendPublicInputs,
+    recipient: &AccountInfo,
+    identifier_account: &AccountInfo,
+    recipient_binding: &RecipientBinding,
+) -> ProgramResult {
+    let hash = generate_hashed_inputs(
+        &recipient.key.to_bytes(),
+        &identifier_account.key.to_bytes(),
+        &recipient_binding.iv,
+        &recipient_binding.encrypted_owner,
+        &recipient_binding.transaction_reference,
+        public_inputs.recipient_is_associated_token_account,
+        &public_inputs.join_split.metadata,
+        &public_inputs.join_split.optional_fee,
+        public_inputs.expiry,
+        public_inputs.unlock_timestamp,
+        (
+            public_inputs.earliest_execution_slot,
+            public_inputs.latest_execution_slot,
+        ),
+        &recipient_binding.memo,
+    );
+    guard!(
+        hash == public_inputs.hashed_inputs,
+        ElusivError::InputsMismatch
+    );
+
+    verify_identifier_format(
+        &identifier_account.key.to_bytes(),
+        &recipient_binding.iv,
+        &recipient.key.to_bytes(),
+    )
+}
+
+/// Verifies the join-split public inputs against the MTs specified by `tree_indices`
+///
+/// # Note
+///
+/// A root of the active MT of an MT-shard is verified against the shard's entry in `storage_accounts`, all other roots against their [`NullifierAccount`].
 fn check_join_split_public_inputs(
     public_inputs: &JoinSplitPublicInputs,
-    storage_account: &StorageAccount,
+    storage_accounts: [&StorageAccount; MT_SHARD_COUNT as usize],
     nullifier_accounts: [&NullifierAccount; MAX_MT_COUNT],
     tree_indices: &[u32; MAX_MT_COUNT],
 ) -> ProgramResult {
@@ -1121,15 +3276,11 @@ fn check_join_split_public_inputs(
         ElusivError::InvalidPublicInputs
     );
     guard!(
-        public_inputs.input_commitments[0].root.is_some(),
-        ElusivError::InvalidPublicInputs
-    );
-    guard!(
-        public_inputs.input_commitments.len() <= JOIN_SPLIT_MAX_N_ARITY,
+        public_inputs.has_circuit_input_commitment_order(),
         ElusivError::InvalidPublicInputs
     );
     guard!(
-        verify_recent_commitment_index(public_inputs.recent_commitment_index, storage_account),
+        verify_recent_commitment_index(public_inputs.recent_commitment_index, storage_accounts[0]),
         ElusivError::InvalidRecentCommitmentIndex
     );
 
@@ -1139,8 +3290,6 @@ fn check_join_split_public_inputs(
         ElusivError::InvalidAmount
     );
 
-    let active_tree_index = storage_account.get_trees_count();
-
     let mut roots = Vec::new();
     let mut tree_index = Vec::with_capacity(public_inputs.input_commitments.len());
     let mut nullifier_hashes = Vec::new();
@@ -1158,7 +3307,10 @@ fn check_join_split_public_inputs(
 
                 // Verify that root is valid
                 // Note: roots are stored in mr-form
-                if tree_indices[index] == active_tree_index {
+                let storage_account = storage_accounts
+                    .get(mt_index_shard(tree_indices[index]) as usize)
+                    .ok_or(ElusivError::InvalidMtShard)?;
+                if tree_indices[index] == storage_account.get_trees_count() {
                     // Active tree
                     guard!(
                         storage_account.is_root_valid(&root.reduce()),
@@ -1223,6 +3375,37 @@ fn check_join_split_public_inputs(
     Ok(())
 }
 
+/// Applies the governor's [`ApaEnforcement`] to a payout of `token_id` to `recipient` (returns whether the payout is quarantined)
+///
+/// # Note
+///
+/// Region-scoped flags are matched against the attested region of the executing `warden`.
+fn enforce_apa_flag(
+    governor: &GovernorAccount,
+    recipient: &Pubkey,
+    apa_flag_account: &AccountInfo,
+    token_id: u16,
+    warden: &Pubkey,
+    warden_attestation_account: &AccountInfo,
+) -> Result<bool, ProgramError> {
+    let apa_enforcement = governor.get_apa_enforcement();
+    if apa_enforcement == ApaEnforcement::Disabled {
+        return Ok(false);
+    }
+
+    let warden_region = warden_attested_region(warden, warden_attestation_account);
+    if !is_apa_flagged(recipient, apa_flag_account, token_id, warden_region)? {
+        return Ok(false);
+    }
+
+    guard!(
+        apa_enforcement == ApaEnforcement::Quarantine,
+        ElusivError::ApaFlaggedRecipient
+    );
+
+    Ok(true)
+}
+
 fn enforce_finalize_send_instructions(
     instructions_account: &AccountInfo,
     uses_lamports: bool,
@@ -1299,12 +3482,21 @@ fn enforce_finalize_send_instructions_inner<I: InstructionsSysvar>(
         ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX
     };
 
+    // ... or the self-finalization by the user ([`ElusivInstruction::FinalizeVerificationTransferByUser`])
     verify_finalize_send_instruction(
         current_ix_index + insertion_ix_count + 1,
         instruction_sysvar,
         transfer_ix_variant_index,
         verification_account_index,
-    )?;
+    )
+    .or_else(|_| {
+        verify_finalize_send_instruction(
+            current_ix_index + insertion_ix_count + 1,
+            instruction_sysvar,
+            ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_BY_USER_INDEX,
+            verification_account_index,
+        )
+    })?;
 
     Ok(())
 }
@@ -1437,23 +3629,30 @@ mod tests {
     };
     use crate::processor::{CommitmentHashRequest, ZERO_COMMITMENT_RAW};
     use crate::proof::verifier::{
-        proof_from_str, COMBINED_MILLER_LOOP_IXS, FINAL_EXPONENTIATION_IXS,
+        proof_from_str, VerificationStep, COMBINED_MILLER_LOOP_IXS, FINAL_EXPONENTIATION_IXS,
     };
     use crate::state::commitment::COMMITMENT_BUFFER_LEN;
     use crate::state::fee::ProgramFee;
-    use crate::state::governor::PoolAccount;
+    use crate::state::governor::{PoolAccount, QuarantineAccount, PROTOCOL_VERSION};
     use crate::state::metadata::CommitmentMetadata;
     use crate::state::nullifier::NullifierChildAccount;
     use crate::state::program_account::{PDAAccount, SizedAccount};
-    use crate::state::storage::empty_root_raw;
-    use crate::token::{spl_token_account_data, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID, USDT_TOKEN_ID};
+    use crate::state::recipient::RecipientPreferenceAccount;
+    use crate::state::storage::{empty_root_raw, sharded_mt_index};
+    use crate::token::{
+        spl_token_account_data, TokenPrice, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID, USDT_TOKEN_ID,
+    };
     use crate::types::{
         compute_fee_rec, compute_fee_rec_lamports, OptionalFee, Proof, RawU256,
         JOIN_SPLIT_MAX_N_ARITY,
     };
+    use ark_bn254::{Fq, Fq12};
+    use ark_ff::One;
+    use elusiv_computation::RAM;
     use elusiv_types::tokens::Price;
     use elusiv_types::{ProgramAccount, TokenError};
     use solana_program::native_token::LAMPORTS_PER_SOL;
+    use solana_program::program_pack::Pack;
     use solana_program::pubkey::Pubkey;
     use solana_program::system_program;
 
@@ -1462,13 +3661,17 @@ mod tests {
     }
 
     #[test]
-    fn test_init_verification() {
+    fn test_init_verification() -> ProgramResult {
         use ProofRequest::*;
 
         parent_account!(storage, StorageAccount);
+        parent_account!(storage1, StorageAccount);
         parent_account!(mut nullifier, NullifierAccount);
         zero_program_account!(mut buffer, CommitmentBufferAccount);
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
         test_account_info!(fee_payer, 0);
+        test_account_info!(recipient, 0);
         test_account_info!(identifier, 0);
         account_info!(
             v_acc,
@@ -1476,6 +3679,11 @@ mod tests {
             vec![0; VerificationAccount::SIZE]
         );
 
+        let binding = RecipientBinding {
+            iv: [1; 32],
+            ..Default::default()
+        };
+
         let mut inputs = SendPublicInputs {
             join_split: JoinSplitPublicInputs {
                 input_commitments: vec![InputCommitment {
@@ -1492,8 +3700,25 @@ mod tests {
                 metadata: CommitmentMetadata::default(),
             },
             recipient_is_associated_token_account: true,
-            hashed_inputs: u256_from_str_skip_mr("1"),
+            hashed_inputs: generate_hashed_inputs(
+                &recipient.key.to_bytes(),
+                &identifier.key.to_bytes(),
+                &binding.iv,
+                &[0; 32],
+                &[0; 32],
+                true,
+                &CommitmentMetadata::default(),
+                &OptionalFee::default(),
+                0,
+                0,
+                (0, 0),
+                &None,
+            ),
             solana_pay_transfer: false,
+            expiry: 0,
+            unlock_timestamp: 0,
+            earliest_execution_slot: 0,
+            latest_execution_slot: 0,
         };
         compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
 
@@ -1513,23 +3738,64 @@ mod tests {
         // TODO: wrong vkey-id
         // TODO: vkey not checked
 
-        // vacc-id exceeds `RESERVED_VERIFICATION_ACCOUNT_IDS`
+        // Unsupported protocol version
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &recipient,
+                &identifier,
+                &storage,
+                &storage1,
+                &mut buffer,
+                &nullifier,
+                &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION + 1,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
+            ),
+            Err(ElusivError::UnsupportedProtocolVersion.into())
+        );
+
+        // vacc-id exceeds `MAX_VERIFICATION_ACCOUNT_INSTANCES`
         assert_eq!(
             init_verification(
                 &fee_payer,
                 &v_acc,
                 &vkey,
                 &n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
-                RESERVED_VERIFICATION_ACCOUNT_IDS + 1,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
+                MAX_VERIFICATION_ACCOUNT_INSTANCES,
                 vkey_id,
                 [0, 1],
                 Send(inputs.clone()),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -1541,11 +3807,18 @@ mod tests {
                 &v_acc,
                 &vkey,
                 &n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 vkey_id,
                 [0, 1],
@@ -1553,6 +3826,9 @@ mod tests {
                     v.join_split.input_commitments.clear();
                 })),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidPublicInputs.into())
         );
@@ -1564,11 +3840,18 @@ mod tests {
                 &v_acc,
                 &vkey,
                 &n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 vkey_id,
                 [0, 1],
@@ -1577,6 +3860,9 @@ mod tests {
                         Some(RawU256::new(u256_from_str_skip_mr("1")));
                 })),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidMerkleRoot.into())
         );
@@ -1588,11 +3874,18 @@ mod tests {
                 &v_acc,
                 &vkey,
                 &n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 vkey_id,
                 [0, 1],
@@ -1600,6 +3893,9 @@ mod tests {
                     v.join_split.input_commitments[0].root = None;
                 })),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidPublicInputs.into())
         );
@@ -1611,11 +3907,18 @@ mod tests {
                 &v_acc,
                 &vkey,
                 &n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 vkey_id,
                 [0, 1],
@@ -1624,6 +3927,9 @@ mod tests {
                     compute_fee_rec_lamports::<SendQuadraVKey, _>(inputs, &fee());
                 })),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidRecentCommitmentIndex.into())
         );
@@ -1635,16 +3941,26 @@ mod tests {
                 &v_acc,
                 &vkey,
                 &n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 vkey_id,
                 [1, 0],
                 Send(inputs.clone()),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidMerkleRoot.into())
         );
@@ -1656,11 +3972,18 @@ mod tests {
                 &v_acc,
                 &vkey,
                 &n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 vkey_id,
                 [0, 1],
@@ -1668,6 +3991,9 @@ mod tests {
                     v.join_split.output_commitment = RawU256::new(ZERO_COMMITMENT_RAW);
                 })),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidPublicInputs.into())
         );
@@ -1686,16 +4012,26 @@ mod tests {
                 &v_acc,
                 &vkey,
                 &n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 vkey_id,
                 [0, 1],
                 Send(inputs.clone()),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Err(ElusivError::CouldNotInsertNullifier.into())
         );
@@ -1713,16 +4049,26 @@ mod tests {
                 &v_acc,
                 &vkey,
                 &invalid_n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 vkey_id,
                 [0, 1],
                 Send(inputs.clone()),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Err(ProgramError::InvalidSeeds)
         );
@@ -1734,16 +4080,26 @@ mod tests {
                 &v_acc,
                 &vkey,
                 &invalid_n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 vkey_id,
                 [0, 1],
                 Send(inputs.clone()),
                 true,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -1760,11 +4116,18 @@ mod tests {
                 &v_acc,
                 &migrate_vkey,
                 &n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 MigrateUnaryVKey::VKEY_ID,
                 [0, 1],
@@ -1774,64 +4137,353 @@ mod tests {
                     next_nsmt_root: RawU256::new([0; 32]),
                 }),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Err(ElusivError::FeatureNotAvailable.into())
         );
 
+        // Recipient does not match `hashed_inputs`
+        {
+            test_account_info!(recipient, 0);
+            assert_eq!(
+                init_verification(
+                    &fee_payer,
+                    &v_acc,
+                    &vkey,
+                    &n_duplicate_acc,
+                    &recipient,
+                    &identifier,
+                    &storage,
+                    &storage1,
+                    &mut buffer,
+                    &nullifier,
+                    &nullifier,
+                    &governor,
+                    &commitment_queue,
+                    &fee_payer,
+                    &fee_payer,
+                    PROTOCOL_VERSION,
+                    0,
+                    vkey_id,
+                    [0, 1],
+                    Send(inputs.clone()),
+                    false,
+                    false,
+                    binding.clone(),
+                    ElusivOption::None,
+                ),
+                Err(ElusivError::InputsMismatch.into())
+            );
+        }
+
+        // Binding does not match `hashed_inputs`
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &recipient,
+                &identifier,
+                &storage,
+                &storage1,
+                &mut buffer,
+                &nullifier,
+                &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                false,
+                RecipientBinding {
+                    memo: Some(vec![1]),
+                    ..Default::default()
+                },
+                ElusivOption::None,
+            ),
+            Err(ElusivError::InputsMismatch.into())
+        );
+
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &recipient,
+                &identifier,
+                &storage,
+                &storage1,
+                &mut buffer,
+                &nullifier,
+                &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
+            ),
+            Ok(())
+        );
+
+        // Recipient is persisted
+        {
+            pda_account!(v_account, VerificationAccount, v_acc);
+            assert_eq!(
+                v_account.get_other_data().recipient_wallet,
+                ElusivOption::Some(RawU256::new(recipient.key.to_bytes()))
+            );
+        }
+
+        let mut inputs = inputs.clone();
+        inputs.join_split.input_commitments[0].nullifier_hash =
+            RawU256::new(u256_from_str_skip_mr("2"));
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+
+        account_info!(
+            n_duplicate_acc,
+            inputs.join_split.nullifier_duplicate_pda().0,
+            vec![1]
+        );
+
+        // Duplicate commitment insertion will fail and only succeed after COMMITMENT_BUFFER_LEN insertions
+        for i in 0..COMMITMENT_BUFFER_LEN {
+            assert_eq!(
+                init_verification(
+                    &fee_payer,
+                    &v_acc,
+                    &vkey,
+                    &n_duplicate_acc,
+                    &recipient,
+                    &identifier,
+                    &storage,
+                    &storage1,
+                    &mut buffer,
+                    &nullifier,
+                    &nullifier,
+                    &governor,
+                    &commitment_queue,
+                    &fee_payer,
+                    &fee_payer,
+                    PROTOCOL_VERSION,
+                    0,
+                    vkey_id,
+                    [0, 1],
+                    Send(inputs.clone()),
+                    false,
+                    false,
+                    binding.clone(),
+                    ElusivOption::None,
+                ),
+                Err(ElusivError::DuplicateValue.into())
+            );
+
+            buffer.try_insert(&[i as u8; 32]).unwrap();
+        }
+
+        // Commitment queue above the watermark
+        governor.set_commitment_queue_watermark(&1);
+        CommitmentQueue::new(&mut commitment_queue)
+            .enqueue(CommitmentHashRequest {
+                commitment: [0; 32],
+                fee_version: 0,
+                min_batching_rate: 0,
+            })
+            .unwrap();
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &recipient,
+                &identifier,
+                &storage,
+                &storage1,
+                &mut buffer,
+                &nullifier,
+                &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
+            ),
+            Err(ElusivError::CommitmentQueuePressure.into())
+        );
+        governor.set_commitment_queue_watermark(&2);
+
+        // Protocol paused
+        governor.set_is_protocol_paused(&true);
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &recipient,
+                &identifier,
+                &storage,
+                &storage1,
+                &mut buffer,
+                &nullifier,
+                &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
+            ),
+            Err(ElusivError::ProtocolPaused.into())
+        );
+        governor.set_is_protocol_paused(&false);
+
+        // Amount above the tree age limit (the active MT is still empty)
+        governor.set_tree_age_amount_limits(0, &1);
         assert_eq!(
             init_verification(
                 &fee_payer,
                 &v_acc,
                 &vkey,
                 &n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 vkey_id,
                 [0, 1],
                 Send(inputs.clone()),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
+            ),
+            Err(ElusivError::AmountExceedsTreeAgeLimit.into())
+        );
+        governor.set_tree_age_amount_limits(0, &0);
+
+        // Sends can not use the instances reserved for merges
+        governor.set_verification_account_instances(&2);
+        governor.set_merge_verification_account_instances(&1);
+        assert_eq!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &recipient,
+                &identifier,
+                &storage,
+                &storage1,
+                &mut buffer,
+                &nullifier,
+                &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
+                1,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
-            Ok(())
-        );
-
-        let mut inputs = inputs.clone();
-        inputs.join_split.input_commitments[0].nullifier_hash =
-            RawU256::new(u256_from_str_skip_mr("2"));
-        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
-
-        account_info!(
-            n_duplicate_acc,
-            inputs.join_split.nullifier_duplicate_pda().0,
-            vec![1]
+            Err(ElusivError::InvalidAccount.into())
         );
+        governor.set_merge_verification_account_instances(&0);
+        governor.set_verification_account_instances(&0);
 
-        // Duplicate commitment insertion will fail and only succeed after COMMITMENT_BUFFER_LEN insertions
-        for i in 0..COMMITMENT_BUFFER_LEN {
+        // Zero iv
+        {
+            let mut inputs = inputs.clone();
+            inputs.hashed_inputs = generate_hashed_inputs(
+                &recipient.key.to_bytes(),
+                &identifier.key.to_bytes(),
+                &[0; 32],
+                &[0; 32],
+                &[0; 32],
+                true,
+                &CommitmentMetadata::default(),
+                &OptionalFee::default(),
+                0,
+                0,
+                (0, 0),
+                &None,
+            );
             assert_eq!(
                 init_verification(
                     &fee_payer,
                     &v_acc,
                     &vkey,
                     &n_duplicate_acc,
+                    &recipient,
                     &identifier,
                     &storage,
+                    &storage1,
                     &mut buffer,
                     &nullifier,
                     &nullifier,
+                    &governor,
+                    &commitment_queue,
+                    &fee_payer,
+                    &fee_payer,
+                    PROTOCOL_VERSION,
                     0,
                     vkey_id,
                     [0, 1],
-                    Send(inputs.clone()),
+                    Send(inputs),
+                    false,
                     false,
+                    RecipientBinding::default(),
+                    ElusivOption::None,
                 ),
-                Err(ElusivError::DuplicateValue.into())
+                Err(ElusivError::InvalidIdentifier.into())
             );
-
-            buffer.try_insert(&[i as u8; 32]).unwrap();
         }
 
         assert_eq!(
@@ -1840,28 +4492,44 @@ mod tests {
                 &v_acc,
                 &vkey,
                 &n_duplicate_acc,
+                &recipient,
                 &identifier,
                 &storage,
+                &storage1,
                 &mut buffer,
                 &nullifier,
                 &nullifier,
+                &governor,
+                &commitment_queue,
+                &fee_payer,
+                &fee_payer,
+                PROTOCOL_VERSION,
                 0,
                 vkey_id,
                 [0, 1],
                 Send(inputs.clone()),
                 false,
+                false,
+                binding.clone(),
+                ElusivOption::None,
             ),
             Ok(())
         );
+
+        Ok(())
     }
 
     #[test]
     #[should_panic]
     fn test_init_verification_commitment_count_too_high() {
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(commitment_queue, CommitmentQueueAccount);
         parent_account!(storage, StorageAccount);
+        parent_account!(storage1, StorageAccount);
         parent_account!(nullifier, NullifierAccount);
         zero_program_account!(mut buffer, CommitmentBufferAccount);
         test_account_info!(fee_payer, 0);
+        test_account_info!(recipient, 0);
         test_account_info!(identifier, 0);
         account_info!(
             v_acc,
@@ -1885,8 +4553,25 @@ mod tests {
                 metadata: CommitmentMetadata::default(),
             },
             recipient_is_associated_token_account: true,
-            hashed_inputs: u256_from_str_skip_mr("1"),
+            hashed_inputs: generate_hashed_inputs(
+                &recipient.key.to_bytes(),
+                &identifier.key.to_bytes(),
+                &[0; 32],
+                &[0; 32],
+                &[0; 32],
+                true,
+                &CommitmentMetadata::default(),
+                &OptionalFee::default(),
+                0,
+                0,
+                (0, 0),
+                &None,
+            ),
             solana_pay_transfer: false,
+            expiry: 0,
+            unlock_timestamp: 0,
+            earliest_execution_slot: 0,
+            latest_execution_slot: 0,
         };
         compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
 
@@ -1913,16 +4598,26 @@ mod tests {
             &v_acc,
             &vkey,
             &n_duplicate_acc,
+            &recipient,
             &identifier,
             &storage,
+            &storage1,
             &mut buffer,
             &nullifier,
             &nullifier,
+            &governor,
+            &commitment_queue,
+            &fee_payer,
+            &fee_payer,
+            PROTOCOL_VERSION,
             0,
             0,
             [0, 1],
             ProofRequest::Send(inputs),
             false,
+            false,
+            RecipientBinding::default(),
+            ElusivOption::None,
         );
     }
 
@@ -1936,6 +4631,10 @@ mod tests {
         account_info!(spl, spl_token::id());
         zero_program_account!(mut governor, GovernorAccount);
         governor.set_program_fee(&fee());
+        zero_program_account!(mut token_registry, TokenRegistryAccount);
+        token_registry
+            .pin_token_config(LAMPORTS_TOKEN_ID, 0, false, false)
+            .unwrap();
 
         let mut inputs = SendPublicInputs {
             join_split: JoinSplitPublicInputs {
@@ -1955,6 +4654,10 @@ mod tests {
             recipient_is_associated_token_account: false,
             hashed_inputs: u256_from_str_skip_mr("1"),
             solana_pay_transfer: false,
+            expiry: 0,
+            unlock_timestamp: 0,
+            earliest_execution_slot: 0,
+            latest_execution_slot: 0,
         };
         compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
         let instructions = prepare_public_inputs_instructions(
@@ -1985,6 +4688,7 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &sys,
                 &sys,
@@ -2006,6 +4710,7 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &sys,
                 &sys,
@@ -2028,6 +4733,7 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &sys,
                 &sys,
@@ -2035,9 +4741,31 @@ mod tests {
             ),
             Err(ElusivError::InvalidFeeVersion.into())
         );
+        governor.set_fee_version(&0);
+
+        // Unverified token
+        zero_program_account!(unverified_token_registry, TokenRegistryAccount);
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &unverified_token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+            ),
+            Err(ElusivError::TokenNotVerified.into())
+        );
 
         // Invalid fee (fee too low, since too high is allowed)
-        governor.set_fee_version(&0);
         inputs.join_split.fee -= 1;
         verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
         assert_eq!(
@@ -2051,6 +4779,7 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &sys,
                 &sys,
@@ -2062,7 +4791,7 @@ mod tests {
         // Invalid system_program
         inputs.join_split.fee = 0;
         compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
-        verification_acc.set_request(&ProofRequest::Send(inputs));
+        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
         assert_eq!(
             init_verification_transfer_fee(
                 &fee_payer,
@@ -2074,6 +4803,7 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &sys,
                 &spl,
@@ -2094,6 +4824,7 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &sys,
                 &sys,
@@ -2114,6 +4845,7 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &sys,
                 &sys,
@@ -2133,6 +4865,7 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &sys,
                 &sys,
@@ -2145,6 +4878,33 @@ mod tests {
             verification_acc.get_state(),
             VerificationState::FeeTransferred
         );
+        assert_eq!(verification_acc.get_other_data().priority_fee, 0);
+
+        // The fee exceeding the required fees is used as priority-bid
+        inputs.join_split.fee += 1_000;
+        verification_acc.set_request(&ProofRequest::Send(inputs));
+        verification_acc.set_state(&VerificationState::None);
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &sys,
+                &sys,
+                0,
+            ),
+            Ok(())
+        );
+        assert_eq!(verification_acc.get_other_data().priority_fee, 1_000);
+        assert_eq!(verification_acc.get_other_data().priority, 1_000);
     }
 
     #[test]
@@ -2154,6 +4914,15 @@ mod tests {
         account_info!(spl, spl_token::id());
         zero_program_account!(mut governor, GovernorAccount);
         governor.set_program_fee(&fee());
+        zero_program_account!(mut token_registry, TokenRegistryAccount);
+        token_registry
+            .pin_token_config(
+                USDC_TOKEN_ID,
+                elusiv_token(USDC_TOKEN_ID).unwrap().decimals,
+                true,
+                true,
+            )
+            .unwrap();
 
         account_info!(
             token_acc,
@@ -2207,8 +4976,12 @@ mod tests {
             recipient_is_associated_token_account: false,
             hashed_inputs: u256_from_str_skip_mr("1"),
             solana_pay_transfer: false,
+            expiry: 0,
+            unlock_timestamp: 0,
+            earliest_execution_slot: 0,
+            latest_execution_slot: 0,
         };
-        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price);
+        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price, 0);
         let instructions = prepare_public_inputs_instructions(
             &inputs.public_signals_skip_mr(),
             SendQuadraVKey::public_inputs_count(),
@@ -2236,6 +5009,7 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &spl,
                 &sys,
@@ -2245,7 +5019,7 @@ mod tests {
         );
 
         inputs.join_split.fee = 0;
-        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price);
+        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price, 0);
         verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
 
         // Invalid system_program
@@ -2260,6 +5034,7 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &spl,
                 &spl,
@@ -2280,6 +5055,7 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &sys,
                 &sys,
@@ -2300,6 +5076,7 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &spl,
                 &sys,
@@ -2320,6 +5097,7 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &spl,
                 &sys,
@@ -2340,6 +5118,7 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &spl,
                 &sys,
@@ -2360,6 +5139,7 @@ mod tests {
                 &usdc,
                 &usdc,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &spl,
                 &sys,
@@ -2380,6 +5160,7 @@ mod tests {
                 &sol,
                 &sol,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &spl,
                 &sys,
@@ -2388,6 +5169,32 @@ mod tests {
             Err(TokenError::InvalidPriceAccount.into())
         );
 
+        // Fee without the token fee spread
+        governor.set_token_fee_spread_bps(&500);
+        assert_eq!(
+            init_verification_transfer_fee(
+                &fee_payer,
+                &token_acc,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &sol,
+                &usdc,
+                &governor,
+                &token_registry,
+                &mut verification_acc,
+                &spl,
+                &sys,
+                0
+            ),
+            Err(ElusivError::InvalidFee.into())
+        );
+
+        inputs.join_split.fee = 0;
+        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price, 500);
+        verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
+
         assert_eq!(
             init_verification_transfer_fee(
                 &fee_payer,
@@ -2399,6 +5206,7 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &token_registry,
                 &mut verification_acc,
                 &spl,
                 &sys,
@@ -2411,6 +5219,14 @@ mod tests {
             verification_acc.get_state(),
             VerificationState::FeeTransferred
         );
+
+        // The spread is collected with the network fee
+        let data = verification_acc.get_other_data();
+        let spread = data.commitment_hash_fee_token + data.proof_verification_fee;
+        assert_eq!(
+            data.network_fee,
+            fee().proof_network_fee.calc(inputs.join_split.amount) + spread * 500 / 10_000
+        );
     }
 
     #[test]
@@ -2470,11 +5286,37 @@ mod tests {
     }
 
     #[test]
-    fn test_compute_verification() {
+    fn test_in_flight_fee_payer() {
+        zero_program_account!(mut verification_account, VerificationAccount);
+        let original_fee_payer = Pubkey::new_unique();
+        let identifier = Pubkey::new_unique();
+
+        // Requests without an identifier count towards the original fee-payer
+        assert_eq!(
+            in_flight_fee_payer(&verification_account, &original_fee_payer),
+            original_fee_payer.to_bytes()
+        );
+
+        // Sends count towards the identifier of the user
+        verification_account.set_identifier(&ElusivOption::Some(identifier));
+        assert_eq!(
+            in_flight_fee_payer(&verification_account, &original_fee_payer),
+            identifier.to_bytes()
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "alt-bn128"))]
+    fn test_compute_verification() -> ProgramResult {
         zero_program_account!(mut verification_account, VerificationAccount);
         vkey_account!(vkey, SendQuadraVKey);
         vkey.set_version(&1);
         test_account_info!(any, 0);
+        account_info!(
+            ram,
+            VerifierRAMAccount::find(Some(0)).0,
+            vec![0; VerifierRAMAccount::SIZE]
+        );
 
         // Setup
         let public_inputs = test_public_inputs();
@@ -2493,75 +5335,365 @@ mod tests {
         // Computation is already finished (is_verified is Some)
         verification_account.set_is_verified(&ElusivOption::Some(true));
         assert_eq!(
-            compute_verification(
+            compute_verification(
+                &mut verification_account,
+                &ram,
+                &vkey,
+                &any,
+                0,
+                SendQuadraVKey::VKEY_ID,
+                0
+            ),
+            Err(ElusivError::ComputationIsAlreadyFinished.into())
+        );
+        verification_account.set_is_verified(&ElusivOption::None);
+
+        // Success for public input preparation
+        for _ in 0..instructions.len() {
+            assert_eq!(
+                compute_verification(
+                    &mut verification_account,
+                    &ram,
+                    &vkey,
+                    &any,
+                    0,
+                    SendQuadraVKey::VKEY_ID,
+                    0
+                ),
+                Ok(())
+            );
+        }
+
+        // RAM is bound to the verification
+        assert_eq!(
+            verification_account.get_ram_account().option(),
+            Some(*ram.key)
+        );
+        {
+            pda_account!(ram_account, VerifierRAMAccount, ram);
+            assert!(ram_account.get_is_in_use());
+        }
+
+        // Failure for miller loop (proof not setup)
+        assert_eq!(
+            compute_verification(
+                &mut verification_account,
+                &ram,
+                &vkey,
+                &any,
+                0,
+                SendQuadraVKey::VKEY_ID,
+                0
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        let proof = test_proof();
+        verification_account.a.set(proof.a);
+        verification_account.b.set(proof.b);
+        verification_account.c.set(proof.c);
+        verification_account.set_state(&VerificationState::ProofSetup);
+
+        // Success
+        for _ in 0..COMBINED_MILLER_LOOP_IXS + FINAL_EXPONENTIATION_IXS {
+            assert_eq!(
+                compute_verification(
+                    &mut verification_account,
+                    &ram,
+                    &vkey,
+                    &any,
+                    0,
+                    SendQuadraVKey::VKEY_ID,
+                    0
+                ),
+                Ok(())
+            );
+        }
+
+        // Computation is finished
+        assert_eq!(
+            compute_verification(
+                &mut verification_account,
+                &ram,
+                &vkey,
+                &any,
+                0,
+                SendQuadraVKey::VKEY_ID,
+                0
+            ),
+            Err(ElusivError::ComputationIsAlreadyFinished.into())
+        );
+        assert_eq!(verification_account.get_is_verified().option(), Some(false));
+
+        // The RAM is released after the computation
+        pda_account!(ram_account, VerifierRAMAccount, ram);
+        assert!(!ram_account.get_is_in_use());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compute_verification_ram_account_lock() -> ProgramResult {
+        zero_program_account!(mut verification_account, VerificationAccount);
+        vkey_account!(vkey, SendQuadraVKey);
+        vkey.set_version(&1);
+        test_account_info!(any, 0);
+        account_info!(
+            ram,
+            VerifierRAMAccount::find(Some(0)).0,
+            vec![0; VerifierRAMAccount::SIZE]
+        );
+        account_info!(
+            other_ram,
+            VerifierRAMAccount::find(Some(1)).0,
+            vec![0; VerifierRAMAccount::SIZE]
+        );
+
+        // RAM is used by another verification
+        {
+            pda_account!(mut ram_account, VerifierRAMAccount, ram);
+            ram_account.set_is_in_use(&true);
+        }
+        assert_eq!(
+            compute_verification(
+                &mut verification_account,
+                &ram,
+                &vkey,
+                &any,
+                0,
+                SendQuadraVKey::VKEY_ID,
+                0
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // Bound RAM has to be used
+        verification_account.set_ram_account(&ElusivOption::Some(*other_ram.key));
+        assert_eq!(
+            compute_verification(
+                &mut verification_account,
+                &ram,
+                &vkey,
+                &any,
+                0,
+                SendQuadraVKey::VKEY_ID,
+                0
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verification_checkpoint() -> ProgramResult {
+        zero_program_account!(mut verification_account, VerificationAccount);
+        test_account_info!(warden, 0);
+        account_info!(
+            ram,
+            VerifierRAMAccount::find(Some(0)).0,
+            vec![0; VerifierRAMAccount::SIZE]
+        );
+        account_info!(
+            new_ram,
+            VerifierRAMAccount::find(Some(1)).0,
+            vec![0; VerifierRAMAccount::SIZE]
+        );
+
+        // Computation is not yet started
+        assert_eq!(
+            export_verification_checkpoint(&warden, &mut verification_account, &ram, 0, 0),
+            Err(ElusivError::ComputationIsNotYetStarted.into())
+        );
+
+        verification_account.set_ram_account(&ElusivOption::Some(*ram.key));
+        verification_account.set_step(&VerificationStep::CombinedMillerLoop);
+        verification_account.set_round(&7);
+        {
+            pda_account!(mut ram_account, VerifierRAMAccount, ram);
+            ram_account.set_is_in_use(&true);
+            ram_account.ram_fq.write(Fq::from(123u64), 2);
+            ram_account.ram_fq12.write(Fq12::one(), 6);
+            ram_account.serialize_rams().unwrap();
+        }
+
+        // No checkpoint exported
+        assert_eq!(
+            import_verification_checkpoint(
                 &mut verification_account,
-                &vkey,
-                &any,
+                &ram,
+                &new_ram,
                 0,
-                SendQuadraVKey::VKEY_ID
+                0,
+                1,
+                [0; 32]
             ),
-            Err(ElusivError::ComputationIsAlreadyFinished.into())
+            Err(ElusivError::InvalidAccountState.into())
         );
-        verification_account.set_is_verified(&ElusivOption::None);
 
-        // Success for public input preparation
-        for _ in 0..instructions.len() {
+        // Invalid RAM
+        assert_eq!(
+            export_verification_checkpoint(&warden, &mut verification_account, &new_ram, 0, 1),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        export_verification_checkpoint(&warden, &mut verification_account, &ram, 0, 0).unwrap();
+        let checkpoint = verification_account.get_checkpoint().option().unwrap();
+        assert_eq!(checkpoint.exporter, *warden.key);
+        {
+            pda_account!(ram_account, VerifierRAMAccount, ram);
             assert_eq!(
-                compute_verification(
-                    &mut verification_account,
-                    &vkey,
-                    &any,
-                    0,
-                    SendQuadraVKey::VKEY_ID
-                ),
-                Ok(())
+                checkpoint.hash,
+                verification_account.checkpoint_hash(&ram_account)
             );
         }
 
-        // Failure for miller loop (proof not setup)
+        // Already exported
+        assert_eq!(
+            export_verification_checkpoint(&warden, &mut verification_account, &ram, 0, 0),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // The computation is paused
+        vkey_account!(vkey, SendQuadraVKey);
+        vkey.set_version(&1);
         assert_eq!(
             compute_verification(
                 &mut verification_account,
+                &ram,
                 &vkey,
-                &any,
+                &warden,
                 0,
-                SendQuadraVKey::VKEY_ID
+                SendQuadraVKey::VKEY_ID,
+                0
             ),
             Err(ElusivError::InvalidAccountState.into())
         );
 
-        let proof = test_proof();
-        verification_account.a.set(proof.a);
-        verification_account.b.set(proof.b);
-        verification_account.c.set(proof.c);
-        verification_account.set_state(&VerificationState::ProofSetup);
+        // Invalid checkpoint hash
+        assert_eq!(
+            import_verification_checkpoint(
+                &mut verification_account,
+                &ram,
+                &new_ram,
+                0,
+                0,
+                1,
+                [1; 32]
+            ),
+            Err(ElusivError::InputsMismatch.into())
+        );
 
-        // Success
-        for _ in 0..COMBINED_MILLER_LOOP_IXS + FINAL_EXPONENTIATION_IXS {
-            assert_eq!(
-                compute_verification(
-                    &mut verification_account,
-                    &vkey,
-                    &any,
-                    0,
-                    SendQuadraVKey::VKEY_ID
-                ),
-                Ok(())
-            );
+        // RAM is not fresh
+        assert_eq!(
+            import_verification_checkpoint(
+                &mut verification_account,
+                &ram,
+                &ram,
+                0,
+                0,
+                0,
+                checkpoint.hash
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        {
+            pda_account!(mut new_ram_account, VerifierRAMAccount, new_ram);
+            new_ram_account.set_is_in_use(&true);
         }
-
-        // Computation is finished
         assert_eq!(
-            compute_verification(
+            import_verification_checkpoint(
                 &mut verification_account,
-                &vkey,
-                &any,
+                &ram,
+                &new_ram,
+                0,
                 0,
-                SendQuadraVKey::VKEY_ID
+                1,
+                checkpoint.hash
             ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+        {
+            pda_account!(mut new_ram_account, VerifierRAMAccount, new_ram);
+            new_ram_account.set_is_in_use(&false);
+        }
+
+        import_verification_checkpoint(
+            &mut verification_account,
+            &ram,
+            &new_ram,
+            0,
+            0,
+            1,
+            checkpoint.hash,
+        )
+        .unwrap();
+
+        assert_eq!(
+            verification_account.get_ram_account().option(),
+            Some(*new_ram.key)
+        );
+        assert!(verification_account.get_checkpoint().option().is_none());
+
+        pda_account!(ram_account, VerifierRAMAccount, ram);
+        assert!(!ram_account.get_is_in_use());
+
+        pda_account!(mut new_ram_account, VerifierRAMAccount, new_ram);
+        assert!(new_ram_account.get_is_in_use());
+        assert_eq!(new_ram_account.ram_fq.read(2), Fq::from(123u64));
+        assert_eq!(new_ram_account.ram_fq12.read(6), Fq12::one());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_release_stale_verifier_ram_account() {
+        zero_program_account!(mut verification_account, VerificationAccount);
+        account_info!(
+            ram,
+            VerifierRAMAccount::find(Some(0)).0,
+            vec![0; VerifierRAMAccount::SIZE]
+        );
+        account_info!(
+            other_ram,
+            VerifierRAMAccount::find(Some(1)).0,
+            vec![0; VerifierRAMAccount::SIZE]
+        );
+
+        verification_account.set_ram_account(&ElusivOption::Some(*ram.key));
+        verification_account.set_step(&VerificationStep::CombinedMillerLoop);
+        verification_account.set_round(&7);
+        {
+            pda_account!(mut ram_account, VerifierRAMAccount, ram);
+            ram_account.set_is_in_use(&true);
+        }
+
+        // Invalid RAM account
+        assert_eq!(
+            release_stale_verifier_ram_account(&mut verification_account, &other_ram, 0, 1),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Computation is finished
+        verification_account.set_is_verified(&ElusivOption::Some(true));
+        assert_eq!(
+            release_stale_verifier_ram_account(&mut verification_account, &ram, 0, 0),
             Err(ElusivError::ComputationIsAlreadyFinished.into())
         );
-        assert_eq!(verification_account.get_is_verified().option(), Some(false));
+
+        // `current_slot` is zero in unit tests
+        verification_account.set_is_verified(&ElusivOption::None);
+        assert_eq!(
+            release_stale_verifier_ram_account(&mut verification_account, &ram, 0, 0),
+            Err(ElusivError::CancellationTimeoutNotReached.into())
+        );
+
+        pda_account!(ram_account, VerifierRAMAccount, ram);
+        assert!(ram_account.get_is_in_use());
+        assert_eq!(
+            verification_account.get_ram_account().option(),
+            Some(*ram.key)
+        );
     }
 
     macro_rules! finalize_send_test {
@@ -2637,9 +5769,16 @@ mod tests {
                     false,
                     &metadata,
                     &optional_fee,
+                    0,
+                    0,
+                    (0, 0),
                     &None,
                 ),
                 solana_pay_transfer: false,
+                expiry: 0,
+                unlock_timestamp: 0,
+                earliest_execution_slot: 0,
+                latest_execution_slot: 0,
             };
 
             let mut $v_data = vec![0; VerificationAccount::SIZE];
@@ -2654,6 +5793,7 @@ mod tests {
                     0,
                     ProofRequest::Send($public_inputs.clone()),
                     [0, 1],
+                    false,
                 )
                 .unwrap();
             v_account.set_state(&VerificationState::ProofSetup);
@@ -2676,6 +5816,28 @@ mod tests {
         };
     }
 
+    /// Creates the `ApaFlagAccount` of a flagged `recipient` (without token constraint and region scope)
+    macro_rules! apa_flag_account {
+        ($id: ident, $recipient: expr) => {
+            let (pk, bump) = Pubkey::find_program_address(
+                &[
+                    crate::processor::utils::APA_FLAG_ACCOUNT_SEED,
+                    &$recipient.to_bytes(),
+                ],
+                &crate::processor::utils::WARDEN_NETWORK_PROGRAM_ID,
+            );
+            let mut data = vec![0; crate::processor::utils::APA_FLAG_TOKEN_CONSTRAINT_OFFSET + 3];
+            data[0] = bump;
+            account_info!(
+                $id,
+                pk,
+                data,
+                crate::processor::utils::WARDEN_NETWORK_PROGRAM_ID,
+                false
+            );
+        };
+    }
+
     macro_rules! simple_storage_account {
         ($id: ident) => {
             let mut data = vec![0; StorageAccount::SIZE];
@@ -2702,6 +5864,7 @@ mod tests {
         let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
         simple_storage_account!(storage);
         zero_program_account!(mut buffer, CommitmentBufferAccount);
+        zero_program_account!(governor, GovernorAccount);
 
         account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
         account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
@@ -2720,6 +5883,8 @@ mod tests {
                 &storage,
                 &mut buffer,
                 &any,
+                &any,
+                &governor,
                 0,
                 finalize_data.clone(),
                 false,
@@ -2742,6 +5907,8 @@ mod tests {
                     &storage,
                     &mut buffer,
                     &any,
+                    &any,
+                    &governor,
                     0,
                     finalize_data.clone(),
                     false,
@@ -2763,6 +5930,8 @@ mod tests {
                     &storage,
                     &mut buffer,
                     &any,
+                    &any,
+                    &governor,
                     0,
                     finalize_data.clone(),
                     false,
@@ -2784,6 +5953,8 @@ mod tests {
                     &storage,
                     &mut buffer,
                     &any,
+                    &any,
+                    &governor,
                     0,
                     finalize_data.clone(),
                     false,
@@ -2813,6 +5984,8 @@ mod tests {
                     &storage,
                     &mut buffer,
                     &any,
+                    &any,
+                    &governor,
                     0,
                     invalid_data,
                     false,
@@ -2821,6 +5994,26 @@ mod tests {
             );
         }
 
+        // MT of a different MT-shard than the output commitment's
+        assert_eq!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &mut buffer,
+                &any,
+                &any,
+                &governor,
+                0,
+                mutate(&finalize_data, |d| d.mt_index = sharded_mt_index(1, 0)),
+                false,
+            ),
+            Err(ElusivError::InvalidMtShard.into())
+        );
+
         // Success
         assert_eq!(
             finalize_verification_send(
@@ -2832,6 +6025,8 @@ mod tests {
                 &storage,
                 &mut buffer,
                 &any,
+                &any,
+                &governor,
                 0,
                 finalize_data.clone(),
                 false,
@@ -2844,7 +6039,129 @@ mod tests {
             VerificationState::InsertNullifiers
         );
 
-        // Called twice
+        // Called twice
+        assert_eq!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &mut buffer,
+                &any,
+                &any,
+                &governor,
+                0,
+                finalize_data,
+                false,
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+    }
+
+    #[test]
+    fn test_finalize_verification_send_invalid() {
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            reference_bytes,
+            finalize_data
+        );
+
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        simple_storage_account!(storage);
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+        zero_program_account!(governor, GovernorAccount);
+        test_account_info!(any, 0);
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+
+        verification_acc.set_is_verified(&ElusivOption::Some(false));
+
+        assert_eq!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &mut buffer,
+                &any,
+                &any,
+                &governor,
+                0,
+                finalize_data,
+                false,
+            ),
+            Ok(())
+        );
+        assert_eq!(verification_acc.get_state(), VerificationState::Finalized);
+    }
+
+    #[test]
+    #[cfg(not(feature = "mainnet"))]
+    fn test_finalize_verification_send_after_reset() {
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            reference_bytes,
+            finalize_data
+        );
+
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        let mut data = vec![0; StorageAccount::SIZE];
+        let storage =
+            <StorageAccount as elusiv_types::accounts::ProgramAccount>::new(&mut data).unwrap();
+        zero_program_account!(mut buffer, CommitmentBufferAccount);
+        zero_program_account!(mut governor, GovernorAccount);
+        test_account_info!(any, 0);
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+
+        verification_acc.set_is_verified(&ElusivOption::Some(false));
+
+        // The verification has been initialized before the reset
+        governor.set_reset_generation(&1);
+        assert_eq!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &mut buffer,
+                &any,
+                &any,
+                &governor,
+                0,
+                finalize_data.clone(),
+                false,
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        verification_acc.set_other_data(&mutate(&verification_acc.get_other_data(), |data| {
+            data.reset_generation = governor.get_reset_generation();
+        }));
         assert_eq!(
             finalize_verification_send(
                 &recipient,
@@ -2855,16 +6172,18 @@ mod tests {
                 &storage,
                 &mut buffer,
                 &any,
+                &any,
+                &governor,
                 0,
                 finalize_data,
                 false,
             ),
-            Err(ElusivError::InvalidAccountState.into())
+            Ok(())
         );
     }
 
     #[test]
-    fn test_finalize_verification_send_invalid() {
+    fn test_finalize_verification_send_execution_window() {
         finalize_send_test!(
             USDC_TOKEN_ID,
             LAMPORTS_PER_SOL,
@@ -2881,6 +6200,7 @@ mod tests {
         let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
         simple_storage_account!(storage);
         zero_program_account!(mut buffer, CommitmentBufferAccount);
+        zero_program_account!(governor, GovernorAccount);
         test_account_info!(any, 0);
 
         account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
@@ -2889,6 +6209,49 @@ mod tests {
 
         verification_acc.set_is_verified(&ElusivOption::Some(false));
 
+        let request = |execution_window: (u64, u64)| {
+            let mut public_inputs = public_inputs.clone();
+            public_inputs.earliest_execution_slot = execution_window.0;
+            public_inputs.latest_execution_slot = execution_window.1;
+            public_inputs.hashed_inputs = generate_hashed_inputs(
+                &recipient_bytes,
+                &identifier_bytes,
+                &finalize_data.iv,
+                &finalize_data.encrypted_owner,
+                &reference_bytes,
+                false,
+                &public_inputs.join_split.metadata,
+                &public_inputs.join_split.optional_fee,
+                0,
+                0,
+                execution_window,
+                &None,
+            );
+            ProofRequest::Send(public_inputs)
+        };
+
+        // The current slot in tests is `0`
+        verification_acc.set_request(&request((1, 0)));
+        assert_eq!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &mut buffer,
+                &any,
+                &any,
+                &governor,
+                0,
+                finalize_data.clone(),
+                false,
+            ),
+            Err(ElusivError::EarliestExecutionSlotNotReached.into())
+        );
+
+        verification_acc.set_request(&request((0, 10)));
         assert_eq!(
             finalize_verification_send(
                 &recipient,
@@ -2899,13 +6262,45 @@ mod tests {
                 &storage,
                 &mut buffer,
                 &any,
+                &any,
+                &governor,
                 0,
                 finalize_data,
                 false,
             ),
             Ok(())
         );
-        assert_eq!(verification_acc.get_state(), VerificationState::Finalized);
+    }
+
+    #[test]
+    fn test_verify_execution_window() {
+        finalize_send_test!(
+            0,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            _verification_acc_data,
+            _recipient_bytes,
+            _identifier_bytes,
+            _reference_bytes,
+            _finalize_data
+        );
+
+        let mut public_inputs = public_inputs;
+        assert_eq!(verify_execution_window(&public_inputs, 0), Ok(()));
+        assert_eq!(verify_execution_window(&public_inputs, u64::MAX), Ok(()));
+
+        public_inputs.earliest_execution_slot = 10;
+        public_inputs.latest_execution_slot = 20;
+        assert_eq!(
+            verify_execution_window(&public_inputs, 9),
+            Err(ElusivError::EarliestExecutionSlotNotReached.into())
+        );
+        assert_eq!(verify_execution_window(&public_inputs, 10), Ok(()));
+        assert_eq!(verify_execution_window(&public_inputs, 20), Ok(()));
+        assert_eq!(
+            verify_execution_window(&public_inputs, 21),
+            Err(ElusivError::LatestExecutionSlotPassed.into())
+        );
     }
 
     #[test]
@@ -2944,6 +6339,7 @@ mod tests {
         let finalize_data = FinalizeSendData::default();
         simple_storage_account!(storage);
         zero_program_account!(mut buffer, CommitmentBufferAccount);
+        zero_program_account!(governor, GovernorAccount);
         test_account_info!(any, 0);
 
         assert_eq!(
@@ -2956,6 +6352,8 @@ mod tests {
                 &storage,
                 &mut buffer,
                 &any,
+                &any,
+                &governor,
                 0,
                 finalize_data,
                 false,
@@ -2996,32 +6394,529 @@ mod tests {
             Err(ElusivError::CouldNotInsertNullifier.into())
         );
 
-        parent_account!(mut n_acc_0, NullifierAccount);
+        parent_account!(mut n_acc_0, NullifierAccount);
+
+        // Success
+        assert_eq!(
+            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0),
+            Ok(())
+        );
+
+        assert!(!n_acc_0
+            .can_insert_nullifier_hash(
+                public_inputs.join_split.input_commitments[0]
+                    .nullifier_hash
+                    .reduce()
+            )
+            .unwrap());
+        assert_eq!(verification_acc.get_state(), VerificationState::Finalized);
+
+        // Called twice
+        assert_eq!(
+            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+    }
+
+    #[test]
+    fn test_finalize_verification_batch_skips_invalid_entries() {
+        zero_program_account!(mut metrics, MetricsAccount);
+        zero_program_account!(mut verification_history, VerificationHistoryAccount);
+        zero_program_account!(governor, GovernorAccount);
+        test_account_info!(f, 0);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        test_account_info!(quarantine, 0);
+        test_account_info!(any, 0);
+        test_account_info!(v_acc, VerificationAccount::SIZE);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+        macro_rules! batch {
+            ($count: expr) => {
+                finalize_verification_batch(
+                    &f,
+                    &pool,
+                    &fee_collector,
+                    &mut commitment_queue,
+                    &mut metadata_queue,
+                    &quarantine,
+                    &any,
+                    &mut metrics,
+                    &mut verification_history,
+                    &governor,
+                    &any,
+                    &any,
+                    &any,
+                    &v_acc,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    &any,
+                    0,
+                    &any,
+                    [0; MAX_FINALIZE_BATCH_SIZE],
+                    $count,
+                )
+            };
+        }
+
+        // Invalid count
+        assert_eq!(batch!(0), Err(ElusivError::InvalidInstructionData.into()));
+        assert_eq!(
+            batch!(MAX_FINALIZE_BATCH_SIZE as u8 + 1),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        // Invalid entries are skipped
+        assert_eq!(batch!(MAX_FINALIZE_BATCH_SIZE as u8), Ok(()));
+        assert!(CommitmentQueue::new(&mut commitment_queue).is_empty());
+    }
+
+    #[test]
+    fn test_finalize_verification_batch_isolates_entries() {
+        zero_program_account!(mut metrics, MetricsAccount);
+        zero_program_account!(mut verification_history, VerificationHistoryAccount);
+        zero_program_account!(governor, GovernorAccount);
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            10,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            _i,
+            _r,
+            _f,
+            optional_fee_collector
+        );
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        let fee_payer_pk = Pubkey::new(
+            &VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk); // fee_payer
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        account_info!(optional_fee_collector, optional_fee_collector);
+        test_account_info!(any, 0);
+        account_info!(
+            recipient_preferences,
+            RecipientPreferenceAccount::find_with_pubkey(*recipient.key, None).0,
+            vec![]
+        );
+        test_pda_account_info!(quarantine, QuarantineAccount, None);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+
+        // Two finalized sends of the same user
+        let identifier = Pubkey::new_unique();
+        let v_data = |index: u32| {
+            let (_, bump) = VerificationAccount::find_with_pubkey(fee_payer_pk, Some(index));
+            let mut data = verification_acc_data.clone();
+            data[0] = bump;
+
+            {
+                let mut v_acc = VerificationAccount::new(&mut data).unwrap();
+                v_acc.set_state(&VerificationState::Finalized);
+                v_acc.set_is_verified(&ElusivOption::Some(true));
+                v_acc.set_identifier(&ElusivOption::Some(identifier));
+            }
+            data
+        };
+        account_info!(
+            v_acc0,
+            VerificationAccount::find_with_pubkey(fee_payer_pk, Some(0)).0,
+            v_data(0)
+        );
+        account_info!(
+            v_acc1,
+            VerificationAccount::find_with_pubkey(fee_payer_pk, Some(1)).0,
+            v_data(1)
+        );
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+        commitment_queue.set_in_flight_cap(&1);
+
+        finalize_verification_batch(
+            &f,
+            &pool,
+            &fee_collector,
+            &mut commitment_queue,
+            &mut metadata_queue,
+            &quarantine,
+            &any,
+            &mut metrics,
+            &mut verification_history,
+            &governor,
+            &any,
+            &recipient,
+            &optional_fee_collector,
+            &v_acc0,
+            &n_pda,
+            &recipient_preferences,
+            &recipient,
+            &optional_fee_collector,
+            &v_acc1,
+            &n_pda,
+            &recipient_preferences,
+            &any,
+            &any,
+            &any,
+            &any,
+            &any,
+            &any,
+            &any,
+            &any,
+            &any,
+            0,
+            &any,
+            [0, 1, 0, 0],
+            2,
+        )
+        .unwrap();
+
+        // The second send exceeds the in-flight cap of the user and is skipped (instead of failing the batch)
+        {
+            pda_account!(v_acc, VerificationAccount, v_acc0);
+            assert_eq!(v_acc.get_state(), VerificationState::Closed);
+        }
+        {
+            pda_account!(v_acc, VerificationAccount, v_acc1);
+            assert_eq!(v_acc.get_state(), VerificationState::Finalized);
+        }
+        let queue = CommitmentQueue::new(&mut commitment_queue);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.in_flight_count(&identifier.to_bytes()), 1);
+    }
+
+    #[test]
+    fn test_close_verification_account() -> ProgramResult {
+        test_account_info!(fee_payer, 0);
+        test_account_info!(other_fee_payer, 0);
+        test_account_info!(v_acc, VerificationAccount::SIZE);
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_other_data(&VerificationAccountData {
+                fee_payer: RawU256::new(fee_payer.key.to_bytes()),
+                reusable: true,
+                ..Default::default()
+            });
+        }
+
+        // Account still in use
+        assert_eq!(
+            close_verification_account(&fee_payer, &v_acc, 0),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Closed);
+        }
+
+        // Invalid fee_payer
+        assert_eq!(
+            close_verification_account(&other_fee_payer, &v_acc, 0),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        let lamports = v_acc.lamports();
+        close_verification_account(&fee_payer, &v_acc, 0)?;
+        assert_eq!(v_acc.lamports(), 0);
+        assert_eq!(fee_payer.lamports(), lamports * 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_verification_accounts() -> ProgramResult {
+        test_account_info!(fee_payer, 0);
+        test_account_info!(unused, 0);
+
+        let verification_account_data = |index: u32| {
+            let (_, bump) = VerificationAccount::find_with_pubkey(*fee_payer.key, Some(index));
+            let mut data = vec![0; VerificationAccount::SIZE];
+            data[0] = bump;
+
+            {
+                let mut v_acc = VerificationAccount::new(&mut data).unwrap();
+                v_acc.set_state(&VerificationState::Closed);
+                v_acc.set_other_data(&VerificationAccountData {
+                    fee_payer: RawU256::new(fee_payer.key.to_bytes()),
+                    reusable: true,
+                    ..Default::default()
+                });
+            }
+            data
+        };
+        account_info!(
+            v_acc0,
+            VerificationAccount::find_with_pubkey(*fee_payer.key, Some(0)).0,
+            verification_account_data(0)
+        );
+        account_info!(
+            v_acc1,
+            VerificationAccount::find_with_pubkey(*fee_payer.key, Some(1)).0,
+            verification_account_data(1)
+        );
+
+        // Invalid count
+        for count in [0, MAX_CLOSE_BATCH_SIZE as u8 + 1] {
+            assert_eq!(
+                close_verification_accounts(
+                    &fee_payer,
+                    &v_acc0,
+                    &v_acc1,
+                    &unused,
+                    &unused,
+                    [0, 1, 0, 0],
+                    count
+                ),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        // Invalid index
+        assert_eq!(
+            close_verification_accounts(
+                &fee_payer,
+                &v_acc0,
+                &v_acc1,
+                &unused,
+                &unused,
+                [1, 0, 0, 0],
+                2
+            ),
+            Err(ProgramError::InvalidSeeds)
+        );
+
+        let lamports = v_acc0.lamports();
+        close_verification_accounts(
+            &fee_payer,
+            &v_acc0,
+            &v_acc1,
+            &unused,
+            &unused,
+            [0, 1, 0, 0],
+            2,
+        )?;
+        assert_eq!(v_acc0.lamports(), 0);
+        assert_eq!(v_acc1.lamports(), 0);
+        assert_eq!(fee_payer.lamports(), lamports * 3);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "alt-bn128")]
+    fn test_batch_verification() -> ProgramResult {
+        use crate::proof::test_proofs::{invalid_proofs, valid_proofs, TestProof};
+        use crate::proof::vkey::TestVKey;
+
+        test_account_info!(fee_payer, 0);
+        test_account_info!(unused, 0);
+        vkey_account!(vkey, TestVKey);
+        vkey.set_version(&1);
+
+        let verification_account_data = |index: u32, proof: &TestProof| {
+            let (_, bump) = VerificationAccount::find_with_pubkey(*fee_payer.key, Some(index));
+            let mut data = vec![0; VerificationAccount::SIZE];
+            data[0] = bump;
+
+            {
+                let mut v_acc = VerificationAccount::new(&mut data).unwrap();
+                v_acc.set_vkey_id(&TestVKey::VKEY_ID);
+                v_acc.set_state(&VerificationState::ProofSetup);
+                v_acc.a.set(proof.proof.a);
+                v_acc.b.set(proof.proof.b);
+                v_acc.c.set(proof.proof.c);
+                for (i, &public_input) in proof.public_inputs.iter().enumerate() {
+                    v_acc.set_public_input(i, &RawU256::new(public_input));
+                }
+            }
+            data
+        };
+        let batch_verification_account_data = |count: u8| {
+            let (_, bump) = BatchVerificationAccount::find_with_pubkey(*fee_payer.key, Some(0));
+            let mut data = vec![0; BatchVerificationAccount::SIZE];
+            data[0] = bump;
+
+            {
+                let mut batch = BatchVerificationAccount::new(&mut data).unwrap();
+                batch.set_vkey_id(&TestVKey::VKEY_ID);
+                batch.set_count(&count);
+                for i in 0..count {
+                    batch.set_verification_account_indices(i as usize, &i);
+                }
+            }
+            data
+        };
+        let v_acc_pubkey =
+            |index: u32| VerificationAccount::find_with_pubkey(*fee_payer.key, Some(index)).0;
+        let batch_pubkey = BatchVerificationAccount::find_with_pubkey(*fee_payer.key, Some(0)).0;
+
+        let valid = valid_proofs();
+        account_info!(
+            v_acc0,
+            v_acc_pubkey(0),
+            verification_account_data(0, &valid[0])
+        );
+        account_info!(
+            v_acc1,
+            v_acc_pubkey(1),
+            verification_account_data(1, &valid[1])
+        );
+
+        // Invalid count
+        for count in [1, MAX_BATCH_VERIFICATION_SIZE as u8 + 1] {
+            assert_eq!(
+                init_batch_verification(
+                    &fee_payer,
+                    &unused,
+                    &v_acc0,
+                    &v_acc1,
+                    &unused,
+                    &unused,
+                    0,
+                    TestVKey::VKEY_ID,
+                    [0, 1, 0, 0],
+                    count
+                ),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
 
-        // Success
+        // Duplicate index
         assert_eq!(
-            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0),
-            Ok(())
+            init_batch_verification(
+                &fee_payer,
+                &unused,
+                &v_acc0,
+                &v_acc0,
+                &unused,
+                &unused,
+                0,
+                TestVKey::VKEY_ID,
+                [0, 0, 0, 0],
+                2
+            ),
+            Err(ElusivError::InvalidInstructionData.into())
         );
 
-        assert!(!n_acc_0
-            .can_insert_nullifier_hash(
-                public_inputs.join_split.input_commitments[0]
-                    .nullifier_hash
-                    .reduce()
-            )
-            .unwrap());
-        assert_eq!(verification_acc.get_state(), VerificationState::Finalized);
+        // Mismatched vkey
+        assert_eq!(
+            init_batch_verification(
+                &fee_payer,
+                &unused,
+                &v_acc0,
+                &v_acc1,
+                &unused,
+                &unused,
+                0,
+                TestVKey::VKEY_ID + 1,
+                [0, 1, 0, 0],
+                2
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
 
-        // Called twice
+        // Valid batch
+        account_info!(batch, batch_pubkey, batch_verification_account_data(2));
+        compute_batch_verification(
+            &fee_payer,
+            &batch,
+            &vkey,
+            &v_acc0,
+            &v_acc1,
+            &unused,
+            &unused,
+            0,
+            TestVKey::VKEY_ID,
+        )?;
+        assert_eq!(batch.lamports(), 0);
+        for v_acc in [&v_acc0, &v_acc1] {
+            pda_account!(verification_account, VerificationAccount, v_acc);
+            assert_eq!(verification_account.get_is_verified().option(), Some(true));
+        }
+
+        // Already verified
+        account_info!(batch, batch_pubkey, batch_verification_account_data(2));
         assert_eq!(
-            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0),
-            Err(ElusivError::InvalidAccountState.into())
+            compute_batch_verification(
+                &fee_payer,
+                &batch,
+                &vkey,
+                &v_acc0,
+                &v_acc1,
+                &unused,
+                &unused,
+                0,
+                TestVKey::VKEY_ID,
+            ),
+            Err(ElusivError::ComputationIsAlreadyFinished.into())
         );
-    }
 
+        // Invalid batch (the verifications are left for their individual computation)
+        let invalid = invalid_proofs();
+        account_info!(
+            v_acc0,
+            v_acc_pubkey(0),
+            verification_account_data(0, &valid[0])
+        );
+        account_info!(
+            v_acc1,
+            v_acc_pubkey(1),
+            verification_account_data(1, &invalid[0])
+        );
+        account_info!(batch, batch_pubkey, batch_verification_account_data(2));
+        compute_batch_verification(
+            &fee_payer,
+            &batch,
+            &vkey,
+            &v_acc0,
+            &v_acc1,
+            &unused,
+            &unused,
+            0,
+            TestVKey::VKEY_ID,
+        )?;
+        assert_eq!(batch.lamports(), 0);
+        for v_acc in [&v_acc0, &v_acc1] {
+            pda_account!(verification_account, VerificationAccount, v_acc);
+            assert_eq!(verification_account.get_is_verified().option(), None);
+        }
+
+        Ok(())
+    }
     #[test]
     fn test_finalize_verification_transfer_lamports() -> ProgramResult {
+        zero_program_account!(mut metrics, MetricsAccount);
+        zero_program_account!(mut verification_history, VerificationHistoryAccount);
+        zero_program_account!(governor, GovernorAccount);
         finalize_send_test!(
             LAMPORTS_TOKEN_ID,
             LAMPORTS_PER_SOL,
@@ -3048,6 +6943,12 @@ mod tests {
         test_account_info!(fee_collector, 0);
         account_info!(optional_fee_collector, optional_fee_collector);
         test_account_info!(any, 0);
+        account_info!(
+            recipient_preferences,
+            RecipientPreferenceAccount::find_with_pubkey(*recipient.key, None).0,
+            vec![]
+        );
+        test_pda_account_info!(quarantine, QuarantineAccount, None);
         test_pda_account_info!(
             n_pda,
             NullifierDuplicateAccount,
@@ -3079,7 +6980,17 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
-                0
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
             ),
             Err(ElusivError::InvalidAccountState.into())
         );
@@ -3107,7 +7018,17 @@ mod tests {
                 &v_acc,
                 &invalid_n_pda,
                 &any,
-                0
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -3125,7 +7046,17 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
-                0
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -3143,11 +7074,81 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
-                0
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
             ),
             Err(ElusivError::InvalidRecipient.into())
         );
 
+        // Commitment queue of a different MT-shard than the output commitment's
+        assert_eq!(
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
+                &pool,
+                &fee_collector,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                &any,
+                1,
+                0,
+            ),
+            Err(ElusivError::InvalidMtShard.into())
+        );
+
+        // Depleted pool (the payout is rejected before any funds are moved)
+        let pool_balance = pool.lamports();
+        **pool.lamports.borrow_mut() = LAMPORTS_PER_SOL - 1;
+        assert_eq!(
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
+                &pool,
+                &fee_collector,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
+            ),
+            Err(ElusivError::PoolLiquidityDepleted.into())
+        );
+        assert_eq!(pool.lamports(), LAMPORTS_PER_SOL - 1);
+        **pool.lamports.borrow_mut() = pool_balance;
+
         // Invalid optional-fee-collector
         test_account_info!(invalid_optional_fee_collector, 0);
         assert_eq!(
@@ -3162,7 +7163,17 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
-                0
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -3192,13 +7203,174 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
-                0
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
+            ),
+            Err(ElusivError::QueueIsFull.into())
+        );
+
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+
+        assert_eq!(
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
+                &pool,
+                &fee_collector,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
+            ),
+            Ok(())
+        );
+
+        assert_eq!(n_pda.lamports(), 0);
+        assert_eq!(v_acc.lamports(), 0);
+        pda_account!(v_acc, VerificationAccount, v_acc);
+        assert_eq!(v_acc.get_state(), VerificationState::Closed);
+
+        assert_eq!(
+            metrics.get_volume_out(LAMPORTS_TOKEN_ID as usize),
+            LAMPORTS_PER_SOL
+        );
+        assert_eq!(metrics.send_count(LAMPORTS_TOKEN_ID), 1);
+
+        let record = verification_history.latest(0).unwrap();
+        assert_eq!(verification_history.get_count(), 1);
+        assert_eq!(record.recipient, recipient.key.to_bytes());
+        assert_eq!(record.warden, f.key.to_bytes());
+        assert_eq!(
+            record.nullifier_set_hash,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey()
+                .to_bytes()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_lamports_timelocked() -> ProgramResult {
+        zero_program_account!(mut metrics, MetricsAccount);
+        zero_program_account!(mut verification_history, VerificationHistoryAccount);
+        zero_program_account!(governor, GovernorAccount);
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            10,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            _i,
+            _r,
+            _f,
+            optional_fee_collector
+        );
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        let fee_payer_pk = Pubkey::new(
+            &VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk); // fee_payer
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        account_info!(optional_fee_collector, optional_fee_collector);
+        test_account_info!(any, 0);
+        account_info!(
+            recipient_preferences,
+            RecipientPreferenceAccount::find_with_pubkey(*recipient.key, None).0,
+            vec![]
+        );
+        test_pda_account_info!(quarantine, QuarantineAccount, None);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(
+            pending_release,
+            PendingReleaseAccount::find_with_pubkey(*n_pda.key, None).0,
+            vec![0; PendingReleaseAccount::SIZE]
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+        // `current_timestamp` is zero in unit tests
+        let unlock_timestamp = 1;
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Finalized);
+            v_acc.set_request(&ProofRequest::Send(SendPublicInputs {
+                unlock_timestamp,
+                ..public_inputs
+            }));
+        }
+
+        let recipient_lamports = recipient.lamports();
+        let pending_release_lamports = pending_release.lamports();
+        let amount = LAMPORTS_PER_SOL - 10;
+
+        // Invalid pending_release_account
+        assert_eq!(
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
+                &pool,
+                &fee_collector,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
             ),
-            Err(ElusivError::QueueIsFull.into())
+            Err(ProgramError::InvalidSeeds)
         );
 
-        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
-
+        // The amount is parked in the pending_release_account
         assert_eq!(
             finalize_verification_transfer_lamports(
                 &f,
@@ -3211,21 +7383,70 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
-                0
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &pending_release,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
             ),
             Ok(())
         );
+        assert_eq!(recipient.lamports(), recipient_lamports);
+        assert_eq!(
+            pending_release.lamports(),
+            pending_release_lamports + amount
+        );
+        {
+            pda_account!(pending_release, PendingReleaseAccount, pending_release);
+            assert_eq!(pending_release.get_recipient(), *recipient.key);
+            assert_eq!(pending_release.get_rent_payer(), *f.key);
+            assert_eq!(pending_release.get_amount(), amount);
+            assert_eq!(pending_release.get_unlock_timestamp(), unlock_timestamp);
+        }
 
-        assert_eq!(n_pda.lamports(), 0);
-        assert_eq!(v_acc.lamports(), 0);
-        pda_account!(v_acc, VerificationAccount, v_acc);
-        assert_eq!(v_acc.get_state(), VerificationState::Closed);
+        // Timelock not reached
+        assert_eq!(
+            release_pending_transfer(&recipient, &f, &pending_release, *n_pda.key),
+            Err(ElusivError::TimelockNotReached.into())
+        );
+
+        {
+            pda_account!(mut pending_release, PendingReleaseAccount, pending_release);
+            pending_release.set_unlock_timestamp(&0);
+        }
+
+        // Invalid recipient
+        assert_eq!(
+            release_pending_transfer(&any, &f, &pending_release, *n_pda.key),
+            Err(ElusivError::InvalidRecipient.into())
+        );
+
+        // Invalid rent_payer
+        assert_eq!(
+            release_pending_transfer(&recipient, &any, &pending_release, *n_pda.key),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Anyone can release the amount after the unlock
+        let f_lamports = f.lamports();
+        release_pending_transfer(&recipient, &f, &pending_release, *n_pda.key)?;
+        assert_eq!(recipient.lamports(), recipient_lamports + amount);
+        assert_eq!(f.lamports(), f_lamports + pending_release_lamports);
+        assert_eq!(pending_release.lamports(), 0);
 
         Ok(())
     }
 
     #[test]
     fn test_finalize_verification_transfer_lamports_merge() -> ProgramResult {
+        zero_program_account!(mut metrics, MetricsAccount);
+        zero_program_account!(mut verification_history, VerificationHistoryAccount);
         finalize_send_test!(
             LAMPORTS_TOKEN_ID,
             0,
@@ -3249,6 +7470,8 @@ mod tests {
         test_account_info!(fee_collector, 0);
         test_account_info!(optional_fee_collector, 0);
         test_account_info!(any, 0);
+        test_account_info!(recipient_preferences, 0);
+        test_pda_account_info!(quarantine, QuarantineAccount, None);
         test_pda_account_info!(
             n_pda,
             NullifierDuplicateAccount,
@@ -3282,7 +7505,17 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
-                0
+                &recipient_preferences,
+                &quarantine,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
             ),
             Ok(())
         );
@@ -3292,6 +7525,9 @@ mod tests {
 
     #[test]
     fn test_finalize_verification_transfer_token() -> ProgramResult {
+        zero_program_account!(mut metrics, MetricsAccount);
+        zero_program_account!(mut verification_history, VerificationHistoryAccount);
+        zero_program_account!(governor, GovernorAccount);
         finalize_send_test!(
             USDC_TOKEN_ID,
             LAMPORTS_PER_SOL,
@@ -3336,6 +7572,14 @@ mod tests {
         );
 
         test_account_info!(any, 0);
+        let recipient_preferences_pk = RecipientPreferenceAccount::find_with_pubkey(
+            Pubkey::new_from_array(recipient_bytes),
+            None,
+        )
+        .0;
+        account_info!(recipient_preferences, recipient_preferences_pk, vec![]);
+        test_pda_account_info!(quarantine, QuarantineAccount, None);
+        program_token_account_info!(quarantine_token, QuarantineAccount, USDC_TOKEN_ID);
         account_info!(spl, spl_token::id(), vec![]);
         test_pda_account_info!(
             n_pda,
@@ -3374,7 +7618,17 @@ mod tests {
                 &spl,
                 &any,
                 &any,
-                0
+                &recipient_preferences,
+                &quarantine,
+                &quarantine_token,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -3398,7 +7652,17 @@ mod tests {
                 &spl,
                 &any,
                 &any,
-                0
+                &recipient_preferences,
+                &quarantine,
+                &quarantine_token,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
@@ -3423,12 +7687,270 @@ mod tests {
                 &spl,
                 &any,
                 &any,
-                0
+                &recipient_preferences,
+                &quarantine,
+                &quarantine_token,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid token_program
+        assert_eq!(
+            finalize_verification_transfer_token(
+                &fee_payer,
+                &fee_payer_token,
+                &r,
+                &r,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &any,
+                &any,
+                &recipient_preferences,
+                &quarantine,
+                &quarantine_token,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Invalid original_fee_payer
+        assert_eq!(
+            finalize_verification_transfer_token(
+                &any,
+                &fee_payer_token,
+                &r,
+                &r,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &spl,
+                &any,
+                &any,
+                &recipient_preferences,
+                &quarantine,
+                &quarantine_token,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
 
-        // Invalid token_program
+        // Invalid recipient
+        assert_eq!(
+            finalize_verification_transfer_token(
+                &fee_payer,
+                &fee_payer_token,
+                &any,
+                &r,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &spl,
+                &any,
+                &any,
+                &recipient_preferences,
+                &quarantine,
+                &quarantine_token,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
+            ),
+            Err(ElusivError::InvalidRecipient.into())
+        );
+
+        assert_eq!(
+            finalize_verification_transfer_token(
+                &fee_payer,
+                &fee_payer_token,
+                &r,
+                &r,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &optional_fee_collector,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &spl,
+                &any,
+                &any,
+                &recipient_preferences,
+                &quarantine,
+                &quarantine_token,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
+            ),
+            Ok(())
+        );
+
+        assert_eq!(n_pda.lamports(), 0);
+        assert_eq!(v_acc.lamports(), 0);
+        pda_account!(v_acc, VerificationAccount, v_acc);
+        assert_eq!(v_acc.get_state(), VerificationState::Closed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_token_quarantine() -> ProgramResult {
+        zero_program_account!(mut metrics, MetricsAccount);
+        zero_program_account!(mut verification_history, VerificationHistoryAccount);
+        zero_program_account!(governor, GovernorAccount);
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            10,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            _i,
+            _r,
+            _f,
+            optional_fee_collector
+        );
+
+        // Non-associated token account, owned by `wallet`
+        let wallet = Pubkey::new_unique();
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(
+            spl_token::state::Account {
+                mint: elusiv_token(USDC_TOKEN_ID)?.mint,
+                owner: wallet,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut data[..],
+        )?;
+        account_info!(
+            r,
+            Pubkey::new_from_array(recipient_bytes),
+            data,
+            spl_token::id(),
+            false
+        );
+
+        let fee_payer_pk = Pubkey::new(
+            &VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(fee_payer, fee_payer_pk, vec![]);
+        account_info!(
+            fee_payer_token,
+            fee_payer_pk,
+            vec![],
+            spl_token::id(),
+            false
+        );
+
+        test_pda_account_info!(pool, PoolAccount, None);
+        test_pda_account_info!(fee_collector, FeeCollectorAccount, None);
+        program_token_account_info!(pool_token, PoolAccount, USDC_TOKEN_ID);
+        program_token_account_info!(fee_collector_token, FeeCollectorAccount, USDC_TOKEN_ID);
+
+        account_info!(
+            optional_fee_collector,
+            optional_fee_collector,
+            vec![],
+            spl_token::id(),
+            false
+        );
+
+        test_account_info!(any, 0);
+        test_pda_account_info!(quarantine, QuarantineAccount, None);
+        program_token_account_info!(quarantine_token, QuarantineAccount, USDC_TOKEN_ID);
+        account_info!(spl, spl_token::id(), vec![]);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Finalized);
+            v_acc.set_is_verified(&ElusivOption::Some(true));
+        }
+
+        // The owner `wallet` denies USDC payouts
+        let (wallet_preferences_pk, bump) =
+            RecipientPreferenceAccount::find_with_pubkey(wallet, None);
+        let mut data = vec![0; RecipientPreferenceAccount::SIZE];
+        data[0] = bump;
+        RecipientPreferenceAccount::new(&mut data)
+            .unwrap()
+            .set_denied_tokens(USDC_TOKEN_ID as usize, &true);
+        account_info!(wallet_preferences, wallet_preferences_pk, data);
+
+        // The preferences of the token account itself are not used
+        let recipient_preferences_pk = RecipientPreferenceAccount::find_with_pubkey(
+            Pubkey::new_from_array(recipient_bytes),
+            None,
+        )
+        .0;
+        account_info!(recipient_preferences, recipient_preferences_pk, vec![]);
         assert_eq!(
             finalize_verification_transfer_token(
                 &fee_payer,
@@ -3444,44 +7966,29 @@ mod tests {
                 &mut metadata_queue,
                 &v_acc,
                 &n_pda,
+                &spl,
                 &any,
                 &any,
+                &recipient_preferences,
+                &quarantine,
+                &quarantine_token,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
                 &any,
-                0
-            ),
-            Err(ElusivError::InvalidAccount.into())
-        );
-
-        // Invalid original_fee_payer
-        assert_eq!(
-            finalize_verification_transfer_token(
-                &any,
-                &fee_payer_token,
-                &r,
-                &r,
-                &pool,
-                &pool_token,
-                &fee_collector,
-                &fee_collector_token,
-                &optional_fee_collector,
-                &mut commitment_queue,
-                &mut metadata_queue,
-                &v_acc,
-                &n_pda,
-                &spl,
                 &any,
                 &any,
-                0
+                0,
+                0,
             ),
             Err(ElusivError::InvalidAccount.into())
         );
 
-        // Invalid recipient
         assert_eq!(
             finalize_verification_transfer_token(
                 &fee_payer,
                 &fee_payer_token,
-                &any,
+                &r,
                 &r,
                 &pool,
                 &pool_token,
@@ -3495,44 +8002,38 @@ mod tests {
                 &spl,
                 &any,
                 &any,
-                0
-            ),
-            Err(ElusivError::InvalidRecipient.into())
-        );
-
-        assert_eq!(
-            finalize_verification_transfer_token(
-                &fee_payer,
-                &fee_payer_token,
-                &r,
-                &r,
-                &pool,
-                &pool_token,
-                &fee_collector,
-                &fee_collector_token,
-                &optional_fee_collector,
-                &mut commitment_queue,
-                &mut metadata_queue,
-                &v_acc,
-                &n_pda,
-                &spl,
+                &wallet_preferences,
+                &quarantine,
+                &quarantine_token,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
                 &any,
                 &any,
-                0
+                &any,
+                0,
+                0,
             ),
             Ok(())
         );
 
-        assert_eq!(n_pda.lamports(), 0);
-        assert_eq!(v_acc.lamports(), 0);
-        pda_account!(v_acc, VerificationAccount, v_acc);
-        assert_eq!(v_acc.get_state(), VerificationState::Closed);
+        pda_account!(
+            wallet_preferences,
+            RecipientPreferenceAccount,
+            wallet_preferences
+        );
+        assert_eq!(
+            wallet_preferences.get_quarantined_amounts(USDC_TOKEN_ID as usize),
+            LAMPORTS_PER_SOL - 10
+        );
 
         Ok(())
     }
 
     #[test]
     fn test_finalize_verification_transfer_token_merge() -> ProgramResult {
+        zero_program_account!(mut metrics, MetricsAccount);
+        zero_program_account!(mut verification_history, VerificationHistoryAccount);
         finalize_send_test!(
             USDC_TOKEN_ID,
             0,
@@ -3566,7 +8067,296 @@ mod tests {
         program_token_account_info!(fee_collector_token, FeeCollectorAccount, USDC_TOKEN_ID);
 
         test_account_info!(any, 0);
-        account_info!(spl, spl_token::id(), vec![]);
+        test_account_info!(recipient_preferences, 0);
+        test_pda_account_info!(quarantine, QuarantineAccount, None);
+        program_token_account_info!(quarantine_token, QuarantineAccount, USDC_TOKEN_ID);
+        account_info!(spl, spl_token::id(), vec![]);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut metadata_queue, MetadataQueueAccount);
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Finalized);
+            v_acc.set_is_verified(&ElusivOption::Some(true));
+        }
+
+        // For merges (zero-amount) the recipient key is ignored
+        account_info!(r, Pubkey::new_unique());
+        assert_eq!(
+            finalize_verification_transfer_token(
+                &fee_payer,
+                &fee_payer_token,
+                &r,
+                &r,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &any,
+                &mut commitment_queue,
+                &mut metadata_queue,
+                &v_acc,
+                &n_pda,
+                &spl,
+                &any,
+                &any,
+                &recipient_preferences,
+                &quarantine,
+                &quarantine_token,
+                &mut metrics,
+                &mut verification_history,
+                &governor,
+                &any,
+                &any,
+                &any,
+                0,
+                0,
+            ),
+            Ok(())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_forfeiture_timeout_reached() {
+        assert!(!is_forfeiture_timeout_reached(0, 0));
+        assert!(!is_forfeiture_timeout_reached(
+            100,
+            100 + VERIFICATION_FORFEITURE_TIMEOUT - 1
+        ));
+        assert!(is_forfeiture_timeout_reached(
+            100,
+            100 + VERIFICATION_FORFEITURE_TIMEOUT
+        ));
+        assert!(!is_forfeiture_timeout_reached(u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn test_is_cancellation_timeout_reached() {
+        assert!(!is_cancellation_timeout_reached(0, 0));
+        assert!(!is_cancellation_timeout_reached(
+            100,
+            100 + VERIFICATION_CANCELLATION_TIMEOUT - 1
+        ));
+        assert!(is_cancellation_timeout_reached(
+            100,
+            100 + VERIFICATION_CANCELLATION_TIMEOUT
+        ));
+        assert!(!is_cancellation_timeout_reached(u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn test_cancel_verification() {
+        test_account_info!(fee_payer, 0);
+        test_account_info!(other_fee_payer, 0);
+        test_account_info!(any, 0);
+        test_account_info!(v_acc, VerificationAccount::SIZE);
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_other_data(&VerificationAccountData {
+                fee_payer: RawU256::new(fee_payer.key.to_bytes()),
+                ..Default::default()
+            });
+            v_acc.set_state(&VerificationState::ProofSetup);
+        }
+
+        macro_rules! cancel {
+            ($fee_payer: expr) => {
+                cancel_verification(
+                    $fee_payer, &any, &any, &any, &any, &v_acc, &any, &any, &any, 0,
+                )
+            };
+        }
+
+        // Invalid fee_payer
+        assert_eq!(
+            cancel!(&other_fee_payer),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Timeout not reached (the current slot in tests is `0`)
+        assert_eq!(
+            cancel!(&fee_payer),
+            Err(ElusivError::CancellationTimeoutNotReached.into())
+        );
+
+        // Verified proofs cannot be cancelled
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_is_verified(&ElusivOption::Some(true));
+        }
+        assert_eq!(
+            cancel!(&fee_payer),
+            Err(ElusivError::ComputationIsAlreadyFinished.into())
+        );
+
+        // Finalization already started
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_is_verified(&ElusivOption::None);
+            v_acc.set_state(&VerificationState::InsertNullifiers);
+        }
+        assert_eq!(
+            cancel!(&fee_payer),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "mainnet"))]
+    fn test_reset_verification_account() {
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            0,
+            public_inputs,
+            verification_acc_data,
+            _recipient_bytes,
+            _identifier_bytes,
+            _reference_bytes,
+            _finalize_data
+        );
+        let fee_payer_pk = {
+            let mut v_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+
+            // The fee has not been transferred yet (nothing to refund)
+            v_acc.set_state(&VerificationState::None);
+            Pubkey::new(&v_acc.get_other_data().fee_payer.skip_mr())
+        };
+
+        account_info!(authority, crate::ID, true);
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        account_info!(fee_payer, fee_payer_pk);
+        account_info!(other_fee_payer, Pubkey::new_unique());
+        test_account_info!(any, 0);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+
+        zero_program_account!(mut governor, GovernorAccount);
+
+        macro_rules! reset {
+            ($authority: expr, $fee_payer: expr) => {
+                reset_verification_account(
+                    $authority, $fee_payer, &any, &any, &any, &any, &governor, &v_acc, &n_pda,
+                    &any, &any, 0,
+                )
+            };
+        }
+
+        assert_eq!(
+            reset!(&invalid_authority, &fee_payer),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // The verification has been initialized after the last reset
+        assert_eq!(
+            reset!(&authority, &fee_payer),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        governor.set_reset_generation(&1);
+
+        // Invalid fee_payer
+        assert_eq!(
+            reset!(&authority, &other_fee_payer),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        let lamports = fee_payer.lamports() + v_acc.lamports() + n_pda.lamports();
+        reset!(&authority, &fee_payer).unwrap();
+        assert_eq!(fee_payer.lamports(), lamports);
+        assert_eq!(v_acc.lamports(), 0);
+        assert_eq!(n_pda.lamports(), 0);
+    }
+
+    #[test]
+    fn test_forfeiture_reimbursement_split() {
+        let data = VerificationAccountData {
+            commitment_hash_fee_token: 100,
+            proof_verification_fee: 1001,
+            ..Default::default()
+        };
+        assert_eq!(forfeiture_reimbursement_split(&data), (100 + 501, 500));
+
+        let data = VerificationAccountData {
+            commitment_hash_fee_token: 0,
+            proof_verification_fee: 0,
+            ..Default::default()
+        };
+        assert_eq!(forfeiture_reimbursement_split(&data), (0, 0));
+    }
+
+    #[test]
+    fn test_is_self_finalization_timeout_reached() {
+        assert!(!is_self_finalization_timeout_reached(0, 0));
+        assert!(!is_self_finalization_timeout_reached(
+            100,
+            100 + VERIFICATION_SELF_FINALIZATION_TIMEOUT - 1
+        ));
+        assert!(is_self_finalization_timeout_reached(
+            100,
+            100 + VERIFICATION_SELF_FINALIZATION_TIMEOUT
+        ));
+        assert!(!is_self_finalization_timeout_reached(u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_by_user() -> ProgramResult {
+        zero_program_account!(mut metrics, MetricsAccount);
+        zero_program_account!(mut verification_history, VerificationHistoryAccount);
+        zero_program_account!(mut governor, GovernorAccount);
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            10,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            _r,
+            _f,
+            _optional_fee_collector
+        );
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes), true);
+        apa_flag_account!(flagged, recipient.key);
+        account_info!(
+            unflagged,
+            crate::processor::utils::apa_flag_account(recipient.key),
+            vec![],
+            crate::processor::utils::WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+        let fee_payer_pk = Pubkey::new(
+            &VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, 0);
+        test_account_info!(any, 0);
+        test_pda_account_info!(quarantine, QuarantineAccount, None);
         test_pda_account_info!(
             n_pda,
             NullifierDuplicateAccount,
@@ -3582,35 +8372,183 @@ mod tests {
         {
             pda_account!(mut v_acc, VerificationAccount, v_acc);
             v_acc.set_state(&VerificationState::Finalized);
+            v_acc.set_identifier(&ElusivOption::Some(*identifier.key));
+        }
+
+        macro_rules! finalize_by_user {
+            ($identifier: expr) => {
+                finalize_by_user!($identifier, &unflagged)
+            };
+            ($identifier: expr, $apa_flag_account: expr) => {
+                finalize_verification_transfer_by_user(
+                    $identifier,
+                    &f,
+                    &recipient,
+                    &pool,
+                    &pool,
+                    &fee_collector,
+                    &fee_collector,
+                    &any,
+                    &mut commitment_queue,
+                    &mut metadata_queue,
+                    &v_acc,
+                    &n_pda,
+                    &any,
+                    &any,
+                    &quarantine,
+                    &quarantine,
+                    &mut metrics,
+                    &mut verification_history,
+                    &governor,
+                    &any,
+                    &any,
+                    $apa_flag_account,
+                    0,
+                    0,
+                )
+            };
+        }
+
+        // Identifier is not a signer
+        {
+            account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+            assert_eq!(
+                finalize_by_user!(&identifier),
+                Err(ProgramError::MissingRequiredSignature)
+            );
+        }
+
+        // Invalid identifier
+        {
+            account_info!(identifier, Pubkey::new_unique(), true);
+            assert_eq!(
+                finalize_by_user!(&identifier),
+                Err(ProgramError::MissingRequiredSignature)
+            );
+        }
+
+        // Timeout not reached
+        assert_eq!(
+            finalize_by_user!(&identifier),
+            Err(ElusivError::SelfFinalizationTimeoutNotReached.into())
+        );
+
+        // Flagged recipient (the flag is enforced independently of the executing warden's region)
+        assert_eq!(
+            finalize_by_user!(&identifier, &flagged),
+            Err(ElusivError::SelfFinalizationTimeoutNotReached.into())
+        );
+        governor.set_apa_enforcement(&ApaEnforcement::Block);
+        assert_eq!(
+            finalize_by_user!(&identifier, &flagged),
+            Err(ElusivError::ApaFlaggedRecipient.into())
+        );
+        assert_eq!(
+            finalize_by_user!(&identifier),
+            Err(ElusivError::SelfFinalizationTimeoutNotReached.into())
+        );
+        governor.set_apa_enforcement(&ApaEnforcement::Disabled);
+
+        // Invalid proof
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_is_verified(&ElusivOption::Some(false));
+        }
+        assert_eq!(
+            finalize_by_user!(&identifier),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // Invalid state
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
             v_acc.set_is_verified(&ElusivOption::Some(true));
+            v_acc.set_state(&VerificationState::InsertNullifiers);
         }
+        assert_eq!(
+            finalize_by_user!(&identifier),
+            Err(ElusivError::InvalidAccountState.into())
+        );
 
-        // For merges (zero-amount) the recipient key is ignored
-        account_info!(r, Pubkey::new_unique());
+        Ok(())
+    }
+
+    #[test]
+    fn test_transfer_send_payout() {
+        finalize_send_test!(
+            0,
+            LAMPORTS_PER_SOL,
+            0,
+            public_inputs,
+            _v_data,
+            recipient,
+            _identifier,
+            _reference,
+            _finalize_data,
+            _optional_fee_collector
+        );
+        account_info!(recipient, Pubkey::new_from_array(recipient));
+        test_pda_account_info!(quarantine, QuarantineAccount, None);
+        test_account_info!(any, 0);
+
+        fn transfer(destination: &AccountInfo, token: Token) -> ProgramResult {
+            **destination.try_borrow_mut_lamports()? += token.amount();
+            Ok(())
+        }
+
+        let token = Token::new(0, LAMPORTS_PER_SOL);
+        let recipient_lamports = recipient.lamports();
+        let quarantine_lamports = quarantine.lamports();
+
+        macro_rules! payout {
+            ($public_inputs: expr, $is_apa_quarantined: expr) => {
+                transfer_send_payout(
+                    &$public_inputs,
+                    token,
+                    &recipient,
+                    recipient.key,
+                    recipient.key,
+                    $is_apa_quarantined,
+                    !$is_apa_quarantined,
+                    &any,
+                    &quarantine,
+                    &quarantine,
+                    &any,
+                    None,
+                    &any,
+                    None,
+                    transfer,
+                )
+            };
+        }
+
+        // Timelocked payouts require a pending_release_account
         assert_eq!(
-            finalize_verification_transfer_token(
-                &fee_payer,
-                &fee_payer_token,
-                &r,
-                &r,
-                &pool,
-                &pool_token,
-                &fee_collector,
-                &fee_collector_token,
-                &any,
-                &mut commitment_queue,
-                &mut metadata_queue,
-                &v_acc,
-                &n_pda,
-                &spl,
-                &any,
-                &any,
-                0
+            payout!(
+                SendPublicInputs {
+                    unlock_timestamp: 1,
+                    ..public_inputs.clone()
+                },
+                false
             ),
-            Ok(())
+            Err(ElusivError::TimelockNotReached.into())
         );
+        assert_eq!(recipient.lamports(), recipient_lamports);
 
-        Ok(())
+        // APA-flagged payouts flow to the quarantine
+        assert_eq!(payout!(public_inputs, true), Ok(()));
+        assert_eq!(recipient.lamports(), recipient_lamports);
+        assert_eq!(
+            quarantine.lamports(),
+            quarantine_lamports + LAMPORTS_PER_SOL
+        );
+
+        assert_eq!(payout!(public_inputs, false), Ok(()));
+        assert_eq!(recipient.lamports(), recipient_lamports + LAMPORTS_PER_SOL);
+        assert_eq!(
+            quarantine.lamports(),
+            quarantine_lamports + LAMPORTS_PER_SOL
+        );
     }
 
     #[test]
@@ -3655,6 +8593,7 @@ mod tests {
     #[test]
     fn test_check_join_split_public_inputs() {
         parent_account!(mut storage, StorageAccount);
+        parent_account!(storage1, StorageAccount);
         parent_account!(n_account, NullifierAccount);
 
         let commitments_count = 1000;
@@ -3760,7 +8699,7 @@ mod tests {
             assert_eq!(
                 check_join_split_public_inputs(
                     &public_inputs,
-                    &storage,
+                    [&storage, &storage1],
                     [&n_account, &n_account],
                     &[0, 1]
                 ),
@@ -3783,7 +8722,7 @@ mod tests {
                         },
                     ];
                 }),
-                &storage,
+                [&storage, &storage1],
                 [&n_account, &n_account],
                 &[0, 0]
             ),
@@ -3794,7 +8733,7 @@ mod tests {
         assert_eq!(
             check_join_split_public_inputs(
                 &valid_inputs,
-                &storage,
+                [&storage, &storage1],
                 [&n_account, &n_account],
                 &[0, 1]
             ),
@@ -3828,7 +8767,7 @@ mod tests {
             assert_eq!(
                 check_join_split_public_inputs(
                     &public_inputs,
-                    &storage,
+                    [&storage, &storage1],
                     [&n_account, &n_account],
                     &[0, 1]
                 ),
@@ -3858,7 +8797,7 @@ mod tests {
                     inputs.input_commitments[0].nullifier_hash =
                         RawU256::new(u256_from_str_skip_mr("1"));
                 }),
-                &storage,
+                [&storage, &storage1],
                 [&n_account, &n_account],
                 &[0, 1]
             ),
@@ -3866,6 +8805,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_check_join_split_public_inputs_mt_shards() {
+        parent_account!(mut storage, StorageAccount);
+        parent_account!(mut storage1, StorageAccount);
+        parent_account!(n_account, NullifierAccount);
+
+        storage.set_node(&empty_root_raw().reduce(), 0, 0).unwrap();
+
+        // Active MT of MT-shard `1`
+        let shard_root = RawU256::new(u256_from_str_skip_mr("7"));
+        storage1.set_mt_shard(&1);
+        storage1.set_trees_count(&sharded_mt_index(1, 0));
+        storage1.set_node(&shard_root.reduce(), 0, 0).unwrap();
+
+        let inputs = JoinSplitPublicInputs {
+            input_commitments: vec![InputCommitment {
+                root: Some(shard_root),
+                nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+            }],
+            output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+            recent_commitment_index: 0,
+            fee_version: 0,
+            amount: 1000,
+            fee: 0,
+            optional_fee: OptionalFee::default(),
+            token_id: 0,
+            metadata: CommitmentMetadata::default(),
+        };
+
+        // The root is verified against the active MT of the MT-shard specified by the tree-index
+        assert_eq!(
+            check_join_split_public_inputs(
+                &inputs,
+                [&storage, &storage1],
+                [&n_account, &n_account],
+                &[sharded_mt_index(1, 0), 1]
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check_join_split_public_inputs(
+                &inputs,
+                [&storage, &storage1],
+                [&n_account, &n_account],
+                &[0, 1]
+            ),
+            Err(ElusivError::InvalidMerkleRoot.into())
+        );
+
+        // Closed MT of MT-shard `1`
+        assert_eq!(
+            check_join_split_public_inputs(
+                &inputs,
+                [&storage, &storage1],
+                [&n_account, &n_account],
+                &[sharded_mt_index(1, 1), 1]
+            ),
+            Err(ElusivError::InvalidMerkleRoot.into())
+        );
+
+        // Roots of both MT-shards
+        let inputs = mutate(&inputs, |inputs| {
+            inputs.input_commitments.push(InputCommitment {
+                root: Some(empty_root_raw()),
+                nullifier_hash: RawU256::new(u256_from_str_skip_mr("2")),
+            });
+        });
+        assert_eq!(
+            check_join_split_public_inputs(
+                &inputs,
+                [&storage, &storage1],
+                [&n_account, &n_account],
+                &[sharded_mt_index(1, 0), 0]
+            ),
+            Ok(())
+        );
+    }
+
     struct StubInstruction(u8, Option<Vec<u8>>, Pubkey);
 
     impl From<StubInstruction> for Instruction {
@@ -3999,6 +9016,34 @@ mod tests {
             );
         }
 
+        // [ElusivInstruction::FinalizeVerificationTransferByUser] replaces the transfer instruction
+        for uses_lamports in [true, false] {
+            assert_eq!(
+                enforce_finalize_send_instructions_inner(
+                    &TestInstructionsSysvar {
+                        current_index: Some(0),
+                        instructions: vec![
+                            StubInstruction(
+                                ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX,
+                                Some(vec![verification_account_index]),
+                                crate::id()
+                            )
+                            .into(),
+                            StubInstruction(
+                                ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_BY_USER_INDEX,
+                                Some(vec![verification_account_index]),
+                                crate::id()
+                            )
+                            .into(),
+                        ],
+                    },
+                    uses_lamports,
+                    verification_account_index,
+                ),
+                Ok(())
+            );
+        }
+
         // Missing [ElusivInstruction::FinalizeVerificationSend]
         // Note: we test this by shifting the current-index to 1
         assert_eq!(
@@ -4286,6 +9331,170 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_enforce_apa_flag() {
+        use crate::processor::utils::{
+            APA_FLAG_ACCOUNT_SEED, APA_FLAG_REGION_SCOPE_OFFSET, APA_FLAG_TOKEN_CONSTRAINT_OFFSET,
+            WARDEN_ATTESTATION_ACCOUNT_SEED, WARDEN_NETWORK_PROGRAM_ID,
+        };
+
+        let recipient = Pubkey::new_unique();
+        let (pk, bump) = Pubkey::find_program_address(
+            &[APA_FLAG_ACCOUNT_SEED, &recipient.to_bytes()],
+            &WARDEN_NETWORK_PROGRAM_ID,
+        );
+        let mut data = vec![0; APA_FLAG_TOKEN_CONSTRAINT_OFFSET + 3];
+        data[0] = bump;
+        account_info!(
+            flag_account,
+            pk,
+            data.clone(),
+            WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+        data.resize(APA_FLAG_REGION_SCOPE_OFFSET, 0);
+        data.extend([1, 5]);
+        account_info!(
+            scoped_flag_account,
+            pk,
+            data,
+            WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+        account_info!(unopened, pk, vec![], WARDEN_NETWORK_PROGRAM_ID, false);
+        test_account_info!(invalid_account, 0);
+
+        let warden = Pubkey::new_unique();
+        let (pk, bump) = Pubkey::find_program_address(
+            &[WARDEN_ATTESTATION_ACCOUNT_SEED, &warden.to_bytes()],
+            &WARDEN_NETWORK_PROGRAM_ID,
+        );
+        account_info!(
+            attestation_account,
+            pk,
+            vec![bump, 0, 0, 0, 0, 0, 2, 1, 3],
+            WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+
+        zero_program_account!(mut governor, GovernorAccount);
+
+        // Disabled: the flag account is not read
+        for account in [&flag_account, &unopened, &invalid_account] {
+            assert_eq!(
+                enforce_apa_flag(&governor, &recipient, account, 0, &warden, &invalid_account,),
+                Ok(false)
+            );
+        }
+
+        governor.set_apa_enforcement(&ApaEnforcement::Block);
+        assert_eq!(
+            enforce_apa_flag(
+                &governor,
+                &recipient,
+                &flag_account,
+                0,
+                &warden,
+                &invalid_account,
+            ),
+            Err(ElusivError::ApaFlaggedRecipient.into())
+        );
+        assert_eq!(
+            enforce_apa_flag(
+                &governor,
+                &recipient,
+                &unopened,
+                0,
+                &warden,
+                &invalid_account,
+            ),
+            Ok(false)
+        );
+        assert_eq!(
+            enforce_apa_flag(
+                &governor,
+                &recipient,
+                &invalid_account,
+                0,
+                &warden,
+                &invalid_account,
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        governor.set_apa_enforcement(&ApaEnforcement::Quarantine);
+        assert_eq!(
+            enforce_apa_flag(
+                &governor,
+                &recipient,
+                &flag_account,
+                0,
+                &warden,
+                &invalid_account,
+            ),
+            Ok(true)
+        );
+        assert_eq!(
+            enforce_apa_flag(
+                &governor,
+                &recipient,
+                &unopened,
+                0,
+                &warden,
+                &invalid_account,
+            ),
+            Ok(false)
+        );
+
+        // Region scope
+        assert_eq!(
+            enforce_apa_flag(
+                &governor,
+                &recipient,
+                &scoped_flag_account,
+                0,
+                &warden,
+                &attestation_account
+            ),
+            Ok(false)
+        );
+        assert_eq!(
+            enforce_apa_flag(
+                &governor,
+                &recipient,
+                &flag_account,
+                0,
+                &warden,
+                &attestation_account
+            ),
+            Ok(true)
+        );
+
+        // Wardens without a (valid) attested region are within any region
+        assert_eq!(
+            enforce_apa_flag(
+                &governor,
+                &recipient,
+                &scoped_flag_account,
+                0,
+                &warden,
+                &invalid_account
+            ),
+            Ok(true)
+        );
+        assert_eq!(
+            enforce_apa_flag(
+                &governor,
+                &recipient,
+                &scoped_flag_account,
+                0,
+                &Pubkey::new_unique(),
+                &attestation_account
+            ),
+            Ok(true)
+        );
+    }
+
     #[test]
     fn test_memo_program_id() {
         assert_eq!(SPL_MEMO_PROGRAM_ID, spl_memo::ID);
@@ -0,0 +1,358 @@
+use super::utils::{
+    open_pda_account_with_associated_pubkey, transfer_token_from_pda, verify_program_token_account,
+};
+use crate::error::ElusivError;
+use crate::event::ElusivEvent;
+use crate::macros::{guard, pda_account};
+use crate::state::governor::QuarantineAccount;
+use crate::state::program_account::PDAAccount;
+use crate::state::recipient::{RecipientPreferenceAccount, TOKEN_COUNT};
+use crate::token::{elusiv_token, unpack_token_state, verify_token_account, Token};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Sets the payout preferences of a recipient (opens the [`RecipientPreferenceAccount`] if required)
+pub fn set_recipient_preferences<'a>(
+    recipient: &AccountInfo<'a>,
+    recipient_preference_account: &AccountInfo<'a>,
+
+    denies_all_payouts: bool,
+    denied_tokens: [bool; TOKEN_COUNT],
+) -> ProgramResult {
+    if recipient_preference_account.data_len() == 0 {
+        open_pda_account_with_associated_pubkey::<RecipientPreferenceAccount>(
+            &crate::id(),
+            recipient,
+            recipient_preference_account,
+            recipient.key,
+            None,
+            None,
+        )?;
+    }
+
+    pda_account!(
+        mut recipient_preference_account,
+        RecipientPreferenceAccount,
+        recipient_preference_account
+    );
+    recipient_preference_account.set_denies_all_payouts(&denies_all_payouts);
+    for (token_id, denied) in denied_tokens.iter().enumerate() {
+        recipient_preference_account.set_denied_tokens(token_id, denied);
+    }
+
+    Ok(())
+}
+
+/// Releases all quarantined funds of a specific token to the recipient
+pub fn release_quarantined_payout<'a>(
+    _recipient: &AccountInfo<'a>,
+    recipient_account: &AccountInfo<'a>,
+    recipient_preference_account: &mut RecipientPreferenceAccount,
+    quarantine: &AccountInfo<'a>,
+    quarantine_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+
+    token_id: u16,
+) -> ProgramResult {
+    elusiv_token(token_id)?;
+    verify_program_token_account(quarantine, quarantine_account, token_id)?;
+
+    let amount = recipient_preference_account.get_quarantined_amounts(token_id as usize);
+    guard!(amount > 0, ElusivError::InvalidAmount);
+    recipient_preference_account.set_quarantined_amounts(token_id as usize, &0);
+
+    // `quarantine` transfers `amount` to `recipient_account` (token)
+    transfer_token_from_pda::<QuarantineAccount>(
+        quarantine,
+        quarantine_account,
+        recipient_account,
+        token_program,
+        Token::new(token_id, amount),
+        None,
+        None,
+    )
+}
+
+/// Returns whether `recipient` accepts payouts of the token `token_id`
+///
+/// # Note
+///
+/// A non-existing [`RecipientPreferenceAccount`] accepts all payouts.
+pub fn recipient_accepts_payout(
+    recipient_preference_account: &AccountInfo,
+    recipient: &Pubkey,
+    token_id: u16,
+) -> Result<bool, ProgramError> {
+    guard!(
+        RecipientPreferenceAccount::pubkey_cached(
+            recipient_preference_account,
+            Some(*recipient),
+            None
+        )? == *recipient_preference_account.key,
+        ElusivError::InvalidAccount
+    );
+
+    if recipient_preference_account.data_len() == 0 {
+        return Ok(true);
+    }
+
+    pda_account!(
+        recipient_preference_account,
+        RecipientPreferenceAccount,
+        recipient_preference_account
+    );
+    Ok(recipient_preference_account.accepts_payout(token_id))
+}
+
+/// Returns the wallet whose [`RecipientPreferenceAccount`] applies to a payout of `token_id` to `recipient_address`
+///
+/// # Note
+///
+/// - Payouts to arbitrary token accounts (`recipient_is_associated_token_account == false`) use the preferences of the token account's owner, since the token account itself can not sign.
+/// - An invalid token account (which receives no payout) falls back to `recipient_address`.
+pub fn payout_preference_wallet(
+    recipient: &AccountInfo,
+    recipient_address: &Pubkey,
+    token_id: u16,
+    recipient_is_associated_token_account: bool,
+) -> Result<Pubkey, ProgramError> {
+    if token_id == 0 || recipient_is_associated_token_account {
+        return Ok(*recipient_address);
+    }
+
+    guard!(
+        recipient.key == recipient_address,
+        ElusivError::InvalidRecipient
+    );
+    if verify_token_account(recipient, token_id) != Ok(true) {
+        return Ok(*recipient_address);
+    }
+
+    Ok(unpack_token_state::<spl_token_2022::state::Account>(recipient)?.owner)
+}
+
+/// Records a payout that has been transferred into the [`QuarantineAccount`] instead of to the recipient
+pub fn record_quarantined_payout(
+    recipient_preference_account: &AccountInfo,
+    recipient: &Pubkey,
+    token_id: u16,
+    amount: u64,
+) -> ProgramResult {
+    pda_account!(
+        mut recipient_preference_account,
+        RecipientPreferenceAccount,
+        recipient_preference_account
+    );
+
+    let quarantined_amount = recipient_preference_account
+        .get_quarantined_amounts(token_id as usize)
+        .checked_add(amount)
+        .ok_or(ElusivError::InvalidAmount)?;
+    recipient_preference_account.set_quarantined_amounts(token_id as usize, &quarantined_amount);
+
+    ElusivEvent::PayoutQuarantined {
+        recipient: *recipient,
+        token_id,
+        amount,
+    }
+    .emit();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{
+        account_info, program_token_account_info, test_account_info, test_pda_account_info,
+        zero_program_account,
+    };
+    use crate::state::program_account::{ProgramAccount, SizedAccount};
+    use crate::token::{LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+    use solana_program::program_pack::Pack;
+
+    #[test]
+    fn test_recipient_accepts_payout() {
+        let recipient = Pubkey::new_unique();
+        let (pda, bump) = RecipientPreferenceAccount::find_with_pubkey(recipient, None);
+
+        // Invalid PDA
+        test_account_info!(invalid, 0);
+        assert_eq!(
+            recipient_accepts_payout(&invalid, &recipient, 0),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Non-existing account
+        account_info!(empty, pda, vec![]);
+        assert_eq!(recipient_accepts_payout(&empty, &recipient, 0), Ok(true));
+
+        let mut data = vec![0; RecipientPreferenceAccount::SIZE];
+        data[0] = bump;
+        RecipientPreferenceAccount::new(&mut data)
+            .unwrap()
+            .set_denied_tokens(1, &true);
+        account_info!(preferences, pda, data);
+
+        // Invalid stored bump
+        let mut data = vec![0; RecipientPreferenceAccount::SIZE];
+        data[0] = bump.wrapping_add(1);
+        account_info!(invalid_bump, pda, data);
+        assert!(recipient_accepts_payout(&invalid_bump, &recipient, 0).is_err());
+
+        assert_eq!(
+            recipient_accepts_payout(&preferences, &recipient, 0),
+            Ok(true)
+        );
+        assert_eq!(
+            recipient_accepts_payout(&preferences, &recipient, 1),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_record_quarantined_payout() -> ProgramResult {
+        let recipient = Pubkey::new_unique();
+        test_account_info!(preferences, RecipientPreferenceAccount::SIZE);
+
+        record_quarantined_payout(&preferences, &recipient, 1, 100).unwrap();
+        record_quarantined_payout(&preferences, &recipient, 1, 23).unwrap();
+
+        pda_account!(preferences, RecipientPreferenceAccount, preferences);
+        assert_eq!(preferences.get_quarantined_amounts(0), 0);
+        assert_eq!(preferences.get_quarantined_amounts(1), 123);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_release_quarantined_payout() {
+        zero_program_account!(mut preferences, RecipientPreferenceAccount);
+        test_account_info!(any, 0);
+        test_pda_account_info!(quarantine, QuarantineAccount, None);
+        program_token_account_info!(quarantine_token, QuarantineAccount, USDC_TOKEN_ID);
+        account_info!(spl, spl_token::id(), vec![]);
+
+        // Invalid quarantine_account
+        preferences.set_quarantined_amounts(USDC_TOKEN_ID as usize, &123);
+        assert_eq!(
+            release_quarantined_payout(
+                &any,
+                &any,
+                &mut preferences,
+                &quarantine,
+                &any,
+                &spl,
+                USDC_TOKEN_ID
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(
+            release_quarantined_payout(
+                &any,
+                &any,
+                &mut preferences,
+                &quarantine,
+                &quarantine_token,
+                &spl,
+                LAMPORTS_TOKEN_ID
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Nothing quarantined
+        assert_eq!(
+            release_quarantined_payout(
+                &any,
+                &any,
+                &mut preferences,
+                &quarantine,
+                &quarantine,
+                &spl,
+                LAMPORTS_TOKEN_ID
+            ),
+            Err(ElusivError::InvalidAmount.into())
+        );
+
+        assert_eq!(
+            release_quarantined_payout(
+                &any,
+                &any,
+                &mut preferences,
+                &quarantine,
+                &quarantine_token,
+                &spl,
+                USDC_TOKEN_ID
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            preferences.get_quarantined_amounts(USDC_TOKEN_ID as usize),
+            0
+        );
+
+        // Already released
+        assert_eq!(
+            release_quarantined_payout(
+                &any,
+                &any,
+                &mut preferences,
+                &quarantine,
+                &quarantine_token,
+                &spl,
+                USDC_TOKEN_ID
+            ),
+            Err(ElusivError::InvalidAmount.into())
+        );
+    }
+
+    #[test]
+    fn test_payout_preference_wallet() {
+        let recipient = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        test_account_info!(any, 0);
+
+        // Lamports and associated token accounts
+        assert_eq!(
+            payout_preference_wallet(&any, &recipient, LAMPORTS_TOKEN_ID, false),
+            Ok(recipient)
+        );
+        assert_eq!(
+            payout_preference_wallet(&any, &recipient, USDC_TOKEN_ID, true),
+            Ok(recipient)
+        );
+
+        // Mismatched recipient
+        assert_eq!(
+            payout_preference_wallet(&any, &recipient, USDC_TOKEN_ID, false),
+            Err(ElusivError::InvalidRecipient.into())
+        );
+
+        // Invalid token account
+        account_info!(invalid, recipient, vec![]);
+        assert_eq!(
+            payout_preference_wallet(&invalid, &recipient, USDC_TOKEN_ID, false),
+            Ok(recipient)
+        );
+
+        // Token account owned by `wallet`
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(
+            spl_token::state::Account {
+                mint: elusiv_token(USDC_TOKEN_ID).unwrap().mint,
+                owner: wallet,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut data[..],
+        )
+        .unwrap();
+        account_info!(token_account, recipient, data, spl_token::id(), false);
+        assert_eq!(
+            payout_preference_wallet(&token_account, &recipient, USDC_TOKEN_ID, false),
+            Ok(wallet)
+        );
+    }
+}
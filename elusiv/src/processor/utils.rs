@@ -1,17 +1,30 @@
+use super::ledger::tracked_transfer;
+use crate::bytes::{BorshSerDeSized, ElusivOption};
 use crate::error::ElusivError;
-use crate::macros::guard;
-use crate::state::program_account::{PDAAccount, PDAOffset};
-use crate::token::{elusiv_token, Lamports, SPLToken, Token};
+use crate::macros::{guard, pda_account};
+use crate::state::governor::FeatureFlagsAccount;
+use crate::state::program_account::{
+    PDAAccount, PDAAccountData, PDAOffset, ProgramAccount, SizedAccount,
+};
+use crate::state::warden_nonce::WardenNonceAccount;
+use crate::state::warden_usage::WardenUsageAccount;
+use crate::token::{elusiv_token, Lamports, SPLToken, Token, LAMPORTS_TOKEN_ID};
+use crate::types::U256;
+use borsh::BorshSerialize;
 use solana_program::instruction::Instruction;
+use solana_program::msg;
 use solana_program::program::invoke;
 use solana_program::program_pack::Pack;
 use solana_program::pubkey::Pubkey;
 use solana_program::sysvar::instructions;
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError, rent::Rent,
-    sysvar::Sysvar,
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, program::set_return_data,
+    program_error::ProgramError, rent::Rent, sysvar::Sysvar,
 };
-use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+#[cfg(feature = "elusiv-client")]
+use borsh::BorshDeserialize;
 
 pub use elusiv_utils::*;
 
@@ -56,14 +69,20 @@ pub fn transfer_token<'a>(
         Token::Lamports(lamports) => {
             transfer_with_system_program(source, destination, token_program, lamports.0)
         }
-        Token::SPLToken(SPLToken { amount, .. }) => transfer_with_token_program(
-            source,
-            source_token_account,
-            destination,
-            token_program,
-            amount,
-            None,
-        ),
+        Token::SPLToken(SPLToken { amount, .. }) => {
+            tracked_transfer(source_token_account, destination, token.token_id(), || {
+                transfer_with_token_program(
+                    source,
+                    source_token_account,
+                    destination,
+                    token_program,
+                    None,
+                    token.token_id(),
+                    amount,
+                    None,
+                )
+            })
+        }
     }
 }
 
@@ -75,6 +94,53 @@ pub fn transfer_token_from_pda<'a, T: PDAAccount>(
     token: Token,
     pda_pubkey: Option<Pubkey>,
     pda_offset: PDAOffset,
+) -> ProgramResult {
+    transfer_token_from_pda_inner::<T>(
+        source,
+        source_token_account,
+        destination,
+        token_program,
+        None,
+        token,
+        pda_pubkey,
+        pda_offset,
+    )
+}
+
+/// [`transfer_token_from_pda`] supplying the `mint` (required by Token-2022 mints with the transfer-fee extension)
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_token_from_pda_with_mint<'a, T: PDAAccount>(
+    source: &AccountInfo<'a>,
+    source_token_account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    token: Token,
+    pda_pubkey: Option<Pubkey>,
+    pda_offset: PDAOffset,
+) -> ProgramResult {
+    transfer_token_from_pda_inner::<T>(
+        source,
+        source_token_account,
+        destination,
+        token_program,
+        Some(mint),
+        token,
+        pda_pubkey,
+        pda_offset,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn transfer_token_from_pda_inner<'a, T: PDAAccount>(
+    source: &AccountInfo<'a>,
+    source_token_account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    mint: Option<&AccountInfo<'a>>,
+    token: Token,
+    pda_pubkey: Option<Pubkey>,
+    pda_offset: PDAOffset,
 ) -> ProgramResult {
     guard!(*source.owner == crate::ID, ElusivError::InvalidAccount);
 
@@ -87,70 +153,132 @@ pub fn transfer_token_from_pda<'a, T: PDAAccount>(
             let seeds = T::signers_seeds(pda_pubkey, pda_offset, bump);
             let signers_seeds = signers_seeds!(seeds);
 
-            transfer_with_token_program(
-                source,
-                source_token_account,
-                destination,
-                token_program,
-                amount,
-                Some(&[&signers_seeds]),
-            )
+            tracked_transfer(source_token_account, destination, token.token_id(), || {
+                transfer_with_token_program(
+                    source,
+                    source_token_account,
+                    destination,
+                    token_program,
+                    mint,
+                    token.token_id(),
+                    amount,
+                    Some(&[&signers_seeds]),
+                )
+            })
         }
     }
 }
 
+/// [`elusiv_utils::transfer_with_system_program`] recorded by the [`super::ledger`]
+pub fn transfer_with_system_program<'a>(
+    source: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    lamports: u64,
+) -> ProgramResult {
+    tracked_transfer(source, destination, LAMPORTS_TOKEN_ID, || {
+        elusiv_utils::transfer_with_system_program(source, destination, system_program, lamports)
+    })
+}
+
+/// [`elusiv_utils::transfer_lamports_from_pda_checked`] recorded by the [`super::ledger`]
+pub fn transfer_lamports_from_pda_checked<'a>(
+    pda: &AccountInfo<'a>,
+    recipient: &AccountInfo<'a>,
+    lamports: u64,
+) -> ProgramResult {
+    tracked_transfer(pda, recipient, LAMPORTS_TOKEN_ID, || {
+        elusiv_utils::transfer_lamports_from_pda_checked(pda, recipient, lamports)
+    })
+}
+
+/// Guards that the (rent-exempt) `pool` can pay out `lamports` before any funds are moved
+///
+/// # Note
+///
+/// With delegated pool lamports the missing liquidity can be withdrawn by anyone through `UndelegatePoolLamports`.
+pub fn verify_pool_liquidity(pool: &AccountInfo, lamports: u64) -> ProgramResult {
+    let rent = if cfg!(test) {
+        0
+    } else {
+        Rent::get()?.minimum_balance(pool.data_len())
+    };
+    guard!(
+        pool.lamports().saturating_sub(rent) >= lamports,
+        ElusivError::PoolLiquidityDepleted
+    );
+
+    Ok(())
+}
+
+/// Transfers SPL-Token or Token-2022 tokens
+///
+/// # Note
+///
+/// For Token-2022 tokens `TransferChecked` is used, if the `mint` is supplied.
+#[allow(clippy::too_many_arguments, deprecated)]
 fn transfer_with_token_program<'a>(
     source: &AccountInfo<'a>,
     source_token_account: &AccountInfo<'a>,
     destination_token_account: &AccountInfo<'a>,
     token_program: &AccountInfo<'a>,
+    mint: Option<&AccountInfo<'a>>,
+    token_id: u16,
     amount: u64,
     signers_seeds: Option<&[&[&[u8]]]>,
 ) -> ProgramResult {
+    let token = elusiv_token(token_id)?;
     guard!(
-        *token_program.key == spl_token::ID,
+        *token_program.key == token.token_program,
         ElusivError::InvalidAccount
     );
 
     guard!(
-        *source_token_account.owner == spl_token::ID,
+        *source_token_account.owner == token.token_program,
         ElusivError::InvalidAccount
     ); // redundant
     guard!(
-        *destination_token_account.owner == spl_token::ID,
+        *destination_token_account.owner == token.token_program,
         ElusivError::InvalidAccount
     );
 
-    let instruction = spl_token::instruction::transfer(
-        &spl_token::id(),
-        source_token_account.key,
-        destination_token_account.key,
-        source.key,
-        &[source.key],
-        amount,
-    )?;
+    let mut account_infos = vec![
+        source.clone(),
+        source_token_account.clone(),
+        destination_token_account.clone(),
+        token_program.clone(),
+    ];
+
+    let instruction = match mint.filter(|_| token.token_program == spl_token_2022::ID) {
+        Some(mint) => {
+            guard!(*mint.key == token.mint, ElusivError::InvalidAccount);
+            account_infos.push(mint.clone());
+
+            spl_token_2022::instruction::transfer_checked(
+                token_program.key,
+                source_token_account.key,
+                mint.key,
+                destination_token_account.key,
+                source.key,
+                &[source.key],
+                amount,
+                token.decimals,
+            )?
+        }
+        None => spl_token_2022::instruction::transfer(
+            token_program.key,
+            source_token_account.key,
+            destination_token_account.key,
+            source.key,
+            &[source.key],
+            amount,
+        )?,
+    };
 
     if let Some(signers_seeds) = signers_seeds {
-        solana_program::program::invoke_signed(
-            &instruction,
-            &[
-                source.clone(),
-                source_token_account.clone(),
-                destination_token_account.clone(),
-                token_program.clone(),
-            ],
-            signers_seeds,
-        )
+        solana_program::program::invoke_signed(&instruction, &account_infos, signers_seeds)
     } else {
-        solana_program::program::invoke(
-            &instruction,
-            &[
-                source.clone(),
-                source_token_account.clone(),
-                destination_token_account.clone(),
-                token_program.clone(),
-            ],
-        )
+        invoke(&instruction, &account_infos)
     }
 }
 
@@ -167,7 +295,7 @@ pub fn create_associated_token_account<'a>(
             payer.key,
             wallet_account.key,
             &elusiv_token(token_id)?.mint,
-            &spl_token::ID,
+            &elusiv_token(token_id)?.token_program,
         ),
         &[
             payer.clone(),
@@ -182,9 +310,11 @@ pub fn program_token_account_address<A: PDAAccount>(
     token_id: u16,
     offset: PDAOffset,
 ) -> Result<Pubkey, ProgramError> {
-    Ok(get_associated_token_address(
+    let token = elusiv_token(token_id)?;
+    Ok(get_associated_token_address_with_program_id(
         &A::find(offset).0,
-        &elusiv_token(token_id)?.mint,
+        &token.mint,
+        &token.token_program,
     ))
 }
 
@@ -199,7 +329,12 @@ pub fn verify_program_token_account(
             ElusivError::InvalidAccount
         );
     } else {
-        let pubkey = get_associated_token_address(owner_pda.key, &elusiv_token(token_id)?.mint);
+        let token = elusiv_token(token_id)?;
+        let pubkey = get_associated_token_address_with_program_id(
+            owner_pda.key,
+            &token.mint,
+            &token.token_program,
+        );
         guard!(pubkey == *token_account.key, ElusivError::InvalidAccount);
     }
 
@@ -224,15 +359,771 @@ pub fn spl_token_account_rent() -> Result<Lamports, ProgramError> {
     ))
 }
 
+pub fn current_timestamp() -> Result<u64, ProgramError> {
+    #[cfg(test)]
+    {
+        Ok(0)
+    }
+
+    #[cfg(not(test))]
+    {
+        let clock = Clock::get()?;
+        clock.unix_timestamp.try_into().or(Err(MATH_ERR))
+    }
+}
+
+pub fn current_epoch() -> Result<u64, ProgramError> {
+    #[cfg(test)]
+    {
+        Ok(0)
+    }
+
+    #[cfg(not(test))]
+    {
+        Ok(Clock::get()?.epoch)
+    }
+}
+
+pub fn current_slot() -> Result<u64, ProgramError> {
+    #[cfg(test)]
+    {
+        Ok(0)
+    }
+
+    #[cfg(not(test))]
+    {
+        Ok(Clock::get()?.slot)
+    }
+}
+
+pub const WARDEN_NETWORK_PROGRAM_ID: Pubkey = crate::macros::program_id!("elusiv-warden-network");
+
+/// PDA-seed of the `BasicWardenMapAccount` of the Elusiv Warden Network
+const BASIC_WARDEN_MAP_ACCOUNT_SEED: &[u8] = b"BasicWardenMap";
+
+/// PDA-seed of the `WardenAttestationAccount` of the Elusiv Warden Network
+pub(crate) const WARDEN_ATTESTATION_ACCOUNT_SEED: &[u8] = b"WardenAttestation";
+
+/// Offset of the `level` in a `WardenAttestationAccount` (after the `PDAAccountData` and the `warden_id`)
+const WARDEN_ATTESTATION_LEVEL_OFFSET: usize = PDAAccountData::SIZE + 4;
+
+/// Offset of the `region` (an `ElusivOption<WardenRegion>`) in a `WardenAttestationAccount` (after the `level`)
+const WARDEN_ATTESTATION_REGION_OFFSET: usize = WARDEN_ATTESTATION_LEVEL_OFFSET + 1;
+
+/// PDA-seed of the `ApaFlagAccount` of the Elusiv Warden Network
+pub(crate) const APA_FLAG_ACCOUNT_SEED: &[u8] = b"ApaFlag";
+
+/// Offset of the `token_constraint` (an `ElusivOption<u16>`) in an `ApaFlagAccount` (after the `PDAAccountData` and the `level`)
+pub(crate) const APA_FLAG_TOKEN_CONSTRAINT_OFFSET: usize = PDAAccountData::SIZE + 1;
+
+/// Offset of the `region_scope` (an `ElusivOption<WardenRegion>`) in an `ApaFlagAccount` (after the `token_constraint` and the `proposal_id`)
+pub(crate) const APA_FLAG_REGION_SCOPE_OFFSET: usize = APA_FLAG_TOKEN_CONSTRAINT_OFFSET + 3 + 4;
+
+/// Verifies that `account` is the Elusiv Warden Network PDA with the seeds `seed` and `warden`
+fn verify_warden_network_account(
+    seed: &[u8],
+    warden: &Pubkey,
+    account: &AccountInfo,
+) -> ProgramResult {
+    guard!(
+        *account.owner == WARDEN_NETWORK_PROGRAM_ID,
+        ElusivError::InvalidAccount
+    );
+    guard!(!account.data_is_empty(), ElusivError::InvalidAccount);
+
+    let bump = account.data.borrow()[0];
+    let expected = Pubkey::create_program_address(
+        &[seed, &warden.to_bytes(), &[bump]],
+        &WARDEN_NETWORK_PROGRAM_ID,
+    )
+    .or(Err(ElusivError::InvalidAccount))?;
+    guard!(expected == *account.key, ElusivError::InvalidAccount);
+
+    Ok(())
+}
+
+/// Verifies that `warden` is registered as a basic warden in the Elusiv Warden Network
+pub fn verify_basic_warden(warden: &Pubkey, warden_map_account: &AccountInfo) -> ProgramResult {
+    verify_warden_network_account(BASIC_WARDEN_MAP_ACCOUNT_SEED, warden, warden_map_account)
+}
+
+/// Verifies that `warden` has been attested with at least `required_level` in the Elusiv Warden Network
+///
+/// # Note
+///
+/// - Only enforced with the `permissioned` feature, otherwise (and for a `required_level` of zero) any `warden_attestation_account` is accepted.
+/// - Attestations are only issued to registered wardens, so no separate `BasicWardenMapAccount` is required.
+pub fn verify_warden_attestation(
+    warden: &Pubkey,
+    warden_attestation_account: &AccountInfo,
+    required_level: u8,
+) -> ProgramResult {
+    if !cfg!(feature = "permissioned") || required_level == 0 {
+        return Ok(());
+    }
+
+    verify_warden_network_account(
+        WARDEN_ATTESTATION_ACCOUNT_SEED,
+        warden,
+        warden_attestation_account,
+    )
+    .or(Err(ElusivError::WardenNotAttested))?;
+
+    let level = warden_attestation_level(&warden_attestation_account.data.borrow());
+    guard!(level >= required_level, ElusivError::WardenNotAttested);
+
+    Ok(())
+}
+
+/// Returns whether payouts of `token_id` to `recipient` are flagged by an accepted APA proposal of the Elusiv Warden Network
+///
+/// # Note
+///
+/// - The `ApaFlagAccount` only exists for flagged recipients, an empty `apa_flag_account` needs to be the (unopened) PDA of `recipient`.
+/// - A flag with a `token_constraint` only applies to payouts of its token.
+/// - A flag with a `region_scope` only applies to payouts executed by a Warden attested within the region (`warden_region`),
+///   Wardens without an attested region are treated as being within the region.
+pub fn is_apa_flagged(
+    recipient: &Pubkey,
+    apa_flag_account: &AccountInfo,
+    token_id: u16,
+    warden_region: Option<u8>,
+) -> Result<bool, ProgramError> {
+    if apa_flag_account.data_is_empty() {
+        let (expected, _) = Pubkey::find_program_address(
+            &[APA_FLAG_ACCOUNT_SEED, &recipient.to_bytes()],
+            &WARDEN_NETWORK_PROGRAM_ID,
+        );
+        guard!(
+            expected == *apa_flag_account.key,
+            ElusivError::InvalidAccount
+        );
+
+        return Ok(false);
+    }
+
+    verify_warden_network_account(APA_FLAG_ACCOUNT_SEED, recipient, apa_flag_account)?;
+
+    let data = apa_flag_account.data.borrow();
+    let constraint = data
+        .get(APA_FLAG_TOKEN_CONSTRAINT_OFFSET..APA_FLAG_TOKEN_CONSTRAINT_OFFSET + 3)
+        .ok_or(ElusivError::InvalidAccount)?;
+    let is_constrained = constraint[0] != 0;
+    let is_in_token_scope =
+        !is_constrained || u16::from_le_bytes([constraint[1], constraint[2]]) == token_id;
+
+    // Flags without a region scope (or of the previous layout) apply globally
+    let is_in_region_scope = match elusiv_option_u8(&data, APA_FLAG_REGION_SCOPE_OFFSET) {
+        Some(region_scope) => warden_region.map_or(true, |region| region == region_scope),
+        None => true,
+    };
+
+    Ok(is_in_token_scope && is_in_region_scope)
+}
+
+/// Returns the attested region (the index of the `WardenRegion`) of `warden`
+///
+/// # Note
+///
+/// Returns `None` for an invalid `warden_attestation_account` or a Warden without an attested region.
+pub fn warden_attested_region(
+    warden: &Pubkey,
+    warden_attestation_account: &AccountInfo,
+) -> Option<u8> {
+    verify_warden_network_account(
+        WARDEN_ATTESTATION_ACCOUNT_SEED,
+        warden,
+        warden_attestation_account,
+    )
+    .ok()?;
+
+    elusiv_option_u8(
+        &warden_attestation_account.data.borrow(),
+        WARDEN_ATTESTATION_REGION_OFFSET,
+    )
+}
+
+/// Reads a serialized `ElusivOption` of a single byte (`None` for invalid data)
+fn elusiv_option_u8(data: &[u8], offset: usize) -> Option<u8> {
+    match data.get(offset..offset + 2) {
+        Some(&[1, value]) => Some(value),
+        _ => None,
+    }
+}
+
+/// Consumes the `nonce` of `warden` in its [`WardenNonceAccount`]
+///
+/// # Note
+///
+/// - Nonces are optional unless `is_required`: without a `nonce`, any `warden_nonce_account` is accepted.
+/// - Required nonces protect wardens against replays of their (signed) transactions by a malicious RPC.
+pub fn consume_warden_nonce(
+    warden: &Pubkey,
+    warden_nonce_account: &AccountInfo,
+    nonce: ElusivOption<u64>,
+    is_required: bool,
+) -> ProgramResult {
+    let nonce = match nonce.option() {
+        Some(nonce) => nonce,
+        None => {
+            guard!(!is_required, ElusivError::InvalidWardenNonce);
+            return Ok(());
+        }
+    };
+
+    guard!(
+        WardenNonceAccount::pubkey_cached(warden_nonce_account, Some(*warden), None)?
+            == *warden_nonce_account.key,
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        *warden_nonce_account.owner == crate::id(),
+        ElusivError::InvalidAccount
+    );
+
+    pda_account!(
+        mut warden_nonce_account,
+        WardenNonceAccount,
+        warden_nonce_account
+    );
+    warden_nonce_account.consume(nonce)
+}
+
+/// Records the usage of `warden` with `record` in its [`WardenUsageAccount`]
+///
+/// # Note
+///
+/// Usage metering is opt-in: if the `warden_usage_account` has not been opened, nothing is recorded.
+pub fn record_warden_usage<F: FnOnce(&mut WardenUsageAccount)>(
+    warden: &Pubkey,
+    warden_usage_account: &AccountInfo,
+    record: F,
+) -> ProgramResult {
+    if *warden_usage_account.owner != crate::id()
+        || warden_usage_account.data_len() != WardenUsageAccount::SIZE
+    {
+        return Ok(());
+    }
+
+    guard!(
+        WardenUsageAccount::pubkey_cached(warden_usage_account, Some(*warden), None)?
+            == *warden_usage_account.key,
+        ElusivError::InvalidAccount
+    );
+
+    pda_account!(
+        mut warden_usage_account,
+        WardenUsageAccount,
+        warden_usage_account
+    );
+    record(&mut warden_usage_account);
+
+    Ok(())
+}
+
+/// Returns the attestation level stored in the data of a `WardenAttestationAccount` (zero for invalid data)
+pub fn warden_attestation_level(data: &[u8]) -> u8 {
+    data.get(WARDEN_ATTESTATION_LEVEL_OFFSET)
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Returns the `BasicWardenMapAccount` of `warden` (passed to reservations of hashing slots)
+#[cfg(feature = "elusiv-client")]
+pub fn basic_warden_map_account(warden: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[BASIC_WARDEN_MAP_ACCOUNT_SEED, &warden.to_bytes()],
+        &WARDEN_NETWORK_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Returns the `WardenAttestationAccount` of `warden` (passed to stores and verifications)
+#[cfg(feature = "elusiv-client")]
+pub fn warden_attestation_account(warden: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[WARDEN_ATTESTATION_ACCOUNT_SEED, &warden.to_bytes()],
+        &WARDEN_NETWORK_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Returns the `ApaFlagAccount` of `recipient` (passed to the finalization of payouts)
+#[cfg(feature = "elusiv-client")]
+pub fn apa_flag_account(recipient: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[APA_FLAG_ACCOUNT_SEED, &recipient.to_bytes()],
+        &WARDEN_NETWORK_PROGRAM_ID,
+    )
+    .0
+}
+
+/// The [`FeatureFlagsAccount`] appended to deprecated instructions by the abi
+pub const FEATURE_FLAGS_ACCOUNT: Pubkey = FeatureFlagsAccount::FIRST_PDA.0;
+
+/// Logs a deprecation warning and rejects the instruction if the [`FeatureFlagsAccount`] enforces its deprecation
+///
+/// # Note
+///
+/// - Called by instructions with the `#[deprecated_since(deprecated_since)]` attribute (before any account is parsed).
+/// - The [`FeatureFlagsAccount`] can be passed at any position (accounts built with an older abi only receive the warning).
+pub fn check_deprecated_instruction(
+    name: &str,
+    deprecated_since: u32,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    msg!(
+        "Warning: {} is deprecated since version {}",
+        name,
+        deprecated_since
+    );
+
+    if let Some(feature_flags) = accounts
+        .iter()
+        .find(|account| *account.key == FEATURE_FLAGS_ACCOUNT)
+    {
+        guard!(
+            *feature_flags.owner == crate::ID,
+            ElusivError::InvalidAccount
+        );
+
+        let data = &mut feature_flags.data.borrow_mut()[..];
+        let feature_flags = FeatureFlagsAccount::new(data)?;
+        guard!(
+            !feature_flags.is_deprecation_enforced(deprecated_since),
+            ElusivError::DeprecatedInstruction
+        );
+    }
+
+    Ok(())
+}
+
+/// The result of a [`warden_self_test`] (written as return data)
+///
+/// # Note
+///
+/// All values are deterministic, so a client can compare the report of the live program with [`SelfTestReport::compute`].
+#[derive(BorshSerialize, Debug, PartialEq)]
+#[cfg_attr(feature = "elusiv-client", derive(BorshDeserialize))]
+pub struct SelfTestReport {
+    /// `(major, minor, patch)` of the program's crate version
+    pub version: [u16; 3],
+
+    /// The derived `PoolAccount` address
+    pub pool_pda: U256,
+
+    /// The state of a binary Poseidon hash of `(0, 1)` after a single round
+    pub poseidon_round: U256,
+
+    /// Whether the doubling of the G1 generator (the verifier's group operation) is consistent
+    pub is_verifier_step_valid: bool,
+}
+
+impl SelfTestReport {
+    pub fn compute() -> Result<Self, ProgramError> {
+        use crate::commitment::poseidon_hash::{
+            binary_poseidon_hash_partial, BinarySpongeHashingState,
+        };
+        use crate::fields::fr_to_u256_le;
+        use crate::state::governor::PoolAccount;
+        use ark_bn254::{Fr, G1Affine};
+        use ark_ec::{AffineCurve, ProjectiveCurve};
+        use ark_ff::{One, Zero};
+
+        let version = crate::state::deployment::crate_version();
+
+        // PDA derivation
+        let bump = PoolAccount::FIRST_PDA.1;
+        let seeds = PoolAccount::signers_seeds(None, None, bump);
+        let seeds: Vec<&[u8]> = seeds.iter().map(|x| &x[..]).collect();
+        let pool_pda = Pubkey::create_program_address(&seeds, &crate::ID)
+            .or(Err(ProgramError::InvalidSeeds))?;
+        guard!(
+            pool_pda == PoolAccount::FIRST_PDA.0,
+            ProgramError::InvalidSeeds
+        );
+
+        // Single poseidon round
+        let mut state = BinarySpongeHashingState::new(Fr::zero(), Fr::one(), false);
+        binary_poseidon_hash_partial(0, &mut state);
+
+        // Tiny verifier step
+        let generator = G1Affine::prime_subgroup_generator();
+        let mut sum = generator.into_projective();
+        sum.add_assign_mixed(&generator);
+        let double = sum.into_affine();
+        let is_verifier_step_valid =
+            double.is_on_curve() && double == generator.into_projective().double().into_affine();
+
+        Ok(SelfTestReport {
+            version,
+            pool_pda: pool_pda.to_bytes(),
+            poseidon_round: fr_to_u256_le(&state.0[0]),
+            is_verifier_step_valid,
+        })
+    }
+}
+
+/// Exercises PDA derivation, a Poseidon round and a verifier step, and writes the [`SelfTestReport`] as return data
+///
+/// # Note
+///
+/// Allows warden operators to validate their deployment and RPC path against the live program (e.g. after upgrades).
+pub fn warden_self_test() -> ProgramResult {
+    let report = SelfTestReport::compute()?;
+    guard!(
+        report.is_verifier_step_valid,
+        ElusivError::InvalidAccountState
+    );
+
+    set_return_data(&report.try_to_vec()?);
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
         macros::{account_info, test_account_info},
-        state::{governor::PoolAccount, proof::VerificationAccount},
+        state::{governor::PoolAccount, program_account::SizedAccount, proof::VerificationAccount},
         token::TOKENS,
     };
     use solana_program::{pubkey::Pubkey, system_program};
+    use spl_associated_token_account::get_associated_token_address;
+
+    #[test]
+    fn test_verify_basic_warden() {
+        let warden = Pubkey::new_unique();
+        let (pk, bump) = Pubkey::find_program_address(
+            &[BASIC_WARDEN_MAP_ACCOUNT_SEED, &warden.to_bytes()],
+            &WARDEN_NETWORK_PROGRAM_ID,
+        );
+        account_info!(
+            map_account,
+            pk,
+            vec![bump, 0, 0, 0, 0],
+            WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+        account_info!(
+            invalid_owner,
+            pk,
+            vec![bump, 0, 0, 0, 0],
+            crate::id(),
+            false
+        );
+        account_info!(empty, pk, vec![], WARDEN_NETWORK_PROGRAM_ID, false);
+
+        assert_eq!(verify_basic_warden(&warden, &map_account), Ok(()));
+        assert_eq!(
+            verify_basic_warden(&Pubkey::new_unique(), &map_account),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(
+            verify_basic_warden(&warden, &invalid_owner),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(
+            verify_basic_warden(&warden, &empty),
+            Err(ElusivError::InvalidAccount.into())
+        );
+    }
+
+    #[test]
+    fn test_consume_warden_nonce() {
+        let warden = Pubkey::new_unique();
+        let (pk, bump) = WardenNonceAccount::find_with_pubkey(warden, None);
+        let mut data = vec![0; WardenNonceAccount::SIZE];
+        data[0] = bump;
+        account_info!(warden_nonce_account, pk, data);
+        account_info!(
+            invalid_owner,
+            pk,
+            vec![bump; WardenNonceAccount::SIZE],
+            system_program::id(),
+            false
+        );
+        test_account_info!(any, WardenNonceAccount::SIZE);
+
+        // Without a nonce any account is accepted
+        assert_eq!(
+            consume_warden_nonce(&warden, &any, ElusivOption::None, false),
+            Ok(())
+        );
+
+        // Unless nonces are required
+        assert_eq!(
+            consume_warden_nonce(&warden, &warden_nonce_account, ElusivOption::None, true),
+            Err(ElusivError::InvalidWardenNonce.into())
+        );
+
+        assert_eq!(
+            consume_warden_nonce(&warden, &any, ElusivOption::Some(1), false),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(
+            consume_warden_nonce(&warden, &invalid_owner, ElusivOption::Some(1), false),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(
+            consume_warden_nonce(
+                &Pubkey::new_unique(),
+                &warden_nonce_account,
+                ElusivOption::Some(1),
+                false
+            ),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        assert_eq!(
+            consume_warden_nonce(&warden, &warden_nonce_account, ElusivOption::Some(1), true),
+            Ok(())
+        );
+
+        // Duplicate submission
+        assert_eq!(
+            consume_warden_nonce(&warden, &warden_nonce_account, ElusivOption::Some(1), false),
+            Err(ElusivError::InvalidWardenNonce.into())
+        );
+    }
+
+    #[test]
+    fn test_record_warden_usage() {
+        let warden = Pubkey::new_unique();
+        let (pk, bump) = WardenUsageAccount::find_with_pubkey(warden, None);
+        let mut data = vec![0; WardenUsageAccount::SIZE];
+        data[0] = bump;
+        account_info!(warden_usage_account, pk, data);
+        test_account_info!(any, 0, system_program::id());
+
+        // Accounts that have not been opened are ignored
+        record_warden_usage(&warden, &any, |usage| usage.record_verification()).unwrap();
+
+        assert_eq!(
+            record_warden_usage(&Pubkey::new_unique(), &warden_usage_account, |usage| {
+                usage.record_verification()
+            }),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        record_warden_usage(&warden, &warden_usage_account, |usage| {
+            usage.record_verification()
+        })
+        .unwrap();
+        let mut data = warden_usage_account.data.borrow_mut();
+        let warden_usage_account = WardenUsageAccount::new(&mut data).unwrap();
+        assert_eq!(warden_usage_account.get_verifications(), 1);
+    }
+
+    #[test]
+    #[cfg(not(feature = "permissioned"))]
+    fn test_verify_warden_attestation_open_mode() {
+        test_account_info!(any, 0);
+
+        // Any account is accepted for all levels
+        assert_eq!(
+            verify_warden_attestation(&Pubkey::new_unique(), &any, 3),
+            Ok(())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "permissioned")]
+    fn test_verify_warden_attestation() {
+        let warden = Pubkey::new_unique();
+        let (pk, bump) = Pubkey::find_program_address(
+            &[WARDEN_ATTESTATION_ACCOUNT_SEED, &warden.to_bytes()],
+            &WARDEN_NETWORK_PROGRAM_ID,
+        );
+        account_info!(
+            attestation_account,
+            pk,
+            vec![bump, 0, 0, 0, 0, 0, 2],
+            WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+        account_info!(
+            invalid_owner,
+            pk,
+            vec![bump, 0, 0, 0, 0, 0, 2],
+            crate::id(),
+            false
+        );
+
+        // Open mode
+        assert_eq!(
+            verify_warden_attestation(&warden, &invalid_owner, 0),
+            Ok(())
+        );
+
+        assert_eq!(
+            verify_warden_attestation(&warden, &attestation_account, 2),
+            Ok(())
+        );
+        assert_eq!(
+            verify_warden_attestation(&warden, &attestation_account, 3),
+            Err(ElusivError::WardenNotAttested.into())
+        );
+        assert_eq!(
+            verify_warden_attestation(&Pubkey::new_unique(), &attestation_account, 1),
+            Err(ElusivError::WardenNotAttested.into())
+        );
+        assert_eq!(
+            verify_warden_attestation(&warden, &invalid_owner, 1),
+            Err(ElusivError::WardenNotAttested.into())
+        );
+    }
+
+    #[test]
+    fn test_is_apa_flagged() {
+        let recipient = Pubkey::new_unique();
+        let (pk, bump) = Pubkey::find_program_address(
+            &[APA_FLAG_ACCOUNT_SEED, &recipient.to_bytes()],
+            &WARDEN_NETWORK_PROGRAM_ID,
+        );
+        let mut data = vec![0; APA_FLAG_TOKEN_CONSTRAINT_OFFSET + 3];
+        data[0] = bump;
+        account_info!(
+            flag_account,
+            pk,
+            data.clone(),
+            WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+        data[APA_FLAG_TOKEN_CONSTRAINT_OFFSET..].copy_from_slice(&[1, 2, 0]);
+        account_info!(
+            constrained_flag_account,
+            pk,
+            data.clone(),
+            WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+        account_info!(invalid_owner, pk, data.clone(), crate::id(), false);
+        data.extend(vec![0; 4]);
+        data.extend([1, 5]);
+        account_info!(
+            scoped_flag_account,
+            pk,
+            data,
+            WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+        account_info!(unopened, pk, vec![], WARDEN_NETWORK_PROGRAM_ID, false);
+        test_account_info!(invalid_unopened, 0);
+
+        assert_eq!(
+            is_apa_flagged(&recipient, &flag_account, 0, None,),
+            Ok(true)
+        );
+        assert_eq!(
+            is_apa_flagged(&recipient, &flag_account, 2, None,),
+            Ok(true)
+        );
+
+        // Token constraint
+        assert_eq!(
+            is_apa_flagged(&recipient, &constrained_flag_account, 0, None,),
+            Ok(false)
+        );
+        assert_eq!(
+            is_apa_flagged(&recipient, &constrained_flag_account, 2, None,),
+            Ok(true)
+        );
+
+        // Region scope
+        assert_eq!(
+            is_apa_flagged(&recipient, &scoped_flag_account, 2, Some(5)),
+            Ok(true)
+        );
+        assert_eq!(
+            is_apa_flagged(&recipient, &scoped_flag_account, 2, Some(3)),
+            Ok(false)
+        );
+        assert_eq!(
+            is_apa_flagged(&recipient, &scoped_flag_account, 2, None),
+            Ok(true)
+        );
+        assert_eq!(
+            is_apa_flagged(&recipient, &scoped_flag_account, 0, Some(5)),
+            Ok(false)
+        );
+        assert_eq!(
+            is_apa_flagged(&recipient, &flag_account, 0, Some(3)),
+            Ok(true)
+        );
+
+        // Unopened flag account
+        assert_eq!(is_apa_flagged(&recipient, &unopened, 0, None,), Ok(false));
+        assert_eq!(
+            is_apa_flagged(&recipient, &invalid_unopened, 0, None,),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        assert_eq!(
+            is_apa_flagged(&Pubkey::new_unique(), &flag_account, 0, None,),
+            Err(ElusivError::InvalidAccount.into())
+        );
+        assert_eq!(
+            is_apa_flagged(&recipient, &invalid_owner, 0, None,),
+            Err(ElusivError::InvalidAccount.into())
+        );
+    }
+
+    #[test]
+    fn test_warden_attested_region() {
+        let warden = Pubkey::new_unique();
+        let (pk, bump) = Pubkey::find_program_address(
+            &[WARDEN_ATTESTATION_ACCOUNT_SEED, &warden.to_bytes()],
+            &WARDEN_NETWORK_PROGRAM_ID,
+        );
+        account_info!(
+            attestation_account,
+            pk,
+            vec![bump, 0, 0, 0, 0, 0, 2, 1, 5],
+            WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+        account_info!(
+            unregioned_attestation_account,
+            pk,
+            vec![bump, 0, 0, 0, 0, 0, 2, 0, 0],
+            WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+        account_info!(
+            legacy_attestation_account,
+            pk,
+            vec![bump, 0, 0, 0, 0, 0, 2],
+            WARDEN_NETWORK_PROGRAM_ID,
+            false
+        );
+        account_info!(
+            invalid_owner,
+            pk,
+            vec![bump, 0, 0, 0, 0, 0, 2, 1, 5],
+            crate::id(),
+            false
+        );
+
+        assert_eq!(
+            warden_attested_region(&warden, &attestation_account),
+            Some(5)
+        );
+        assert_eq!(
+            warden_attested_region(&warden, &unregioned_attestation_account),
+            None
+        );
+        assert_eq!(
+            warden_attested_region(&warden, &legacy_attestation_account),
+            None
+        );
+        assert_eq!(warden_attested_region(&warden, &invalid_owner), None);
+        assert_eq!(
+            warden_attested_region(&Pubkey::new_unique(), &attestation_account),
+            None
+        );
+    }
 
     #[test]
     fn test_transfer_token_from_pda() {
@@ -514,6 +1405,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_verify_pool_liquidity() {
+        account_info!(pool, Pubkey::new_unique(), vec![]);
+        let balance = pool.lamports();
+
+        assert_eq!(verify_pool_liquidity(&pool, balance), Ok(()));
+        assert_eq!(
+            verify_pool_liquidity(&pool, balance + 1),
+            Err(ElusivError::PoolLiquidityDepleted.into())
+        );
+    }
+
     #[test]
     fn test_close_account() {
         account_info!(account, Pubkey::new_unique(), vec![]);
@@ -556,4 +1459,86 @@ mod tests {
             Err(ElusivError::InvalidAccount.into())
         );
     }
+
+    #[test]
+    fn test_warden_self_test() {
+        let report = SelfTestReport::compute().unwrap();
+        assert_eq!(report, SelfTestReport::compute().unwrap());
+        assert_eq!(report.pool_pda, PoolAccount::find(None).0.to_bytes());
+        assert!(report.is_verifier_step_valid);
+        assert_eq!(
+            report.version,
+            [
+                env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap(),
+                env!("CARGO_PKG_VERSION_MINOR").parse().unwrap(),
+                env!("CARGO_PKG_VERSION_PATCH").parse().unwrap(),
+            ]
+        );
+        assert_eq!(report.try_to_vec().unwrap().len(), 3 * 2 + 32 + 32 + 1);
+
+        assert_eq!(warden_self_test(), Ok(()));
+    }
+
+    #[test]
+    fn test_check_deprecated_instruction() {
+        account_info!(
+            feature_flags,
+            FEATURE_FLAGS_ACCOUNT,
+            vec![0; FeatureFlagsAccount::SIZE]
+        );
+        test_account_info!(other);
+
+        // Without the feature-flags account
+        assert_eq!(check_deprecated_instruction("A", 1, &[]), Ok(()));
+
+        assert_eq!(
+            check_deprecated_instruction("A", 1, &[other.clone(), feature_flags.clone()]),
+            Ok(())
+        );
+
+        {
+            let data = &mut feature_flags.data.borrow_mut()[..];
+            let mut account = FeatureFlagsAccount::new(data).unwrap();
+            account.set_deprecation_enforcement_version(&1);
+        }
+        assert_eq!(
+            check_deprecated_instruction("A", 1, &[other.clone(), feature_flags.clone()]),
+            Err(ElusivError::DeprecatedInstruction.into())
+        );
+        assert_eq!(
+            check_deprecated_instruction("A", 2, &[feature_flags.clone()]),
+            Ok(())
+        );
+
+        // Invalid owner
+        account_info!(
+            invalid_feature_flags,
+            FEATURE_FLAGS_ACCOUNT,
+            vec![0; FeatureFlagsAccount::SIZE],
+            Pubkey::new_unique(),
+            false
+        );
+        assert_eq!(
+            check_deprecated_instruction("A", 1, &[invalid_feature_flags]),
+            Err(ElusivError::InvalidAccount.into())
+        );
+    }
+
+    #[test]
+    fn test_load_instructions_sysvar_account() {
+        use elusiv_types::sysvar::InstructionsAccount;
+
+        // Invalid key
+        account_info!(account, Pubkey::new_unique(), vec![3, 0]);
+        assert_eq!(
+            InstructionsAccount::load(&account).err(),
+            Some(ProgramError::InvalidArgument)
+        );
+
+        // The current index is stored in the last two bytes
+        account_info!(account, instructions::ID, vec![0, 0, 3, 0]);
+        let sysvar = InstructionsAccount::load(&account).unwrap();
+        assert_eq!(sysvar.sysvar().current_index, 3);
+        assert_eq!(*sysvar.key, instructions::ID);
+    }
 }
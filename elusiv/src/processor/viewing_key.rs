@@ -0,0 +1,107 @@
+use super::utils::open_pda_account_with_associated_pubkey;
+use crate::error::ElusivError;
+use crate::event::ElusivEvent;
+use crate::macros::{guard, pda_account};
+use crate::state::viewing_key::ViewingKeyAccount;
+use crate::types::U256;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+/// Publishes the viewing key of an `identifier` (opens the [`ViewingKeyAccount`])
+pub fn publish_viewing_key<'a>(
+    payer: &AccountInfo<'a>,
+    identifier_account: &AccountInfo<'a>,
+    viewing_key_account: &AccountInfo<'a>,
+
+    viewing_key: U256,
+) -> ProgramResult {
+    guard!(viewing_key != [0; 32], ElusivError::InvalidInstructionData);
+    guard!(
+        viewing_key_account.data_len() == 0,
+        ElusivError::InvalidAccountState
+    );
+
+    open_pda_account_with_associated_pubkey::<ViewingKeyAccount>(
+        &crate::id(),
+        payer,
+        viewing_key_account,
+        identifier_account.key,
+        None,
+        None,
+    )?;
+
+    pda_account!(
+        mut viewing_key_account,
+        ViewingKeyAccount,
+        viewing_key_account
+    );
+    viewing_key_account.set_viewing_key(&viewing_key);
+
+    ElusivEvent::ViewingKeyPublished {
+        identifier: *identifier_account.key,
+        viewing_key,
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Records the (encrypted) memo of a send, if a viewing key has been published for its `identifier`
+///
+/// # Note
+///
+/// A non-existing [`ViewingKeyAccount`] records no memos.
+pub fn record_viewing_key_memo(
+    viewing_key_account: &AccountInfo,
+    identifier: &Pubkey,
+    memo: &[u8],
+) -> ProgramResult {
+    if viewing_key_account.data_len() == 0 {
+        return Ok(());
+    }
+
+    pda_account!(
+        mut viewing_key_account,
+        ViewingKeyAccount,
+        viewing_key_account
+    );
+    let memo_index = viewing_key_account.get_memo_count();
+    viewing_key_account.set_memo_count(
+        &memo_index
+            .checked_add(1)
+            .ok_or(ElusivError::InvalidAccountState)?,
+    );
+
+    ElusivEvent::ViewingKeyMemoRecorded {
+        identifier: *identifier,
+        memo_index,
+        memo: memo.to_vec(),
+    }
+    .emit();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::test_account_info;
+    use crate::state::program_account::SizedAccount;
+
+    #[test]
+    fn test_record_viewing_key_memo() -> ProgramResult {
+        let identifier = Pubkey::new_unique();
+
+        // Non-existing account
+        test_account_info!(empty);
+        assert_eq!(record_viewing_key_memo(&empty, &identifier, &[1]), Ok(()));
+
+        test_account_info!(viewing_key_account, ViewingKeyAccount::SIZE);
+        record_viewing_key_memo(&viewing_key_account, &identifier, &[1]).unwrap();
+        record_viewing_key_memo(&viewing_key_account, &identifier, &[2, 3]).unwrap();
+
+        pda_account!(viewing_key_account, ViewingKeyAccount, viewing_key_account);
+        assert_eq!(viewing_key_account.get_memo_count(), 2);
+
+        Ok(())
+    }
+}
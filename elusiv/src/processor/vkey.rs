@@ -1,6 +1,10 @@
 use crate::{
-    error::ElusivError, processor::setup_child_account, proof::vkey::VerifyingKey,
-    state::vkey::VKeyAccount,
+    bytes::is_zero,
+    error::ElusivError,
+    processor::setup_child_account,
+    proof::vkey::VerifyingKey,
+    state::{circuit_registry::CircuitRegistryAccount, vkey::VKeyAccount},
+    types::U256,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_types::{BorshSerDeSized, ChildAccountConfig, ElusivOption, ParentAccount};
@@ -13,7 +17,7 @@ use solana_program::{
 };
 
 pub const VKEY_ACCOUNT_DATA_PACKET_SIZE: usize = 964;
-const MAX_NUMBER_OF_VKEYS: u32 = 1;
+pub const MAX_NUMBER_OF_VKEYS: u32 = 2;
 
 /// A binary data packet containing [`VKEY_ACCOUNT_DATA_PACKET_SIZE`] bytes
 #[derive(BorshSerialize, BorshDeserialize)]
@@ -174,6 +178,53 @@ pub fn change_vkey_authority(
     Ok(())
 }
 
+/// Registers the proving-key hash and the public-input layout of the circuit with `vkey_id` in the [`CircuitRegistryAccount`]
+///
+/// # Note
+///
+/// - `authority` needs to be the program's keypair
+/// - the vkey hash, vkey version and public inputs count are read from the [`VKeyAccount`]
+/// - after a vkey version upgrade, the circuit needs to be registered again
+pub fn register_circuit(
+    authority: &AccountInfo,
+    circuit_registry: &mut CircuitRegistryAccount,
+    vkey_account: &VKeyAccount,
+
+    vkey_id: u32,
+    proving_key_hash: U256,
+    public_inputs_layout_hash: U256,
+) -> ProgramResult {
+    guard!(
+        *authority.key == crate::ID,
+        ProgramError::MissingRequiredSignature
+    );
+    guard!(
+        vkey_id < MAX_NUMBER_OF_VKEYS,
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        !is_zero(&proving_key_hash) && !is_zero(&public_inputs_layout_hash),
+        ElusivError::InvalidInstructionData
+    );
+    guard!(vkey_account.is_setup(), ElusivError::InvalidAccountState);
+
+    let public_inputs_count = vkey_account.get_public_inputs_count();
+    let vkey_hash = vkey_account.execute_on_child_account(0, |data| {
+        VerifyingKey::new(data, public_inputs_count as usize)
+            .map(|vkey| vkey.key_hash())
+            .ok_or(ElusivError::InvalidAccountState)
+    })??;
+
+    let index = vkey_id as usize;
+    circuit_registry.set_proving_key_hashes(index, &proving_key_hash);
+    circuit_registry.set_vkey_hashes(index, &vkey_hash);
+    circuit_registry.set_vkey_versions(index, &vkey_account.get_version());
+    circuit_registry.set_public_inputs_counts(index, &public_inputs_count);
+    circuit_registry.set_public_inputs_layout_hashes(index, &public_inputs_layout_hash);
+
+    Ok(())
+}
+
 fn verify_vkey_modification(signer: &AccountInfo, vkey_account: &VKeyAccount) -> ProgramResult {
     guard!(
         !vkey_account.get_is_frozen(),
@@ -198,7 +249,9 @@ mod test {
     use super::*;
     use crate::{
         bytes::div_ceiling_usize,
-        macros::{signing_test_account_info, test_account_info},
+        macros::{
+            account_info, signing_test_account_info, test_account_info, zero_program_account,
+        },
         processor::vkey_account,
         proof::vkey::{TestVKey, VerifyingKeyInfo},
     };
@@ -427,4 +480,93 @@ mod test {
             Err(ProgramError::MissingRequiredSignature)
         );
     }
+
+    #[test]
+    fn test_register_circuit() {
+        zero_program_account!(mut circuit_registry, CircuitRegistryAccount);
+        vkey_account!(vkey_account, TestVKey);
+        let proving_key_hash = [1; 32];
+        let layout_hash = [2; 32];
+
+        account_info!(invalid_authority, Pubkey::new_unique(), true);
+        assert_eq!(
+            register_circuit(
+                &invalid_authority,
+                &mut circuit_registry,
+                &vkey_account,
+                0,
+                proving_key_hash,
+                layout_hash
+            ),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        account_info!(authority, crate::ID, true);
+        for (vkey_id, proving_key_hash, layout_hash) in [
+            (MAX_NUMBER_OF_VKEYS, proving_key_hash, layout_hash),
+            (0, [0; 32], layout_hash),
+            (0, proving_key_hash, [0; 32]),
+        ] {
+            assert_eq!(
+                register_circuit(
+                    &authority,
+                    &mut circuit_registry,
+                    &vkey_account,
+                    vkey_id,
+                    proving_key_hash,
+                    layout_hash
+                ),
+                Err(ElusivError::InvalidInstructionData.into())
+            );
+        }
+
+        // Vkey is not setup
+        assert_eq!(
+            register_circuit(
+                &authority,
+                &mut circuit_registry,
+                &vkey_account,
+                0,
+                proving_key_hash,
+                layout_hash
+            ),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        vkey_account.set_version(&1);
+        register_circuit(
+            &authority,
+            &mut circuit_registry,
+            &vkey_account,
+            0,
+            proving_key_hash,
+            layout_hash,
+        )
+        .unwrap();
+
+        let vkey_hash = VerifyingKey::new(
+            &TestVKey::verifying_key_source(),
+            TestVKey::public_inputs_count(),
+        )
+        .unwrap()
+        .key_hash();
+        assert_eq!(circuit_registry.get_vkey_hashes(0), vkey_hash);
+        assert_eq!(
+            circuit_registry.get_public_inputs_counts(0),
+            TestVKey::PUBLIC_INPUTS_COUNT
+        );
+        assert_eq!(
+            circuit_registry.get_public_inputs_layout_hashes(0),
+            layout_hash
+        );
+
+        assert!(circuit_registry.is_registered(0, 1));
+        assert!(circuit_registry.matches_artifacts(0, &proving_key_hash, &vkey_hash));
+        assert!(!circuit_registry.matches_artifacts(0, &[3; 32], &vkey_hash));
+        assert!(!circuit_registry.matches_artifacts(1, &proving_key_hash, &vkey_hash));
+
+        // A new vkey version requires a new registration
+        vkey_account.set_version(&2);
+        assert!(!circuit_registry.is_registered(0, 2));
+    }
 }
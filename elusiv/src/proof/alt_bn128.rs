@@ -0,0 +1,455 @@
+//! Groth16 proof verification using the `alt_bn128` syscalls
+//!
+//! # Note
+//!
+//! - Instead of the partial computations in [`super::verifier`] the whole verification fits into a single `ComputeVerification` instruction.
+//! - Only clusters that support `sol_alt_bn128_group_op` can load a program built with the `alt-bn128` feature.
+//! - Off-chain (and in tests) the syscalls are substituted by equivalent arkworks computations.
+//! - Multiple proofs can be verified at once using a random linear combination (see [`verify_proofs_batched`]).
+//! - Points and scalars are encoded in the big-endian EIP-196/EIP-197 format.
+
+use super::vkey::VerifyingKey;
+use crate::error::ElusivError::{self, CouldNotProcessProof};
+use crate::types::{Proof, U256};
+use ark_bn254::{Fr, G1Affine, G2Affine};
+use ark_ec::ProjectiveCurve;
+use ark_ff::{BigInteger256, PrimeField, Zero};
+use borsh::BorshSerialize;
+use elusiv_utils::guard;
+use solana_program::hash::{hash, hashv};
+
+pub const ALT_BN128_ADD: u64 = 0;
+pub const ALT_BN128_MUL: u64 = 2;
+pub const ALT_BN128_PAIRING: u64 = 3;
+
+pub const G1_SIZE: usize = 64;
+pub const G2_SIZE: usize = 128;
+pub const SCALAR_SIZE: usize = 32;
+pub const PAIRING_ELEMENT_SIZE: usize = G1_SIZE + G2_SIZE;
+
+#[cfg(any(target_os = "solana", target_arch = "bpf"))]
+extern "C" {
+    fn sol_alt_bn128_group_op(
+        group_op: u64,
+        input: *const u8,
+        input_size: u64,
+        result: *mut u8,
+    ) -> u64;
+}
+
+/// Performs the group operation `op` on `input` and writes the output into `result`
+#[cfg(any(target_os = "solana", target_arch = "bpf"))]
+fn group_op(op: u64, input: &[u8], result: &mut [u8]) -> Result<(), ElusivError> {
+    let code = unsafe {
+        sol_alt_bn128_group_op(op, input.as_ptr(), input.len() as u64, result.as_mut_ptr())
+    };
+    guard!(code == 0, CouldNotProcessProof);
+
+    Ok(())
+}
+
+/// Performs the group operation `op` on `input` and writes the output into `result`
+#[cfg(not(any(target_os = "solana", target_arch = "bpf")))]
+fn group_op(op: u64, input: &[u8], result: &mut [u8]) -> Result<(), ElusivError> {
+    use ark_bn254::{Bn254, Fq12};
+    use ark_ec::{AffineCurve, PairingEngine};
+    use ark_ff::One;
+
+    match op {
+        ALT_BN128_ADD => {
+            guard!(input.len() == 2 * G1_SIZE, CouldNotProcessProof);
+            let mut sum = decode_g1(&input[..G1_SIZE])?.into_projective();
+            sum.add_assign_mixed(&decode_g1(&input[G1_SIZE..])?);
+            result[..G1_SIZE].copy_from_slice(&encode_g1(&sum.into_affine()));
+        }
+        ALT_BN128_MUL => {
+            guard!(input.len() == G1_SIZE + SCALAR_SIZE, CouldNotProcessProof);
+            let point = decode_g1(&input[..G1_SIZE])?;
+            let scalar = be_big_uint(&input[G1_SIZE..]);
+            result[..G1_SIZE].copy_from_slice(&encode_g1(&point.mul(scalar).into_affine()));
+        }
+        ALT_BN128_PAIRING => {
+            guard!(
+                input.len() % PAIRING_ELEMENT_SIZE == 0,
+                CouldNotProcessProof
+            );
+            let mut pairs: Vec<(
+                <Bn254 as PairingEngine>::G1Prepared,
+                <Bn254 as PairingEngine>::G2Prepared,
+            )> = Vec::with_capacity(input.len() / PAIRING_ELEMENT_SIZE);
+            for element in input.chunks(PAIRING_ELEMENT_SIZE) {
+                pairs.push((
+                    decode_g1(&element[..G1_SIZE])?.into(),
+                    decode_g2(&element[G1_SIZE..])?.into(),
+                ));
+            }
+
+            result[..32].fill(0);
+            if Bn254::product_of_pairings(&pairs) == Fq12::one() {
+                result[31] = 1;
+            }
+        }
+        _ => return Err(CouldNotProcessProof),
+    }
+
+    Ok(())
+}
+
+/// Verifies a Groth16 `proof` in a single computation
+///
+/// # Note
+///
+/// - `public_inputs` are raw (non-montgomery) little-endian scalars, non-canonical scalars (`>= r`) are rejected.
+/// - Checks `e(-A, B) * e(alpha, beta) * e(prepared_inputs, gamma) * e(C, delta) == 1`.
+pub fn verify_proof(
+    vkey: &VerifyingKey,
+    proof: &Proof,
+    public_inputs: &[U256],
+) -> Result<bool, ElusivError> {
+    guard!(
+        public_inputs.len() == vkey.public_inputs_count,
+        CouldNotProcessProof
+    );
+
+    let scalars = public_inputs
+        .iter()
+        .map(|public_input| canonical_scalar(public_input).map(|s| fr_be_bytes(&s)))
+        .collect::<Result<Vec<U256>, ElusivError>>()?;
+    let prepared_inputs = prepare_public_inputs(
+        vkey,
+        encode_g1(&vkey.gamma_abc_base().into_affine()),
+        &scalars,
+    )?;
+
+    pairing_check(&[
+        (encode_g1(&-proof.a.0), encode_g2(&proof.b.0)),
+        (encode_g1(&vkey.alpha()), encode_g2(&vkey.beta())),
+        (prepared_inputs, encode_g2(&vkey.gamma())),
+        (encode_g1(&proof.c.0), encode_g2(&vkey.delta())),
+    ])
+}
+
+/// Verifies multiple Groth16 `proofs` (of the same `vkey`) using a random linear combination
+///
+/// # Note
+///
+/// - Requires `proofs.len() + 3` instead of `4 * proofs.len()` pairings and a single public input preparation.
+/// - Returns `Ok(false)` if at least one proof is invalid, without identifying the invalid proof.
+/// - Non-canonical public inputs (`>= r`) are rejected.
+/// - Checks `prod(e(-r_j * A_j, B_j)) * e(r * alpha, beta) * e(sum(r_j * prepared_inputs_j), gamma) * e(sum(r_j * C_j), delta) == 1` (with `r = sum(r_j)`).
+pub fn verify_proofs_batched(
+    vkey: &VerifyingKey,
+    proofs: &[(Proof, Vec<U256>)],
+) -> Result<bool, ElusivError> {
+    guard!(!proofs.is_empty(), CouldNotProcessProof);
+    for (_, public_inputs) in proofs {
+        guard!(
+            public_inputs.len() == vkey.public_inputs_count,
+            CouldNotProcessProof
+        );
+    }
+
+    let mut r_sum = Fr::zero();
+    let mut combined_inputs = vec![Fr::zero(); vkey.public_inputs_count];
+    let mut c = [0; G1_SIZE];
+    let mut elements = Vec::with_capacity(proofs.len() + 3);
+
+    for ((proof, public_inputs), r) in proofs.iter().zip(batch_randomness(vkey, proofs)?) {
+        r_sum += r;
+        for (combined_input, public_input) in combined_inputs.iter_mut().zip(public_inputs) {
+            *combined_input += canonical_scalar(public_input)? * r;
+        }
+
+        let r = fr_be_bytes(&r);
+        elements.push((g1_mul(&encode_g1(&-proof.a.0), &r)?, encode_g2(&proof.b.0)));
+        c = g1_add(&c, &g1_mul(&encode_g1(&proof.c.0), &r)?)?;
+    }
+
+    let r_sum = fr_be_bytes(&r_sum);
+    let scalars: Vec<U256> = combined_inputs.iter().map(fr_be_bytes).collect();
+    let prepared_inputs = prepare_public_inputs(
+        vkey,
+        g1_mul(&encode_g1(&vkey.gamma_abc_base().into_affine()), &r_sum)?,
+        &scalars,
+    )?;
+
+    elements.push((
+        g1_mul(&encode_g1(&vkey.alpha()), &r_sum)?,
+        encode_g2(&vkey.beta()),
+    ));
+    elements.push((prepared_inputs, encode_g2(&vkey.gamma())));
+    elements.push((c, encode_g2(&vkey.delta())));
+
+    pairing_check(&elements)
+}
+
+/// Derives the (128 bit) batching scalars from the `vkey` and all proofs and public inputs
+fn batch_randomness(
+    vkey: &VerifyingKey,
+    proofs: &[(Proof, Vec<U256>)],
+) -> Result<Vec<Fr>, ElusivError> {
+    let mut seed_input = vkey.key_hash().to_vec();
+    for (proof, public_inputs) in proofs {
+        seed_input.extend(proof.try_to_vec().or(Err(CouldNotProcessProof))?);
+        for public_input in public_inputs {
+            seed_input.extend(public_input);
+        }
+    }
+    let seed = hash(&seed_input).to_bytes();
+
+    Ok((0..proofs.len())
+        .map(|i| {
+            let h = hashv(&[&seed, &(i as u32).to_le_bytes()]).to_bytes();
+            Fr::from_le_bytes_mod_order(&h[..16])
+        })
+        .collect())
+}
+
+/// Parses a raw little-endian public input, rejecting non-canonical encodings (`>= r`)
+fn canonical_scalar(public_input: &U256) -> Result<Fr, ElusivError> {
+    Fr::from_repr(crate::fields::u256_to_big_uint(public_input)).ok_or(CouldNotProcessProof)
+}
+
+/// Computes `base + sum(scalar_i * IC_{i+1})` (with big-endian `scalars`)
+fn prepare_public_inputs(
+    vkey: &VerifyingKey,
+    base: [u8; G1_SIZE],
+    scalars: &[U256],
+) -> Result<[u8; G1_SIZE], ElusivError> {
+    let mut acc = base;
+
+    for (i, scalar) in scalars.iter().enumerate() {
+        if *scalar == [0; 32] {
+            continue;
+        }
+
+        let product = g1_mul(&encode_g1(&vkey.gamma_abc(i, 0, 1)), scalar)?;
+        acc = g1_add(&acc, &product)?;
+    }
+
+    Ok(acc)
+}
+
+fn g1_add(a: &[u8; G1_SIZE], b: &[u8; G1_SIZE]) -> Result<[u8; G1_SIZE], ElusivError> {
+    let mut input = [0; 2 * G1_SIZE];
+    input[..G1_SIZE].copy_from_slice(a);
+    input[G1_SIZE..].copy_from_slice(b);
+
+    let mut result = [0; G1_SIZE];
+    group_op(ALT_BN128_ADD, &input, &mut result)?;
+    Ok(result)
+}
+
+/// Multiplies `p` by the big-endian `scalar`
+fn g1_mul(p: &[u8; G1_SIZE], scalar: &U256) -> Result<[u8; G1_SIZE], ElusivError> {
+    let mut input = [0; G1_SIZE + SCALAR_SIZE];
+    input[..G1_SIZE].copy_from_slice(p);
+    input[G1_SIZE..].copy_from_slice(scalar);
+
+    let mut result = [0; G1_SIZE];
+    group_op(ALT_BN128_MUL, &input, &mut result)?;
+    Ok(result)
+}
+
+/// Returns true if the product of all pairings is one
+fn pairing_check(elements: &[([u8; G1_SIZE], [u8; G2_SIZE])]) -> Result<bool, ElusivError> {
+    let mut input = Vec::with_capacity(elements.len() * PAIRING_ELEMENT_SIZE);
+    for (g1, g2) in elements {
+        input.extend(g1);
+        input.extend(g2);
+    }
+
+    let mut result = [0; 32];
+    group_op(ALT_BN128_PAIRING, &input, &mut result)?;
+
+    Ok(result[..31] == [0; 31] && result[31] == 1)
+}
+
+fn fr_be_bytes(fr: &Fr) -> U256 {
+    be_bytes(&crate::fields::big_uint_to_u256(&fr.into_repr()))
+}
+
+fn be_bytes(v: &U256) -> U256 {
+    let mut v = *v;
+    v.reverse();
+    v
+}
+
+fn be_big_uint(slice: &[u8]) -> BigInteger256 {
+    let mut v: U256 = slice.try_into().unwrap();
+    v.reverse();
+    crate::fields::u256_to_big_uint(&v)
+}
+
+fn encode_fq(fq: &ark_bn254::Fq) -> U256 {
+    be_bytes(&crate::fields::big_uint_to_u256(&fq.into_repr()))
+}
+
+fn decode_fq(slice: &[u8]) -> Result<ark_bn254::Fq, ElusivError> {
+    ark_bn254::Fq::from_repr(be_big_uint(slice)).ok_or(CouldNotProcessProof)
+}
+
+/// Encodes a G1 point as `x || y` (the point at infinity is encoded as zeros)
+pub fn encode_g1(p: &G1Affine) -> [u8; G1_SIZE] {
+    let mut buf = [0; G1_SIZE];
+    if !p.infinity {
+        buf[..32].copy_from_slice(&encode_fq(&p.x));
+        buf[32..].copy_from_slice(&encode_fq(&p.y));
+    }
+    buf
+}
+
+/// Encodes a G2 point as `x.c1 || x.c0 || y.c1 || y.c0` (the point at infinity is encoded as zeros)
+pub fn encode_g2(p: &G2Affine) -> [u8; G2_SIZE] {
+    let mut buf = [0; G2_SIZE];
+    if !p.infinity {
+        buf[..32].copy_from_slice(&encode_fq(&p.x.c1));
+        buf[32..64].copy_from_slice(&encode_fq(&p.x.c0));
+        buf[64..96].copy_from_slice(&encode_fq(&p.y.c1));
+        buf[96..].copy_from_slice(&encode_fq(&p.y.c0));
+    }
+    buf
+}
+
+pub fn decode_g1(slice: &[u8]) -> Result<G1Affine, ElusivError> {
+    guard!(slice.len() == G1_SIZE, CouldNotProcessProof);
+    if slice == [0; G1_SIZE] {
+        return Ok(G1Affine::zero());
+    }
+
+    let p = G1Affine::new(decode_fq(&slice[..32])?, decode_fq(&slice[32..])?, false);
+    guard!(p.is_on_curve(), CouldNotProcessProof);
+
+    Ok(p)
+}
+
+pub fn decode_g2(slice: &[u8]) -> Result<G2Affine, ElusivError> {
+    guard!(slice.len() == G2_SIZE, CouldNotProcessProof);
+    if slice == [0; G2_SIZE] {
+        return Ok(G2Affine::zero());
+    }
+
+    let x = ark_bn254::Fq2::new(decode_fq(&slice[32..64])?, decode_fq(&slice[..32])?);
+    let y = ark_bn254::Fq2::new(decode_fq(&slice[96..])?, decode_fq(&slice[64..96])?);
+    let p = G2Affine::new(x, y, false);
+    guard!(
+        p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve(),
+        CouldNotProcessProof
+    );
+
+    Ok(p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::test_proofs::{invalid_proofs, valid_proofs};
+    use crate::proof::vkey::{SendQuadraVKey, TestVKey, VerifyingKeyInfo};
+    use ark_ff::BigInteger;
+
+    #[test]
+    fn test_encoding() {
+        let p = valid_proofs()[0].proof;
+
+        assert_eq!(decode_g1(&encode_g1(&p.a.0)).unwrap(), p.a.0);
+        assert_eq!(decode_g2(&encode_g2(&p.b.0)).unwrap(), p.b.0);
+
+        let zero = G1Affine::zero();
+        assert_eq!(encode_g1(&zero), [0; G1_SIZE]);
+        assert_eq!(decode_g1(&[0; G1_SIZE]).unwrap(), zero);
+
+        // Not on the curve
+        assert_eq!(decode_g1(&[1; G1_SIZE]), Err(CouldNotProcessProof));
+        assert_eq!(decode_g2(&[1; G2_SIZE]), Err(CouldNotProcessProof));
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        let source = TestVKey::verifying_key_source();
+        let vkey = VerifyingKey::new(&source, TestVKey::public_inputs_count()).unwrap();
+
+        for p in valid_proofs() {
+            assert_eq!(verify_proof(&vkey, &p.proof, &p.public_inputs), Ok(true));
+        }
+
+        for p in invalid_proofs() {
+            assert_ne!(verify_proof(&vkey, &p.proof, &p.public_inputs), Ok(true));
+        }
+
+        // Invalid public inputs count
+        let p = &valid_proofs()[0];
+        assert_eq!(
+            verify_proof(&vkey, &p.proof, &p.public_inputs[1..]),
+            Err(CouldNotProcessProof)
+        );
+    }
+
+    #[test]
+    fn test_verify_proofs_batched() {
+        let source = TestVKey::verifying_key_source();
+        let vkey = VerifyingKey::new(&source, TestVKey::public_inputs_count()).unwrap();
+        let valid: Vec<(Proof, Vec<U256>)> = valid_proofs()
+            .into_iter()
+            .map(|p| (p.proof, p.public_inputs))
+            .collect();
+
+        assert_eq!(verify_proofs_batched(&vkey, &valid), Ok(true));
+        assert_eq!(verify_proofs_batched(&vkey, &valid[..1]), Ok(true));
+
+        // A single invalid proof invalidates the batch
+        for p in invalid_proofs() {
+            let mut batch = valid.clone();
+            batch.push((p.proof, p.public_inputs));
+            assert_ne!(verify_proofs_batched(&vkey, &batch), Ok(true));
+        }
+
+        // Public inputs of another proof
+        let mut batch = valid.clone();
+        batch[0].1 = valid[1].1.clone();
+        assert_ne!(verify_proofs_batched(&vkey, &batch), Ok(true));
+
+        assert_eq!(verify_proofs_batched(&vkey, &[]), Err(CouldNotProcessProof));
+    }
+
+    #[test]
+    fn test_non_canonical_public_inputs() {
+        let source = TestVKey::verifying_key_source();
+        let vkey = VerifyingKey::new(&source, TestVKey::public_inputs_count()).unwrap();
+        let p = &valid_proofs()[0];
+
+        // `public_input + r` is reduced to the same scalar
+        let mut public_inputs = p.public_inputs.clone();
+        let mut repr = crate::fields::u256_to_big_uint(&public_inputs[0]);
+        assert!(!repr.add_nocarry(&crate::fields::SCALAR_MODULUS_RAW));
+        public_inputs[0] = crate::fields::big_uint_to_u256(&repr);
+
+        assert_eq!(
+            verify_proof(&vkey, &p.proof, &public_inputs),
+            Err(CouldNotProcessProof)
+        );
+        assert_eq!(
+            verify_proofs_batched(&vkey, &[(p.proof, public_inputs)]),
+            Err(CouldNotProcessProof)
+        );
+    }
+
+    #[test]
+    fn test_batch_randomness() {
+        let source = TestVKey::verifying_key_source();
+        let vkey = VerifyingKey::new(&source, TestVKey::public_inputs_count()).unwrap();
+        let other_source = SendQuadraVKey::verifying_key_source();
+        let other_vkey =
+            VerifyingKey::new(&other_source, SendQuadraVKey::public_inputs_count()).unwrap();
+        let proofs: Vec<(Proof, Vec<U256>)> = valid_proofs()
+            .into_iter()
+            .map(|p| (p.proof, p.public_inputs))
+            .collect();
+
+        let r = batch_randomness(&vkey, &proofs).unwrap();
+        assert_eq!(r.len(), proofs.len());
+        assert_eq!(r, batch_randomness(&vkey, &proofs).unwrap());
+
+        // The challenges are bound to the vkey
+        assert_ne!(r, batch_randomness(&other_vkey, &proofs).unwrap());
+    }
+}
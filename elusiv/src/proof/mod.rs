@@ -1,5 +1,7 @@
+#[cfg(any(test, feature = "alt-bn128"))]
+pub mod alt_bn128;
 pub mod verifier;
 pub mod vkey;
 
 #[cfg(test)]
-mod test_proofs;
+pub(crate) mod test_proofs;
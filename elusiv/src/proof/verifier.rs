@@ -16,7 +16,7 @@ use crate::error::ElusivError::{
 use crate::error::ElusivResult;
 use crate::fields::{G2HomProjective, Wrap, G1A, G2A};
 use crate::processor::COMPUTE_VERIFICATION_IX_COUNT;
-use crate::state::proof::{RAMFq, VerificationAccount, VerificationState};
+use crate::state::proof::{RAMFq, VerificationAccount, VerificationState, VerifierRAMAccount};
 use crate::types::U256;
 use ark_bn254::{
     Fq, Fq12, Fq12Parameters, Fq2, Fq6, Fq6Parameters, G1Affine, G1Projective, G2Affine, Parameters,
@@ -45,6 +45,7 @@ pub enum VerificationStep {
 /// Requires `verification_account.prepare_inputs_instructions_count + COMBINED_MILLER_LOOP_IXS + FINAL_EXPONENTIATION_IXS` calls to verify a valid proof
 pub fn verify_partial(
     verification_account: &mut VerificationAccount,
+    ram_account: &mut VerifierRAMAccount,
     vkey: &VerifyingKey,
     instruction_index: u16,
 ) -> Result<Option<bool>, ElusivError> {
@@ -59,8 +60,8 @@ pub fn verify_partial(
                 return Ok(None);
             }
 
-            prepare_public_inputs(verification_account, vkey, instruction, round)?;
-            verification_account.serialize_rams().unwrap();
+            prepare_public_inputs(verification_account, ram_account, vkey, instruction, round)?;
+            ram_account.serialize_rams().unwrap();
         }
         VerificationStep::CombinedMillerLoop => {
             // Proof first has to be setup
@@ -69,8 +70,8 @@ pub fn verify_partial(
                 InvalidAccountState
             );
 
-            combined_miller_loop(verification_account, vkey, instruction, round)?;
-            verification_account.serialize_rams().unwrap();
+            combined_miller_loop(verification_account, ram_account, vkey, instruction, round)?;
+            ram_account.serialize_rams().unwrap();
         }
         VerificationStep::FinalExponentiation => {
             // This enables us to use a uniform number of ixs per tx (by only allowing the last ix to perform the computation)
@@ -78,8 +79,9 @@ pub fn verify_partial(
                 return Ok(None);
             }
 
-            let v = final_exponentiation(verification_account, vkey, instruction, round);
-            verification_account.serialize_rams().unwrap();
+            let v =
+                final_exponentiation(verification_account, ram_account, vkey, instruction, round);
+            ram_account.serialize_rams().unwrap();
             return v;
         }
     }
@@ -89,13 +91,20 @@ pub fn verify_partial(
 
 pub fn prepare_public_inputs(
     verification_account: &mut VerificationAccount,
+    ram_account: &mut VerifierRAMAccount,
     vkey: &VerifyingKey,
     instruction: usize,
     round: usize,
 ) -> ElusivResult {
     let rounds = verification_account.get_prepare_inputs_instructions(instruction);
 
-    let result = prepare_public_inputs_partial(round, rounds as usize, verification_account, vkey);
+    let result = prepare_public_inputs_partial(
+        round,
+        rounds as usize,
+        verification_account,
+        ram_account,
+        vkey,
+    );
 
     if round + rounds as usize == prepare_public_inputs_rounds(vkey.public_inputs_count) {
         let prepared_inputs = result.ok_or(CouldNotProcessProof)?;
@@ -117,6 +126,7 @@ pub fn prepare_public_inputs(
 
 pub fn combined_miller_loop(
     verification_account: &mut VerificationAccount,
+    ram_account: &mut VerifierRAMAccount,
     vkey: &VerifyingKey,
     instruction: usize,
     round: usize,
@@ -136,7 +146,7 @@ pub fn combined_miller_loop(
     for round in round..round + rounds {
         result = combined_miller_loop_partial(
             round,
-            verification_account,
+            ram_account,
             vkey,
             &a,
             &b,
@@ -172,6 +182,7 @@ pub fn combined_miller_loop(
 
 pub fn final_exponentiation(
     verification_account: &mut VerificationAccount,
+    ram_account: &mut VerifierRAMAccount,
     vkey: &VerifyingKey,
     instruction: usize,
     round: usize,
@@ -187,7 +198,7 @@ pub fn final_exponentiation(
 
     let mut result = None;
     for round in round..round + rounds {
-        result = final_exponentiation_partial(round, verification_account, &f)?;
+        result = final_exponentiation_partial(round, ram_account, &f)?;
     }
 
     verification_account.set_round(&usize_as_u32_safe(round + rounds));
@@ -230,10 +241,11 @@ const fn prepare_public_inputs_rounds(public_inputs_count: usize) -> usize {
 fn prepare_public_inputs_partial(
     round: usize,
     rounds: usize,
-    storage: &mut VerificationAccount,
+    storage: &VerificationAccount,
+    ram_account: &mut VerifierRAMAccount,
     vkey: &VerifyingKey,
 ) -> Option<G1Affine> {
-    let mut acc: G1Projective = read_g1_p!(storage.ram_fq, 3);
+    let mut acc: G1Projective = read_g1_p!(ram_account.ram_fq, 3);
     let mut input_index = round / PREPARE_PUBLIC_INPUTS_ROUNDS;
     let mut public_input = storage.get_public_input(input_index).skip_mr();
 
@@ -251,7 +263,7 @@ fn prepare_public_inputs_partial(
             let mut g_ic = if input_index == 0 {
                 vkey.gamma_abc_base()
             } else {
-                read_g1_p!(storage.ram_fq, 0)
+                read_g1_p!(ram_account.ram_fq, 0)
             };
 
             if public_input != [0; 32] {
@@ -259,7 +271,7 @@ fn prepare_public_inputs_partial(
             }
 
             if input_index < vkey.public_inputs_count - 1 {
-                write_g1_projective(&mut storage.ram_fq, &g_ic, 0);
+                write_g1_projective(&mut ram_account.ram_fq, &g_ic, 0);
 
                 input_index += 1;
                 public_input = storage.get_public_input(input_index).skip_mr();
@@ -269,7 +281,7 @@ fn prepare_public_inputs_partial(
         }
     }
 
-    write_g1_projective(&mut storage.ram_fq, &acc, 3);
+    write_g1_projective(&mut ram_account.ram_fq, &acc, 3);
 
     None
 }
@@ -390,7 +402,7 @@ elusiv_computations!(
 
     // Doubling step
     // https://github.com/arkworks-rs/algebra/blob/6ea310ef09f8b7510ce947490919ea6229bbecd6/ec/src/models/bn/g2.rs#L139
-    doubling_step(storage: &mut VerificationAccount, r: &mut G2HomProjective) -> Coefficients {
+    doubling_step(storage: &mut VerifierRAMAccount, r: &mut G2HomProjective) -> Coefficients {
         {   /// 43_000
             let mut a: Fq2 = r.x * r.y;
             a = mul_by_fp(&a, TWO_INV);
@@ -418,7 +430,7 @@ elusiv_computations!(
 
     // Addition step
     // https://github.com/arkworks-rs/algebra/blob/6ea310ef09f8b7510ce947490919ea6229bbecd6/ec/src/models/bn/g2.rs#L168
-    addition_step(storage: &mut VerificationAccount, r: &mut G2HomProjective, q: &G2Affine) -> Coefficients {
+    addition_step(storage: &mut VerifierRAMAccount, r: &mut G2HomProjective, q: &G2Affine) -> Coefficients {
         {   /// 43_000
             let theta: Fq2 = r.y - (q.y * r.z);
             let lambda: Fq2 = r.x - (q.x * r.z);
@@ -445,7 +457,7 @@ elusiv_computations!(
 
     // Mul by characteristics
     // https://github.com/arkworks-rs/algebra/blob/6ea310ef09f8b7510ce947490919ea6229bbecd6/ec/src/models/bn/g2.rs#L127
-    mul_by_characteristics(storage: &mut VerificationAccount, r: &G2Affine) -> G2Affine {
+    mul_by_characteristics(storage: &mut VerifierRAMAccount, r: &G2Affine) -> G2Affine {
         {   /// 12_000
             let mut x: Fq2 = frobenius_map_fq2_one(r.x);
             x = x * TWIST_MUL_BY_Q_X;
@@ -460,7 +472,7 @@ elusiv_computations!(
     // f.mul_by_034(c0, c1, coeffs.2); (with: self -> f; c0 -> c0; d0 -> c1; d1 -> coeffs.2)
     // https://github.com/arkworks-rs/r1cs-std/blob/b7874406ec614748608b1739b1578092a8c97fb8/src/fields/fp12.rs#L43
     mul_by_034(
-        storage: &mut VerificationAccount,
+        storage: &mut VerifierRAMAccount,
         c0: &Fq2, d0: &Fq2, d1: &Fq2, f: Fq12
     ) -> Fq12 {
         {   /// 20_500
@@ -482,7 +494,7 @@ elusiv_computations!(
     // - multi_ell combines those three calls in one function
     // - normal ell implementation: https://github.com/arkworks-rs/algebra/blob/6ea310ef09f8b7510ce947490919ea6229bbecd6/ec/src/models/bn/mod.rs#L59
     combined_ell(
-        storage: &mut VerificationAccount,
+        storage: &mut VerifierRAMAccount,
         vkey: &VerifyingKey,
         a: &G1Affine, prepared_inputs: &G1Affine, c: &G1Affine,
         c0: &Fq2, c1: &Fq2, c2: &Fq2, coeff_index: usize, f: Fq12,
@@ -537,7 +549,7 @@ elusiv_computations!(
     // - the miller loop receives an iterator over 3 elements (https://github.com/arkworks-rs/groth16/blob/765817f77a6e14964c6f264d565b18676b11bd59/src/verifier.rs#L41)
     // - for B we need to generate the coefficients (all other coefficients are already generated before compilation)
     combined_miller_loop(
-        storage: &mut VerificationAccount,
+        storage: &mut VerifierRAMAccount,
         vkey: &VerifyingKey,
         a: &G1Affine, b: &G2Affine, c: &G1Affine, prepared_inputs: &G1Affine,
         r: &mut G2HomProjective, j: &mut usize, alt_b: &mut G2A,
@@ -635,7 +647,7 @@ elusiv_computations!(
 
     // https://github.com/arkworks-rs/algebra/blob/80857c9714c5a59068f8c20f1298e2138440a1d0/ff/src/fields/models/quadratic_extension.rs#L688
     // Guide to Pairing-based cryprography, Algorithm 5.16.
-    /*mul_fq12(storage: &mut VerificationAccount, a: Fq12, b: Fq12) -> Fq12 {
+    /*mul_fq12(storage: &mut VerifierRAMAccount, a: Fq12, b: Fq12) -> Fq12 {
         {   /// 63_000
             let v0: Fq6 = a.c0 * b.c0;
             let v1: Fq6 = a.c1 * b.c1;
@@ -654,7 +666,7 @@ elusiv_computations!(
 
     // https://github.com/arkworks-rs/algebra/blob/4dd6c3446e8ab22a2ba13505a645ea7b3a69f493/ff/src/fields/models/quadratic_extension.rs#L366
     // Guide to Pairing-based Cryptography, Algorithm 5.19.
-    inverse_fq12(storage: &mut VerificationAccount, f: Fq12) -> Fq12 {
+    inverse_fq12(storage: &mut VerifierRAMAccount, f: Fq12) -> Fq12 {
         {   /// 28_500
             let v1: Fq6 = f.c1.square();
         }
@@ -674,7 +686,7 @@ elusiv_computations!(
     // Using exp_by_neg_x and cyclotomic_exp
     // https://github.com/arkworks-rs/algebra/blob/6ea310ef09f8b7510ce947490919ea6229bbecd6/ec/src/models/bn/mod.rs#L78
     // https://github.com/arkworks-rs/algebra/blob/6ea310ef09f8b7510ce947490919ea6229bbecd6/ff/src/fields/models/fp12_2over3over2.rs#L56
-    exp_by_neg_x(storage: &mut VerificationAccount, fe: Fq12) -> Fq12 {
+    exp_by_neg_x(storage: &mut VerifierRAMAccount, fe: Fq12) -> Fq12 {
         {   /// 1_500
             let fe_inverse: Fq12 = conjugate(fe);
             let res: Fq12 = Fq12::one();
@@ -710,7 +722,7 @@ elusiv_computations!(
 
     // Final exponentiation
     // - reference implementation: https://github.com/arkworks-rs/algebra/blob/6ea310ef09f8b7510ce947490919ea6229bbecd6/ec/src/models/bn/mod.rs#L153
-    final_exponentiation(storage: &mut VerificationAccount, f: &Fq12) -> Fq12 {
+    final_exponentiation(storage: &mut VerifierRAMAccount, f: &Fq12) -> Fq12 {
         {   /// 1_500
             let r: Fq12 = conjugate(*f);
             let q: Fq12 = r;
@@ -1065,6 +1077,7 @@ mod tests {
 
         // First version
         zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut ram, VerifierRAMAccount);
         for (i, public_input) in public_inputs.iter().enumerate() {
             storage.set_public_input(i, &RawU256::new(u256_from_str_skip_mr(public_input)));
         }
@@ -1082,7 +1095,8 @@ mod tests {
         let result = prepare_public_inputs_partial(
             0,
             prepare_public_inputs_rounds(TestVKey::public_inputs_count()),
-            &mut storage,
+            &storage,
+            &mut ram,
             &vkey,
         )
         .unwrap();
@@ -1096,12 +1110,14 @@ mod tests {
 
         // Second version
         zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut ram, VerifierRAMAccount);
         let public_inputs = valid_proofs()[0].public_inputs.clone();
         setup_storage_account::<TestVKey>(&mut storage, valid_proofs()[0].proof, &public_inputs);
 
         for i in 0..storage.get_prepare_inputs_instructions_count() {
             let round = storage.get_round();
-            prepare_public_inputs(&mut storage, &vkey, i as usize, round as usize).unwrap();
+            prepare_public_inputs(&mut storage, &mut ram, &vkey, i as usize, round as usize)
+                .unwrap();
         }
         let expected = prepare_inputs(
             &pvk,
@@ -1117,7 +1133,7 @@ mod tests {
 
     #[test]
     fn test_mul_by_characteristics() {
-        zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut storage, VerifierRAMAccount);
         let mut value: Option<G2Affine> = None;
         for round in 0..MUL_BY_CHARACTERISTICS_ROUNDS_COUNT {
             value = mul_by_characteristics_partial(round, &mut storage, &g2_affine()).unwrap();
@@ -1129,7 +1145,7 @@ mod tests {
     #[test]
     fn test_combined_ell() {
         vkey!(vkey, TestVKey);
-        zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut storage, VerifierRAMAccount);
         let mut value: Option<Fq12> = None;
         let a = G1Affine::new(
             Fq::from_str(
@@ -1194,7 +1210,7 @@ mod tests {
     #[test]
     fn test_combined_miller_loop() {
         vkey!(vkey, TestVKey);
-        zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut storage, VerifierRAMAccount);
         let prepared_inputs = G1Affine::new(
             Fq::new(BigInteger256([
                 8166105574990738357,
@@ -1277,6 +1293,7 @@ mod tests {
 
         // Second version
         zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut ram, VerifierRAMAccount);
         storage.a.set(proof.a);
         storage.b.set(proof.b);
         storage.c.set(proof.c);
@@ -1285,14 +1302,14 @@ mod tests {
 
         for i in 0..COMBINED_MILLER_LOOP_IXS {
             let round = storage.get_round();
-            combined_miller_loop(&mut storage, &vkey, i, round as usize).unwrap();
+            combined_miller_loop(&mut storage, &mut ram, &vkey, i, round as usize).unwrap();
         }
         assert_eq!(storage.f.get().0, expected);
     }
 
     #[test]
     fn test_addition_step() {
-        zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut storage, VerifierRAMAccount);
         let q = g2_affine();
         let mut r = G2HomProjective {
             x: Fq2::new(
@@ -1343,7 +1360,7 @@ mod tests {
 
     #[test]
     fn test_doubling_step() {
-        zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut storage, VerifierRAMAccount);
         let mut r = G2HomProjective {
             x: Fq2::new(
                 Fq::from_str(
@@ -1404,7 +1421,7 @@ mod tests {
 
     #[test]
     fn test_inverse_fq12() {
-        zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut storage, VerifierRAMAccount);
         let mut value: Option<Fq12> = None;
         for round in 0..INVERSE_FQ12_ROUNDS_COUNT {
             value = inverse_fq12_partial(round, &mut storage, f()).unwrap();
@@ -1415,7 +1432,7 @@ mod tests {
 
     #[test]
     fn test_exp_by_neg_x() {
-        zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut storage, VerifierRAMAccount);
         let mut value: Option<Fq12> = None;
         for round in 0..EXP_BY_NEG_X_ROUNDS_COUNT {
             value = exp_by_neg_x_partial(round, &mut storage, f()).unwrap();
@@ -1429,7 +1446,7 @@ mod tests {
         vkey!(vkey, TestVKey);
 
         // First version
-        zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut storage, VerifierRAMAccount);
         let mut value = None;
         for round in 0..FINAL_EXPONENTIATION_ROUNDS_COUNT {
             value = final_exponentiation_partial(round, &mut storage, &f()).unwrap();
@@ -1440,12 +1457,13 @@ mod tests {
 
         // Second version
         zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut ram, VerifierRAMAccount);
         storage.set_step(&VerificationStep::FinalExponentiation);
         storage.f.set(Wrap(f()));
 
         for i in 0..FINAL_EXPONENTIATION_IXS {
             let round = storage.get_round();
-            final_exponentiation(&mut storage, &vkey, i, round as usize).unwrap();
+            final_exponentiation(&mut storage, &mut ram, &vkey, i, round as usize).unwrap();
         }
         assert_eq!(storage.f.get().0, expected);
     }
@@ -1476,6 +1494,10 @@ mod tests {
             hashed_inputs: u256_from_str_skip_mr("230508240750559904196809564625"),
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
+            expiry: 0,
+            unlock_timestamp: 0,
+            earliest_execution_slot: 0,
+            latest_execution_slot: 0,
         };
         let p = public_inputs.public_signals_skip_mr();
         let v = prepare_public_inputs_instructions(&p, TestVKey::public_inputs_count());
@@ -1501,6 +1523,7 @@ mod tests {
         vkey: &VerifyingKey,
     ) -> bool {
         zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut ram, VerifierRAMAccount);
         setup_storage_account::<VKey>(&mut storage, proof, public_inputs);
         let instruction_count = storage.get_prepare_inputs_instructions_count() as usize
             + COMBINED_MILLER_LOOP_IXS
@@ -1508,7 +1531,13 @@ mod tests {
 
         let mut result = None;
         for _ in 0..instruction_count {
-            result = verify_partial(&mut storage, vkey, COMPUTE_VERIFICATION_IX_COUNT - 1).unwrap();
+            result = verify_partial(
+                &mut storage,
+                &mut ram,
+                vkey,
+                COMPUTE_VERIFICATION_IX_COUNT - 1,
+            )
+            .unwrap();
         }
 
         result.unwrap()
@@ -1540,6 +1569,7 @@ mod tests {
         let proof = valid_proofs()[0].proof;
         let public_inputs = valid_proofs()[0].public_inputs.clone();
         zero_program_account!(mut storage, VerificationAccount);
+        zero_program_account!(mut ram, VerifierRAMAccount);
         setup_storage_account::<TestVKey>(&mut storage, proof, &public_inputs);
         let instruction_count = storage.get_prepare_inputs_instructions_count() as usize
             + COMBINED_MILLER_LOOP_IXS
@@ -1548,12 +1578,23 @@ mod tests {
         vkey!(vkey, TestVKey);
 
         for _ in 0..instruction_count {
-            verify_partial(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1).unwrap();
+            verify_partial(
+                &mut storage,
+                &mut ram,
+                &vkey,
+                COMPUTE_VERIFICATION_IX_COUNT - 1,
+            )
+            .unwrap();
         }
 
         // Additional ix will result in error
         assert_eq!(
-            verify_partial(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1),
+            verify_partial(
+                &mut storage,
+                &mut ram,
+                &vkey,
+                COMPUTE_VERIFICATION_IX_COUNT - 1
+            ),
             Err(ElusivError::ComputationIsAlreadyFinished)
         );
     }
@@ -166,7 +166,18 @@ impl<'a> VerifyingKey<'a> {
         Wrap::try_from_slice(slice).unwrap().0
     }
 
-    #[cfg(feature = "elusiv-client")]
+    /// Returns the hash of the Groth16 key components (`gamma_abc_base`, `alpha`, `beta`, `gamma`, `delta`)
+    ///
+    /// # Note
+    ///
+    /// All other values are precomputed from these components.
+    pub fn key_hash(&self) -> [u8; 32] {
+        let gamma_abc_base = &self.source[Wrap::<Fq12>::SIZE..Wrap::<Fq12>::SIZE + G1A::SIZE];
+        let components = &self.source[self.source.len() - G1A::SIZE - 3 * G2A::SIZE..];
+
+        solana_program::hash::hashv(&[gamma_abc_base, components]).to_bytes()
+    }
+
     pub fn alpha(&self) -> G1Affine {
         let offset =
             Wrap::<Fq12>::SIZE + G1A::SIZE + self.gamma_abc_size + 2 * Self::COEFFS_ARRAY_SIZE;
@@ -174,7 +185,6 @@ impl<'a> VerifyingKey<'a> {
         G1A::try_from_slice(slice).unwrap().0
     }
 
-    #[cfg(feature = "elusiv-client")]
     pub fn beta(&self) -> ark_bn254::G2Affine {
         let offset = Wrap::<Fq12>::SIZE
             + G1A::SIZE
@@ -185,7 +195,6 @@ impl<'a> VerifyingKey<'a> {
         G2A::try_from_slice(slice).unwrap().0
     }
 
-    #[cfg(feature = "elusiv-client")]
     pub fn gamma(&self) -> ark_bn254::G2Affine {
         let offset = Wrap::<Fq12>::SIZE
             + G1A::SIZE
@@ -197,7 +206,6 @@ impl<'a> VerifyingKey<'a> {
         G2A::try_from_slice(slice).unwrap().0
     }
 
-    #[cfg(feature = "elusiv-client")]
     pub fn delta(&self) -> ark_bn254::G2Affine {
         let offset = Wrap::<Fq12>::SIZE
             + G1A::SIZE
@@ -326,6 +334,19 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_vkey_key_hash() {
+        let send_source = SendQuadraVKey::verifying_key_source();
+        let send_vkey =
+            VerifyingKey::new(&send_source, SendQuadraVKey::public_inputs_count()).unwrap();
+        let migrate_source = MigrateUnaryVKey::verifying_key_source();
+        let migrate_vkey =
+            VerifyingKey::new(&migrate_source, MigrateUnaryVKey::public_inputs_count()).unwrap();
+
+        assert_eq!(send_vkey.key_hash(), send_vkey.key_hash());
+        assert_ne!(send_vkey.key_hash(), migrate_vkey.key_hash());
+    }
+
     #[test]
     fn test_send_quadra_vkey() {
         test_vkey::<SendQuadraVKey>()
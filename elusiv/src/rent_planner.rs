@@ -0,0 +1,415 @@
+//! Rent-burden report and reclaim planner for clients (wardens, operators)
+//!
+//! # Note
+//!
+//! - [`rent_report`] classifies all program accounts (e.g. fetched with `getProgramAccounts`) by their size into [`RentSubsystem`]s.
+//! - Only rent (and not the funds of the pool, fee collector or quarantine) is counted as locked.
+//! - At the moment only closed reusable [`VerificationAccount`]s can be reclaimed, archived MTs are kept until N-SMT archiving is implemented.
+
+use crate::instruction::ElusivInstruction;
+use crate::processor::{MAX_CLOSE_BATCH_SIZE, MAX_VERIFICATION_ACCOUNT_INSTANCES};
+use crate::state::circuit_registry::CircuitRegistryAccount;
+use crate::state::commitment::{
+    BaseCommitmentBufferAccount, BaseCommitmentHashingAccount, BaseCommitmentHashingSlotsAccount,
+    CommitmentBufferAccount, CommitmentHashingAccount, CommitmentQueueAccount,
+    COMMITMENT_QUEUE_GROWTH, COMMITMENT_QUEUE_LEN, MAX_COMMITMENT_QUEUE_LEN,
+};
+use crate::state::fee::FeeAccount;
+use crate::state::governor::{
+    FeatureFlagsAccount, GovernorAccount, PoolYieldAccount, TokenRegistryAccount,
+    UpgradeGateAccount,
+};
+use crate::state::history::VerificationHistoryAccount;
+use crate::state::metadata::{
+    CommitmentMetadataAccount, MetadataAccount, MetadataChildAccount, MetadataQueueAccount,
+};
+use crate::state::metrics::MetricsAccount;
+use crate::state::nullifier::{ArchivedNullifierAccount, NullifierAccount, NullifierChildAccount};
+use crate::state::pending_release::PendingReleaseAccount;
+use crate::state::program_account::{
+    ChildAccount, ChildAccountConfig, PDAAccount, PDAAccountData, ProgramAccount, SizedAccount,
+};
+use crate::state::proof::{
+    BatchVerificationAccount, NullifierDuplicateAccount, VerificationAccount, VerificationState,
+    VerifierRAMAccount,
+};
+use crate::state::recipient::RecipientPreferenceAccount;
+use crate::state::storage::{StorageAccount, StorageChildAccount, TreeConfigAccount};
+use crate::state::viewing_key::ViewingKeyAccount;
+use crate::state::vkey::{VKeyAccount, VKeyChildAccount};
+use crate::state::warden_nonce::WardenNonceAccount;
+use crate::state::warden_usage::WardenUsageAccount;
+use elusiv_types::{BorshSerDeSized, WritableSignerAccount, WritableUserAccount};
+use solana_program::{instruction::Instruction, pubkey::Pubkey, rent::Rent};
+use std::collections::BTreeMap;
+
+/// The subsystems of the program (used to attribute locked rent)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RentSubsystem {
+    /// Governor, fees, pool, fee collector, quarantine, upgrade gate, token registry, metrics and feature-flags
+    Governance,
+
+    /// Commitment queues, buffers and hashing accounts
+    CommitmentHashing,
+
+    /// The active MT, the commitment metadata and the encrypted notes
+    Storage,
+
+    /// Nullifier accounts (active and archived) and nullifier duplicate accounts
+    Nullifiers,
+
+    /// Verification, verifier RAM, verification history, warden nonce and warden usage accounts
+    Verifications,
+
+    VKeys,
+
+    /// Recipient preferences and viewing keys
+    Recipients,
+
+    /// Accounts with an unknown size
+    Unknown,
+}
+
+fn child_account_size<C: ChildAccount>() -> usize {
+    C::INNER_SIZE + ChildAccountConfig::SIZE
+}
+
+/// Returns the [`RentSubsystem`] of a program account with `data_len` bytes
+///
+/// # Note
+///
+/// Singleton accounts without any data besides the [`PDAAccountData`] (pool, fee collector, quarantine) are attributed to [`RentSubsystem::Governance`].
+pub fn account_subsystem(data_len: usize) -> RentSubsystem {
+    use RentSubsystem::*;
+
+    let sizes = [
+        (PDAAccountData::SIZE, Governance),
+        (GovernorAccount::SIZE, Governance),
+        (FeeAccount::SIZE, Governance),
+        (TokenRegistryAccount::SIZE, Governance),
+        (UpgradeGateAccount::SIZE, Governance),
+        (MetricsAccount::SIZE, Governance),
+        (FeatureFlagsAccount::SIZE, Governance),
+        (PoolYieldAccount::SIZE, Governance),
+        (
+            <CommitmentQueueAccount as SizedAccount>::SIZE,
+            CommitmentHashing,
+        ),
+        (
+            <MetadataQueueAccount as SizedAccount>::SIZE,
+            CommitmentHashing,
+        ),
+        (BaseCommitmentBufferAccount::SIZE, CommitmentHashing),
+        (CommitmentBufferAccount::SIZE, CommitmentHashing),
+        (CommitmentHashingAccount::SIZE, CommitmentHashing),
+        (BaseCommitmentHashingAccount::SIZE, CommitmentHashing),
+        (BaseCommitmentHashingSlotsAccount::SIZE, CommitmentHashing),
+        (StorageAccount::SIZE, Storage),
+        (TreeConfigAccount::SIZE, Storage),
+        (child_account_size::<StorageChildAccount>(), Storage),
+        (MetadataAccount::SIZE, Storage),
+        (child_account_size::<MetadataChildAccount>(), Storage),
+        (CommitmentMetadataAccount::SIZE, Storage),
+        (NullifierAccount::SIZE, Nullifiers),
+        (child_account_size::<NullifierChildAccount>(), Nullifiers),
+        (ArchivedNullifierAccount::SIZE, Nullifiers),
+        (NullifierDuplicateAccount::SIZE, Nullifiers),
+        (VerificationAccount::SIZE, Verifications),
+        (VerifierRAMAccount::SIZE, Verifications),
+        (BatchVerificationAccount::SIZE, Verifications),
+        (VerificationHistoryAccount::SIZE, Verifications),
+        (WardenNonceAccount::SIZE, Verifications),
+        (WardenUsageAccount::SIZE, Verifications),
+        (VKeyAccount::SIZE, VKeys),
+        (child_account_size::<VKeyChildAccount>(), VKeys),
+        (CircuitRegistryAccount::SIZE, VKeys),
+        (RecipientPreferenceAccount::SIZE, Recipients),
+        (ViewingKeyAccount::SIZE, Recipients),
+        (PendingReleaseAccount::SIZE, Recipients),
+    ];
+
+    if is_grown_queue_size(
+        data_len,
+        <CommitmentQueueAccount as SizedAccount>::SIZE,
+        CommitmentQueueAccount::SLOT_SIZE,
+    ) || is_grown_queue_size(
+        data_len,
+        <MetadataQueueAccount as SizedAccount>::SIZE,
+        MetadataQueueAccount::SLOT_SIZE,
+    ) {
+        return CommitmentHashing;
+    }
+
+    sizes
+        .iter()
+        .find(|(size, _)| *size == data_len)
+        .map_or(Unknown, |(_, subsystem)| *subsystem)
+}
+
+/// Whether `data_len` is the size of a queue account (with `base_size` and `slot_size`) grown by `ResizeQueueAccount`
+fn is_grown_queue_size(data_len: usize, base_size: usize, slot_size: usize) -> bool {
+    let growth_size = COMMITMENT_QUEUE_GROWTH * slot_size;
+    let max_size = base_size + (MAX_COMMITMENT_QUEUE_LEN - COMMITMENT_QUEUE_LEN) * slot_size;
+
+    data_len > base_size && data_len <= max_size && (data_len - base_size) % growth_size == 0
+}
+
+/// A program account (as returned by the RPC)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramAccountSnapshot {
+    pub pubkey: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockedRent {
+    pub account_count: usize,
+    pub lamports: u64,
+}
+
+/// A closed reusable [`VerificationAccount`], whose rent can be reclaimed by its fee payer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReclaimableVerificationAccount {
+    pub pubkey: Pubkey,
+    pub fee_payer: Pubkey,
+    pub verification_account_index: u8,
+    pub lamports: u64,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RentReport {
+    pub locked_rent: BTreeMap<RentSubsystem, LockedRent>,
+    pub reclaimable: Vec<ReclaimableVerificationAccount>,
+}
+
+impl RentReport {
+    pub fn total_locked_rent(&self) -> u64 {
+        self.locked_rent
+            .values()
+            .fold(0, |acc, rent| acc.saturating_add(rent.lamports))
+    }
+
+    /// Returns the accounts reclaimable by `fee_payer`
+    pub fn reclaimable_by(
+        &self,
+        fee_payer: &Pubkey,
+    ) -> impl Iterator<Item = &ReclaimableVerificationAccount> {
+        let fee_payer = *fee_payer;
+        self.reclaimable
+            .iter()
+            .filter(move |account| account.fee_payer == fee_payer)
+    }
+
+    pub fn reclaimable_lamports(&self, fee_payer: &Pubkey) -> u64 {
+        self.reclaimable_by(fee_payer)
+            .fold(0, |acc, account| acc.saturating_add(account.lamports))
+    }
+
+    /// Returns the `CloseVerificationAccounts` instructions reclaiming all accounts of `fee_payer`
+    ///
+    /// # Note
+    ///
+    /// Unused entries of a batch are filled with the `fee_payer`.
+    pub fn reclaim_instructions(&self, fee_payer: Pubkey) -> Vec<Instruction> {
+        let accounts: Vec<_> = self.reclaimable_by(&fee_payer).collect();
+
+        accounts
+            .chunks(MAX_CLOSE_BATCH_SIZE)
+            .map(|batch| {
+                let mut pubkeys = [fee_payer; MAX_CLOSE_BATCH_SIZE];
+                let mut indices = [0; MAX_CLOSE_BATCH_SIZE];
+                for (i, account) in batch.iter().enumerate() {
+                    pubkeys[i] = account.pubkey;
+                    indices[i] = account.verification_account_index;
+                }
+
+                ElusivInstruction::close_verification_accounts_instruction(
+                    indices,
+                    batch.len() as u8,
+                    WritableSignerAccount(fee_payer),
+                    WritableUserAccount(pubkeys[0]),
+                    WritableUserAccount(pubkeys[1]),
+                    WritableUserAccount(pubkeys[2]),
+                    WritableUserAccount(pubkeys[3]),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Returns the [`ReclaimableVerificationAccount`] if `account` is a closed reusable [`VerificationAccount`]
+fn reclaimable_verification_account(
+    account: &ProgramAccountSnapshot,
+) -> Option<ReclaimableVerificationAccount> {
+    let mut data = account.data.clone();
+    let verification_account = VerificationAccount::new(&mut data).ok()?;
+    let other_data = verification_account.get_other_data();
+
+    if verification_account.get_state() != VerificationState::Closed || !other_data.reusable {
+        return None;
+    }
+
+    let fee_payer = Pubkey::new_from_array(other_data.fee_payer.skip_mr());
+    let verification_account_index = (0..MAX_VERIFICATION_ACCOUNT_INSTANCES).find(|index| {
+        VerificationAccount::find_with_pubkey(fee_payer, Some(*index as u32)).0 == account.pubkey
+    })?;
+
+    Some(ReclaimableVerificationAccount {
+        pubkey: account.pubkey,
+        fee_payer,
+        verification_account_index,
+        lamports: account.lamports,
+    })
+}
+
+/// Computes the locked rent per [`RentSubsystem`] and identifies all reclaimable accounts
+pub fn rent_report(accounts: &[ProgramAccountSnapshot], rent: &Rent) -> RentReport {
+    let mut report = RentReport::default();
+
+    for account in accounts {
+        let subsystem = account_subsystem(account.data.len());
+        let locked_rent = report.locked_rent.entry(subsystem).or_default();
+        locked_rent.account_count += 1;
+        locked_rent.lamports = locked_rent.lamports.saturating_add(
+            account
+                .lamports
+                .min(rent.minimum_balance(account.data.len())),
+        );
+
+        if subsystem == RentSubsystem::Verifications {
+            if let Some(reclaimable) = reclaimable_verification_account(account) {
+                report.reclaimable.push(reclaimable);
+            }
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::proof::VerificationAccountData;
+    use crate::types::RawU256;
+
+    fn verification_account(
+        fee_payer: &Pubkey,
+        index: u8,
+        state: VerificationState,
+    ) -> ProgramAccountSnapshot {
+        let mut data = vec![0; VerificationAccount::SIZE];
+        {
+            let mut verification_account = VerificationAccount::new(&mut data).unwrap();
+            verification_account.set_state(&state);
+            verification_account.set_other_data(&VerificationAccountData {
+                fee_payer: RawU256::new(fee_payer.to_bytes()),
+                reusable: true,
+                ..Default::default()
+            });
+        }
+
+        ProgramAccountSnapshot {
+            pubkey: VerificationAccount::find_with_pubkey(*fee_payer, Some(index as u32)).0,
+            lamports: 1_000_000,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_account_subsystem() {
+        assert_eq!(
+            account_subsystem(GovernorAccount::SIZE),
+            RentSubsystem::Governance
+        );
+        assert_eq!(
+            account_subsystem(StorageAccount::SIZE),
+            RentSubsystem::Storage
+        );
+        assert_eq!(
+            account_subsystem(VerificationAccount::SIZE),
+            RentSubsystem::Verifications
+        );
+        assert_eq!(account_subsystem(1), RentSubsystem::Unknown);
+
+        // Grown queues
+        let grown_size = <CommitmentQueueAccount as SizedAccount>::SIZE
+            + COMMITMENT_QUEUE_GROWTH * CommitmentQueueAccount::SLOT_SIZE;
+        assert_eq!(
+            account_subsystem(grown_size),
+            RentSubsystem::CommitmentHashing
+        );
+        assert_eq!(account_subsystem(grown_size + 1), RentSubsystem::Unknown);
+    }
+
+    #[test]
+    fn test_rent_report() {
+        let rent = Rent::default();
+        let fee_payer = Pubkey::new_unique();
+        let other_fee_payer = Pubkey::new_unique();
+
+        let mut accounts: Vec<_> = (0..5)
+            .map(|i| verification_account(&fee_payer, i, VerificationState::Closed))
+            .collect();
+        accounts.push(verification_account(
+            &fee_payer,
+            5,
+            VerificationState::ProofSetup,
+        ));
+        accounts.push(verification_account(
+            &other_fee_payer,
+            0,
+            VerificationState::Closed,
+        ));
+        accounts.push(ProgramAccountSnapshot {
+            pubkey: Pubkey::new_unique(),
+            lamports: u64::MAX,
+            data: vec![0; PDAAccountData::SIZE],
+        });
+
+        let report = rent_report(&accounts, &rent);
+
+        assert_eq!(
+            report.locked_rent[&RentSubsystem::Verifications],
+            LockedRent {
+                account_count: 7,
+                lamports: 7 * 1_000_000u64.min(rent.minimum_balance(VerificationAccount::SIZE)),
+            }
+        );
+
+        // Pool funds are not counted as rent
+        assert_eq!(
+            report.locked_rent[&RentSubsystem::Governance].lamports,
+            rent.minimum_balance(PDAAccountData::SIZE)
+        );
+
+        assert_eq!(report.reclaimable.len(), 6);
+        assert_eq!(report.reclaimable_by(&fee_payer).count(), 5);
+        assert_eq!(report.reclaimable_lamports(&fee_payer), 5 * 1_000_000);
+        assert_eq!(
+            report
+                .reclaimable_by(&fee_payer)
+                .nth(3)
+                .unwrap()
+                .verification_account_index,
+            3
+        );
+
+        let instructions = report.reclaim_instructions(fee_payer);
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(
+            instructions[1],
+            ElusivInstruction::close_verification_accounts_instruction(
+                [4, 0, 0, 0],
+                1,
+                WritableSignerAccount(fee_payer),
+                WritableUserAccount(accounts[4].pubkey),
+                WritableUserAccount(fee_payer),
+                WritableUserAccount(fee_payer),
+                WritableUserAccount(fee_payer),
+            )
+        );
+
+        assert!(report.reclaim_instructions(Pubkey::new_unique()).is_empty());
+    }
+}
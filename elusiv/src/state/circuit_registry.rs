@@ -0,0 +1,56 @@
+use crate::processor::MAX_NUMBER_OF_VKEYS;
+use crate::types::U256;
+use elusiv_proc_macros::elusiv_account;
+use elusiv_types::PDAAccountData;
+
+const VKEY_COUNT: usize = MAX_NUMBER_OF_VKEYS as usize;
+
+/// Registry of the circuit artifacts (indexed by `vkey_id`), the trust anchor for artifacts downloaded off-chain by wardens and wallets
+///
+/// # Note
+///
+/// - The proving-key hash and the public-input layout of a circuit are registered by governance.
+/// - The vkey hash and public inputs count are read from the [`crate::state::vkey::VKeyAccount`] at registration, so they match the vkey enforced by the program.
+/// - A registration is only valid for the vkey version it has been registered with (see [`CircuitRegistryAccount::is_registered`]).
+#[elusiv_account]
+pub struct CircuitRegistryAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The hash of the proving key (zkey) of each circuit
+    pub proving_key_hashes: [U256; VKEY_COUNT],
+
+    /// See [`crate::proof::vkey::VerifyingKey::key_hash`]
+    pub vkey_hashes: [U256; VKEY_COUNT],
+
+    /// The vkey version each circuit has been registered with (`0` if not registered)
+    pub vkey_versions: [u32; VKEY_COUNT],
+
+    pub public_inputs_counts: [u32; VKEY_COUNT],
+
+    /// The hash of the ordered public-input signal names of each circuit
+    pub public_inputs_layout_hashes: [U256; VKEY_COUNT],
+}
+
+impl<'a> CircuitRegistryAccount<'a> {
+    /// Returns true if the circuit with `vkey_id` is registered for the vkey version `vkey_version`
+    pub fn is_registered(&self, vkey_id: u32, vkey_version: u32) -> bool {
+        let index = vkey_id as usize;
+        index < VKEY_COUNT && vkey_version != 0 && self.get_vkey_versions(index) == vkey_version
+    }
+
+    /// Returns true if the artifacts with the hashes `proving_key_hash` and `vkey_hash` match the registered circuit with `vkey_id`
+    pub fn matches_artifacts(
+        &self,
+        vkey_id: u32,
+        proving_key_hash: &U256,
+        vkey_hash: &U256,
+    ) -> bool {
+        let index = vkey_id as usize;
+        index < VKEY_COUNT
+            && self.get_vkey_versions(index) != 0
+            && self.get_proving_key_hashes(index) == *proving_key_hash
+            && self.get_vkey_hashes(index) == *vkey_hash
+    }
+}
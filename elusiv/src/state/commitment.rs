@@ -1,22 +1,28 @@
 use super::metadata::CommitmentMetadata;
 use super::queue::{queue_account, RingQueue};
 use crate::buffer::buffer_account;
-use crate::bytes::usize_as_u32_safe;
+use crate::bytes::{usize_as_u32_safe, BorshSerDeSized};
 use crate::commitment::poseidon_hash::BinarySpongeHashingState;
-use crate::commitment::{commitments_per_batch, MAX_HT_SIZE, MT_HEIGHT};
+use crate::commitment::{
+    commitments_per_batch, MAX_COMMITMENT_BATCHING_RATE, MAX_HT_SIZE, MT_HEIGHT,
+};
 use crate::error::ElusivError;
 use crate::fields::{fr_to_u256_le, u256_to_fr_skip_mr};
-use crate::macros::{elusiv_account, guard, two_pow};
+use crate::macros::{elusiv_account, guard, two_pow, BorshSerDeSized};
 use crate::processor::{BaseCommitmentHashRequest, CommitmentHashRequest};
+use crate::state::fee::ProgramFee;
 use crate::state::program_account::PDAAccountData;
-use crate::state::storage::{StorageAccount, HISTORY_ARRAY_SIZE};
+use crate::state::storage::{StorageAccount, HISTORY_ARRAY_SIZE, MT_COMMITMENT_COUNT};
 use crate::types::U256;
 use ark_bn254::Fr;
 use ark_ff::{BigInteger256, PrimeField};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::entrypoint::ProgramResult;
 use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
 
 /// Account used for computing `commitment = h(base_commitment, amount)`
-#[elusiv_account(partial_computation: true, eager_type: true)]
+#[elusiv_account(partial_computation: true)]
 pub struct BaseCommitmentHashingAccount {
     #[no_getter]
     #[no_setter]
@@ -33,6 +39,9 @@ pub struct BaseCommitmentHashingAccount {
     pub state: BinarySpongeHashingState,
     pub min_batching_rate: u32,
     pub metadata: CommitmentMetadata,
+
+    /// Unix-timestamp of the last `setup`
+    pub setup_timestamp: u64,
 }
 
 impl<'a> BaseCommitmentHashingAccount<'a> {
@@ -69,8 +78,156 @@ impl<'a> BaseCommitmentHashingAccount<'a> {
     }
 }
 
+/// The number of [`BaseCommitmentHashingAccount`] instances managed by the [`BaseCommitmentHashingSlotsAccount`]
+pub const BASE_COMMITMENT_HASHING_SLOTS_COUNT: usize = 128;
+
+/// The number of slots an acquired [`BaseCommitmentHashingAccount`] instance stays reserved
+pub const BASE_COMMITMENT_HASHING_SLOT_RESERVATION: u64 = 750;
+
+/// The maximum number of pending (not yet stored) reservations of a single fee payer (one batch store)
+pub const BASE_COMMITMENT_HASHING_SLOTS_PER_FEE_PAYER: usize = 4;
+
+/// Reservations of the [`BaseCommitmentHashingAccount`] instances `0..BASE_COMMITMENT_HASHING_SLOTS_COUNT`
+///
+/// # Note
+///
+/// - Stores reject instances reserved by another fee payer and mark their instance as open until it is finalized or closed.
+/// - Only free instances (neither open nor reserved) are acquired.
+/// - A reservation expires after [`BASE_COMMITMENT_HASHING_SLOT_RESERVATION`] slots, long enough to store, compute and finalize a base-commitment hash.
+/// - A fee payer can hold at most [`BASE_COMMITMENT_HASHING_SLOTS_PER_FEE_PAYER`] pending reservations.
+#[elusiv_account]
+pub struct BaseCommitmentHashingSlotsAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The instance at which the search for the next free instance starts
+    pub next_index: u32,
+
+    pub reserved_by: [Pubkey; BASE_COMMITMENT_HASHING_SLOTS_COUNT],
+
+    /// The slot at which each reservation expires
+    pub reserved_until: [u64; BASE_COMMITMENT_HASHING_SLOTS_COUNT],
+
+    /// Whether the [`BaseCommitmentHashingAccount`] of an instance is open
+    pub is_open: [bool; BASE_COMMITMENT_HASHING_SLOTS_COUNT],
+}
+
+impl<'a> BaseCommitmentHashingSlotsAccount<'a> {
+    /// Reserves the next free instance for `fee_payer` and returns its index
+    pub fn acquire(&mut self, fee_payer: &Pubkey, slot: u64) -> Result<u32, ProgramError> {
+        let pending = (0..BASE_COMMITMENT_HASHING_SLOTS_COUNT)
+            .filter(|&i| {
+                self.get_reserved_by(i) == *fee_payer
+                    && !self.get_is_open(i)
+                    && slot < self.get_reserved_until(i)
+            })
+            .count();
+        guard!(
+            pending < BASE_COMMITMENT_HASHING_SLOTS_PER_FEE_PAYER,
+            ElusivError::NoFreeHashingSlot
+        );
+
+        let next_index = self.get_next_index() as usize;
+        let index = (0..BASE_COMMITMENT_HASHING_SLOTS_COUNT)
+            .map(|i| (next_index + i) % BASE_COMMITMENT_HASHING_SLOTS_COUNT)
+            .find(|&i| !self.get_is_open(i) && slot >= self.get_reserved_until(i))
+            .ok_or(ElusivError::NoFreeHashingSlot)?;
+
+        self.set_reserved_by(index, fee_payer);
+        self.set_reserved_until(
+            index,
+            &slot.saturating_add(BASE_COMMITMENT_HASHING_SLOT_RESERVATION),
+        );
+        self.set_next_index(&(((index + 1) % BASE_COMMITMENT_HASHING_SLOTS_COUNT) as u32));
+
+        Ok(index as u32)
+    }
+
+    /// Marks the instance `index` as opened by `fee_payer` (only instances not reserved by another fee payer can be opened)
+    pub fn open(&mut self, index: u32, fee_payer: &Pubkey, slot: u64) -> ProgramResult {
+        let index = index as usize;
+        guard!(
+            index < BASE_COMMITMENT_HASHING_SLOTS_COUNT,
+            ElusivError::InvalidAccount
+        );
+        guard!(
+            !self.get_is_open(index)
+                && (self.get_reserved_by(index) == *fee_payer
+                    || slot >= self.get_reserved_until(index)),
+            ElusivError::HashingSlotReserved
+        );
+
+        self.set_is_open(index, &true);
+        self.set_reserved_by(index, fee_payer);
+
+        Ok(())
+    }
+
+    /// Frees the instance `index` once its [`BaseCommitmentHashingAccount`] has been closed
+    pub fn release(&mut self, index: u32) {
+        let index = index as usize;
+        if index < BASE_COMMITMENT_HASHING_SLOTS_COUNT {
+            self.set_is_open(index, &false);
+            self.set_reserved_until(index, &0);
+        }
+    }
+}
+
+/// Escrow of the commitment hash fees of a single batch
+///
+/// # Note
+///
+/// Each commitment has paid [`ProgramFee::commitment_hash_computation_fee`] for its `min_batching_rate`.
+/// Since the escrow is derived from the batch itself, aborting a batch (or a batch with a different composition) requires no per-commitment refunds.
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Default)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct CommitmentHashFeeEscrow {
+    /// The number of commitments in the batch per `min_batching_rate`
+    pub commitment_counts: [u32; MAX_COMMITMENT_BATCHING_RATE + 1],
+
+    /// Lamports paid out as hash-tx-compensation
+    pub paid: u64,
+}
+
+impl CommitmentHashFeeEscrow {
+    pub fn new(batch: &[CommitmentHashRequest]) -> Result<Self, ProgramError> {
+        let mut escrow = CommitmentHashFeeEscrow::default();
+        for request in batch {
+            let count = escrow
+                .commitment_counts
+                .get_mut(request.min_batching_rate as usize)
+                .ok_or(ElusivError::InvalidBatchingRate)?;
+            *count += 1;
+        }
+
+        Ok(escrow)
+    }
+
+    /// The lamports escrowed for the batch
+    pub fn amount(&self, fee: &ProgramFee) -> Result<u64, ProgramError> {
+        let mut amount: u64 = 0;
+        for (min_batching_rate, count) in self.commitment_counts.iter().enumerate() {
+            let commitment_fee = fee
+                .commitment_hash_computation_fee(usize_as_u32_safe(min_batching_rate))
+                .0;
+            amount = commitment_fee
+                .checked_mul(*count as u64)
+                .and_then(|fee| amount.checked_add(fee))
+                .ok_or(ElusivError::InvalidFee)?;
+        }
+
+        Ok(amount)
+    }
+
+    /// The escrowed lamports that have not been paid out
+    pub fn remaining(&self, fee: &ProgramFee) -> Result<u64, ProgramError> {
+        Ok(self.amount(fee)?.saturating_sub(self.paid))
+    }
+}
+
 /// Account used for computing the hashes of a MT
-#[elusiv_account(partial_computation: true, eager_type: true)]
+#[elusiv_account(partial_computation: true)]
 pub struct CommitmentHashingAccount {
     #[no_getter]
     #[no_setter]
@@ -95,9 +252,25 @@ pub struct CommitmentHashingAccount {
 
     // commitments and hashes in the HT
     pub hash_tree: [U256; MAX_HT_SIZE],
+
+    /// The fee escrow of the active batch (settled at the finalization)
+    pub fee_escrow: CommitmentHashFeeEscrow,
+
+    /// Number of commitments the active MT can hold (zero for [`MT_COMMITMENT_COUNT`])
+    pub mt_capacity: u32,
+
+    /// The highest batching rate, queued commitments are batched with (if enough commitments are queued)
+    pub max_batching_rate: u32,
 }
 
 impl<'a> CommitmentHashingAccount<'a> {
+    pub fn mt_commitment_capacity(&self) -> usize {
+        match self.get_mt_capacity() {
+            0 => MT_COMMITMENT_COUNT,
+            capacity => capacity as usize,
+        }
+    }
+
     /// Called before reset, sets the siblings
     pub fn setup(&mut self, ordering: u32, siblings: &[U256]) -> Result<(), ProgramError> {
         guard!(!self.get_is_active(), ElusivError::InvalidAccountState);
@@ -139,6 +312,16 @@ impl<'a> CommitmentHashingAccount<'a> {
         Ok(())
     }
 
+    /// Stops an active computation (a new setup is required for the next batch)
+    pub fn abort(&mut self) {
+        self.set_fee_escrow(&CommitmentHashFeeEscrow::default());
+        self.set_is_active(&false);
+        self.set_setup(&false);
+        self.set_instruction(&0);
+        self.set_round(&0);
+        self.set_finalization_ix(&0);
+    }
+
     /// Returns the initial state for the next hash
     /// - hashing order:
     ///     1. commitment sibling hashes on MT-layer `n`: h(c0, c1), h(c2, c3), ..
@@ -284,15 +467,359 @@ buffer_account!(
 
 pub const COMMITMENT_QUEUE_LEN: usize = 240;
 
+/// The max number of slots of a grown [`CommitmentQueue`] (and [`super::metadata::MetadataQueue`])
+pub const MAX_COMMITMENT_QUEUE_LEN: usize = 4 * COMMITMENT_QUEUE_LEN;
+
+/// The number of slots appended to the [`CommitmentQueue`] (and [`super::metadata::MetadataQueue`]) by a single resize
+pub const COMMITMENT_QUEUE_GROWTH: usize = COMMITMENT_QUEUE_LEN / 2;
+
+/// Number of distinct fee-payers that can have commitments in the [`CommitmentQueue`] at the same time
+pub const IN_FLIGHT_FEE_PAYERS_COUNT: usize = 32;
+
+/// The in-flight owner of requests not counted for any fee-payer-slot
+pub const UNTRACKED_IN_FLIGHT_OWNER: u8 = u8::MAX;
+
+/// The per-slot data of an appended [`CommitmentQueue`] slot (the in-flight owner and the priority)
+const COMMITMENT_QUEUE_SLOT_DATA_SIZE: usize = 1 + 8;
+
 // Queue used for storing commitments that should sequentially inserted into the active MT
+// - `in_flight_*` fields track the number of queued commitments per fee-payer (`in_flight_owners` maps queue-indices to fee-payer-slots)
+// - `priorities` maps queue-indices to the priority-bid (in Lamports) of the request (the queue is ordered by descending priority)
+// - appended slots store their in-flight owner and priority next to the request
+// - `fair_share_*` fields track the base-commitment hashes finalized per fee-payer within the active fair-share window
 queue_account!(
     CommitmentQueue,
     CommitmentQueueAccount,
     COMMITMENT_QUEUE_LEN,
-    CommitmentHashRequest,
+    CommitmentHashRequest; slot_data_size: COMMITMENT_QUEUE_SLOT_DATA_SIZE,
+    pub in_flight_cap: u32,
+    pub in_flight_fee_payers: [U256; IN_FLIGHT_FEE_PAYERS_COUNT],
+    pub in_flight_counts: [u32; IN_FLIGHT_FEE_PAYERS_COUNT],
+    pub in_flight_owners: [u8; COMMITMENT_QUEUE_LEN],
+    pub priorities: [u64; COMMITMENT_QUEUE_LEN],
+    pub fair_share_window_start: u64,
+    pub fair_share_window_total: u32,
+    pub fair_share_fee_payers: [U256; IN_FLIGHT_FEE_PAYERS_COUNT],
+    pub fair_share_counts: [u32; IN_FLIGHT_FEE_PAYERS_COUNT],
 );
 
+/// The number of base-commitment hashes a fee-payer can always finalize within a fair-share window
+pub const MIN_FAIR_SHARE_COUNT: u32 = 2;
+
+impl<'a> CommitmentQueueAccount<'a> {
+    /// Returns the number of queued requests (without requiring mutable access to the account)
+    pub fn queue_len(&self) -> u32 {
+        let size = self.queue_size();
+        (self.get_tail() + size - self.get_head()) % size
+    }
+
+    /// Returns the priority-bid of the first queued request
+    pub fn head_priority(&self) -> Option<u64> {
+        if self.queue_len() == 0 {
+            return None;
+        }
+
+        Some(self.priority(self.get_head() as usize))
+    }
+
+    /// Returns the fee-payer-slot of the request at the queue-index `index`
+    pub fn in_flight_owner(&self, index: usize) -> u8 {
+        if index < COMMITMENT_QUEUE_LEN {
+            return self.get_in_flight_owners(index);
+        }
+
+        self.appended_slot(index)[<CommitmentHashRequest as BorshSerDeSized>::SIZE]
+    }
+
+    pub fn set_in_flight_owner(&mut self, index: usize, owner: u8) {
+        if index < COMMITMENT_QUEUE_LEN {
+            return self.set_in_flight_owners(index, &owner);
+        }
+
+        self.appended_slot_mut(index)[<CommitmentHashRequest as BorshSerDeSized>::SIZE] = owner;
+    }
+
+    /// Returns the priority-bid of the request at the queue-index `index`
+    pub fn priority(&self, index: usize) -> u64 {
+        if index < COMMITMENT_QUEUE_LEN {
+            return self.get_priorities(index);
+        }
+
+        let offset = <CommitmentHashRequest as BorshSerDeSized>::SIZE + 1;
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&self.appended_slot(index)[offset..offset + 8]);
+        u64::from_le_bytes(bytes)
+    }
+
+    pub fn set_priority(&mut self, index: usize, priority: u64) {
+        if index < COMMITMENT_QUEUE_LEN {
+            return self.set_priorities(index, &priority);
+        }
+
+        let offset = <CommitmentHashRequest as BorshSerDeSized>::SIZE + 1;
+        self.appended_slot_mut(index)[offset..offset + 8].copy_from_slice(&priority.to_le_bytes());
+    }
+
+    /// Returns the number of fee-payers with at least one queued request
+    pub fn active_fee_payers(&self) -> usize {
+        (0..IN_FLIGHT_FEE_PAYERS_COUNT)
+            .filter(|&slot| self.get_in_flight_counts(slot) > 0)
+            .count()
+    }
+
+    /// Records a base-commitment hash of `fee_payer` finalized at `slot` in the fair-share window
+    ///
+    /// # Notes
+    ///
+    /// - A window spans `window_slots` slots, a new window starts with the first finalization after the active window.
+    /// - Within a window, a fee-payer can finalize at most `share_bps` of all finalized hashes (but always [`MIN_FAIR_SHARE_COUNT`] hashes).
+    /// - Fee-payers exceeding the tracked fee-payers of a window are not limited (they hold no share yet).
+    /// - A `share_bps` of `0` disables the fair-share.
+    pub fn record_fair_share(
+        &mut self,
+        fee_payer: &U256,
+        slot: u64,
+        share_bps: u16,
+        window_slots: u64,
+    ) -> ProgramResult {
+        if share_bps == 0 {
+            return Ok(());
+        }
+
+        let window_start = self.get_fair_share_window_start();
+        if self.get_fair_share_window_total() == 0
+            || slot >= window_start.saturating_add(window_slots)
+        {
+            self.set_fair_share_window_start(&slot);
+            self.set_fair_share_window_total(&0);
+            for i in 0..IN_FLIGHT_FEE_PAYERS_COUNT {
+                self.set_fair_share_counts(i, &0);
+            }
+        }
+
+        let total = self.get_fair_share_window_total() + 1;
+        let tracked = (0..IN_FLIGHT_FEE_PAYERS_COUNT).find(|&i| {
+            self.get_fair_share_counts(i) > 0 && self.get_fair_share_fee_payers(i) == *fee_payer
+        });
+        let index = match tracked.or_else(|| {
+            (0..IN_FLIGHT_FEE_PAYERS_COUNT).find(|&i| self.get_fair_share_counts(i) == 0)
+        }) {
+            Some(index) => index,
+            None => {
+                self.set_fair_share_window_total(&total);
+                return Ok(());
+            }
+        };
+
+        let count = self.get_fair_share_counts(index) + 1;
+        let share = (total as u64 * share_bps as u64 / 10_000) as u32;
+        guard!(
+            count <= std::cmp::max(share, MIN_FAIR_SHARE_COUNT),
+            ElusivError::FairShareExceeded
+        );
+
+        self.set_fair_share_window_total(&total);
+        self.set_fair_share_fee_payers(index, fee_payer);
+        self.set_fair_share_counts(index, &count);
+
+        Ok(())
+    }
+}
+
 impl<'a, 'b> CommitmentQueue<'a, 'b> {
+    /// Enqueues a request on behalf of `fee_payer`
+    ///
+    /// # Note
+    ///
+    /// - Fails if `fee_payer` already has `in_flight_cap` requests in the queue (a cap of `0` disables this check).
+    /// - Fails if all fee-payer-slots are used by other fee-payers (otherwise sybil fee-payers could bypass the cap).
+    /// - Requests are not tracked if the cap is disabled.
+    pub fn enqueue_for_fee_payer(
+        &mut self,
+        request: CommitmentHashRequest,
+        fee_payer: &U256,
+    ) -> Result<(), ProgramError> {
+        let slot = self.verify_in_flight(fee_payer)?;
+        let count = slot.map_or(0, |slot| self.account.get_in_flight_counts(slot));
+
+        let index = self.get_tail() as usize;
+        self.enqueue(request)?;
+
+        match slot {
+            Some(slot) => {
+                self.account.set_in_flight_fee_payers(slot, fee_payer);
+                self.account.set_in_flight_counts(slot, &(count + 1));
+                self.account.set_in_flight_owner(index, slot as u8);
+            }
+            None => self
+                .account
+                .set_in_flight_owner(index, UNTRACKED_IN_FLIGHT_OWNER),
+        }
+        self.account.set_priority(index, 0);
+
+        Ok(())
+    }
+
+    /// Verifies that a request of `fee_payer` can be enqueued (see [`Self::enqueue_for_fee_payer`]) and returns its fee-payer-slot (`None` if the cap is disabled)
+    pub fn verify_in_flight(&self, fee_payer: &U256) -> Result<Option<usize>, ProgramError> {
+        let cap = self.account.get_in_flight_cap();
+        if cap == 0 {
+            return Ok(None);
+        }
+
+        let slot = self
+            .in_flight_slot(fee_payer)
+            .ok_or(ElusivError::InFlightFeePayerSlotsExhausted)?;
+        guard!(
+            self.account.get_in_flight_counts(slot) < cap,
+            ElusivError::InFlightCapReached
+        );
+
+        Ok(Some(slot))
+    }
+
+    /// Enqueues a request on behalf of `fee_payer` ahead of all queued requests with a lower `priority`
+    ///
+    /// # Notes
+    ///
+    /// - requests with the same priority keep their FIFO order
+    /// - a request is never moved ahead of a request with a different fee-version (a batch requires a single fee-version)
+    /// - returns the number of positions the request has been moved ahead (parallel queues need to be reordered by the caller)
+    pub fn enqueue_with_priority(
+        &mut self,
+        request: CommitmentHashRequest,
+        fee_payer: &U256,
+        priority: u64,
+    ) -> Result<u32, ProgramError> {
+        let fee_version = request.fee_version;
+        let size = self.size();
+        let index = self.get_tail();
+        self.enqueue_for_fee_payer(request, fee_payer)?;
+        self.account.set_priority(index as usize, priority);
+
+        let mut moves = 0;
+        let mut ptr = index;
+        while ptr != self.get_head() {
+            let previous = ((ptr + size - 1) % size) as usize;
+            if self.account.priority(previous) >= priority
+                || self.get_data(previous).fee_version != fee_version
+            {
+                break;
+            }
+
+            ptr = previous as u32;
+            moves += 1;
+        }
+
+        if moves > 0 {
+            let owner = self.account.in_flight_owner(index as usize);
+            let size = size as usize;
+            let mut ptr = index as usize;
+            for _ in 0..moves {
+                let previous = (ptr + size - 1) % size;
+                self.account
+                    .set_in_flight_owner(ptr, self.account.in_flight_owner(previous));
+                self.account
+                    .set_priority(ptr, self.account.priority(previous));
+                ptr = previous;
+            }
+            self.account.set_in_flight_owner(ptr, owner);
+            self.account.set_priority(ptr, priority);
+
+            self.move_last_ahead(moves)?;
+        }
+
+        Ok(moves)
+    }
+
+    /// Removes the first `count` requests and releases them from the in-flight counts of their fee-payers
+    pub fn remove_in_flight(&mut self, count: u32) -> Result<(), ProgramError> {
+        let size = self.size() as usize;
+        let head = self.get_head() as usize;
+        self.remove(count)?;
+
+        for i in 0..count as usize {
+            let slot = self.account.in_flight_owner((head + i) % size);
+            if slot == UNTRACKED_IN_FLIGHT_OWNER {
+                continue;
+            }
+
+            let in_flight = self.account.get_in_flight_counts(slot as usize);
+            self.account
+                .set_in_flight_counts(slot as usize, &in_flight.saturating_sub(1));
+        }
+
+        Ok(())
+    }
+
+    /// Re-adds the last `count` removed requests to the front of the queue and to the in-flight counts of their fee-payers
+    ///
+    /// # Note
+    ///
+    /// If the fee-payer-slot of a request has been reused in the meantime, the request is counted for the new fee-payer.
+    pub fn restore_in_flight(&mut self, count: u32) -> Result<(), ProgramError> {
+        self.restore(count)?;
+        let size = self.size() as usize;
+        let head = self.get_head() as usize;
+
+        for i in 0..count as usize {
+            let slot = self.account.in_flight_owner((head + i) % size);
+            if slot == UNTRACKED_IN_FLIGHT_OWNER {
+                continue;
+            }
+
+            let in_flight = self.account.get_in_flight_counts(slot as usize);
+            self.account
+                .set_in_flight_counts(slot as usize, &in_flight.saturating_add(1));
+        }
+
+        Ok(())
+    }
+
+    /// Removes all requests and releases all fee-payer-slots
+    pub fn clear_in_flight(&mut self) {
+        self.clear();
+
+        for slot in 0..IN_FLIGHT_FEE_PAYERS_COUNT {
+            self.account.set_in_flight_counts(slot, &0);
+        }
+    }
+
+    /// Adopts the slots appended to the queue (see [`RingQueue::grow`]) and relocates the in-flight owners and priorities
+    pub fn grow_in_flight(&mut self, previous_size: u32) -> Result<(), ProgramError> {
+        for (from, to) in self.grow(previous_size)? {
+            self.account
+                .set_in_flight_owner(to, self.account.in_flight_owner(from));
+            self.account.set_priority(to, self.account.priority(from));
+        }
+
+        Ok(())
+    }
+
+    pub fn in_flight_count(&self, fee_payer: &U256) -> u32 {
+        (0..IN_FLIGHT_FEE_PAYERS_COUNT)
+            .find(|&slot| {
+                self.account.get_in_flight_counts(slot) > 0
+                    && self.account.get_in_flight_fee_payers(slot) == *fee_payer
+            })
+            .map(|slot| self.account.get_in_flight_counts(slot))
+            .unwrap_or(0)
+    }
+
+    /// Returns the slot already used by `fee_payer` or the first free slot (`None` if all slots are in use)
+    fn in_flight_slot(&self, fee_payer: &U256) -> Option<usize> {
+        let mut free_slot = None;
+        for slot in 0..IN_FLIGHT_FEE_PAYERS_COUNT {
+            if self.account.get_in_flight_counts(slot) == 0 {
+                free_slot = free_slot.or(Some(slot));
+            } else if self.account.get_in_flight_fee_payers(slot) == *fee_payer {
+                return Some(slot);
+            }
+        }
+
+        free_slot
+    }
+
     /// Returns the next batch of commitments to be hashed together
     pub fn next_batch(&self) -> Result<(Vec<CommitmentHashRequest>, u32), ProgramError> {
         let mut requests = Vec::new();
@@ -321,6 +848,49 @@ impl<'a, 'b> CommitmentQueue<'a, 'b> {
 
         Ok((requests, highest_batching_rate))
     }
+
+    /// Returns the next batch of commitments, extended to up to `2^max_batching_rate` commitments
+    ///
+    /// # Note
+    ///
+    /// The batch is only extended, if enough commitments with the same fee-version are queued and the extended HT is aligned at `ordering` and fits into the MT (with `capacity` commitments).
+    pub fn next_extended_batch(
+        &self,
+        max_batching_rate: u32,
+        ordering: u32,
+        capacity: usize,
+    ) -> Result<(Vec<CommitmentHashRequest>, u32), ProgramError> {
+        let (mut requests, batching_rate) = self.next_batch()?;
+        let fee_version = requests[0].fee_version;
+        let max_batching_rate = std::cmp::min(
+            max_batching_rate,
+            usize_as_u32_safe(MAX_COMMITMENT_BATCHING_RATE),
+        );
+
+        for rate in (batching_rate + 1..=max_batching_rate).rev() {
+            let count = commitments_per_batch(rate);
+            if ordering as usize % count != 0
+                || ordering as usize + count > capacity
+                || (self.len() as usize) < count
+            {
+                continue;
+            }
+
+            let extension = (requests.len()..count)
+                .map(|i| self.view(i))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if extension
+                .iter()
+                .all(|r| r.fee_version == fee_version && r.min_batching_rate <= rate)
+            {
+                requests.extend(extension);
+                return Ok((requests, rate));
+            }
+        }
+
+        Ok((requests, batching_rate))
+    }
 }
 
 #[cfg(test)]
@@ -349,9 +919,7 @@ pub fn base_commitment_request(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::commitment::{
-        hash_count_per_batch, MAX_COMMITMENT_BATCHING_RATE, MAX_HT_COMMITMENTS,
-    };
+    use crate::commitment::{hash_count_per_batch, MAX_HT_COMMITMENTS};
     use crate::fields::{u64_to_scalar, u64_to_scalar_skip_mr, u64_to_u256_skip_mr};
     use crate::macros::{parent_account, zero_program_account};
     use crate::state::queue::Queue;
@@ -609,6 +1177,91 @@ mod tests {
         assert!(account.get_is_active());
     }
 
+    #[test]
+    fn test_acquire_base_commitment_hashing_slot() {
+        zero_program_account!(mut slots, BaseCommitmentHashingSlotsAccount);
+        let warden_a = Pubkey::new_unique();
+        let warden_b = Pubkey::new_unique();
+
+        assert_eq!(slots.acquire(&warden_a, 0), Ok(0));
+        assert_eq!(slots.acquire(&warden_b, 0), Ok(1));
+        assert_eq!(slots.get_reserved_by(1), warden_b);
+        assert_eq!(
+            slots.get_reserved_until(1),
+            BASE_COMMITMENT_HASHING_SLOT_RESERVATION
+        );
+
+        // Pending reservations per fee payer are limited
+        for index in 2..BASE_COMMITMENT_HASHING_SLOTS_PER_FEE_PAYER + 1 {
+            assert_eq!(slots.acquire(&warden_a, 1), Ok(index as u32));
+        }
+        assert_eq!(
+            slots.acquire(&warden_a, 1),
+            Err(ElusivError::NoFreeHashingSlot.into())
+        );
+
+        // Opened instances are no longer pending
+        assert_eq!(slots.open(0, &warden_a, 1), Ok(()));
+        assert_eq!(
+            slots.acquire(&warden_a, 1),
+            Ok(BASE_COMMITMENT_HASHING_SLOTS_PER_FEE_PAYER as u32 + 1)
+        );
+
+        for index in
+            BASE_COMMITMENT_HASHING_SLOTS_PER_FEE_PAYER + 2..BASE_COMMITMENT_HASHING_SLOTS_COUNT
+        {
+            assert_eq!(slots.acquire(&Pubkey::new_unique(), 1), Ok(index as u32));
+        }
+        assert_eq!(
+            slots.acquire(&warden_b, 1),
+            Err(ElusivError::NoFreeHashingSlot.into())
+        );
+
+        // Expired reservations are acquired again, open instances are skipped
+        assert_eq!(
+            slots.acquire(&warden_b, BASE_COMMITMENT_HASHING_SLOT_RESERVATION + 1),
+            Ok(1)
+        );
+        assert_eq!(slots.get_reserved_by(1), warden_b);
+    }
+
+    #[test]
+    fn test_open_base_commitment_hashing_slot() {
+        zero_program_account!(mut slots, BaseCommitmentHashingSlotsAccount);
+        let warden_a = Pubkey::new_unique();
+        let warden_b = Pubkey::new_unique();
+
+        assert_eq!(slots.acquire(&warden_a, 0), Ok(0));
+
+        // Reserved by another fee payer
+        assert_eq!(
+            slots.open(0, &warden_b, 0),
+            Err(ElusivError::HashingSlotReserved.into())
+        );
+
+        // Invalid index
+        assert_eq!(
+            slots.open(BASE_COMMITMENT_HASHING_SLOTS_COUNT as u32, &warden_a, 0),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        // Unreserved instances can be opened directly
+        assert_eq!(slots.open(1, &warden_b, 0), Ok(()));
+
+        assert_eq!(slots.open(0, &warden_a, 0), Ok(()));
+        assert!(slots.get_is_open(0));
+
+        // Open instances can not be opened again (even after the reservation expired)
+        assert_eq!(
+            slots.open(0, &warden_a, BASE_COMMITMENT_HASHING_SLOT_RESERVATION),
+            Err(ElusivError::HashingSlotReserved.into())
+        );
+
+        slots.release(0);
+        assert!(!slots.get_is_open(0));
+        assert_eq!(slots.open(0, &warden_b, 0), Ok(()));
+    }
+
     #[test]
     #[allow(clippy::needless_range_loop)]
     fn test_commitment_account_reset() {
@@ -718,4 +1371,392 @@ mod tests {
         .unwrap();
         assert_eq!(q.next_batch(), Err(ElusivError::InvalidFeeVersion.into()));
     }
+
+    #[test]
+    fn test_commitment_queue_next_extended_batch() {
+        zero_program_account!(mut account, CommitmentQueueAccount);
+        let mut q = CommitmentQueue::new(&mut account);
+        for i in 0..6 {
+            q.enqueue(request(i)).unwrap();
+        }
+
+        // No extension
+        let (batch, batching_rate) = q.next_extended_batch(0, 0, 16).unwrap();
+        assert_eq!((batch.len(), batching_rate), (1, 0));
+
+        // Extended to the highest rate with enough queued commitments
+        let (batch, batching_rate) = q.next_extended_batch(4, 0, 16).unwrap();
+        assert_eq!((batch.len(), batching_rate), (4, 2));
+        for (i, request) in batch.iter().enumerate() {
+            assert_eq!(request.commitment, [i as u8; 32]);
+        }
+
+        // Unaligned ordering
+        let (batch, batching_rate) = q.next_extended_batch(4, 2, 16).unwrap();
+        assert_eq!((batch.len(), batching_rate), (2, 1));
+        let (_, batching_rate) = q.next_extended_batch(4, 1, 16).unwrap();
+        assert_eq!(batching_rate, 0);
+
+        // Exceeding the MT capacity
+        let (_, batching_rate) = q.next_extended_batch(4, 12, 14).unwrap();
+        assert_eq!(batching_rate, 1);
+
+        // Mismatching fee-version
+        q.clear();
+        q.enqueue(request(0)).unwrap();
+        q.enqueue(CommitmentHashRequest {
+            fee_version: 1,
+            ..request(1)
+        })
+        .unwrap();
+        let (_, batching_rate) = q.next_extended_batch(4, 0, 16).unwrap();
+        assert_eq!(batching_rate, 0);
+    }
+
+    fn request(commitment: u8) -> CommitmentHashRequest {
+        CommitmentHashRequest {
+            commitment: [commitment; 32],
+            fee_version: 0,
+            min_batching_rate: 0,
+        }
+    }
+
+    #[test]
+    fn test_commitment_queue_in_flight_cap() {
+        zero_program_account!(mut account, CommitmentQueueAccount);
+        account.set_in_flight_cap(&3);
+        let mut q = CommitmentQueue::new(&mut account);
+
+        let adversary = [1; 32];
+        let user = [2; 32];
+
+        // The adversary fills up its in-flight share
+        for i in 0..3 {
+            q.enqueue_for_fee_payer(request(i), &adversary).unwrap();
+        }
+        assert_eq!(
+            q.enqueue_for_fee_payer(request(3), &adversary),
+            Err(ElusivError::InFlightCapReached.into())
+        );
+        assert_eq!(q.in_flight_count(&adversary), 3);
+
+        // Other fee-payers are not starved
+        q.enqueue_for_fee_payer(request(4), &user).unwrap();
+        assert_eq!(q.in_flight_count(&user), 1);
+        assert_eq!(q.len(), 4);
+
+        // Hashing the first batch releases the adversary's in-flight commitments
+        q.remove_in_flight(2).unwrap();
+        assert_eq!(q.in_flight_count(&adversary), 1);
+        assert_eq!(q.in_flight_count(&user), 1);
+        q.enqueue_for_fee_payer(request(5), &adversary).unwrap();
+        q.enqueue_for_fee_payer(request(6), &adversary).unwrap();
+        assert_eq!(
+            q.enqueue_for_fee_payer(request(7), &adversary),
+            Err(ElusivError::InFlightCapReached.into())
+        );
+
+        q.remove_in_flight(4).unwrap();
+        assert_eq!(q.in_flight_count(&adversary), 0);
+        assert_eq!(q.in_flight_count(&user), 0);
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn test_commitment_queue_in_flight_slots_exhausted() {
+        let fee_payer = |i: usize| [i as u8 + 1; 32];
+        let count = IN_FLIGHT_FEE_PAYERS_COUNT + 1;
+
+        // Without a cap, requests are not tracked at all
+        zero_program_account!(mut account, CommitmentQueueAccount);
+        let mut q = CommitmentQueue::new(&mut account);
+        for i in 0..count {
+            q.enqueue_for_fee_payer(request(i as u8), &fee_payer(i))
+                .unwrap();
+        }
+        assert_eq!(q.len(), count as u32);
+        assert_eq!(q.account.active_fee_payers(), 0);
+
+        q.remove_in_flight(count as u32).unwrap();
+        assert!(q.is_empty());
+
+        // With a cap, sybil fee-payers can at most occupy all fee-payer-slots
+        zero_program_account!(mut account, CommitmentQueueAccount);
+        account.set_in_flight_cap(&1);
+        let mut q = CommitmentQueue::new(&mut account);
+        for i in 0..IN_FLIGHT_FEE_PAYERS_COUNT {
+            q.enqueue_for_fee_payer(request(i as u8), &fee_payer(i))
+                .unwrap();
+        }
+        assert_eq!(
+            q.enqueue_for_fee_payer(request(0), &fee_payer(count - 1)),
+            Err(ElusivError::InFlightFeePayerSlotsExhausted.into())
+        );
+        assert_eq!(q.len(), IN_FLIGHT_FEE_PAYERS_COUNT as u32);
+        assert_eq!(q.account.active_fee_payers(), IN_FLIGHT_FEE_PAYERS_COUNT);
+        assert_eq!(q.in_flight_count(&fee_payer(count - 1)), 0);
+
+        // Tracked fee-payers are still limited by the cap
+        assert_eq!(
+            q.enqueue_for_fee_payer(request(0), &fee_payer(0)),
+            Err(ElusivError::InFlightCapReached.into())
+        );
+
+        // Released slots are reused
+        q.remove_in_flight(1).unwrap();
+        q.enqueue_for_fee_payer(request(0), &fee_payer(count - 1))
+            .unwrap();
+        assert_eq!(q.in_flight_count(&fee_payer(0)), 0);
+        assert_eq!(q.in_flight_count(&fee_payer(count - 1)), 1);
+    }
+
+    #[test]
+    fn test_commitment_queue_in_flight_fee_payer_slots() {
+        zero_program_account!(mut account, CommitmentQueueAccount);
+        let mut q = CommitmentQueue::new(&mut account);
+
+        // Without a cap, a single fee-payer can use the whole queue
+        for _ in 0..CommitmentQueue::CAPACITY {
+            q.enqueue_for_fee_payer(request(0), &[0; 32]).unwrap();
+        }
+        assert_eq!(q.len(), CommitmentQueue::CAPACITY);
+        q.remove_in_flight(CommitmentQueue::CAPACITY).unwrap();
+
+        // With a cap, each fee-payer uses a single slot
+        q.account.set_in_flight_cap(&CommitmentQueue::CAPACITY);
+        for i in 0..3 {
+            q.enqueue_for_fee_payer(request(0), &[0; 32]).unwrap();
+            assert_eq!(q.in_flight_count(&[0; 32]), i + 1);
+        }
+        q.enqueue_for_fee_payer(request(0), &[1; 32]).unwrap();
+        assert_eq!(q.account.active_fee_payers(), 2);
+
+        q.remove_in_flight(3).unwrap();
+        assert_eq!(q.in_flight_count(&[0; 32]), 0);
+        assert_eq!(q.account.active_fee_payers(), 1);
+    }
+
+    #[test]
+    fn test_commitment_queue_fair_share() {
+        zero_program_account!(mut account, CommitmentQueueAccount);
+
+        let adversary = [1; 32];
+        let user = [2; 32];
+
+        // Disabled
+        for _ in 0..10 {
+            account.record_fair_share(&adversary, 0, 0, 100).unwrap();
+        }
+        assert_eq!(account.get_fair_share_window_total(), 0);
+
+        // A fee-payer can always finalize the minimum count
+        for _ in 0..MIN_FAIR_SHARE_COUNT {
+            account
+                .record_fair_share(&adversary, 10, 6_000, 100)
+                .unwrap();
+        }
+        assert_eq!(
+            account.record_fair_share(&adversary, 10, 6_000, 100),
+            Err(ElusivError::FairShareExceeded.into())
+        );
+        assert_eq!(account.get_fair_share_window_total(), MIN_FAIR_SHARE_COUNT);
+
+        // Other fee-payers are not starved and increase the share
+        account.record_fair_share(&user, 20, 6_000, 100).unwrap();
+        account.record_fair_share(&user, 20, 6_000, 100).unwrap();
+        account
+            .record_fair_share(&adversary, 20, 6_000, 100)
+            .unwrap();
+        assert_eq!(
+            account.record_fair_share(&adversary, 20, 6_000, 100),
+            Err(ElusivError::FairShareExceeded.into())
+        );
+
+        // A new window resets all shares
+        account
+            .record_fair_share(&adversary, 110, 6_000, 100)
+            .unwrap();
+        assert_eq!(account.get_fair_share_window_start(), 110);
+        assert_eq!(account.get_fair_share_window_total(), 1);
+    }
+
+    #[test]
+    fn test_commitment_queue_len() {
+        zero_program_account!(mut account, CommitmentQueueAccount);
+        assert_eq!(account.queue_len(), 0);
+
+        for i in 0..CommitmentQueue::CAPACITY {
+            CommitmentQueue::new(&mut account)
+                .enqueue(request(0))
+                .unwrap();
+            assert_eq!(account.queue_len(), i + 1);
+        }
+
+        // Wrapped around
+        let mut q = CommitmentQueue::new(&mut account);
+        q.remove(CommitmentQueue::CAPACITY - 1).unwrap();
+        q.enqueue(request(0)).unwrap();
+        q.enqueue(request(0)).unwrap();
+        let len = q.len();
+        assert_eq!(account.queue_len(), len);
+        assert_eq!(len, 3);
+    }
+
+    #[test]
+    fn test_commitment_queue_restore_in_flight() {
+        zero_program_account!(mut account, CommitmentQueueAccount);
+        let mut q = CommitmentQueue::new(&mut account);
+
+        let fee_payer = [1; 32];
+        for i in 0..4 {
+            q.enqueue_for_fee_payer(request(i), &fee_payer).unwrap();
+        }
+        q.remove_in_flight(2).unwrap();
+        assert_eq!(q.in_flight_count(&fee_payer), 2);
+
+        q.restore_in_flight(2).unwrap();
+        assert_eq!(q.in_flight_count(&fee_payer), 4);
+        assert_eq!(q.len(), 4);
+        for i in 0..4 {
+            assert_eq!(q.view(i).unwrap(), request(i as u8));
+        }
+    }
+
+    #[test]
+    fn test_commitment_queue_enqueue_with_priority() {
+        zero_program_account!(mut account, CommitmentQueueAccount);
+        let mut q = CommitmentQueue::new(&mut account);
+
+        let fee_payer = [1; 32];
+        let bidder = [2; 32];
+
+        // Wraps around the end of the queue storage
+        q.set_head(&(COMMITMENT_QUEUE_LEN as u32 - 2));
+        q.set_tail(&(COMMITMENT_QUEUE_LEN as u32 - 2));
+
+        for i in 0..3 {
+            assert_eq!(
+                q.enqueue_with_priority(request(i), &fee_payer, 0).unwrap(),
+                0
+            );
+        }
+
+        // Moved ahead of all requests without a bid
+        assert_eq!(q.enqueue_with_priority(request(3), &bidder, 10).unwrap(), 3);
+
+        // Equal bids keep their FIFO order
+        assert_eq!(q.enqueue_with_priority(request(4), &bidder, 10).unwrap(), 3);
+
+        // Higher bids are moved in front of lower bids
+        assert_eq!(
+            q.enqueue_with_priority(request(5), &fee_payer, 20).unwrap(),
+            5
+        );
+
+        for (i, r) in [5, 3, 4, 0, 1, 2].into_iter().enumerate() {
+            assert_eq!(q.view(i).unwrap(), request(r));
+        }
+
+        // The in-flight counts are released for the moved requests
+        q.remove_in_flight(3).unwrap();
+        assert_eq!(q.in_flight_count(&bidder), 0);
+        assert_eq!(q.in_flight_count(&fee_payer), 3);
+
+        // Requests are never moved ahead of other fee-versions
+        let mut newer_fee_version = request(6);
+        newer_fee_version.fee_version = 1;
+        assert_eq!(
+            q.enqueue_with_priority(newer_fee_version, &fee_payer, 100)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_commitment_queue_grow_in_flight() {
+        let fee_payer = [1; 32];
+        let bidder = [2; 32];
+
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        {
+            let mut account = CommitmentQueueAccount::new(&mut data).unwrap();
+            let mut q = CommitmentQueue::new(&mut account);
+
+            // Wraps around the end of the queue storage
+            q.set_head(&(COMMITMENT_QUEUE_LEN as u32 - 2));
+            q.set_tail(&(COMMITMENT_QUEUE_LEN as u32 - 2));
+
+            for i in 0..4 {
+                let owner = if i % 2 == 0 { &fee_payer } else { &bidder };
+                q.enqueue_with_priority(request(i), owner, 0).unwrap();
+            }
+        }
+
+        data.extend(vec![
+            0;
+            COMMITMENT_QUEUE_GROWTH
+                * CommitmentQueueAccount::SLOT_SIZE
+        ]);
+        let mut account = CommitmentQueueAccount::new(&mut data).unwrap();
+        assert_eq!(
+            account.queue_size() as usize,
+            COMMITMENT_QUEUE_LEN + COMMITMENT_QUEUE_GROWTH
+        );
+
+        let mut q = CommitmentQueue::new(&mut account);
+        q.grow_in_flight(COMMITMENT_QUEUE_LEN as u32).unwrap();
+        assert_eq!(q.len(), 4);
+        assert_eq!(
+            q.capacity() as usize,
+            COMMITMENT_QUEUE_LEN + COMMITMENT_QUEUE_GROWTH - 1
+        );
+
+        // The priorities of the requests in the appended slots are considered
+        assert_eq!(q.enqueue_with_priority(request(4), &bidder, 10).unwrap(), 4);
+        assert_eq!(q.enqueue_with_priority(request(5), &bidder, 5).unwrap(), 4);
+
+        for (i, r) in [4, 5, 0, 1, 2, 3].into_iter().enumerate() {
+            assert_eq!(q.view(i).unwrap(), request(r));
+        }
+
+        // The relocated owners are released
+        q.remove_in_flight(4).unwrap();
+        assert_eq!(q.in_flight_count(&bidder), 1);
+        assert_eq!(q.in_flight_count(&fee_payer), 1);
+
+        q.remove_in_flight(2).unwrap();
+        assert_eq!(q.in_flight_count(&bidder), 0);
+        assert_eq!(q.in_flight_count(&fee_payer), 0);
+    }
+
+    #[test]
+    fn test_commitment_hash_fee_escrow() {
+        let request = |min_batching_rate| CommitmentHashRequest {
+            commitment: [0; 32],
+            fee_version: 0,
+            min_batching_rate,
+        };
+
+        // Invalid batching rate
+        assert_eq!(
+            CommitmentHashFeeEscrow::new(&[request(MAX_COMMITMENT_BATCHING_RATE as u32 + 1)]),
+            Err(ElusivError::InvalidBatchingRate.into())
+        );
+
+        let mut escrow =
+            CommitmentHashFeeEscrow::new(&[request(0), request(1), request(1)]).unwrap();
+        assert_eq!(escrow.commitment_counts, [1, 2, 0, 0, 0]);
+
+        let fee = ProgramFee::new(5000, 11, 100, 33, 44, 300, 555).unwrap();
+        let amount =
+            fee.commitment_hash_computation_fee(0).0 + 2 * fee.commitment_hash_computation_fee(1).0;
+        assert_eq!(escrow.amount(&fee).unwrap(), amount);
+        assert_eq!(escrow.remaining(&fee).unwrap(), amount);
+
+        escrow.paid = 1;
+        assert_eq!(escrow.remaining(&fee).unwrap(), amount - 1);
+
+        escrow.paid = amount + 1;
+        assert_eq!(escrow.remaining(&fee).unwrap(), 0);
+    }
 }
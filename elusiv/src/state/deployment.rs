@@ -0,0 +1,136 @@
+use crate::processor::MAX_NUMBER_OF_VKEYS;
+use crate::types::U256;
+use elusiv_proc_macros::elusiv_account;
+use elusiv_types::PDAAccountData;
+
+/// Bits of the `features` of a [`DeploymentInfoAccount`] (the cargo features the program has been compiled with)
+pub const DEPLOYMENT_FEATURE_MAINNET: u64 = 1 << 0;
+pub const DEPLOYMENT_FEATURE_DEVNET: u64 = 1 << 1;
+pub const DEPLOYMENT_FEATURE_PERMISSIONED: u64 = 1 << 2;
+pub const DEPLOYMENT_FEATURE_ALT_BN128: u64 = 1 << 3;
+pub const DEPLOYMENT_FEATURE_POOL_YIELD: u64 = 1 << 4;
+pub const DEPLOYMENT_FEATURE_BALANCE_TRACKING: u64 = 1 << 5;
+
+pub const GIT_HASH_LEN: usize = 20;
+
+const VKEY_COUNT: usize = MAX_NUMBER_OF_VKEYS as usize;
+
+/// Describes the deployed program (the on-chain source of truth for client compatibility checks)
+///
+/// # Note
+///
+/// All values (except the vkey hashes, which are read from the [`crate::state::vkey::VKeyAccount`]s) are embedded in the program binary.
+#[elusiv_account]
+pub struct DeploymentInfoAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// `(major, minor, patch)` of the program's crate version
+    pub version: [u16; 3],
+
+    /// The git commit the program has been built from (zero if unknown)
+    pub git_hash: [u8; GIT_HASH_LEN],
+
+    /// Bitset of `DEPLOYMENT_FEATURE_*` flags
+    pub features: u64,
+    pub protocol_version: u8,
+
+    /// See [`crate::proof::vkey::VerifyingKey::key_hash`] (zero for vkeys that are not setup)
+    pub vkey_hashes: [U256; VKEY_COUNT],
+    pub vkey_versions: [u32; VKEY_COUNT],
+}
+
+/// Returns `(major, minor, patch)` of the program's crate version
+pub fn crate_version() -> [u16; 3] {
+    let version_part = |part: &str| part.parse::<u16>().unwrap_or_default();
+    [
+        version_part(env!("CARGO_PKG_VERSION_MAJOR")),
+        version_part(env!("CARGO_PKG_VERSION_MINOR")),
+        version_part(env!("CARGO_PKG_VERSION_PATCH")),
+    ]
+}
+
+/// Returns the `DEPLOYMENT_FEATURE_*` flags of all features the program has been compiled with
+pub const fn compiled_features() -> u64 {
+    let mut features = 0;
+    if cfg!(feature = "mainnet") {
+        features |= DEPLOYMENT_FEATURE_MAINNET;
+    }
+    if cfg!(feature = "devnet") {
+        features |= DEPLOYMENT_FEATURE_DEVNET;
+    }
+    if cfg!(feature = "permissioned") {
+        features |= DEPLOYMENT_FEATURE_PERMISSIONED;
+    }
+    if cfg!(feature = "alt-bn128") {
+        features |= DEPLOYMENT_FEATURE_ALT_BN128;
+    }
+    if cfg!(feature = "pool-yield") {
+        features |= DEPLOYMENT_FEATURE_POOL_YIELD;
+    }
+    if cfg!(feature = "balance-tracking") {
+        features |= DEPLOYMENT_FEATURE_BALANCE_TRACKING;
+    }
+    features
+}
+
+/// Returns the git commit the program has been built from (`GITHUB_SHA` at build time)
+pub fn git_hash() -> [u8; GIT_HASH_LEN] {
+    parse_git_hash(default_env::default_env!("GITHUB_SHA", ""))
+}
+
+/// Parses a hex-encoded (40 characters) git hash (zero if `hash` is invalid)
+fn parse_git_hash(hash: &str) -> [u8; GIT_HASH_LEN] {
+    let mut bytes = [0; GIT_HASH_LEN];
+    if hash.len() != GIT_HASH_LEN * 2 {
+        return bytes;
+    }
+
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        match u8::from_str_radix(&hash[i * 2..i * 2 + 2], 16) {
+            Ok(b) => *byte = b,
+            Err(_) => return [0; GIT_HASH_LEN],
+        }
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_git_hash() {
+        let mut expected = [0xab; GIT_HASH_LEN];
+        expected[0] = 0x01;
+        assert_eq!(
+            parse_git_hash("01abababababababababababababababababababab"),
+            expected
+        );
+
+        assert_eq!(parse_git_hash(""), [0; GIT_HASH_LEN]);
+        assert_eq!(parse_git_hash("01ab"), [0; GIT_HASH_LEN]);
+        assert_eq!(
+            parse_git_hash("zzabababababababababababababababababababab"),
+            [0; GIT_HASH_LEN]
+        );
+    }
+
+    #[test]
+    fn test_compiled_features() {
+        assert_eq!(
+            compiled_features() & DEPLOYMENT_FEATURE_MAINNET != 0,
+            cfg!(feature = "mainnet")
+        );
+        assert_eq!(
+            compiled_features() & DEPLOYMENT_FEATURE_PERMISSIONED != 0,
+            cfg!(feature = "permissioned")
+        );
+        assert_eq!(
+            compiled_features() & DEPLOYMENT_FEATURE_ALT_BN128 != 0,
+            cfg!(feature = "alt-bn128")
+        );
+    }
+}
@@ -21,6 +21,23 @@ impl BasisPointFee {
     }
 }
 
+/// Returns the spread (of `spread_bps` basis points) charged for settling the lamport-denominated `fee` in the sent token
+///
+/// # Note
+///
+/// The spread covers the conversion of the fee at the oracle rate, fees settled in Lamports are not charged a spread.
+pub fn token_fee_spread(fee: Token, spread_bps: u16) -> Token {
+    let token_id = fee.token_id();
+    if token_id == 0 {
+        return Token::new(token_id, 0);
+    }
+
+    Token::new(
+        token_id,
+        (fee.amount() as u128 * spread_bps as u128 / 10_000) as u64,
+    )
+}
+
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone)]
 #[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
 pub struct ProgramFee {
@@ -109,6 +126,9 @@ pub struct FeeAccount {
     pda_data: PDAAccountData,
 
     pub program_fee: ProgramFee,
+
+    /// The slot from which on this fee-version is used
+    pub activation_slot: u64,
 }
 
 impl ProgramFee {
@@ -152,6 +172,7 @@ impl ProgramFee {
         amount: u64,
         token_id: u16,
         price: &TokenPrice,
+        token_fee_spread_bps: u16,
     ) -> Result<Token, TokenError> {
         let proof_verification_fee = self
             .proof_verification_computation_fee(input_preparation_tx_count)
@@ -159,7 +180,11 @@ impl ProgramFee {
         let commitment_hash_fee = self
             .commitment_hash_computation_fee(min_batching_rate)
             .into_token(price, token_id)?;
-        let network_fee = Token::new(token_id, self.proof_network_fee.calc(amount));
+        let spread = token_fee_spread(
+            (proof_verification_fee + commitment_hash_fee)?,
+            token_fee_spread_bps,
+        );
+        let network_fee = (Token::new(token_id, self.proof_network_fee.calc(amount)) + spread)?;
         let subvention = self.proof_subvention.into_token(price, token_id)?;
 
         ((proof_verification_fee + commitment_hash_fee)? + network_fee)? - subvention
@@ -1,7 +1,20 @@
-use super::{fee::ProgramFee, program_account::PDAAccountData};
-use crate::macros::elusiv_account;
+use super::{
+    fee::ProgramFee, program_account::PDAAccountData, recipient::TOKEN_COUNT,
+    storage::commitment_mt_shard,
+};
+use crate::bytes::ElusivOption;
+use crate::error::ElusivError;
+use crate::macros::{elusiv_account, guard, BorshSerDeSized};
+use crate::processor::MAX_VERIFICATION_ACCOUNT_INSTANCES;
+use crate::token::{elusiv_token, TokenPrice};
+use crate::types::U256;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey, rent::Rent,
+};
 
-#[elusiv_account(eager_type: true)]
+#[elusiv_account]
 pub struct GovernorAccount {
     #[no_getter]
     #[no_setter]
@@ -17,18 +30,850 @@ pub struct GovernorAccount {
     pub commitment_batching_rate: u32,
 
     program_version: u32,
+
+    /// The next fee-version (only pending if `scheduled_fee_version > fee_version`)
+    pub scheduled_fee_version: u32,
+    pub scheduled_program_fee: ProgramFee,
+
+    /// The slot at which `scheduled_fee_version` replaces `fee_version`
+    pub scheduled_fee_activation_slot: u64,
+
+    /// The commitment queue length at which new verifications are rejected (`0` disables the watermark)
+    pub commitment_queue_watermark: u32,
+
+    /// The number of `VerificationAccount` instances per fee-payer (`0` uses [`MAX_VERIFICATION_ACCOUNT_INSTANCES`])
+    pub verification_account_instances: u8,
+
+    /// The warden attestation level required from fee-payers of stores and verifications (`0` is the open mode)
+    ///
+    /// # Note
+    ///
+    /// Only enforced by builds with the `permissioned` feature.
+    pub required_warden_attestation_level: u8,
+
+    /// The lowest protocol version accepted by versioned instructions
+    pub min_protocol_version: u8,
+
+    /// The highest protocol version accepted by versioned instructions (`0` uses [`PROTOCOL_VERSION`])
+    pub max_protocol_version: u8,
+
+    /// The max join-split amount per commitment in the active MT, per token (`0` disables the limit)
+    ///
+    /// # Note
+    ///
+    /// Young MTs have small anonymity sets, so large amounts need to wait until the MT has grown.
+    pub tree_age_amount_limits: [u64; TOKEN_COUNT],
+
+    /// The number of `VerificationAccount` instances (with the highest indices) reserved for merges and migrations
+    pub merge_verification_account_instances: u8,
+
+    /// Emergency halt of new stores, verifications and commitment hashing batches
+    ///
+    /// # Note
+    ///
+    /// In-flight computations can still be finalized, forfeited and closed (rent and funds are never locked).
+    pub is_protocol_paused: bool,
+
+    /// The index of the active MT (re-pointed by the last step of a MT rollover)
+    pub active_mt_index: u32,
+
+    /// The slot at which the commitment queue has been observed full by `ResizeQueueAccount` (`0` if it has not been full since)
+    pub commitment_queue_full_since_slot: u64,
+
+    /// The max ciphertext length (in bytes) of each commitment metadata size class (`0` disables the class)
+    ///
+    /// # Note
+    ///
+    /// The enabled classes are ordered by their max ciphertext length.
+    pub metadata_size_class_max_lens: [u16; METADATA_SIZE_CLASS_COUNT],
+
+    /// The fee multiplier (in basis points) applied to the rent of the ciphertext bytes of each size class
+    pub metadata_size_class_fee_multipliers: [u16; METADATA_SIZE_CLASS_COUNT],
+
+    /// The spread (in basis points) charged on lamport-denominated fees settled in the stored or sent token
+    ///
+    /// # Note
+    ///
+    /// The spread is collected as part of the network fee (see [`crate::state::fee::token_fee_spread`]).
+    pub token_fee_spread_bps: u16,
+
+    /// The max share (in basis points) of a fee-payer in the base-commitment hashes finalized within a window (`0` disables the fair-share)
+    ///
+    /// # Note
+    ///
+    /// See [`crate::state::commitment::CommitmentQueueAccount::record_fair_share`].
+    pub hashing_fair_share_bps: u16,
+
+    /// The length (in slots) of a fair-share window
+    pub hashing_fair_share_window: u64,
+
+    /// Rejects stores and verifications without a warden nonce
+    ///
+    /// # Note
+    ///
+    /// Fee-payers need to open their [`crate::state::warden_nonce::WardenNonceAccount`] before switching this on.
+    pub require_warden_nonces: bool,
+
+    /// The handling of payouts to recipients flagged by the APA of the Elusiv Warden Network
+    pub apa_enforcement: ApaEnforcement,
+
+    /// Incremented by each devnet reset (verifications initialized before a reset cannot be finalized)
+    pub reset_generation: u32,
+
+    /// The number of concurrently active MTs (`0` is a single active MT)
+    ///
+    /// # Note
+    ///
+    /// New commitments are assigned to the MT-shards by [`crate::state::storage::commitment_mt_shard`].
+    pub active_mt_shards: u32,
+}
+
+/// The handling of payouts to recipients flagged by an accepted APA proposal (see [`crate::processor::is_apa_flagged`])
+///
+/// # Note
+///
+/// Flags are only checked for single payouts, batched payouts are rejected while the enforcement is enabled.
+#[repr(u8)]
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ApaEnforcement {
+    Disabled,
+
+    /// The finalization of payouts to flagged recipients fails
+    Block,
+
+    /// Payouts to flagged recipients are moved into the [`QuarantineAccount`] (without being releasable by the recipient)
+    Quarantine,
+}
+
+/// The number of governance-configured size classes of commitment metadata ciphertexts
+pub const METADATA_SIZE_CLASS_COUNT: usize = 4;
+
+/// Upper bound of the max ciphertext length of a commitment metadata size class (the account layouts are sized for this length)
+pub const MAX_METADATA_CIPHERTEXT_LEN: u16 = 1024;
+
+/// Lower bound of the fee multiplier of a commitment metadata size class (ciphertexts always pay for their rent)
+pub const MIN_METADATA_FEE_MULTIPLIER_BPS: u16 = 10_000;
+
+/// Upper bound of the spread charged on fees settled in a token
+pub const MAX_TOKEN_FEE_SPREAD_BPS: u16 = 1_000;
+
+/// Lower bound of the fair-share of a fee-payer in the base-commitment hashes finalized within a window
+pub const MIN_HASHING_FAIR_SHARE_BPS: u16 = 1_000;
+
+/// The protocol version implemented by this build (sent by clients as the first byte of versioned instructions)
+pub const PROTOCOL_VERSION: u8 = 1;
+
+impl<'a> GovernorAccount<'a> {
+    pub fn has_scheduled_fee_version(&self) -> bool {
+        self.get_scheduled_fee_version() > self.get_fee_version()
+    }
+
+    /// Returns the fee-version and its `ProgramFee` active at `slot`
+    pub fn active_fee_version(&self, slot: u64) -> (u32, ProgramFee) {
+        if self.has_scheduled_fee_version() && slot >= self.get_scheduled_fee_activation_slot() {
+            (
+                self.get_scheduled_fee_version(),
+                self.get_scheduled_program_fee(),
+            )
+        } else {
+            (self.get_fee_version(), self.get_program_fee())
+        }
+    }
+
+    /// Returns the `ProgramFee` for a client-quoted `fee_version`, if this version is active at `slot`
+    pub fn program_fee_for_quote(
+        &self,
+        fee_version: u32,
+        slot: u64,
+    ) -> Result<ProgramFee, ProgramError> {
+        let (active_fee_version, program_fee) = self.active_fee_version(slot);
+        guard!(
+            !(self.has_scheduled_fee_version()
+                && fee_version == self.get_scheduled_fee_version()
+                && fee_version != active_fee_version),
+            ElusivError::FeeVersionNotYetActive
+        );
+        guard!(
+            fee_version == active_fee_version,
+            ElusivError::InvalidFeeVersion
+        );
+
+        Ok(program_fee)
+    }
+
+    /// Returns true if the commitment queue has reached the watermark
+    pub fn is_commitment_queue_under_pressure(&self, queue_len: u32) -> bool {
+        let watermark = self.get_commitment_queue_watermark();
+        watermark > 0 && queue_len >= watermark
+    }
+
+    /// Returns true if a `VerificationAccount` can be used with the offset `verification_account_index`
+    pub fn is_valid_verification_account_index(&self, verification_account_index: u8) -> bool {
+        verification_account_index < self.effective_verification_account_instances()
+    }
+
+    /// Returns true if a `VerificationAccount` with the offset `verification_account_index` can be used for a new request
+    ///
+    /// # Note
+    ///
+    /// Sends can not use the instances reserved for merges and migrations (merges can use all instances).
+    pub fn is_valid_verification_account_index_for_request(
+        &self,
+        verification_account_index: u8,
+        is_merge: bool,
+    ) -> bool {
+        let instances = self.effective_verification_account_instances();
+        if is_merge {
+            return verification_account_index < instances;
+        }
+
+        verification_account_index
+            < instances.saturating_sub(self.get_merge_verification_account_instances())
+    }
+
+    fn effective_verification_account_instances(&self) -> u8 {
+        match self.get_verification_account_instances() {
+            0 => MAX_VERIFICATION_ACCOUNT_INSTANCES,
+            instances => instances,
+        }
+    }
+
+    /// Returns true if the commitment queue has reached the watermark by growing from `previous_len` to `queue_len`
+    pub fn has_crossed_commitment_queue_watermark(
+        &self,
+        previous_len: u32,
+        queue_len: u32,
+    ) -> bool {
+        !self.is_commitment_queue_under_pressure(previous_len)
+            && self.is_commitment_queue_under_pressure(queue_len)
+    }
+
+    /// Returns true if `protocol_version` lies in the supported range of protocol versions
+    pub fn is_supported_protocol_version(&self, protocol_version: u8) -> bool {
+        let max_protocol_version = match self.get_max_protocol_version() {
+            0 => PROTOCOL_VERSION,
+            max_protocol_version => max_protocol_version,
+        };
+
+        protocol_version >= self.get_min_protocol_version()
+            && protocol_version <= max_protocol_version
+    }
+
+    /// Returns the max join-split amount for `token_id` in a MT with `commitment_count` commitments (`None` if there is no limit)
+    pub fn max_amount_for_tree_age(&self, token_id: u16, commitment_count: u32) -> Option<u64> {
+        match self.get_tree_age_amount_limits(token_id as usize) {
+            0 => None,
+            limit => Some(limit.saturating_mul(commitment_count as u64)),
+        }
+    }
+
+    /// Returns the index of the smallest enabled size class fitting a ciphertext of `ciphertext_len` bytes
+    pub fn metadata_size_class(&self, ciphertext_len: usize) -> Result<usize, ProgramError> {
+        (0..METADATA_SIZE_CLASS_COUNT)
+            .filter(|&class| self.get_metadata_size_class_max_lens(class) != 0)
+            .find(|&class| self.get_metadata_size_class_max_lens(class) as usize >= ciphertext_len)
+            .ok_or_else(|| ElusivError::InvalidInstructionData.into())
+    }
+
+    /// Returns the fee for storing a ciphertext of `ciphertext_len` bytes
+    ///
+    /// # Note
+    ///
+    /// The ciphertext pays for the rent of the max length of its size class (multiplied by the class' fee multiplier).
+    pub fn metadata_ciphertext_fee(
+        &self,
+        ciphertext_len: usize,
+        rent: &Rent,
+    ) -> Result<u64, ProgramError> {
+        let class = self.metadata_size_class(ciphertext_len)?;
+        let max_len = self.get_metadata_size_class_max_lens(class) as usize;
+        let ciphertext_rent = rent.minimum_balance(max_len) - rent.minimum_balance(0);
+
+        Ok(bps_of(
+            ciphertext_rent,
+            self.get_metadata_size_class_fee_multipliers(class),
+        ))
+    }
+
+    /// Returns the MT-shard into which `commitment` is inserted
+    pub fn commitment_mt_shard(&self, commitment: &U256) -> u32 {
+        commitment_mt_shard(commitment, self.get_active_mt_shards())
+    }
+
+    /// Replaces the current fee-version with the scheduled one, once the activation slot has been reached
+    pub fn activate_scheduled_fee_version(&mut self, slot: u64) {
+        let (fee_version, program_fee) = self.active_fee_version(slot);
+        self.set_fee_version(&fee_version);
+        self.set_program_fee(&program_fee);
+    }
 }
 
-#[elusiv_account(eager_type: true)]
+#[elusiv_account]
 pub struct PoolAccount {
     #[no_getter]
     #[no_setter]
     pda_data: PDAAccountData,
 }
 
-#[elusiv_account(eager_type: true)]
+/// Upper bound of [`PoolYieldAccount`]'s `max_delegation_bps`
+pub const MAX_POOL_DELEGATION_BPS: u16 = 2_000;
+
+/// Lower bound of [`PoolYieldAccount`]'s `buffer_bps`
+pub const MIN_POOL_BUFFER_BPS: u16 = 5_000;
+
+/// Configuration and principal of the pool lamports delegated to a whitelisted SPL stake pool
+///
+/// # Note
+///
+/// - At most `max_delegation_bps` of the pool's lamports (liquid and delegated) can be delegated.
+/// - The liquid pool lamports always cover `buffer_bps` of the lamports liability (see [`crate::state::metrics::MetricsAccount::pool_liability`]).
+/// - If the liquid lamports fall below this buffer (or can't cover a pending payout), anyone can withdraw the deficit from the stake pool back into the pool.
+#[elusiv_account]
+pub struct PoolYieldAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The whitelisted stake pool (`None` disables delegations)
+    pub stake_pool: ElusivOption<Pubkey>,
+
+    pub max_delegation_bps: u16,
+    pub buffer_bps: u16,
+
+    /// The delegated principal (in lamports)
+    pub delegated_lamports: u64,
+}
+
+impl<'a> PoolYieldAccount<'a> {
+    /// The liquid lamports the pool needs to hold for a lamports `liability`
+    pub fn required_buffer(&self, liability: u64) -> u64 {
+        bps_of(liability, self.get_buffer_bps())
+    }
+
+    /// Returns the maximum amount of lamports that can be delegated from a pool holding `pool_balance` liquid lamports
+    pub fn max_delegation(&self, pool_balance: u64, liability: u64) -> u64 {
+        if self.get_stake_pool().option().is_none() {
+            return 0;
+        }
+
+        let delegated_lamports = self.get_delegated_lamports();
+        let cap = bps_of(
+            pool_balance.saturating_add(delegated_lamports),
+            self.get_max_delegation_bps(),
+        );
+
+        cap.saturating_sub(delegated_lamports)
+            .min(pool_balance.saturating_sub(self.required_buffer(liability)))
+    }
+
+    /// Returns true if the liquid lamports don't cover the required buffer anymore
+    pub fn is_buffer_depleted(&self, pool_balance: u64, liability: u64) -> bool {
+        pool_balance < self.required_buffer(liability)
+    }
+
+    /// Returns the lamports missing for the required buffer and a `pending_payout` (the maximum amount of a permissionless withdrawal)
+    pub fn buffer_deficit(&self, pool_balance: u64, liability: u64, pending_payout: u64) -> u64 {
+        self.required_buffer(liability)
+            .saturating_add(pending_payout)
+            .saturating_sub(pool_balance)
+    }
+}
+
+fn bps_of(amount: u64, bps: u16) -> u64 {
+    (amount as u128 * bps as u128 / 10_000) as u64
+}
+
+#[elusiv_account]
 pub struct FeeCollectorAccount {
     #[no_getter]
     #[no_setter]
     pda_data: PDAAccountData,
 }
+
+/// Escrow for payouts that have been denied by their recipients
+#[elusiv_account]
+pub struct QuarantineAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+}
+
+/// Mint configurations pinned from the on-chain mints (indexed by `token_id`)
+///
+/// # Note
+///
+/// Sends of a token are only accepted after its mint has been verified against `Token.toml`.
+#[elusiv_account]
+pub struct TokenRegistryAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub is_verified: [bool; TOKEN_COUNT],
+    pub decimals: [u8; TOKEN_COUNT],
+    pub has_mint_authority: [bool; TOKEN_COUNT],
+    pub has_freeze_authority: [bool; TOKEN_COUNT],
+
+    /// Governance-pinned conversion rates (Lamports per whole token), replacing the oracle prices
+    pub is_fixed_rate: [bool; TOKEN_COUNT],
+    pub fixed_rate: [u64; TOKEN_COUNT],
+}
+
+impl<'a> TokenRegistryAccount<'a> {
+    /// Pins the mint state of a token (only possible once and only if the decimals match the token's config)
+    pub fn pin_token_config(
+        &mut self,
+        token_id: u16,
+        decimals: u8,
+        has_mint_authority: bool,
+        has_freeze_authority: bool,
+    ) -> ProgramResult {
+        let token = elusiv_token(token_id)?;
+        let index = token_id as usize;
+
+        guard!(
+            !self.get_is_verified(index),
+            ElusivError::InvalidAccountState
+        );
+        guard!(decimals == token.decimals, ElusivError::InvalidTokenConfig);
+
+        self.set_is_verified(index, &true);
+        self.set_decimals(index, &decimals);
+        self.set_has_mint_authority(index, &has_mint_authority);
+        self.set_has_freeze_authority(index, &has_freeze_authority);
+
+        Ok(())
+    }
+
+    /// Returns an error, if a token has not been verified or its pinned decimals differ from the token's config
+    pub fn enforce_verified_token(&self, token_id: u16) -> ProgramResult {
+        let token = elusiv_token(token_id)?;
+        let index = token_id as usize;
+
+        guard!(self.get_is_verified(index), ElusivError::TokenNotVerified);
+        guard!(
+            self.get_decimals(index) == token.decimals,
+            ElusivError::InvalidTokenConfig
+        );
+
+        Ok(())
+    }
+
+    /// Pins a fixed conversion rate for a verified SPL token (a rate of zero removes the fixed rate)
+    pub fn set_token_fixed_rate(
+        &mut self,
+        token_id: u16,
+        lamports_per_token: u64,
+    ) -> ProgramResult {
+        guard!(token_id != 0, ElusivError::InvalidInstructionData);
+        self.enforce_verified_token(token_id)?;
+
+        let index = token_id as usize;
+        self.set_is_fixed_rate(index, &(lamports_per_token > 0));
+        self.set_fixed_rate(index, &lamports_per_token);
+
+        Ok(())
+    }
+
+    /// Returns the [`TokenPrice`] of a token, the price accounts are only read if the token has no fixed rate
+    pub fn token_price(
+        &self,
+        sol_usd_price_account: &AccountInfo,
+        token_usd_price_account: &AccountInfo,
+        token_id: u16,
+    ) -> Result<TokenPrice, ProgramError> {
+        elusiv_token(token_id)?;
+        let index = token_id as usize;
+
+        if self.get_is_fixed_rate(index) {
+            return Ok(TokenPrice::new_fixed_rate(
+                self.get_fixed_rate(index),
+                token_id,
+            )?);
+        }
+
+        TokenPrice::new(sol_usd_price_account, token_usd_price_account, token_id)
+    }
+}
+
+/// The number of slots between the approval of an upgrade and the earliest deployment of it (~ two days)
+pub const UPGRADE_TIMELOCK_SLOTS: u64 = 2 * 24 * 60 * 60 * 1000 / 400;
+
+/// Gate for program upgrades
+///
+/// # Note
+///
+/// - the program-data hash of an upgrade needs to be approved by governance before the upgrade authority deploys it
+/// - after the deployment, `confirm_upgrade` records the active hash (the auditable upgrade trail)
+#[elusiv_account]
+pub struct UpgradeGateAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The approved program-data hash (only pending if `is_pending`)
+    pub approved_program_data_hash: U256,
+    pub is_pending: bool,
+
+    /// The earliest slot at which the approved upgrade can be deployed
+    pub earliest_deployment_slot: u64,
+
+    /// The program-data hash of the currently deployed (and confirmed) program
+    pub active_program_data_hash: U256,
+    pub active_deployment_slot: u64,
+
+    /// The number of confirmed upgrades
+    pub upgrade_count: u32,
+}
+
+impl<'a> UpgradeGateAccount<'a> {
+    /// Approves a new program-data hash, which can be deployed after [`UPGRADE_TIMELOCK_SLOTS`]
+    pub fn approve(&mut self, program_data_hash: &U256, slot: u64) -> Result<(), ProgramError> {
+        let earliest_deployment_slot = slot
+            .checked_add(UPGRADE_TIMELOCK_SLOTS)
+            .ok_or(ElusivError::InvalidInstructionData)?;
+
+        self.set_approved_program_data_hash(program_data_hash);
+        self.set_is_pending(&true);
+        self.set_earliest_deployment_slot(&earliest_deployment_slot);
+
+        Ok(())
+    }
+
+    /// Records a deployed program as the active program, if it matches the pending approval
+    pub fn confirm(
+        &mut self,
+        program_data_hash: &U256,
+        deployment_slot: u64,
+    ) -> Result<(), ProgramError> {
+        guard!(self.get_is_pending(), ElusivError::InvalidAccountState);
+        guard!(
+            self.get_approved_program_data_hash() == *program_data_hash,
+            ElusivError::InvalidAccountState
+        );
+        guard!(
+            deployment_slot >= self.get_earliest_deployment_slot(),
+            ElusivError::UpgradeTimelockNotReached
+        );
+
+        self.set_is_pending(&false);
+        self.set_active_program_data_hash(program_data_hash);
+        self.set_active_deployment_slot(&deployment_slot);
+        self.set_upgrade_count(&(self.get_upgrade_count() + 1));
+
+        Ok(())
+    }
+}
+
+/// Runtime feature-flags (controlled by governance)
+#[elusiv_account]
+pub struct FeatureFlagsAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Instructions deprecated since this version (or earlier) are rejected (`0` only logs deprecation warnings)
+    pub deprecation_enforcement_version: u32,
+}
+
+impl<'a> FeatureFlagsAccount<'a> {
+    /// Returns true if an instruction with `#[deprecated_since(deprecated_since)]` is rejected
+    pub fn is_deprecation_enforced(&self, deprecated_since: u32) -> bool {
+        let enforcement_version = self.get_deprecation_enforcement_version();
+        enforcement_version > 0 && deprecated_since <= enforcement_version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{test_account_info, zero_program_account};
+    use crate::token::{Lamports, Token, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID, USDT_TOKEN_ID};
+
+    #[test]
+    fn test_pool_yield_max_delegation() {
+        zero_program_account!(mut pool_yield, PoolYieldAccount);
+
+        // No stake pool
+        assert_eq!(pool_yield.max_delegation(1_000_000, 0), 0);
+
+        pool_yield.set_stake_pool(&ElusivOption::Some(Pubkey::new_unique()));
+        pool_yield.set_max_delegation_bps(&MAX_POOL_DELEGATION_BPS);
+        pool_yield.set_buffer_bps(&MIN_POOL_BUFFER_BPS);
+
+        // Bounded by the cap
+        assert_eq!(pool_yield.max_delegation(1_000_000, 0), 200_000);
+
+        // Bounded by the buffer
+        assert_eq!(pool_yield.max_delegation(1_000_000, 1_800_000), 100_000);
+        assert_eq!(pool_yield.max_delegation(1_000_000, 2_000_000), 0);
+
+        // Delegated lamports count towards the cap
+        pool_yield.set_delegated_lamports(&150_000);
+        assert_eq!(pool_yield.max_delegation(850_000, 0), 50_000);
+        assert_eq!(pool_yield.max_delegation(600_000, 0), 0);
+
+        assert!(!pool_yield.is_buffer_depleted(850_000, 1_700_000));
+        assert!(pool_yield.is_buffer_depleted(849_999, 1_700_000));
+
+        // The deficit covers the buffer and a pending payout
+        assert_eq!(pool_yield.buffer_deficit(850_000, 1_700_000, 0), 0);
+        assert_eq!(pool_yield.buffer_deficit(849_999, 1_700_000, 0), 1);
+        assert_eq!(
+            pool_yield.buffer_deficit(850_000, 1_700_000, 100_000),
+            100_000
+        );
+        assert_eq!(pool_yield.buffer_deficit(2_000_000, 1_700_000, 100_000), 0);
+    }
+
+    #[test]
+    fn test_scheduled_fee_version() {
+        zero_program_account!(mut governor, GovernorAccount);
+        let fee = ProgramFee::new(1, 2, 3, 0, 0, 4, 5).unwrap();
+        let next_fee = ProgramFee::new(2, 3, 4, 0, 0, 5, 6).unwrap();
+        governor.set_program_fee(&fee);
+
+        assert!(!governor.has_scheduled_fee_version());
+        assert_eq!(governor.program_fee_for_quote(0, 100), Ok(fee.clone()));
+
+        governor.set_scheduled_fee_version(&1);
+        governor.set_scheduled_program_fee(&next_fee);
+        governor.set_scheduled_fee_activation_slot(&100);
+        assert!(governor.has_scheduled_fee_version());
+
+        // Before activation
+        assert_eq!(governor.active_fee_version(99), (0, fee.clone()));
+        assert_eq!(governor.program_fee_for_quote(0, 99), Ok(fee));
+        assert_eq!(
+            governor.program_fee_for_quote(1, 99),
+            Err(ElusivError::FeeVersionNotYetActive.into())
+        );
+        assert_eq!(
+            governor.program_fee_for_quote(2, 99),
+            Err(ElusivError::InvalidFeeVersion.into())
+        );
+
+        // After activation
+        assert_eq!(governor.active_fee_version(100), (1, next_fee.clone()));
+        assert_eq!(
+            governor.program_fee_for_quote(0, 100),
+            Err(ElusivError::InvalidFeeVersion.into())
+        );
+        assert_eq!(governor.program_fee_for_quote(1, 100), Ok(next_fee.clone()));
+
+        governor.activate_scheduled_fee_version(100);
+        assert!(!governor.has_scheduled_fee_version());
+        assert_eq!(governor.get_fee_version(), 1);
+        assert_eq!(governor.get_program_fee(), next_fee);
+    }
+
+    #[test]
+    fn test_commitment_queue_watermark() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        // Disabled watermark
+        assert!(!governor.is_commitment_queue_under_pressure(u32::MAX));
+        assert!(!governor.has_crossed_commitment_queue_watermark(0, u32::MAX));
+
+        governor.set_commitment_queue_watermark(&10);
+        assert!(!governor.is_commitment_queue_under_pressure(9));
+        assert!(governor.is_commitment_queue_under_pressure(10));
+        assert!(governor.is_commitment_queue_under_pressure(11));
+
+        assert!(!governor.has_crossed_commitment_queue_watermark(8, 9));
+        assert!(governor.has_crossed_commitment_queue_watermark(9, 10));
+        assert!(governor.has_crossed_commitment_queue_watermark(0, 11));
+        assert!(!governor.has_crossed_commitment_queue_watermark(10, 11));
+        assert!(!governor.has_crossed_commitment_queue_watermark(11, 10));
+    }
+
+    #[test]
+    fn test_is_valid_verification_account_index() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        // Compile-time ceiling
+        assert!(
+            governor.is_valid_verification_account_index(MAX_VERIFICATION_ACCOUNT_INSTANCES - 1)
+        );
+        assert!(!governor.is_valid_verification_account_index(MAX_VERIFICATION_ACCOUNT_INSTANCES));
+
+        governor.set_verification_account_instances(&2);
+        assert!(governor.is_valid_verification_account_index(1));
+        assert!(!governor.is_valid_verification_account_index(2));
+    }
+
+    #[test]
+    fn test_is_valid_verification_account_index_for_request() {
+        zero_program_account!(mut governor, GovernorAccount);
+        governor.set_verification_account_instances(&4);
+        assert!(governor.is_valid_verification_account_index_for_request(3, false));
+
+        // The last instance is reserved for merges
+        governor.set_merge_verification_account_instances(&1);
+        assert!(governor.is_valid_verification_account_index_for_request(2, false));
+        assert!(!governor.is_valid_verification_account_index_for_request(3, false));
+        assert!(governor.is_valid_verification_account_index_for_request(3, true));
+        assert!(!governor.is_valid_verification_account_index_for_request(4, true));
+    }
+
+    #[test]
+    fn test_is_supported_protocol_version() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        assert!(governor.is_supported_protocol_version(0));
+        assert!(governor.is_supported_protocol_version(PROTOCOL_VERSION));
+        assert!(!governor.is_supported_protocol_version(PROTOCOL_VERSION + 1));
+
+        governor.set_min_protocol_version(&PROTOCOL_VERSION);
+        assert!(!governor.is_supported_protocol_version(PROTOCOL_VERSION - 1));
+        assert!(governor.is_supported_protocol_version(PROTOCOL_VERSION));
+
+        governor.set_max_protocol_version(&(PROTOCOL_VERSION + 1));
+        assert!(governor.is_supported_protocol_version(PROTOCOL_VERSION + 1));
+    }
+
+    #[test]
+    fn test_commitment_mt_shard() {
+        zero_program_account!(mut governor, GovernorAccount);
+        assert_eq!(governor.commitment_mt_shard(&[1; 32]), 0);
+
+        governor.set_active_mt_shards(&2);
+        assert_eq!(governor.commitment_mt_shard(&[0; 32]), 0);
+        assert_eq!(governor.commitment_mt_shard(&[1; 32]), 1);
+    }
+
+    #[test]
+    fn test_upgrade_gate() {
+        zero_program_account!(mut gate, UpgradeGateAccount);
+        let hash = [1; 32];
+
+        // No pending approval
+        assert_eq!(
+            gate.confirm(&hash, UPGRADE_TIMELOCK_SLOTS),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        gate.approve(&hash, 100).unwrap();
+        assert_eq!(
+            gate.get_earliest_deployment_slot(),
+            100 + UPGRADE_TIMELOCK_SLOTS
+        );
+
+        // Unapproved hash
+        assert_eq!(
+            gate.confirm(&[2; 32], 100 + UPGRADE_TIMELOCK_SLOTS),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // Deployed before the timelock
+        assert_eq!(
+            gate.confirm(&hash, 100 + UPGRADE_TIMELOCK_SLOTS - 1),
+            Err(ElusivError::UpgradeTimelockNotReached.into())
+        );
+
+        gate.confirm(&hash, 100 + UPGRADE_TIMELOCK_SLOTS).unwrap();
+        assert!(!gate.get_is_pending());
+        assert_eq!(gate.get_active_program_data_hash(), hash);
+        assert_eq!(
+            gate.get_active_deployment_slot(),
+            100 + UPGRADE_TIMELOCK_SLOTS
+        );
+        assert_eq!(gate.get_upgrade_count(), 1);
+
+        // An approval can only be confirmed once
+        assert_eq!(
+            gate.confirm(&hash, 100 + UPGRADE_TIMELOCK_SLOTS),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        assert!(gate.approve(&hash, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_token_registry() {
+        zero_program_account!(mut registry, TokenRegistryAccount);
+        let usdc = elusiv_token(USDC_TOKEN_ID).unwrap();
+
+        // Unverified tokens
+        assert_eq!(
+            registry.enforce_verified_token(LAMPORTS_TOKEN_ID),
+            Err(ElusivError::TokenNotVerified.into())
+        );
+        assert_eq!(
+            registry.enforce_verified_token(USDC_TOKEN_ID),
+            Err(ElusivError::TokenNotVerified.into())
+        );
+
+        // Mismatched decimals
+        assert_eq!(
+            registry.pin_token_config(USDC_TOKEN_ID, usdc.decimals + 1, true, false),
+            Err(ElusivError::InvalidTokenConfig.into())
+        );
+
+        // Invalid token-id
+        assert!(registry
+            .pin_token_config(TOKEN_COUNT as u16, 0, false, false)
+            .is_err());
+
+        registry
+            .pin_token_config(USDC_TOKEN_ID, usdc.decimals, true, false)
+            .unwrap();
+        assert_eq!(registry.enforce_verified_token(USDC_TOKEN_ID), Ok(()));
+        assert!(registry.get_has_mint_authority(USDC_TOKEN_ID as usize));
+        assert!(!registry.get_has_freeze_authority(USDC_TOKEN_ID as usize));
+        assert_eq!(
+            registry.enforce_verified_token(LAMPORTS_TOKEN_ID),
+            Err(ElusivError::TokenNotVerified.into())
+        );
+
+        // Config can only be pinned once
+        assert_eq!(
+            registry.pin_token_config(USDC_TOKEN_ID, usdc.decimals, false, false),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        // Fixed rates
+        assert_eq!(
+            registry.set_token_fixed_rate(LAMPORTS_TOKEN_ID, 1),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+        assert_eq!(
+            registry.set_token_fixed_rate(USDT_TOKEN_ID, 1),
+            Err(ElusivError::TokenNotVerified.into())
+        );
+
+        test_account_info!(any, 0);
+        assert!(registry.token_price(&any, &any, USDC_TOKEN_ID).is_err());
+
+        registry.set_token_fixed_rate(USDC_TOKEN_ID, 2_000).unwrap();
+        assert!(registry.get_is_fixed_rate(USDC_TOKEN_ID as usize));
+        let price = registry.token_price(&any, &any, USDC_TOKEN_ID).unwrap();
+        assert_eq!(
+            price.token_into_lamports(Token::new(USDC_TOKEN_ID, 10u64.pow(usdc.decimals as u32))),
+            Ok(Lamports(2_000))
+        );
+
+        registry.set_token_fixed_rate(USDC_TOKEN_ID, 0).unwrap();
+        assert!(!registry.get_is_fixed_rate(USDC_TOKEN_ID as usize));
+        assert!(registry.token_price(&any, &any, USDC_TOKEN_ID).is_err());
+
+        // Pinned decimals differ from the config
+        registry.set_decimals(USDC_TOKEN_ID as usize, &(usdc.decimals + 1));
+        assert_eq!(
+            registry.enforce_verified_token(USDC_TOKEN_ID),
+            Err(ElusivError::InvalidTokenConfig.into())
+        );
+    }
+
+    #[test]
+    fn test_is_deprecation_enforced() {
+        zero_program_account!(mut feature_flags, FeatureFlagsAccount);
+        assert!(!feature_flags.is_deprecation_enforced(0));
+        assert!(!feature_flags.is_deprecation_enforced(1));
+
+        feature_flags.set_deprecation_enforcement_version(&2);
+        assert!(feature_flags.is_deprecation_enforced(1));
+        assert!(feature_flags.is_deprecation_enforced(2));
+        assert!(!feature_flags.is_deprecation_enforced(3));
+    }
+}
@@ -0,0 +1,141 @@
+use super::program_account::PDAAccountData;
+use crate::macros::{elusiv_account, BorshSerDeSized};
+use crate::types::U256;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Number of finalized verifications kept in the [`VerificationHistoryAccount`]
+pub const VERIFICATION_HISTORY_LEN: usize = 64;
+
+/// A finalized verification, as referenced in payout disputes
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct VerificationRecord {
+    /// `sha256` of the nullifier-hashes (see [`crate::state::proof::NullifierDuplicateAccount::associated_pubkey`])
+    pub nullifier_set_hash: U256,
+
+    /// The recipient wallet (zero for merges and migrations)
+    pub recipient: U256,
+
+    /// `sha256(token_id || amount || optional_fee_amount)`
+    pub amount_hash: U256,
+
+    /// The original fee-payer of the verification
+    pub warden: U256,
+
+    pub slot: u64,
+}
+
+impl VerificationRecord {
+    pub fn amount_hash(token_id: u16, amount: u64, optional_fee_amount: u64) -> U256 {
+        solana_program::hash::hashv(&[
+            &token_id.to_le_bytes(),
+            &amount.to_le_bytes(),
+            &optional_fee_amount.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+/// Append-only ring of the last [`VERIFICATION_HISTORY_LEN`] finalized verifications
+///
+/// # Note
+///
+/// - Records are only ever overwritten by newer records, once the ring is full.
+/// - Invalid proofs are not recorded.
+#[elusiv_account]
+pub struct VerificationHistoryAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Total number of recorded verifications (the next record is stored at `count % VERIFICATION_HISTORY_LEN`)
+    pub count: u64,
+    pub records: [VerificationRecord; VERIFICATION_HISTORY_LEN],
+}
+
+impl<'a> VerificationHistoryAccount<'a> {
+    pub fn record(&mut self, record: &VerificationRecord) {
+        let count = self.get_count();
+        self.set_records((count % VERIFICATION_HISTORY_LEN as u64) as usize, record);
+        self.set_count(&count.saturating_add(1));
+    }
+
+    /// Returns the `offset`-th most recent record
+    pub fn latest(&self, offset: usize) -> Option<VerificationRecord> {
+        let count = self.get_count();
+        if offset >= VERIFICATION_HISTORY_LEN || offset as u64 >= count {
+            return None;
+        }
+
+        let index = (count - 1 - offset as u64) % VERIFICATION_HISTORY_LEN as u64;
+        Some(self.get_records(index as usize))
+    }
+
+    /// Returns the most recent record with the `nullifier_set_hash`
+    pub fn find_by_nullifier_set_hash(
+        &self,
+        nullifier_set_hash: &U256,
+    ) -> Option<VerificationRecord> {
+        (0..VERIFICATION_HISTORY_LEN)
+            .map_while(|offset| self.latest(offset))
+            .find(|record| record.nullifier_set_hash == *nullifier_set_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+
+    fn record(n: u8) -> VerificationRecord {
+        VerificationRecord {
+            nullifier_set_hash: [n; 32],
+            slot: n as u64,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_record_verification_history() {
+        zero_program_account!(mut history, VerificationHistoryAccount);
+        assert_eq!(history.latest(0), None);
+
+        history.record(&record(1));
+        history.record(&record(2));
+        assert_eq!(history.latest(0), Some(record(2)));
+        assert_eq!(history.latest(1), Some(record(1)));
+        assert_eq!(history.latest(2), None);
+        assert_eq!(
+            history.find_by_nullifier_set_hash(&[1; 32]),
+            Some(record(1))
+        );
+
+        // Overwrite the oldest records
+        for n in 3..=(VERIFICATION_HISTORY_LEN as u8 + 1) {
+            history.record(&record(n));
+        }
+        assert_eq!(history.get_count(), VERIFICATION_HISTORY_LEN as u64 + 1);
+        assert_eq!(
+            history.latest(VERIFICATION_HISTORY_LEN - 1),
+            Some(record(2))
+        );
+        assert_eq!(history.latest(VERIFICATION_HISTORY_LEN), None);
+        assert_eq!(history.find_by_nullifier_set_hash(&[1; 32]), None);
+        assert_eq!(
+            history.find_by_nullifier_set_hash(&[2; 32]),
+            Some(record(2))
+        );
+    }
+
+    #[test]
+    fn test_amount_hash() {
+        assert_ne!(
+            VerificationRecord::amount_hash(0, 1, 0),
+            VerificationRecord::amount_hash(1, 1, 0)
+        );
+        assert_ne!(
+            VerificationRecord::amount_hash(0, 1, 0),
+            VerificationRecord::amount_hash(0, 1, 1)
+        );
+    }
+}
@@ -1,5 +1,10 @@
-use super::{commitment::COMMITMENT_QUEUE_LEN, queue::queue_account};
+use super::{
+    commitment::COMMITMENT_QUEUE_LEN, governor::MAX_METADATA_CIPHERTEXT_LEN, queue::queue_account,
+};
 use crate::commitment::MT_HEIGHT;
+use crate::error::ElusivError;
+use crate::macros::guard;
+use crate::types::U256;
 use elusiv_proc_macros::elusiv_account;
 use elusiv_types::{
     accounts::PDAAccountData, BorshSerDeSized, ChildAccount, ElusivOption, ParentAccount,
@@ -28,7 +33,7 @@ impl ChildAccount for MetadataChildAccount {
     const INNER_SIZE: usize = VALUES_PER_METADATA_CHILD_ACCOUNT * CommitmentMetadata::SIZE;
 }
 
-#[elusiv_account(parent_account: { child_account_count: ACCOUNTS_COUNT, child_account: MetadataChildAccount }, eager_type: true)]
+#[elusiv_account(parent_account: { child_account_count: ACCOUNTS_COUNT, child_account: MetadataChildAccount })]
 pub struct MetadataAccount {
     #[no_getter]
     #[no_setter]
@@ -54,13 +59,22 @@ impl<'a, 'b, 't> MetadataAccount<'a, 'b, 't> {
         Ok(())
     }
 
+    /// Removes the last `count` added [`CommitmentMetadata`]
+    pub fn remove_last_commitment_metadata(&mut self, count: u32) -> ProgramResult {
+        let metadata_index = self.get_next_metadata_ptr();
+        let metadata_index = metadata_index
+            .checked_sub(count)
+            .ok_or(ElusivError::InvalidAccountState)?;
+        self.set_next_metadata_ptr(&metadata_index);
+
+        Ok(())
+    }
+
     #[cfg(feature = "elusiv-client")]
     pub fn get_commitment_metadata(
         &self,
         index: usize,
     ) -> Result<CommitmentMetadata, solana_program::program_error::ProgramError> {
-        use crate::error::ElusivError;
-
         let metadata_index = self.get_next_metadata_ptr() as usize;
         crate::macros::guard!(index < metadata_index, ElusivError::MissingValue);
 
@@ -82,10 +96,86 @@ impl<'a, 'b, 't> MetadataAccount<'a, 'b, 't> {
     }
 }
 
+const CIPHERTEXT_LEN: usize = MAX_METADATA_CIPHERTEXT_LEN as usize;
+
+/// The encrypted note of a stored commitment (PDA associated with the commitment), allowing wallets to recover their funds from chain data alone
+///
+/// # Note
+///
+/// - The account is associated with the commitment instead of its MT-index, since the index of a queued commitment is only known once it has been inserted into the MT.
+/// - The ciphertext is written once, when the base-commitment hash is finalized.
+/// - Notes of commitments stored without a ciphertext can be backfilled by their depositor (see [`CommitmentMetadataAccount::backfill_pubkey`]).
+#[elusiv_account]
+pub struct CommitmentMetadataAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub commitment: U256,
+
+    /// See [`crate::state::governor::GovernorAccount::metadata_size_class`]
+    pub size_class: u8,
+    pub ciphertext_len: u16,
+
+    #[no_getter]
+    #[no_setter]
+    ciphertext: [u8; CIPHERTEXT_LEN],
+
+    /// The identifier of the depositor (only set for backfilled notes)
+    pub backfill_identifier: ElusivOption<Pubkey>,
+
+    /// The position of a backfilled note (verified against the leaf of the active MT)
+    pub mt_index: u32,
+    pub commitment_index: u32,
+}
+
+impl<'a> CommitmentMetadataAccount<'a> {
+    /// The pubkey a backfilled note of `commitment` is associated with
+    ///
+    /// # Note
+    ///
+    /// Backfilled notes are associated with the commitment and the `identifier` of the depositor,
+    /// so a backfill by any other identifier can neither block nor replace the depositor's note.
+    pub fn backfill_pubkey(commitment: &U256, identifier: &Pubkey) -> Pubkey {
+        Pubkey::new_from_array(
+            solana_program::hash::hashv(&[commitment, &identifier.to_bytes()]).to_bytes(),
+        )
+    }
+
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext[..self.get_ciphertext_len() as usize]
+    }
+
+    pub fn set_ciphertext(&mut self, ciphertext: &[u8]) -> ProgramResult {
+        guard!(
+            ciphertext.len() <= CIPHERTEXT_LEN,
+            ElusivError::InvalidInstructionData
+        );
+
+        self.ciphertext[..ciphertext.len()].copy_from_slice(ciphertext);
+        self.set_ciphertext_len(&(ciphertext.len() as u16));
+
+        Ok(())
+    }
+}
+
+/// A note to backfill by its depositor (see [`crate::instruction::ElusivInstruction::backfill_commitment_metadata_transactions`])
+#[cfg(feature = "elusiv-client")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct CommitmentMetadataBackfill {
+    pub mt_index: u32,
+    pub commitment_index: u32,
+    pub commitment: U256,
+    /// The identifier key of the depositor (signs the backfill)
+    pub identifier: Pubkey,
+    pub ciphertext: Vec<u8>,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::macros::parent_account;
+    use crate::macros::{parent_account, zero_program_account};
 
     fn usize_to_metadata(u: usize) -> CommitmentMetadata {
         let mut metadata = [0; CommitmentMetadata::SIZE];
@@ -112,4 +202,60 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_remove_last_commitment_metadata() {
+        parent_account!(mut metadata_account, MetadataAccount);
+
+        for i in 0..3 {
+            metadata_account
+                .add_commitment_metadata(&usize_to_metadata(i))
+                .unwrap();
+        }
+
+        metadata_account.remove_last_commitment_metadata(2).unwrap();
+        assert_eq!(metadata_account.get_next_metadata_ptr(), 1);
+        assert!(metadata_account.remove_last_commitment_metadata(2).is_err());
+
+        metadata_account
+            .add_commitment_metadata(&usize_to_metadata(3))
+            .unwrap();
+        assert_eq!(
+            metadata_account.get_commitment_metadata(1).unwrap(),
+            usize_to_metadata(3)
+        );
+    }
+
+    #[test]
+    fn test_commitment_metadata_ciphertext() {
+        zero_program_account!(mut account, CommitmentMetadataAccount);
+        assert!(account.ciphertext().is_empty());
+
+        account.set_ciphertext(&[1, 2, 3]).unwrap();
+        assert_eq!(account.ciphertext(), &[1, 2, 3]);
+
+        assert_eq!(
+            account.set_ciphertext(&[0; CIPHERTEXT_LEN + 1]),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        account.set_ciphertext(&[4; CIPHERTEXT_LEN]).unwrap();
+        assert_eq!(account.ciphertext(), &[4; CIPHERTEXT_LEN]);
+    }
+
+    #[test]
+    fn test_backfill_pubkey() {
+        let identifier = Pubkey::new_unique();
+        let pubkey = CommitmentMetadataAccount::backfill_pubkey(&[1; 32], &identifier);
+
+        assert_ne!(pubkey, Pubkey::new_from_array([1; 32]));
+        assert_ne!(
+            pubkey,
+            CommitmentMetadataAccount::backfill_pubkey(&[2; 32], &identifier)
+        );
+        assert_ne!(
+            pubkey,
+            CommitmentMetadataAccount::backfill_pubkey(&[1; 32], &Pubkey::new_unique())
+        );
+    }
 }
@@ -0,0 +1,223 @@
+use super::{program_account::PDAAccountData, recipient::TOKEN_COUNT};
+use crate::macros::elusiv_account;
+use crate::token::elusiv_token;
+use solana_program::entrypoint::ProgramResult;
+
+/// Number of buckets of the send size histogram (per token)
+pub const SEND_SIZE_BUCKET_COUNT: usize = 8;
+
+const SEND_SIZE_HISTOGRAM_LEN: usize = TOKEN_COUNT * SEND_SIZE_BUCKET_COUNT;
+
+/// Aggregated protocol statistics (indexed by `token_id`)
+///
+/// # Note
+///
+/// - Updated when base-commitments are stored and when verifications are finalized.
+/// - All counters saturate instead of failing the instruction.
+/// - `liability` is the pool's accounting (not statistics) and is therefore kept by [`MetricsAccount::reset`].
+#[elusiv_account]
+pub struct MetricsAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Total amount stored into the pool
+    pub volume_in: [u64; TOKEN_COUNT],
+
+    /// Total amount sent out of the pool
+    pub volume_out: [u64; TOKEN_COUNT],
+
+    /// Estimate of unspent notes (inserted commitments minus used nullifiers)
+    pub active_notes: [u64; TOKEN_COUNT],
+
+    /// Number of sends per order of magnitude of the sent amount (see [`send_size_bucket`])
+    pub send_size_histogram: [u32; SEND_SIZE_HISTOGRAM_LEN],
+
+    /// Total amount of (rounding) dust swept from the pool into the fee collector
+    pub dust_swept: [u64; TOKEN_COUNT],
+
+    /// Amount owed to the holders of unspent notes (stored minus sent amounts)
+    pub liability: [u64; TOKEN_COUNT],
+}
+
+impl<'a> MetricsAccount<'a> {
+    /// Records a stored base-commitment (one new note)
+    pub fn record_store(&mut self, token_id: u16, amount: u64) -> ProgramResult {
+        elusiv_token(token_id)?;
+        let index = token_id as usize;
+
+        self.set_volume_in(index, &self.get_volume_in(index).saturating_add(amount));
+        self.set_liability(index, &self.get_liability(index).saturating_add(amount));
+        self.set_active_notes(index, &self.get_active_notes(index).saturating_add(1));
+
+        Ok(())
+    }
+
+    /// Records a finalized verification that spends `input_count` notes and creates one new note
+    ///
+    /// # Note
+    ///
+    /// `amount` is the amount leaving the pool (zero for merges and migrations).
+    pub fn record_finalization(
+        &mut self,
+        token_id: u16,
+        amount: u64,
+        input_count: usize,
+    ) -> ProgramResult {
+        let token = elusiv_token(token_id)?;
+        let index = token_id as usize;
+
+        let active_notes = self
+            .get_active_notes(index)
+            .saturating_add(1)
+            .saturating_sub(input_count as u64);
+        self.set_active_notes(index, &active_notes);
+
+        if amount > 0 {
+            self.set_volume_out(index, &self.get_volume_out(index).saturating_add(amount));
+            self.set_liability(index, &self.get_liability(index).saturating_sub(amount));
+
+            let histogram_index =
+                index * SEND_SIZE_BUCKET_COUNT + send_size_bucket(amount, token.decimals);
+            self.set_send_size_histogram(
+                histogram_index,
+                &self
+                    .get_send_size_histogram(histogram_index)
+                    .saturating_add(1),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Records dust that has been swept from the pool (`volume_in - volume_out - dust_swept` remains the pool's tracked balance)
+    pub fn record_dust_sweep(&mut self, token_id: u16, amount: u64) -> ProgramResult {
+        elusiv_token(token_id)?;
+        let index = token_id as usize;
+
+        self.set_dust_swept(index, &self.get_dust_swept(index).saturating_add(amount));
+
+        Ok(())
+    }
+
+    /// Resets all counters (except for the `liability`)
+    pub fn reset(&mut self) {
+        for index in 0..TOKEN_COUNT {
+            self.set_volume_in(index, &0);
+            self.set_volume_out(index, &0);
+            self.set_active_notes(index, &0);
+            self.set_dust_swept(index, &0);
+        }
+
+        for index in 0..SEND_SIZE_HISTOGRAM_LEN {
+            self.set_send_size_histogram(index, &0);
+        }
+    }
+
+    /// Estimate of the amount owed to the holders of unspent notes (stored minus sent amounts, not affected by [`Self::reset`])
+    pub fn pool_liability(&self, token_id: u16) -> u64 {
+        self.get_liability(token_id as usize)
+    }
+
+    pub fn send_count(&self, token_id: u16) -> u64 {
+        let offset = token_id as usize * SEND_SIZE_BUCKET_COUNT;
+        (0..SEND_SIZE_BUCKET_COUNT)
+            .map(|i| self.get_send_size_histogram(offset + i) as u64)
+            .sum()
+    }
+}
+
+/// Returns the histogram bucket of an amount
+///
+/// # Note
+///
+/// Bucket `0` contains amounts below one whole token, bucket `i` amounts in `[10^(i-1), 10^i)` whole tokens (the last bucket is unbounded).
+pub fn send_size_bucket(amount: u64, decimals: u8) -> usize {
+    let mut whole_tokens = amount / 10u64.saturating_pow(decimals as u32);
+    let mut bucket = 0;
+
+    while whole_tokens > 0 && bucket < SEND_SIZE_BUCKET_COUNT - 1 {
+        whole_tokens /= 10;
+        bucket += 1;
+    }
+
+    bucket
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+    use crate::token::{LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+    use solana_program::native_token::LAMPORTS_PER_SOL;
+
+    #[test]
+    fn test_send_size_bucket() {
+        assert_eq!(send_size_bucket(0, 9), 0);
+        assert_eq!(send_size_bucket(LAMPORTS_PER_SOL - 1, 9), 0);
+        assert_eq!(send_size_bucket(LAMPORTS_PER_SOL, 9), 1);
+        assert_eq!(send_size_bucket(9 * LAMPORTS_PER_SOL, 9), 1);
+        assert_eq!(send_size_bucket(10 * LAMPORTS_PER_SOL, 9), 2);
+        assert_eq!(send_size_bucket(999_999, 6), 0);
+        assert_eq!(send_size_bucket(1_000_000, 6), 1);
+        assert_eq!(send_size_bucket(u64::MAX, 0), SEND_SIZE_BUCKET_COUNT - 1);
+    }
+
+    #[test]
+    fn test_record_metrics() {
+        zero_program_account!(mut metrics, MetricsAccount);
+
+        metrics.record_store(USDC_TOKEN_ID, 100).unwrap();
+        metrics.record_store(USDC_TOKEN_ID, 200).unwrap();
+        assert_eq!(metrics.get_volume_in(USDC_TOKEN_ID as usize), 300);
+        assert_eq!(metrics.get_active_notes(USDC_TOKEN_ID as usize), 2);
+        assert_eq!(metrics.get_volume_in(LAMPORTS_TOKEN_ID as usize), 0);
+
+        // Send spending both notes
+        metrics
+            .record_finalization(USDC_TOKEN_ID, 2_000_000, 2)
+            .unwrap();
+        assert_eq!(metrics.get_active_notes(USDC_TOKEN_ID as usize), 1);
+        assert_eq!(metrics.get_volume_out(USDC_TOKEN_ID as usize), 2_000_000);
+        assert_eq!(
+            metrics.get_send_size_histogram(USDC_TOKEN_ID as usize * SEND_SIZE_BUCKET_COUNT + 1),
+            1
+        );
+        assert_eq!(metrics.send_count(USDC_TOKEN_ID), 1);
+
+        // Merge (no amount)
+        metrics.record_finalization(USDC_TOKEN_ID, 0, 2).unwrap();
+        assert_eq!(metrics.get_active_notes(USDC_TOKEN_ID as usize), 0);
+        assert_eq!(metrics.send_count(USDC_TOKEN_ID), 1);
+
+        // Dust sweep
+        metrics.record_dust_sweep(USDC_TOKEN_ID, 7).unwrap();
+        metrics.record_dust_sweep(USDC_TOKEN_ID, 3).unwrap();
+        assert_eq!(metrics.get_dust_swept(USDC_TOKEN_ID as usize), 10);
+        assert!(metrics.record_dust_sweep(u16::MAX, 1).is_err());
+
+        assert!(metrics.record_store(u16::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn test_pool_liability() {
+        zero_program_account!(mut metrics, MetricsAccount);
+
+        metrics.record_store(LAMPORTS_TOKEN_ID, 300).unwrap();
+        metrics
+            .record_finalization(LAMPORTS_TOKEN_ID, 100, 1)
+            .unwrap();
+        assert_eq!(metrics.pool_liability(LAMPORTS_TOKEN_ID), 200);
+        assert_eq!(metrics.pool_liability(USDC_TOKEN_ID), 0);
+
+        // The liability is not affected by a reset of the statistics
+        metrics.reset();
+        assert_eq!(metrics.get_volume_in(LAMPORTS_TOKEN_ID as usize), 0);
+        assert_eq!(metrics.pool_liability(LAMPORTS_TOKEN_ID), 200);
+
+        metrics
+            .record_finalization(LAMPORTS_TOKEN_ID, 300, 1)
+            .unwrap();
+        assert_eq!(metrics.pool_liability(LAMPORTS_TOKEN_ID), 0);
+    }
+}
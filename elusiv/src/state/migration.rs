@@ -0,0 +1,212 @@
+use super::program_account::PDAAccountData;
+use crate::bytes::ElusivOption;
+use crate::error::ElusivError;
+use crate::macros::{elusiv_account, guard};
+use crate::types::U256;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::pubkey::Pubkey;
+
+/// Maximum number of accounts tracked by a [`MigrationManifestAccount`]
+pub const MAX_MIGRATION_ENTRIES: usize = 64;
+
+/// Maximum size of a single exported or imported chunk (the export is written as return data)
+pub const MAX_MIGRATION_CHUNK_SIZE: usize = solana_program::program::MAX_RETURN_DATA;
+
+/// Extends the hash-chain `hash` of an account's migrated data by the next `chunk`
+pub fn migration_hash_chain(hash: &U256, chunk: &[u8]) -> U256 {
+    solana_program::hash::hashv(&[hash, chunk]).to_bytes()
+}
+
+/// Tracks the completeness of the export (or import) of program accounts for a cluster migration
+///
+/// # Note
+///
+/// - A manifest is either used for exporting (source program) or importing (target program).
+/// - The data of each account is migrated in sequential chunks, starting at the offset the entry has been registered with (e.g. to skip PDA headers).
+/// - An imported account is only completed if the hash-chain of the imported chunks matches the hash-chain of the export.
+#[elusiv_account]
+pub struct MigrationManifestAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Set with the first registered entry
+    pub is_import: ElusivOption<bool>,
+    pub entries_count: u32,
+
+    pub accounts: [Pubkey; MAX_MIGRATION_ENTRIES],
+    pub data_lens: [u32; MAX_MIGRATION_ENTRIES],
+
+    /// The offset of the next chunk of each entry
+    pub offsets: [u32; MAX_MIGRATION_ENTRIES],
+
+    /// See [`migration_hash_chain`]
+    pub hashes: [U256; MAX_MIGRATION_ENTRIES],
+
+    /// The hash-chain of the exported data (only used for imports)
+    pub expected_hashes: [U256; MAX_MIGRATION_ENTRIES],
+}
+
+impl<'a> MigrationManifestAccount<'a> {
+    /// Registers `account` as the entry `entry_index` (or verifies it against an existing entry)
+    ///
+    /// # Note
+    ///
+    /// Entries are registered in order, `offset` has to match the offset of the next chunk.
+    pub fn register_or_verify_entry(
+        &mut self,
+        is_import: bool,
+        entry_index: u32,
+        account: &Pubkey,
+        data_len: u32,
+        offset: u32,
+        expected_hash: &U256,
+    ) -> ProgramResult {
+        guard!(
+            self.get_is_import().option().unwrap_or(is_import) == is_import,
+            ElusivError::InvalidAccountState
+        );
+
+        let entries_count = self.get_entries_count();
+        let index = entry_index as usize;
+        guard!(
+            entry_index <= entries_count,
+            ElusivError::InvalidInstructionData
+        );
+
+        if entry_index == entries_count {
+            guard!(
+                index < MAX_MIGRATION_ENTRIES && offset <= data_len,
+                ElusivError::InvalidInstructionData
+            );
+
+            self.set_is_import(&ElusivOption::Some(is_import));
+            self.set_entries_count(&(entries_count + 1));
+            self.set_accounts(index, account);
+            self.set_data_lens(index, &data_len);
+            self.set_offsets(index, &offset);
+            self.set_expected_hashes(index, expected_hash);
+
+            return Ok(());
+        }
+
+        guard!(
+            self.get_accounts(index) == *account
+                && self.get_data_lens(index) == data_len
+                && self.get_expected_hashes(index) == *expected_hash,
+            ElusivError::InvalidAccount
+        );
+        guard!(
+            self.get_offsets(index) == offset,
+            ElusivError::InvalidInstructionData
+        );
+
+        Ok(())
+    }
+
+    /// Appends the next `chunk` to the (registered) entry `entry_index`
+    ///
+    /// # Note
+    ///
+    /// The last chunk of an import fails with [`ElusivError::InputsMismatch`] if the hash-chains do not match.
+    pub fn append_chunk(&mut self, entry_index: u32, chunk: &[u8]) -> ProgramResult {
+        let index = entry_index as usize;
+        let offset = self.get_offsets(index) as usize + chunk.len();
+        guard!(
+            offset <= self.get_data_lens(index) as usize,
+            ElusivError::InvalidInstructionData
+        );
+
+        let hash = migration_hash_chain(&self.get_hashes(index), chunk);
+        if offset == self.get_data_lens(index) as usize {
+            if let ElusivOption::Some(true) = self.get_is_import() {
+                guard!(
+                    hash == self.get_expected_hashes(index),
+                    ElusivError::InputsMismatch
+                );
+            }
+        }
+
+        self.set_hashes(index, &hash);
+        self.set_offsets(index, &(offset as u32));
+
+        Ok(())
+    }
+
+    pub fn is_entry_complete(&self, index: usize) -> bool {
+        self.get_offsets(index) == self.get_data_lens(index)
+    }
+
+    /// Returns true if all registered entries are complete
+    pub fn is_complete(&self) -> bool {
+        (0..self.get_entries_count() as usize).all(|i| self.is_entry_complete(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+
+    #[test]
+    fn test_migration_manifest() {
+        zero_program_account!(mut export, MigrationManifestAccount);
+        zero_program_account!(mut import, MigrationManifestAccount);
+        let account = Pubkey::new_unique();
+        let data: Vec<u8> = (0..10).collect();
+
+        // Entries are registered in order
+        assert_eq!(
+            export.register_or_verify_entry(false, 1, &account, 10, 2, &[0; 32]),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+
+        export
+            .register_or_verify_entry(false, 0, &account, 10, 2, &[0; 32])
+            .unwrap();
+        export.append_chunk(0, &data[2..6]).unwrap();
+        assert!(!export.is_complete());
+
+        // Chunks are sequential
+        assert_eq!(
+            export.register_or_verify_entry(false, 0, &account, 10, 2, &[0; 32]),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+        assert_eq!(
+            export.register_or_verify_entry(false, 0, &Pubkey::new_unique(), 10, 6, &[0; 32]),
+            Err(ElusivError::InvalidAccount.into())
+        );
+
+        export
+            .register_or_verify_entry(false, 0, &account, 10, 6, &[0; 32])
+            .unwrap();
+        assert_eq!(
+            export.append_chunk(0, &data[5..]),
+            Err(ElusivError::InvalidInstructionData.into())
+        );
+        export.append_chunk(0, &data[6..]).unwrap();
+        assert!(export.is_complete());
+
+        // An export manifest can not be used for imports
+        assert_eq!(
+            export.register_or_verify_entry(true, 1, &account, 10, 2, &[0; 32]),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        let expected_hash = export.get_hashes(0);
+        let target = Pubkey::new_unique();
+        import
+            .register_or_verify_entry(true, 0, &target, 10, 2, &expected_hash)
+            .unwrap();
+        import.append_chunk(0, &data[2..8]).unwrap();
+
+        // Mismatching data
+        assert_eq!(
+            import.append_chunk(0, &[0, 0]),
+            Err(ElusivError::InputsMismatch.into())
+        );
+
+        import.append_chunk(0, &data[8..]).unwrap();
+        assert!(import.is_complete());
+    }
+}
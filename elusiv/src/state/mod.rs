@@ -1,10 +1,22 @@
+pub mod circuit_registry;
 pub mod commitment;
+pub mod deployment;
 pub mod fee;
 pub mod governor;
+pub mod history;
 pub mod metadata;
+pub mod metrics;
+#[cfg(not(feature = "mainnet"))]
+pub mod migration;
 pub mod nullifier;
+pub mod pending_release;
 pub mod program_account;
 pub mod proof;
 pub mod queue;
+pub mod recipient;
+pub mod rollover;
 pub mod storage;
+pub mod viewing_key;
 pub mod vkey;
+pub mod warden_nonce;
+pub mod warden_usage;
@@ -22,10 +22,66 @@ const ACCOUNTS_COUNT: usize = div_ceiling_usize(NULLIFIERS_COUNT, NULLIFIERS_PER
 #[cfg(test)]
 const_assert_eq!(ACCOUNTS_COUNT, 16);
 
+/// Size of the bloom filter stored behind the [`NullifierMap`] of each child-account (`2^19` bits for [`NULLIFIERS_PER_ACCOUNT`] nullifier-hashes)
+///
+/// # Note
+///
+/// With three bits per nullifier-hash, a full child-account has a false-positive rate of about 3%.
+pub const NULLIFIER_BLOOM_FILTER_BYTES: usize = two_pow!(16);
+
+/// Number of bits set per nullifier-hash in the bloom filter
+const NULLIFIER_BLOOM_FILTER_HASHES: usize = 3;
+
+const NULLIFIER_BLOOM_FILTER_BITS: usize = NULLIFIER_BLOOM_FILTER_BYTES * 8;
+
+#[cfg(test)]
+const_assert_eq!(NULLIFIER_BLOOM_FILTER_BITS, NULLIFIERS_PER_ACCOUNT * 8);
+
+/// A [`NullifierMap`] followed by its bloom filter
+///
+/// # Note
+///
+/// Child-accounts registered before the bloom filter existed only contain the [`NullifierMap`] and are searched without a filter.
 pub struct NullifierChildAccount;
 
 impl ChildAccount for NullifierChildAccount {
-    const INNER_SIZE: usize = NullifierMap::SIZE;
+    const INNER_SIZE: usize = NullifierMap::SIZE + NULLIFIER_BLOOM_FILTER_BYTES;
+}
+
+/// Splits the inner data of a child-account into the [`NullifierMap`] and the (optional) bloom filter
+pub fn split_nullifier_child_data(data: &mut [u8]) -> (&mut [u8], Option<&mut [u8]>) {
+    let (map, bloom_filter) = data.split_at_mut(NullifierMap::SIZE);
+    let bloom_filter = if bloom_filter.len() >= NULLIFIER_BLOOM_FILTER_BYTES {
+        Some(&mut bloom_filter[..NULLIFIER_BLOOM_FILTER_BYTES])
+    } else {
+        None
+    };
+
+    (map, bloom_filter)
+}
+
+/// Returns `false` only if the nullifier-hash has definitely not been inserted into the filter
+fn bloom_filter_may_contain(bloom_filter: &[u8], nullifier_hash: &U256) -> bool {
+    bloom_filter_bits(nullifier_hash)
+        .iter()
+        .all(|&bit| bloom_filter[bit / 8] & (1 << (bit % 8)) != 0)
+}
+
+fn add_to_bloom_filter(bloom_filter: &mut [u8], nullifier_hash: &U256) {
+    for bit in bloom_filter_bits(nullifier_hash) {
+        bloom_filter[bit / 8] |= 1 << (bit % 8);
+    }
+}
+
+/// Nullifier-hashes are uniformly distributed field elements, so we use the lowest bytes of the first limbs as bit indices
+fn bloom_filter_bits(nullifier_hash: &U256) -> [usize; NULLIFIER_BLOOM_FILTER_HASHES] {
+    let mut bits = [0; NULLIFIER_BLOOM_FILTER_HASHES];
+    for (i, bit) in bits.iter_mut().enumerate() {
+        let limb = &nullifier_hash[i * 8..];
+        *bit = u32::from_le_bytes([limb[0], limb[1], limb[2], limb[3]]) as usize
+            % NULLIFIER_BLOOM_FILTER_BITS;
+    }
+    bits
 }
 
 /// Account storing [`NULLIFIERS_COUNT`] nullifiers over multiple accounts
@@ -33,7 +89,7 @@ impl ChildAccount for NullifierChildAccount {
 /// # Note
 ///
 /// We use [`NullifierMap`]s to store the nullifiers.
-#[elusiv_account(parent_account: { child_account_count: ACCOUNTS_COUNT, child_account: NullifierChildAccount }, eager_type: true)]
+#[elusiv_account(parent_account: { child_account_count: ACCOUNTS_COUNT, child_account: NullifierChildAccount })]
 pub struct NullifierAccount {
     #[no_getter]
     #[no_setter]
@@ -81,8 +137,18 @@ impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
         }
 
         let contains = self.execute_on_child_account_mut(account_index, |data| {
-            let mut map = NullifierMap::new(data);
-            map.contains(&nullifier_hash).is_some()
+            let (map_data, bloom_filter) = split_nullifier_child_data(data);
+
+            // The common case (a new nullifier-hash) does not require a map lookup
+            if let Some(bloom_filter) = bloom_filter {
+                if !bloom_filter_may_contain(bloom_filter, &nullifier_hash.0) {
+                    return false;
+                }
+            }
+
+            NullifierMap::new(map_data)
+                .contains(&nullifier_hash)
+                .is_some()
         })?;
 
         Ok(!contains)
@@ -118,7 +184,12 @@ impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
 
         // Insert the nullifier-hash into the correct map account
         let (insertion, max) = self.execute_on_child_account_mut(account_index, |data| {
-            let mut map = NullifierMap::new(data);
+            let (map_data, bloom_filter) = split_nullifier_child_data(data);
+            if let Some(bloom_filter) = bloom_filter {
+                add_to_bloom_filter(bloom_filter, &nullifier_hash.0);
+            }
+
+            let mut map = NullifierMap::new(map_data);
             let res = map
                 .try_insert_default(nullifier_hash)
                 .map_err(|_| ElusivError::CouldNotInsertNullifier);
@@ -162,9 +233,14 @@ impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
 
         // Insert all values (as mins), large to small into the map
         let (max_values, max) = self.execute_on_child_account_mut(target as usize, |data| {
-            let mut map = NullifierMap::new(data);
+            let (map_data, mut bloom_filter) = split_nullifier_child_data(data);
+            let mut map = NullifierMap::new(map_data);
             let mut max_values = Vec::new();
             for (v, _) in values {
+                if let Some(bloom_filter) = bloom_filter.as_deref_mut() {
+                    add_to_bloom_filter(bloom_filter, &v.0);
+                }
+
                 let res = map
                     .try_insert_default(v)
                     .map_err(|_| ElusivError::CouldNotInsertNullifier)?;
@@ -214,6 +290,35 @@ impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
         }
     }
 
+    /// Removes all nullifier-hashes (used to reset devnet deployments)
+    ///
+    /// # Note
+    ///
+    /// All enabled child-accounts need to be supplied.
+    #[cfg(not(feature = "mainnet"))]
+    pub fn reset(&mut self) -> ProgramResult {
+        for i in 0..ACCOUNTS_COUNT {
+            if self.get_child_pubkey(i).is_some() {
+                self.execute_on_child_account_mut(i, |data| {
+                    let (map_data, bloom_filter) = split_nullifier_child_data(data);
+                    if let Some(bloom_filter) = bloom_filter {
+                        bloom_filter.fill(0);
+                    }
+
+                    NullifierMap::new(map_data).reset()
+                })?;
+            }
+
+            self.set_max_values(i, &ElusivOption::None);
+        }
+
+        self.set_root(&[0; 32]);
+        self.set_nullifier_hash_count(&0);
+        self.set_all_moved_values(&[]);
+
+        Ok(())
+    }
+
     pub fn is_moved_nullifier_empty(&self) -> bool {
         self.get_moved_values_count() == 0
     }
@@ -263,7 +368,8 @@ mod tests {
     use super::*;
     use crate::{
         fields::{u256_from_str, u64_to_u256, u64_to_u256_skip_mr},
-        macros::parent_account,
+        macros::{account_info, parent_account},
+        state::program_account::SizedAccount,
     };
 
     #[test]
@@ -303,6 +409,59 @@ mod tests {
         assert!(!nullifier_account.can_insert_nullifier_hash(b).unwrap());
     }
 
+    #[test]
+    fn test_bloom_filter() {
+        parent_account!(mut nullifier_account, NullifierAccount);
+
+        let a = u64_to_u256_skip_mr(1);
+        let b = u64_to_u256_skip_mr(2);
+        fn may_contain(nullifier_account: &NullifierAccount, nullifier_hash: &U256) -> bool {
+            nullifier_account
+                .execute_on_child_account_mut(0, |data| {
+                    let (_, bloom_filter) = split_nullifier_child_data(data);
+                    bloom_filter_may_contain(bloom_filter.unwrap(), nullifier_hash)
+                })
+                .unwrap()
+        }
+        assert!(!may_contain(&nullifier_account, &a));
+
+        nullifier_account.try_insert_nullifier_hash(a).unwrap();
+        assert!(may_contain(&nullifier_account, &a));
+        assert!(!may_contain(&nullifier_account, &b));
+        assert!(nullifier_account.can_insert_nullifier_hash(b).unwrap());
+
+        nullifier_account.try_insert_nullifier_hash(b).unwrap();
+        assert!(may_contain(&nullifier_account, &b));
+        assert!(!nullifier_account.can_insert_nullifier_hash(b).unwrap());
+    }
+
+    #[test]
+    fn test_bloom_filter_legacy_child_account() {
+        // Child-accounts registered before the bloom filter existed
+        let mut data = vec![0; NullifierAccount::SIZE];
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; NullifierChildAccount::SIZE - NULLIFIER_BLOOM_FILTER_BYTES]
+        );
+        let mut nullifier_account = NullifierAccount::new_with_child_accounts(
+            &mut data,
+            vec![Some(&child_account); ACCOUNTS_COUNT],
+        )
+        .unwrap();
+
+        let a = u64_to_u256_skip_mr(1);
+        assert!(nullifier_account.can_insert_nullifier_hash(a).unwrap());
+        nullifier_account.try_insert_nullifier_hash(a).unwrap();
+        assert!(!nullifier_account.can_insert_nullifier_hash(a).unwrap());
+
+        nullifier_account
+            .execute_on_child_account_mut(0, |data| {
+                assert!(split_nullifier_child_data(data).1.is_none());
+            })
+            .unwrap();
+    }
+
     #[test]
     fn test_try_insert_nullifier_hash() {
         parent_account!(mut nullifier_account, NullifierAccount);
@@ -0,0 +1,32 @@
+use super::program_account::PDAAccountData;
+use crate::macros::elusiv_account;
+use solana_program::pubkey::Pubkey;
+
+/// Lamports of a timelocked send, parked between the finalization and the `unlock_timestamp` (PDA associated with the send's nullifier duplicate account)
+///
+/// # Note
+///
+/// - Opened by [`crate::processor::finalize_verification_transfer_lamports`] with the rent paid by the original fee payer.
+/// - Released (and closed) by anyone after the `unlock_timestamp` (see [`crate::processor::release_pending_transfer`]).
+#[elusiv_account]
+pub struct PendingReleaseAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub recipient: Pubkey,
+
+    /// The account that paid the rent (and receives it once the account is closed)
+    pub rent_payer: Pubkey,
+
+    pub amount: u64,
+
+    /// Unix-timestamp from which on the `amount` can be released to the `recipient`
+    pub unlock_timestamp: u64,
+}
+
+impl<'a> PendingReleaseAccount<'a> {
+    pub fn is_unlocked(&self, timestamp: u64) -> bool {
+        timestamp >= self.get_unlock_timestamp()
+    }
+}
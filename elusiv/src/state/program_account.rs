@@ -3,10 +3,13 @@ pub use elusiv_types::accounts::*;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::macros::{account_info, parent_account};
+    use crate::macros::{account_info, elusiv_account, parent_account};
     use borsh::BorshDeserialize;
     use elusiv_types::{split_child_account_data, BorshSerDeSized, ElusivOption};
-    use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+    use solana_program::{
+        account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+        pubkey::Pubkey,
+    };
 
     struct TestPDAAccount;
 
@@ -31,6 +34,97 @@ mod tests {
         //assert_eq!(TestPDAAccount::find(None).0, Pubkey::find_program_address(&[TestPDAAccount::SEED], &crate::PROGRAM_ID).0);
     }
 
+    #[test]
+    fn test_pubkey_cached() {
+        let pubkey = Pubkey::new_unique();
+        let (pda, bump) = TestPDAAccount::find_with_pubkey(pubkey, Some(1));
+
+        // Not rented accounts
+        account_info!(empty, pda, vec![]);
+        assert_eq!(
+            TestPDAAccount::pubkey_cached(&empty, Some(pubkey), Some(1)),
+            Ok(pda)
+        );
+
+        // Stored bump
+        account_info!(account, pda, vec![bump, 0]);
+        assert_eq!(
+            TestPDAAccount::pubkey_cached(&account, Some(pubkey), Some(1)),
+            Ok(pda)
+        );
+        assert_ne!(
+            TestPDAAccount::pubkey_cached(&account, Some(pubkey), Some(2)),
+            Ok(pda)
+        );
+        assert!(TestPDAAccount::verify_account_with_pubkey(&account, pubkey, Some(1)).is_ok());
+
+        let (pda, bump) = TestPDAAccount::find(Some(3));
+        account_info!(account, pda, vec![bump, 0]);
+        assert_eq!(
+            TestPDAAccount::pubkey_cached(&account, None, Some(3)),
+            Ok(pda)
+        );
+        assert!(TestPDAAccount::verify_account(&account, Some(3)).is_ok());
+        assert!(TestPDAAccount::verify_account(&account, Some(4)).is_err());
+    }
+
+    #[elusiv_account(version: 1)]
+    struct TestVersionedAccount {
+        #[no_getter]
+        #[no_setter]
+        pda_data: PDAAccountData,
+
+        a: u32,
+        b: u64,
+    }
+
+    impl<'a> VersionedAccount<'a> for TestVersionedAccount<'a> {
+        fn migrate(&mut self, previous_version: u8, previous_bytes: &[u8]) -> ProgramResult {
+            match previous_version {
+                // Version 0: `a: u32, b: u32`
+                0 => {
+                    self.set_a(&u32::try_from_slice(&previous_bytes[2..6])?);
+                    self.set_b(&(u32::try_from_slice(&previous_bytes[6..10])? as u64));
+                    Ok(())
+                }
+                _ => Err(ProgramError::InvalidAccountData),
+            }
+        }
+    }
+
+    #[test]
+    fn test_versioned_account() {
+        assert_eq!(TestVersionedAccount::VERSION, 1);
+
+        // Previous layout is rejected
+        let mut data = vec![0; TestVersionedAccount::SIZE];
+        assert!(TestVersionedAccount::new(&mut data).is_err());
+
+        let mut previous_bytes = vec![123, 0];
+        previous_bytes.extend(1u32.to_le_bytes());
+        previous_bytes.extend(2u32.to_le_bytes());
+
+        let account = TestVersionedAccount::migrate_from(&mut data, &previous_bytes).unwrap();
+        assert_eq!(account.get_a(), 1);
+        assert_eq!(account.get_b(), 2);
+
+        let pda_data = PDAAccountData::new(&data).unwrap();
+        assert_eq!(pda_data.bump_seed, 123);
+        assert_eq!(pda_data.version, 1);
+        assert!(TestVersionedAccount::new(&mut data).is_ok());
+
+        // Current layout cannot be migrated
+        let previous_bytes = data.clone();
+        assert_eq!(
+            TestVersionedAccount::migrate_from(&mut data, &previous_bytes).err(),
+            Some(ProgramError::InvalidAccountData)
+        );
+
+        // Invalid size
+        let mut data = vec![0; TestVersionedAccount::SIZE + 1];
+        assert!(TestVersionedAccount::migrate_from(&mut data, &[0, 0]).is_err());
+    }
+
     struct TestChildAccount;
 
     impl ChildAccount for TestChildAccount {
@@ -244,6 +338,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_child_accounts_consumption() {
+        parent_account!(mut parent, TestParentAccount);
+        parent.set_child_pubkey(1, ElusivOption::Some(*parent.accounts[1].unwrap().key));
+
+        account_info!(skipped_account, Pubkey::new_unique(), vec![1, 0]);
+        account_info!(next_account, Pubkey::new_unique(), vec![1, 0]);
+        let accounts = [&skipped_account, parent.accounts[1].unwrap(), &next_account];
+
+        // The skipped account and the matched account are consumed
+        let account_info_iter = &mut accounts.iter().copied();
+        let matched_accounts =
+            TestParentAccount::find_child_accounts(&parent, &crate::ID, false, account_info_iter)
+                .unwrap();
+        assert_eq!(
+            matched_accounts[1].unwrap().key,
+            parent.accounts[1].unwrap().key
+        );
+        assert_eq!(account_info_iter.next().unwrap().key, next_account.key);
+        assert!(account_info_iter.next().is_none());
+
+        // Nothing is consumed without a match
+        let accounts = [&skipped_account, &next_account];
+        let account_info_iter = &mut accounts.iter().copied();
+        let matched_accounts =
+            TestParentAccount::find_child_accounts(&parent, &crate::ID, false, account_info_iter)
+                .unwrap();
+        assert!(matched_accounts.iter().all(|a| a.is_none()));
+        assert_eq!(account_info_iter.next().unwrap().key, skipped_account.key);
+    }
+
     #[test]
     fn test_unverified_account_info() {
         account_info!(account, Pubkey::new_unique());
@@ -1,7 +1,9 @@
 use crate::bytes::{
     usize_as_u32_safe, BorshSerDeSized, BorshSerDeSizedEnum, ElusivOption, SizedType,
 };
+use crate::error::ElusivError;
 use crate::fields::{G2HomProjective, Wrap, G1A, G2A};
+use crate::macros::guard;
 use crate::processor::{ProofRequest, MAX_MT_COUNT};
 use crate::proof::verifier::VerificationStep;
 use crate::state::program_account::PDAAccountData;
@@ -45,7 +47,10 @@ pub enum VerificationState {
 /// # Note
 ///
 /// Exists only temporarily for verifying a single proof and is closed afterwards.
-#[elusiv_account(partial_computation: true, eager_type: true)]
+/// The computation values that don't fit into the lazy fields are stored in a pooled [`VerifierRAMAccount`] (referenced by `ram_account`).
+/// If the account is flagged as `reusable` in [`VerificationAccountData`], it stays open after a successful finalization
+/// and is recycled by the next request of the same fee payer (see [`VerificationAccount::reset_for_reuse`]).
+#[elusiv_account(partial_computation: true)]
 pub struct VerificationAccount {
     #[no_getter]
     #[no_setter]
@@ -83,6 +88,62 @@ pub struct VerificationAccount {
     pub(crate) alt_b: Lazy<'a, G2A>,
     pub(crate) coeff_index: u8,
 
+    // If true, the proof request can be finalized
+    pub is_verified: ElusivOption<bool>,
+
+    pub other_data: VerificationAccountData,
+    #[no_getter]
+    pub request: ProofRequest,
+    pub tree_indices: [u32; MAX_MT_COUNT],
+
+    /// Unix-timestamp at which the verification result has been computed
+    pub verification_timestamp: u64,
+
+    /// The [`VerifierRAMAccount`] used for the computation (bound by the first computation instruction)
+    pub ram_account: ElusivOption<Pubkey>,
+
+    /// The identifier account of the request (bound at initialization, used for the self-finalization by the user)
+    pub identifier: ElusivOption<Pubkey>,
+
+    /// The exported checkpoint of the computation (the computation is paused until the checkpoint is imported)
+    pub checkpoint: ElusivOption<VerificationCheckpoint>,
+
+    /// The slot in which the verification has been initialized (used for the cancellation timeout)
+    pub init_slot: u64,
+}
+
+/// Maximum number of [`VerificationAccount`]s in a [`BatchVerificationAccount`]
+pub const MAX_BATCH_VERIFICATION_SIZE: usize = 4;
+
+/// Groups multiple [`VerificationAccount`]s (of the same fee payer and vkey) for a combined proof verification
+///
+/// # Note
+///
+/// Exists only temporarily between the batch initialization and computation.
+#[elusiv_account]
+pub struct BatchVerificationAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub vkey_id: u32,
+    pub count: u8,
+    pub verification_account_indices: [u8; MAX_BATCH_VERIFICATION_SIZE],
+}
+
+/// RAM of the proof verifier
+///
+/// # Note
+///
+/// RAM accounts are pooled: a [`VerificationAccount`] locks a RAM account for the duration of its computation and releases it afterwards.
+#[elusiv_account]
+pub struct VerifierRAMAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub is_in_use: bool,
+
     // RAMs for storing computation values
     #[lazy]
     pub(crate) ram_fq: RAMFq<'a>,
@@ -92,14 +153,36 @@ pub struct VerificationAccount {
     pub(crate) ram_fq6: RAMFq6<'a>,
     #[lazy]
     pub(crate) ram_fq12: RAMFq12<'a>,
+}
 
-    // If true, the proof request can be finalized
-    pub is_verified: ElusivOption<bool>,
+impl<'a> VerifierRAMAccount<'a> {
+    pub fn serialize_rams(&mut self) -> Result<(), std::io::Error> {
+        self.ram_fq.serialize()?;
+        self.ram_fq2.serialize()?;
+        self.ram_fq6.serialize()?;
+        self.ram_fq12.serialize()?;
 
-    pub other_data: VerificationAccountData,
-    #[no_getter]
-    pub request: ProofRequest,
-    pub tree_indices: [u32; MAX_MT_COUNT],
+        Ok(())
+    }
+
+    /// Overwrites the RAMs with the serialized RAMs of `other`
+    pub fn copy_rams_from(&mut self, other: &VerifierRAMAccount) {
+        self.ram_fq.copy_from(&other.ram_fq);
+        self.ram_fq2.copy_from(&other.ram_fq2);
+        self.ram_fq6.copy_from(&other.ram_fq6);
+        self.ram_fq12.copy_from(&other.ram_fq12);
+    }
+}
+
+/// A checkpoint of a partial verification computation, used to hand-off the computation to another [`VerifierRAMAccount`]
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone)]
+#[cfg_attr(feature = "elusiv-client", derive(Debug))]
+pub struct VerificationCheckpoint {
+    /// See [`VerificationAccount::checkpoint_hash`]
+    pub hash: U256,
+
+    /// The warden that exported the checkpoint
+    pub exporter: Pubkey,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Default)]
@@ -133,6 +216,18 @@ pub struct VerificationAccountData {
 
     /// The expected associated-token-account-rent in `token_id`-Token
     pub associated_token_account_rent: u64,
+
+    /// Flag that keeps the account open after a successful finalization (the fee payer can reuse it for the next request)
+    pub reusable: bool,
+
+    /// The fee exceeding the required fees in `token_id`-Token (bid for an earlier commitment insertion)
+    pub priority_fee: u64,
+
+    /// The `priority_fee` in `Lamports` (orders the commitment queue)
+    pub priority: u64,
+
+    /// The [`crate::state::governor::GovernorAccount`]'s `reset_generation` at the initialization
+    pub reset_generation: u32,
 }
 
 impl<'a> VerificationAccount<'a> {
@@ -146,6 +241,7 @@ impl<'a> VerificationAccount<'a> {
         vkey_id: u32,
         request: ProofRequest,
         tree_indices: [u32; MAX_MT_COUNT],
+        reusable: bool,
     ) -> ProgramResult {
         self.set_vkey_id(&vkey_id);
         self.set_request(&request);
@@ -165,12 +261,68 @@ impl<'a> VerificationAccount<'a> {
         self.set_other_data(&VerificationAccountData {
             fee_payer: signer,
             skip_nullifier_pda,
+            reusable,
             ..Default::default()
         });
 
         Ok(())
     }
 
+    /// Resets a closed (but still rented) account, so that it can be setup for a new request
+    ///
+    /// # Note
+    ///
+    /// All computation values (lazy fields and RAMs) are written before being read during a verification, so they don't require zeroing.
+    pub fn reset_for_reuse(&mut self) -> ProgramResult {
+        guard!(
+            self.get_state() == VerificationState::Closed,
+            ElusivError::InvalidAccountState
+        );
+        guard!(
+            self.get_other_data().reusable,
+            ElusivError::InvalidAccountState
+        );
+
+        self.restart_computation();
+        self.set_state(&VerificationState::None);
+        self.set_is_verified(&ElusivOption::None);
+        self.set_other_data(&VerificationAccountData::default());
+        self.set_verification_timestamp(&0);
+        self.set_identifier(&ElusivOption::None);
+        self.set_init_slot(&0);
+
+        Ok(())
+    }
+
+    /// Resets the position of the partial computation and unbinds its [`VerifierRAMAccount`] (and checkpoint)
+    ///
+    /// # Note
+    ///
+    /// The next computation instruction restarts the verification with a freshly bound RAM account.
+    pub fn restart_computation(&mut self) {
+        self.set_instruction(&0);
+        self.set_round(&0);
+        self.set_step(&VerificationStep::PublicInputPreparation);
+        self.set_coeff_index(&0);
+        self.set_ram_account(&ElusivOption::None);
+        self.set_checkpoint(&ElusivOption::None);
+    }
+
+    /// Returns the hash committing to the computation state (the position of the computation and the serialized RAMs of `ram_account`)
+    pub fn checkpoint_hash(&self, ram_account: &VerifierRAMAccount) -> U256 {
+        solana_program::hash::hashv(&[
+            &self.instruction[..],
+            &self.round[..],
+            &self.step[..],
+            &self.coeff_index[..],
+            ram_account.ram_fq.source(),
+            ram_account.ram_fq2.source(),
+            ram_account.ram_fq6.source(),
+            ram_account.ram_fq12.source(),
+        ])
+        .to_bytes()
+    }
+
     pub fn setup_public_inputs_instructions(
         &mut self,
         instructions: &Vec<u32>,
@@ -193,15 +345,6 @@ impl<'a> VerificationAccount<'a> {
         self.public_input[offset..offset + 32].try_into().unwrap()
     }
 
-    pub fn serialize_rams(&mut self) -> Result<(), std::io::Error> {
-        self.ram_fq.serialize()?;
-        self.ram_fq2.serialize()?;
-        self.ram_fq6.serialize()?;
-        self.ram_fq12.serialize()?;
-
-        Ok(())
-    }
-
     pub fn all_tree_indices(&self) -> [u32; MAX_MT_COUNT] {
         let mut m = [0; MAX_MT_COUNT];
         for (i, m) in m.iter_mut().enumerate() {
@@ -296,6 +439,18 @@ where
         }
     }
 
+    /// The serialized values (changes are only visible after [`Self::serialize`])
+    pub fn source(&self) -> &[u8] {
+        &self.source[..]
+    }
+
+    /// Overwrites all values with the serialized values of `other`
+    pub fn copy_from(&mut self, other: &Self) {
+        self.source.copy_from_slice(other.source());
+        self.data.clear();
+        self.changes.clear();
+    }
+
     pub fn serialize(&mut self) -> Result<(), std::io::Error> {
         for (i, &change) in self.changes.iter().enumerate() {
             if change {
@@ -318,8 +473,14 @@ pub struct NullifierDuplicateAccount {
 }
 
 impl<'a> NullifierDuplicateAccount<'a> {
+    /// Returns the pubkey associated with a set of nullifier-hashes
+    ///
+    /// # Note
+    ///
+    /// The nullifier-hashes are sorted, so the pubkey is independent of their order.
     pub fn associated_pubkey(nullifier_hashes: &[&RawU256]) -> Pubkey {
-        let hashes: Vec<U256> = nullifier_hashes.iter().map(|n| n.skip_mr()).collect();
+        let mut hashes: Vec<U256> = nullifier_hashes.iter().map(|n| n.skip_mr()).collect();
+        hashes.sort_unstable();
         let hash = solana_program::hash::hashv(&hashes.iter().map(|b| &b[..]).collect::<Vec<_>>());
         Pubkey::new_from_array(hash.to_bytes())
     }
@@ -335,7 +496,7 @@ mod tests {
             InputCommitment, JoinSplitPublicInputs, OptionalFee, PublicInputs, SendPublicInputs,
         },
     };
-    use elusiv_types::SizedAccount;
+    use elusiv_types::{EagerAccount, SizedAccount};
 
     #[test]
     fn test_setup_verification_account() {
@@ -360,6 +521,10 @@ mod tests {
             hashed_inputs: u256_from_str_skip_mr("7777777"),
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
+            expiry: 0,
+            unlock_timestamp: 0,
+            earliest_execution_slot: 0,
+            latest_execution_slot: 0,
         };
         let request = ProofRequest::Send(public_inputs.clone());
         let data = VerificationAccountData {
@@ -381,6 +546,7 @@ mod tests {
                 vkey_id,
                 request,
                 [123, 456],
+                false,
             )
             .unwrap();
 
@@ -409,6 +575,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_reset_for_reuse() {
+        let mut data = vec![0; VerificationAccount::SIZE];
+        let mut verification_account = VerificationAccount::new(&mut data).unwrap();
+
+        // Only closed accounts can be reused
+        verification_account.set_other_data(&VerificationAccountData {
+            reusable: true,
+            ..Default::default()
+        });
+        assert!(verification_account.reset_for_reuse().is_err());
+
+        verification_account.set_state(&VerificationState::Closed);
+        verification_account.set_instruction(&10);
+        verification_account.set_round(&20);
+        verification_account.set_step(&VerificationStep::FinalExponentiation);
+        verification_account.set_coeff_index(&3);
+        verification_account.set_is_verified(&ElusivOption::Some(true));
+        verification_account.set_ram_account(&ElusivOption::Some(Pubkey::new_unique()));
+        verification_account.set_identifier(&ElusivOption::Some(Pubkey::new_unique()));
+
+        // Only reusable accounts can be reused
+        verification_account.set_other_data(&VerificationAccountData::default());
+        assert!(verification_account.reset_for_reuse().is_err());
+
+        verification_account.set_other_data(&VerificationAccountData {
+            fee_payer: RawU256::new([1; 32]),
+            token_id: 2,
+            reusable: true,
+            ..Default::default()
+        });
+        verification_account.reset_for_reuse().unwrap();
+
+        assert_eq!(verification_account.get_state(), VerificationState::None);
+        assert_eq!(verification_account.get_instruction(), 0);
+        assert_eq!(verification_account.get_round(), 0);
+        assert_eq!(
+            verification_account.get_step(),
+            VerificationStep::PublicInputPreparation
+        );
+        assert_eq!(verification_account.get_coeff_index(), 0);
+        assert_eq!(verification_account.get_is_verified().option(), None);
+        assert_eq!(verification_account.get_ram_account().option(), None);
+        assert_eq!(verification_account.get_identifier().option(), None);
+        assert_eq!(
+            verification_account.get_other_data(),
+            VerificationAccountData::default()
+        );
+    }
+
     impl BorshDeserialize for Wrap<u64> {
         fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
             Ok(Wrap(u64::deserialize(buf)?))
@@ -423,6 +639,31 @@ mod tests {
         const SIZE: usize = u64::SIZE;
     }
 
+    #[test]
+    fn test_verification_account_eager() {
+        let ram_account = Pubkey::new_unique();
+        let mut data = vec![0; VerificationAccount::SIZE];
+        {
+            let mut verification_account = VerificationAccount::new(&mut data).unwrap();
+            verification_account.set_round(&7);
+            verification_account.set_tree_indices(1, &3);
+            verification_account.set_ram_account(&ElusivOption::Some(ram_account));
+        }
+
+        let eager = VerificationAccountEager::try_from_slice(&data).unwrap();
+        assert_eq!(eager.round, 7);
+        assert_eq!(eager.tree_indices[1], 3);
+        assert_eq!(eager.ram_account.option(), Some(ram_account));
+
+        let eager = VerificationAccount::new_eager(data.clone()).unwrap();
+        assert_eq!(eager.round, 7);
+
+        // Invalid data len
+        data.push(0);
+        assert!(VerificationAccountEager::try_from_slice(&data).is_err());
+        assert!(VerificationAccount::new_eager(data).is_err());
+    }
+
     #[test]
     fn test_lazy_ram() {
         let mut data = vec![0; u64::SIZE * 2];
@@ -446,8 +687,8 @@ mod tests {
 
     #[test]
     fn test_check_vector_size() {
-        let mut data = vec![0; VerificationAccount::SIZE];
-        let account = VerificationAccount::new(&mut data).unwrap();
+        let mut data = vec![0; VerifierRAMAccount::SIZE];
+        let account = VerifierRAMAccount::new(&mut data).unwrap();
         let mut ram = account.ram_fq12;
 
         assert_eq!(ram.data.len(), 0);
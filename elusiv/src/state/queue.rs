@@ -7,10 +7,16 @@ use elusiv_types::ProgramAccount;
 use solana_program::program_error::ProgramError;
 
 /// Generates a [`QueueAccount`] and a [`Queue`] that implements the [`RingQueue`] trait
+///
+/// # Notes
+///
+/// - Additional fields (`$field_vis $field: $ty_field`) are appended to the queue account.
+/// - The queue account is resizable: growing the account by whole slots (see `SLOT_SIZE`) appends slots to the queue (see [`RingQueue::grow`]).
+/// - `slot_data_size` reserves bytes of per-slot data (following the element) in each appended slot.
 macro_rules! queue_account {
-    ($id: ident, $id_account: ident, $size: expr, $ty_element: ty $(,)?) => {
+    ($id: ident, $id_account: ident, $size: expr, $ty_element: ty $(; slot_data_size: $slot_data_size: expr)? $(, $field_vis: vis $field: ident: $ty_field: ty)* $(,)?) => {
         #[allow(dead_code)] // required for the pda_data field
-        #[crate::macros::elusiv_account]
+        #[crate::macros::elusiv_account(resizable: true)]
         pub struct $id_account {
             #[no_getter]
             #[no_setter]
@@ -19,6 +25,7 @@ macro_rules! queue_account {
             head: u32,
             tail: u32,
             raw_data: [$ty_element; $size],
+            $($field_vis $field: $ty_field,)*
         }
 
         #[cfg(test)]
@@ -27,11 +34,34 @@ macro_rules! queue_account {
             <elusiv_types::accounts::PDAAccountData as elusiv_types::bytes::BorshSerDeSized>::SIZE
                 + (4 + 4)
                 + <$ty_element as elusiv_types::bytes::BorshSerDeSized>::SIZE * ($size)
+                $(+ <$ty_field as elusiv_types::bytes::BorshSerDeSized>::SIZE)*
         );
 
         #[cfg(test)]
         const_assert_eq!(<$id as crate::state::queue::RingQueue>::SIZE, $size as u32);
 
+        impl<'a> $id_account<'a> {
+            /// The size of a slot appended by growing the account
+            pub const SLOT_SIZE: usize =
+                <$ty_element as elusiv_types::bytes::BorshSerDeSized>::SIZE $(+ $slot_data_size)?;
+
+            /// The number of slots (including the slots appended by growing the account)
+            pub fn queue_size(&self) -> u32 {
+                ($size + self.extension().len() / Self::SLOT_SIZE) as u32
+            }
+
+            /// The bytes of an appended slot (`index >= $size`)
+            pub fn appended_slot(&self, index: usize) -> &[u8] {
+                let offset = (index - $size) * Self::SLOT_SIZE;
+                &self.extension()[offset..offset + Self::SLOT_SIZE]
+            }
+
+            pub fn appended_slot_mut(&mut self, index: usize) -> &mut [u8] {
+                let offset = (index - $size) * Self::SLOT_SIZE;
+                &mut self.extension_mut()[offset..offset + Self::SLOT_SIZE]
+            }
+        }
+
         pub struct $id<'a, 'b> {
             account: &'b mut $id_account<'a>,
         }
@@ -47,6 +77,10 @@ macro_rules! queue_account {
             type N = $ty_element;
             const CAPACITY: u32 = ($size as u32) - 1;
 
+            fn size(&self) -> u32 {
+                self.account.queue_size()
+            }
+
             fn get_head(&self) -> u32 {
                 self.account.get_head()
             }
@@ -64,11 +98,25 @@ macro_rules! queue_account {
             }
 
             fn get_data(&self, index: usize) -> Self::N {
-                self.account.get_raw_data(index)
+                if index < $size {
+                    return self.account.get_raw_data(index);
+                }
+
+                let slot = self.account.appended_slot(index);
+                <$ty_element as borsh::BorshDeserialize>::try_from_slice(
+                    &slot[..<$ty_element as elusiv_types::bytes::BorshSerDeSized>::SIZE],
+                )
+                .unwrap()
             }
 
             fn set_data(&mut self, index: usize, value: &Self::N) {
-                self.account.set_raw_data(index, value)
+                if index < $size {
+                    return self.account.set_raw_data(index, value);
+                }
+
+                let mut slot = &mut self.account.appended_slot_mut(index)
+                    [..<$ty_element as elusiv_types::bytes::BorshSerDeSized>::SIZE];
+                borsh::BorshSerialize::serialize(value, &mut slot).unwrap();
             }
         }
 
@@ -89,20 +137,30 @@ pub trait Queue<'a, 'b, Account: ProgramAccount<'a>> {
     fn new(account: &'b mut Account) -> Self::T;
 }
 
-/// Ring-queue with a capacity of [`RingQueue::CAPACITY`] elements
+/// Ring-queue with a capacity of [`RingQueue::capacity`] elements
 ///
 /// # Notes
 ///
 /// - works by having two pointers, `head` and `tail` and a some data storage with getter, setter
 /// - `head` points to the first element (first according to the FIFO definition)
 /// - `tail` points to the location to insert the next element
-/// - `head == (tail - 1) mod SIZE` => queue is full
+/// - `head == (tail - 1) mod size` => queue is full
 /// - `head == tail` => queue is empty
+/// - [`RingQueue::CAPACITY`] is the base capacity, a grown queue has a larger [`RingQueue::capacity`]
 pub trait RingQueue {
     type N: PartialEq + BorshSerDeSized + Clone;
     const CAPACITY: u32;
     const SIZE: u32 = Self::CAPACITY + 1;
 
+    /// The number of slots (one more than the capacity)
+    fn size(&self) -> u32 {
+        Self::SIZE
+    }
+
+    fn capacity(&self) -> u32 {
+        self.size() - 1
+    }
+
     fn get_head(&self) -> u32;
     fn set_head(&mut self, value: &u32);
 
@@ -114,10 +172,11 @@ pub trait RingQueue {
 
     /// Try to enqueue a new element in the queue
     fn enqueue(&mut self, value: Self::N) -> Result<(), ProgramError> {
+        let size = self.size();
         let head = self.get_head();
         let tail = self.get_tail();
 
-        let next_tail = (tail + 1) % Self::SIZE;
+        let next_tail = (tail + 1) % size;
         guard!(next_tail != head, QueueIsFull);
 
         self.set_data(tail as usize, &value);
@@ -132,34 +191,77 @@ pub trait RingQueue {
     }
 
     fn view(&self, offset: usize) -> Result<Self::N, ProgramError> {
+        let size = self.size();
         let head = self.get_head();
         let tail = self.get_tail();
         guard!(head != tail, QueueIsEmpty);
         guard!(usize_as_u32_safe(offset) < self.len(), InvalidQueueAccess);
 
-        Ok(self.get_data((head as usize + offset) % Self::SIZE as usize))
+        Ok(self.get_data((head as usize + offset) % size as usize))
     }
 
     /// Try to remove the first element from the queue
     fn dequeue_first(&mut self) -> Result<Self::N, ProgramError> {
+        let size = self.size();
         let head = self.get_head();
         let tail = self.get_tail();
         guard!(head != tail, QueueIsEmpty);
 
         let value = self.get_data(head as usize);
-        self.set_head(&((head + 1) % Self::SIZE));
+        self.set_head(&((head + 1) % size));
 
         Ok(value)
     }
 
     fn remove(&mut self, count: u32) -> Result<(), ProgramError> {
+        let size = self.size();
         let head = self.get_head();
         guard!(self.len() >= count, InvalidQueueAccess);
-        self.set_head(&((head + count) % Self::SIZE));
+        self.set_head(&((head + count) % size));
+        Ok(())
+    }
+
+    /// Re-adds the last `count` removed elements to the front of the queue
+    ///
+    /// # Note
+    ///
+    /// The removed elements are only intact, if no other elements have been removed since and if the caller verifies them.
+    fn restore(&mut self, count: u32) -> Result<(), ProgramError> {
+        let size = self.size();
+        guard!(
+            count <= self.capacity() && self.len() + count <= self.capacity(),
+            QueueIsFull
+        );
+
+        let head = self.get_head();
+        self.set_head(&((head + size - count) % size));
+        Ok(())
+    }
+
+    /// Moves the last element `count` positions towards the front of the queue
+    ///
+    /// # Note
+    ///
+    /// The skipped elements are moved back by one position each.
+    fn move_last_ahead(&mut self, count: u32) -> Result<(), ProgramError> {
+        let size = self.size();
+        guard!(count < self.len(), InvalidQueueAccess);
+
+        let mut ptr = (self.get_tail() + size - 1) % size;
+        let value = self.get_data(ptr as usize);
+
+        for _ in 0..count {
+            let previous = (ptr + size - 1) % size;
+            self.set_data(ptr as usize, &self.get_data(previous as usize));
+            ptr = previous;
+        }
+        self.set_data(ptr as usize, &value);
+
         Ok(())
     }
 
     fn contains(&self, value: &Self::N) -> bool {
+        let size = self.size();
         let mut ptr = self.get_head();
         let tail = self.get_tail();
 
@@ -167,20 +269,21 @@ pub trait RingQueue {
             if self.get_data(ptr as usize) == *value {
                 return true;
             }
-            ptr = (ptr + 1) % Self::SIZE;
+            ptr = (ptr + 1) % size;
         }
 
         false
     }
 
     fn len(&self) -> u32 {
+        let size = self.size();
         let head = self.get_head();
         let tail = self.get_tail();
 
         if tail >= head {
             tail - head
         } else {
-            Self::SIZE - head + tail
+            size - head + tail
         }
     }
 
@@ -188,15 +291,49 @@ pub trait RingQueue {
         self.len() == 0
     }
 
+    fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
     fn empty_slots(&self) -> u32 {
-        Self::CAPACITY - self.len()
+        self.capacity() - self.len()
     }
 
-    #[cfg(test)]
     fn clear(&mut self) {
         self.set_head(&0);
         self.set_tail(&0);
     }
+
+    /// Adopts the slots appended to a queue of `previous_size` slots (after its storage has been grown to [`RingQueue::size`])
+    ///
+    /// # Note
+    ///
+    /// Returns the relocated slots as `(from, to)` in the order of relocation, so that per-slot data stored in parallel can be relocated by the caller.
+    fn grow(&mut self, previous_size: u32) -> Result<Vec<(usize, usize)>, ProgramError> {
+        let size = self.size();
+        guard!(size >= previous_size, InvalidQueueAccess);
+
+        let head = self.get_head();
+        let tail = self.get_tail();
+        guard!(
+            head < previous_size && tail < previous_size,
+            InvalidQueueAccess
+        );
+
+        // Only a wrapped queue requires relocations: the elements in front of `tail` are moved behind the previous last slot
+        // - if the appended slots do not suffice, the moved elements wrap around (and each target has already been moved)
+        let mut relocations = Vec::new();
+        if tail < head {
+            for from in 0..tail {
+                let to = (previous_size + from) % size;
+                self.set_data(to as usize, &self.get_data(from as usize));
+                relocations.push((from as usize, to as usize));
+            }
+            self.set_tail(&((previous_size + tail) % size));
+        }
+
+        Ok(relocations)
+    }
 }
 
 #[cfg(test)]
@@ -387,6 +524,43 @@ mod tests {
         assert_eq!(queue.view_first().unwrap(), 2);
     }
 
+    #[test]
+    fn test_move_last_ahead() {
+        test_queue!(queue, 6, 4, 4);
+        assert_eq!(
+            queue.move_last_ahead(0),
+            Err(ElusivError::InvalidQueueAccess.into())
+        );
+
+        for i in 0..4 {
+            queue.enqueue(i).unwrap();
+        }
+
+        assert_eq!(
+            queue.move_last_ahead(4),
+            Err(ElusivError::InvalidQueueAccess.into())
+        );
+
+        queue.move_last_ahead(0).unwrap();
+        assert_eq!(
+            (0..4).map(|i| queue.view(i).unwrap()).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+
+        // Wraps around the end of the storage
+        queue.move_last_ahead(2).unwrap();
+        assert_eq!(
+            (0..4).map(|i| queue.view(i).unwrap()).collect::<Vec<_>>(),
+            vec![0, 3, 1, 2]
+        );
+
+        queue.move_last_ahead(3).unwrap();
+        assert_eq!(
+            (0..4).map(|i| queue.view(i).unwrap()).collect::<Vec<_>>(),
+            vec![2, 0, 3, 1]
+        );
+    }
+
     #[test]
     fn test_remove_invalid() {
         test_queue!(queue, 10, 0, 0);
@@ -406,6 +580,27 @@ mod tests {
         queue.remove(1).unwrap();
     }
 
+    #[test]
+    fn test_restore() {
+        test_queue!(queue, 5, 3, 3);
+
+        for i in 0..4 {
+            queue.enqueue(i).unwrap();
+        }
+        queue.remove(3).unwrap();
+        assert_eq!(queue.view_first().unwrap(), 3);
+
+        queue.restore(2).unwrap();
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.view(0).unwrap(), 1);
+        assert_eq!(queue.view(2).unwrap(), 3);
+
+        // Restoring would overwrite existing elements
+        queue.enqueue(4).unwrap();
+        assert_eq!(queue.restore(1), Err(ElusivError::QueueIsFull.into()));
+        assert_eq!(queue.restore(5), Err(ElusivError::QueueIsFull.into()));
+    }
+
     #[test]
     fn test_clear_queue() {
         test_queue!(queue, 13, 0, 0);
@@ -413,4 +608,106 @@ mod tests {
         queue.clear();
         assert!(queue.is_empty());
     }
+
+    struct GrowableQueue {
+        head: u32,
+        tail: u32,
+        data: Vec<u32>,
+    }
+
+    impl RingQueue for GrowableQueue {
+        type N = u32;
+        const CAPACITY: u32 = 4;
+
+        fn size(&self) -> u32 {
+            self.data.len() as u32
+        }
+
+        fn get_head(&self) -> u32 {
+            self.head
+        }
+        fn set_head(&mut self, value: &u32) {
+            self.head = *value;
+        }
+
+        fn get_tail(&self) -> u32 {
+            self.tail
+        }
+        fn set_tail(&mut self, value: &u32) {
+            self.tail = *value;
+        }
+
+        fn get_data(&self, index: usize) -> u32 {
+            self.data[index]
+        }
+        fn set_data(&mut self, index: usize, value: &u32) {
+            self.data[index] = *value;
+        }
+    }
+
+    #[test]
+    fn test_grow() {
+        let elements = |queue: &GrowableQueue| {
+            (0..queue.len())
+                .map(|i| queue.view(i as usize).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // Not wrapped
+        let mut queue = GrowableQueue {
+            head: 0,
+            tail: 0,
+            data: vec![0; 5],
+        };
+        for i in 0..4 {
+            queue.enqueue(i).unwrap();
+        }
+        assert!(queue.is_full());
+
+        queue.data.extend([0; 3]);
+        assert_eq!(queue.grow(5).unwrap(), vec![]);
+        assert_eq!(elements(&queue), vec![0, 1, 2, 3]);
+        assert!(!queue.is_full());
+        assert_eq!(queue.empty_slots(), 3);
+
+        // Wrapped
+        let mut queue = GrowableQueue {
+            head: 3,
+            tail: 3,
+            data: vec![0; 5],
+        };
+        for i in 0..4 {
+            queue.enqueue(i).unwrap();
+        }
+        assert_eq!(queue.get_tail(), 2);
+
+        queue.data.extend([0; 3]);
+        assert_eq!(queue.grow(5).unwrap(), vec![(0, 5), (1, 6)]);
+        assert_eq!(elements(&queue), vec![0, 1, 2, 3]);
+
+        for i in 4..7 {
+            queue.enqueue(i).unwrap();
+        }
+        assert!(queue.is_full());
+        assert_eq!(elements(&queue), (0..7).collect::<Vec<_>>());
+
+        // Wrapped, with fewer appended slots than relocated elements
+        let mut queue = GrowableQueue {
+            head: 4,
+            tail: 4,
+            data: vec![0; 5],
+        };
+        for i in 0..4 {
+            queue.enqueue(i).unwrap();
+        }
+        assert_eq!(queue.get_tail(), 3);
+
+        queue.data.extend([0; 1]);
+        assert_eq!(queue.grow(5).unwrap(), vec![(0, 5), (1, 0), (2, 1)]);
+        assert_eq!(elements(&queue), vec![0, 1, 2, 3]);
+        assert_eq!(queue.get_tail(), 2);
+
+        // Invalid previous size
+        assert_eq!(queue.grow(7), Err(ElusivError::InvalidQueueAccess.into()));
+    }
 }
@@ -0,0 +1,64 @@
+use crate::macros::elusiv_account;
+use crate::state::program_account::PDAAccountData;
+use crate::token::SPL_TOKEN_COUNT;
+
+pub const TOKEN_COUNT: usize = SPL_TOKEN_COUNT + 1;
+
+/// Payout preferences of a recipient (PDA associated with the recipient's wallet address)
+///
+/// # Note
+///
+/// - A recipient without this account accepts all payouts.
+/// - Denied payouts are moved into the [`crate::state::governor::QuarantineAccount`] and tracked in `quarantined_amounts`.
+#[elusiv_account]
+pub struct RecipientPreferenceAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Denies payouts of all tokens
+    pub denies_all_payouts: bool,
+
+    /// Denies payouts of specific tokens (indexed by `token_id`)
+    pub denied_tokens: [bool; TOKEN_COUNT],
+
+    /// Amounts held in the quarantine escrow for this recipient (indexed by `token_id`)
+    pub quarantined_amounts: [u64; TOKEN_COUNT],
+}
+
+impl<'a> RecipientPreferenceAccount<'a> {
+    pub fn accepts_payout(&self, token_id: u16) -> bool {
+        let token_id = token_id as usize;
+        if token_id >= TOKEN_COUNT {
+            return false;
+        }
+
+        !self.get_denies_all_payouts() && !self.get_denied_tokens(token_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+
+    #[test]
+    fn test_accepts_payout() {
+        zero_program_account!(mut account, RecipientPreferenceAccount);
+
+        for token_id in 0..TOKEN_COUNT as u16 {
+            assert!(account.accepts_payout(token_id));
+        }
+        assert!(!account.accepts_payout(TOKEN_COUNT as u16));
+
+        account.set_denied_tokens(1, &true);
+        assert!(account.accepts_payout(0));
+        assert!(!account.accepts_payout(1));
+
+        account.set_denied_tokens(1, &false);
+        account.set_denies_all_payouts(&true);
+        for token_id in 0..TOKEN_COUNT as u16 {
+            assert!(!account.accepts_payout(token_id));
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use super::program_account::PDAAccountData;
+use crate::error::ElusivError;
+use crate::macros::{elusiv_account, guard};
+use solana_program::entrypoint::ProgramResult;
+
+/// Step 1: the root of the closed MT is frozen in its `NullifierAccount`
+pub const ROLLOVER_STEP_FREEZE_CLOSED_MT: u8 = 1;
+
+/// Step 2: the `NullifierAccount` of the next MT exists
+pub const ROLLOVER_STEP_OPEN_NULLIFIER_ACCOUNT: u8 = 1 << 1;
+
+/// Step 3: the `StorageAccount` has been reset for the next MT
+pub const ROLLOVER_STEP_INIT_STORAGE: u8 = 1 << 2;
+
+/// Step 4: the `GovernorAccount` points to the next MT
+pub const ROLLOVER_STEP_REPOINT_GOVERNOR: u8 = 1 << 3;
+
+pub const ROLLOVER_STEPS: u8 = ROLLOVER_STEP_FREEZE_CLOSED_MT
+    | ROLLOVER_STEP_OPEN_NULLIFIER_ACCOUNT
+    | ROLLOVER_STEP_INIT_STORAGE
+    | ROLLOVER_STEP_REPOINT_GOVERNOR;
+
+/// Tracks the completed steps of the rollover from the active MT to the next MT
+///
+/// # Note
+///
+/// - Each step is idempotent, so a rollover can be resumed from any step after a failed transaction.
+/// - The state of a completed rollover is kept until the next rollover begins.
+#[elusiv_account]
+pub struct RolloverStateAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The index of the MT that is being (or has last been) closed
+    pub closed_mt_index: u32,
+
+    /// Bitmask of the completed `ROLLOVER_STEP_*` steps
+    pub completed_steps: u8,
+}
+
+impl<'a> RolloverStateAccount<'a> {
+    pub fn is_in_progress(&self) -> bool {
+        let completed_steps = self.get_completed_steps();
+        completed_steps != 0 && completed_steps != ROLLOVER_STEPS
+    }
+
+    pub fn is_step_completed(&self, closed_mt_index: u32, step: u8) -> bool {
+        self.get_closed_mt_index() == closed_mt_index && self.get_completed_steps() & step == step
+    }
+
+    /// Begins the rollover of `closed_mt_index` (or resumes it, if it has already begun)
+    pub fn begin(&mut self, closed_mt_index: u32) -> ProgramResult {
+        if self.get_closed_mt_index() == closed_mt_index && self.get_completed_steps() != 0 {
+            return Ok(());
+        }
+
+        guard!(!self.is_in_progress(), ElusivError::InvalidAccountState);
+
+        self.set_closed_mt_index(&closed_mt_index);
+        self.set_completed_steps(&0);
+
+        Ok(())
+    }
+
+    /// Marks the `step` of the begun rollover of `closed_mt_index` as completed
+    pub fn complete_step(&mut self, closed_mt_index: u32, step: u8) -> ProgramResult {
+        guard!(
+            self.get_closed_mt_index() == closed_mt_index,
+            ElusivError::InvalidAccountState
+        );
+
+        self.set_completed_steps(&(self.get_completed_steps() | step));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+
+    #[test]
+    fn test_rollover_steps() {
+        zero_program_account!(mut rollover_state, RolloverStateAccount);
+        assert!(!rollover_state.is_in_progress());
+        assert!(!rollover_state.is_step_completed(0, ROLLOVER_STEP_FREEZE_CLOSED_MT));
+
+        rollover_state.begin(0).unwrap();
+        rollover_state
+            .complete_step(0, ROLLOVER_STEP_FREEZE_CLOSED_MT)
+            .unwrap();
+        assert!(rollover_state.is_in_progress());
+        assert!(rollover_state.is_step_completed(0, ROLLOVER_STEP_FREEZE_CLOSED_MT));
+        assert!(!rollover_state.is_step_completed(0, ROLLOVER_STEP_INIT_STORAGE));
+        assert!(!rollover_state.is_step_completed(1, ROLLOVER_STEP_FREEZE_CLOSED_MT));
+
+        // Resuming keeps the completed steps
+        rollover_state.begin(0).unwrap();
+        assert!(rollover_state.is_step_completed(0, ROLLOVER_STEP_FREEZE_CLOSED_MT));
+
+        // Another rollover can only begin once the active one is completed
+        assert_eq!(
+            rollover_state.begin(1),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+        assert_eq!(
+            rollover_state.complete_step(1, ROLLOVER_STEP_INIT_STORAGE),
+            Err(ElusivError::InvalidAccountState.into())
+        );
+
+        for step in [
+            ROLLOVER_STEP_OPEN_NULLIFIER_ACCOUNT,
+            ROLLOVER_STEP_INIT_STORAGE,
+            ROLLOVER_STEP_REPOINT_GOVERNOR,
+        ] {
+            rollover_state.complete_step(0, step).unwrap();
+        }
+        assert!(!rollover_state.is_in_progress());
+        assert!(rollover_state.is_step_completed(0, ROLLOVER_STEPS));
+
+        rollover_state.begin(1).unwrap();
+        assert_eq!(rollover_state.get_closed_mt_index(), 1);
+        assert!(!rollover_state.is_step_completed(1, ROLLOVER_STEP_FREEZE_CLOSED_MT));
+    }
+}
@@ -14,6 +14,9 @@ use solana_program::pubkey::Pubkey;
 /// We define the height by the number of leaves, so a tree with `2^n` leaves has height `n`.
 pub const MT_HEIGHT: u32 = 20;
 
+/// Minimal height of MTs configured with the [`TreeConfigAccount`]
+pub const MIN_MT_HEIGHT: u32 = 16;
+
 /// Number of all nodes in the MT
 pub const MT_SIZE: usize = mt_size(MT_HEIGHT);
 
@@ -27,6 +30,39 @@ pub const MT_COMMITMENT_COUNT: usize = two_pow!(MT_HEIGHT);
 /// Since before submitting a proof request the current root can change, we store the [`HISTORY_ARRAY_SIZE`] previous ones
 pub const HISTORY_ARRAY_SIZE: usize = 100;
 
+/// The number of MTs that can be active concurrently (each MT-shard has its own storage-, queue-, metadata- and hashing-accounts)
+pub const MT_SHARD_COUNT: u32 = 2;
+
+/// The MT-indices of a MT-shard start at `mt_shard << MT_SHARD_INDEX_SHIFT` (so proofs specify the MT-shard with the MT-index)
+const MT_SHARD_INDEX_SHIFT: u32 = 31;
+
+/// The PDA-offset of the single-instance accounts of a MT-shard (the first MT-shard uses the accounts without an offset)
+pub const fn mt_shard_offset(mt_shard: u32) -> Option<u32> {
+    match mt_shard {
+        0 => None,
+        _ => Some(mt_shard),
+    }
+}
+
+/// The MT-index of the `index`-th MT of a MT-shard
+pub const fn sharded_mt_index(mt_shard: u32, index: u32) -> u32 {
+    (mt_shard << MT_SHARD_INDEX_SHIFT) | index
+}
+
+/// The MT-shard containing the MT with the MT-index `mt_index`
+pub const fn mt_index_shard(mt_index: u32) -> u32 {
+    mt_index >> MT_SHARD_INDEX_SHIFT
+}
+
+/// Assigns a commitment to one of the `active_mt_shards` (first) MT-shards by its parity
+pub fn commitment_mt_shard(commitment: &U256, active_mt_shards: u32) -> u32 {
+    if active_mt_shards <= 1 {
+        return 0;
+    }
+
+    (commitment[0] & 1) as u32 % active_mt_shards
+}
+
 pub const VALUES_PER_STORAGE_SUB_ACCOUNT: usize = 83_887;
 const ACCOUNTS_COUNT: usize = div_ceiling_usize(MT_SIZE, VALUES_PER_STORAGE_SUB_ACCOUNT);
 
@@ -44,7 +80,7 @@ impl ChildAccount for StorageChildAccount {
 /// # Note
 ///
 /// The MT is stored linearly as an array with the first element being the root.
-#[elusiv_account(parent_account: { child_account_count: ACCOUNTS_COUNT, child_account: StorageChildAccount }, eager_type: true)]
+#[elusiv_account(parent_account: { child_account_count: ACCOUNTS_COUNT, child_account: StorageChildAccount })]
 pub struct StorageAccount {
     #[no_getter]
     #[no_setter]
@@ -54,7 +90,7 @@ pub struct StorageAccount {
     /// Points to the next commitment in the active MT
     pub next_commitment_ptr: u32,
 
-    /// The amount of already finished (closed) MTs
+    /// The amount of already finished (closed) MTs, offset by the first MT-index of the MT-shard (so this is the MT-index of the active MT)
     pub trees_count: u32,
 
     /// The amount of archived MTs
@@ -63,6 +99,12 @@ pub struct StorageAccount {
     /// Stores the last [`HISTORY_ARRAY_SIZE`] roots of the active tree (including the current root)
     pub active_mt_root_history: [U256; HISTORY_ARRAY_SIZE],
     pub mt_roots_count: u32, // required since we batch insert commitments
+
+    /// The height of the active MT (zero for [`MT_HEIGHT`])
+    pub height: u32,
+
+    /// The MT-shard of this account (see [`MT_SHARD_COUNT`])
+    pub mt_shard: u32,
 }
 
 impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
@@ -75,9 +117,33 @@ impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
         }
     }
 
+    /// Resets the active MT and the counts of closed and archived MTs (used to reset devnet deployments)
+    #[cfg(not(feature = "mainnet"))]
+    pub fn reset_all_trees(&mut self) {
+        self.reset();
+        self.set_trees_count(&sharded_mt_index(self.get_mt_shard(), 0));
+        self.set_archived_count(&0);
+    }
+
+    pub fn mt_height(&self) -> u32 {
+        match self.get_height() {
+            0 => MT_HEIGHT,
+            height => height,
+        }
+    }
+
+    /// Number of commitments that can be inserted into the active MT
+    ///
+    /// # Note
+    ///
+    /// The MT is always stored with [`MT_HEIGHT`] levels (the circuits are compiled for it), a lower height restricts the number of leaves.
+    pub fn commitment_capacity(&self) -> usize {
+        two_pow!(self.mt_height())
+    }
+
     pub fn is_full(&self) -> bool {
         let ptr = self.get_next_commitment_ptr() as usize;
-        ptr >= MT_COMMITMENT_COUNT
+        ptr >= self.commitment_capacity()
     }
 
     fn account_and_local_index(&self, index: usize) -> (usize, usize) {
@@ -154,11 +220,157 @@ impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
     }
 }
 
+/// Governance configuration of new MTs
+#[elusiv_account]
+pub struct TreeConfigAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The height of MTs activated after the active MT (zero for [`MT_HEIGHT`])
+    pub height: u32,
+}
+
+impl<'a> TreeConfigAccount<'a> {
+    pub fn next_mt_height(&self) -> u32 {
+        match self.get_height() {
+            0 => MT_HEIGHT,
+            height => height,
+        }
+    }
+}
+
 pub fn mt_array_index(index: usize, level: usize) -> usize {
     assert!(index < two_pow!(usize_as_u32_safe(level)));
     two_pow!(usize_as_u32_safe(level)) - 1 + index
 }
 
+/// The location of a node of the active MT in a [`StorageChildAccount`]
+///
+/// # Note
+///
+/// Allows light indexers to fetch single nodes (e.g. with the RPC `dataSlice` config) instead of mirroring the whole [`StorageAccount`].
+#[cfg(feature = "elusiv-client")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageNodeSlice {
+    /// `level`: `0` is the root level, `MT_HEIGHT` the commitment level
+    pub index: u32,
+    pub level: u32,
+
+    /// The index of the [`StorageChildAccount`] containing the node
+    pub child_index: u32,
+
+    /// The byte-offset of the node in the child-account's data (including the [`ChildAccountConfig`])
+    pub offset: usize,
+}
+
+#[cfg(feature = "elusiv-client")]
+impl StorageNodeSlice {
+    pub const LEN: usize = U256::SIZE;
+
+    pub fn new(index: usize, level: usize) -> Self {
+        let array_index = mt_array_index(index, level);
+
+        StorageNodeSlice {
+            index: usize_as_u32_safe(index),
+            level: usize_as_u32_safe(level),
+            child_index: usize_as_u32_safe(array_index / VALUES_PER_STORAGE_SUB_ACCOUNT),
+            offset: ChildAccountConfig::SIZE
+                + (array_index % VALUES_PER_STORAGE_SUB_ACCOUNT) * U256::SIZE,
+        }
+    }
+}
+
+/// Returns the minimal set of [`StorageNodeSlice`]s required to verify the opening of the commitment at `index`
+///
+/// # Note
+///
+/// - Contains the non-default siblings (ordered from the commitment level upwards), followed by the root.
+/// - Nodes with default values ([`EMPTY_TREE`]) are omitted, since they are known to the client.
+#[cfg(feature = "elusiv-client")]
+pub fn mt_opening_slices(index: usize, next_commitment_ptr: usize) -> Vec<StorageNodeSlice> {
+    let mut slices = Vec::new();
+    let mut index = index;
+
+    for i in 0..MT_HEIGHT as usize {
+        let level = MT_HEIGHT as usize - i;
+        let n_index = if index % 2 == 0 { index + 1 } else { index - 1 };
+        if !use_default_value(n_index, level, next_commitment_ptr) {
+            slices.push(StorageNodeSlice::new(n_index, level));
+        }
+        index >>= 1;
+    }
+
+    if !use_default_value(0, 0, next_commitment_ptr) {
+        slices.push(StorageNodeSlice::new(0, 0));
+    }
+
+    slices
+}
+
+/// Reconstructs the opening and root of the commitment at `index` from the `nodes` fetched for [`mt_opening_slices`]
+///
+/// # Note
+///
+/// Returns [`None`] if the number of `nodes` does not match the number of slices.
+#[cfg(feature = "elusiv-client")]
+pub fn mt_opening_from_nodes(
+    index: usize,
+    next_commitment_ptr: usize,
+    nodes: &[U256],
+) -> Option<([U256; MT_HEIGHT as usize], U256)> {
+    let slices = mt_opening_slices(index, next_commitment_ptr);
+    if slices.len() != nodes.len() {
+        return None;
+    }
+
+    let node = |index: usize, level: usize| -> U256 {
+        match slices
+            .iter()
+            .position(|s| s.index as usize == index && s.level as usize == level)
+        {
+            Some(position) => nodes[position],
+            None => EMPTY_TREE[MT_HEIGHT as usize - level],
+        }
+    };
+
+    let mut opening = [[0; 32]; MT_HEIGHT as usize];
+    let mut index = index;
+    for (i, sibling) in opening.iter_mut().enumerate() {
+        let level = MT_HEIGHT as usize - i;
+        let n_index = if index % 2 == 0 { index + 1 } else { index - 1 };
+        *sibling = node(n_index, level);
+        index >>= 1;
+    }
+
+    Some((opening, node(0, 0)))
+}
+
+/// Computes the root of the MT containing `commitment` at `index` with the `opening` (all values in mr-form)
+#[cfg(feature = "elusiv-client")]
+pub fn mt_root_from_opening(
+    commitment: &U256,
+    index: usize,
+    opening: &[U256; MT_HEIGHT as usize],
+) -> U256 {
+    use crate::commitment::poseidon_hash::full_poseidon2_hash;
+    use crate::fields::{fr_to_u256_le, u256_to_fr_skip_mr};
+
+    let mut hash = u256_to_fr_skip_mr(commitment);
+    let mut index = index;
+    for sibling in opening {
+        let sibling = u256_to_fr_skip_mr(sibling);
+        hash = if index % 2 == 0 {
+            full_poseidon2_hash(hash, sibling)
+        } else {
+            full_poseidon2_hash(sibling, hash)
+        };
+        index >>= 1;
+    }
+
+    fr_to_u256_le(&hash)
+}
+
 fn use_default_value(index: usize, level: usize, next_leaf_ptr: usize) -> bool {
     let level_inv = MT_HEIGHT as usize - level;
     next_leaf_ptr == 0 || index > (next_leaf_ptr - 1) >> level_inv
@@ -293,6 +505,25 @@ mod tests {
         assert_eq!(empty_root_raw().reduce(), EMPTY_TREE[MT_HEIGHT as usize]);
     }
 
+    #[test]
+    fn test_commitment_capacity() {
+        parent_account!(mut storage_account, StorageAccount);
+        assert_eq!(storage_account.mt_height(), MT_HEIGHT);
+        assert_eq!(storage_account.commitment_capacity(), MT_COMMITMENT_COUNT);
+
+        storage_account.set_height(&MIN_MT_HEIGHT);
+        assert_eq!(
+            storage_account.commitment_capacity(),
+            two_pow!(MIN_MT_HEIGHT)
+        );
+
+        storage_account.set_next_commitment_ptr(&(two_pow!(MIN_MT_HEIGHT) as u32 - 1));
+        assert!(!storage_account.is_full());
+
+        storage_account.set_next_commitment_ptr(&(two_pow!(MIN_MT_HEIGHT) as u32));
+        assert!(storage_account.is_full());
+    }
+
     #[test]
     fn test_set_node() {
         parent_account!(mut storage_account, StorageAccount);
@@ -432,6 +663,121 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "elusiv-client")]
+    fn test_mt_opening_slices() {
+        parent_account!(mut storage_account, StorageAccount);
+
+        // Empty MT -> only default values
+        assert!(mt_opening_slices(0, 0).is_empty());
+        let (opening, root) = mt_opening_from_nodes(0, 0, &[]).unwrap();
+        assert_eq!(opening, storage_account.get_mt_opening(0).unwrap());
+        assert_eq!(root, EMPTY_TREE[MT_HEIGHT as usize]);
+        assert_eq!(mt_root_from_opening(&EMPTY_TREE[0], 0, &opening), root);
+
+        let ptr = 5;
+        storage_account.set_next_commitment_ptr(&(ptr as u32));
+        for level in 0..=MT_HEIGHT as usize {
+            for index in 0..=((ptr - 1) >> (MT_HEIGHT as usize - level)) {
+                let mut value = [0; 32];
+                value[0] = level as u8;
+                value[1] = index as u8 + 1;
+                storage_account.set_node(&value, index, level).unwrap();
+            }
+        }
+
+        // Commitment 4: only the sibling at level `MT_HEIGHT - 2` and the root
+        assert_eq!(mt_opening_slices(4, ptr).len(), 2);
+
+        for index in 0..ptr {
+            let slices = mt_opening_slices(index, ptr);
+            assert_eq!(slices.last().unwrap().level, 0);
+
+            let nodes: Vec<U256> = slices
+                .iter()
+                .map(|slice| {
+                    let offset = slice.offset - ChildAccountConfig::SIZE;
+                    storage_account
+                        .execute_on_child_account(slice.child_index as usize, |data| {
+                            U256::try_from_slice(&data[offset..offset + StorageNodeSlice::LEN])
+                        })
+                        .unwrap()
+                        .unwrap()
+                })
+                .collect();
+
+            let (opening, root) = mt_opening_from_nodes(index, ptr, &nodes).unwrap();
+            assert_eq!(opening, storage_account.get_mt_opening(index).unwrap());
+            assert_eq!(root, storage_account.get_root().unwrap());
+
+            // Invalid number of nodes
+            assert!(mt_opening_from_nodes(index, ptr, &nodes[1..]).is_none());
+        }
+
+        // Nodes in later child-accounts
+        let slice = StorageNodeSlice::new(MT_COMMITMENT_COUNT - 1, MT_HEIGHT as usize);
+        assert_eq!(slice.child_index as usize, ACCOUNTS_COUNT - 1);
+        assert_eq!(
+            slice.offset,
+            ChildAccountConfig::SIZE
+                + ((MT_SIZE - 1) % VALUES_PER_STORAGE_SUB_ACCOUNT) * U256::SIZE
+        );
+    }
+
+    #[test]
+    fn test_mt_shard_offset() {
+        assert_eq!(mt_shard_offset(0), None);
+        assert_eq!(mt_shard_offset(1), Some(1));
+    }
+
+    #[test]
+    fn test_sharded_mt_index() {
+        assert_eq!(sharded_mt_index(0, 7), 7);
+        assert_eq!(mt_index_shard(sharded_mt_index(0, 7)), 0);
+
+        assert_ne!(sharded_mt_index(1, 7), 7);
+        assert_eq!(mt_index_shard(sharded_mt_index(1, 7)), 1);
+        assert_eq!(sharded_mt_index(1, 7) + 1, sharded_mt_index(1, 8));
+
+        for mt_shard in 0..MT_SHARD_COUNT {
+            assert_eq!(mt_index_shard(sharded_mt_index(mt_shard, 0)), mt_shard);
+        }
+    }
+
+    #[test]
+    fn test_commitment_mt_shard() {
+        let even = [2; 32];
+        let odd = [3; 32];
+
+        // Single active MT
+        for active_mt_shards in 0..=1 {
+            assert_eq!(commitment_mt_shard(&even, active_mt_shards), 0);
+            assert_eq!(commitment_mt_shard(&odd, active_mt_shards), 0);
+        }
+
+        assert_eq!(commitment_mt_shard(&even, MT_SHARD_COUNT), 0);
+        assert_eq!(commitment_mt_shard(&odd, MT_SHARD_COUNT), 1);
+
+        // Only the parity is relevant
+        let mut commitment = odd;
+        commitment[31] = 0;
+        commitment[0] = 255;
+        assert_eq!(commitment_mt_shard(&commitment, MT_SHARD_COUNT), 1);
+    }
+
+    #[cfg(not(feature = "mainnet"))]
+    #[test]
+    fn test_reset_all_trees_mt_shard() {
+        parent_account!(mut storage_account, StorageAccount);
+        storage_account.set_mt_shard(&1);
+        storage_account.set_trees_count(&sharded_mt_index(1, 3));
+        storage_account.set_next_commitment_ptr(&5);
+
+        storage_account.reset_all_trees();
+        assert_eq!(storage_account.get_trees_count(), sharded_mt_index(1, 0));
+        assert_eq!(storage_account.get_next_commitment_ptr(), 0);
+    }
+
     #[test]
     fn test_is_root_valid() {
         parent_account!(storage_account, StorageAccount);
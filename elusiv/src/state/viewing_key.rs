@@ -0,0 +1,22 @@
+use crate::macros::elusiv_account;
+use crate::state::program_account::PDAAccountData;
+use crate::types::U256;
+
+/// A viewing key voluntarily disclosed by a user (PDA associated with the `identifier` of the user's sends)
+///
+/// # Note
+///
+/// - The viewing key allows an auditor to link the commitments of the user.
+/// - Once published, a viewing key cannot be changed or revoked.
+/// - Memos of sends finalized with the `identifier` are recorded (as [`crate::event::ElusivEvent::ViewingKeyMemoRecorded`]), they need to be encrypted under the viewing key by the user.
+#[elusiv_account]
+pub struct ViewingKeyAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub viewing_key: U256,
+
+    /// The number of recorded memos
+    pub memo_count: u32,
+}
@@ -9,7 +9,7 @@ impl ChildAccount for VKeyChildAccount {
 }
 
 /// Account used for storing a single immutable [`VerifyingKey`]
-#[elusiv_account(parent_account: { child_account_count: 2, child_account: VKeyChildAccount }, eager_type: true)]
+#[elusiv_account(parent_account: { child_account_count: 2, child_account: VKeyChildAccount })]
 pub struct VKeyAccount {
     #[no_getter]
     #[no_setter]
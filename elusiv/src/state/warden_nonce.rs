@@ -0,0 +1,61 @@
+use super::program_account::PDAAccountData;
+use crate::error::ElusivError;
+use crate::macros::{elusiv_account, guard};
+use solana_program::entrypoint::ProgramResult;
+
+/// Monotonic instruction nonce of a warden (PDA associated with the warden's pubkey)
+///
+/// # Note
+///
+/// - Wardens opt-in by opening the account and supplying a nonce with their init instructions.
+/// - A (retried) duplicate submission fails on the nonce check, before any account is created.
+#[elusiv_account]
+pub struct WardenNonceAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The highest nonce consumed so far
+    pub last_nonce: u64,
+}
+
+impl<'a> WardenNonceAccount<'a> {
+    /// Consumes `nonce`, which has to be strictly greater than all previously consumed nonces
+    pub fn consume(&mut self, nonce: u64) -> ProgramResult {
+        guard!(
+            nonce > self.get_last_nonce(),
+            ElusivError::InvalidWardenNonce
+        );
+        self.set_last_nonce(&nonce);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+
+    #[test]
+    fn test_consume() {
+        zero_program_account!(mut warden_nonce, WardenNonceAccount);
+
+        assert_eq!(
+            warden_nonce.consume(0),
+            Err(ElusivError::InvalidWardenNonce.into())
+        );
+
+        warden_nonce.consume(1).unwrap();
+        warden_nonce.consume(3).unwrap();
+        assert_eq!(warden_nonce.get_last_nonce(), 3);
+
+        // Duplicate and outdated nonces
+        for nonce in [3, 2] {
+            assert_eq!(
+                warden_nonce.consume(nonce),
+                Err(ElusivError::InvalidWardenNonce.into())
+            );
+        }
+    }
+}
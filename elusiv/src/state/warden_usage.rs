@@ -0,0 +1,63 @@
+use super::program_account::PDAAccountData;
+use crate::macros::elusiv_account;
+
+/// Usage counters of a warden (PDA associated with the warden's pubkey), the data basis for billing and reward formulas
+///
+/// # Note
+///
+/// - Wardens opt-in by opening the account, metered instructions accept any other account in its place.
+/// - All counters saturate instead of failing the instruction.
+#[elusiv_account]
+pub struct WardenUsageAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Finalized verifications with the warden as the original fee-payer
+    pub verifications: u64,
+
+    /// Poseidon rounds of base-commitment and commitment hashes cranked by the warden
+    pub hashing_rounds: u64,
+
+    /// Bytes of commitment metadata (encrypted notes) written by the warden
+    pub metadata_bytes: u64,
+}
+
+impl<'a> WardenUsageAccount<'a> {
+    pub fn record_verification(&mut self) {
+        self.set_verifications(&self.get_verifications().saturating_add(1));
+    }
+
+    pub fn record_hashing_rounds(&mut self, rounds: u32) {
+        self.set_hashing_rounds(&self.get_hashing_rounds().saturating_add(rounds as u64));
+    }
+
+    pub fn record_metadata_bytes(&mut self, len: usize) {
+        self.set_metadata_bytes(&self.get_metadata_bytes().saturating_add(len as u64));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+
+    #[test]
+    fn test_record_usage() {
+        zero_program_account!(mut warden_usage, WardenUsageAccount);
+
+        warden_usage.record_verification();
+        warden_usage.record_verification();
+        warden_usage.record_hashing_rounds(65);
+        warden_usage.record_metadata_bytes(128);
+
+        assert_eq!(warden_usage.get_verifications(), 2);
+        assert_eq!(warden_usage.get_hashing_rounds(), 65);
+        assert_eq!(warden_usage.get_metadata_bytes(), 128);
+
+        // Saturating
+        warden_usage.set_hashing_rounds(&(u64::MAX - 1));
+        warden_usage.record_hashing_rounds(2);
+        assert_eq!(warden_usage.get_hashing_rounds(), u64::MAX);
+    }
+}
@@ -200,6 +200,84 @@ mod tests {
         assert!(!verify_token_account(&usdc_account, 2).unwrap());
     }
 
+    #[test]
+    fn test_unpack_token_state() {
+        use solana_program::{program_error::ProgramError, program_pack::Pack};
+        use spl_token_2022::extension::{
+            immutable_owner::ImmutableOwner, ExtensionType, StateWithExtensionsMut,
+        };
+        use spl_token_2022::state::Account;
+
+        let data = spl_token_account_data(USDC_TOKEN_ID);
+        account_info!(
+            spl_account,
+            Pubkey::new_unique(),
+            data.clone(),
+            spl_token::id(),
+            false
+        );
+        assert_eq!(
+            unpack_token_state::<Account>(&spl_account).unwrap().mint,
+            usdc_token().mint
+        );
+
+        // Token-2022 account with an extension
+        let mut extended_data =
+            vec![0; ExtensionType::get_account_len::<Account>(&[ExtensionType::ImmutableOwner])];
+        {
+            let mut state =
+                StateWithExtensionsMut::<Account>::unpack_uninitialized(&mut extended_data)
+                    .unwrap();
+            state.init_extension::<ImmutableOwner>(true).unwrap();
+            state.base = Account::unpack(&data).unwrap();
+            state.pack_base();
+            state.init_account_type().unwrap();
+        }
+        account_info!(
+            extended_account,
+            Pubkey::new_unique(),
+            extended_data,
+            spl_token_2022::id(),
+            false
+        );
+        assert_eq!(
+            unpack_token_state::<Account>(&extended_account)
+                .unwrap()
+                .mint,
+            usdc_token().mint
+        );
+
+        // USDC is managed by SPL-Token
+        assert!(!verify_token_account(&extended_account, USDC_TOKEN_ID).unwrap());
+
+        account_info!(
+            invalid_account,
+            Pubkey::new_unique(),
+            data,
+            Pubkey::new_unique(),
+            false
+        );
+        assert_eq!(
+            unpack_token_state::<Account>(&invalid_account),
+            Err(ProgramError::IncorrectProgramId)
+        );
+    }
+
+    #[test]
+    fn test_transfer_fee() {
+        account_info!(mint, usdc_token().mint, vec![]);
+
+        // No transfer-fees for Lamports and SPL-Token mints
+        for token in [
+            Token::new(LAMPORTS_TOKEN_ID, LAMPORTS_PER_SOL),
+            Token::new(USDC_TOKEN_ID, 1_000_000),
+        ] {
+            assert!(!is_token_2022(token.token_id()).unwrap());
+            assert_eq!(transfer_fee(&mint, &token, 0), Ok(0));
+            assert_eq!(token.net_of_transfer_fee(&mint, 0), Ok(token));
+        }
+    }
+
     #[test]
     fn test_token_price_new() {
         let sol_usd = Price {
@@ -302,6 +380,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_token_price_new_fixed_rate() {
+        // 1 USDC = 10^6 base units = 2_000 LAMPORTS
+        let price = TokenPrice::new_fixed_rate(2_000, USDC_TOKEN_ID).unwrap();
+        assert_eq!(
+            price.token_into_lamports(Token::new(USDC_TOKEN_ID, 1_000_000)),
+            Ok(Lamports(2_000))
+        );
+        assert_eq!(
+            price
+                .lamports_into_token(&Lamports(1), USDC_TOKEN_ID)
+                .unwrap()
+                .amount(),
+            500
+        );
+
+        // 1 USDC = 1 SOL
+        let price = TokenPrice::new_fixed_rate(LAMPORTS_PER_SOL, USDC_TOKEN_ID).unwrap();
+        assert_eq!(
+            price
+                .lamports_into_token(&Lamports(1_000), USDC_TOKEN_ID)
+                .unwrap()
+                .amount(),
+            1
+        );
+
+        let price = TokenPrice::new_fixed_rate(123, LAMPORTS_TOKEN_ID).unwrap();
+        assert_eq!(price.token_id, LAMPORTS_TOKEN_ID);
+        assert_eq!(
+            price.token_into_lamports(Token::Lamports(Lamports(123))),
+            Ok(Lamports(123))
+        );
+
+        assert!(TokenPrice::new_fixed_rate(u64::MAX, USDC_TOKEN_ID).is_err());
+    }
+
     #[test]
     fn test_token_into_lamports() {
         // 1 LAMPORT = 39 * 10^{-9} USD
@@ -1,3 +1,4 @@
+pub use crate::bytes::u256_to_le_limbs;
 use crate::bytes::BorshSerDeSized;
 use crate::fields::{fr_to_u256_le, u256_to_big_uint, u64_to_u256_skip_mr, G1A, G2A};
 use crate::macros::BorshSerDeSized;
@@ -5,7 +6,6 @@ use crate::processor::MAX_MT_COUNT;
 use crate::proof::vkey::{MigrateUnaryVKey, SendQuadraVKey, VerifyingKeyInfo};
 use crate::state::metadata::CommitmentMetadata;
 use crate::state::proof::NullifierDuplicateAccount;
-use crate::u64_array;
 use ark_bn254::Fr;
 use ark_ff::PrimeField;
 use borsh::BorshDeserialize;
@@ -321,6 +321,35 @@ impl JoinSplitPublicInputs {
             .collect()
     }
 
+    /// Returns the nullifier-hashes in their canonical (ascending byte) order
+    ///
+    /// # Note
+    ///
+    /// The order of `input_commitments` is bound to the proof through the public signals and can not be changed.
+    /// Nullifier-hashes are canonicalized for the duplicate-PDA derivation and for their insertion.
+    pub fn canonical_nullifier_hashes(&self) -> Vec<RawU256> {
+        let mut nullifier_hashes = self.nullifier_hashes();
+        nullifier_hashes.sort_unstable_by_key(|n| n.skip_mr());
+        nullifier_hashes
+    }
+
+    /// Returns true if the `input_commitments` are ordered as expected by the circuits
+    ///
+    /// # Note
+    ///
+    /// - the first input commitment has to reference a root
+    /// - a [`None`] root references the first root
+    /// - unused input commitments (up to [`JOIN_SPLIT_MAX_N_ARITY`]) are zero-padded public signals
+    pub fn has_circuit_input_commitment_order(&self) -> bool {
+        match self.input_commitments.first() {
+            Some(input_commitment) => {
+                input_commitment.root.is_some()
+                    && self.input_commitments.len() <= JOIN_SPLIT_MAX_N_ARITY
+            }
+            None => false,
+        }
+    }
+
     pub fn associated_nullifier_duplicate_pda_pubkey(&self) -> Pubkey {
         let nullifier_hashes: Vec<&RawU256> = self
             .input_commitments
@@ -399,6 +428,61 @@ pub struct SendPublicInputs {
     pub recipient_is_associated_token_account: bool,
     pub solana_pay_transfer: bool,
     pub hashed_inputs: U256,
+
+    /// Unix-timestamp after which the request can neither be initialized nor finalized (`0` for no expiry)
+    ///
+    /// # Note
+    ///
+    /// The expiry is bound to the proof through `hashed_inputs`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub expiry: u64,
+
+    /// Unix-timestamp before which the funds of the request are not released to the recipient (`0` for no timelock)
+    ///
+    /// # Note
+    ///
+    /// - The unlock timestamp is bound to the proof through `hashed_inputs`.
+    /// - Timelocked sends are restricted to lamports transfers (no solana-pay).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unlock_timestamp: u64,
+
+    /// The first slot in which the request can be finalized (`0` if there is no restriction)
+    ///
+    /// # Note
+    ///
+    /// The execution window is chosen by the user and bound to the proof through `hashed_inputs`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub earliest_execution_slot: u64,
+
+    /// The last slot in which the request can be finalized (`0` if there is no restriction)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub latest_execution_slot: u64,
+}
+
+impl SendPublicInputs {
+    /// Returns true if the request has an expiry and `timestamp` lies after it
+    pub fn is_expired(&self, timestamp: u64) -> bool {
+        self.expiry > 0 && timestamp > self.expiry
+    }
+
+    pub fn is_timelocked(&self) -> bool {
+        self.unlock_timestamp > 0
+    }
+
+    /// Returns true if the request has a timelock and `timestamp` lies before its unlock
+    pub fn is_locked(&self, timestamp: u64) -> bool {
+        self.is_timelocked() && timestamp < self.unlock_timestamp
+    }
+
+    /// Returns true if `slot` lies before the execution window of the request
+    pub fn is_before_execution_window(&self, slot: u64) -> bool {
+        slot < self.earliest_execution_slot
+    }
+
+    /// Returns true if `slot` lies after the execution window of the request
+    pub fn is_after_execution_window(&self, slot: u64) -> bool {
+        self.latest_execution_slot > 0 && slot > self.latest_execution_slot
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -411,6 +495,9 @@ pub fn generate_hashed_inputs(
     is_associated_token_account: bool,
     metadata: &CommitmentMetadata,
     optional_fee: &OptionalFee,
+    expiry: u64,
+    unlock_timestamp: u64,
+    execution_window: (u64, u64),
     memo: &Option<Vec<u8>>,
 ) -> U256 {
     let mut data = recipient.to_vec();
@@ -422,6 +509,19 @@ pub fn generate_hashed_inputs(
     data.extend(metadata);
     data.extend(optional_fee.collector.to_bytes());
     data.extend(optional_fee.amount.to_le_bytes());
+    data.extend(expiry.to_le_bytes());
+
+    // The unlock timestamp is skipped without a timelock (existing requests are not affected)
+    if unlock_timestamp > 0 {
+        data.extend(unlock_timestamp.to_le_bytes());
+    }
+
+    // The execution window is skipped without a restriction (existing requests are not affected)
+    let (earliest_execution_slot, latest_execution_slot) = execution_window;
+    if earliest_execution_slot > 0 || latest_execution_slot > 0 {
+        data.extend(earliest_execution_slot.to_le_bytes());
+        data.extend(latest_execution_slot.to_le_bytes());
+    }
 
     if let Some(memo) = memo {
         data.extend(memo);
@@ -464,6 +564,18 @@ impl PublicInputs for SendPublicInputs {
             return false;
         }
 
+        // The execution window can not be empty
+        if self.latest_execution_slot > 0
+            && self.latest_execution_slot < self.earliest_execution_slot
+        {
+            return false;
+        }
+
+        // Timelocked sends are lamports transfers (no solana-pay)
+        if self.is_timelocked() && (self.join_split.token_id != 0 || self.solana_pay_transfer) {
+            return false;
+        }
+
         true
     }
 
@@ -565,6 +677,7 @@ pub fn compute_fee_rec<V: crate::proof::vkey::VerifyingKeyInfo, P: PublicInputs>
     public_inputs: &mut P,
     program_fee: &crate::state::fee::ProgramFee,
     price: &crate::token::TokenPrice,
+    token_fee_spread_bps: u16,
 ) {
     let fee = program_fee
         .proof_verification_fee(
@@ -577,13 +690,14 @@ pub fn compute_fee_rec<V: crate::proof::vkey::VerifyingKeyInfo, P: PublicInputs>
             public_inputs.join_split_inputs().amount,
             public_inputs.join_split_inputs().token_id,
             price,
+            token_fee_spread_bps,
         )
         .unwrap()
         .amount();
 
     if fee != public_inputs.join_split_inputs().fee {
         public_inputs.set_fee(fee);
-        compute_fee_rec::<V, P>(public_inputs, program_fee, price)
+        compute_fee_rec::<V, P>(public_inputs, program_fee, price, token_fee_spread_bps)
     }
 }
 
@@ -593,16 +707,7 @@ pub fn compute_fee_rec_lamports<V: crate::proof::vkey::VerifyingKeyInfo, P: Publ
     program_fee: &crate::state::fee::ProgramFee,
 ) {
     use crate::token::TokenPrice;
-    compute_fee_rec::<V, P>(public_inputs, program_fee, &TokenPrice::new_lamports())
-}
-
-pub fn u256_to_le_limbs(v: U256) -> [u64; 4] {
-    [
-        u64::from_le_bytes(u64_array!(v, 0)),
-        u64::from_le_bytes(u64_array!(v, 8)),
-        u64::from_le_bytes(u64_array!(v, 16)),
-        u64::from_le_bytes(u64_array!(v, 24)),
-    ]
+    compute_fee_rec::<V, P>(public_inputs, program_fee, &TokenPrice::new_lamports(), 0)
 }
 
 /// Can be used to split a number > scalar field modulus (like Curve25519 keys) into two public inputs
@@ -757,6 +862,10 @@ mod test {
             hashed_inputs: [0; 32],
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
+            expiry: 0,
+            unlock_timestamp: 0,
+            earliest_execution_slot: 0,
+            latest_execution_slot: 0,
         };
         assert!(valid_inputs.verify_additional_constraints());
 
@@ -775,9 +884,47 @@ mod test {
         assert!(!inputs.verify_additional_constraints());
 
         // The first root has to be != `None`
-        let mut inputs = valid_inputs;
+        let mut inputs = valid_inputs.clone();
         inputs.join_split.input_commitments[0].root = None;
         assert!(!inputs.verify_additional_constraints());
+
+        // Expiry
+        let mut inputs = valid_inputs.clone();
+        assert!(!inputs.is_expired(u64::MAX));
+        inputs.expiry = 100;
+        assert!(!inputs.is_expired(100));
+        assert!(inputs.is_expired(101));
+
+        // Execution window
+        let mut inputs = valid_inputs.clone();
+        assert!(!inputs.is_before_execution_window(0));
+        assert!(!inputs.is_after_execution_window(u64::MAX));
+        inputs.earliest_execution_slot = 10;
+        inputs.latest_execution_slot = 20;
+        assert!(inputs.verify_additional_constraints());
+        assert!(inputs.is_before_execution_window(9));
+        assert!(!inputs.is_before_execution_window(10));
+        assert!(!inputs.is_after_execution_window(20));
+        assert!(inputs.is_after_execution_window(21));
+        inputs.latest_execution_slot = 9;
+        assert!(!inputs.verify_additional_constraints());
+
+        // Timelock
+        let mut inputs = valid_inputs.clone();
+        assert!(!inputs.is_locked(0));
+        inputs.unlock_timestamp = 100;
+        assert!(inputs.verify_additional_constraints());
+        assert!(inputs.is_locked(99));
+        assert!(!inputs.is_locked(100));
+
+        // Timelocked sends are lamports transfers (no solana-pay)
+        let mut timelocked = inputs.clone();
+        timelocked.join_split.token_id = 1;
+        assert!(!timelocked.verify_additional_constraints());
+
+        let mut timelocked = inputs;
+        timelocked.solana_pay_transfer = true;
+        assert!(!timelocked.verify_additional_constraints());
     }
 
     #[test]
@@ -802,6 +949,10 @@ mod test {
             hashed_inputs: u256_from_str_skip_mr("306186522190603117929438292402982536627"),
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
+            expiry: 0,
+            unlock_timestamp: 0,
+            earliest_execution_slot: 0,
+            latest_execution_slot: 0,
         };
 
         let expected = [
@@ -828,6 +979,51 @@ mod test {
         assert_eq!(expected.len(), SendPublicInputs::PUBLIC_INPUTS_COUNT);
     }
 
+    #[test]
+    fn test_canonical_nullifier_hashes() {
+        let input_commitment = |root: Option<RawU256>, n: u8| InputCommitment {
+            root,
+            nullifier_hash: RawU256::new([n; 32]),
+        };
+        let mut inputs = JoinSplitPublicInputs {
+            input_commitments: vec![
+                input_commitment(Some(RawU256::new([1; 32])), 3),
+                input_commitment(None, 1),
+                input_commitment(None, 2),
+            ],
+            output_commitment: RawU256::new([1; 32]),
+            recent_commitment_index: 0,
+            fee_version: 0,
+            amount: 0,
+            fee: 0,
+            optional_fee: OptionalFee::default(),
+            token_id: 0,
+            metadata: CommitmentMetadata::default(),
+        };
+
+        assert_eq!(
+            inputs.canonical_nullifier_hashes(),
+            vec![
+                RawU256::new([1; 32]),
+                RawU256::new([2; 32]),
+                RawU256::new([3; 32])
+            ]
+        );
+        assert!(inputs.has_circuit_input_commitment_order());
+
+        // The duplicate-PDA is independent of the order
+        let pda = inputs.nullifier_duplicate_pda();
+        inputs.input_commitments.swap(1, 2);
+        assert_eq!(inputs.nullifier_duplicate_pda(), pda);
+
+        // The first input commitment has to reference a root
+        inputs.input_commitments.swap(0, 1);
+        assert!(!inputs.has_circuit_input_commitment_order());
+
+        inputs.input_commitments.clear();
+        assert!(!inputs.has_circuit_input_commitment_order());
+    }
+
     #[test]
     fn test_join_split_public_inputs_size() {
         let mut input_commitments = vec![
@@ -897,6 +1093,7 @@ mod test {
         str.retain(|c| !c.is_whitespace());
 
         let result: SendPublicInputs = serde_json::from_str(&str).unwrap();
+        assert_eq!(result.expiry, 0);
         serde_json::to_string(&result).unwrap();
         result.try_to_vec().unwrap();
     }
@@ -1048,7 +1245,7 @@ mod test {
         };
 
         let expected = u256_from_str_skip_mr(
-            "5593953132782974239527342909647286690390142208813910555015910557707363192433",
+            "7120349926635409882453952300488322721786264151158876798491361538550512980730",
         );
 
         assert_eq!(
@@ -1061,14 +1258,40 @@ mod test {
                 is_associated_token_account,
                 &metadata,
                 &optional_fee,
-                &None
+                0,
+                0,
+                (0, 0),
+                &None,
             ),
             expected
         );
 
         let memo = Some(vec![1, 6, 7, 88, 88, 8, 8, 8, 8, 84, 3]);
         let expected = u256_from_str_skip_mr(
-            "7190753645577115026314391505244643580055580854837751025314898582887072501874",
+            "4039520565751451891130967281887959466115097488840448843594817457992614629295",
+        );
+
+        assert_eq!(
+            generate_hashed_inputs(
+                &recipient,
+                &identifier,
+                &iv,
+                &encrypted_owner,
+                &solana_pay_id,
+                is_associated_token_account,
+                &metadata,
+                &optional_fee,
+                0,
+                0,
+                (0, 0),
+                &memo,
+            ),
+            expected
+        );
+
+        // Expiry
+        let expected = u256_from_str_skip_mr(
+            "1537131267201502344980810491032389816107281178458592842557631984206850508664",
         );
 
         assert_eq!(
@@ -1081,9 +1304,33 @@ mod test {
                 is_associated_token_account,
                 &metadata,
                 &optional_fee,
-                &memo
+                1700000000,
+                0,
+                (0, 0),
+                &None,
             ),
             expected
         );
+
+        // The execution window is only part of the hash if the request is restricted
+        let hash = |execution_window: (u64, u64)| {
+            generate_hashed_inputs(
+                &recipient,
+                &identifier,
+                &iv,
+                &encrypted_owner,
+                &solana_pay_id,
+                is_associated_token_account,
+                &metadata,
+                &optional_fee,
+                1700000000,
+                0,
+                execution_window,
+                &None,
+            )
+        };
+        assert_eq!(hash((0, 0)), expected);
+        assert_ne!(hash((10, 20)), expected);
+        assert_ne!(hash((10, 20)), hash((10, 21)));
     }
 }
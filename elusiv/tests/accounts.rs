@@ -2,24 +2,39 @@
 
 mod common;
 
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use common::*;
 use elusiv::instruction::*;
-use elusiv::processor::CommitmentHashRequest;
+use elusiv::processor::{CommitmentHashRequest, SubAccountKind};
 use elusiv::state::commitment::{
-    BaseCommitmentBufferAccount, CommitmentHashingAccount, CommitmentQueue, CommitmentQueueAccount,
+    BaseCommitmentBufferAccount, BaseCommitmentHashingSlotsAccount, CommitmentHashingAccount,
+    CommitmentQueue, CommitmentQueueAccount,
 };
 use elusiv::state::program_account::PDAOffset;
 use elusiv::state::queue::{Queue, RingQueue};
 use elusiv::state::{
-    fee::FeeAccount,
-    governor::{FeeCollectorAccount, GovernorAccount, PoolAccount},
+    fee::{FeeAccount, ProgramFee},
+    governor::{
+        FeeCollectorAccount, GovernorAccount, PoolAccount, QuarantineAccount, TokenRegistryAccount,
+        UpgradeGateAccount,
+    },
+    history::VerificationHistoryAccount,
+    metadata::{MetadataAccount, MetadataQueueAccount},
+    metrics::MetricsAccount,
     nullifier::{NullifierAccount, NullifierChildAccount},
     program_account::{PDAAccount, PDAAccountData, ProgramAccount, SizedAccount},
-    storage::{StorageAccount, MT_COMMITMENT_COUNT},
+    proof::VerifierRAMAccount,
+    rollover::{RolloverStateAccount, ROLLOVER_STEPS},
+    storage::{
+        sharded_mt_index, StorageAccount, TreeConfigAccount, MT_COMMITMENT_COUNT, MT_HEIGHT,
+        MT_SHARD_COUNT,
+    },
+};
+use elusiv::token::{Lamports, SPL_TOKEN_COUNT};
+use elusiv_types::{
+    split_child_account_data_mut, BorshSerDeSized, ChildAccountConfig, ChildAccountTag,
+    ParentAccount,
 };
-use elusiv::token::SPL_TOKEN_COUNT;
-use elusiv_types::split_child_account_data_mut;
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program_test::*;
 use solana_sdk::signer::Signer;
@@ -50,12 +65,20 @@ async fn test_setup_initial_accounts() {
     assert_account::<GovernorAccount>(&mut test, None).await;
     assert_account::<PoolAccount>(&mut test, None).await;
     assert_account::<FeeCollectorAccount>(&mut test, None).await;
+    assert_account::<QuarantineAccount>(&mut test, None).await;
+    assert_account::<VerifierRAMAccount>(&mut test, Some(0)).await;
+    assert_account::<UpgradeGateAccount>(&mut test, None).await;
+    assert_account::<TokenRegistryAccount>(&mut test, None).await;
+    assert_account::<MetricsAccount>(&mut test, None).await;
+    assert_account::<VerificationHistoryAccount>(&mut test, None).await;
 
     assert_account::<CommitmentHashingAccount>(&mut test, None).await;
     assert_account::<CommitmentQueueAccount>(&mut test, None).await;
     assert_account::<BaseCommitmentBufferAccount>(&mut test, None).await;
+    assert_account::<BaseCommitmentHashingSlotsAccount>(&mut test, None).await;
 
     assert_account::<StorageAccount>(&mut test, None).await;
+    assert_account::<TreeConfigAccount>(&mut test, None).await;
 }
 
 #[tokio::test]
@@ -73,6 +96,83 @@ async fn test_setup_initial_accounts_duplicate() {
     test.tx_should_fail_simple(&ixs).await;
 }
 
+#[tokio::test]
+async fn test_open_mt_shard_accounts() {
+    let mut test = start_test().await;
+    let payer = test.payer();
+    test.tx_should_succeed_simple(&initial_single_instance_pdas(payer))
+        .await;
+
+    // The accounts of MT-shard `0` are single-instance-accounts
+    for mt_shard in [0, MT_SHARD_COUNT] {
+        test.ix_should_fail_simple(ElusivInstruction::open_mt_shard_accounts_instruction(
+            mt_shard,
+            WritableSignerAccount(payer),
+        ))
+        .await;
+    }
+
+    test.ix_should_succeed_simple(ElusivInstruction::open_mt_shard_accounts_instruction(
+        1,
+        WritableSignerAccount(payer),
+    ))
+    .await;
+
+    // Second time will fail
+    test.ix_should_fail_simple(ElusivInstruction::open_mt_shard_accounts_instruction(
+        1,
+        WritableSignerAccount(payer),
+    ))
+    .await;
+
+    assert!(
+        test.account_does_exist(&CommitmentQueueAccount::find(Some(1)).0)
+            .await
+    );
+    assert!(
+        test.account_does_exist(&CommitmentHashingAccount::find(Some(1)).0)
+            .await
+    );
+    assert!(
+        test.account_does_exist(&MetadataQueueAccount::find(Some(1)).0)
+            .await
+    );
+    assert!(
+        test.account_does_exist(&MetadataAccount::find(Some(1)).0)
+            .await
+    );
+
+    // The MT-indices of MT-shard `1` are disjoint from the ones of MT-shard `0`
+    pda_account!(storage_account, StorageAccount, None, Some(1), test);
+    assert_eq!(storage_account.get_mt_shard(), 1);
+    assert_eq!(storage_account.get_trees_count(), sharded_mt_index(1, 0));
+
+    // Child-accounts of MT-shard `1`
+    let pubkeys = test
+        .create_parent_account::<StorageAccount>(&elusiv::id())
+        .await;
+    let ixs: Vec<_> = pubkeys
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            ElusivInstruction::enable_storage_child_account_instruction(
+                1,
+                i as u32,
+                WritableUserAccount(*p),
+            )
+        })
+        .collect();
+    test.tx_should_succeed_simple(&ixs).await;
+
+    // A child-account can only be enabled in a single MT-shard
+    test.ix_should_fail_simple(ElusivInstruction::enable_storage_child_account_instruction(
+        0,
+        0,
+        WritableUserAccount(pubkeys[0]),
+    ))
+    .await;
+}
+
 #[tokio::test]
 async fn test_enable_token_account() {
     let mut test = start_test().await;
@@ -135,6 +235,47 @@ async fn test_setup_fee_account() {
     .await;
 }
 
+#[tokio::test]
+async fn test_fast_forward_scheduled_fee_version() {
+    let mut test = start_test_with_setup().await;
+    let genesis_fee = genesis_fee(&mut test).await;
+    let scheduled_fee = ProgramFee {
+        proof_subvention: Lamports(45),
+        ..genesis_fee.clone()
+    };
+    let activation_slot = test.clock().await.slot + 1000;
+
+    test.set_pda_account::<GovernorAccount, _>(&elusiv::id(), None, None, |data| {
+        let mut account = GovernorAccount::new(data).unwrap();
+        account.set_scheduled_fee_version(&1);
+        account.set_scheduled_program_fee(&scheduled_fee);
+        account.set_scheduled_fee_activation_slot(&activation_slot);
+    })
+    .await;
+
+    {
+        pda_account!(governor, GovernorAccount, None, None, test);
+        let slot = test.clock().await.slot;
+        assert_eq!(governor.active_fee_version(slot), (0, genesis_fee));
+    }
+
+    fast_forward_scheduled_fee_version(&mut test).await;
+
+    let slot = test.clock().await.slot;
+    assert_eq!(slot, activation_slot);
+    pda_account!(governor, GovernorAccount, None, None, test);
+    assert_eq!(governor.active_fee_version(slot), (1, scheduled_fee));
+
+    // Already active
+    fast_forward_scheduled_fee_version(&mut test).await;
+    assert_eq!(test.clock().await.slot, activation_slot);
+
+    // Timestamp manipulation
+    test.set_clock_timestamp(1_000).await;
+    test.warp_timestamp(60).await;
+    assert_eq!(test.clock().await.unix_timestamp, 1_060);
+}
+
 #[tokio::test]
 async fn test_setup_pda_accounts_invalid_pda() {
     let mut test = start_test().await;
@@ -231,6 +372,7 @@ async fn test_reset_active_mt() {
             AccountMeta::new(StorageAccount::find(None).0, false),
             AccountMeta::new(CommitmentQueueAccount::find(None).0, false),
             AccountMeta::new(NullifierAccount::find(Some(1)).0, false),
+            AccountMeta::new_readonly(TreeConfigAccount::find(None).0, false),
         ],
     ))
     .await;
@@ -246,6 +388,7 @@ async fn test_reset_active_mt() {
             AccountMeta::new(root_storage_account, false),
             AccountMeta::new(CommitmentQueueAccount::find(None).0, false),
             AccountMeta::new(NullifierAccount::find(Some(0)).0, false),
+            AccountMeta::new_readonly(TreeConfigAccount::find(None).0, false),
         ],
     ))
     .await;
@@ -260,6 +403,7 @@ async fn test_reset_active_mt() {
         assert_eq!(s.get_trees_count(), 1);
         assert_eq!(s.get_next_commitment_ptr(), 0);
         assert_eq!(s.get_mt_roots_count(), 0);
+        assert_eq!(s.mt_height(), MT_HEIGHT);
     })
     .await;
 
@@ -304,6 +448,86 @@ async fn test_reset_active_mt() {
     .await;
 }
 
+#[tokio::test]
+async fn test_resumable_mt_rollover() {
+    let mut test = start_test().await;
+    setup_initial_pdas(&mut test).await;
+    setup_storage_account(&mut test).await;
+    create_merkle_tree(&mut test, 0).await;
+
+    test.ix_should_succeed_simple(ElusivInstruction::open_rollover_state_account_instruction(
+        WritableSignerAccount(test.payer()),
+    ))
+    .await;
+
+    let storage_accounts = storage_accounts(&mut test).await;
+    let storage_accounts = user_accounts(&storage_accounts);
+
+    // Steps depend on the frozen closed MT
+    test.ix_should_fail_simple(ElusivInstruction::rollover_init_storage_instruction(0))
+        .await;
+
+    // Failure since active MT is not full
+    test.ix_should_fail_simple(
+        ElusivInstruction::rollover_freeze_closed_merkle_tree_instruction(0, &storage_accounts),
+    )
+    .await;
+
+    test.set_pda_account::<StorageAccount, _>(&elusiv::id(), None, None, |data| {
+        let mut storage_account = StorageAccount::new(data).unwrap();
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+    })
+    .await;
+
+    test.ix_should_succeed_simple(
+        ElusivInstruction::rollover_freeze_closed_merkle_tree_instruction(0, &storage_accounts),
+    )
+    .await;
+
+    test.ix_should_succeed_simple(ElusivInstruction::rollover_init_storage_instruction(0))
+        .await;
+
+    // Resuming after the init-storage step (completed steps are no-ops)
+    test.ix_should_succeed_simple(
+        ElusivInstruction::rollover_freeze_closed_merkle_tree_instruction(0, &storage_accounts),
+    )
+    .await;
+    test.ix_should_succeed_simple(ElusivInstruction::rollover_init_storage_instruction(0))
+        .await;
+
+    storage_account(None, &mut test, |s: &StorageAccount| {
+        assert_eq!(s.get_trees_count(), 1);
+        assert_eq!(s.get_next_commitment_ptr(), 0);
+    })
+    .await;
+
+    // The next NullifierAccount is required before re-pointing the governor
+    test.ix_should_fail_simple(ElusivInstruction::rollover_repoint_governor_instruction(0))
+        .await;
+
+    test.ix_should_succeed_simple(
+        ElusivInstruction::rollover_open_nullifier_account_instruction(
+            0,
+            WritableSignerAccount(test.payer()),
+        ),
+    )
+    .await;
+    assert!(
+        test.account_does_exist(&NullifierAccount::find(Some(1)).0)
+            .await
+    );
+
+    test.ix_should_succeed_simple(ElusivInstruction::rollover_repoint_governor_instruction(0))
+        .await;
+
+    pda_account!(governor, GovernorAccount, None, None, test);
+    assert_eq!(governor.get_active_mt_index(), 1);
+
+    pda_account!(rollover_state, RolloverStateAccount, None, None, test);
+    assert_eq!(rollover_state.get_closed_mt_index(), 0);
+    assert_eq!(rollover_state.get_completed_steps(), ROLLOVER_STEPS);
+}
+
 #[tokio::test]
 async fn test_global_child_account_duplicates() {
     let mut test = start_test().await;
@@ -368,6 +592,7 @@ async fn test_global_child_account_duplicates() {
 
     // Setting in storage-account
     test.ix_should_fail_simple(ElusivInstruction::enable_storage_child_account_instruction(
+        0,
         0,
         WritableUserAccount(account.pubkey()),
     ))
@@ -406,5 +631,78 @@ async fn test_global_child_account_duplicates() {
     // Check map size
     let data = test.data(&account2.pubkey()).await;
     assert_eq!(data[0], 1);
-    assert_eq!(&data[1..5], &[0, 0, 0, 0]);
+    assert_eq!(
+        &data[ChildAccountConfig::SIZE..ChildAccountConfig::SIZE + 4],
+        &[0, 0, 0, 0]
+    );
+}
+
+#[tokio::test]
+async fn test_register_sub_account() {
+    let mut test = start_test_with_setup().await;
+
+    test.ix_should_succeed_simple(ElusivInstruction::open_nullifier_account_instruction(
+        0,
+        WritableSignerAccount(test.payer()),
+    ))
+    .await;
+
+    let nullifier_account = NullifierAccount::find(Some(0)).0;
+    let account = test
+        .create_program_account_rent_exempt(&elusiv::id(), NullifierChildAccount::SIZE)
+        .await;
+
+    // Invalid parent-account
+    test.ix_should_fail_simple(ElusivInstruction::register_sub_account_instruction(
+        SubAccountKind::Nullifier { mt_index: 1 },
+        0,
+        WritableUserAccount(nullifier_account),
+        WritableUserAccount(account.pubkey()),
+    ))
+    .await;
+
+    // Invalid size
+    let invalid_account = test
+        .create_program_account_rent_exempt(&elusiv::id(), NullifierChildAccount::SIZE - 1)
+        .await;
+    test.ix_should_fail_simple(ElusivInstruction::register_sub_account_instruction(
+        SubAccountKind::Nullifier { mt_index: 0 },
+        0,
+        WritableUserAccount(nullifier_account),
+        WritableUserAccount(invalid_account.pubkey()),
+    ))
+    .await;
+
+    test.ix_should_succeed_simple(ElusivInstruction::register_sub_account_instruction(
+        SubAccountKind::Nullifier { mt_index: 0 },
+        1,
+        WritableUserAccount(nullifier_account),
+        WritableUserAccount(account.pubkey()),
+    ))
+    .await;
+
+    let mut data = test.data(&nullifier_account).await;
+    let parent = NullifierAccount::new(&mut data).unwrap();
+    assert_eq!(parent.get_child_pubkey(0), None);
+    assert_eq!(parent.get_child_pubkey(1), Some(account.pubkey()));
+
+    let data = test.data(&account.pubkey()).await;
+    let config = ChildAccountConfig::try_from_slice(&data[..ChildAccountConfig::SIZE]).unwrap();
+    assert!(config.is_in_use);
+    assert_eq!(
+        config.tag.option(),
+        Some(ChildAccountTag {
+            parent: nullifier_account,
+            child_index: 1
+        })
+    );
+
+    // Registering twice
+    test.ix_should_fail_simple(ElusivInstruction::register_sub_account_instruction(
+        SubAccountKind::Nullifier { mt_index: 0 },
+        2,
+        WritableUserAccount(nullifier_account),
+        WritableUserAccount(account.pubkey()),
+    ))
+    .await;
 }
@@ -20,17 +20,20 @@ use elusiv::{
             BaseCommitmentHashingAccount, CommitmentHashingAccount, CommitmentQueue,
             CommitmentQueueAccount,
         },
-        governor::{FeeCollectorAccount, GovernorAccount, PoolAccount},
-        metadata::{CommitmentMetadata, MetadataQueue, MetadataQueueAccount},
+        governor::{FeeCollectorAccount, GovernorAccount, PoolAccount, PROTOCOL_VERSION},
+        metadata::{
+            CommitmentMetadata, CommitmentMetadataAccount, MetadataQueue, MetadataQueueAccount,
+        },
+        metrics::MetricsAccount,
         program_account::{PDAAccount, ProgramAccount, SizedAccount},
         queue::{Queue, RingQueue},
-        storage::{StorageAccount, EMPTY_TREE, MT_HEIGHT},
+        storage::{StorageAccount, StorageNodeSlice, EMPTY_TREE, MT_HEIGHT},
     },
     token::{Lamports, Token, TokenPrice, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID},
     types::{RawU256, U256},
 };
 use elusiv_computation::PartialComputation;
-use elusiv_types::{tokens::Price, BorshSerDeSized};
+use elusiv_types::{tokens::Price, BorshSerDeSized, ElusivOption};
 use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, system_program};
 use solana_program_test::*;
 
@@ -117,10 +120,12 @@ async fn test_store_base_commitment_lamports_transfer() {
     let sol_price_account = test.token_to_usd_price_pyth_account(0);
     test.ix_should_succeed(
         ElusivInstruction::store_base_commitment_instruction(
+            PROTOCOL_VERSION,
             0,
             hashing_account_bump,
             request.clone(),
             metadata,
+            ElusivOption::None,
             SignerAccount(client.pubkey),
             WritableUserAccount(client.pubkey),
             WritableSignerAccount(warden.pubkey),
@@ -130,6 +135,8 @@ async fn test_store_base_commitment_lamports_transfer() {
             UserAccount(sol_price_account),
             UserAccount(sol_price_account),
             UserAccount(system_program::id()),
+            UserAccount(system_program::id()),
+            warden_nonce_account(warden.pubkey),
         ),
         &[&client.keypair, &warden.keypair],
     )
@@ -154,6 +161,13 @@ async fn test_store_base_commitment_lamports_transfer() {
 
     // Warden has computation_fee lamports
     assert_eq!(computation_fee, warden.lamports(&mut test).await);
+
+    pda_account!(metrics, MetricsAccount, None, None, test);
+    assert_eq!(
+        metrics.get_volume_in(LAMPORTS_TOKEN_ID as usize),
+        request.amount
+    );
+    assert_eq!(metrics.get_active_notes(LAMPORTS_TOKEN_ID as usize), 1);
 }
 
 #[tokio::test]
@@ -233,10 +247,12 @@ async fn test_store_base_commitment_token_transfer() {
     let hashing_account_bump = BaseCommitmentHashingAccount::find(Some(0)).1;
     test.ix_should_succeed(
         ElusivInstruction::store_base_commitment_instruction(
+            PROTOCOL_VERSION,
             0,
             hashing_account_bump,
             request.clone(),
             metadata,
+            ElusivOption::None,
             SignerAccount(client.pubkey),
             WritableUserAccount(client.get_token_account(USDC_TOKEN_ID)),
             WritableSignerAccount(warden.pubkey),
@@ -246,6 +262,8 @@ async fn test_store_base_commitment_token_transfer() {
             UserAccount(sol_price_account),
             UserAccount(token_price_account),
             UserAccount(spl_token::id()),
+            UserAccount(system_program::id()),
+            warden_nonce_account(warden.pubkey),
         ),
         &[&client.keypair, &warden.keypair],
     )
@@ -316,6 +334,7 @@ async fn test_base_commitment_lamports() {
         0,
         request0.clone(),
         metadata,
+        ElusivOption::None,
         client.pubkey,
         warden_a.pubkey,
     );
@@ -362,10 +381,12 @@ async fn test_base_commitment_lamports() {
     // Store fails: Invalid pool_account
     test.ix_should_fail(
         ElusivInstruction::store_base_commitment_instruction(
+            PROTOCOL_VERSION,
             0,
             hashing_account_bump,
             request0.clone(),
             metadata,
+            ElusivOption::None,
             SignerAccount(client.pubkey),
             WritableUserAccount(client.pubkey),
             WritableSignerAccount(warden_a.pubkey),
@@ -375,6 +396,8 @@ async fn test_base_commitment_lamports() {
             UserAccount(system_program::id()),
             UserAccount(system_program::id()),
             UserAccount(system_program::id()),
+            UserAccount(system_program::id()),
+            warden_nonce_account(warden_a.pubkey),
         ),
         &[&client.keypair, &warden_a.keypair],
     )
@@ -383,10 +406,12 @@ async fn test_base_commitment_lamports() {
     // Store fails: Invalid fee_collector_account
     test.ix_should_fail(
         ElusivInstruction::store_base_commitment_instruction(
+            PROTOCOL_VERSION,
             0,
             hashing_account_bump,
             request0.clone(),
             metadata,
+            ElusivOption::None,
             SignerAccount(client.pubkey),
             WritableUserAccount(client.pubkey),
             WritableSignerAccount(warden_a.pubkey),
@@ -396,6 +421,8 @@ async fn test_base_commitment_lamports() {
             UserAccount(system_program::id()),
             UserAccount(system_program::id()),
             UserAccount(system_program::id()),
+            UserAccount(system_program::id()),
+            warden_nonce_account(warden_a.pubkey),
         ),
         &[&client.keypair, &warden_a.keypair],
     )
@@ -453,6 +480,7 @@ async fn test_base_commitment_lamports() {
             0,
             request1.clone(),
             metadata,
+            ElusivOption::None,
             client.pubkey,
             warden_a.pubkey,
         ),
@@ -466,6 +494,7 @@ async fn test_base_commitment_lamports() {
             1,
             request0.clone(),
             metadata,
+            ElusivOption::None,
             client.pubkey,
             warden_b.pubkey,
         ),
@@ -479,6 +508,7 @@ async fn test_base_commitment_lamports() {
             1,
             request1.clone(),
             metadata,
+            ElusivOption::None,
             client.pubkey,
             warden_b.pubkey,
         ),
@@ -514,7 +544,11 @@ async fn test_base_commitment_lamports() {
     let finalize_ix = ElusivInstruction::finalize_base_commitment_hash_instruction(
         0,
         0,
+        0,
+        vec![],
         WritableUserAccount(warden_a.pubkey),
+        commitment_metadata_account(&request0.commitment.reduce()),
+        warden_usage_account(warden_a.pubkey),
     );
 
     // Compute each base_commitment_hash
@@ -549,7 +583,11 @@ async fn test_base_commitment_lamports() {
         ElusivInstruction::finalize_base_commitment_hash_instruction(
             0,
             0,
+            0,
+            vec![],
             WritableUserAccount(warden_b.pubkey),
+            commitment_metadata_account(&request0.commitment.reduce()),
+            warden_usage_account(warden_b.pubkey),
         ),
     )
     .await;
@@ -557,7 +595,11 @@ async fn test_base_commitment_lamports() {
     let finalize_ix = ElusivInstruction::finalize_base_commitment_hash_instruction(
         0,
         0,
+        0,
+        vec![],
         WritableUserAccount(warden_a.pubkey),
+        commitment_metadata_account(&request0.commitment.reduce()),
+        warden_usage_account(warden_a.pubkey),
     );
 
     // Finalize fails: two finalize ix in a single tx
@@ -678,10 +720,12 @@ async fn test_base_commitment_token() {
     let hashing_account_bump = BaseCommitmentHashingAccount::find(Some(0)).1;
     test.ix_should_succeed(
         ElusivInstruction::store_base_commitment_instruction(
+            PROTOCOL_VERSION,
             0,
             hashing_account_bump,
             request.clone(),
             metadata,
+            ElusivOption::None,
             SignerAccount(client.pubkey),
             WritableUserAccount(client.get_token_account(USDC_TOKEN_ID)),
             WritableSignerAccount(warden.pubkey),
@@ -691,6 +735,8 @@ async fn test_base_commitment_token() {
             UserAccount(sol_price_account),
             UserAccount(token_price_account),
             UserAccount(spl_token::id()),
+            UserAccount(system_program::id()),
+            warden_nonce_account(warden.pubkey),
         ),
         &[&client.keypair, &warden.keypair],
     )
@@ -708,7 +754,11 @@ async fn test_base_commitment_token() {
         ElusivInstruction::finalize_base_commitment_hash_instruction(
             0,
             0,
+            0,
+            vec![],
             WritableUserAccount(warden.pubkey),
+            commitment_metadata_account(&request.commitment.reduce()),
+            warden_usage_account(warden.pubkey),
         ),
     )
     .await;
@@ -816,8 +866,9 @@ async fn test_single_commitment() {
 
     // Init succeeds
     test.tx_should_succeed_simple(&[
-        ElusivInstruction::init_commitment_hash_setup_instruction(false, &[]),
+        ElusivInstruction::init_commitment_hash_setup_instruction(0, false, &[]),
         ElusivInstruction::init_commitment_hash_instruction(
+            0,
             false,
             &writable_user_accounts(&metadata_accounts),
         ),
@@ -845,8 +896,9 @@ async fn test_single_commitment() {
 
     // Second init fails, since a hashing is already active
     test.tx_should_fail_simple(&[
-        ElusivInstruction::init_commitment_hash_setup_instruction(false, &[]),
+        ElusivInstruction::init_commitment_hash_setup_instruction(0, false, &[]),
         ElusivInstruction::init_commitment_hash_instruction(
+            0,
             false,
             &writable_user_accounts(&metadata_accounts),
         ),
@@ -854,13 +906,17 @@ async fn test_single_commitment() {
     .await;
 
     let finalize_ix = ElusivInstruction::finalize_commitment_hash_instruction(
+        0,
+        0,
         &writable_user_accounts(&storage_accounts),
     );
 
     let compute_ix = ElusivInstruction::compute_commitment_hash_instruction(
+        0,
         0,
         0,
         WritableSignerAccount(warden.pubkey),
+        warden_usage_account(warden.pubkey),
     );
 
     // Computation
@@ -877,12 +933,13 @@ async fn test_single_commitment() {
         }
 
         // Success for correct compute budget
-        test.tx_should_succeed(
+        test.tx_should_succeed_with_max_cu(
             &[
                 request_compute_units(required_compute_budget),
                 compute_ix.clone(),
             ],
             &[&warden.keypair],
+            required_compute_budget as u64,
         )
         .await;
 
@@ -963,6 +1020,7 @@ async fn set_finished_base_commitment_hash(
             Fr::zero(),
         ]));
         hashing_account.set_fee_payer(&original_fee_payer.to_bytes());
+        hashing_account.set_is_active(&true);
     }
     test.set_program_account_rent_exempt(
         &elusiv::id(),
@@ -1005,8 +1063,186 @@ async fn test_commitment_full_queue() {
         ElusivInstruction::finalize_base_commitment_hash_instruction(
             0,
             0,
+            0,
+            vec![],
+            WritableUserAccount(warden.pubkey),
+            commitment_metadata_account(&request.commitment),
+            warden_usage_account(warden.pubkey),
+        ),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_finalize_base_commitment_hash_account_permutations() {
+    let mut test = start_test_with_setup().await;
+    let warden = test.new_actor().await;
+
+    let pool = PoolAccount::find(None).0;
+    test.airdrop_lamports(&pool, LAMPORTS_PER_SOL).await;
+    set_finished_base_commitment_hash(0, &u256_from_str("1"), &warden.pubkey, &mut test).await;
+
+    test.test_instruction_account_permutations(
+        &[],
+        ElusivInstruction::finalize_base_commitment_hash_instruction(
+            0,
+            0,
+            0,
+            vec![],
             WritableUserAccount(warden.pubkey),
+            commitment_metadata_account(&u256_from_str("1")),
+            warden_usage_account(warden.pubkey),
         ),
+        &[],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_finalize_base_commitment_hash_ciphertext() {
+    let mut test = start_test_with_setup().await;
+    let warden = test.new_actor().await;
+    let commitment = u256_from_str("1");
+
+    let pool = PoolAccount::find(None).0;
+    test.airdrop_lamports(&pool, LAMPORTS_PER_SOL).await;
+    test.airdrop_lamports(&warden.pubkey, LAMPORTS_PER_SOL)
+        .await;
+    set_finished_base_commitment_hash(0, &commitment, &warden.pubkey, &mut test).await;
+
+    test.set_pda_account::<GovernorAccount, _>(&elusiv::id(), None, None, |data| {
+        let mut account = GovernorAccount::new(data).unwrap();
+        account.set_metadata_size_class_max_lens(0, &128);
+    })
+    .await;
+
+    let mut finalize_ix = ElusivInstruction::finalize_base_commitment_hash_instruction(
+        0,
+        0,
+        0,
+        vec![1; 100],
+        WritableUserAccount(warden.pubkey),
+        commitment_metadata_account(&commitment),
+        warden_usage_account(warden.pubkey),
+    );
+
+    // The original fee payer has to sign
+    test.ix_should_fail_simple(finalize_ix.clone()).await;
+
+    finalize_ix.accounts[0].is_signer = true;
+    test.ix_should_succeed(finalize_ix, &[&warden.keypair])
+        .await;
+
+    pda_account!(
+        commitment_metadata_account,
+        CommitmentMetadataAccount,
+        Some(Pubkey::new_from_array(commitment)),
+        None,
+        test
+    );
+    assert_eq!(commitment_metadata_account.get_commitment(), commitment);
+    assert_eq!(commitment_metadata_account.get_size_class(), 0);
+    assert_eq!(commitment_metadata_account.ciphertext(), &[1; 100]);
+}
+
+#[tokio::test]
+async fn test_backfill_commitment_metadata() {
+    let mut test = start_test_with_setup().await;
+    let payer = test.new_actor().await;
+    let identifier = test.new_actor().await;
+    let commitment = u256_from_str("1");
+
+    setup_storage_account(&mut test).await;
+    let storage_accounts = storage_accounts(&mut test).await;
+
+    test.set_pda_account::<StorageAccount, _>(&elusiv::id(), None, None, |data| {
+        let mut storage_account = StorageAccount::new(data).unwrap();
+        storage_account.set_next_commitment_ptr(&1);
+    })
+    .await;
+    test.set_pda_account::<GovernorAccount, _>(&elusiv::id(), None, None, |data| {
+        let mut account = GovernorAccount::new(data).unwrap();
+        account.set_metadata_size_class_max_lens(0, &128);
+    })
+    .await;
+
+    // Insert the commitment as the first leaf of the active MT
+    let slice = StorageNodeSlice::new(0, MT_HEIGHT as usize);
+    let storage_child_account = storage_accounts[slice.child_index as usize];
+    let mut data = test.data(&storage_child_account).await;
+    data[slice.offset..slice.offset + StorageNodeSlice::LEN].copy_from_slice(&commitment);
+    test.set_program_account_rent_exempt(&elusiv::id(), &storage_child_account, &data)
+        .await;
+
+    let backfill_ix = |commitment_index: u32, commitment: U256| {
+        ElusivInstruction::backfill_commitment_metadata_instruction(
+            0,
+            commitment_index,
+            commitment,
+            identifier.pubkey.to_bytes(),
+            vec![1; 100],
+            WritableSignerAccount(payer.pubkey),
+            SignerAccount(identifier.pubkey),
+            &[UserAccount(storage_child_account)],
+            WritableUserAccount(
+                CommitmentMetadataAccount::find_with_pubkey(
+                    CommitmentMetadataAccount::backfill_pubkey(&commitment, &identifier.pubkey),
+                    None,
+                )
+                .0,
+            ),
+        )
+    };
+
+    // No commitment has been inserted at the index
+    test.ix_should_fail(
+        backfill_ix(1, commitment),
+        &[&payer.keypair, &identifier.keypair],
+    )
+    .await;
+
+    // A different commitment has been inserted at the index
+    test.ix_should_fail(
+        backfill_ix(0, u256_from_str("2")),
+        &[&payer.keypair, &identifier.keypair],
+    )
+    .await;
+
+    // The identifier key has to sign
+    let mut unsigned_ix = backfill_ix(0, commitment);
+    unsigned_ix.accounts[1].is_signer = false;
+    test.ix_should_fail(unsigned_ix, &[&payer.keypair]).await;
+
+    test.ix_should_succeed(
+        backfill_ix(0, commitment),
+        &[&payer.keypair, &identifier.keypair],
+    )
+    .await;
+
+    pda_account!(
+        commitment_metadata_account,
+        CommitmentMetadataAccount,
+        Some(CommitmentMetadataAccount::backfill_pubkey(
+            &commitment,
+            &identifier.pubkey
+        )),
+        None,
+        test
+    );
+    assert_eq!(commitment_metadata_account.get_commitment(), commitment);
+    assert_eq!(commitment_metadata_account.ciphertext(), &[1; 100]);
+    assert_eq!(
+        commitment_metadata_account
+            .get_backfill_identifier()
+            .option(),
+        Some(identifier.pubkey)
+    );
+    assert_eq!(commitment_metadata_account.get_commitment_index(), 0);
+
+    // A note can only be backfilled once per identifier
+    test.ix_should_fail(
+        backfill_ix(0, commitment),
+        &[&payer.keypair, &identifier.keypair],
     )
     .await;
 }
@@ -1034,6 +1270,8 @@ async fn test_commitment_correct_storage_account_insertion() {
         .await;
 
         test.ix_should_succeed_simple(ElusivInstruction::finalize_commitment_hash_instruction(
+            0,
+            0,
             &writable_user_accounts(&storage_accounts),
         ))
         .await;
@@ -1139,10 +1377,12 @@ async fn test_commitment_hash_multiple_commitments_zero_batch() {
     for i in 0..requests.len() {
         test.tx_should_succeed_simple(&[
             ElusivInstruction::init_commitment_hash_setup_instruction(
+                0,
                 false,
                 &user_accounts(&storage_accounts),
             ),
             ElusivInstruction::init_commitment_hash_instruction(
+                0,
                 false,
                 &writable_user_accounts(&metadata_accounts),
             ),
@@ -1154,9 +1394,11 @@ async fn test_commitment_hash_multiple_commitments_zero_batch() {
                 &[
                     request_compute_units(COMMITMENT_HASH_COMPUTE_BUDGET),
                     ElusivInstruction::compute_commitment_hash_instruction(
+                        0,
                         0,
                         0,
                         WritableSignerAccount(warden.pubkey),
+                        warden_usage_account(warden.pubkey),
                     ),
                 ],
                 &[&warden.keypair],
@@ -1165,6 +1407,8 @@ async fn test_commitment_hash_multiple_commitments_zero_batch() {
         }
 
         test.ix_should_succeed_simple(ElusivInstruction::finalize_commitment_hash_instruction(
+            0,
+            0,
             &writable_user_accounts(&storage_accounts),
         ))
         .await;
@@ -1247,10 +1491,12 @@ async fn test_commitment_hash_with_batching_rate(
     // Init, compute, finalize every commitment
     test.tx_should_succeed_simple(&[
         ElusivInstruction::init_commitment_hash_setup_instruction(
+            0,
             false,
             &user_accounts(&storage_accounts),
         ),
         ElusivInstruction::init_commitment_hash_instruction(
+            0,
             false,
             &writable_user_accounts(&metadata_accounts),
         ),
@@ -1262,9 +1508,11 @@ async fn test_commitment_hash_with_batching_rate(
             &[
                 request_compute_units(COMMITMENT_HASH_COMPUTE_BUDGET),
                 ElusivInstruction::compute_commitment_hash_instruction(
+                    0,
                     0,
                     0,
                     WritableSignerAccount(warden.pubkey),
+                    warden_usage_account(warden.pubkey),
                 ),
             ],
             &[&warden.keypair],
@@ -1274,6 +1522,8 @@ async fn test_commitment_hash_with_batching_rate(
 
     for _ in 0..=batching_rate {
         test.ix_should_succeed_simple(ElusivInstruction::finalize_commitment_hash_instruction(
+            0,
+            0,
             &writable_user_accounts(&storage_accounts),
         ))
         .await;
@@ -1465,3 +1715,102 @@ async fn test_commitment_hash_batching_rate_four() {
     )
     .await;
 }
+
+#[tokio::test]
+async fn test_commitment_hashing_simulated_wardens() {
+    const WARDEN_COUNT: usize = 3;
+    const MAX_STEPS: usize = 1_000;
+
+    let mut test = start_test_with_setup().await;
+    let fee_payer = test.new_actor().await;
+
+    setup_storage_account(&mut test).await;
+    setup_metadata_account(&mut test).await;
+
+    let pool = PoolAccount::find(None).0;
+    test.airdrop_lamports(&pool, LAMPORTS_PER_SOL * 100).await;
+
+    let base_commitments = [
+        u256_from_str(
+            "17695089122606640046122050453568281484908329551111425943069599106344573268591",
+        ),
+        u256_from_str(
+            "6647356857703578745245713474272809288360618637120301827353679811066213900723",
+        ),
+    ];
+    for (i, commitment) in base_commitments.iter().enumerate() {
+        set_finished_base_commitment_hash(i as u32, commitment, &fee_payer.pubkey, &mut test).await;
+    }
+
+    let requests: Vec<CommitmentHashRequest> = [
+        "15379640546683409691976024780847698243281026803042985142030905481489858510622",
+        "9526685147941891237781527305630522288121859341465303072844645355022143819256",
+    ]
+    .iter()
+    .map(|c| CommitmentHashRequest {
+        commitment: u256_from_str(c),
+        fee_version: 0,
+        min_batching_rate: 0,
+    })
+    .collect();
+    enqueue_commitments(&mut test, &requests, None).await;
+
+    // All wardens race for the same hashing accounts and queue
+    let mut handles = Vec::new();
+    for _ in 0..WARDEN_COUNT {
+        let warden = SimulatedWarden::new(&mut test)
+            .await
+            .with_base_commitment_hash_accounts(&[0, 1]);
+        handles.push(warden.spawn(MAX_STEPS));
+    }
+
+    let mut stats = Vec::new();
+    for handle in handles {
+        stats.push(handle.await.unwrap().stats);
+    }
+
+    // Each finalization and dequeue succeeds exactly once
+    let commitment_count = base_commitments.len() + requests.len();
+    assert_eq!(
+        stats
+            .iter()
+            .map(|s| s.base_commitments_finalized)
+            .sum::<usize>(),
+        base_commitments.len()
+    );
+    assert_eq!(
+        stats
+            .iter()
+            .map(|s| s.commitment_batches_initialized)
+            .sum::<usize>(),
+        commitment_count
+    );
+    assert_eq!(
+        stats
+            .iter()
+            .map(|s| s.commitment_finalization_ixs)
+            .sum::<usize>(),
+        commitment_count
+    );
+
+    for i in 0..base_commitments.len() {
+        assert!(
+            test.account_does_not_exist(&BaseCommitmentHashingAccount::find(Some(i as u32)).0)
+                .await
+        );
+    }
+
+    queue!(queue, CommitmentQueue, test);
+    assert!(queue.is_empty());
+
+    // The insertion order depends on the race, but all commitments are inserted
+    let mut expected: Vec<U256> = base_commitments.to_vec();
+    expected.extend(requests.iter().map(|r| r.commitment));
+    storage_account(None, &mut test, |s: &StorageAccount| {
+        assert_eq!(s.get_next_commitment_ptr(), commitment_count as u32);
+        for i in 0..commitment_count {
+            assert!(expected.contains(&s.get_node(i, MT_HEIGHT as usize).unwrap()));
+        }
+    })
+    .await;
+}
@@ -2,21 +2,37 @@
 #![allow(dead_code)]
 
 use elusiv::{
+    commitment::{
+        commitment_hash_computation_instructions, BaseCommitmentHashComputation,
+        COMMITMENT_HASH_COMPUTE_BUDGET,
+    },
     fields::fr_to_u256_le,
     instruction::ElusivInstruction,
     proof::verifier::{CombinedMillerLoop, FinalExponentiation},
     state::{
+        commitment::{
+            BaseCommitmentHashingAccount, CommitmentHashingAccount, CommitmentQueue,
+            CommitmentQueueAccount,
+        },
         fee::{BasisPointFee, ProgramFee},
-        metadata::MetadataAccount,
+        governor::GovernorAccount,
+        metadata::{CommitmentMetadataAccount, MetadataAccount},
         nullifier::NullifierAccount,
-        storage::StorageAccount,
+        pending_release::PendingReleaseAccount,
+        program_account::ProgramAccount,
+        queue::{Queue, RingQueue},
+        recipient::RecipientPreferenceAccount,
+        storage::{StorageAccount, MT_SHARD_COUNT},
+        warden_nonce::WardenNonceAccount,
+        warden_usage::WardenUsageAccount,
     },
     types::U256,
 };
 use elusiv_computation::PartialComputation;
 pub use elusiv_test::*;
 use elusiv_types::{
-    elusiv_token, Lamports, PDAAccount, PDAOffset, WritableSignerAccount, WritableUserAccount,
+    elusiv_token, EagerAccount, Lamports, PDAAccount, PDAOffset, UserAccount,
+    WritableSignerAccount, WritableUserAccount,
 };
 use std::str::FromStr;
 
@@ -56,6 +72,15 @@ pub async fn genesis_fee(test: &mut ElusivProgramTest) -> ProgramFee {
 pub async fn setup_initial_pdas(test: &mut ElusivProgramTest) {
     let ixs = initial_single_instance_pdas(test.payer());
     test.tx_should_succeed_simple(&ixs).await;
+
+    // The accounts of MT-shard `0` are single-instance-accounts
+    for mt_shard in 1..MT_SHARD_COUNT {
+        let ix = ElusivInstruction::open_mt_shard_accounts_instruction(
+            mt_shard,
+            WritableSignerAccount(test.payer()),
+        );
+        test.ix_should_succeed_simple(ix).await;
+    }
 }
 
 pub fn initial_single_instance_pdas(payer: Pubkey) -> Vec<Instruction> {
@@ -63,9 +88,63 @@ pub fn initial_single_instance_pdas(payer: Pubkey) -> Vec<Instruction> {
         ElusivInstruction::setup_governor_account_instruction(WritableSignerAccount(payer)),
         ElusivInstruction::open_single_instance_accounts_instruction(WritableSignerAccount(payer)),
         ElusivInstruction::create_new_accounts_v1_instruction(WritableSignerAccount(payer)),
+        ElusivInstruction::open_quarantine_account_instruction(WritableSignerAccount(payer)),
+        ElusivInstruction::open_verifier_ram_account_instruction(0, WritableSignerAccount(payer)),
+        ElusivInstruction::open_upgrade_gate_account_instruction(WritableSignerAccount(payer)),
+        ElusivInstruction::open_token_registry_account_instruction(WritableSignerAccount(payer)),
+        ElusivInstruction::open_metrics_account_instruction(WritableSignerAccount(payer)),
+        ElusivInstruction::open_base_commitment_hashing_slots_account_instruction(
+            WritableSignerAccount(payer),
+        ),
+        ElusivInstruction::open_tree_config_account_instruction(WritableSignerAccount(payer)),
+        ElusivInstruction::open_verification_history_account_instruction(WritableSignerAccount(
+            payer,
+        )),
     ]
 }
 
+/// Pins the mint config of a token (the mint of an SPL token needs to exist)
+pub async fn verify_token_config(test: &mut ElusivProgramTest, token_id: u16) {
+    let mint = elusiv_token(token_id).unwrap().mint;
+    test.ix_should_succeed_simple(ElusivInstruction::verify_token_config_instruction(
+        token_id,
+        UserAccount(mint),
+    ))
+    .await;
+}
+
+pub fn recipient_preference_account(recipient: Pubkey) -> WritableUserAccount {
+    WritableUserAccount(RecipientPreferenceAccount::find_with_pubkey(recipient, None).0)
+}
+
+pub fn warden_nonce_account(warden: Pubkey) -> WritableUserAccount {
+    WritableUserAccount(WardenNonceAccount::find_with_pubkey(warden, None).0)
+}
+
+pub fn pending_release_account(nullifier_duplicate_account: Pubkey) -> WritableUserAccount {
+    WritableUserAccount(
+        PendingReleaseAccount::find_with_pubkey(nullifier_duplicate_account, None).0,
+    )
+}
+
+pub fn commitment_metadata_account(commitment: &U256) -> WritableUserAccount {
+    WritableUserAccount(
+        CommitmentMetadataAccount::find_with_pubkey(Pubkey::new_from_array(*commitment), None).0,
+    )
+}
+
+pub fn warden_usage_account(warden: Pubkey) -> WritableUserAccount {
+    WritableUserAccount(WardenUsageAccount::find_with_pubkey(warden, None).0)
+}
+
+pub fn apa_flag_account(recipient: Pubkey) -> UserAccount {
+    UserAccount(elusiv::processor::apa_flag_account(&recipient))
+}
+
+pub fn warden_attestation_account(warden: Pubkey) -> UserAccount {
+    UserAccount(elusiv::processor::warden_attestation_account(&warden))
+}
+
 pub async fn setup_fee(test: &mut ElusivProgramTest, fee_version: u32, program_fee: ProgramFee) {
     let ix = ElusivInstruction::init_new_fee_version_instruction(
         fee_version,
@@ -75,6 +154,19 @@ pub async fn setup_fee(test: &mut ElusivProgramTest, fee_version: u32, program_f
     test.ix_should_succeed_simple(ix).await;
 }
 
+/// Warps to the activation slot of the governor's scheduled fee-version (no-op if it is already active)
+pub async fn fast_forward_scheduled_fee_version(test: &mut ElusivProgramTest) {
+    let data = test.data(&GovernorAccount::find(None).0).await;
+    let governor = GovernorAccount::new_eager(data).unwrap();
+
+    if governor.scheduled_fee_version > governor.fee_version
+        && test.clock().await.slot < governor.scheduled_fee_activation_slot
+    {
+        test.warp_to_slot(governor.scheduled_fee_activation_slot)
+            .await;
+    }
+}
+
 macro_rules! setup_parent_account {
     ($fn_id: ident, $ty: ty, $instruction: ident) => {
         pub async fn $fn_id(test: &mut ElusivProgramTest) -> Vec<Pubkey> {
@@ -83,6 +175,7 @@ macro_rules! setup_parent_account {
 
             for (i, p) in pubkeys.iter().enumerate() {
                 instructions.push(ElusivInstruction::$instruction(
+                    0,
                     i as u32,
                     WritableUserAccount(*p),
                 ));
@@ -188,7 +281,7 @@ pub(crate) use pda_account;
 pub(crate) use queue;
 
 use solana_program::{instruction::Instruction, pubkey::Pubkey};
-use solana_program_test::processor;
+use solana_program_test::{processor, tokio, BanksClient};
 use spl_associated_token_account::instruction::create_associated_token_account;
 
 macro_rules! parent_account {
@@ -270,3 +363,218 @@ pub async fn enable_program_token_account<A: PDAAccount>(
     );
     test.process_transaction(&[ix], &[]).await.unwrap();
 }
+
+#[derive(Debug, Default, Clone)]
+pub struct SimulatedWardenStats {
+    pub base_commitments_finalized: usize,
+    pub commitment_batches_initialized: usize,
+    pub commitment_finalization_ixs: usize,
+    pub compute_ixs: usize,
+    pub failed_txs: usize,
+}
+
+/// A warden running the commitment hashing pipeline (poll, init, compute, finalize)
+///
+/// # Note
+///
+/// Each [`SimulatedWarden`] uses its own handle to the banks client, so multiple wardens can be spawned as async tasks racing for the same queues.
+pub struct SimulatedWarden {
+    pub actor: Actor,
+    pub stats: SimulatedWardenStats,
+
+    banks_client: BanksClient,
+    storage_accounts: Vec<Pubkey>,
+    metadata_accounts: Vec<Pubkey>,
+    base_commitment_hash_accounts: Vec<u32>,
+}
+
+impl SimulatedWarden {
+    /// Requires the [`StorageAccount`] and [`MetadataAccount`] to be setup
+    pub async fn new(test: &mut ElusivProgramTest) -> Self {
+        let actor = test.new_actor().await;
+        let storage_accounts = storage_accounts(test).await;
+        let metadata_accounts = metadata_accounts(test).await;
+
+        Self {
+            actor,
+            stats: SimulatedWardenStats::default(),
+            banks_client: test.banks_client(),
+            storage_accounts,
+            metadata_accounts,
+            base_commitment_hash_accounts: Vec::new(),
+        }
+    }
+
+    /// Also polls the [`BaseCommitmentHashingAccount`]s with the `hash_account_indices`
+    pub fn with_base_commitment_hash_accounts(mut self, hash_account_indices: &[u32]) -> Self {
+        self.base_commitment_hash_accounts = hash_account_indices.to_vec();
+        self
+    }
+
+    pub fn spawn(self, max_steps: usize) -> tokio::task::JoinHandle<Self> {
+        tokio::spawn(self.run(max_steps))
+    }
+
+    /// Steps until there is no work left or `max_steps` have been performed
+    pub async fn run(mut self, max_steps: usize) -> Self {
+        for _ in 0..max_steps {
+            if !self.step().await {
+                break;
+            }
+        }
+        self
+    }
+
+    /// Polls all accounts once and submits at most one transaction per account
+    /// - returns `false` if there was nothing to do
+    pub async fn step(&mut self) -> bool {
+        let mut has_work = false;
+        for hash_account_index in self.base_commitment_hash_accounts.clone() {
+            has_work |= self.step_base_commitment(hash_account_index).await;
+        }
+        has_work |= self.step_commitment().await;
+        has_work
+    }
+
+    async fn step_base_commitment(&mut self, hash_account_index: u32) -> bool {
+        let address = BaseCommitmentHashingAccount::find(Some(hash_account_index)).0;
+        let mut data = match self.data(&address).await {
+            Some(data) => data,
+            None => return false,
+        };
+        let (is_active, instruction, fee_version, fee_payer, commitment) = {
+            let account = BaseCommitmentHashingAccount::new(&mut data).unwrap();
+            (
+                account.get_is_active(),
+                account.get_instruction() as usize,
+                account.get_fee_version(),
+                Pubkey::new_from_array(account.get_fee_payer()),
+                fr_to_u256_le(&account.get_state().result()),
+            )
+        };
+
+        if !is_active {
+            return false;
+        }
+
+        if instruction < BaseCommitmentHashComputation::IX_COUNT {
+            let ixs = [
+                request_compute_units(BaseCommitmentHashComputation::COMPUTE_BUDGET_PER_IX),
+                ElusivInstruction::compute_base_commitment_hash_instruction(hash_account_index),
+            ];
+            if self.send(&ixs).await {
+                self.stats.compute_ixs += 1;
+            }
+        } else {
+            let ix = ElusivInstruction::finalize_base_commitment_hash_instruction(
+                0,
+                hash_account_index,
+                fee_version,
+                vec![],
+                WritableUserAccount(fee_payer),
+                commitment_metadata_account(&commitment),
+                warden_usage_account(fee_payer),
+            );
+            if self.send(&[ix]).await {
+                self.stats.base_commitments_finalized += 1;
+            }
+        }
+
+        true
+    }
+
+    async fn step_commitment(&mut self) -> bool {
+        let (is_active, instruction, fee_version, batching_rate) = {
+            let mut data = self
+                .data(&CommitmentHashingAccount::find(None).0)
+                .await
+                .unwrap();
+            let account = CommitmentHashingAccount::new(&mut data).unwrap();
+            (
+                account.get_is_active(),
+                account.get_instruction() as usize,
+                account.get_fee_version(),
+                account.get_batching_rate(),
+            )
+        };
+
+        if is_active {
+            if instruction < commitment_hash_computation_instructions(batching_rate).len() {
+                let ixs = [
+                    request_compute_units(COMMITMENT_HASH_COMPUTE_BUDGET),
+                    ElusivInstruction::compute_commitment_hash_instruction(
+                        0,
+                        fee_version,
+                        rand::random(),
+                        WritableSignerAccount(self.actor.pubkey),
+                        warden_usage_account(self.actor.pubkey),
+                    ),
+                ];
+                if self.send(&ixs).await {
+                    self.stats.compute_ixs += 1;
+                }
+            } else {
+                let ix = ElusivInstruction::finalize_commitment_hash_instruction(
+                    0,
+                    fee_version,
+                    &writable_user_accounts(&self.storage_accounts),
+                );
+                if self.send(&[ix]).await {
+                    self.stats.commitment_finalization_ixs += 1;
+                }
+            }
+
+            return true;
+        }
+
+        let queue_len = {
+            let mut data = self
+                .data(&CommitmentQueueAccount::find(None).0)
+                .await
+                .unwrap();
+            let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+            CommitmentQueue::new(&mut queue).len()
+        };
+
+        if queue_len == 0 {
+            return false;
+        }
+
+        let ixs = [
+            ElusivInstruction::init_commitment_hash_setup_instruction(
+                0,
+                false,
+                &user_accounts(&self.storage_accounts),
+            ),
+            ElusivInstruction::init_commitment_hash_instruction(
+                0,
+                false,
+                &writable_user_accounts(&self.metadata_accounts),
+            ),
+        ];
+        if self.send(&ixs).await {
+            self.stats.commitment_batches_initialized += 1;
+        }
+
+        true
+    }
+
+    /// Failing transactions are expected, since wardens race each other
+    async fn send(&mut self, ixs: &[Instruction]) -> bool {
+        let result =
+            process_transaction_with_payer(&mut self.banks_client, ixs, &self.actor.keypair).await;
+
+        if result.is_err() {
+            self.stats.failed_txs += 1;
+        }
+        result.is_ok()
+    }
+
+    async fn data(&mut self, address: &Pubkey) -> Option<Vec<u8>> {
+        self.banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .map(|account| account.data)
+    }
+}
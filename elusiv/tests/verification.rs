@@ -9,7 +9,10 @@ use elusiv::fields::{u64_to_u256, u64_to_u256_skip_mr};
 use elusiv::instruction::{
     ElusivInstruction, SignerAccount, UserAccount, WritableSignerAccount, WritableUserAccount,
 };
-use elusiv::processor::{program_token_account_address, FinalizeSendData, ProofRequest};
+use elusiv::processor::{
+    program_token_account_address, FinalizeSendData, ProofRequest, RecipientBinding,
+    COMPUTE_VERIFICATION_IX_COUNT, VERIFICATION_CANCELLATION_TIMEOUT,
+};
 use elusiv::proof::verifier::{
     prepare_public_inputs_instructions, proof_from_str, CombinedMillerLoop, FinalExponentiation,
     VerificationStep,
@@ -17,7 +20,9 @@ use elusiv::proof::verifier::{
 use elusiv::proof::vkey::{SendQuadraVKey, VerifyingKeyInfo};
 use elusiv::state::commitment::CommitmentQueue;
 use elusiv::state::fee::ProgramFee;
-use elusiv::state::governor::{FeeCollectorAccount, PoolAccount};
+use elusiv::state::governor::{
+    FeeCollectorAccount, PoolAccount, QuarantineAccount, PROTOCOL_VERSION,
+};
 use elusiv::state::metadata::{CommitmentMetadata, MetadataQueue};
 use elusiv::state::nullifier::{NullifierAccount, NullifierMap, NULLIFIERS_PER_ACCOUNT};
 use elusiv::state::program_account::{PDAAccount, PDAAccountData, ProgramAccount, SizedAccount};
@@ -34,9 +39,9 @@ use elusiv::types::{
     JoinSplitPublicInputs, OptionalFee, OrdU256, Proof, PublicInputs, RawProof, RawU256,
     SendPublicInputs, JOIN_SPLIT_MAX_N_ARITY, U256,
 };
-use elusiv_computation::PartialComputation;
+use elusiv_computation::{PartialComputation, MAX_COMPUTE_UNIT_LIMIT};
 use elusiv_types::tokens::Price;
-use elusiv_types::ParentAccount;
+use elusiv_types::{ChildAccountConfig, ParentAccount};
 use elusiv_utils::two_pow;
 use solana_program::instruction::Instruction;
 use solana_program::native_token::LAMPORTS_PER_SOL;
@@ -53,6 +58,7 @@ async fn start_verification_test() -> ElusivProgramTest {
     setup_storage_account(&mut test).await;
     create_merkle_tree(&mut test, 0).await;
     create_merkle_tree(&mut test, 1).await;
+    verify_token_config(&mut test, LAMPORTS_TOKEN_ID).await;
 
     test
 }
@@ -69,7 +75,7 @@ impl FullSendRequest {
     }
 
     fn update_fee_token(&mut self, fee: &ProgramFee, price: &TokenPrice) {
-        compute_fee_rec::<SendQuadraVKey, _>(&mut self.public_inputs, fee, price)
+        compute_fee_rec::<SendQuadraVKey, _>(&mut self.public_inputs, fee, price, 0)
     }
 }
 
@@ -123,6 +129,10 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                expiry: 0,
+                unlock_timestamp: 0,
+                earliest_execution_slot: 0,
+                latest_execution_slot: 0,
             }
         },
         FullSendRequest {
@@ -151,6 +161,10 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                expiry: 0,
+                unlock_timestamp: 0,
+                earliest_execution_slot: 0,
+                latest_execution_slot: 0,
             }
         },
         FullSendRequest {
@@ -179,6 +193,10 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                expiry: 0,
+                unlock_timestamp: 0,
+                earliest_execution_slot: 0,
+                latest_execution_slot: 0,
             }
         },
         FullSendRequest {
@@ -211,6 +229,10 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                expiry: 0,
+                unlock_timestamp: 0,
+                earliest_execution_slot: 0,
+                latest_execution_slot: 0,
             }
         },
     ];
@@ -258,6 +280,9 @@ impl ExtraData {
             self.is_associated_token_account,
             &self.metadata,
             &self.optional_fee,
+            0,
+            0,
+            (0, 0),
             &self.memo,
         )
     }
@@ -273,28 +298,44 @@ impl ExtraData {
     fn reference(&self) -> Pubkey {
         Pubkey::new_from_array(self.reference)
     }
+
+    fn binding(&self) -> RecipientBinding {
+        RecipientBinding {
+            iv: self.iv,
+            encrypted_owner: self.encrypted_owner,
+            transaction_reference: self.reference,
+            memo: self.memo.clone(),
+        }
+    }
 }
 
 async fn init_verification_simple(
     proof: &Proof,
     public_inputs: &SendPublicInputs,
-    identifier: U256,
+    extra_data: &ExtraData,
     test: &mut ElusivProgramTest,
 ) {
     let nullifier_accounts = nullifier_accounts(test, 0).await;
 
     test.tx_should_succeed_simple(&[
         ElusivInstruction::init_verification_instruction(
+            PROTOCOL_VERSION,
             0,
             SendQuadraVKey::VKEY_ID,
             [0, 1],
             ProofRequest::Send(public_inputs.clone()),
             false,
+            false,
+            extra_data.binding(),
+            ElusivOption::None,
             WritableSignerAccount(test.payer()),
             WritableUserAccount(public_inputs.join_split.nullifier_duplicate_pda().0),
-            UserAccount(Pubkey::new_from_array(identifier)),
+            UserAccount(extra_data.recipient()),
+            UserAccount(extra_data.identifier()),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
+            UserAccount(system_program::id()),
+            warden_nonce_account(test.payer()),
         ),
         ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, test.payer()),
         ElusivInstruction::init_verification_proof_instruction(
@@ -391,7 +432,7 @@ async fn insert_nullifier_hashes(
         // Modify the child accounts locally
         let mut maps: Vec<_> = nullifier_accounts_data
             .iter_mut()
-            .map(|data| NullifierMap::new(&mut data[1..]))
+            .map(|data| NullifierMap::new(&mut data[ChildAccountConfig::SIZE..]))
             .collect();
 
         while let Some(nullifier_hash) = nullifier_hashes.pop() {
@@ -437,9 +478,24 @@ fn merge(a: &[Instruction], b: &[&Instruction]) -> Vec<Instruction> {
     a
 }
 
+/// Processes a transaction of compute-verification-instructions, each of which has to stay within its compute budget
+async fn compute_verification_should_succeed(
+    test: &mut ElusivProgramTest,
+    instructions: &[Instruction],
+) {
+    let compute_units_per_ix =
+        MAX_COMPUTE_UNIT_LIMIT as u64 / (COMPUTE_VERIFICATION_IX_COUNT as u64 - 2);
+
+    let report = test
+        .tx_should_succeed_with_max_cu_simple(instructions, MAX_COMPUTE_UNIT_LIMIT as u64)
+        .await;
+    assert!(report.max_instruction_units() <= compute_units_per_ix);
+}
+
 #[tokio::test]
 async fn test_init_proof_signers() {
     let mut test = start_verification_test().await;
+    let extra_data = ExtraData::default();
     let warden = test.new_actor().await;
     let warden2 = test.new_actor().await;
     let nullifier_accounts = nullifier_accounts(&mut test, 0).await;
@@ -474,16 +530,23 @@ async fn test_init_proof_signers() {
 
     test.ix_should_succeed(
         ElusivInstruction::init_verification_instruction(
+            PROTOCOL_VERSION,
             0,
             SendQuadraVKey::VKEY_ID,
             [0, 1],
             ProofRequest::Send(request.public_inputs.clone()),
             false,
+            false,
+            extra_data.binding(),
+            ElusivOption::None,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(nullifier_duplicate_account),
-            UserAccount(Pubkey::new_unique()),
+            UserAccount(extra_data.recipient()),
+            UserAccount(extra_data.identifier()),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
+            UserAccount(system_program::id()),
+            warden_nonce_account(warden.pubkey),
         ),
         &[&warden.keypair],
     )
@@ -551,6 +614,7 @@ async fn test_init_proof_signers() {
 #[tokio::test]
 async fn test_init_proof_lamports() {
     let mut test = start_verification_test().await;
+    let extra_data = ExtraData::default();
     let warden = test.new_actor().await;
     let nullifier_accounts = nullifier_accounts(&mut test, 0).await;
     setup_vkey_account::<SendQuadraVKey>(&mut test).await;
@@ -582,16 +646,23 @@ async fn test_init_proof_lamports() {
             }
 
             ElusivInstruction::init_verification_instruction(
+                PROTOCOL_VERSION,
                 v_index,
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs),
                 skip_nullifier_pda,
+                false,
+                extra_data.binding(),
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
-                UserAccount(Pubkey::new_unique()),
+                UserAccount(extra_data.recipient()),
+                UserAccount(extra_data.identifier()),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
+                UserAccount(system_program::id()),
+                warden_nonce_account(warden.pubkey),
             )
         };
 
@@ -708,7 +779,9 @@ async fn test_init_proof_lamports() {
 #[tokio::test]
 async fn test_init_proof_token() {
     let mut test = start_verification_test().await;
+    let extra_data = ExtraData::default();
     test.create_spl_token(USDC_TOKEN_ID).await;
+    verify_token_config(&mut test, USDC_TOKEN_ID).await;
     enable_program_token_account::<PoolAccount>(&mut test, USDC_TOKEN_ID, None).await;
     enable_program_token_account::<FeeCollectorAccount>(&mut test, USDC_TOKEN_ID, None).await;
     setup_vkey_account::<SendQuadraVKey>(&mut test).await;
@@ -765,16 +838,23 @@ async fn test_init_proof_token() {
 
     test.ix_should_succeed(
         ElusivInstruction::init_verification_instruction(
+            PROTOCOL_VERSION,
             0,
             SendQuadraVKey::VKEY_ID,
             [0, 1],
             ProofRequest::Send(request.public_inputs.clone()),
             false,
+            false,
+            extra_data.binding(),
+            ElusivOption::None,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(nullifier_duplicate_account),
-            UserAccount(Pubkey::new_unique()),
+            UserAccount(extra_data.recipient()),
+            UserAccount(extra_data.identifier()),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
+            UserAccount(system_program::id()),
+            warden_nonce_account(warden.pubkey),
         ),
         &[&warden.keypair],
     )
@@ -886,16 +966,23 @@ async fn test_finalize_proof_lamports() {
     test.tx_should_succeed(
         &[
             ElusivInstruction::init_verification_instruction(
+                PROTOCOL_VERSION,
                 0,
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs.clone()),
                 false,
+                false,
+                extra_data.binding(),
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
-                UserAccount(Pubkey::new_from_array(extra_data.identifier)),
+                UserAccount(extra_data.recipient()),
+                UserAccount(extra_data.identifier()),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
+                UserAccount(system_program::id()),
+                warden_nonce_account(warden.pubkey),
             ),
             ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, warden.pubkey),
             ElusivInstruction::init_verification_proof_instruction(
@@ -958,11 +1045,17 @@ async fn test_finalize_proof_lamports() {
         );
     let finalize_verification_transfer_lamports_instruction =
         ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+            0,
             0,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(recipient),
             WritableUserAccount(optional_fee_collector.pubkey),
             WritableUserAccount(nullifier_duplicate_account),
+            recipient_preference_account(recipient),
+            pending_release_account(nullifier_duplicate_account),
+            warden_usage_account(warden.pubkey),
+            apa_flag_account(recipient),
+            warden_attestation_account(warden.pubkey),
         );
 
     // IMPORTANT: Pool already contains subvention (so we airdrop commitment_hash_fee - subvention)
@@ -1060,6 +1153,7 @@ async fn test_finalize_proof_lamports() {
 async fn test_finalize_proof_token() {
     let mut test = start_verification_test().await;
     test.create_spl_token(USDC_TOKEN_ID).await;
+    verify_token_config(&mut test, USDC_TOKEN_ID).await;
     enable_program_token_account::<PoolAccount>(&mut test, USDC_TOKEN_ID, None).await;
     enable_program_token_account::<FeeCollectorAccount>(&mut test, USDC_TOKEN_ID, None).await;
     setup_vkey_account::<SendQuadraVKey>(&mut test).await;
@@ -1141,6 +1235,8 @@ async fn test_finalize_proof_token() {
     let nullifier_duplicate_account_rent = test.rent(PDAAccountData::SIZE).await;
 
     let pool_account = program_token_account_address::<PoolAccount>(USDC_TOKEN_ID, None).unwrap();
+    let quarantine_account =
+        program_token_account_address::<QuarantineAccount>(USDC_TOKEN_ID, None).unwrap();
     let fee_collector_account =
         program_token_account_address::<FeeCollectorAccount>(USDC_TOKEN_ID, None).unwrap();
 
@@ -1159,16 +1255,23 @@ async fn test_finalize_proof_token() {
     test.tx_should_succeed(
         &[
             ElusivInstruction::init_verification_instruction(
+                PROTOCOL_VERSION,
                 0,
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs.clone()),
                 false,
+                false,
+                extra_data.binding(),
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
-                UserAccount(Pubkey::new_from_array(extra_data.identifier)),
+                UserAccount(extra_data.recipient()),
+                UserAccount(extra_data.identifier()),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
+                UserAccount(system_program::id()),
+                warden_nonce_account(warden.pubkey),
             ),
             ElusivInstruction::init_verification_transfer_fee_instruction(
                 0,
@@ -1231,6 +1334,7 @@ async fn test_finalize_proof_token() {
         );
     let finalize_verification_transfer_token_instruction =
         ElusivInstruction::finalize_verification_transfer_token_instruction(
+            0,
             0,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
@@ -1241,6 +1345,11 @@ async fn test_finalize_proof_token() {
             WritableUserAccount(optional_fee_collector.get_token_account(USDC_TOKEN_ID)),
             WritableUserAccount(nullifier_duplicate_account),
             UserAccount(spl_token::id()),
+            recipient_preference_account(recipient.pubkey),
+            WritableUserAccount(quarantine_account),
+            warden_usage_account(warden.pubkey),
+            apa_flag_account(recipient_token_account),
+            warden_attestation_account(warden.pubkey),
         );
 
     // IMPORTANT: Pool already contains subvention (so we airdrop commitment_hash_fee - subvention)
@@ -1373,16 +1482,23 @@ async fn test_finalize_proof_skip_nullifier_pda() {
 
         [
             ElusivInstruction::init_verification_instruction(
+                PROTOCOL_VERSION,
                 v_index,
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs.clone()),
                 skip_nullifier_pda,
+                false,
+                extra_data.binding(),
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
-                UserAccount(Pubkey::new_from_array(extra_data.identifier)),
+                UserAccount(extra_data.recipient()),
+                UserAccount(extra_data.identifier()),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
+                UserAccount(system_program::id()),
+                warden_nonce_account(warden.pubkey),
             ),
             ElusivInstruction::init_verification_transfer_fee_sol_instruction(
                 v_index,
@@ -1441,11 +1557,17 @@ async fn test_finalize_proof_skip_nullifier_pda() {
                 &writable_user_accounts(&[nullifier_accounts[0]]),
             ),
             ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+                0,
                 v_index,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(recipient.pubkey),
                 WritableUserAccount(Pubkey::new_unique()),
                 WritableUserAccount(nullifier_duplicate_account),
+                recipient_preference_account(recipient.pubkey),
+                pending_release_account(nullifier_duplicate_account),
+                warden_usage_account(warden.pubkey),
+                apa_flag_account(recipient.pubkey),
+                warden_attestation_account(warden.pubkey),
             ),
         ];
 
@@ -1518,16 +1640,23 @@ async fn test_finalize_proof_commitment_index() {
 
     let init_instructions = [
         ElusivInstruction::init_verification_instruction(
+            PROTOCOL_VERSION,
             0,
             SendQuadraVKey::VKEY_ID,
             [0, 1],
             ProofRequest::Send(request.public_inputs.clone()),
             false,
+            false,
+            extra_data.binding(),
+            ElusivOption::None,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(nullifier_duplicate_account),
-            UserAccount(Pubkey::new_from_array(extra_data.identifier)),
+            UserAccount(extra_data.recipient()),
+            UserAccount(extra_data.identifier()),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
+            UserAccount(system_program::id()),
+            warden_nonce_account(warden.pubkey),
         ),
         ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, warden.pubkey),
         ElusivInstruction::init_verification_proof_instruction(
@@ -1566,11 +1695,17 @@ async fn test_finalize_proof_commitment_index() {
                 &writable_user_accounts(&[nullifier_accounts[0]]),
             ),
             ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+                0,
                 0,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(recipient.pubkey),
                 WritableUserAccount(Pubkey::new_unique()),
                 WritableUserAccount(nullifier_duplicate_account),
+                recipient_preference_account(recipient.pubkey),
+                pending_release_account(nullifier_duplicate_account),
+                warden_usage_account(warden.pubkey),
+                apa_flag_account(recipient.pubkey),
+                warden_attestation_account(warden.pubkey),
             ),
         ]
     };
@@ -1604,6 +1739,7 @@ async fn test_finalize_proof_commitment_index() {
 async fn test_associated_token_account() {
     let mut test = start_verification_test().await;
     test.create_spl_token(USDC_TOKEN_ID).await;
+    verify_token_config(&mut test, USDC_TOKEN_ID).await;
     enable_program_token_account::<PoolAccount>(&mut test, USDC_TOKEN_ID, None).await;
     enable_program_token_account::<FeeCollectorAccount>(&mut test, USDC_TOKEN_ID, None).await;
     setup_vkey_account::<SendQuadraVKey>(&mut test).await;
@@ -1667,22 +1803,31 @@ async fn test_associated_token_account() {
         .await;
 
     let pool_account = program_token_account_address::<PoolAccount>(USDC_TOKEN_ID, None).unwrap();
+    let quarantine_account =
+        program_token_account_address::<QuarantineAccount>(USDC_TOKEN_ID, None).unwrap();
     let fee_collector_account =
         program_token_account_address::<FeeCollectorAccount>(USDC_TOKEN_ID, None).unwrap();
     test.airdrop(&fee_collector_account, subvention).await;
 
     test.ix_should_succeed(
         ElusivInstruction::init_verification_instruction(
+            PROTOCOL_VERSION,
             0,
             SendQuadraVKey::VKEY_ID,
             [0, 1],
             ProofRequest::Send(request.clone().public_inputs),
             false,
+            false,
+            extra_data.binding(),
+            ElusivOption::None,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(nullifier_duplicate_account),
-            UserAccount(Pubkey::new_from_array(extra_data.identifier)),
+            UserAccount(extra_data.recipient()),
+            UserAccount(extra_data.identifier()),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
+            UserAccount(system_program::id()),
+            warden_nonce_account(warden.pubkey),
         ),
         &[&warden.keypair],
     )
@@ -1744,6 +1889,7 @@ async fn test_associated_token_account() {
                 &writable_user_accounts(&[nullifier_accounts[0]]),
             ),
             ElusivInstruction::finalize_verification_transfer_token_instruction(
+                0,
                 0,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
@@ -1754,6 +1900,11 @@ async fn test_associated_token_account() {
                 WritableUserAccount(Pubkey::new_unique()),
                 WritableUserAccount(nullifier_duplicate_account),
                 UserAccount(mint),
+                recipient_preference_account(recipient_wallet),
+                WritableUserAccount(quarantine_account),
+                warden_usage_account(warden.pubkey),
+                apa_flag_account(recipient_wallet),
+                warden_attestation_account(warden.pubkey),
             ),
         ]
     };
@@ -1867,6 +2018,7 @@ async fn test_finalize_proof_failure_token() {
 #[tokio::test]
 async fn test_compute_proof_verifcation_invalid_proof() {
     let mut test = start_verification_test().await;
+    let extra_data = ExtraData::default();
     let (_, vkey_sub_account) = setup_vkey_account::<SendQuadraVKey>(&mut test).await;
     let warden = test.new_actor().await;
     let nullifier_accounts = nullifier_accounts(&mut test, 0).await;
@@ -1900,16 +2052,23 @@ async fn test_compute_proof_verifcation_invalid_proof() {
     test.tx_should_succeed(
         &[
             ElusivInstruction::init_verification_instruction(
+                PROTOCOL_VERSION,
                 0,
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs.clone()),
                 false,
+                false,
+                extra_data.binding(),
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
-                UserAccount(Pubkey::new_unique()),
+                UserAccount(extra_data.recipient()),
+                UserAccount(extra_data.identifier()),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
+                UserAccount(system_program::id()),
+                warden_nonce_account(warden.pubkey),
             ),
             ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, warden.pubkey),
             ElusivInstruction::init_verification_proof_instruction(
@@ -1928,30 +2087,35 @@ async fn test_compute_proof_verifcation_invalid_proof() {
         ElusivInstruction::compute_verification_instruction(
             0,
             SendQuadraVKey::VKEY_ID,
+            0,
             UserAccount(warden.pubkey),
             &[UserAccount(vkey_sub_account)],
         ),
         ElusivInstruction::compute_verification_instruction(
             0,
             SendQuadraVKey::VKEY_ID,
+            0,
             UserAccount(warden.pubkey),
             &[UserAccount(vkey_sub_account)],
         ),
         ElusivInstruction::compute_verification_instruction(
             0,
             SendQuadraVKey::VKEY_ID,
+            0,
             UserAccount(warden.pubkey),
             &[UserAccount(vkey_sub_account)],
         ),
         ElusivInstruction::compute_verification_instruction(
             0,
             SendQuadraVKey::VKEY_ID,
+            0,
             UserAccount(warden.pubkey),
             &[UserAccount(vkey_sub_account)],
         ),
         ElusivInstruction::compute_verification_instruction(
             0,
             SendQuadraVKey::VKEY_ID,
+            0,
             UserAccount(warden.pubkey),
             &[UserAccount(vkey_sub_account)],
         ),
@@ -1959,7 +2123,7 @@ async fn test_compute_proof_verifcation_invalid_proof() {
 
     // Input preparation
     for _ in 0..input_preparation_tx_count {
-        test.tx_should_succeed_simple(&instructions).await;
+        compute_verification_should_succeed(&mut test, &instructions).await;
     }
 
     pda_account!(
@@ -1974,7 +2138,7 @@ async fn test_compute_proof_verifcation_invalid_proof() {
 
     // Combined miller loop
     for _ in 0..CombinedMillerLoop::TX_COUNT {
-        test.tx_should_succeed_simple(&instructions).await;
+        compute_verification_should_succeed(&mut test, &instructions).await;
     }
 
     pda_account!(
@@ -1989,7 +2153,7 @@ async fn test_compute_proof_verifcation_invalid_proof() {
 
     // Final exponentiation
     for _ in 0..FinalExponentiation::TX_COUNT {
-        test.tx_should_succeed_simple(&instructions).await;
+        compute_verification_should_succeed(&mut test, &instructions).await;
     }
 
     pda_account!(
@@ -2003,6 +2167,115 @@ async fn test_compute_proof_verifcation_invalid_proof() {
     assert_eq!(v_acc.get_step(), VerificationStep::FinalExponentiation);
 }
 
+#[tokio::test]
+async fn test_cancel_verification() {
+    let mut test = start_verification_test().await;
+    let extra_data = ExtraData::default();
+    let warden = test.new_actor().await;
+    let nullifier_accounts = nullifier_accounts(&mut test, 0).await;
+    setup_vkey_account::<SendQuadraVKey>(&mut test).await;
+
+    let fee = genesis_fee(&mut test).await;
+    let mut request = send_request(0);
+    request.public_inputs.hashed_inputs = extra_data.hash();
+    request.update_fee_lamports(&fee);
+
+    let pool = PoolAccount::find(None).0;
+    let fee_collector = FeeCollectorAccount::find(None).0;
+    let nullifier_duplicate_account = request.public_inputs.join_split.nullifier_duplicate_pda().0;
+    let verification_account = VerificationAccount::find_with_pubkey(warden.pubkey, Some(0)).0;
+
+    let subvention = fee.proof_subvention;
+    let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
+    let rent =
+        test.rent(VerificationAccount::SIZE).await.0 + test.rent(PDAAccountData::SIZE).await.0;
+    warden
+        .airdrop(LAMPORTS_TOKEN_ID, rent + commitment_hash_fee.0, &mut test)
+        .await;
+    test.airdrop_lamports(&fee_collector, subvention.0).await;
+
+    test.tx_should_succeed(
+        &[
+            ElusivInstruction::init_verification_instruction(
+                PROTOCOL_VERSION,
+                0,
+                SendQuadraVKey::VKEY_ID,
+                [0, 1],
+                ProofRequest::Send(request.public_inputs.clone()),
+                false,
+                false,
+                extra_data.binding(),
+                ElusivOption::None,
+                WritableSignerAccount(warden.pubkey),
+                WritableUserAccount(nullifier_duplicate_account),
+                UserAccount(extra_data.recipient()),
+                UserAccount(extra_data.identifier()),
+                &user_accounts(&[nullifier_accounts[0]]),
+                &[],
+                UserAccount(system_program::id()),
+                warden_nonce_account(warden.pubkey),
+            ),
+            ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, warden.pubkey),
+            ElusivInstruction::init_verification_proof_instruction(
+                0,
+                request.proof,
+                SignerAccount(warden.pubkey),
+            ),
+        ],
+        &[&warden.keypair],
+    )
+    .await;
+    assert_eq!(0, warden.lamports(&mut test).await);
+
+    let cancel_verification_instruction = |fee_payer: Pubkey| {
+        ElusivInstruction::cancel_verification_instruction(
+            0,
+            WritableSignerAccount(fee_payer),
+            WritableUserAccount(pool),
+            WritableUserAccount(fee_collector),
+            WritableUserAccount(nullifier_duplicate_account),
+            WritableUserAccount(Pubkey::new_unique()),
+            UserAccount(system_program::id()),
+        )
+    };
+
+    // Timeout not reached
+    test.ix_should_fail(
+        cancel_verification_instruction(warden.pubkey),
+        &[&warden.keypair],
+    )
+    .await;
+
+    test.warp_slots(VERIFICATION_CANCELLATION_TIMEOUT).await;
+
+    // Invalid fee_payer
+    test.ix_should_fail_simple(cancel_verification_instruction(test.payer()))
+        .await;
+
+    test.ix_should_succeed(
+        cancel_verification_instruction(warden.pubkey),
+        &[&warden.keypair],
+    )
+    .await;
+
+    assert!(test.account_does_not_exist(&verification_account).await);
+    assert!(
+        test.account_does_not_exist(&nullifier_duplicate_account)
+            .await
+    );
+    assert_eq!(
+        rent + commitment_hash_fee.0,
+        warden.lamports(&mut test).await
+    );
+    assert_eq!(
+        subvention.0,
+        test.pda_lamports(&fee_collector, FeeCollectorAccount::SIZE)
+            .await
+            .0
+    );
+    assert_eq!(0, test.pda_lamports(&pool, PoolAccount::SIZE).await.0);
+}
+
 #[tokio::test]
 async fn test_enforced_finalization_order() {
     let mut test = start_verification_test().await;
@@ -2024,7 +2297,7 @@ async fn test_enforced_finalization_order() {
     init_verification_simple(
         &request.proof,
         &request.public_inputs,
-        extra_data.identifier,
+        &extra_data,
         &mut test,
     )
     .await;
@@ -2054,11 +2327,17 @@ async fn test_enforced_finalization_order() {
         );
     let finalize_verification_transfer_lamports_instruction =
         ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+            0,
             0,
             WritableSignerAccount(test.payer()),
             WritableUserAccount(extra_data.recipient()),
             WritableUserAccount(Pubkey::new_unique()),
             WritableUserAccount(nullifier_duplicate_account),
+            recipient_preference_account(extra_data.recipient()),
+            pending_release_account(nullifier_duplicate_account),
+            warden_usage_account(test.payer()),
+            apa_flag_account(extra_data.recipient()),
+            warden_attestation_account(test.payer()),
         );
 
     set_verification_state(test.payer(), 0, VerificationState::ProofSetup, &mut test).await;
@@ -2123,6 +2402,10 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
         recipient_is_associated_token_account: false,
         hashed_inputs: extra_data.hash(),
         solana_pay_transfer: false,
+        expiry: 0,
+        unlock_timestamp: 0,
+        earliest_execution_slot: 0,
+        latest_execution_slot: 0,
     };
     compute_fee_rec_lamports::<SendQuadraVKey, _>(
         &mut public_inputs,
@@ -2137,7 +2420,7 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
         .await;
     test.airdrop_lamports(&pool, LAMPORTS_PER_SOL * 1000).await;
 
-    init_verification_simple(&proof, &public_inputs, extra_data.identifier, &mut test).await;
+    init_verification_simple(&proof, &public_inputs, &extra_data, &mut test).await;
     skip_computation(test.payer(), 0, true, &mut test).await;
     set_verification_state(test.payer(), 0, VerificationState::ProofSetup, &mut test).await;
 
@@ -2200,11 +2483,17 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
 
     instructions.push(
         ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+            0,
             0,
             WritableSignerAccount(test.payer()),
             WritableUserAccount(recipient),
             WritableUserAccount(Pubkey::new_unique()),
             WritableUserAccount(nullifier_duplicate_account),
+            recipient_preference_account(recipient),
+            pending_release_account(nullifier_duplicate_account),
+            warden_usage_account(test.payer()),
+            apa_flag_account(recipient),
+            warden_attestation_account(test.payer()),
         ),
     );
 
@@ -2254,11 +2543,17 @@ async fn finalize_instructions(
             &writable_user_accounts(&[nullifier_accounts[0]]),
         ),
         ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+            0,
             0,
             WritableSignerAccount(*signer),
             WritableUserAccount(extra_data.recipient()),
             WritableUserAccount(Pubkey::new_unique()),
             WritableUserAccount(request.public_inputs.join_split.nullifier_duplicate_pda().0),
+            recipient_preference_account(extra_data.recipient()),
+            pending_release_account(request.public_inputs.join_split.nullifier_duplicate_pda().0),
+            warden_usage_account(*signer),
+            apa_flag_account(extra_data.recipient()),
+            warden_attestation_account(*signer),
         ),
     ]
 }
@@ -2286,7 +2581,7 @@ async fn test_isolated_memo() {
     init_verification_simple(
         &request.proof,
         &request.public_inputs,
-        extra_data.identifier,
+        &extra_data,
         &mut test,
     )
     .await;
@@ -2366,7 +2661,7 @@ async fn test_solana_pay_lamports() {
     init_verification_simple(
         &request.proof,
         &request.public_inputs,
-        extra_data.identifier,
+        &extra_data,
         &mut test,
     )
     .await;
@@ -2442,7 +2737,7 @@ async fn test_solana_pay_lamports_with_memo() {
     init_verification_simple(
         &request.proof,
         &request.public_inputs,
-        extra_data.identifier,
+        &extra_data,
         &mut test,
     )
     .await;
@@ -2594,6 +2889,7 @@ async fn test_solana_pay_lamports_with_memo() {
 async fn test_solana_pay_tokens() {
     let mut test = start_verification_test().await;
     test.create_spl_token(USDC_TOKEN_ID).await;
+    verify_token_config(&mut test, USDC_TOKEN_ID).await;
     enable_program_token_account::<PoolAccount>(&mut test, USDC_TOKEN_ID, None).await;
     enable_program_token_account::<FeeCollectorAccount>(&mut test, USDC_TOKEN_ID, None).await;
     setup_vkey_account::<SendQuadraVKey>(&mut test).await;
@@ -2640,6 +2936,8 @@ async fn test_solana_pay_tokens() {
 
     let nullifier_duplicate_account = request.public_inputs.join_split.nullifier_duplicate_pda().0;
     let pool_account = program_token_account_address::<PoolAccount>(USDC_TOKEN_ID, None).unwrap();
+    let quarantine_account =
+        program_token_account_address::<QuarantineAccount>(USDC_TOKEN_ID, None).unwrap();
     let fee_collector_account =
         program_token_account_address::<FeeCollectorAccount>(USDC_TOKEN_ID, None).unwrap();
 
@@ -2655,16 +2953,23 @@ async fn test_solana_pay_tokens() {
     test.tx_should_succeed(
         &[
             ElusivInstruction::init_verification_instruction(
+                PROTOCOL_VERSION,
                 0,
                 SendQuadraVKey::VKEY_ID,
                 [0, 1],
                 ProofRequest::Send(request.public_inputs.clone()),
                 false,
+                false,
+                extra_data.binding(),
+                ElusivOption::None,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
-                UserAccount(Pubkey::new_from_array(extra_data.identifier)),
+                UserAccount(extra_data.recipient()),
+                UserAccount(extra_data.identifier()),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
+                UserAccount(system_program::id()),
+                warden_nonce_account(warden.pubkey),
             ),
             ElusivInstruction::init_verification_transfer_fee_instruction(
                 0,
@@ -2711,6 +3016,7 @@ async fn test_solana_pay_tokens() {
             &writable_user_accounts(&[nullifier_accounts[0]]),
         ),
         ElusivInstruction::finalize_verification_transfer_token_instruction(
+            0,
             0,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
@@ -2721,6 +3027,11 @@ async fn test_solana_pay_tokens() {
             WritableUserAccount(Pubkey::new_unique()),
             WritableUserAccount(nullifier_duplicate_account),
             UserAccount(spl_token::id()),
+            recipient_preference_account(recipient.pubkey),
+            WritableUserAccount(quarantine_account),
+            warden_usage_account(warden.pubkey),
+            apa_flag_account(recipient_token_account),
+            warden_attestation_account(warden.pubkey),
         ),
     ];
 
@@ -11,6 +11,8 @@ const MAP_ATTR: &str = "map";
 
 const RESERVED_ATTR_IDENTS: [&str; 4] = [ACC_ATTR, SYS_ATTR, PDA_ATTR, MAP_ATTR];
 
+const DEPRECATED_SINCE_ATTR: &str = "deprecated_since";
+
 enum AttrType {
     Docs,
     Any,
@@ -24,6 +26,7 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
     let mut functions = quote!();
     let mut abi_functions = quote!();
     let mut variant_indices = quote!();
+    let mut variant_names = quote!();
 
     if let syn::Data::Enum(e) = &ast.data {
         for (var_index, var) in e.variants.clone().iter().enumerate() {
@@ -39,6 +42,9 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                 pub const #var_index_name: u8 = #var_index;
             });
 
+            let var_name = ident.to_string();
+            variant_names.extend(quote! { #var_name, });
+
             // Processor calls
             let mut accounts = quote!();
             let mut fields = quote!();
@@ -53,6 +59,10 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
             let mut other_attrs = quote!();
             let mut current_attr_type = AttrType::Docs;
 
+            // Deprecation check and the feature-flags account appended in the abi
+            let mut deprecation = quote!();
+            let mut deprecation_account = quote!();
+
             for field in &var.fields {
                 let field_name = field.ident.clone().unwrap();
                 let ty = field.ty.clone();
@@ -65,6 +75,30 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
             for (_, attr) in var.attrs.iter().enumerate() {
                 let attr_name = attr.path.get_ident().unwrap().to_string();
 
+                // Deprecated instruction (usage: #[deprecated_since(<version>)])
+                if attr_name == DEPRECATED_SINCE_ATTR {
+                    assert!(
+                        !matches!(current_attr_type, AttrType::Account),
+                        "Invalid attribute order"
+                    );
+
+                    let version: u32 = attr
+                        .parse_args::<syn::LitInt>()
+                        .unwrap()
+                        .base10_parse()
+                        .unwrap();
+                    let ident_name = ident.to_string();
+
+                    deprecation = quote! {
+                        processor::check_deprecated_instruction(#ident_name, #version, accounts)?;
+                    };
+                    deprecation_account = quote! {
+                        accounts.push(solana_program::instruction::AccountMeta::new_readonly(processor::FEATURE_FLAGS_ACCOUNT, false));
+                    };
+
+                    continue;
+                }
+
                 // No `ElusivInstruction` specific attribute
                 if !RESERVED_ATTR_IDENTS.contains(&attr_name.as_str()) {
                     if attr_name == "doc" {
@@ -191,15 +225,31 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                         });
                     }
 
-                    // System program `AccountInfo` (usage: <name> <key = ..>)
+                    // System program `AccountInfo` (usage: <name> <key = ..> or <name> <sysvar = Clock | Instructions | Rent>)
                     SYS_ATTR => {
-                        // Check that system program pubkey is correct (for this we have a field `key` that the pubkey gets compared to)
-                        let key: TokenStream =
-                            value::<String>(&sub_attrs, "key").unwrap().parse().unwrap();
+                        let sysvar: Option<TokenStream> = value(&sub_attrs, "sysvar");
 
-                        accounts.extend(quote!{
-                            if #key != *#account.key { return Err(solana_program::program_error::ProgramError::InvalidArgument) };
-                        });
+                        let key = if let Some(sysvar) = sysvar {
+                            // Typed sysvar accounts are loaded (and their keys verified) before being passed as `SysvarAccount`
+                            let ty = quote! { elusiv_types::sysvar::#sysvar };
+
+                            accounts.extend(quote!{
+                                let #account = elusiv_types::sysvar::SysvarAccount::<#ty>::load(#account)?;
+                            });
+                            account = quote! { &#account };
+
+                            quote! { <#ty as elusiv_types::sysvar::LoadableSysvar>::ID }
+                        } else {
+                            // Check that system program pubkey is correct (for this we have a field `key` that the pubkey gets compared to)
+                            let key: TokenStream =
+                                value::<String>(&sub_attrs, "key").unwrap().parse().unwrap();
+
+                            accounts.extend(quote!{
+                                if #key != *#account.key { return Err(solana_program::program_error::ProgramError::InvalidArgument) };
+                            });
+
+                            key
+                        };
 
                         account_init.push(quote!{
                             accounts.push(solana_program::instruction::AccountMeta::#account_init_fn(#key, #is_signer));
@@ -286,15 +336,16 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                         #[allow(clippy::collapsible_else_if)]
                         if !skip_pda_verification {
                             let check_pda = if find_pda {
+                                // Existing accounts are verified with their stored bump
                                 if let Some(pda_pubkey) = pda_pubkey {
                                     quote! {
-                                        if <#ty as elusiv_types::accounts::PDAAccount>::find_with_pubkey(#pda_pubkey, #pda_offset).0 != *#account.key {
+                                        if <#ty as elusiv_types::accounts::PDAAccount>::pubkey_cached(&#account, Some(#pda_pubkey), #pda_offset)? != *#account.key {
                                             return Err(solana_program::program_error::ProgramError::InvalidArgument)
                                         }
                                     }
                                 } else {
                                     quote! {
-                                        if <#ty as elusiv_types::accounts::PDAAccount>::find(#pda_offset).0 != *#account.key {
+                                        if <#ty as elusiv_types::accounts::PDAAccount>::pubkey_cached(&#account, None, #pda_offset)? != *#account.key {
                                             return Err(solana_program::program_error::ProgramError::InvalidArgument)
                                         }
                                     }
@@ -407,6 +458,7 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                 #docs
                 #other_attrs
                 fn #fn_name(accounts: &[solana_program::account_info::AccountInfo], #fields_with_type) -> solana_program::entrypoint::ProgramResult {
+                    #deprecation
                     let mut account_info_iter = &mut accounts.iter();
                     #accounts
                     processor::#fn_name(#signature #fields)
@@ -420,6 +472,7 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                     let mut accounts = Vec::new();
 
                     #instruction_accounts
+                    #deprecation_account
                     let data = #ast_ident::#ident { #fields };
                     let data = #ast_ident::try_to_vec(&data).unwrap();
 
@@ -449,6 +502,9 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
             #[cfg(feature = "elusiv-client")]
             impl #ast_ident {
                 #abi_functions
+
+                /// The names of all variants (indexed by the variant-index)
+                pub const VARIANT_NAMES: &'static [&'static str] = &[#variant_names];
             }
 
         }
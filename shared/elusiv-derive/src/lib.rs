@@ -35,12 +35,17 @@ use pda_account::*;
 ///     - extra_attributes:
 ///         - `signer`
 ///         - `writable`
-///         - `find_pda`: does a PDA verification with a pda_offset but with unknown runtime, since no bump is supplied (used for renting new PDAs)
+///         - `find_pda`: does a PDA verification for accounts that might not be rented yet (uses the stored bump if the account holds data, otherwise `find_program_address` with unknown runtime) (used for renting new PDAs)
 ///         - `skip_pda_verification`: skips the PDA verification, resulting in an [`UnverifiedAccountInfo`] (requires the `account_info` attribute)
 ///         - `account_info`: returns an `AccountInfo` object (only relevant for PDAs)
 ///         - `include_child_accounts`: the `Type` has to implement the `crate::state::program_account::ParentAccount` trait and up to `Type::COUNT + 1` accounts can be matched (but at least 1)
 ///         - `skip_abi`: can be used to add manual pda_offsets in the abi
 ///
+/// # Deprecation
+/// - `#[deprecated_since(<version>)]` (before the account attrs) marks a legacy instruction.
+/// - Each call logs a warning and is rejected once the feature-flags account enforces deprecations since `version`.
+/// - The abi appends the feature-flags account (`processor::FEATURE_FLAGS_ACCOUNT`), checked by `processor::check_deprecated_instruction`.
+///
 /// # Other attributes
 /// - Each variant can also be equipped with any other kind of attributes (cfg or do documentation).
 /// - Documentation can either be added using the `doc` attribute or with the normal syntax.
@@ -56,7 +61,7 @@ use pda_account::*;
 ///     }
 /// }
 /// ```
-#[proc_macro_derive(ElusivInstruction, attributes(acc, sys, pda, map))]
+#[proc_macro_derive(ElusivInstruction, attributes(acc, sys, pda, map, deprecated_since))]
 pub fn elusiv_instruction(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
     impl_elusiv_instruction(&ast).into()
@@ -81,7 +86,11 @@ pub fn enum_variant_index(input: proc_macro::TokenStream) -> proc_macro::TokenSt
 }
 
 /// Derives the [`PDAAccount`] trait
-#[proc_macro_derive(PDAAccount)]
+///
+/// # Usage
+///
+/// - `#[pda_version(<version>)]` (optional) sets the layout version (`PDAAccount::VERSION`)
+#[proc_macro_derive(PDAAccount, attributes(pda_version))]
 pub fn pda_account(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
     impl_pda_account(&ast).into()
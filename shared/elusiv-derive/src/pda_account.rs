@@ -24,6 +24,18 @@ pub fn impl_pda_account(ast: &syn::DeriveInput) -> TokenStream {
     let first_pubkey: TokenStream = format!("{:?}", first_pubkey.to_bytes()).parse().unwrap();
     let ident_str = ident_str.as_str();
 
+    let version = match ast
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("pda_version"))
+    {
+        Some(attr) => {
+            let version: syn::LitInt = attr.parse_args().unwrap();
+            quote! { const VERSION: u8 = #version; }
+        }
+        None => quote!(),
+    };
+
     if let syn::Data::Struct(_s) = &ast.data {
         // TODO: The first field always has to be [`PDAAccountData`] (serialization also needs to ensure this order)
 
@@ -35,6 +47,8 @@ pub fn impl_pda_account(ast: &syn::DeriveInput) -> TokenStream {
 
                 #[cfg(feature = "elusiv-client")]
                 const IDENT: &'static str = #ident_str;
+
+                #version
             }
         }
     } else {
@@ -127,7 +127,8 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
     let mut eager_idents = quote!();
     let mut eager_defs = quote!();
     let mut eager_init = quote!();
-    let mut use_eager_type = false;
+    let mut version = None;
+    let mut resizable = false;
 
     // 'a lifetime for the `ProgramAccount` impl
     let program_account_lifetime = quote!('a);
@@ -233,9 +234,15 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                 todo!("deserialized_type")
             }
 
-            // Adds the eager type variant (IFF the 'elusiv-client' feature is active)
-            "eager_type" => {
-                use_eager_type = true;
+            // Versions the account layout (accounts with a different version are rejected by `new`)
+            // - requires an impl of `elusiv_types::accounts::VersionedAccount` (used by `migrate_from`)
+            "version" => {
+                version = Some(attr.value.clone());
+            }
+
+            // Accepts accounts grown beyond `SIZE` (the appended bytes are accessible as the `extension`)
+            "resizable" => {
+                resizable = true;
             }
 
             any => panic!("Invalid attribute '{}'", any),
@@ -409,7 +416,12 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
 
                 eager_init.extend(quote!{
                     let (#field_ident, data) = data.split_at(#size);
-                    let #field_ident = <[#ty; #len] as borsh::BorshDeserialize>::try_from_slice(#field_ident)?;
+                    // Element-wise deserialization, since borsh only deserializes arrays of `Default + Copy` types
+                    let #field_ident = #field_ident.chunks(<#ty as elusiv_types::bytes::BorshSerDeSized>::SIZE)
+                        .map(<#ty as borsh::BorshDeserialize>::try_from_slice)
+                        .collect::<Result<Vec<#ty>, std::io::Error>>()?;
+                    let #field_ident: [#ty; #len] = #field_ident.try_into()
+                        .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "Invalid array len"))?;
                 });
 
                 if use_getter {
@@ -450,40 +462,125 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
     });
     let anonymous_lifetimes = lifetimes.as_anonymous_lifetimes();
 
-    let eager_type = if use_eager_type {
-        quote! {
-            #[cfg(feature = "elusiv-client")]
-            #[derive(Debug, Clone)]
-            #[derive(borsh::BorshSerialize)]
-            #vis struct #eager_ident {
-                #eager_defs
+    let invalid_data_len = if resizable {
+        field_defs.extend(quote! {
+            extension: &'a mut [u8],
+        });
+        field_idents.extend(quote! {
+            extension,
+        });
+        fields_split.extend(quote! {
+            let extension = data;
+        });
+        fns.extend(quote! {
+            /// The bytes appended to the account by growing it beyond its base size
+            pub fn extension(&self) -> &[u8] {
+                &self.extension[..]
             }
 
-            #[cfg(feature = "elusiv-client")]
-            impl < #lifetimes > elusiv_types::accounts::EagerAccount < #program_account_lifetime > for #ident < #lifetimes > {
-                type Repr = #eager_ident;
+            pub fn extension_mut(&mut self) -> &mut [u8] {
+                &mut self.extension[..]
             }
+        });
+
+        quote! { data.len() < <Self as elusiv_types::accounts::SizedAccount>::SIZE }
+    } else {
+        quote! { data.len() != <Self as elusiv_types::accounts::SizedAccount>::SIZE }
+    };
+    let invalid_eager_data_len = if resizable {
+        quote! { data.len() < < #ident < #anonymous_lifetimes > as elusiv_types::accounts::SizedAccount>::SIZE }
+    } else {
+        quote! { data.len() != < #ident < #anonymous_lifetimes > as elusiv_types::accounts::SizedAccount>::SIZE }
+    };
+
+    let (version_attr, version_check) = match &version {
+        Some(version) => {
+            fns.extend(quote! {
+                /// Creates the account from `data` (sized for the current layout) by migrating the `previous_bytes` of a previous layout
+                pub fn migrate_from(data: & #program_account_lifetime mut [u8], previous_bytes: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
+                    if previous_bytes.len() < <elusiv_types::accounts::PDAAccountData as elusiv_types::bytes::BorshSerDeSized>::SIZE {
+                        return Err(solana_program::program_error::ProgramError::InvalidAccountData)
+                    }
 
-            #[cfg(feature = "elusiv-client")]
-            impl elusiv_types::accounts::EagerAccountRepr for #eager_ident {
-                fn new(data: Vec<u8>) -> Result<Self, std::io::Error> {
-                    if data.len() != < #ident < #anonymous_lifetimes > as elusiv_types::accounts::SizedAccount>::SIZE {
-                        return Err(std::io::Error::new(std::io::ErrorKind::Other, "Invalid account data len"))
+                    let previous = elusiv_types::accounts::PDAAccountData::new(previous_bytes)?;
+                    if previous.version >= #version || data.len() != <Self as elusiv_types::accounts::SizedAccount>::SIZE {
+                        return Err(solana_program::program_error::ProgramError::InvalidAccountData)
                     }
 
-                    #eager_init
+                    let pda_data = elusiv_types::accounts::PDAAccountData {
+                        bump_seed: previous.bump_seed,
+                        version: #version,
+                    };
+                    let mut slice = &mut data[..<elusiv_types::accounts::PDAAccountData as elusiv_types::bytes::BorshSerDeSized>::SIZE];
+                    borsh::BorshSerialize::serialize(&pda_data, &mut slice)?;
 
-                    Ok(Self { #eager_idents })
+                    let mut account = <Self as elusiv_types::accounts::ProgramAccount< #program_account_lifetime >>::new(data)?;
+                    <Self as elusiv_types::accounts::VersionedAccount< #program_account_lifetime >>::migrate(&mut account, previous.version, previous_bytes)?;
+                    Ok(account)
                 }
+            });
+
+            (
+                quote! { #[pda_version(#version)] },
+                quote! {
+                    // `PDAAccountData::version` is stored after the bump seed
+                    if data[1] != #version {
+                        return Err(solana_program::program_error::ProgramError::InvalidAccountData)
+                    }
+                },
+            )
+        }
+        None => (quote!(), quote!()),
+    };
+
+    // The eager type variant (IFF the 'elusiv-client' feature is active) is used for decoding accounts off-chain
+    let eager_type = quote! {
+        #[cfg(feature = "elusiv-client")]
+        #[derive(Debug, Clone)]
+        #[derive(borsh::BorshSerialize)]
+        #vis struct #eager_ident {
+            #eager_defs
+        }
+
+        #[cfg(feature = "elusiv-client")]
+        impl borsh::BorshDeserialize for #eager_ident {
+            fn deserialize(buf: &mut &[u8]) -> Result<Self, std::io::Error> {
+                let size = < #ident < #anonymous_lifetimes > as elusiv_types::accounts::SizedAccount>::SIZE;
+                if buf.len() < size {
+                    return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Invalid account data len"))
+                }
+
+                let (data, tail) = buf.split_at(size);
+                *buf = tail;
+
+                #eager_init
+
+                Ok(Self { #eager_idents })
+            }
+        }
+
+        #[cfg(feature = "elusiv-client")]
+        impl < #lifetimes > elusiv_types::accounts::EagerAccount < #program_account_lifetime > for #ident < #lifetimes > {
+            type Repr = #eager_ident;
+        }
+
+        #[cfg(feature = "elusiv-client")]
+        impl elusiv_types::accounts::EagerAccountRepr for #eager_ident {
+            fn new(data: Vec<u8>) -> Result<Self, std::io::Error> {
+                if #invalid_eager_data_len {
+                    return Err(std::io::Error::new(std::io::ErrorKind::Other, "Invalid account data len"))
+                }
+
+                // The `extension` of a resizable account is not part of the eager type
+                <Self as borsh::BorshDeserialize>::deserialize(&mut &data[..])
             }
         }
-    } else {
-        quote!()
     };
 
     quote! {
         #struct_attrs
         #[derive(elusiv_derive::PDAAccount)]
+        #version_attr
         #vis struct #ident < #lifetimes > {
             #field_defs
         }
@@ -496,10 +593,12 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
 
         impl < #lifetimes > elusiv_types::accounts::ProgramAccount < #program_account_lifetime > for #ident < #lifetimes > {
             fn new(data: &'a mut [u8]) -> Result<Self, solana_program::program_error::ProgramError> {
-                if data.len() != <Self as elusiv_types::accounts::SizedAccount>::SIZE {
+                if #invalid_data_len {
                     return Err(solana_program::program_error::ProgramError::InvalidAccountData)
                 }
 
+                #version_check
+
                 #fields_split
 
                 Ok(Self { #field_idents })
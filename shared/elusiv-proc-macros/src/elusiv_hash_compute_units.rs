@@ -5,8 +5,51 @@ use proc_macro2::TokenStream;
 use quote::quote;
 
 const COMPUTE_UNIT_PADDING: u32 = 20_000;
-const FULL_ROUNDS_CUS: u32 = 15411 + 17740 + 600;
-const PARTIAL_ROUNDS_CUS: u32 = 5200 + 17740 + 600;
+
+/// Round structure and costs of a Poseidon hash with a specific arity (state width `arity + 1`)
+struct PoseidonParameters {
+    full_rounds: usize,
+    partial_rounds: usize,
+    full_round_cus: u32,
+    partial_round_cus: u32,
+}
+
+impl PoseidonParameters {
+    fn new(arity: usize) -> Self {
+        match arity {
+            // Measured costs of our binary input Poseidon hash
+            2 => PoseidonParameters {
+                full_rounds: 8,
+                partial_rounds: 57,
+                full_round_cus: 15411 + 17740 + 600,
+                partial_round_cus: 5200 + 17740 + 600,
+            },
+
+            // Estimated costs: full round S-boxes scale linearly and the MDS multiplication quadratically with the width
+            4 => PoseidonParameters {
+                full_rounds: 8,
+                partial_rounds: 60,
+                full_round_cus: 25685 + 49278 + 600,
+                partial_round_cus: 5200 + 49278 + 600,
+            },
+            _ => panic!("Unsupported Poseidon arity {}", arity),
+        }
+    }
+
+    fn total_rounds(&self) -> usize {
+        self.full_rounds + self.partial_rounds
+    }
+
+    /// Full rounds are split in half before and after the partial rounds
+    fn round_cus(&self, round: usize) -> u32 {
+        let half_full_rounds = self.full_rounds / 2;
+        if !(half_full_rounds..half_full_rounds + self.partial_rounds).contains(&round) {
+            self.full_round_cus
+        } else {
+            self.partial_round_cus
+        }
+    }
+}
 
 pub fn impl_elusiv_hash_compute_units(attrs: TokenStream) -> TokenStream {
     let attrs = sub_attrs_prepare(attrs.to_string());
@@ -18,32 +61,28 @@ pub fn impl_elusiv_hash_compute_units(attrs: TokenStream) -> TokenStream {
     // Number of hashes
     let hashes: usize = attrs[1].parse().unwrap();
 
-    // Optional compute units reduction
-    let reduction: Option<u32> = if let Some(attr) = attrs.get(2) {
-        try_parse_usize(attr).map(|v| v as u32)
-    } else {
-        None
-    };
-
-    // Stub representation of our binary input Poseidon hash
-    let mut rounds = Vec::new();
-    for round in 0..65 * hashes {
-        let round = round % 65;
-
-        // Cost based on full or partial rounds
-        rounds.push(if !(4..61).contains(&round) {
-            // 8 full rounds
-            FULL_ROUNDS_CUS
+    // Optional compute units reduction and arity (binary by default)
+    let mut reduction: Option<u32> = None;
+    let mut arity = 2;
+    for attr in attrs.iter().skip(2) {
+        if let Some(value) = attr.strip_prefix("arity=") {
+            arity = try_parse_usize(value).expect("Invalid arity");
         } else {
-            // 57 partial rounds
-            PARTIAL_ROUNDS_CUS
-        });
+            reduction = try_parse_usize(attr).map(|v| v as u32);
+        }
     }
 
+    // Stub representation of the Poseidon hash
+    let poseidon = PoseidonParameters::new(arity);
+    let rounds_per_hash = poseidon.total_rounds();
+    let rounds: Vec<u32> = (0..rounds_per_hash * hashes)
+        .map(|round| poseidon.round_cus(round % rounds_per_hash))
+        .collect();
+
     let max_compute_budget = MAX_COMPUTE_UNIT_LIMIT - COMPUTE_UNIT_PADDING - reduction.unwrap_or(0);
     let result = compute_unit_optimization(rounds, max_compute_budget);
 
-    let total_rounds = (hashes * 65) as u32;
+    let total_rounds = (hashes * rounds_per_hash) as u32;
     let total_compute_units = result.total_compute_units;
     assert_eq!(result.total_rounds, total_rounds);
 
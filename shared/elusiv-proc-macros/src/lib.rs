@@ -12,13 +12,19 @@ use elusiv_hash_compute_units::impl_elusiv_hash_compute_units;
 use parse_tokens::impl_parse_tokens;
 use program_id::{impl_declare_program_id, impl_program_id};
 use repeat::impl_repeat;
-use syn::{parse_macro_input, DeriveInput};
+use syn::{parse_macro_input, DeriveInput, LitStr};
 
 /// Just-in-time mutable-byte-slice-backed serialization account
 ///
 /// # Notes
 ///
-/// Automatically also derives [`elusiv_types::PDAAccount`]
+/// - Automatically also derives [`elusiv_types::PDAAccount`]
+/// - With the `elusiv-client` feature, a read-only eager type `<Account>Eager` (owned fields) is generated for off-chain decoding (see [`elusiv_types::accounts::EagerAccount`])
+///
+/// # Usage
+///
+/// - `version: <version>` versions the layout and generates `migrate_from` (requires an impl of [`elusiv_types::accounts::VersionedAccount`])
+/// - `resizable: true` accepts accounts grown beyond their `SIZE` and generates `extension`/`extension_mut` for the appended bytes
 #[proc_macro_attribute]
 pub fn elusiv_account(
     args: proc_macro::TokenStream,
@@ -32,6 +38,8 @@ pub fn elusiv_account(
 ///
 /// # Usage
 /// - `elusiv_hash_compute_units!(<name>, <NUMBER_OF_HASHES>)`
+/// - `elusiv_hash_compute_units!(<name>, <NUMBER_OF_HASHES>, <COMPUTE_UNITS_REDUCTION>)`
+/// - `elusiv_hash_compute_units!(<name>, <NUMBER_OF_HASHES>, arity = <2 | 4>)` (binary by default, 4-ary costs are estimates)
 #[proc_macro]
 pub fn elusiv_hash_compute_units(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     impl_elusiv_hash_compute_units(input.into()).into()
@@ -58,18 +66,18 @@ pub fn elusiv_tokens(_: proc_macro::TokenStream) -> proc_macro::TokenStream {
 ///
 /// # Usage
 ///
-/// Provide the name of the program as argument.
-/// If no name is supplied, the runtime value of `CARGO_PKG_NAME` will be used as fallback.
+/// Provide the name of the program as string literal argument.
 ///
 /// # Example
 ///
 /// ```
-/// const ELUSIV_PROGRAM_ID: solana_program::pubkey::Pubkey = program_id!(elusiv);
-/// const ELUSIV_2_PROGRAM_ID: solana_program::pubkey::Pubkey = program_id!(elusiv-2);
+/// const ELUSIV_PROGRAM_ID: solana_program::pubkey::Pubkey = program_id!("elusiv");
+/// const WARDEN_NETWORK_PROGRAM_ID: solana_program::pubkey::Pubkey = program_id!("elusiv-warden-network");
 /// ```
 #[proc_macro]
 pub fn program_id(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    impl_program_id(input.to_string()).into()
+    let program_name = parse_macro_input!(input as LitStr);
+    impl_program_id(program_name.value()).into()
 }
 
 /// Parses `Id.toml` and implements [`solana_program::declare_id`]
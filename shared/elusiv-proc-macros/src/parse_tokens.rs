@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 
 const TOKEN_TOML_PATH: &str = "/Token.toml";
+const SPL_TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+const SPL_TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
 
 #[derive(Serialize, Deserialize, Debug)]
 struct Tokens {
@@ -18,6 +20,7 @@ struct Token {
     mint_devnet: String,
     active: bool,
     decimals: Option<u8>,
+    token_program: Option<String>,
     price_base_exp: Option<u8>,
     min: u64,
     max: u64,
@@ -63,6 +66,12 @@ pub fn impl_parse_tokens() -> TokenStream {
             pubkey_bytes(&token.mint)
         };
 
+        let token_program = match token.token_program.as_deref() {
+            None | Some("spl-token") => pubkey_bytes(SPL_TOKEN_PROGRAM_ID),
+            Some("spl-token-2022") => pubkey_bytes(SPL_TOKEN_2022_PROGRAM_ID),
+            Some(program) => panic!("Invalid token program '{}' for {}", program, ident),
+        };
+
         let pyth_usd_price_key = if cfg!(feature = "devnet") {
             pubkey_bytes(&token.pyth_usd_price_devnet)
         } else {
@@ -75,6 +84,7 @@ pub fn impl_parse_tokens() -> TokenStream {
                 ident: #ident,
 
                 mint: solana_program::pubkey::Pubkey::new_from_array(#mint),
+                token_program: solana_program::pubkey::Pubkey::new_from_array(#token_program),
                 decimals: #decimals,
                 price_base_exp: #price_base_exp,
                 pyth_usd_price_key: solana_program::pubkey::Pubkey::new_from_array(#pyth_usd_price_key),
@@ -10,6 +10,7 @@ use elusiv_types::{
 };
 use solana_program::program_pack::Pack;
 use solana_program::{
+    clock::Clock,
     instruction::{AccountMeta, Instruction, InstructionError},
     native_token::LAMPORTS_PER_SOL,
     program_option::COption,
@@ -18,8 +19,11 @@ use solana_program::{
 };
 use solana_program_test::*;
 use solana_sdk::{
-    account::AccountSharedData, compute_budget::ComputeBudgetInstruction, signature::Keypair,
-    signer::Signer, transaction::Transaction,
+    account::AccountSharedData,
+    compute_budget::ComputeBudgetInstruction,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
 };
 use spl_associated_token_account::instruction::create_associated_token_account;
 use std::{collections::HashMap, process::Command, str::FromStr};
@@ -98,6 +102,21 @@ impl ElusivProgramTest {
         instructions: &[Instruction],
         signing_keypairs: &[&Keypair],
     ) -> Result<(), BanksClientError> {
+        let tx = self
+            .signed_transaction(instructions, signing_keypairs)
+            .await?;
+
+        self.context
+            .banks_client
+            .process_transaction_with_preflight(tx)
+            .await
+    }
+
+    async fn signed_transaction(
+        &mut self,
+        instructions: &[Instruction],
+        signing_keypairs: &[&Keypair],
+    ) -> Result<Transaction, BanksClientError> {
         let mut signing_keypairs = signing_keypairs.to_vec();
         signing_keypairs.insert(0, &self.context.payer);
 
@@ -107,12 +126,50 @@ impl ElusivProgramTest {
         tx.try_sign(&signing_keypairs, self.context.last_blockhash)
             .or(Err(BanksClientError::ClientError("Signature failure")))?;
 
-        self.context
-            .banks_client
-            .process_transaction_with_preflight(tx)
+        Ok(tx)
+    }
+
+    /// Simulates a nonced transaction and returns its consumed compute units (the transaction is not processed)
+    pub async fn simulate_compute_units(
+        &mut self,
+        instructions: &[Instruction],
+        signing_keypairs: &[&Keypair],
+    ) -> Result<ComputeUnitReport, BanksClientError> {
+        let mut instructions = instructions.to_vec();
+        instructions[0] = nonce_instruction(instructions[0].clone());
+        let tx = self
+            .signed_transaction(&instructions, signing_keypairs)
+            .await?;
+
+        self.simulate_transaction_compute_units(tx, instructions.len())
             .await
     }
 
+    async fn simulate_transaction_compute_units(
+        &mut self,
+        tx: Transaction,
+        instruction_count: usize,
+    ) -> Result<ComputeUnitReport, BanksClientError> {
+        let simulation = self.context.banks_client.simulate_transaction(tx).await?;
+        let details = simulation
+            .simulation_details
+            .ok_or(BanksClientError::ClientError("Missing simulation details"))?;
+
+        if let Some(Err(err)) = simulation.result {
+            return Err(BanksClientError::SimulationError {
+                err,
+                logs: details.logs,
+                units_consumed: details.units_consumed,
+            });
+        }
+
+        Ok(ComputeUnitReport::from_logs(
+            details.units_consumed,
+            &details.logs,
+            instruction_count,
+        ))
+    }
+
     pub async fn process_transaction_nonced(
         &mut self,
         instructions: &[Instruction],
@@ -132,6 +189,11 @@ impl ElusivProgramTest {
         self.context.payer.pubkey()
     }
 
+    /// Returns a handle to the banks client, used by actors running concurrently to the test
+    pub fn banks_client(&self) -> BanksClient {
+        self.context.banks_client.clone()
+    }
+
     pub async fn account_does_exist(&mut self, address: &Pubkey) -> bool {
         matches!(
             self.context
@@ -186,6 +248,39 @@ impl ElusivProgramTest {
         Lamports(rent.minimum_balance(data_len))
     }
 
+    pub async fn clock(&mut self) -> Clock {
+        self.context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+    }
+
+    /// Warps the bank forward by `slots` slots
+    pub async fn warp_slots(&mut self, slots: u64) {
+        let slot = self.clock().await.slot;
+        self.warp_to_slot(slot + slots).await;
+    }
+
+    /// Warps the bank forward to `slot` (has to be larger than the current slot)
+    pub async fn warp_to_slot(&mut self, slot: u64) {
+        self.context.warp_to_slot(slot).unwrap();
+    }
+
+    /// Overrides the unix-timestamp of the [`Clock`] sysvar (until the next warp)
+    pub async fn set_clock_timestamp(&mut self, unix_timestamp: i64) {
+        let mut clock = self.clock().await;
+        clock.unix_timestamp = unix_timestamp;
+        self.context.set_sysvar(&clock);
+    }
+
+    /// Advances the unix-timestamp of the [`Clock`] sysvar by `seconds`
+    pub async fn warp_timestamp(&mut self, seconds: u64) {
+        let unix_timestamp = self.clock().await.unix_timestamp;
+        self.set_clock_timestamp(unix_timestamp + seconds as i64)
+            .await;
+    }
+
     #[allow(deprecated)]
     pub async fn lamports_per_signature(&mut self) -> Lamports {
         Lamports(
@@ -408,6 +503,61 @@ impl ElusivProgramTest {
         assert!(self.process_transaction_nonced(&[ix], &[]).await.is_ok());
     }
 
+    /// Processes a transaction that needs to succeed consuming at most `cu_limit` compute units (in total)
+    ///
+    /// # Note
+    ///
+    /// The consumed compute units are printed per instruction and returned (e.g. for asserting per-instruction limits).
+    pub async fn tx_should_succeed_with_max_cu(
+        &mut self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+        cu_limit: u64,
+    ) -> ComputeUnitReport {
+        let mut ixs = ixs.to_vec();
+        ixs[0] = nonce_instruction(ixs[0].clone());
+        let tx = self.signed_transaction(&ixs, signers).await.unwrap();
+
+        let report = self
+            .simulate_transaction_compute_units(tx.clone(), ixs.len())
+            .await
+            .unwrap();
+        println!("{report}");
+        assert!(
+            report.total <= cu_limit,
+            "Consumed {} compute units (limit: {})",
+            report.total,
+            cu_limit
+        );
+
+        assert!(self
+            .context
+            .banks_client
+            .process_transaction_with_preflight(tx)
+            .await
+            .is_ok());
+
+        report
+    }
+
+    pub async fn tx_should_succeed_with_max_cu_simple(
+        &mut self,
+        ixs: &[Instruction],
+        cu_limit: u64,
+    ) -> ComputeUnitReport {
+        self.tx_should_succeed_with_max_cu(ixs, &[], cu_limit).await
+    }
+
+    pub async fn ix_should_succeed_with_max_cu(
+        &mut self,
+        ix: Instruction,
+        signers: &[&Keypair],
+        cu_limit: u64,
+    ) -> ComputeUnitReport {
+        self.tx_should_succeed_with_max_cu(&[ix], signers, cu_limit)
+            .await
+    }
+
     pub async fn tx_should_fail(
         &mut self,
         ixs: &[Instruction],
@@ -512,6 +662,62 @@ impl ElusivProgramTest {
         self.tx_should_succeed(&ixs, &[&signer.keypair]).await;
     }
 
+    /// All account permutations of `valid_ix` should either fail cleanly or result in the same account states as `valid_ix`, afterwards the original ix should succeed
+    /// - prefix_ixs are not permuted
+    /// - each variant is processed on a fork of the accounts
+    pub async fn test_instruction_account_permutations(
+        &mut self,
+        prefix_ixs: &[Instruction],
+        valid_ix: Instruction,
+        signers: &[&Keypair],
+    ) {
+        let mut valid_ixs = prefix_ixs.to_vec();
+        valid_ixs.push(valid_ix.clone());
+        let pubkeys: Vec<Pubkey> = valid_ix.accounts.iter().map(|a| a.pubkey).collect();
+
+        let mut reference = self.fork_for_instructions(&valid_ixs).await;
+        reference.tx_should_succeed(&valid_ixs, signers).await;
+        let expected_accounts = reference.accounts(&pubkeys).await;
+
+        for ix in account_permutations(&valid_ix) {
+            let mut ixs = prefix_ixs.to_vec();
+            ixs.push(ix);
+
+            let mut fork = self.fork_for_instructions(&valid_ixs).await;
+            match fork.process_transaction_nonced(&ixs, signers).await {
+                Ok(()) => assert_eq!(
+                    fork.accounts(&pubkeys).await,
+                    expected_accounts,
+                    "Permuted accounts {:?} accepted with different results",
+                    ixs.last().unwrap().accounts
+                ),
+                Err(err) => assert!(
+                    is_instruction_error(&err),
+                    "Permuted accounts {:?} not rejected cleanly: {:?}",
+                    ixs.last().unwrap().accounts,
+                    err
+                ),
+            }
+        }
+
+        self.tx_should_succeed(&valid_ixs, signers).await;
+    }
+
+    /// Returns the lamports, data and owner of each account (`None` for non-existing accounts)
+    pub async fn accounts(&mut self, pubkeys: &[Pubkey]) -> Vec<Option<(u64, Vec<u8>, Pubkey)>> {
+        let mut accounts = Vec::new();
+        for pubkey in pubkeys {
+            let account = self
+                .context
+                .banks_client
+                .get_account(*pubkey)
+                .await
+                .unwrap();
+            accounts.push(account.map(|a| (a.lamports, a.data, a.owner)));
+        }
+        accounts
+    }
+
     pub async fn set_pda_account<A: SizedAccount + PDAAccount, F>(
         &mut self,
         program_id: &Pubkey,
@@ -655,6 +861,152 @@ impl Actor {
     }
 }
 
+/// Returns all variants of `ix` with two swapped accounts or with a (non-signer) account substituted by another account of `ix`
+pub fn account_permutations(ix: &Instruction) -> Vec<Instruction> {
+    let mut result = Vec::new();
+    let accounts = &ix.accounts;
+
+    for i in 0..accounts.len() {
+        for j in 0..accounts.len() {
+            if accounts[i].pubkey == accounts[j].pubkey {
+                continue;
+            }
+
+            // Swap
+            if i < j {
+                let mut ix = ix.clone();
+                ix.accounts.swap(i, j);
+                result.push(ix);
+            }
+
+            // Substitution (the set of signers is not changed)
+            if !accounts[i].is_signer {
+                let mut ix = ix.clone();
+                ix.accounts[i].pubkey = accounts[j].pubkey;
+                result.push(ix);
+            }
+        }
+    }
+
+    result
+}
+
+/// The compute units consumed by a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComputeUnitReport {
+    pub total: u64,
+
+    /// The compute units consumed by each instruction (`None` for builtin programs like the compute-budget program)
+    pub instructions: Vec<Option<u64>>,
+}
+
+impl ComputeUnitReport {
+    /// Parses the compute units of the top-level instructions from the program logs of a transaction
+    ///
+    /// # Note
+    ///
+    /// The compute units of an instruction include the compute units of its CPIs.
+    pub fn from_logs(total: u64, logs: &[String], instruction_count: usize) -> Self {
+        let mut instructions = vec![None; instruction_count];
+        let mut index: Option<usize> = None;
+        let mut depth = 0;
+
+        for log in logs {
+            let mut words = log.split_whitespace();
+            if words.next() != Some("Program") {
+                continue;
+            }
+
+            // Skips program logs, data and return data
+            match words.next() {
+                Some(program_id) if !program_id.ends_with(':') => {}
+                _ => continue,
+            }
+
+            match words.next() {
+                Some("invoke") => {
+                    depth = words
+                        .next()
+                        .and_then(|d| d.trim_matches(|c| c == '[' || c == ']').parse().ok())
+                        .unwrap_or(depth + 1);
+
+                    if depth == 1 {
+                        index = Some(index.map_or(0, |i| i + 1));
+                    }
+                }
+                Some("success") | Some("failed:") => depth -= 1,
+                Some("consumed") if depth == 1 => {
+                    let units = words.next().and_then(|u| u.parse().ok());
+                    if let Some(i) = index.filter(|&i| i < instruction_count) {
+                        instructions[i] = units;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            total,
+            instructions,
+        }
+    }
+
+    /// The maximum compute units consumed by a single instruction
+    pub fn max_instruction_units(&self) -> u64 {
+        self.instructions
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl std::fmt::Display for ComputeUnitReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Consumed compute units: {}", self.total)?;
+        for (i, units) in self.instructions.iter().enumerate() {
+            match units {
+                Some(units) => writeln!(f, "  #{i}: {units}")?,
+                None => writeln!(f, "  #{i}: -")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if the transaction has been rejected by an instruction (and not e.g. by a crash of the bank)
+pub fn is_instruction_error(err: &BanksClientError) -> bool {
+    matches!(
+        err,
+        BanksClientError::TransactionError(TransactionError::InstructionError(..))
+            | BanksClientError::SimulationError {
+                err: TransactionError::InstructionError(..),
+                ..
+            }
+    )
+}
+
+/// Processes a nonced transaction, paid for and signed by `payer` (instead of the test's payer)
+pub async fn process_transaction_with_payer(
+    banks_client: &mut BanksClient,
+    instructions: &[Instruction],
+    payer: &Keypair,
+) -> Result<(), BanksClientError> {
+    let mut instructions = instructions.to_vec();
+    instructions[0] = nonce_instruction(instructions[0].clone());
+
+    let blockhash = banks_client.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        blockhash,
+    );
+
+    banks_client.process_transaction_with_preflight(tx).await
+}
+
 /// Adds random nonce bytes at the end of the ix data
 /// - prevents rejection of previously failed ix times without repeated execution
 pub fn nonce_instruction(ix: Instruction) -> Instruction {
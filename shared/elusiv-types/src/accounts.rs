@@ -31,6 +31,14 @@ pub trait ChildAccount: Sized {
 
     /// Attempts to set the child-accounts [`ChildAccountConfig`]
     fn try_start_using_account(account: &AccountInfo) -> Result<(), ProgramError> {
+        Self::try_start_using_account_with_tag(account, ElusivOption::None)
+    }
+
+    /// Attempts to set the child-accounts [`ChildAccountConfig`] and binds it to a parent-account with `tag`
+    fn try_start_using_account_with_tag(
+        account: &AccountInfo,
+        tag: ElusivOption<ChildAccountTag>,
+    ) -> Result<(), ProgramError> {
         let data = &mut account.data.borrow_mut()[..];
         let (config_data, _) = split_child_account_data_mut(data)?;
         let mut config = ChildAccountConfig::try_from_slice(config_data)?;
@@ -39,6 +47,7 @@ pub trait ChildAccount: Sized {
             return Err(ProgramError::AccountAlreadyInitialized);
         }
         config.is_in_use = true;
+        config.tag = tag;
 
         let mut slice = &mut config_data[..ChildAccountConfig::SIZE];
         borsh::BorshSerialize::serialize(&config, &mut slice).unwrap();
@@ -64,6 +73,17 @@ pub fn split_child_account_data_mut(
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized)]
 pub struct ChildAccountConfig {
     pub is_in_use: bool,
+
+    /// Integrity tag, set if the child-account has been registered with it's parent-account
+    pub tag: ElusivOption<ChildAccountTag>,
+}
+
+/// Binds a [`ChildAccount`] to a specific [`ParentAccount`] and index
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct ChildAccountTag {
+    pub parent: Pubkey,
+    pub child_index: u32,
 }
 
 pub const fn child_account_size(inner_size: usize) -> usize {
@@ -128,7 +148,7 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
     ///
     /// # Notes
     ///
-    /// - All matched accounts are consumed from the iterator.
+    /// - All accounts up to (and including) the last matched account are consumed from the iterator.
     /// - The accounts need to match the order in which their pubkeys are stored.
     /// - Any account which pubkey has been previously set can be used.
     fn find_child_accounts<'c, 'd, I>(
@@ -146,10 +166,14 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
 
         let mut accounts = vec![None; Self::COUNT];
         let mut remaining_iter = account_info_iter.clone();
+        let mut scanned_count = 0;
+        let mut consumed_count = 0;
         let mut i = 0;
         while i < Self::COUNT {
             match next_account_info(account_info_iter) {
                 Ok(account) => {
+                    scanned_count += 1;
+
                     #[allow(clippy::needless_range_loop)]
                     for child_index in i..Self::COUNT {
                         match child_pubkeys[child_index] {
@@ -170,7 +194,7 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
                         }
 
                         accounts[child_index] = Some(account);
-                        next_account_info(&mut remaining_iter)?;
+                        consumed_count = scanned_count;
                         i = child_index;
 
                         break;
@@ -181,6 +205,11 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
             }
         }
 
+        // Skipped accounts in front of a match are consumed as well, so they are not parsed as the following accounts
+        for _ in 0..consumed_count {
+            next_account_info(&mut remaining_iter)?;
+        }
+
         *account_info_iter = remaining_iter;
         Ok(accounts)
     }
@@ -216,7 +245,7 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
     }
 }
 
-pub type PDAOffset = Option<u32>;
+pub use crate::pda::PDAOffset;
 
 /// A [`PDAAccount`] uses a seed, an (optional) [`Pubkey`] and a [`PDAOffset`] to derive PDAs
 pub trait PDAAccount {
@@ -229,6 +258,9 @@ pub trait PDAAccount {
     #[cfg(feature = "elusiv-client")]
     const IDENT: &'static str;
 
+    /// The layout version, stored in the [`PDAAccountData`] when opening the account
+    const VERSION: u8 = 0;
+
     fn find(offset: PDAOffset) -> (Pubkey, u8) {
         if offset.is_none() {
             return Self::FIRST_PDA;
@@ -278,17 +310,7 @@ pub trait PDAAccount {
     }
 
     fn seeds(seed: &[u8], pubkey: Option<Pubkey>, offset: PDAOffset) -> Vec<Vec<u8>> {
-        let mut seed = vec![seed.to_vec()];
-
-        if let Some(pubkey) = pubkey {
-            seed.push(pubkey.to_bytes().to_vec());
-        }
-
-        if let Some(offset) = offset {
-            seed.push(offset.to_le_bytes().to_vec());
-        }
-
-        seed
+        crate::pda::pda_seeds(seed, pubkey.map(|p| p.to_bytes()).as_ref(), offset)
     }
 
     fn signers_seeds(pubkey: Option<Pubkey>, offset: PDAOffset, bump: u8) -> Vec<Vec<u8>> {
@@ -306,8 +328,33 @@ pub trait PDAAccount {
         account.data.borrow()[0]
     }
 
+    /// Reconstructs the PDA of an account using the bump stored in its [`PDAAccountData`]
+    ///
+    /// # Note
+    ///
+    /// - uses `create_program_address` instead of the more costly `find_program_address`
+    /// - accounts without data (not yet rented) fall back to `find_program_address`
+    fn pubkey_cached(
+        account: &AccountInfo,
+        pubkey: Option<Pubkey>,
+        offset: PDAOffset,
+    ) -> Result<Pubkey, ProgramError> {
+        if account.data_is_empty() {
+            return Ok(match pubkey {
+                Some(pubkey) => Self::find_with_pubkey(pubkey, offset).0,
+                None => Self::find(offset).0,
+            });
+        }
+
+        let bump = Self::get_bump(account);
+        match pubkey {
+            Some(pubkey) => Self::create_with_pubkey(pubkey, offset, bump),
+            None => Self::create(offset, bump),
+        }
+    }
+
     fn verify_account(account: &AccountInfo, offset: PDAOffset) -> ProgramResult {
-        if Self::create(offset, Self::get_bump(account))? != *account.key {
+        if Self::pubkey_cached(account, None, offset)? != *account.key {
             return Err(ProgramError::InvalidSeeds);
         }
 
@@ -319,7 +366,7 @@ pub trait PDAAccount {
         pubkey: Pubkey,
         offset: PDAOffset,
     ) -> ProgramResult {
-        if Self::create_with_pubkey(pubkey, offset, Self::get_bump(account))? != *account.key {
+        if Self::pubkey_cached(account, Some(pubkey), offset)? != *account.key {
             return Err(ProgramError::InvalidSeeds);
         }
 
@@ -337,7 +384,7 @@ pub trait ComputationAccount: PDAAccount {
 pub struct PDAAccountData {
     pub bump_seed: u8,
 
-    /// Used for future account migrations
+    /// Layout version of the account (see [`PDAAccount::VERSION`] and [`VersionedAccount`])
     pub version: u8,
 }
 
@@ -347,6 +394,17 @@ impl PDAAccountData {
     }
 }
 
+/// A [`ProgramAccount`] with a versioned layout that can be migrated from previous layouts
+///
+/// # Note
+///
+/// - Accounts with a [`PDAAccountData::version`] other than [`PDAAccount::VERSION`] are rejected by [`ProgramAccount::new`].
+/// - The bump seed and the version are written before [`VersionedAccount::migrate`] is called.
+pub trait VersionedAccount<'a>: ProgramAccount<'a> + PDAAccount {
+    /// Writes the data of a previous layout (`previous_version`) into the current layout
+    fn migrate(&mut self, previous_version: u8, previous_bytes: &[u8]) -> ProgramResult;
+}
+
 /// A [`ProgramAccount`] that also has a eager representation
 #[cfg(feature = "elusiv-client")]
 pub trait EagerAccount<'a>: ProgramAccount<'a> {
@@ -1,5 +1,7 @@
+//! Fixed size (de-)serialization and byte utilities (`no_std` compatible)
+
+use borsh::maybestd::io;
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
 
 pub trait BorshSerDeSized: BorshSerialize + BorshDeserialize {
     const SIZE: usize;
@@ -13,14 +15,14 @@ pub trait BorshSerDeSizedEnum: BorshSerDeSized {
     fn len(variant_index: u8) -> usize;
 
     /// Deserializes an enum by reading only up to `len` bytes of the buffer
-    fn deserialize_enum(buf: &mut &[u8]) -> std::io::Result<Self> {
+    fn deserialize_enum(buf: &mut &[u8]) -> io::Result<Self> {
         let len = Self::len(buf[0]) + 1;
-        let v = Self::deserialize(&mut &buf[..std::cmp::min(len, buf.len())])?;
+        let v = Self::deserialize(&mut &buf[..core::cmp::min(len, buf.len())])?;
         Ok(v)
     }
 
     /// Deserializes an enum by reading all bytes of the buffer
-    fn deserialize_enum_full(buf: &mut &[u8]) -> std::io::Result<Self> {
+    fn deserialize_enum_full(buf: &mut &[u8]) -> io::Result<Self> {
         let len = Self::len(buf[0]) + 1;
         let v = Self::deserialize(&mut &buf[..len])?;
         *buf = &buf[Self::SIZE - len..];
@@ -53,6 +55,7 @@ impl_borsh_sized!(u64, 8);
 impl_borsh_sized!(u128, 16);
 
 impl_borsh_sized!(bool, 1);
+#[cfg(feature = "std")]
 impl_borsh_sized!(std::net::Ipv4Addr, 4);
 
 /// The advantage of [`ElusivOption`] over [`Option`] is the fixed serialization length
@@ -88,7 +91,7 @@ impl<N: Clone> ElusivOption<N> {
 }
 
 impl<T: BorshSerDeSized> BorshDeserialize for ElusivOption<T> {
-    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
         if buf[0] == 0 {
             *buf = &buf[<ElusivOption<T>>::SIZE..];
             Ok(ElusivOption::None)
@@ -102,7 +105,7 @@ impl<T: BorshSerDeSized> BorshDeserialize for ElusivOption<T> {
 }
 
 impl<T: BorshSerDeSized> BorshSerialize for ElusivOption<T> {
-    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
         match self {
             ElusivOption::Some(v) => {
                 writer.write_all(&[1])?;
@@ -110,7 +113,7 @@ impl<T: BorshSerDeSized> BorshSerialize for ElusivOption<T> {
             }
             ElusivOption::None => {
                 writer.write_all(&[0])?;
-                writer.write_all(&vec![0; T::SIZE])?;
+                writer.write_all(&alloc::vec![0; T::SIZE])?;
 
                 Ok(())
             }
@@ -128,10 +131,139 @@ impl<T: BorshSerDeSized> BorshSerDeSized for ElusivOption<T> {
     const SIZE: usize = 1 + T::SIZE;
 }
 
-impl BorshSerDeSized for Pubkey {
+#[cfg(feature = "std")]
+impl BorshSerDeSized for solana_program::pubkey::Pubkey {
     const SIZE: usize = 32;
 }
 
 impl BorshSerDeSized for () {
     const SIZE: usize = 0;
 }
+
+macro_rules! div_ceiling {
+    ($id: ident, $ty: ty) => {
+        #[doc = " Rounds a integer division up"]
+        #[doc = ""]
+        #[doc = " # Panics"]
+        #[doc = ""]
+        #[doc = " Panics for a zero-divisor"]
+        pub const fn $id(divident: $ty, divisor: $ty) -> $ty {
+            if divisor == 0 {
+                panic!()
+            }
+            (divident + divisor - 1) / divisor
+        }
+    };
+}
+
+div_ceiling!(div_ceiling_u32, u32);
+div_ceiling!(div_ceiling_u64, u64);
+div_ceiling!(div_ceiling_usize, usize);
+
+macro_rules! safe_num_downcast {
+    ($id: ident, $h: ty, $l: ty) => {
+        pub const fn $id(u: $h) -> $l {
+            if u > <$l>::MAX as $h {
+                panic!()
+            }
+            u as $l
+        }
+    };
+}
+
+safe_num_downcast!(u64_as_u32_safe, u64, u32);
+safe_num_downcast!(usize_as_u32_safe, usize, u32);
+safe_num_downcast!(usize_as_u16_safe, usize, u16);
+safe_num_downcast!(usize_as_u8_safe, usize, u8);
+
+pub const fn u64_as_usize_safe(u: u64) -> usize {
+    u64_as_u32_safe(u) as usize
+}
+
+pub fn contains<N: BorshSerialize + BorshSerDeSized>(v: &N, data: &[u8]) -> bool {
+    let length = data.len() / N::SIZE;
+    find(v, data, length).is_some()
+}
+
+pub fn find<N: BorshSerialize + BorshSerDeSized>(
+    v: &N,
+    data: &[u8],
+    length: usize,
+) -> Option<usize> {
+    let bytes = match N::try_to_vec(v) {
+        Ok(v) => v,
+        Err(_) => return None,
+    };
+
+    assert!(data.len() >= length);
+
+    // TODO: optimize with byte alignment
+
+    let last_index = N::SIZE - 1;
+    let mut offset = 0;
+    for i in 0..length {
+        if data[offset] == bytes[0] {
+            for j in 1..N::SIZE {
+                if data[offset + j] != bytes[j] {
+                    break;
+                }
+
+                if j == last_index {
+                    return Some(i);
+                }
+            }
+        }
+
+        offset += N::SIZE;
+    }
+
+    None
+}
+
+pub fn is_zero(s: &[u8]) -> bool {
+    for i in (0..s.len()).step_by(16) {
+        if s.len() - i >= 16 {
+            let arr: [u8; 16] = s[i..i + 16].try_into().unwrap();
+            if u128::from_be_bytes(arr) != 0 {
+                return false;
+            }
+        } else {
+            for &bit in s.iter().skip(i) {
+                if bit != 0 {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+pub fn slice_to_array<N: Default + Copy, const SIZE: usize>(s: &[N]) -> [N; SIZE] {
+    assert!(s.len() >= SIZE);
+    let mut a = [N::default(); SIZE];
+    a[..SIZE].copy_from_slice(&s[..SIZE]);
+    a
+}
+
+/// Reads the LE [`u64`] starting at `offset`
+pub fn u64_limb(slice: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(slice_to_array(&slice[offset..]))
+}
+
+/// Splits a LE 256 bit integer into its four LE [`u64`] limbs
+pub fn u256_to_le_limbs(v: [u8; 32]) -> [u64; 4] {
+    [
+        u64_limb(&v, 0),
+        u64_limb(&v, 8),
+        u64_limb(&v, 16),
+        u64_limb(&v, 24),
+    ]
+}
+
+/// Converts an [`u64`] into a LE 256 bit integer (without performing a montgomery reduction)
+pub fn u64_to_u256_skip_mr(v: u64) -> [u8; 32] {
+    let mut u = [0; 32];
+    let v = v.to_le_bytes();
+    u[..8].copy_from_slice(&v[..]);
+    u
+}
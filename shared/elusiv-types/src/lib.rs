@@ -1,7 +1,20 @@
+//! Shared types of the Elusiv programs
+//!
+//! # Note
+//!
+//! The `bytes` and `pda` modules are `no_std` (+ `alloc`) compatible cores, which can be used without the `std` feature (and thus without `solana-program`).
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 #[cfg(feature = "accounts")]
 pub mod accounts;
 #[cfg(feature = "bytes")]
 pub mod bytes;
+pub mod pda;
+#[cfg(feature = "accounts")]
+pub mod sysvar;
 #[cfg(feature = "tokens")]
 pub mod tokens;
 
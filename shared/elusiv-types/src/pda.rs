@@ -0,0 +1,115 @@
+//! PDA seeds and (with the `pda` feature) PDA derivation (`no_std` compatible)
+//!
+//! # Note
+//!
+//! Mirrors `Pubkey::create_program_address` and `Pubkey::find_program_address`, so components without `solana-program` derive the exact same PDAs.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Optional offset of a PDA's seed (used for multi-instance accounts)
+pub type PDAOffset = Option<u32>;
+
+pub const MAX_SEEDS: usize = 16;
+pub const MAX_SEED_LEN: usize = 32;
+
+#[cfg(feature = "pda")]
+const PDA_MARKER: &[u8; 21] = b"ProgramDerivedAddress";
+
+/// Returns the seeds of a PDA (without the bump)
+pub fn pda_seeds(seed: &[u8], pubkey: Option<&[u8; 32]>, offset: PDAOffset) -> Vec<Vec<u8>> {
+    let mut seeds = vec![seed.to_vec()];
+
+    if let Some(pubkey) = pubkey {
+        seeds.push(pubkey.to_vec());
+    }
+
+    if let Some(offset) = offset {
+        seeds.push(offset.to_le_bytes().to_vec());
+    }
+
+    seeds
+}
+
+/// Derives a program address from `seeds` (including the bump) and `program_id`
+///
+/// # Note
+///
+/// Returns [`None`] for invalid seeds and for addresses that lie on the ed25519 curve.
+#[cfg(feature = "pda")]
+pub fn create_program_address(seeds: &[&[u8]], program_id: &[u8; 32]) -> Option<[u8; 32]> {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+    use sha2::{Digest, Sha256};
+
+    if seeds.len() > MAX_SEEDS || seeds.iter().any(|seed| seed.len() > MAX_SEED_LEN) {
+        return None;
+    }
+
+    let mut hasher = Sha256::new();
+    for seed in seeds {
+        hasher.update(seed);
+    }
+    hasher.update(program_id);
+    hasher.update(PDA_MARKER);
+    let hash: [u8; 32] = hasher.finalize().into();
+
+    if CompressedEdwardsY(hash).decompress().is_some() {
+        return None;
+    }
+
+    Some(hash)
+}
+
+/// Finds the program address with the highest valid bump for `seeds` (without the bump)
+#[cfg(feature = "pda")]
+pub fn find_program_address(seeds: &[&[u8]], program_id: &[u8; 32]) -> Option<([u8; 32], u8)> {
+    (1..=u8::MAX).rev().find_map(|bump| {
+        let bump_seed = [bump];
+        let mut seeds = seeds.to_vec();
+        seeds.push(&bump_seed);
+
+        create_program_address(&seeds, program_id).map(|address| (address, bump))
+    })
+}
+
+#[cfg(all(test, feature = "std", feature = "pda"))]
+mod tests {
+    use super::*;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_find_program_address() {
+        let program_id = Pubkey::new_unique();
+        let pubkey = Pubkey::new_unique();
+
+        for (pubkey, offset) in [
+            (None, None),
+            (None, Some(3)),
+            (Some(pubkey.to_bytes()), None),
+            (Some(pubkey.to_bytes()), Some(u32::MAX)),
+        ] {
+            let seeds = pda_seeds(b"seed", pubkey.as_ref(), offset);
+            let seeds: Vec<&[u8]> = seeds.iter().map(|x| &x[..]).collect();
+
+            let (expected, bump) = Pubkey::find_program_address(&seeds, &program_id);
+            assert_eq!(
+                find_program_address(&seeds, &program_id.to_bytes()),
+                Some((expected.to_bytes(), bump))
+            );
+
+            let mut signers_seeds = seeds.clone();
+            let bump_seed = [bump];
+            signers_seeds.push(&bump_seed);
+            assert_eq!(
+                create_program_address(&signers_seeds, &program_id.to_bytes()),
+                Some(expected.to_bytes())
+            );
+        }
+
+        // Too long seed
+        assert_eq!(
+            create_program_address(&[&[0; MAX_SEED_LEN + 1]], &program_id.to_bytes()),
+            None
+        );
+    }
+}
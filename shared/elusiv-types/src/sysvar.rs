@@ -0,0 +1,96 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::{self, Sysvar};
+use std::ops::Deref;
+
+pub use solana_program::clock::Clock;
+pub use solana_program::rent::Rent;
+
+/// A sysvar that can be loaded from its account
+pub trait LoadableSysvar: Sized {
+    /// The address of the sysvar account
+    const ID: Pubkey;
+
+    /// Loads the sysvar from an account with the key [`Self::ID`]
+    fn load(account: &AccountInfo) -> Result<Self, ProgramError>;
+}
+
+impl LoadableSysvar for Clock {
+    const ID: Pubkey = sysvar::clock::ID;
+
+    fn load(account: &AccountInfo) -> Result<Self, ProgramError> {
+        Clock::from_account_info(account)
+    }
+}
+
+impl LoadableSysvar for Rent {
+    const ID: Pubkey = sysvar::rent::ID;
+
+    fn load(account: &AccountInfo) -> Result<Self, ProgramError> {
+        Rent::from_account_info(account)
+    }
+}
+
+/// The instructions sysvar
+///
+/// # Note
+///
+/// The serialized instructions are not loaded as a whole, only the index of the currently executing instruction is.
+pub struct Instructions {
+    pub current_index: u16,
+}
+
+impl LoadableSysvar for Instructions {
+    const ID: Pubkey = sysvar::instructions::ID;
+
+    fn load(account: &AccountInfo) -> Result<Self, ProgramError> {
+        Ok(Instructions {
+            current_index: sysvar::instructions::load_current_index_checked(account)?,
+        })
+    }
+}
+
+/// A sysvar account with a verified key and its loaded sysvar
+///
+/// # Note
+///
+/// Dereferences to the [`AccountInfo`], so it can be supplied to processors (and CPIs) expecting the raw account.
+pub struct SysvarAccount<'a, 'b, T: LoadableSysvar> {
+    account: &'b AccountInfo<'a>,
+    sysvar: T,
+}
+
+impl<'a, 'b, T: LoadableSysvar> SysvarAccount<'a, 'b, T> {
+    /// Verifies the key of `account` and loads the sysvar
+    pub fn load(account: &'b AccountInfo<'a>) -> Result<Self, ProgramError> {
+        if *account.key != T::ID {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Ok(SysvarAccount {
+            account,
+            sysvar: T::load(account)?,
+        })
+    }
+
+    pub fn sysvar(&self) -> &T {
+        &self.sysvar
+    }
+
+    pub fn account_info(&self) -> &'b AccountInfo<'a> {
+        self.account
+    }
+}
+
+impl<'a, 'b, T: LoadableSysvar> Deref for SysvarAccount<'a, 'b, T> {
+    type Target = AccountInfo<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.account
+    }
+}
+
+pub type ClockAccount<'a, 'b> = SysvarAccount<'a, 'b, Clock>;
+pub type RentAccount<'a, 'b> = SysvarAccount<'a, 'b, Rent>;
+pub type InstructionsAccount<'a, 'b> = SysvarAccount<'a, 'b, Instructions>;
@@ -4,7 +4,8 @@ use elusiv_derive::BorshSerDeSized;
 use solana_program::{
     account_info::AccountInfo, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
 };
-use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::extension::{transfer_fee::TransferFeeConfig, BaseState, StateWithExtensions};
 use std::{
     num::NonZeroU16,
     ops::{Add, Sub},
@@ -19,6 +20,10 @@ pub struct ElusivToken {
     pub ident: &'static str,
 
     pub mint: Pubkey,
+
+    /// Owner of `mint` (either SPL-Token or Token-2022)
+    pub token_program: Pubkey,
+
     pub decimals: u8,
     pub price_base_exp: u8,
 
@@ -125,6 +130,16 @@ impl Token {
         }
     }
 
+    /// Returns the amount received by a recipient of this token after the Token-2022 transfer-fee (if any) is withheld
+    pub fn net_of_transfer_fee(
+        &self,
+        mint: &AccountInfo,
+        epoch: u64,
+    ) -> Result<Self, ProgramError> {
+        let fee = transfer_fee(mint, self, epoch)?;
+        Ok((*self - Self::new(self.token_id(), fee))?)
+    }
+
     pub fn into_lamports(&self) -> Result<Lamports, TokenError> {
         match self {
             Token::Lamports(lamports) => Ok(*lamports),
@@ -220,22 +235,69 @@ impl SPLToken {
     }
 }
 
+/// Returns `true` if the token is managed by the Token-2022 program
+pub fn is_token_2022(token_id: TokenID) -> Result<bool, TokenError> {
+    if token_id == 0 {
+        return Ok(false);
+    }
+
+    Ok(elusiv_token(token_id)?.token_program == spl_token_2022::ID)
+}
+
+/// Unpacks the base state of a SPL-Token or Token-2022 account (ignoring all extensions)
+pub fn unpack_token_state<S: BaseState>(account: &AccountInfo) -> Result<S, ProgramError> {
+    let data = &account.data.borrow()[..];
+
+    if *account.owner == spl_token_2022::ID {
+        Ok(StateWithExtensions::<S>::unpack(data)?.base)
+    } else if *account.owner == spl_token::ID {
+        S::unpack(data)
+    } else {
+        Err(ProgramError::IncorrectProgramId)
+    }
+}
+
+/// Computes the Token-2022 transfer-fee withheld when transferring `token` at `epoch`
+///
+/// # Note
+///
+/// Returns zero for lamports, SPL-Token mints and Token-2022 mints without the transfer-fee extension.
+pub fn transfer_fee(mint: &AccountInfo, token: &Token, epoch: u64) -> Result<u64, ProgramError> {
+    let token_id = token.token_id();
+    if !is_token_2022(token_id)? {
+        return Ok(0);
+    }
+
+    if *mint.key != elusiv_token(token_id)?.mint || *mint.owner != spl_token_2022::ID {
+        return Err(TokenError::InvalidTokenAccount.into());
+    }
+
+    let data = &mint.data.borrow()[..];
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)?;
+    match mint.get_extension::<TransferFeeConfig>() {
+        Ok(config) => Ok(config
+            .calculate_epoch_fee(epoch, token.amount())
+            .ok_or(TokenError::Overflow)?),
+        Err(_) => Ok(0),
+    }
+}
+
 /// Ensures that a given account is able to receive the specified token
 pub fn verify_token_account(
     account: &AccountInfo,
     token_id: TokenID,
 ) -> Result<bool, ProgramError> {
     if token_id == 0 {
-        Ok(*account.owner != spl_token::ID)
+        Ok(*account.owner != spl_token::ID && *account.owner != spl_token_2022::ID)
     } else {
-        if *account.owner != spl_token::ID {
+        let token = elusiv_token(token_id)?;
+        if *account.owner != token.token_program {
             return Ok(false);
         }
 
-        let data = &account.data.borrow()[..];
-        let account = spl_token::state::Account::unpack(data)?;
+        let account = unpack_token_state::<spl_token_2022::state::Account>(account)?;
 
-        Ok(account.mint == elusiv_token(token_id)?.mint)
+        Ok(account.mint == token.mint)
     }
 }
 
@@ -248,7 +310,12 @@ pub fn verify_associated_token_account(
     if token_id == 0 {
         Ok(*wallet_address == *token_account_address)
     } else {
-        let expected = get_associated_token_address(wallet_address, &elusiv_token(token_id)?.mint);
+        let token = elusiv_token(token_id)?;
+        let expected = get_associated_token_address_with_program_id(
+            wallet_address,
+            &token.mint,
+            &token.token_program,
+        );
 
         Ok(*token_account_address == expected)
     }
@@ -337,6 +404,33 @@ impl TokenPrice {
         }
     }
 
+    /// Price of a token with a governance-pinned conversion rate (no oracle required)
+    ///
+    /// # Note
+    ///
+    /// `lamports_per_token` is the amount of Lamports equivalent to one whole token (`10^decimals` base units).
+    pub fn new_fixed_rate(lamports_per_token: u64, token_id: TokenID) -> Result<Self, TokenError> {
+        if token_id == 0 {
+            return Ok(Self::new_lamports());
+        }
+
+        Ok(Self {
+            lamports_usd: Price {
+                price: 1,
+                conf: 0,
+                expo: 0,
+            },
+            token_usd: Price {
+                price: lamports_per_token
+                    .try_into()
+                    .or(Err(TokenError::PriceError))?,
+                conf: 0,
+                expo: 0,
+            },
+            token_id,
+        })
+    }
+
     pub fn new_lamports() -> Self {
         Self {
             lamports_usd: Price {
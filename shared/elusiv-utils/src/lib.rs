@@ -70,6 +70,87 @@ pub fn open_pda_account_with_associated_pubkey<'a, T: PDAAccount + SizedAccount>
     )
 }
 
+/// Rent fronted by a `payer` for a PDA, which is owed to the `owner` of the PDA
+///
+/// # Note
+///
+/// The `owner` is the account to which the rent is repaid once the PDA is closed (e.g. a warden renting an account on behalf of a user flow).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RentLedgerEntry {
+    pub pda: Pubkey,
+    pub payer: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+}
+
+/// Opens a PDA with the rent fronted by `payer` and returns the [`RentLedgerEntry`] of the rent owed to `owner`
+#[allow(clippy::too_many_arguments)]
+pub fn open_pda_account_with_rent_owner<'a, T: PDAAccount + SizedAccount>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    owner: &Pubkey,
+    pda_account: &AccountInfo<'a>,
+    pda_pubkey: Option<Pubkey>,
+    pda_offset: PDAOffset,
+    bump: Option<u8>,
+) -> Result<RentLedgerEntry, ProgramError> {
+    open_pda_account::<T>(
+        program_id,
+        payer,
+        pda_account,
+        pda_pubkey,
+        pda_offset,
+        bump,
+        T::SIZE,
+    )?;
+
+    Ok(RentLedgerEntry {
+        pda: *pda_account.key,
+        payer: *payer.key,
+        owner: *owner,
+        lamports: pda_account.lamports(),
+    })
+}
+
+pub fn open_pda_account_with_offset_and_rent_owner<'a, T: PDAAccount + SizedAccount>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    owner: &Pubkey,
+    pda_account: &AccountInfo<'a>,
+    pda_offset: u32,
+    bump: Option<u8>,
+) -> Result<RentLedgerEntry, ProgramError> {
+    open_pda_account_with_rent_owner::<T>(
+        program_id,
+        payer,
+        owner,
+        pda_account,
+        None,
+        Some(pda_offset),
+        bump,
+    )
+}
+
+pub fn open_pda_account_with_associated_pubkey_and_rent_owner<'a, T: PDAAccount + SizedAccount>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    owner: &Pubkey,
+    pda_account: &AccountInfo<'a>,
+    pubkey: &Pubkey,
+    pda_offset: PDAOffset,
+    bump: Option<u8>,
+) -> Result<RentLedgerEntry, ProgramError> {
+    open_pda_account_with_rent_owner::<T>(
+        program_id,
+        payer,
+        owner,
+        pda_account,
+        Some(*pubkey),
+        pda_offset,
+        bump,
+    )
+}
+
 pub fn open_pda_account<'a, T: PDAAccount>(
     program_id: &Pubkey,
     payer: &AccountInfo<'a>,
@@ -103,6 +184,7 @@ pub fn open_pda_account<'a, T: PDAAccount>(
         pda_account,
         account_size,
         bump,
+        T::VERSION,
         &signers_seeds,
     )
 }
@@ -113,6 +195,7 @@ pub fn create_pda_account<'a>(
     pda_account: &AccountInfo<'a>,
     account_size: usize,
     bump: u8,
+    version: u8,
     signers_seeds: &[&[u8]],
 ) -> ProgramResult {
     // We require the test-unit feature since cfg!(test) does not work in deps
@@ -144,7 +227,7 @@ pub fn create_pda_account<'a>(
     borsh::BorshSerialize::serialize(
         &PDAAccountData {
             bump_seed: bump,
-            version: 0,
+            version,
         },
         &mut data,
     )?;
@@ -243,6 +326,138 @@ pub fn batch_instructions(
     v
 }
 
+#[cfg(feature = "sdk")]
+/// Returns the compute-budget instructions requesting `compute_units` and (optionally) a heap frame of `heap_frame_bytes`
+///
+/// # Note
+///
+/// The instructions need to be placed in front of all other instructions of a transaction.
+pub fn compute_budget_instructions(
+    compute_units: u32,
+    heap_frame_bytes: Option<u32>,
+) -> Vec<Instruction> {
+    let mut ixs = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        compute_units.min(elusiv_computation::MAX_COMPUTE_UNIT_LIMIT),
+    )];
+
+    if let Some(heap_frame_bytes) = heap_frame_bytes {
+        ixs.push(ComputeBudgetInstruction::request_heap_frame(
+            heap_frame_bytes,
+        ));
+    }
+
+    ixs
+}
+
+#[cfg(feature = "sdk")]
+/// The serialized size of a single instruction in a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionSize {
+    pub name: String,
+    pub accounts_count: usize,
+    pub data_len: usize,
+    pub size: usize,
+}
+
+#[cfg(feature = "sdk")]
+/// A transaction exceeding [`solana_sdk::packet::PACKET_DATA_SIZE`] (with a breakdown of its size)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionSizeError {
+    pub size: usize,
+    pub signatures_size: usize,
+    pub account_keys_count: usize,
+    pub account_keys_size: usize,
+    pub instructions: Vec<InstructionSize>,
+}
+
+#[cfg(feature = "sdk")]
+impl std::fmt::Display for TransactionSizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Transaction size of {} bytes exceeds the limit of {} bytes by {} bytes",
+            self.size,
+            solana_sdk::packet::PACKET_DATA_SIZE,
+            self.size - solana_sdk::packet::PACKET_DATA_SIZE
+        )?;
+        writeln!(f, "- signatures: {} bytes", self.signatures_size)?;
+        writeln!(
+            f,
+            "- account keys: {} bytes ({} unique accounts)",
+            self.account_keys_size, self.account_keys_count
+        )?;
+        for ix in &self.instructions {
+            writeln!(
+                f,
+                "- {}: {} bytes ({} accounts, {} bytes of data)",
+                ix.name, ix.size, ix.accounts_count, ix.data_len
+            )?;
+        }
+        write!(
+            f,
+            "Split the instructions into multiple transactions or reference the accounts with an address lookup table"
+        )
+    }
+}
+
+#[cfg(feature = "sdk")]
+impl std::error::Error for TransactionSizeError {}
+
+#[cfg(feature = "sdk")]
+/// The length of a compact-u16 (short-vec) encoded length
+const fn compact_u16_len(len: usize) -> usize {
+    if len < 0x80 {
+        1
+    } else if len < 0x4000 {
+        2
+    } else {
+        3
+    }
+}
+
+#[cfg(feature = "sdk")]
+/// Verifies that the `instructions` fit into a single (legacy) transaction paid by `fee_payer`
+///
+/// # Note
+///
+/// - Returns the serialized size of the transaction, the `instruction_name` is only used for the breakdown in the [`TransactionSizeError`].
+/// - Builders don't know the remaining instructions of a transaction, so the check has to be performed once the transaction is assembled.
+pub fn check_transaction_size<F: Fn(&Instruction) -> String>(
+    instructions: &[Instruction],
+    fee_payer: &Pubkey,
+    instruction_name: F,
+) -> Result<usize, TransactionSizeError> {
+    let message = solana_sdk::message::Message::new(instructions, Some(fee_payer));
+    let signatures_count = message.header.num_required_signatures as usize;
+    let signatures_size = compact_u16_len(signatures_count) + signatures_count * 64;
+    let size = signatures_size + message.serialize().len();
+
+    if size <= solana_sdk::packet::PACKET_DATA_SIZE {
+        return Ok(size);
+    }
+
+    let account_keys_count = message.account_keys.len();
+    Err(TransactionSizeError {
+        size,
+        signatures_size,
+        account_keys_count,
+        account_keys_size: compact_u16_len(account_keys_count) + account_keys_count * 32,
+        instructions: instructions
+            .iter()
+            .map(|ix| InstructionSize {
+                name: instruction_name(ix),
+                accounts_count: ix.accounts.len(),
+                data_len: ix.data.len(),
+                size: 1
+                    + compact_u16_len(ix.accounts.len())
+                    + ix.accounts.len()
+                    + compact_u16_len(ix.data.len())
+                    + ix.data.len(),
+            })
+            .collect(),
+    })
+}
+
 #[cfg(feature = "computation")]
 pub fn batched_instructions_tx_count(total_ix_count: usize, compute_units_per_ix: u32) -> usize {
     let batch_size =